@@ -0,0 +1,662 @@
+//! Audio output: the global mute toggle, ducking and the volume preview beep. Sink
+//! volumes are always derived from [`AudioSettings::output_level`], so muting or ducking
+//! never changes the [`Volume`] the player picked.
+//!
+//! Music and sound effects read from files go through the [`AudioLibrary`], which keeps
+//! them within the [`AudioBudget`]: only the track playing, the one predicted next and the
+//! few effects played most recently stay loaded. Every other handle is dropped, so Bevy
+//! unloads the sound, and it's loaded again when it's asked for. An [`AudioSource`] holds
+//! the encoded file and is decoded as it plays, so a long track costs its file size.
+//!
+//! [`ResidentAudio`] counts the sounds held in memory, for the debug overlay. Generated
+//! [`Pitch`]es hold no samples and don't count against the budget.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::{
+    asset::{TrackAssets, handle_internal_asset_events},
+    audio::Volume as Level,
+    prelude::*,
+    window::WindowFocused,
+};
+
+use crate::Volume;
+use crate::fonts::ui_font;
+use crate::palette::{DARKER, LIGHT};
+use crate::profiles::{ProfilePaths, profile_switched};
+use crate::save_file::{CorruptFiles, SaveFiles};
+use crate::screens::text_field::typing;
+use crate::settings::SavedSettings;
+use crate::timers::{RealUiTime, UiTimer};
+
+pub const MUTE_KEY: KeyCode = KeyCode::KeyM;
+/// The loudest [`Volume`] setting.
+const MAX_VOLUME: u32 = 9;
+/// How loud audio stays while the window is in the background.
+const FOCUS_LOST_DUCK: f32 = 0.2;
+/// Minimum time between two volume previews.
+const PREVIEW_INTERVAL: Duration = Duration::from_millis(150);
+const PREVIEW_FREQUENCY: f32 = 660.0;
+const PREVIEW_LENGTH: Duration = Duration::from_millis(120);
+
+/// Who asked for audio to be ducked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuckSource {
+    PauseMenu,
+    FocusLost,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct AudioSettings {
+    pub muted: bool,
+    /// The deepest active duck, as a factor on the volume.
+    pub duck: Option<f32>,
+    ducks: Vec<(DuckSource, f32)>,
+}
+
+impl AudioSettings {
+    /// Lowers the volume to `level` until `source` calls [`AudioSettings::unduck`].
+    /// Overlapping ducks don't multiply: the quietest one applies, and a source ducking
+    /// again replaces its earlier request.
+    pub fn duck(&mut self, source: DuckSource, level: f32) {
+        self.ducks.retain(|(other, _)| *other != source);
+        self.ducks.push((source, level.clamp(0.0, 1.0)));
+        self.update_duck();
+    }
+
+    pub fn unduck(&mut self, source: DuckSource) {
+        self.ducks.retain(|(other, _)| *other != source);
+        self.update_duck();
+    }
+
+    fn update_duck(&mut self) {
+        self.duck = self.ducks.iter().map(|(_, level)| *level).reduce(f32::min);
+    }
+
+    /// The linear output level for a volume setting, after muting and ducking.
+    pub fn output_level(&self, volume: Volume) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+        volume.0.min(MAX_VOLUME) as f32 / MAX_VOLUME as f32 * self.duck.unwrap_or(1.0)
+    }
+}
+
+/// Sent by the settings screen when the player picks a volume, to play a preview at the
+/// new level. Changes to [`Volume`] made by code don't send it, so they stay silent.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct VolumeAdjusted;
+
+/// Rate-limits the preview: adjustments during the cooldown are folded into one preview
+/// at its end, played at whatever the volume is by then.
+#[derive(Resource)]
+struct VolumePreview {
+    sound: Handle<Pitch>,
+    cooldown: UiTimer,
+    queued: bool,
+}
+
+impl FromWorld for VolumePreview {
+    fn from_world(world: &mut World) -> Self {
+        VolumePreview {
+            sound: world.add_asset(Pitch::new(PREVIEW_FREQUENCY, PREVIEW_LENGTH)),
+            cooldown: UiTimer::run_out(PREVIEW_INTERVAL),
+            queued: false,
+        }
+    }
+}
+
+#[derive(Component)]
+struct MuteIndicator;
+
+/// The sounds loaded right now.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct ResidentAudio {
+    /// Sounds read from files.
+    pub files: usize,
+    /// Their encoded bytes, which is what stays in memory: they're decoded as they play.
+    pub file_bytes: usize,
+    /// Generated tones, which are made as they play.
+    pub tones: usize,
+}
+
+/// How much audio read from files may stay loaded.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct AudioBudget {
+    /// Encoded bytes, over every loaded [`AudioSource`].
+    pub bytes: usize,
+    /// Sound effects kept loaded after they were played.
+    pub effects: usize,
+}
+
+impl Default for AudioBudget {
+    fn default() -> Self {
+        AudioBudget {
+            bytes: 24 * 1024 * 1024,
+            effects: 8,
+        }
+    }
+}
+
+/// Switches the music to the track at this path, as soon as it has loaded.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct PlayMusic(pub String);
+
+/// Loads the track at this path ahead of the [`PlayMusic`] expected next, like the music
+/// of the area the player is heading to. Ignored while a switch is waiting on its track.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct PrewarmMusic(pub String);
+
+/// Plays the sound effect at this path once.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct PlayEffect(pub String);
+
+/// The music playing.
+#[derive(Component)]
+struct Music;
+
+/// A sound read from a file, kept loaded by its handle.
+#[derive(Debug)]
+struct Track {
+    path: String,
+    handle: Handle<AudioSource>,
+}
+
+/// The handles keeping audio read from files loaded.
+#[derive(Resource, Debug, Default)]
+pub struct AudioLibrary {
+    playing: Option<Track>,
+    /// The track loaded ahead of time.
+    next: Option<Track>,
+    /// Whether `next` replaces `playing` once it's loaded.
+    switching: bool,
+    /// Least recently played first.
+    effects: VecDeque<Track>,
+}
+
+impl AudioLibrary {
+    fn play_music(&mut self, path: &str, server: &AssetServer) {
+        // Heading back before the switch happened keeps the music going.
+        if self
+            .playing
+            .as_ref()
+            .is_some_and(|track| track.path == path)
+        {
+            self.switching = false;
+            return;
+        }
+        self.load_next(path, server);
+        self.switching = true;
+    }
+
+    fn prewarm(&mut self, path: &str, server: &AssetServer) {
+        if !self.switching {
+            self.load_next(path, server);
+        }
+    }
+
+    fn load_next(&mut self, path: &str, server: &AssetServer) {
+        let loaded = |track: &Option<Track>| track.as_ref().is_some_and(|track| track.path == path);
+        if loaded(&self.playing) || loaded(&self.next) {
+            return;
+        }
+        self.next = Some(Track {
+            path: path.to_string(),
+            handle: server.load(path.to_string()),
+        });
+    }
+
+    /// The effect at `path`, loading it if it isn't, and now the most recently played.
+    fn effect(&mut self, path: &str, server: &AssetServer) -> Handle<AudioSource> {
+        let played = self.effects.iter().position(|track| track.path == path);
+        let track = played
+            .and_then(|index| self.effects.remove(index))
+            .unwrap_or_else(|| Track {
+                path: path.to_string(),
+                handle: server.load(path.to_string()),
+            });
+        let handle = track.handle.clone();
+        self.effects.push_back(track);
+        handle
+    }
+}
+
+pub fn audio_plugin(app: &mut App) {
+    let muted = SavedSettings::load_active(app.world_mut()).muted;
+    app.insert_resource(AudioSettings { muted, ..default() })
+        .add_plugins(audio_library_plugin)
+        .init_resource::<VolumePreview>()
+        .add_event::<VolumeAdjusted>()
+        .add_systems(PreUpdate, load_profile_mute.run_if(profile_switched))
+        .add_systems(
+            Update,
+            (
+                toggle_mute.run_if(not(typing)),
+                duck_on_focus_loss,
+                apply_output_level
+                    .run_if(resource_changed::<AudioSettings>.or(resource_changed::<Volume>)),
+                show_mute_indicator.run_if(resource_changed::<AudioSettings>),
+                play_volume_preview,
+            )
+                .chain(),
+        );
+}
+
+fn audio_library_plugin(app: &mut App) {
+    app.register_type::<ResidentAudio>()
+        .register_type::<AudioBudget>()
+        .init_resource::<ResidentAudio>()
+        .init_resource::<AudioBudget>()
+        .init_resource::<AudioLibrary>()
+        .add_event::<PlayMusic>()
+        .add_event::<PrewarmMusic>()
+        .add_event::<PlayEffect>()
+        .add_systems(Update, request_audio)
+        // Between the loads landing and the dropped handles being unloaded, so a sound over
+        // the budget is gone again before anything sees it.
+        .add_systems(
+            PreUpdate,
+            (start_music, enforce_budget)
+                .chain()
+                .after(handle_internal_asset_events)
+                .before(TrackAssets),
+        )
+        .add_systems(
+            PostUpdate,
+            count_resident_audio.run_if(
+                resource_changed::<Assets<AudioSource>>.or(resource_changed::<Assets<Pitch>>),
+            ),
+        );
+}
+
+fn load_profile_mute(
+    profile: Res<ProfilePaths>,
+    mut corrupt: ResMut<CorruptFiles>,
+    mut audio: ResMut<AudioSettings>,
+) {
+    audio.muted = SavedSettings::load(&profile, &mut corrupt).muted;
+}
+
+fn toggle_mute(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    profile: Res<ProfilePaths>,
+    mut files: SaveFiles,
+    mut audio: ResMut<AudioSettings>,
+) {
+    if !keyboard_input.just_pressed(MUTE_KEY) {
+        return;
+    }
+    audio.muted = !audio.muted;
+    let mut saved = SavedSettings::load(&profile, &mut files.corrupt);
+    saved.muted = audio.muted;
+    saved.save(&profile, &mut files.saves);
+}
+
+fn duck_on_focus_loss(mut focus: EventReader<WindowFocused>, mut audio: ResMut<AudioSettings>) {
+    for event in focus.read() {
+        if event.focused {
+            audio.unduck(DuckSource::FocusLost);
+        } else {
+            audio.duck(DuckSource::FocusLost, FOCUS_LOST_DUCK);
+        }
+    }
+}
+
+/// Sets the global volume for new sounds and updates the ones already playing.
+fn apply_output_level(
+    audio: Res<AudioSettings>,
+    volume: Res<Volume>,
+    mut global: ResMut<GlobalVolume>,
+    mut sinks: Query<(&mut AudioSink, &PlaybackSettings)>,
+) {
+    global.volume = Level::Linear(audio.output_level(*volume));
+    for (mut sink, settings) in &mut sinks {
+        sink.set_volume(settings.volume * global.volume);
+    }
+}
+
+fn play_volume_preview(
+    mut commands: Commands,
+    mut adjusted: EventReader<VolumeAdjusted>,
+    mut preview: ResMut<VolumePreview>,
+    time: RealUiTime,
+) {
+    if adjusted.read().count() > 0 {
+        preview.queued = true;
+    }
+    preview.cooldown.tick(&time);
+    if preview.queued && preview.cooldown.finished() {
+        preview.queued = false;
+        preview.cooldown.reset();
+        commands.spawn((
+            AudioPlayer(preview.sound.clone()),
+            PlaybackSettings::DESPAWN,
+        ));
+    }
+}
+
+fn request_audio(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    mut library: ResMut<AudioLibrary>,
+    mut music: EventReader<PlayMusic>,
+    mut prewarm: EventReader<PrewarmMusic>,
+    mut effects: EventReader<PlayEffect>,
+) {
+    for PlayMusic(path) in music.read() {
+        library.play_music(path, &server);
+    }
+    for PrewarmMusic(path) in prewarm.read() {
+        library.prewarm(path, &server);
+    }
+    for PlayEffect(path) in effects.read() {
+        let handle = library.effect(path, &server);
+        commands.spawn((AudioPlayer(handle), PlaybackSettings::DESPAWN));
+    }
+}
+
+/// Swaps the music only once the new track has loaded, so it starts right away.
+fn start_music(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    mut library: ResMut<AudioLibrary>,
+    playing: Query<Entity, With<Music>>,
+) {
+    let library = &mut *library;
+    let Some(next) = library.next.as_ref().filter(|_| library.switching) else {
+        return;
+    };
+    let state = server.load_state(&next.handle);
+    if state.is_failed() {
+        warn!("Couldn't load the music {}", next.path);
+        library.next = None;
+        library.switching = false;
+        return;
+    }
+    if !state.is_loaded() {
+        return;
+    }
+    for music in &playing {
+        commands.entity(music).despawn();
+    }
+    commands.spawn((
+        AudioPlayer(next.handle.clone()),
+        PlaybackSettings::LOOP,
+        Music,
+    ));
+    library.playing = library.next.take();
+    library.switching = false;
+}
+
+/// Unloads the effects played longest ago until the library is within the budget. The
+/// music playing and the track loaded for next are never unloaded.
+fn enforce_budget(
+    mut commands: Commands,
+    budget: Res<AudioBudget>,
+    sources: Res<Assets<AudioSource>>,
+    mut library: ResMut<AudioLibrary>,
+    players: Query<(Entity, &AudioPlayer)>,
+) {
+    let mut bytes = sources
+        .iter()
+        .map(|(_, source)| source.bytes.len())
+        .sum::<usize>();
+    while library.effects.len() > budget.effects || bytes > budget.bytes {
+        let Some(track) = library.effects.pop_front() else {
+            break;
+        };
+        // One still playing would keep it loaded.
+        for (entity, player) in &players {
+            if player.0.id() == track.handle.id() {
+                commands.entity(entity).despawn();
+            }
+        }
+        bytes -= sources
+            .get(&track.handle)
+            .map_or(0, |source| source.bytes.len());
+    }
+}
+
+fn count_resident_audio(
+    files: Res<Assets<AudioSource>>,
+    tones: Res<Assets<Pitch>>,
+    mut resident: ResMut<ResidentAudio>,
+) {
+    resident.set_if_neq(ResidentAudio {
+        files: files.len(),
+        file_bytes: files.iter().map(|(_, source)| source.bytes.len()).sum(),
+        tones: tones.len(),
+    });
+}
+
+fn show_mute_indicator(
+    mut commands: Commands,
+    audio: Res<AudioSettings>,
+    indicators: Query<Entity, With<MuteIndicator>>,
+    assets: Res<AssetServer>,
+) {
+    let shown = !indicators.is_empty();
+    if audio.muted == shown {
+        return;
+    }
+    if !audio.muted {
+        for indicator in &indicators {
+            commands.entity(indicator).despawn();
+        }
+        return;
+    }
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(4.0),
+            right: Val::Px(4.0),
+            padding: UiRect::axes(Val::Px(6.0), Val::Px(4.0)),
+            ..default()
+        },
+        BackgroundColor(DARKER.with_alpha(0.9)),
+        GlobalZIndex(100),
+        MuteIndicator,
+        children![(
+            Text::new("Muted (M)"),
+            TextFont {
+                font: ui_font(&assets),
+                font_size: 10.0,
+                ..default()
+            },
+            TextColor(LIGHT),
+        )],
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use bevy::asset::io::{
+        AssetSource,
+        memory::{Dir, MemoryAssetReader},
+    };
+    use bevy::audio::AudioLoader;
+
+    use super::*;
+
+    const AREAS: usize = 6;
+    const EFFECTS: usize = 12;
+    const TRACK_BYTES: usize = 300_000;
+    const EFFECT_BYTES: usize = 20_000;
+    /// Frames a sound has to load in.
+    const LOAD_FRAMES: usize = 500;
+
+    fn area(index: usize) -> String {
+        format!("memory://area{}.ogg", index % AREAS)
+    }
+
+    fn effect(index: usize) -> String {
+        format!("memory://effect{}.ogg", index % EFFECTS)
+    }
+
+    /// The library over files held in memory. They're never decoded, so any bytes will do.
+    fn app(budget: AudioBudget) -> App {
+        let files = Dir::default();
+        for index in 0..AREAS {
+            files.insert_asset(Path::new(&format!("area{index}.ogg")), vec![0; TRACK_BYTES]);
+        }
+        for index in 0..EFFECTS {
+            files.insert_asset(
+                Path::new(&format!("effect{index}.ogg")),
+                vec![0; EFFECT_BYTES],
+            );
+        }
+        let mut app = App::new();
+        app.register_asset_source(
+            "memory",
+            AssetSource::build().with_reader(move || {
+                Box::new(MemoryAssetReader {
+                    root: files.clone(),
+                })
+            }),
+        )
+        .add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<AudioSource>()
+        .init_asset::<Pitch>()
+        .init_asset_loader::<AudioLoader>()
+        .add_plugins(audio_library_plugin)
+        .insert_resource(budget);
+        app
+    }
+
+    fn playing(app: &App) -> Option<&str> {
+        let library = app.world().resource::<AudioLibrary>();
+        library.playing.as_ref().map(|track| track.path.as_str())
+    }
+
+    fn update_until(app: &mut App, done: impl Fn(&App) -> bool) {
+        for _ in 0..LOAD_FRAMES {
+            if done(app) {
+                return;
+            }
+            app.update();
+        }
+        panic!("still waiting after {LOAD_FRAMES} frames");
+    }
+
+    #[test]
+    fn cycling_areas_stays_within_the_budget() {
+        let budget = AudioBudget {
+            bytes: 2 * TRACK_BYTES + 4 * EFFECT_BYTES,
+            effects: 8,
+        };
+        let mut app = app(budget);
+        for frame in 0..600 {
+            let here = frame / 3;
+            app.world_mut().send_event(PlayMusic(area(here)));
+            app.world_mut().send_event(PrewarmMusic(area(here + 1)));
+            app.world_mut().send_event(PlayEffect(effect(frame * 7)));
+            app.update();
+
+            let resident = *app.world().resource::<ResidentAudio>();
+            assert!(
+                resident.file_bytes <= budget.bytes,
+                "frame {frame}: {resident:?} is over {budget:?}"
+            );
+            // The effect played this frame comes within the budget at the start of the next.
+            let library = app.world().resource::<AudioLibrary>();
+            assert!(library.effects.len() <= budget.effects + 1);
+        }
+
+        // Once the player stops somewhere, its music plays.
+        let last = area(599 / 3);
+        update_until(&mut app, |app| playing(app) == Some(last.as_str()));
+        let mut music = app.world_mut().query_filtered::<(), With<Music>>();
+        assert_eq!(music.iter(app.world()).count(), 1);
+    }
+
+    #[test]
+    fn a_prewarmed_track_plays_on_the_next_frame() {
+        let mut app = app(AudioBudget::default());
+        app.world_mut().send_event(PlayMusic(area(0)));
+        update_until(&mut app, |app| playing(app) == Some(area(0).as_str()));
+
+        app.world_mut().send_event(PrewarmMusic(area(1)));
+        update_until(&mut app, |app| {
+            let library = app.world().resource::<AudioLibrary>();
+            let server = app.world().resource::<AssetServer>();
+            (library.next.as_ref()).is_some_and(|next| server.is_loaded(&next.handle))
+        });
+        assert_eq!(playing(&app), Some(area(0).as_str()));
+
+        app.world_mut().send_event(PlayMusic(area(1)));
+        app.update();
+        app.update();
+        assert_eq!(playing(&app), Some(area(1).as_str()));
+        assert_eq!(app.world().resource::<ResidentAudio>().files, 1);
+    }
+
+    #[test]
+    fn effects_played_longest_ago_are_unloaded_first() {
+        let mut app = app(AudioBudget {
+            bytes: AudioBudget::default().bytes,
+            effects: 3,
+        });
+        for index in [0, 1, 2, 0, 3] {
+            app.world_mut().send_event(PlayEffect(effect(index)));
+            app.update();
+        }
+        update_until(&mut app, |app| {
+            let library = app.world().resource::<AudioLibrary>();
+            library.effects.len() == 3 && app.world().resource::<ResidentAudio>().files == 3
+        });
+
+        let library = app.world().resource::<AudioLibrary>();
+        let kept = library
+            .effects
+            .iter()
+            .map(|track| track.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(kept, [effect(2), effect(0), effect(3)]);
+    }
+
+    #[test]
+    fn the_deepest_duck_applies_until_its_source_lets_go() {
+        let mut audio = AudioSettings::default();
+        audio.duck(DuckSource::PauseMenu, 0.5);
+        audio.duck(DuckSource::FocusLost, 0.2);
+        assert_eq!(audio.duck, Some(0.2));
+        audio.unduck(DuckSource::FocusLost);
+        assert_eq!(audio.duck, Some(0.5));
+        // Letting go twice, or of a duck never asked for, changes nothing.
+        audio.unduck(DuckSource::FocusLost);
+        assert_eq!(audio.duck, Some(0.5));
+        audio.unduck(DuckSource::PauseMenu);
+        assert_eq!(audio.duck, None);
+    }
+
+    #[test]
+    fn ducking_again_replaces_the_sources_earlier_request() {
+        let mut audio = AudioSettings::default();
+        audio.duck(DuckSource::PauseMenu, 0.3);
+        audio.duck(DuckSource::PauseMenu, 0.8);
+        assert_eq!(audio.duck, Some(0.8));
+        audio.duck(DuckSource::FocusLost, 2.0);
+        assert_eq!(audio.duck, Some(0.8));
+        audio.duck(DuckSource::FocusLost, -1.0);
+        assert_eq!(audio.duck, Some(0.0));
+    }
+
+    #[test]
+    fn ducks_lower_the_volume_without_multiplying() {
+        let mut audio = AudioSettings::default();
+        let full = Volume(MAX_VOLUME);
+        assert_eq!(audio.output_level(full), 1.0);
+        audio.duck(DuckSource::PauseMenu, 0.5);
+        audio.duck(DuckSource::FocusLost, 0.5);
+        assert_eq!(audio.output_level(full), 0.5);
+        assert_eq!(audio.output_level(Volume(0)), 0.0);
+        audio.muted = true;
+        assert_eq!(audio.output_level(full), 0.0);
+    }
+}
@@ -0,0 +1,448 @@
+//! The `--balance` launch mode, for tuning rents, wages and event odds without playing
+//! hundreds of runs by hand.
+//!
+//! The game runs without a window and plays the given number of runs under each
+//! [`Policy`], seeded the same way for every policy, from `--seed` (or 1) up. A run ends
+//! when it's won, when the player is evicted, or after `--days` days. Everything goes
+//! through the game's own systems: the policies press the actions the action panel
+//! offers, and each day's hours run the way the fixed loop runs them, all at once. Every
+//! event card is answered with its first button, and any other overlay that opens, like
+//! the monthly report, is closed again.
+//!
+//! The game has nowhere to move to yet, so where a run gets first is measured by the day
+//! its first area unlocks.
+//!
+//! At the end a table of medians per policy is printed, and every run written to `--csv`
+//! if given. The game quits with a failing exit code if the median "Always work" run is
+//! evicted within [`MIN_MEDIAN_DAYS`], which no default balance should allow. The runs are
+//! played in their own [`PROFILE`], which is deleted afterwards. `cargo test` plays a few
+//! short runs of each policy.
+
+use std::path::Path;
+use std::time::Duration;
+
+use bevy::{
+    app::{PluginGroupBuilder, ScheduleRunnerPlugin},
+    prelude::*,
+};
+
+use crate::AppState;
+use crate::launch::LaunchOptions;
+use crate::profiles::{self, ProfilePaths};
+use crate::screens::actions::{ActionPressed, AvailableActions, GameAction};
+use crate::screens::overlay::{ModalStack, Overlay};
+use crate::settings::{ContentFilter, ContentFilters};
+use crate::sim::{
+    bank::Bank,
+    clock::{Clock, HOURS_PER_DAY},
+    economy::{CurrentArea, Money},
+    event_cards::{CardChosen, EventCardQueue},
+    step_ticks,
+    travel::{AreaUnlocks, TravelRequested},
+};
+
+/// The profile the runs are played in, unless `--profile` picks one.
+pub const PROFILE: &str = "Balance";
+/// How long a run lasts when `--days` isn't given.
+pub const DEFAULT_DAYS: u32 = 365;
+/// The fewest days the median "Always work" run may last before it's evicted.
+const MIN_MEDIAN_DAYS: u32 = 5;
+/// Frames a run may go without the clock moving before it's given up as stuck.
+const STUCK_FRAMES: u32 = 120;
+
+/// `plugins` without a window or a renderer, updating as fast as it can.
+pub fn headless(plugins: PluginGroupBuilder) -> PluginGroupBuilder {
+    crate::self_test::headless(plugins).set(ScheduleRunnerPlugin::run_loop(Duration::ZERO))
+}
+
+/// How a scripted player plays a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Policy {
+    /// Works a shift every day it can, and takes every better job offered.
+    AlwaysWork,
+    /// Always works, and goes to each area as soon as it unlocks.
+    MoveUp,
+    /// Always works, in runs with the gambling content hidden.
+    NeverGamble,
+}
+
+impl Policy {
+    const ALL: [Policy; 3] = [Policy::AlwaysWork, Policy::MoveUp, Policy::NeverGamble];
+
+    fn name(self) -> &'static str {
+        match self {
+            Policy::AlwaysWork => "Always work",
+            Policy::MoveUp => "Move up",
+            Policy::NeverGamble => "Never gamble",
+        }
+    }
+
+    fn content_filters(self) -> ContentFilters {
+        ContentFilters {
+            gambling: match self {
+                Policy::NeverGamble => ContentFilter::Hidden,
+                Policy::AlwaysWork | Policy::MoveUp => ContentFilter::Shown,
+            },
+            crime: ContentFilter::Shown,
+        }
+    }
+
+    // Presses what the policy wants of the actions on offer, and travels.
+    fn act(self, world: &mut World) {
+        let offered: Vec<_> = world
+            .resource::<AvailableActions>()
+            .offered()
+            .iter()
+            .filter(|offered| offered.blocked.is_none())
+            .map(|offered| offered.action)
+            .collect();
+        // Hired first, so the shift is worked at the new job.
+        for action in [GameAction::AcceptJobOffer, GameAction::WorkShift] {
+            if offered.contains(&action) {
+                world.send_event(ActionPressed(action));
+            }
+        }
+        if self == Policy::MoveUp
+            && let Some(&newest) = world.resource::<AreaUnlocks>().0.last()
+            && world.resource::<CurrentArea>().0 != newest
+        {
+            world.send_event(TravelRequested(newest));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Won,
+    Evicted,
+    /// Still going after the last day.
+    Survived,
+    /// The clock stopped moving, with a modal the runs can't close.
+    Stuck,
+}
+
+impl Outcome {
+    fn name(self) -> &'static str {
+        match self {
+            Outcome::Won => "won",
+            Outcome::Evicted => "evicted",
+            Outcome::Survived => "survived",
+            Outcome::Stuck => "stuck",
+        }
+    }
+}
+
+struct RunResult {
+    policy: Policy,
+    seed: u64,
+    outcome: Outcome,
+    /// The day the run ended on.
+    day: u32,
+    first_unlock: Option<u32>,
+    /// Cash and savings at the end.
+    wealth: u32,
+}
+
+/// The run being played.
+struct Run {
+    policy: Policy,
+    seed: u64,
+    first_unlock: Option<u32>,
+    /// [`Clock::total_hours`] when the policy last acted, to let the hours run after.
+    acted_at: Option<u32>,
+    /// [`Clock::total_hours`] as last seen, and for how many frames.
+    last_hours: u32,
+    still_frames: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Loading,
+    /// Between runs, on the way back to the menu.
+    Starting,
+    Playing,
+}
+
+#[derive(Resource)]
+struct Balance {
+    step: Step,
+    runs: u32,
+    days: u32,
+    first_seed: u64,
+    /// The runs still to play, next last.
+    queue: Vec<(Policy, u64)>,
+    run: Option<Run>,
+    results: Vec<RunResult>,
+}
+
+pub fn balance_plugin(app: &mut App) {
+    let options = app.world().resource::<LaunchOptions>();
+    let runs = options.balance.unwrap_or(1);
+    let first_seed = options.seed.unwrap_or(1);
+    let mut queue: Vec<_> = Policy::ALL
+        .into_iter()
+        .flat_map(|policy| (0..u64::from(runs)).map(move |run| (policy, first_seed + run)))
+        .collect();
+    queue.reverse();
+    app.insert_resource(Balance {
+        step: Step::Loading,
+        runs,
+        days: options.days.unwrap_or(DEFAULT_DAYS),
+        first_seed,
+        queue,
+        run: None,
+        results: Vec::new(),
+    })
+    // After the frame's actions were offered, like the walk in `self_test`.
+    .add_systems(PostUpdate, play);
+}
+
+fn play(world: &mut World) {
+    let mut balance = world
+        .remove_resource::<Balance>()
+        .expect("balance is set up");
+    let state = *world.resource::<State<AppState>>().get();
+    match balance.step {
+        Step::Loading => match state {
+            AppState::Menu => {
+                // Only the hours run here move the clock.
+                world.resource_mut::<Time<Virtual>>().pause();
+                balance.step = Step::Starting;
+            }
+            AppState::AssetError => {
+                error!("Balance: the assets didn't load");
+                world.send_event(AppExit::from_code(1));
+            }
+            _ => {}
+        },
+        Step::Starting if state != AppState::Menu => set_state(world, AppState::Menu),
+        Step::Starting => match balance.queue.pop() {
+            Some((policy, seed)) => {
+                world.resource_mut::<LaunchOptions>().seed = Some(seed);
+                world.insert_resource(policy.content_filters());
+                set_state(world, AppState::Game);
+                balance.run = Some(Run {
+                    policy,
+                    seed,
+                    first_unlock: None,
+                    acted_at: None,
+                    last_hours: 0,
+                    still_frames: 0,
+                });
+                balance.step = Step::Playing;
+            }
+            None => finish(world, &balance),
+        },
+        Step::Playing => {
+            let run = balance.run.as_mut().expect("a run is being played");
+            if let Some(outcome) = play_run(world, run, state, balance.days) {
+                let clock = world.resource::<Clock>();
+                let result = RunResult {
+                    policy: run.policy,
+                    seed: run.seed,
+                    outcome,
+                    day: clock.day,
+                    first_unlock: run.first_unlock,
+                    wealth: world.resource::<Money>().amount + world.resource::<Bank>().savings,
+                };
+                balance.results.push(result);
+                balance.run = None;
+                balance.step = Step::Starting;
+            }
+        }
+    }
+    world.insert_resource(balance);
+}
+
+// Takes the run one step further, returning how it ended once it has.
+fn play_run(world: &mut World, run: &mut Run, state: AppState, days: u32) -> Option<Outcome> {
+    match state {
+        AppState::Game => {}
+        AppState::Win => return Some(Outcome::Won),
+        AppState::GameOver => return Some(Outcome::Evicted),
+        // Still on the way in.
+        _ => return None,
+    }
+    let hours = world.resource::<Clock>().total_hours();
+    if hours == run.last_hours {
+        run.still_frames += 1;
+        if run.still_frames > STUCK_FRAMES {
+            warn!(
+                "Balance: seed {} got stuck on day {}",
+                run.seed,
+                hours / HOURS_PER_DAY
+            );
+            return Some(Outcome::Stuck);
+        }
+    } else {
+        run.last_hours = hours;
+        run.still_frames = 0;
+    }
+
+    if answer_cards(world) || close_overlay(world) {
+        return None;
+    }
+    let clock = *world.resource::<Clock>();
+    if run.first_unlock.is_none() && !world.resource::<AreaUnlocks>().0.is_empty() {
+        run.first_unlock = Some(clock.day);
+    }
+    if clock.day > days {
+        return Some(Outcome::Survived);
+    }
+    if !world.resource::<ModalStack>().is_empty() {
+        return None;
+    }
+    if run.acted_at == Some(hours) {
+        // A day's ticks, stopping early if the run ends.
+        step_ticks(world, HOURS_PER_DAY);
+    } else {
+        // The actions are handled next frame, before any more hours go by.
+        run.policy.act(world);
+        run.acted_at = Some(hours);
+    }
+    None
+}
+
+// Answers every card waiting with its first button. Returns whether there were any.
+fn answer_cards(world: &mut World) -> bool {
+    let mut answered = false;
+    while let Some((card, _)) = world.resource::<EventCardQueue>().front() {
+        world.resource_mut::<EventCardQueue>().remove(card);
+        world.send_event(CardChosen { card, button: 0 });
+        answered = true;
+    }
+    answered
+}
+
+// Closes whatever overlay opened. Returns whether one had.
+fn close_overlay(world: &mut World) -> bool {
+    let open = world
+        .get_resource::<State<Overlay>>()
+        .is_some_and(|overlay| *overlay.get() != Overlay::None);
+    if open {
+        world
+            .resource_mut::<NextState<Overlay>>()
+            .set(Overlay::None);
+    }
+    open
+}
+
+fn set_state(world: &mut World, state: AppState) {
+    world.resource_mut::<NextState<AppState>>().set(state);
+}
+
+fn median(mut values: Vec<u32>) -> Option<u32> {
+    values.sort_unstable();
+    values.get(values.len() / 2).copied()
+}
+
+// Prints the table, writes the CSV and quits.
+fn finish(world: &mut World, balance: &Balance) {
+    println!(
+        "Balance: {} runs per policy, seeds {}-{}, up to {} days",
+        balance.runs,
+        balance.first_seed,
+        balance.first_seed + u64::from(balance.runs) - 1,
+        balance.days
+    );
+    println!(
+        "{:<14}{:>6}{:>10}{:>8}{:>14}{:>10}{:>10}{:>14}",
+        "policy", "won", "evicted", "stuck", "first unlock", "win day", "days", "cash+savings"
+    );
+    let mut failed = false;
+    for policy in Policy::ALL {
+        let runs: Vec<_> = balance
+            .results
+            .iter()
+            .filter(|result| result.policy == policy)
+            .collect();
+        let count = |outcome| runs.iter().filter(|run| run.outcome == outcome).count();
+        let percent = |outcome| format!("{}%", count(outcome) * 100 / runs.len().max(1));
+        let days = median(runs.iter().map(|run| run.day).collect());
+        let show = |value: Option<u32>| value.map_or("-".to_string(), |value| value.to_string());
+        println!(
+            "{:<14}{:>6}{:>10}{:>8}{:>14}{:>10}{:>10}{:>14}",
+            policy.name(),
+            percent(Outcome::Won),
+            percent(Outcome::Evicted),
+            count(Outcome::Stuck),
+            show(median(
+                runs.iter().filter_map(|run| run.first_unlock).collect()
+            )),
+            show(median(
+                runs.iter()
+                    .filter(|run| run.outcome == Outcome::Won)
+                    .map(|run| run.day)
+                    .collect()
+            )),
+            show(days),
+            show(median(runs.iter().map(|run| run.wealth).collect())),
+        );
+        if policy == Policy::AlwaysWork && days.is_some_and(|days| days < MIN_MEDIAN_DAYS) {
+            failed = true;
+            println!("  FAIL the median run lasted under {MIN_MEDIAN_DAYS} days");
+        }
+    }
+    if let Some(path) = &world.resource::<LaunchOptions>().csv {
+        match write_csv(path, &balance.results) {
+            Ok(()) => println!("Every run written to {}", path.display()),
+            Err(error) => warn!("Couldn't write {}: {error}", path.display()),
+        }
+    }
+    let profile = world.resource::<ProfilePaths>();
+    if profile.name() == PROFILE
+        && let Err(error) = profiles::delete(profile.root(), PROFILE)
+    {
+        warn!("Couldn't delete the {PROFILE} profile: {error}");
+    }
+    world.send_event(if failed {
+        AppExit::from_code(1)
+    } else {
+        AppExit::Success
+    });
+}
+
+fn write_csv(path: &Path, results: &[RunResult]) -> std::io::Result<()> {
+    let mut csv = "policy,seed,outcome,day,first_unlock,cash_and_savings\n".to_string();
+    for result in results {
+        csv += &format!(
+            "{},{},{},{},{},{}\n",
+            result.policy.name(),
+            result.seed,
+            result.outcome.name(),
+            result.day,
+            result
+                .first_unlock
+                .map_or(String::new(), |day| day.to_string()),
+            result.wealth
+        );
+    }
+    std::fs::write(path, csv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestGame;
+
+    #[test]
+    fn every_policy_plays_its_runs_to_the_end() {
+        let mut app = TestGame::new(LaunchOptions {
+            balance: Some(2),
+            days: Some(20),
+            profile: Some(PROFILE.to_string()),
+            ..default()
+        });
+        let exit = app.run_to_exit();
+        let results = &app.world().resource::<Balance>().results;
+        assert_eq!(results.len(), 2 * Policy::ALL.len());
+        for result in results {
+            let run = format!("{} seed {}", result.policy.name(), result.seed);
+            assert_eq!(result.outcome, Outcome::Survived, "{run}");
+            assert_eq!(result.day, 21, "{run}");
+            // The first area unlocks within the days played.
+            assert!(result.first_unlock.is_some(), "{run}");
+        }
+        assert_eq!(exit, AppExit::Success);
+    }
+}
@@ -0,0 +1,98 @@
+//! Loading game balance data from RON files in `assets/data`.
+
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::de::DeserializeOwned;
+
+use crate::toast::Toast;
+
+/// Loads any deserializable asset from a RON file with the given extensions.
+struct RonLoader<A> {
+    extensions: &'static [&'static str],
+    _asset: PhantomData<fn() -> A>,
+}
+
+impl<A: Asset + DeserializeOwned> AssetLoader for RonLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<A, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
+
+/// Registers `A` as an asset loaded from RON files ending in one of `extensions`, like
+/// `"areas.ron"`. Each data asset gets its own extension so its loader is unambiguous.
+pub fn register_ron_asset<A: Asset + DeserializeOwned>(
+    app: &mut App,
+    extensions: &'static [&'static str],
+) {
+    app.init_asset::<A>()
+        .register_asset_loader(RonLoader::<A> {
+            extensions,
+            _asset: PhantomData,
+        })
+        .add_systems(Update, announce_reload::<A>);
+}
+
+// Only changes count, so it's quiet at startup. The file has to have parsed for a change to
+// show up here, so a typo doesn't announce anything.
+fn announce_reload<A: Asset>(
+    mut events: EventReader<AssetEvent<A>>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if events
+        .read()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }))
+    {
+        toasts.write(Toast::new("Data reloaded"));
+    }
+}
+
+/// Registers `A` like [`register_ron_asset`] and keeps it inserted as a resource: once the
+/// file first loads, and again every time it changes with the `dev` feature's file watching.
+pub fn register_ron_resource<A: Asset + Resource + Clone + DeserializeOwned>(
+    app: &mut App,
+    extensions: &'static [&'static str],
+) {
+    register_ron_resource_as::<A, A>(app, extensions);
+}
+
+/// Like [`register_ron_resource`], for data that's wrapped in a resource `R` of its own.
+pub fn register_ron_resource_as<A: Asset + Clone + DeserializeOwned, R: Resource + From<A>>(
+    app: &mut App,
+    extensions: &'static [&'static str],
+) {
+    register_ron_asset::<A>(app, extensions);
+    app.add_systems(Update, apply_ron_resource::<A, R>);
+}
+
+fn apply_ron_resource<A: Asset + Clone, R: Resource + From<A>>(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<A>>,
+    data: Res<Assets<A>>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && let Some(data) = data.get(*id)
+        {
+            commands.insert_resource(R::from(data.clone()));
+        }
+    }
+}
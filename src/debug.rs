@@ -0,0 +1,375 @@
+//! Developer overlay, toggled with F4: outlines every UI node and lists the live values of
+//! the game's resources.
+//!
+//! The resource list is built from the type registry, so any resource in this crate that
+//! derives `Reflect` with `#[reflect(Resource)]` and is registered shows up without
+//! further wiring.
+//!
+//! In debug builds the overlay also shows the latest leak check for each state, see
+//! [`crate::diagnostics`].
+//!
+//! F6 cycles through the locales, to check number formatting and the mirrored layout.
+//! Screens that are already up keep their layout until they're opened again.
+//!
+//! `]` warps a run one hour ahead and `Shift+]` a whole day. The warp runs the simulation's
+//! ticks as usual, just all at once, so everything that happens at midnight
+//! happens once per day skipped. A toast sums up the money that changed hands, from the
+//! ledger. There's no rewinding, and no warping while a modal is open.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+#[cfg(debug_assertions)]
+use crate::diagnostics::TransitionReports;
+use crate::palette::DARKER;
+use crate::screens::overlay::ModalStack;
+use crate::settings::Locale;
+use crate::sim::{
+    clock::{Clock, HOURS_PER_DAY},
+    ledger::{Category, Ledger},
+    step_ticks,
+};
+use crate::toast::Toast;
+use crate::{AppState, format};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F4;
+const LOCALE_KEY: KeyCode = KeyCode::F6;
+const WARP_KEY: KeyCode = KeyCode::BracketRight;
+const REWIND_KEY: KeyCode = KeyCode::BracketLeft;
+const CRATE_PREFIX: &str = concat!(env!("CARGO_PKG_NAME"), "::");
+
+#[derive(Component)]
+struct ResourcePanel;
+
+/// A warp under way: when it ends, and the ledger's totals from before it.
+#[derive(Resource)]
+struct TimeWarp {
+    until: u32,
+    income: BTreeMap<Category, u32>,
+    expenses: BTreeMap<Category, u32>,
+}
+
+pub fn debug_plugin(app: &mut App) {
+    app.add_systems(Update, (toggle_debug_overlay, cycle_locale))
+        .add_systems(
+            Update,
+            (
+                warp_time.run_if(not(resource_exists::<TimeWarp>)),
+                report_warp.run_if(resource_exists::<TimeWarp>),
+            )
+                .run_if(in_state(AppState::Game)),
+        )
+        .add_systems(OnExit(AppState::Game), end_warp)
+        .add_systems(
+            Update,
+            update_resource_panel.run_if(any_with_component::<ResourcePanel>),
+        );
+}
+
+fn cycle_locale(keyboard_input: Res<ButtonInput<KeyCode>>, mut locale: ResMut<Locale>) {
+    if !keyboard_input.just_pressed(LOCALE_KEY) {
+        return;
+    }
+    *locale = match *locale {
+        Locale::En => Locale::Fr,
+        Locale::Fr => Locale::De,
+        Locale::De => Locale::PseudoRtl,
+        Locale::PseudoRtl => Locale::En,
+    };
+    info!("Locale: {:?}", *locale);
+}
+
+fn warp_time(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    modals: Res<ModalStack>,
+    clock: Res<Clock>,
+    ledger: Res<Ledger>,
+) {
+    if keyboard_input.just_pressed(REWIND_KEY) {
+        warn!("Time only warps forward");
+        return;
+    }
+    if !keyboard_input.just_pressed(WARP_KEY) {
+        return;
+    }
+    if !modals.is_empty() {
+        warn!("Not warping while a modal is open");
+        return;
+    }
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let hours = if shift { HOURS_PER_DAY } else { 1 };
+    let (income, expenses) = ledger_totals(&ledger);
+    commands.insert_resource(TimeWarp {
+        until: clock.total_hours() + hours,
+        income,
+        expenses,
+    });
+    // A run that ends partway through stops the warp there.
+    commands.queue(move |world: &mut World| {
+        step_ticks(world, hours);
+    });
+}
+
+/// Every category's total over the whole run.
+fn ledger_totals(ledger: &Ledger) -> (BTreeMap<Category, u32>, BTreeMap<Category, u32>) {
+    let mut income = BTreeMap::new();
+    let mut expenses = BTreeMap::new();
+    for month in ledger.history.iter().chain([&ledger.current]) {
+        for (category, amount) in &month.income {
+            *income.entry(*category).or_default() += amount;
+        }
+        for (category, amount) in &month.expenses {
+            *expenses.entry(*category).or_default() += amount;
+        }
+    }
+    (income, expenses)
+}
+
+// The ledger books a tick's transactions in the tick itself, so it's up to date once the
+// clock gets there.
+fn report_warp(
+    mut commands: Commands,
+    warp: Res<TimeWarp>,
+    clock: Res<Clock>,
+    ledger: Res<Ledger>,
+    mut toasts: EventWriter<Toast>,
+    locale: Res<Locale>,
+) {
+    if clock.total_hours() < warp.until {
+        return;
+    }
+    commands.remove_resource::<TimeWarp>();
+    info!("Warped to day {}, {:02}:00", clock.day, clock.hour);
+    let (income, expenses) = ledger_totals(&ledger);
+    let changes = |after: &BTreeMap<Category, u32>, before: &BTreeMap<Category, u32>| {
+        after
+            .iter()
+            .filter_map(|(category, amount)| {
+                let change = amount - before.get(category).copied().unwrap_or(0);
+                (change > 0)
+                    .then(|| format!("{} {}", category.label(), format::money(change, *locale)))
+            })
+            .collect::<Vec<_>>()
+    };
+    let earned = changes(&income, &warp.income);
+    let charged = changes(&expenses, &warp.expenses);
+    let mut summary = format!("Day {}, {:02}:00", clock.day, clock.hour);
+    if !earned.is_empty() {
+        summary += &format!(". Earned: {}", earned.join(", "));
+    }
+    if !charged.is_empty() {
+        summary += &format!(". Charged: {}", charged.join(", "));
+    }
+    toasts.write(Toast::new(summary));
+}
+
+// A run that ends partway through has nothing left to report.
+fn end_warp(mut commands: Commands) {
+    commands.remove_resource::<TimeWarp>();
+}
+
+fn toggle_debug_overlay(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut options: ResMut<UiDebugOptions>,
+    panels: Query<Entity, With<ResourcePanel>>,
+) {
+    if !keyboard_input.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+
+    options.toggle();
+    if !options.enabled {
+        for panel in &panels {
+            commands.entity(panel).despawn();
+        }
+        return;
+    }
+
+    commands.spawn((
+        Text::default(),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.0),
+            right: Val::Px(5.0),
+            max_width: Val::Percent(45.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(DARKER.with_alpha(0.85)),
+        GlobalZIndex(1000),
+        Pickable::IGNORE,
+        ResourcePanel,
+    ));
+}
+
+fn update_resource_panel(world: &mut World) {
+    let text = {
+        let registry = world.resource::<AppTypeRegistry>().read();
+        let mut lines = registry
+            .iter()
+            .filter(|registration| {
+                registration
+                    .type_info()
+                    .type_path()
+                    .starts_with(CRATE_PREFIX)
+            })
+            .filter_map(|registration| {
+                let reflect_resource = registration.data::<ReflectResource>()?;
+                let value = reflect_resource.reflect(&*world).ok()?;
+                let name = registration.type_info().type_path_table().short_path();
+                Some(format!("{name}: {value:?}"))
+            })
+            .collect::<Vec<_>>();
+        lines.sort();
+        #[cfg(debug_assertions)]
+        lines.extend(
+            world
+                .resource::<TransitionReports>()
+                .0
+                .iter()
+                .map(|(state, report)| format!("Last {state} transition: {report}")),
+        );
+        lines.join("\n")
+    };
+
+    let mut panels = world.query_filtered::<&mut Text, With<ResourcePanel>>();
+    for mut panel in panels.iter_mut(world) {
+        panel.0.clone_from(&text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput, NativeKey},
+    };
+
+    use crate::launch::LaunchOptions;
+    use crate::screens::{event_card::CardAnswered, overlay::Overlay};
+    use crate::sim::{
+        bills::Bills,
+        economy::{Money, RENT_PERIOD_DAYS, RentHistory, WIN_MONEY},
+        event_cards::EventCardQueue,
+    };
+    use crate::testing::{MAX_FRAMES, TestGame};
+
+    /// A run that's started, with the sim only moving when it's warped.
+    fn game(profile: &str) -> TestGame {
+        let mut app = TestGame::new(LaunchOptions {
+            skip_menu: true,
+            headless: true,
+            seed: Some(1),
+            profile: Some(profile.to_string()),
+            ..default()
+        });
+        app.update_until(|app| *app.world().resource::<State<AppState>>().get() == AppState::Game);
+        app.world_mut().resource_mut::<Time<Virtual>>().pause();
+        close_modals(&mut app);
+        app
+    }
+
+    // Dismisses whatever the run put up, cards with their last button.
+    fn close_modals(app: &mut App) {
+        for _ in 0..MAX_FRAMES {
+            if app.world().resource::<ModalStack>().is_empty() {
+                return;
+            }
+            let last_button = app
+                .world()
+                .resource::<EventCardQueue>()
+                .front()
+                .map(|(_, card)| card.buttons.len() - 1);
+            match last_button {
+                Some(button) => {
+                    app.world_mut().send_event(CardAnswered(button));
+                }
+                None => {
+                    app.world_mut()
+                        .resource_mut::<NextState<Overlay>>()
+                        .set(Overlay::None);
+                }
+            }
+            app.update();
+            app.update();
+        }
+        panic!("the modals were still open after {MAX_FRAMES} tries");
+    }
+
+    // Through the input events, as the input plugin rebuilds `ButtonInput` from them.
+    fn press(app: &mut App, keys: &[KeyCode]) {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            for key in keys {
+                app.world_mut().send_event(KeyboardInput {
+                    key_code: *key,
+                    logical_key: Key::Unidentified(NativeKey::Unidentified),
+                    state,
+                    text: None,
+                    repeat: false,
+                    window: Entity::PLACEHOLDER,
+                });
+            }
+            app.update();
+        }
+    }
+
+    fn hours(app: &App) -> u32 {
+        app.world().resource::<Clock>().total_hours()
+    }
+
+    #[test]
+    fn time_doesnt_warp_under_a_modal() {
+        const PROFILE: &str = "Warp modal";
+        let mut app = game(PROFILE);
+        let start = hours(&app);
+        let token = app.world_mut().resource_mut::<ModalStack>().open_modal();
+        press(&mut app, &[WARP_KEY]);
+        assert_eq!(hours(&app), start);
+        assert!(!app.world().contains_resource::<TimeWarp>());
+
+        app.world_mut()
+            .resource_mut::<ModalStack>()
+            .close_modal(token);
+        press(&mut app, &[WARP_KEY]);
+        assert_eq!(hours(&app), start + 1);
+    }
+
+    #[test]
+    fn warping_several_weeks_pays_every_weeks_rent() {
+        const PROFILE: &str = "Warp weeks";
+        const PERIODS: u32 = 3;
+        let mut app = game(PROFILE);
+        app.world_mut().resource_mut::<Money>().amount = WIN_MONEY / 10;
+        let start = hours(&app);
+        for _ in 0..PERIODS * RENT_PERIOD_DAYS {
+            press(&mut app, &[KeyCode::ShiftLeft, WARP_KEY]);
+            app.update_until(|app| !app.world().contains_resource::<TimeWarp>());
+            close_modals(&mut app);
+        }
+        assert_eq!(
+            hours(&app),
+            start + PERIODS * RENT_PERIOD_DAYS * HOURS_PER_DAY
+        );
+
+        let charges = &app.world().resource::<RentHistory>().0;
+        // Each paid by autopay on the day it fell due, one period after the last.
+        let days: Vec<_> = charges.iter().map(|charge| charge.day).collect();
+        let due_days: Vec<_> = (1..=PERIODS)
+            .map(|period| period * RENT_PERIOD_DAYS)
+            .collect();
+        assert_eq!(days, due_days, "{charges:?}");
+        assert!(app.world().resource::<Bills>().outstanding.is_empty());
+        let paid: u32 = charges.iter().map(|charge| charge.net).sum();
+        let (_, expenses) = ledger_totals(app.world().resource::<Ledger>());
+        assert_eq!(expenses.get(&Category::Rent), Some(&paid));
+        assert_eq!(expenses.get(&Category::LateFees), None);
+    }
+}
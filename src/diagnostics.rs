@@ -0,0 +1,203 @@
+//! Leak checks on every state transition, in debug builds: the entity count and the count
+//! of each screen's marker (see [`crate::track_screen`]) are logged, with a warning for any
+//! screen that still has entities after it exited. Any marker that more than one entity
+//! has, of those that should only ever be on one (see
+//! [`track_singleton`](crate::screens::singleton::track_singleton)), is logged as an error
+//! with the entities.
+//!
+//! How long the exit and enter systems took is logged at debug level, and the latest
+//! report for each state is kept in [`TransitionReports`] for the dev builds' overlay.
+
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+use bevy::ecs::event::EventCursor;
+use bevy::prelude::*;
+use bevy::state::state::{EnterSchedules, ExitSchedules, StateTransitionSteps};
+
+use crate::AppState;
+use crate::screens::{
+    menu::MenuState, nav::ScreenMarkers, overlay::Overlay, singleton::Singletons,
+};
+
+/// When the current step of a transition of `S` started, and how long its exit took.
+#[derive(Resource)]
+struct TransitionTiming<S: States> {
+    started: Instant,
+    exit: Duration,
+    _state: PhantomData<S>,
+}
+
+impl<S: States> Default for TransitionTiming<S> {
+    fn default() -> Self {
+        TransitionTiming {
+            started: Instant::now(),
+            exit: Duration::ZERO,
+            _state: PhantomData,
+        }
+    }
+}
+
+/// The latest transition report for each state type.
+#[derive(Resource, Default)]
+pub struct TransitionReports(pub BTreeMap<&'static str, String>);
+
+pub fn diagnostics_plugin(app: &mut App) {
+    app.init_resource::<TransitionReports>()
+        .init_resource::<Singletons>();
+    diagnose_transitions::<AppState>(app);
+    diagnose_transitions::<MenuState>(app);
+    diagnose_transitions::<Overlay>(app);
+}
+
+fn diagnose_transitions<S: States>(app: &mut App) {
+    app.init_resource::<ScreenMarkers<S>>()
+        .init_resource::<TransitionTiming<S>>()
+        .add_systems(
+            StateTransition,
+            (
+                (
+                    start_timing::<S>.before(ExitSchedules::<S>::default()),
+                    end_exit_timing::<S>.after(ExitSchedules::<S>::default()),
+                )
+                    .in_set(StateTransitionSteps::ExitSchedules),
+                (
+                    start_timing::<S>.before(EnterSchedules::<S>::default()),
+                    report_transition::<S>.after(EnterSchedules::<S>::default()),
+                )
+                    .in_set(StateTransitionSteps::EnterSchedules),
+            ),
+        );
+}
+
+fn start_timing<S: States>(mut timing: ResMut<TransitionTiming<S>>) {
+    timing.started = Instant::now();
+}
+
+fn end_exit_timing<S: States>(mut timing: ResMut<TransitionTiming<S>>) {
+    timing.exit = timing.started.elapsed();
+}
+
+// Runs after the enter systems, once everything the exit systems despawned is gone.
+fn report_transition<S: States>(
+    world: &mut World,
+    mut cursor: Local<EventCursor<StateTransitionEvent<S>>>,
+) {
+    let enter = world.resource::<TransitionTiming<S>>().started.elapsed();
+    let exit = world.resource::<TransitionTiming<S>>().exit;
+    let Some(transition) = cursor
+        .read(world.resource::<Events<StateTransitionEvent<S>>>())
+        .last()
+        .cloned()
+    else {
+        return;
+    };
+    if transition.exited == transition.entered {
+        return;
+    }
+
+    let state_name = std::any::type_name::<S>()
+        .rsplit("::")
+        .next()
+        .unwrap_or_default();
+    debug!(
+        "{state_name} {:?} -> {:?}: exit took {exit:?}, enter took {enter:?}",
+        transition.exited, transition.entered
+    );
+    let mut lines = vec![format!(
+        "{:?} -> {:?}, {} entities",
+        transition.exited,
+        transition.entered,
+        world.entities().len()
+    )];
+    for marker in &world.resource::<ScreenMarkers<S>>().0 {
+        let count = marker.count(world);
+        if count == 0 {
+            continue;
+        }
+        lines.push(format!("{}: {count}", marker.name));
+        if transition.exited.as_ref() == Some(&marker.state)
+            && transition.entered.as_ref() != Some(&marker.state)
+        {
+            warn!(
+                "{count} {} entities left after exiting {:?}",
+                marker.name, marker.state
+            );
+        }
+    }
+    for singleton in &world.resource::<Singletons>().0 {
+        let entities: Vec<_> = singleton
+            .entities(world)
+            .iter()
+            .map(Entity::to_string)
+            .collect();
+        if entities.len() > 1 {
+            error!(
+                "{} entities have {} after {:?} -> {:?}, which should be one: {}",
+                entities.len(),
+                singleton.name,
+                transition.exited,
+                transition.entered,
+                entities.join(", ")
+            );
+            lines.push(format!(
+                "{}: {}, which should be one",
+                singleton.name,
+                entities.len()
+            ));
+        }
+    }
+    info!("{state_name} {}", lines.join(", "));
+    world
+        .resource_mut::<TransitionReports>()
+        .0
+        .insert(state_name, lines.join("\n  "));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+    use crate::screens::singleton::track_singleton;
+
+    #[derive(Component)]
+    struct OnMenuScreen;
+
+    #[derive(Component)]
+    struct MoneyUi;
+
+    #[test]
+    fn transitions_report_leaks_and_duplicates_without_panicking() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<AppState>()
+            .init_state::<MenuState>()
+            .add_sub_state::<Overlay>();
+        crate::track_screen::<OnMenuScreen>(&mut app, AppState::Menu);
+        track_singleton::<MoneyUi>(&mut app);
+        app.add_plugins(diagnostics_plugin);
+        app.update();
+
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Menu);
+        app.update();
+        // The menu's screen is left behind, and the HUD's money spawned twice.
+        app.world_mut().spawn(OnMenuScreen);
+        app.world_mut().spawn_batch([MoneyUi, MoneyUi]);
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Game);
+        app.update();
+
+        let report = &app.world().resource::<TransitionReports>().0["AppState"];
+        assert!(report.starts_with("Some(Menu) -> Some(Game)"), "{report}");
+        assert!(report.contains("\n  OnMenuScreen: 1"), "{report}");
+        assert!(
+            report.contains("\n  MoneyUi: 2, which should be one"),
+            "{report}"
+        );
+    }
+}
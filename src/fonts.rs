@@ -0,0 +1,150 @@
+//! UI font loading with a fallback compiled into the binary.
+//!
+//! If the assets folder is missing, every text node using the regular UI font would
+//! render nothing. The fallback font is embedded with `include_bytes!` so text stays
+//! readable even when no asset can be loaded from disk.
+
+use bevy::{
+    asset::{AssetLoadFailedEvent, LoadState, weak_handle},
+    prelude::*,
+};
+
+pub const UI_FONT_PATH: &str = "fonts/PressStart2P-Regular.ttf";
+
+/// The embedded font, for text that has to render whatever is on disk.
+pub const FALLBACK_FONT: Handle<Font> = weak_handle!("376b5569-19cb-4578-809f-32e0cb8afbf8");
+const FALLBACK_FONT_DATA: &[u8] = include_bytes!("../assets/fonts/FiraMono-subset.ttf");
+
+/// The font every UI text should use: the regular UI font, or the embedded fallback if
+/// the regular one failed to load.
+pub fn ui_font(assets: &AssetServer) -> Handle<Font> {
+    let font = assets.load(UI_FONT_PATH);
+    match assets.load_state(&font) {
+        LoadState::Failed(_) => FALLBACK_FONT,
+        _ => font,
+    }
+}
+
+pub fn fonts_plugin(app: &mut App) {
+    let fallback =
+        Font::try_from_bytes(FALLBACK_FONT_DATA.to_vec()).expect("embedded font is valid");
+    app.world_mut()
+        .resource_mut::<Assets<Font>>()
+        .insert(&FALLBACK_FONT, fallback);
+
+    app.add_systems(Update, replace_failed_fonts);
+}
+
+// Text spawned before the UI font failed to load still points at the broken handle, so
+// swap those over to the fallback once the failure is known.
+fn replace_failed_fonts(
+    mut failures: EventReader<AssetLoadFailedEvent<Font>>,
+    mut texts: Query<&mut TextFont>,
+    mut warned: Local<bool>,
+) {
+    for failure in failures.read() {
+        if !*warned {
+            warn!(
+                "Couldn't load font {}, falling back to the embedded font: {}",
+                failure.path, failure.error
+            );
+            *warned = true;
+        }
+
+        for mut text_font in &mut texts {
+            if text_font.font.id() == failure.id {
+                text_font.font = FALLBACK_FONT;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::text::FontLoader;
+
+    use super::*;
+
+    /// Frames a font has to load or fail in.
+    const LOAD_FRAMES: usize = 500;
+
+    fn font_app(assets_folder: &str) -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                file_path: assets_folder.to_string(),
+                ..default()
+            },
+        ))
+        .init_asset::<Font>()
+        .init_asset_loader::<FontLoader>()
+        .add_plugins(fonts_plugin);
+        app
+    }
+
+    fn text_font(app: &App, text: Entity) -> Handle<Font> {
+        app.world().get::<TextFont>(text).unwrap().font.clone()
+    }
+
+    fn update_until(app: &mut App, done: impl Fn(&LoadState) -> bool) {
+        let font = app
+            .world()
+            .resource::<AssetServer>()
+            .load::<Font>(UI_FONT_PATH);
+        for _ in 0..LOAD_FRAMES {
+            if done(&app.world().resource::<AssetServer>().load_state(&font)) {
+                return;
+            }
+            app.update();
+        }
+        panic!("still loading after {LOAD_FRAMES} frames");
+    }
+
+    #[test]
+    fn without_the_ui_font_text_gets_the_fallback() {
+        let mut app = font_app("no-such-assets-folder");
+        let font = ui_font(app.world().resource::<AssetServer>());
+        assert_ne!(font, FALLBACK_FONT);
+        let early = app.world_mut().spawn(TextFont { font, ..default() }).id();
+
+        update_until(&mut app, LoadState::is_failed);
+        app.update();
+        assert_eq!(text_font(&app, early), FALLBACK_FONT);
+        assert!(
+            app.world()
+                .resource::<Assets<Font>>()
+                .contains(&FALLBACK_FONT)
+        );
+
+        // Once nothing holds the broken handle the font is tried again, and text spawned
+        // meanwhile falls back when that fails too.
+        let font = ui_font(app.world().resource::<AssetServer>());
+        let late = app.world_mut().spawn(TextFont { font, ..default() }).id();
+        for _ in 0..LOAD_FRAMES {
+            if text_font(&app, late) == FALLBACK_FONT {
+                return;
+            }
+            app.update();
+        }
+        panic!("still loading after {LOAD_FRAMES} frames");
+    }
+
+    #[test]
+    fn with_the_ui_font_text_keeps_it() {
+        let mut app = font_app("assets");
+        let font = ui_font(app.world().resource::<AssetServer>());
+        let text = app
+            .world_mut()
+            .spawn(TextFont {
+                font: font.clone(),
+                ..default()
+            })
+            .id();
+
+        update_until(&mut app, LoadState::is_loaded);
+        app.update();
+        assert_eq!(text_font(&app, text), font);
+        assert_eq!(ui_font(app.world().resource::<AssetServer>()), font);
+    }
+}
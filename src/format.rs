@@ -0,0 +1,170 @@
+//! Formatting of numbers for display, in the style of the player's [`Locale`]. Numbers
+//! always read left to right, so in right-to-left locales they're isolated from the text
+//! around them.
+
+use std::time::Duration;
+
+use crate::settings::Locale;
+
+/// How a locale writes numbers.
+struct NumberStyle {
+    decimal: char,
+    grouping: char,
+    /// Whether the currency symbol goes before the amount, like `$5`, or after, like `5 $`.
+    symbol_first: bool,
+}
+
+fn number_style(locale: Locale) -> NumberStyle {
+    match locale {
+        Locale::En | Locale::PseudoRtl => NumberStyle {
+            decimal: '.',
+            grouping: ',',
+            symbol_first: true,
+        },
+        Locale::Fr => NumberStyle {
+            decimal: ',',
+            grouping: ' ',
+            symbol_first: false,
+        },
+        Locale::De => NumberStyle {
+            decimal: ',',
+            grouping: '.',
+            symbol_first: false,
+        },
+    }
+}
+
+fn group_digits(n: u64, grouping: char) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(grouping);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// First strong isolate, left to right, and the pop that ends it.
+const LEFT_TO_RIGHT_ISOLATE: char = '\u{2066}';
+const POP_ISOLATE: char = '\u{2069}';
+
+/// Keeps `number` reading left to right inside right-to-left text.
+fn isolate(number: String, locale: Locale) -> String {
+    if locale.direction().is_rtl() {
+        format!("{LEFT_TO_RIGHT_ISOLATE}{number}{POP_ISOLATE}")
+    } else {
+        number
+    }
+}
+
+/// Formats a whole number with thousands separators, like `-1,250` or `-1.250`.
+pub fn format_int(n: i64, locale: Locale) -> String {
+    let sign = if n < 0 { "-" } else { "" };
+    isolate(
+        format!(
+            "{sign}{}",
+            group_digits(n.unsigned_abs(), number_style(locale).grouping)
+        ),
+        locale,
+    )
+}
+
+/// Formats an amount of cents as dollars, like `$1,234.56` or `1 234,56 $`. The cents are
+/// left out when there are none.
+pub fn format_money(cents: i64, locale: Locale) -> String {
+    let style = number_style(locale);
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    let mut amount = group_digits(cents / 100, style.grouping);
+    if !cents.is_multiple_of(100) {
+        amount = format!("{amount}{}{:02}", style.decimal, cents % 100);
+    }
+    let money = if style.symbol_first {
+        format!("{sign}${amount}")
+    } else {
+        format!("{sign}{amount} $")
+    };
+    isolate(money, locale)
+}
+
+/// Formats a whole amount of dollars, like `$1,250`.
+pub fn money(amount: u32, locale: Locale) -> String {
+    format_money(i64::from(amount) * 100, locale)
+}
+
+/// Formats a price, with what it costs before the cost of living when that's different,
+/// like `$6 (base $5)`.
+pub fn price(price: u32, base: u32, locale: Locale) -> String {
+    if price == base {
+        money(price, locale)
+    } else {
+        format!("{} (base {})", money(price, locale), money(base, locale))
+    }
+}
+
+/// Formats time played, like `3h 24m`, or `24m` under an hour.
+pub fn playtime(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes}m"),
+        (hours, minutes) => format!("{hours}h {minutes:02}m"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn money_in_each_locale_style() {
+        assert_eq!(format_money(123_456, Locale::En), "$1,234.56");
+        assert_eq!(format_money(123_456, Locale::Fr), "1 234,56 $");
+        assert_eq!(format_money(123_456, Locale::De), "1.234,56 $");
+    }
+
+    #[test]
+    fn money_leaves_out_zero_cents() {
+        assert_eq!(format_money(0, Locale::En), "$0");
+        assert_eq!(format_money(500_000, Locale::De), "5.000 $");
+        assert_eq!(format_money(5, Locale::Fr), "0,05 $");
+    }
+
+    #[test]
+    fn negative_money_keeps_the_sign_in_front() {
+        assert_eq!(format_money(-123_456, Locale::En), "-$1,234.56");
+        assert_eq!(format_money(-123_456, Locale::Fr), "-1 234,56 $");
+    }
+
+    #[test]
+    fn ints_group_by_thousands() {
+        assert_eq!(format_int(0, Locale::En), "0");
+        assert_eq!(format_int(999, Locale::En), "999");
+        assert_eq!(format_int(1_234_567, Locale::En), "1,234,567");
+        assert_eq!(format_int(-1_250, Locale::Fr), "-1 250");
+        assert_eq!(format_int(-1_250, Locale::De), "-1.250");
+    }
+
+    #[test]
+    fn right_to_left_numbers_are_isolated() {
+        assert_eq!(
+            format_int(1_250, Locale::PseudoRtl),
+            format!("{LEFT_TO_RIGHT_ISOLATE}1,250{POP_ISOLATE}")
+        );
+    }
+
+    #[test]
+    fn playtime_in_hours_and_minutes() {
+        let playtime = |seconds| playtime(Duration::from_secs(seconds));
+        assert_eq!(playtime(0), "0m");
+        assert_eq!(playtime(59), "0m");
+        assert_eq!(playtime(60), "1m");
+        assert_eq!(playtime(59 * 60 + 59), "59m");
+        assert_eq!(playtime(60 * 60), "1h 00m");
+        assert_eq!(playtime(60 * 60 + 5 * 60), "1h 05m");
+        assert_eq!(playtime(3 * 60 * 60 + 24 * 60 + 59), "3h 24m");
+        assert_eq!(playtime(100 * 60 * 60 - 1), "99h 59m");
+        assert_eq!(playtime(100 * 60 * 60), "100h 00m");
+    }
+}
@@ -0,0 +1,188 @@
+//! Gamepad rumble for the run's big moments: rent going out or money lost, payday, and
+//! winning. Anything can ask for one with [`Haptics::play`]. Only the most important
+//! request of a frame plays, and it can't be cut short by a lesser one, so a burst of
+//! events never piles up rumbles.
+//!
+//! Nothing rumbles unless the player is on a gamepad and [`Rumble`] is on.
+
+use std::time::Duration;
+
+use bevy::{
+    input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest},
+    prelude::*,
+};
+
+use crate::AppState;
+use crate::input_device::{ActiveInputDevice, InputDevice};
+use crate::settings::Rumble;
+use crate::sim::ledger::{Category, Transaction};
+use crate::timers::RealUiTime;
+
+/// One buzz of a pattern: when it starts, how long it lasts and how hard each motor runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pulse {
+    pub at: Duration,
+    pub duration: Duration,
+    pub strong: f32,
+    pub weak: f32,
+}
+
+impl Pulse {
+    const fn new(at_millis: u64, millis: u64, strong: f32, weak: f32) -> Self {
+        Pulse {
+            at: Duration::from_millis(at_millis),
+            duration: Duration::from_millis(millis),
+            strong,
+            weak,
+        }
+    }
+}
+
+/// A short, strong jolt.
+const SETBACK: &[Pulse] = &[Pulse::new(0, 200, 1.0, 0.6)];
+/// Two soft taps.
+const PAYDAY: &[Pulse] = &[Pulse::new(0, 80, 0.0, 0.4), Pulse::new(160, 80, 0.0, 0.4)];
+/// Three taps building up to a long swell.
+const WIN: &[Pulse] = &[
+    Pulse::new(0, 100, 0.2, 0.5),
+    Pulse::new(200, 100, 0.4, 0.7),
+    Pulse::new(400, 100, 0.6, 0.9),
+    Pulse::new(600, 600, 1.0, 1.0),
+];
+
+/// What to rumble for, least important first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Haptic {
+    Payday,
+    Setback,
+    Win,
+}
+
+impl Haptic {
+    pub fn pattern(self) -> &'static [Pulse] {
+        match self {
+            Haptic::Payday => PAYDAY,
+            Haptic::Setback => SETBACK,
+            Haptic::Win => WIN,
+        }
+    }
+}
+
+/// The rumble asked for this frame and the one playing.
+#[derive(Resource, Debug, Default)]
+pub struct Haptics {
+    requested: Option<Haptic>,
+    playing: Option<Playing>,
+}
+
+#[derive(Debug)]
+struct Playing {
+    haptic: Haptic,
+    elapsed: Duration,
+    /// Index of the next pulse to start.
+    next: usize,
+}
+
+impl Haptics {
+    /// Asks for `haptic` to play. Of everything asked for in a frame, only the most
+    /// important plays.
+    pub fn play(&mut self, haptic: Haptic) {
+        self.requested = self.requested.max(Some(haptic));
+    }
+}
+
+pub fn haptics_plugin(app: &mut App) {
+    app.init_resource::<Haptics>()
+        .add_systems(OnEnter(AppState::Win), rumble_on_win)
+        .add_systems(OnEnter(AppState::GameOver), rumble_on_game_over)
+        .add_systems(Update, (rumble_for_transactions, play_haptics).chain());
+}
+
+fn rumble_on_win(mut haptics: ResMut<Haptics>) {
+    haptics.play(Haptic::Win);
+}
+
+fn rumble_on_game_over(mut haptics: ResMut<Haptics>) {
+    haptics.play(Haptic::Setback);
+}
+
+fn rumble_for_transactions(
+    mut transactions: EventReader<Transaction>,
+    mut haptics: ResMut<Haptics>,
+) {
+    for transaction in transactions.read() {
+        match *transaction {
+            Transaction::Expense(Category::Rent | Category::Theft, _) => {
+                haptics.play(Haptic::Setback);
+            }
+            Transaction::Income(Category::Wages, _) => haptics.play(Haptic::Payday),
+            _ => {}
+        }
+    }
+}
+
+fn play_haptics(
+    time: RealUiTime,
+    rumble: Res<Rumble>,
+    device: Res<ActiveInputDevice>,
+    mut haptics: ResMut<Haptics>,
+    gamepads: Query<Entity, With<Gamepad>>,
+    mut requests: EventWriter<GamepadRumbleRequest>,
+) {
+    let enabled = *rumble == Rumble::On && device.0 == InputDevice::Gamepad;
+    let requested = haptics.requested.take();
+    if !enabled {
+        if haptics.playing.take().is_some() {
+            for gamepad in &gamepads {
+                requests.write(GamepadRumbleRequest::Stop { gamepad });
+            }
+        }
+        return;
+    }
+
+    let outranks = |haptic| {
+        haptics
+            .playing
+            .as_ref()
+            .is_none_or(|playing| haptic >= playing.haptic)
+    };
+    if let Some(haptic) = requested.filter(|haptic| outranks(*haptic)) {
+        for gamepad in &gamepads {
+            requests.write(GamepadRumbleRequest::Stop { gamepad });
+        }
+        haptics.playing = Some(Playing {
+            haptic,
+            elapsed: Duration::ZERO,
+            next: 0,
+        });
+    }
+
+    let Some(playing) = &mut haptics.playing else {
+        return;
+    };
+    let pattern = playing.haptic.pattern();
+    while let Some(pulse) = pattern.get(playing.next)
+        && pulse.at <= playing.elapsed
+    {
+        for gamepad in &gamepads {
+            requests.write(GamepadRumbleRequest::Add {
+                duration: pulse.duration,
+                intensity: GamepadRumbleIntensity {
+                    strong_motor: pulse.strong,
+                    weak_motor: pulse.weak,
+                },
+                gamepad,
+            });
+        }
+        playing.next += 1;
+    }
+    playing.elapsed += time.delta();
+    let end = pattern
+        .iter()
+        .map(|pulse| pulse.at + pulse.duration)
+        .max()
+        .unwrap_or_default();
+    if playing.elapsed >= end {
+        haptics.playing = None;
+    }
+}
@@ -0,0 +1,232 @@
+//! Which kind of input the player last used, so prompts can name the right buttons. Also
+//! how long it's been since any input at all, see [`IdleTime`].
+
+use std::time::Duration;
+
+use bevy::{
+    input::{
+        gamepad::{GamepadAxisChangedEvent, GamepadButtonChangedEvent},
+        keyboard::KeyboardInput,
+        mouse::{AccumulatedMouseMotion, MouseButtonInput, MouseWheel},
+    },
+    prelude::*,
+};
+
+use crate::timers::RealUiTime;
+
+/// How far the mouse has to travel, in pixels, to take over from a gamepad. A nudged desk
+/// shouldn't flip every prompt.
+const MOUSE_SWITCH_DISTANCE: f32 = 24.0;
+/// How far a stick or trigger has to move to take over from the keyboard and mouse.
+const GAMEPAD_AXIS_THRESHOLD: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputDevice {
+    #[default]
+    KeyboardMouse,
+    Gamepad,
+}
+
+impl InputDevice {
+    /// Picks the prompt matching this device.
+    pub fn prompt(self, keyboard_mouse: &'static str, gamepad: &'static str) -> &'static str {
+        match self {
+            InputDevice::KeyboardMouse => keyboard_mouse,
+            InputDevice::Gamepad => gamepad,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default, PartialEq, Eq)]
+pub struct ActiveInputDevice(pub InputDevice);
+
+/// How long since the player last pressed, moved or scrolled anything.
+#[derive(Resource, Debug, Default)]
+pub struct IdleTime(pub Duration);
+
+/// Sent when [`ActiveInputDevice`] switches.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct InputDeviceChanged(pub InputDevice);
+
+pub fn input_device_plugin(app: &mut App) {
+    app.init_resource::<ActiveInputDevice>()
+        .init_resource::<IdleTime>()
+        .add_event::<InputDeviceChanged>()
+        .add_systems(PreUpdate, (track_input_device, track_idle_time));
+}
+
+fn track_input_device(
+    mut keys: EventReader<KeyboardInput>,
+    mut clicks: EventReader<MouseButtonInput>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    mut gamepad_buttons: EventReader<GamepadButtonChangedEvent>,
+    mut gamepad_axes: EventReader<GamepadAxisChangedEvent>,
+    mut mouse_travel: Local<f32>,
+    mut active: ResMut<ActiveInputDevice>,
+    mut changed: EventWriter<InputDeviceChanged>,
+) {
+    // Every reader is drained each frame, so nothing stale decides the next one.
+    let buttons_pressed = gamepad_buttons
+        .read()
+        .filter(|event| event.state.is_pressed())
+        .count();
+    let sticks_moved = gamepad_axes
+        .read()
+        .filter(|event| event.value.abs() >= GAMEPAD_AXIS_THRESHOLD)
+        .count();
+    let gamepad_used = buttons_pressed + sticks_moved > 0;
+    let keyboard_used = keys.read().count() + clicks.read().count() > 0;
+    if active.0 == InputDevice::Gamepad {
+        *mouse_travel += mouse_motion.delta.length();
+    }
+
+    let device = if gamepad_used {
+        InputDevice::Gamepad
+    } else if keyboard_used || *mouse_travel >= MOUSE_SWITCH_DISTANCE {
+        InputDevice::KeyboardMouse
+    } else {
+        return;
+    };
+    *mouse_travel = 0.0;
+    if active.set_if_neq(ActiveInputDevice(device)) {
+        changed.write(InputDeviceChanged(device));
+    }
+}
+
+fn track_idle_time(
+    mut keys: EventReader<KeyboardInput>,
+    mut clicks: EventReader<MouseButtonInput>,
+    mut wheel: EventReader<MouseWheel>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    mut gamepad_buttons: EventReader<GamepadButtonChangedEvent>,
+    mut gamepad_axes: EventReader<GamepadAxisChangedEvent>,
+    time: RealUiTime,
+    mut idle: ResMut<IdleTime>,
+) {
+    let events = keys.read().count()
+        + clicks.read().count()
+        + wheel.read().count()
+        + gamepad_buttons.read().count();
+    let sticks_moved = gamepad_axes
+        .read()
+        .filter(|event| event.value.abs() >= GAMEPAD_AXIS_THRESHOLD)
+        .count();
+    if events + sticks_moved > 0 || mouse_motion.delta != Vec2::ZERO {
+        idle.0 = Duration::ZERO;
+    } else {
+        idle.0 += time.delta();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::{ButtonState, keyboard::Key};
+
+    use super::*;
+
+    fn input_app() -> App {
+        let mut app = App::new();
+        app.add_event::<KeyboardInput>()
+            .add_event::<MouseButtonInput>()
+            .add_event::<GamepadButtonChangedEvent>()
+            .add_event::<GamepadAxisChangedEvent>()
+            .add_event::<InputDeviceChanged>()
+            .init_resource::<AccumulatedMouseMotion>()
+            .init_resource::<ActiveInputDevice>()
+            .add_systems(Update, track_input_device);
+        app
+    }
+
+    fn device(app: &App) -> InputDevice {
+        app.world().resource::<ActiveInputDevice>().0
+    }
+
+    fn changes(app: &mut App) -> Vec<InputDevice> {
+        app.world_mut()
+            .resource_mut::<Events<InputDeviceChanged>>()
+            .drain()
+            .map(|InputDeviceChanged(device)| device)
+            .collect()
+    }
+
+    fn press_south(app: &mut App) {
+        app.world_mut().send_event(GamepadButtonChangedEvent::new(
+            Entity::PLACEHOLDER,
+            GamepadButton::South,
+            ButtonState::Pressed,
+            1.0,
+        ));
+        app.update();
+    }
+
+    fn move_mouse(app: &mut App, distance: f32) {
+        app.world_mut()
+            .resource_mut::<AccumulatedMouseMotion>()
+            .delta = Vec2::new(distance, 0.0);
+        app.update();
+    }
+
+    #[test]
+    fn a_gamepad_button_switches_at_once() {
+        let mut app = input_app();
+        press_south(&mut app);
+        assert_eq!(device(&app), InputDevice::Gamepad);
+        assert_eq!(changes(&mut app), [InputDevice::Gamepad]);
+    }
+
+    #[test]
+    fn a_light_stick_touch_doesnt_switch() {
+        let mut app = input_app();
+        app.world_mut().send_event(GamepadAxisChangedEvent::new(
+            Entity::PLACEHOLDER,
+            GamepadAxis::LeftStickX,
+            GAMEPAD_AXIS_THRESHOLD / 2.0,
+        ));
+        app.update();
+        assert_eq!(device(&app), InputDevice::KeyboardMouse);
+        assert!(changes(&mut app).is_empty());
+    }
+
+    #[test]
+    fn the_mouse_takes_over_only_after_travelling_far_enough() {
+        let mut app = input_app();
+        press_south(&mut app);
+        changes(&mut app);
+
+        let step = MOUSE_SWITCH_DISTANCE * 0.4;
+        move_mouse(&mut app, step);
+        move_mouse(&mut app, step);
+        assert_eq!(device(&app), InputDevice::Gamepad);
+        move_mouse(&mut app, step);
+        assert_eq!(device(&app), InputDevice::KeyboardMouse);
+        assert_eq!(changes(&mut app), [InputDevice::KeyboardMouse]);
+    }
+
+    #[test]
+    fn using_the_gamepad_resets_the_mouse_travel() {
+        let mut app = input_app();
+        press_south(&mut app);
+        move_mouse(&mut app, MOUSE_SWITCH_DISTANCE * 0.6);
+        press_south(&mut app);
+        move_mouse(&mut app, MOUSE_SWITCH_DISTANCE * 0.6);
+        assert_eq!(device(&app), InputDevice::Gamepad);
+    }
+
+    #[test]
+    fn a_key_switches_back_at_once() {
+        let mut app = input_app();
+        press_south(&mut app);
+        changes(&mut app);
+        app.world_mut().send_event(KeyboardInput {
+            key_code: KeyCode::Space,
+            logical_key: Key::Space,
+            state: ButtonState::Pressed,
+            text: None,
+            repeat: false,
+            window: Entity::PLACEHOLDER,
+        });
+        app.update();
+        assert_eq!(device(&app), InputDevice::KeyboardMouse);
+        assert_eq!(changes(&mut app), [InputDevice::KeyboardMouse]);
+    }
+}
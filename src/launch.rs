@@ -0,0 +1,390 @@
+//! Command line flags, for testing and streaming setups that shouldn't have to click
+//! through the menus. [`parse`] turns the arguments into [`LaunchOptions`], which the
+//! plugins that care read at startup.
+//!
+//! Flags only last for the session: they override the profile's settings without writing
+//! them back, unless `--save-settings` is passed too.
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::profiles::ProfilePaths;
+use crate::screens::menu::MenuState;
+use crate::screens::nav::{ScreenNav, TransitionCause};
+use crate::settings::DisplayMode;
+use crate::sim::quicksave::{LoadOnStart, QUICKSAVE_SLOT, save_slots};
+use crate::snapshots::SnapshotMode;
+
+pub const USAGE: &str = "\
+Usage: breakout [options]
+
+  --windowed            start in a window
+  --fullscreen          start fullscreen
+  --resolution WxH      start with a window this size, like 1280x720
+  --profile NAME        play as this profile, made if it doesn't exist
+  --seed N              seed new runs with N
+  --skip-menu           go straight into a new run
+  --load [SLOT]         continue the profile's save in SLOT, its quicksave if not
+                        given
+  --replay FILE         play back a replay log, see the dev builds' replay.ron
+  --headless            with --replay, play it back without a window as fast as it
+                        can and quit, failing if it diverged
+  --save-settings       keep the profile picked with --profile for next time
+  --self-test           walk every screen without a window, report and quit
+  --balance RUNS        play RUNS runs per scripted policy without a window, report
+                        and quit, see --seed, --days and --csv
+  --snapshots           lay out the main screens without a window, compare them with
+                        their snapshot files and quit
+  --update-snapshots    the same, writing the snapshot files instead
+  --days N              with --balance, end each run after N days, 365 if not given
+  --csv FILE            with --balance, also write every run to FILE
+  -h, --help            show this and quit";
+
+/// What the game was started with.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchOptions {
+    pub display: Option<DisplayMode>,
+    pub resolution: Option<UVec2>,
+    pub profile: Option<String>,
+    pub seed: Option<u64>,
+    pub skip_menu: bool,
+    /// The save slot to continue.
+    pub load: Option<String>,
+    pub replay: Option<PathBuf>,
+    pub headless: bool,
+    pub save_settings: bool,
+    pub self_test: bool,
+    pub balance: Option<u32>,
+    pub days: Option<u32>,
+    pub csv: Option<PathBuf>,
+    pub snapshots: Option<SnapshotMode>,
+    pub help: bool,
+    /// Where the profiles are kept, [`crate::profiles::PROFILES_DIR`] if not set. There's
+    /// no flag for it: tests point it at a scratch directory.
+    pub profiles_dir: Option<PathBuf>,
+}
+
+/// Reads the flags in `args`, without the program name. The error says what's wrong with
+/// them.
+pub fn parse(args: Vec<String>) -> Result<LaunchOptions, String> {
+    let mut options = LaunchOptions::default();
+    let mut args = args.into_iter().peekable();
+    while let Some(flag) = args.next() {
+        let mut value = || {
+            args.next()
+                .filter(|value| !value.starts_with("--"))
+                .ok_or_else(|| format!("{flag} needs a value"))
+        };
+        match flag.as_str() {
+            "--windowed" | "--fullscreen" => {
+                let display = if flag == "--windowed" {
+                    DisplayMode::Windowed
+                } else {
+                    DisplayMode::Fullscreen
+                };
+                if options.display.is_some_and(|picked| picked != display) {
+                    return Err("--windowed and --fullscreen can't both be used".to_string());
+                }
+                options.display = Some(display);
+            }
+            "--resolution" => {
+                let value = value()?;
+                options.resolution = Some(
+                    parse_resolution(&value)
+                        .ok_or_else(|| format!("--resolution {value} isn't like 1280x720"))?,
+                );
+            }
+            "--profile" => options.profile = Some(value()?),
+            "--seed" => {
+                let value = value()?;
+                options.seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("--seed {value} isn't a whole number"))?,
+                );
+            }
+            "--skip-menu" => options.skip_menu = true,
+            "--load" => {
+                let slot = args.next_if(|value| !value.starts_with('-'));
+                options.load = Some(slot.unwrap_or_else(|| QUICKSAVE_SLOT.to_string()));
+            }
+            "--replay" => options.replay = Some(PathBuf::from(value()?)),
+            "--headless" => options.headless = true,
+            "--save-settings" => options.save_settings = true,
+            "--self-test" => options.self_test = true,
+            "--balance" | "--days" => {
+                let value = value()?;
+                let count = value
+                    .parse()
+                    .ok()
+                    .filter(|count| *count > 0)
+                    .ok_or_else(|| format!("{flag} {value} isn't a whole number above 0"))?;
+                if flag == "--balance" {
+                    options.balance = Some(count);
+                } else {
+                    options.days = Some(count);
+                }
+            }
+            "--csv" => options.csv = Some(PathBuf::from(value()?)),
+            "--snapshots" | "--update-snapshots" => {
+                let mode = if flag == "--snapshots" {
+                    SnapshotMode::Check
+                } else {
+                    SnapshotMode::Update
+                };
+                if options.snapshots.is_some_and(|picked| picked != mode) {
+                    return Err("--snapshots and --update-snapshots can't both be used".to_string());
+                }
+                options.snapshots = Some(mode);
+            }
+            "-h" | "--help" => options.help = true,
+            _ => return Err(format!("Unknown option {flag}")),
+        }
+    }
+    if options.skip_menu && options.load.is_some() {
+        return Err("--skip-menu starts a new run, so it can't be used with --load".to_string());
+    }
+    if options.load.is_some() && options.replay.is_some() {
+        return Err("--replay plays back its own run, so it can't be used with --load".to_string());
+    }
+    if options.headless && options.replay.is_none() {
+        return Err("--headless only goes with --replay".to_string());
+    }
+    if options.self_test
+        && (options.skip_menu || options.load.is_some() || options.replay.is_some())
+    {
+        return Err(
+            "--self-test walks the screens itself, so it can't be used with --skip-menu, --load or --replay"
+                .to_string(),
+        );
+    }
+    if options.balance.is_none() && (options.days.is_some() || options.csv.is_some()) {
+        return Err("--days and --csv only go with --balance".to_string());
+    }
+    if options.balance.is_some()
+        && (options.self_test
+            || options.skip_menu
+            || options.load.is_some()
+            || options.replay.is_some())
+    {
+        return Err(
+            "--balance plays its own runs, so it can't be used with --self-test, --skip-menu, --load or --replay"
+                .to_string(),
+        );
+    }
+    if options.snapshots.is_some()
+        && (options.self_test
+            || options.balance.is_some()
+            || options.skip_menu
+            || options.load.is_some()
+            || options.replay.is_some()
+            || options.resolution.is_some())
+    {
+        return Err(
+            "--snapshots lays out its own screens at a fixed size, so it can't be used with --self-test, --balance, --skip-menu, --load, --replay or --resolution"
+                .to_string(),
+        );
+    }
+    Ok(options)
+}
+
+fn parse_resolution(value: &str) -> Option<UVec2> {
+    let (width, height) = value.split_once(['x', 'X'])?;
+    let resolution = UVec2::new(width.parse().ok()?, height.parse().ok()?);
+    (resolution.min_element() > 0).then_some(resolution)
+}
+
+/// Prints what's wrong and the [`USAGE`], and quits.
+pub fn exit_with_usage(error: &str) -> ! {
+    eprintln!("{error}\n\n{USAGE}");
+    std::process::exit(2)
+}
+
+impl LaunchOptions {
+    /// The flags the game was started with, quitting with the usage if they're wrong.
+    pub fn from_args() -> Self {
+        let mut options = parse(std::env::args().skip(1).collect())
+            .unwrap_or_else(|error| exit_with_usage(&error));
+        if options.help {
+            println!("{USAGE}");
+            std::process::exit(0);
+        }
+        // The walk plays like anyone would, so it gets a profile of its own to do it in.
+        if options.self_test && options.profile.is_none() {
+            options.profile = Some(crate::self_test::PROFILE.to_string());
+        }
+        if options.balance.is_some() && options.profile.is_none() {
+            options.profile = Some(crate::balance::PROFILE.to_string());
+        }
+        if options.snapshots.is_some() && options.profile.is_none() {
+            options.profile = Some(crate::snapshots::PROFILE.to_string());
+        }
+        options
+    }
+}
+
+pub fn launch_plugin(app: &mut App) {
+    let options = app.world().resource::<LaunchOptions>().clone();
+    if let Some(display) = options.display {
+        app.insert_resource(display);
+    }
+    if let Some(slot) = options.load.clone() {
+        let profile = app.world().resource::<ProfilePaths>();
+        if let Err(error) = check_slot(profile, &slot) {
+            exit_with_usage(&error);
+        }
+        app.insert_resource(LoadOnStart(slot));
+    }
+    app.add_systems(
+        OnEnter(MenuState::Main),
+        skip_menu.run_if(|options: Res<LaunchOptions>| options.skip_menu || options.load.is_some()),
+    );
+}
+
+/// Whether `profile` has a save in `slot` for `--load`. The error lists the ones it has.
+fn check_slot(profile: &ProfilePaths, slot: &str) -> Result<(), String> {
+    let slots = save_slots(profile);
+    if slots.iter().any(|saved| saved == slot) {
+        Ok(())
+    } else if slots.is_empty() {
+        Err(format!("Profile {} has no saves", profile.name()))
+    } else {
+        Err(format!(
+            "Profile {} has no save in {slot}, its saves are: {}",
+            profile.name(),
+            slots.join(", ")
+        ))
+    }
+}
+
+// Once the menu has opened its main screen, so the menu is left the way its Play button
+// leaves it. Only the first time, so quitting a run still goes back to the menu.
+fn skip_menu(mut skipped: Local<bool>, mut nav: ScreenNav) {
+    if !*skipped {
+        *skipped = true;
+        nav.set_app(AppState::Game, TransitionCause::Automatic);
+        nav.set_menu(MenuState::Disabled, TransitionCause::Automatic);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::quicksave::QUICKSAVE_FILE;
+    use crate::testing::ScratchDir;
+
+    fn parse_str(args: &str) -> Result<LaunchOptions, String> {
+        parse(args.split_whitespace().map(str::to_string).collect())
+    }
+
+    #[test]
+    fn each_flag_sets_its_option() {
+        let parsed = |args| parse_str(args).unwrap();
+        assert_eq!(parsed(""), LaunchOptions::default());
+        assert_eq!(parsed("--windowed").display, Some(DisplayMode::Windowed));
+        assert_eq!(
+            parsed("--fullscreen --fullscreen").display,
+            Some(DisplayMode::Fullscreen)
+        );
+        assert_eq!(
+            parsed("--resolution 1280X720").resolution,
+            Some(UVec2::new(1280, 720))
+        );
+        assert_eq!(parsed("--profile Sam").profile.as_deref(), Some("Sam"));
+        assert_eq!(parsed("--seed 2024").seed, Some(2024));
+        assert!(parsed("--skip-menu").skip_menu);
+        assert_eq!(
+            parsed("--load --profile Sam").load.as_deref(),
+            Some(QUICKSAVE_SLOT)
+        );
+        assert_eq!(
+            parsed("--load before-boss").load.as_deref(),
+            Some("before-boss")
+        );
+        let replay = parsed("--replay run.ron --headless");
+        assert_eq!(replay.replay, Some(PathBuf::from("run.ron")));
+        assert!(replay.headless);
+        assert!(parsed("--save-settings").save_settings);
+        assert!(parsed("--self-test").self_test);
+        let balance = parsed("--balance 5 --days 30 --csv runs.csv");
+        assert_eq!(
+            (balance.balance, balance.days, balance.csv),
+            (Some(5), Some(30), Some(PathBuf::from("runs.csv")))
+        );
+        assert_eq!(parsed("--snapshots").snapshots, Some(SnapshotMode::Check));
+        assert_eq!(
+            parsed("--update-snapshots").snapshots,
+            Some(SnapshotMode::Update)
+        );
+        assert!(parsed("-h").help && parsed("--help").help);
+    }
+
+    #[test]
+    fn conflicting_flags_are_refused() {
+        for args in [
+            "--skip-menu --load",
+            "--windowed --fullscreen",
+            "--load --replay run.ron",
+            "--headless",
+            "--self-test --skip-menu",
+            "--days 30",
+            "--balance 5 --load",
+            "--snapshots --resolution 800x600",
+            "--snapshots --update-snapshots",
+        ] {
+            assert!(parse_str(args).is_err(), "{args} was accepted");
+        }
+        assert_eq!(
+            parse_str("--skip-menu --load").unwrap_err(),
+            "--skip-menu starts a new run, so it can't be used with --load"
+        );
+        assert_eq!(
+            parse_str("--fullscreen --windowed").unwrap_err(),
+            "--windowed and --fullscreen can't both be used"
+        );
+    }
+
+    #[test]
+    fn bad_values_are_refused() {
+        for (args, error) in [
+            ("--resolution 1280", "--resolution 1280 isn't like 1280x720"),
+            (
+                "--resolution 0x720",
+                "--resolution 0x720 isn't like 1280x720",
+            ),
+            ("--resolution wide", "--resolution wide isn't like 1280x720"),
+            ("--resolution", "--resolution needs a value"),
+            ("--seed -1", "--seed -1 isn't a whole number"),
+            ("--seed --skip-menu", "--seed needs a value"),
+            ("--seed lucky", "--seed lucky isn't a whole number"),
+            ("--seed 1.5", "--seed 1.5 isn't a whole number"),
+            ("--balance 0", "--balance 0 isn't a whole number above 0"),
+            ("--bogus", "Unknown option --bogus"),
+        ] {
+            assert_eq!(parse_str(args).unwrap_err(), error);
+        }
+    }
+
+    #[test]
+    fn load_only_takes_a_slot_the_profile_has() {
+        let dir = ScratchDir::new();
+        let profile = ProfilePaths::new(&dir.profiles(), "Sam");
+        assert_eq!(
+            check_slot(&profile, QUICKSAVE_SLOT).unwrap_err(),
+            "Profile Sam has no saves"
+        );
+        std::fs::create_dir_all(profile.dir()).unwrap();
+        for file in [QUICKSAVE_FILE, "before-boss.scn.ron", "settings.ron"] {
+            std::fs::write(profile.file(file), "").unwrap();
+        }
+        assert_eq!(save_slots(&profile), ["before-boss", QUICKSAVE_SLOT]);
+        assert_eq!(check_slot(&profile, QUICKSAVE_SLOT), Ok(()));
+        assert_eq!(check_slot(&profile, "before-boss"), Ok(()));
+        assert_eq!(
+            check_slot(&profile, "settings").unwrap_err(),
+            "Profile Sam has no save in settings, its saves are: before-boss, quicksave"
+        );
+    }
+}
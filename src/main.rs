@@ -2,41 +2,198 @@
 //!
 //! Demonstrates Bevy's stepping capabilities if compiled with the `bevy_debug_stepping` feature.
 
+mod audio;
+mod balance;
+mod data;
+#[cfg(feature = "dev")]
+mod debug;
+#[cfg(debug_assertions)]
+mod diagnostics;
+mod fonts;
+mod format;
+mod haptics;
+mod input_device;
+mod launch;
 mod palette;
+mod power;
+mod profiles;
+mod save_file;
 mod screens;
+mod self_test;
+mod settings;
+mod sim;
+mod snapshots;
+#[cfg(feature = "bevy_debug_stepping")]
 mod stepping;
+#[cfg(test)]
+mod testing;
+mod timers;
+mod toast;
 
 use bevy::prelude::*;
 
-use screens::{game, gameover, menu, win};
+use screens::{
+    actions, amount_input, area_theme, attract, background_animation, button_press, calendar,
+    charity, clock_dial, command_palette, deposit, dialogue, drift, energy_bar, event_card,
+    freelance, game, gameover, hold_confirm, hud, idle_dimming, image_warming, inbox, investments,
+    job_board, journal, loading, loss_feedback, menu, menu_slide, money_feedback, nav, negotiation,
+    net_worth, onboarding, overlay, pause, pet_status, profile_picker, quit_prompt, repair_prompt,
+    report, savings_goal, scenario_picker, scroll, text_backing, text_field, tooltip, travel,
+    virtual_keyboard, win,
+};
 
 const TEXT_COLOR: Color = Color::srgb(0.5, 0.5, 1.0);
 
 #[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
-struct Volume(u32);
+pub struct Volume(u32);
 
-#[derive(States, Debug, Clone, Eq, PartialEq, Hash, Default)]
-enum AppState {
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default, Reflect)]
+pub enum AppState {
     #[default]
+    Loading,
+    AssetError,
     Menu,
     Game,
     GameOver,
     Win,
 }
 
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
+fn main() -> AppExit {
+    game_app(launch::LaunchOptions::from_args()).run()
+}
+
+/// The whole game, set up the way `launch_options` asks, but not running yet.
+fn game_app(launch_options: launch::LaunchOptions) -> App {
+    let mut app = App::new();
+    let assets_root = loading::AssetsRoot::find();
+    let asset_plugin = AssetPlugin {
+        file_path: assets_root.file_path(),
+        ..default()
+    };
+    // Closing the window asks first, see `quit_prompt`.
+    let window_plugin = WindowPlugin {
+        primary_window: Some(Window {
+            resolution: launch_options
+                .resolution
+                .map_or_else(default, |size| size.as_vec2().into()),
+            ..default()
+        }),
+        close_when_requested: false,
+        ..default()
+    };
+    let default_plugins = DefaultPlugins.set(asset_plugin).set(window_plugin);
+    let self_test = launch_options.self_test;
+    let balance = launch_options.balance.is_some();
+    let snapshots = launch_options.snapshots.is_some();
+    if self_test {
+        app.add_plugins(self_test::headless(default_plugins));
+    } else if balance || launch_options.headless {
+        app.add_plugins(balance::headless(default_plugins));
+    } else if snapshots {
+        app.add_plugins(snapshots::headless(default_plugins));
+    } else {
+        app.add_plugins(default_plugins);
+    }
+    app.insert_resource(assets_root)
+        .insert_resource(launch_options)
         .insert_resource(Volume(7))
         .init_state::<AppState>()
         .add_systems(Startup, setup)
+        // Before anything that reads the player's files.
+        .add_plugins(save_file::save_file_plugin)
+        .add_plugins(profiles::profiles_plugin)
+        .add_plugins(launch::launch_plugin)
         .add_plugins((
             menu::menu_plugin,
             game::game_plugin,
             gameover::gameover_plugin,
             win::win_plugin,
+            nav::nav_plugin,
+            loading::loading_plugin,
+            actions::actions_plugin,
+            job_board::job_board_plugin,
+            pet_status::pet_status_plugin,
+            freelance::freelance_plugin,
+            overlay::overlay_plugin,
+            report::report_plugin,
+            pause::pause_plugin,
+            negotiation::negotiation_plugin,
+            dialogue::dialogue_plugin,
+        ))
+        .add_plugins((
+            sim::sim_plugin,
+            fonts::fonts_plugin,
+            toast::toast_plugin,
+            settings::settings_plugin,
+            audio::audio_plugin,
+            hud::hud_plugin,
+            input_device::input_device_plugin,
+            hold_confirm::hold_confirm_plugin,
+            investments::investments_plugin,
+            calendar::calendar_plugin,
+            clock_dial::clock_dial_plugin,
+            inbox::inbox_plugin,
+            scroll::scroll_plugin,
+            amount_input::amount_input_plugin,
+            charity::charity_plugin,
         ))
-        .run();
+        .add_plugins((
+            deposit::deposit_plugin,
+            tooltip::tooltip_plugin,
+            drift::drift_plugin,
+            energy_bar::energy_bar_plugin,
+            background_animation::background_animation_plugin,
+            money_feedback::money_feedback_plugin,
+            quit_prompt::quit_prompt_plugin,
+            attract::attract_plugin,
+            savings_goal::savings_goal_plugin,
+            haptics::haptics_plugin,
+            loss_feedback::loss_feedback_plugin,
+            onboarding::onboarding_plugin,
+            menu_slide::menu_slide_plugin,
+            travel::travel_plugin,
+        ))
+        .add_plugins((
+            net_worth::net_worth_plugin,
+            power::power_plugin,
+            event_card::event_card_plugin,
+            profile_picker::profile_picker_plugin,
+            text_field::text_field_plugin,
+            area_theme::area_theme_plugin,
+            command_palette::command_palette_plugin,
+            virtual_keyboard::virtual_keyboard_plugin,
+            text_backing::text_backing_plugin,
+            button_press::button_press_plugin,
+            repair_prompt::repair_prompt_plugin,
+            journal::journal_plugin,
+            image_warming::image_warming_plugin,
+            idle_dimming::idle_dimming_plugin,
+            scenario_picker::scenario_picker_plugin,
+        ));
+
+    #[cfg(debug_assertions)]
+    app.add_plugins(diagnostics::diagnostics_plugin);
+    #[cfg(feature = "dev")]
+    app.add_plugins(debug::debug_plugin);
+
+    #[cfg(feature = "bevy_debug_stepping")]
+    app.add_plugins(
+        stepping::SteppingPlugin::default()
+            .add_schedule(FixedUpdate)
+            .at(Val::Percent(35.0), Val::Percent(50.0)),
+    );
+
+    if self_test {
+        app.add_plugins(self_test::self_test_plugin);
+    }
+    if balance {
+        app.add_plugins(balance::balance_plugin);
+    }
+    if snapshots {
+        app.add_plugins(snapshots::snapshots_plugin);
+    }
+
+    app
 }
 
 fn setup(mut commands: Commands) {
@@ -49,3 +206,10 @@ fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands
         commands.entity(entity).despawn();
     }
 }
+
+/// Records `T` as the marker of everything a screen spawns while in `state`, so debug
+/// builds can warn when the screen leaves entities behind after exiting, and `--self-test` can
+/// check that it spawns anything at all.
+fn track_screen<T: Component>(app: &mut App, state: impl States) {
+    nav::track_screen::<T, _>(app, state);
+}
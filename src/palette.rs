@@ -6,3 +6,4 @@ pub const DARKER: Color = Color::srgb(80.0 / 255.0, 80.0 / 255.0, 83.0 / 255.0);
 pub const YELLOW: Color = Color::srgb(252.0 / 255.0, 221.0 / 255.0, 104.0 / 255.0);
 pub const CORAL: Color = Color::srgb(250.0 / 255.0, 162.0 / 255.0, 138.0 / 255.0);
 pub const BLUE: Color = Color::srgb(112.0 / 255.0, 185.0 / 255.0, 194.0 / 255.0);
+pub const RED: Color = Color::srgb(214.0 / 255.0, 69.0 / 255.0, 65.0 / 255.0);
@@ -0,0 +1,77 @@
+//! Power saving. While no game is running, on the menus, the end screens and the pause
+//! menu, the app only updates on input, and every [`IDLE_WAKE`] so timers like the toasts'
+//! and the attract mode's keep going. Anything animating there asks for full speed through
+//! [`AnimationsActive`] while it runs.
+
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    winit::{UpdateMode, WinitSettings},
+};
+
+use crate::AppState;
+use crate::screens::overlay::Overlay;
+
+/// How long an idle screen waits for input before updating anyway.
+const IDLE_WAKE: Duration = Duration::from_millis(100);
+/// The same without focus, where only the audio and the attract mode need it.
+const UNFOCUSED_IDLE_WAKE: Duration = Duration::from_secs(1);
+
+/// How many animations ran this frame. Animation systems count themselves in with
+/// [`AnimationsActive::keep_awake`] every frame they run, and the count starts over at
+/// the start of the next.
+#[derive(Resource, Debug, Default)]
+pub struct AnimationsActive(u32);
+
+impl AnimationsActive {
+    pub fn keep_awake(&mut self) {
+        self.0 += 1;
+    }
+
+    /// Whether anything animated this frame. Only complete once `Update` has run.
+    pub fn any(&self) -> bool {
+        self.0 > 0
+    }
+}
+
+pub fn power_plugin(app: &mut App) {
+    app.init_resource::<AnimationsActive>()
+        .init_resource::<WinitSettings>()
+        .add_systems(First, reset_animations)
+        .add_systems(Last, choose_update_mode);
+}
+
+fn reset_animations(mut animations: ResMut<AnimationsActive>) {
+    animations.0 = 0;
+}
+
+fn idle_settings() -> WinitSettings {
+    WinitSettings {
+        focused_mode: UpdateMode::reactive(IDLE_WAKE),
+        unfocused_mode: UpdateMode::reactive_low_power(UNFOCUSED_IDLE_WAKE),
+    }
+}
+
+// Loading polls the asset server, so it runs flat out like the game.
+fn choose_update_mode(
+    app_state: Res<State<AppState>>,
+    overlay: Option<Res<State<Overlay>>>,
+    animations: Res<AnimationsActive>,
+    mut winit: ResMut<WinitSettings>,
+) {
+    let running = match app_state.get() {
+        AppState::Loading => true,
+        AppState::Game => overlay.is_none_or(|overlay| *overlay.get() != Overlay::Pause),
+        AppState::AssetError | AppState::Menu | AppState::GameOver | AppState::Win => false,
+    };
+    let wanted = if running || animations.0 > 0 {
+        WinitSettings::game()
+    } else {
+        idle_settings()
+    };
+    if winit.focused_mode != wanted.focused_mode || winit.unfocused_mode != wanted.unfocused_mode {
+        debug!("Update mode: {:?}", wanted.focused_mode);
+        *winit = wanted;
+    }
+}
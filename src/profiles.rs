@@ -0,0 +1,214 @@
+//! Player profiles, for players sharing a machine. Each profile keeps its own settings,
+//! checklist progress and high scores in its own directory under [`PROFILES_DIR`], or
+//! wherever [`LaunchOptions::profiles_dir`] says, and everything that reads or writes those
+//! files goes through the active [`ProfilePaths`].
+//!
+//! Files from before profiles, kept next to the profiles directory, move into a
+//! [`DEFAULT_PROFILE`] the first time this version runs.
+
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::launch::{LaunchOptions, exit_with_usage};
+use crate::save_file::{CorruptFiles, Saves, load_ron};
+use crate::settings::SETTINGS_FILE;
+use crate::sim::challenge::HIGH_SCORES_FILE;
+
+pub const PROFILES_DIR: &str = "profiles";
+/// Remembers which profile was active last, inside [`PROFILES_DIR`].
+const ACTIVE_FILE: &str = "active.ron";
+pub const DEFAULT_PROFILE: &str = "Default";
+pub const MAX_NAME_LENGTH: usize = 20;
+
+/// Where the active profile's files are.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct ProfilePaths {
+    name: String,
+    root: PathBuf,
+    dir: PathBuf,
+}
+
+impl ProfilePaths {
+    /// The profile called `name` in the profiles directory `root`.
+    pub fn new(root: &Path, name: &str) -> Self {
+        ProfilePaths {
+            name: name.to_string(),
+            root: root.to_path_buf(),
+            dir: root.join(name),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The profiles directory this profile is in, next to the others.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// This profile's directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The path of `file` in this profile's directory.
+    pub fn file(&self, file: &str) -> PathBuf {
+        self.dir.join(file)
+    }
+}
+
+/// Run condition for reloading what a profile keeps when the player switches to another.
+pub fn profile_switched(profile: Res<ProfilePaths>) -> bool {
+    profile.is_changed() && !profile.is_added()
+}
+
+/// Whether this is the first time the game runs here, so the player picks a profile
+/// before anything else.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirstLaunch(pub bool);
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+struct ActiveProfile {
+    name: String,
+}
+
+/// The profiles in `root`, sorted by name.
+pub fn list(root: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut names: Vec<_> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort_by_key(|name| name.to_lowercase());
+    names
+}
+
+/// Whether `c` can be in a profile name: letters, digits, spaces, dashes and underscores.
+pub fn name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, ' ' | '-' | '_')
+}
+
+/// Whether `name` can name a new profile in `root`: made of [`name_char`]s, and not taken
+/// by another profile whatever the case.
+pub fn valid_name(root: &Path, name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty()
+        && name.chars().count() <= MAX_NAME_LENGTH
+        && name.chars().all(name_char)
+        && !list(root)
+            .iter()
+            .any(|taken| taken.eq_ignore_ascii_case(name))
+}
+
+pub fn create(root: &Path, name: &str) -> std::io::Result<ProfilePaths> {
+    let profile = ProfilePaths::new(root, name.trim());
+    std::fs::create_dir_all(&profile.dir)?;
+    Ok(profile)
+}
+
+/// Removes the profile's directory and everything in it.
+pub fn delete(root: &Path, name: &str) -> std::io::Result<()> {
+    std::fs::remove_dir_all(ProfilePaths::new(root, name).dir)
+}
+
+/// Makes `profile` the one the game starts with next time.
+pub fn remember_active(profile: &ProfilePaths, saves: &mut Saves) {
+    saves.save_ron(
+        profile.root.join(ACTIVE_FILE),
+        ActiveProfile {
+            name: profile.name.clone(),
+        },
+    );
+}
+
+pub fn profiles_plugin(app: &mut App) {
+    let options = app.world().resource::<LaunchOptions>().clone();
+    let root = options
+        .profiles_dir
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(PROFILES_DIR));
+    let mut first_launch = !root.exists() && !migrate_flat_files(&root);
+    let profile = match &options.profile {
+        Some(name) => {
+            first_launch = false;
+            let profile = launch_profile(&root, name);
+            if options.save_settings {
+                remember_active(&profile, &mut app.world_mut().resource_mut::<Saves>());
+            }
+            profile
+        }
+        None => last_active(&root, &mut app.world_mut().resource_mut::<CorruptFiles>())
+            .unwrap_or_else(|| {
+                create(&root, DEFAULT_PROFILE).unwrap_or_else(|error| {
+                    warn!("Couldn't create the {DEFAULT_PROFILE} profile: {error}");
+                    ProfilePaths::new(&root, DEFAULT_PROFILE)
+                })
+            }),
+    };
+    info!("Profile: {}", profile.name);
+    app.insert_resource(profile)
+        .insert_resource(FirstLaunch(first_launch));
+}
+
+// The profile picked with `--profile`, made if there's none by that name yet.
+fn launch_profile(root: &Path, name: &str) -> ProfilePaths {
+    if let Some(existing) = list(root)
+        .into_iter()
+        .find(|taken| taken.eq_ignore_ascii_case(name.trim()))
+    {
+        return ProfilePaths::new(root, &existing);
+    }
+    if !valid_name(root, name) {
+        exit_with_usage(&format!(
+            "--profile {name}: names are up to {MAX_NAME_LENGTH} letters, digits, spaces, - or _"
+        ));
+    }
+    create(root, name).unwrap_or_else(|error| {
+        exit_with_usage(&format!("Couldn't create the profile {name}: {error}"))
+    })
+}
+
+fn last_active(root: &Path, corrupt: &mut CorruptFiles) -> Option<ProfilePaths> {
+    let remembered =
+        load_ron::<ActiveProfile>(&root.join(ACTIVE_FILE), corrupt).map(|active| active.name);
+    let profiles = list(root);
+    remembered
+        .filter(|name| profiles.contains(name))
+        .or_else(|| profiles.into_iter().next())
+        .map(|name| ProfilePaths::new(root, &name))
+}
+
+// Moves the files kept next to the profiles directory before profiles into the default
+// profile. Returns whether there were any.
+fn migrate_flat_files(root: &Path) -> bool {
+    let beside = root.parent().unwrap_or(Path::new(""));
+    let files: Vec<_> = [SETTINGS_FILE, HIGH_SCORES_FILE]
+        .into_iter()
+        .map(|file| (file, beside.join(file)))
+        .filter(|(_, path)| path.is_file())
+        .collect();
+    if files.is_empty() {
+        return false;
+    }
+    let profile = match create(root, DEFAULT_PROFILE) {
+        Ok(profile) => profile,
+        Err(error) => {
+            warn!("Couldn't create the {DEFAULT_PROFILE} profile: {error}");
+            return false;
+        }
+    };
+    for (file, path) in files {
+        match std::fs::rename(path, profile.file(file)) {
+            Ok(()) => info!("Moved {file} into the {DEFAULT_PROFILE} profile"),
+            Err(error) => warn!("Couldn't move {file} into the {DEFAULT_PROFILE} profile: {error}"),
+        }
+    }
+    true
+}
@@ -0,0 +1,527 @@
+//! Writing the files the game keeps between sessions, like a profile's [`SETTINGS_FILE`]
+//! and [`HIGH_SCORES_FILE`], off the main thread. Serializing and writing run on the
+//! [`AsyncComputeTaskPool`], so a slow disk doesn't hitch the game, and a failed write
+//! shows a toast once it's done.
+//!
+//! Saves go through the [`Saves`] resource, so each app only hears about its own, and
+//! [`SaveFinished`] says when each is written, for the screens that wait on one.
+//! Saving a file again before the last save is written only writes the newest copy.
+//! Quitting waits for the writes still going.
+//!
+//! Big files, like the quicksave, are read back with [`load_in_background`], which parses
+//! them on the same pool and applies them in one frame once they're ready. A "Loading…"
+//! overlay blocks input in the meantime. The small ones go through [`load_ron`]. A file
+//! that doesn't parse, like one half-written when the game crashed, is kept under its
+//! `.bad` name and listed in [`CorruptFiles`], so the player can be asked whether to
+//! salvage what's left of it.
+//!
+//! [`SETTINGS_FILE`]: crate::settings::SETTINGS_FILE
+//! [`HIGH_SCORES_FILE`]: crate::sim::challenge::HIGH_SCORES_FILE
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, TaskPool, block_on, futures::check_ready};
+use bevy::ui::FocusPolicy;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::fonts::ui_font;
+use crate::palette::{DARKER, LIGHT};
+use crate::screens::overlay::{ModalStack, ModalToken};
+use crate::toast::Toast;
+
+/// Above everything else, quit prompt included, since nothing can be used while loading.
+const LOADING_Z_INDEX: i32 = 300;
+
+/// The files being written, kept until each is reported on.
+#[derive(Resource, Default)]
+pub struct Saves {
+    order: Arc<SaveOrder>,
+    pending: Vec<Save>,
+    last_id: u64,
+}
+
+/// Tells one save from another, see [`SaveFinished`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SaveId(u64);
+
+/// Sent once a save is written, or with why it couldn't be. A save replaced by a newer
+/// one before it was written counts as written.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct SaveFinished {
+    pub id: SaveId,
+    pub result: Result<(), String>,
+}
+
+/// Both of the app's file lists, for systems that read a file and write it back.
+#[derive(SystemParam)]
+pub struct SaveFiles<'w> {
+    pub saves: ResMut<'w, Saves>,
+    pub corrupt: ResMut<'w, CorruptFiles>,
+}
+
+/// Shared with the writes in the background.
+#[derive(Default)]
+struct SaveOrder {
+    /// The number of the latest save asked for each file. A save that isn't the latest by
+    /// the time it's ready to write is dropped.
+    latest: Mutex<BTreeMap<PathBuf, u64>>,
+    /// Held while writing, so an older save can't land after a newer one.
+    writing: Mutex<()>,
+}
+
+struct Save {
+    id: SaveId,
+    path: PathBuf,
+    task: Task<Result<(), String>>,
+    /// Shown once it's written.
+    saved: Option<String>,
+}
+
+impl Saves {
+    /// Writes `value` to `path` as RON in the background, replacing any save of `path`
+    /// not yet written. Missing directories on the way are created.
+    pub fn save_ron<T: Serialize + Send + 'static>(&mut self, path: PathBuf, value: T) -> SaveId {
+        self.save(
+            path,
+            move || {
+                ron::ser::to_string_pretty(&value, default())
+                    .map(String::into_bytes)
+                    .map_err(|error| error.to_string())
+            },
+            None,
+        )
+    }
+
+    /// Writes the bytes `encode` makes to `path` like [`Saves::save_ron`], encoding in the
+    /// background too, for files that aren't text, like the journal's photos.
+    pub fn save_bytes(
+        &mut self,
+        path: PathBuf,
+        encode: impl FnOnce() -> Result<Vec<u8>, String> + Send + 'static,
+    ) -> SaveId {
+        self.save(path, encode, None)
+    }
+
+    /// Writes the bytes `encode` makes to `path` like [`Saves::save_bytes`], and shows
+    /// `saved` as a toast once they're written, for saves the player asked for.
+    pub fn save_bytes_with_toast(
+        &mut self,
+        path: PathBuf,
+        saved: impl Into<String>,
+        encode: impl FnOnce() -> Result<Vec<u8>, String> + Send + 'static,
+    ) -> SaveId {
+        self.save(path, encode, Some(saved.into()))
+    }
+
+    fn save(
+        &mut self,
+        path: PathBuf,
+        serialize: impl FnOnce() -> Result<Vec<u8>, String> + Send + 'static,
+        saved: Option<String>,
+    ) -> SaveId {
+        let number = {
+            let mut latest = self
+                .order
+                .latest
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let number = latest.entry(path.clone()).or_default();
+            *number += 1;
+            *number
+        };
+        let order = self.order.clone();
+        let target = path.clone();
+        let task = AsyncComputeTaskPool::get_or_init(TaskPool::new).spawn(async move {
+            let bytes = serialize()?;
+            let _writing = order.writing.lock().unwrap_or_else(PoisonError::into_inner);
+            let latest = order.latest.lock().unwrap_or_else(PoisonError::into_inner)[&target];
+            if latest != number {
+                return Ok(());
+            }
+            write(&target, &bytes).map_err(|error| error.to_string())
+        });
+        self.last_id += 1;
+        let id = SaveId(self.last_id);
+        self.pending.push(Save {
+            id,
+            path,
+            task,
+            saved,
+        });
+        id
+    }
+}
+
+fn write(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, bytes)
+}
+
+/// A file that didn't parse, moved aside so the next save doesn't overwrite it.
+#[derive(Debug, Clone)]
+pub struct CorruptFile {
+    pub path: PathBuf,
+    /// Where the broken copy is kept: the same name with a `bad` extension.
+    pub bad: PathBuf,
+    /// What can be read of the broken copy, written out again, see [`salvage_ron`].
+    salvage: fn(&str) -> Option<String>,
+}
+
+impl CorruptFile {
+    /// Writes what can be salvaged from the broken copy back to [`CorruptFile::path`].
+    /// It's written right away rather than in the background, so it can be read again
+    /// straight after.
+    pub fn recover(&self) -> Result<(), String> {
+        let text = std::fs::read_to_string(&self.bad).map_err(|error| error.to_string())?;
+        let salvaged = (self.salvage)(&text).ok_or("nothing in it could be read")?;
+        write(&self.path, salvaged.as_bytes()).map_err(|error| error.to_string())
+    }
+}
+
+/// The broken files the player hasn't been asked about yet.
+#[derive(Resource, Debug, Default)]
+pub struct CorruptFiles(pub Vec<CorruptFile>);
+
+/// Reads `path` as RON, or `None` if it's missing. A file that doesn't parse is moved to
+/// its `.bad` name and listed in `corrupt`, and reads as `None` too.
+pub fn load_ron<T: Serialize + DeserializeOwned>(
+    path: &Path,
+    corrupt: &mut CorruptFiles,
+) -> Option<T> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let error = match ron::from_str(&text) {
+        Ok(value) => return Some(value),
+        Err(error) => error,
+    };
+    let bad = path.with_extension("bad");
+    warn!(
+        "Moving unreadable {} to {}: {error}",
+        path.display(),
+        bad.display()
+    );
+    match std::fs::rename(path, &bad) {
+        Ok(()) => corrupt.0.push(CorruptFile {
+            path: path.to_path_buf(),
+            bad,
+            salvage: salvage_text::<T>,
+        }),
+        Err(error) => warn!("Couldn't move {}: {error}", path.display()),
+    }
+    None
+}
+
+fn salvage_text<T: Serialize + DeserializeOwned>(text: &str) -> Option<String> {
+    let value: T = salvage_ron(text)?;
+    ron::ser::to_string_pretty(&value, default()).ok()
+}
+
+/// Reads what it can of a broken RON struct, like one cut off halfway or with a value of
+/// the wrong type: every top-level field that reads on its own is kept, and the rest are
+/// left out, so `T` needs `#[serde(default)]`. `None` if `text` isn't a struct at all.
+pub fn salvage_ron<T: DeserializeOwned>(text: &str) -> Option<T> {
+    let fields: Vec<_> = top_level_fields(text)?
+        .into_iter()
+        .filter(|field| ron::from_str::<T>(&format!("({field})")).is_ok())
+        .collect();
+    ron::from_str(&format!("({})", fields.join(","))).ok()
+}
+
+// The fields of the outermost struct in `text`, like `muted: true`, as written. If the
+// struct is cut off, so is its last field.
+fn top_level_fields(text: &str) -> Option<Vec<&str>> {
+    let start = text.find('(')? + 1;
+    let mut fields = Vec::new();
+    let mut field_start = start;
+    let mut end = text.len();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in text[start..].char_indices() {
+        let index = start + index;
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == open {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth == 0 => {
+                end = index;
+                break;
+            }
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                fields.push(&text[field_start..index]);
+                field_start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(&text[field_start..end]);
+    Some(
+        fields
+            .into_iter()
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .collect(),
+    )
+}
+
+/// What a finished read does with what it read.
+type ApplyLoad = Box<dyn FnOnce(&mut World) + Send>;
+
+/// Files being read by [`load_in_background`].
+#[derive(Resource, Default)]
+struct Loads {
+    tasks: Vec<Task<ApplyLoad>>,
+    /// Held while the loading overlay is up, so the game takes no input.
+    modal: Option<ModalToken>,
+}
+
+#[derive(Component)]
+struct LoadingOverlay;
+
+/// Reads `path` and turns its text into a `T` with `parse`, both in the background, then
+/// calls `apply` with it, or with why it couldn't, at the start of the first frame it's
+/// ready for. Until then the loading overlay is up.
+pub fn load_in_background<T: Send + 'static>(
+    world: &mut World,
+    path: PathBuf,
+    parse: impl FnOnce(&str) -> Result<T, String> + Send + 'static,
+    apply: impl FnOnce(&mut World, Result<T, String>) + Send + 'static,
+) {
+    let task = AsyncComputeTaskPool::get_or_init(TaskPool::new).spawn(async move {
+        let read = std::fs::read_to_string(&path)
+            .map_err(|error| format!("{}: {error}", path.display()))
+            .and_then(|text| parse(&text));
+        Box::new(move |world: &mut World| apply(world, read)) as ApplyLoad
+    });
+    world.resource_mut::<Loads>().tasks.push(task);
+}
+
+pub fn save_file_plugin(app: &mut App) {
+    app.init_resource::<Saves>()
+        .init_resource::<CorruptFiles>()
+        .init_resource::<Loads>()
+        .add_event::<SaveFinished>()
+        .add_systems(PreUpdate, apply_loads)
+        .add_systems(Update, (report_saves, show_loading))
+        .add_systems(Last, finish_saves_on_exit);
+}
+
+fn apply_loads(world: &mut World) {
+    let mut ready = Vec::new();
+    world.resource_mut::<Loads>().tasks.retain_mut(|task| {
+        let Some(apply) = check_ready(task) else {
+            return true;
+        };
+        ready.push(apply);
+        false
+    });
+    for apply in ready {
+        apply(world);
+    }
+}
+
+fn show_loading(
+    mut commands: Commands,
+    mut loads: ResMut<Loads>,
+    overlay: Query<Entity, With<LoadingOverlay>>,
+    mut modals: ResMut<ModalStack>,
+    assets: Res<AssetServer>,
+) {
+    match (loads.tasks.is_empty(), loads.modal) {
+        (false, None) => {
+            loads.modal = Some(modals.open_modal());
+            commands.spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                BackgroundColor(DARKER.with_alpha(0.8)),
+                FocusPolicy::Block,
+                GlobalZIndex(LOADING_Z_INDEX),
+                LoadingOverlay,
+                children![(
+                    Text::new("Loading…"),
+                    TextFont {
+                        font: ui_font(&assets),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(LIGHT),
+                )],
+            ));
+        }
+        (true, Some(token)) => {
+            loads.modal = None;
+            modals.close_modal(token);
+            for entity in &overlay {
+                commands.entity(entity).despawn();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn report_saves(
+    mut saves: ResMut<Saves>,
+    mut toasts: EventWriter<Toast>,
+    mut finished: EventWriter<SaveFinished>,
+) {
+    saves.pending.retain_mut(|save| {
+        let Some(result) = check_ready(&mut save.task) else {
+            return true;
+        };
+        match &result {
+            Ok(()) => {
+                if let Some(saved) = save.saved.take() {
+                    toasts.write(Toast::new(saved));
+                }
+            }
+            Err(error) => {
+                warn!("Couldn't save {}: {error}", save.path.display());
+                toasts.write(Toast::new(format!("Couldn't save {}", save.path.display())));
+            }
+        }
+        finished.write(SaveFinished {
+            id: save.id,
+            result,
+        });
+        false
+    });
+}
+
+// `AppExit` ends the app after this frame, so the saves still going are waited for here.
+fn finish_saves_on_exit(mut exit: EventReader<AppExit>, mut saves: ResMut<Saves>) {
+    if exit.is_empty() {
+        return;
+    }
+    exit.clear();
+    for save in std::mem::take(&mut saves.pending) {
+        if let Err(error) = block_on(save.task) {
+            warn!("Couldn't save {}: {error}", save.path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+    #[serde(default)]
+    struct Prefs {
+        volume: f32,
+        muted: bool,
+        name: String,
+        keys: Vec<(String, u32)>,
+    }
+
+    fn prefs() -> Prefs {
+        Prefs {
+            volume: 0.5,
+            muted: true,
+            name: "a, (b)".to_string(),
+            keys: vec![("jump".to_string(), 32), ("quit".to_string(), 27)],
+        }
+    }
+
+    const WHOLE: &str =
+        r#"(volume: 0.5, muted: true, name: "a, (b)", keys: [("jump", 32), ("quit", 27)])"#;
+
+    #[test]
+    fn an_intact_file_salvages_whole() {
+        assert_eq!(salvage_ron::<Prefs>(WHOLE), Some(prefs()));
+    }
+
+    #[test]
+    fn a_cut_off_file_keeps_the_fields_before_the_cut() {
+        let cut = &WHOLE[..WHOLE.find("(\"quit\"").unwrap()];
+        assert_eq!(
+            salvage_ron::<Prefs>(cut),
+            Some(Prefs {
+                keys: Vec::new(),
+                ..prefs()
+            })
+        );
+    }
+
+    #[test]
+    fn a_value_of_the_wrong_type_is_left_out() {
+        let text = r#"(volume: "loud", muted: true, name: "a, (b)")"#;
+        assert_eq!(
+            salvage_ron::<Prefs>(text),
+            Some(Prefs {
+                volume: 0.0,
+                keys: Vec::new(),
+                ..prefs()
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_fields_are_ignored() {
+        let text = r#"(volume: 0.5, shiny: Some([1, 2]), muted: true)"#;
+        assert_eq!(
+            salvage_ron::<Prefs>(text),
+            Some(Prefs {
+                volume: 0.5,
+                muted: true,
+                ..default()
+            })
+        );
+    }
+
+    #[test]
+    fn text_that_isnt_a_struct_salvages_nothing() {
+        assert_eq!(salvage_ron::<Prefs>("garbage"), None);
+        assert_eq!(salvage_ron::<Prefs>(""), None);
+    }
+
+    #[test]
+    fn an_unreadable_file_is_moved_aside_and_can_be_recovered() {
+        let dir = std::env::temp_dir().join(format!("breakout-save-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("prefs.ron");
+        std::fs::write(&path, &WHOLE[..WHOLE.find("name").unwrap()]).unwrap();
+
+        let mut corrupt = CorruptFiles::default();
+        assert_eq!(load_ron::<Prefs>(&path, &mut corrupt), None);
+        assert!(!path.exists());
+        let [file] = &corrupt.0[..] else {
+            panic!("{corrupt:?}");
+        };
+        assert_eq!(file.path, path);
+        assert_eq!(file.bad, dir.join("prefs.bad"));
+
+        file.recover().unwrap();
+        assert_eq!(
+            load_ron::<Prefs>(&path, &mut corrupt),
+            Some(Prefs {
+                volume: 0.5,
+                muted: true,
+                ..default()
+            })
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,503 @@
+//! The panel of buttons for whatever the player can do right now on the game screen.
+//!
+//! Features decide which [`GameAction`]s are available by pushing them into
+//! [`AvailableActions`] during [`ActionSet::Offer`], and react to [`ActionPressed`]
+//! events when the player clicks one. An action can also be offered blocked, which shows
+//! it greyed out with the reason in a tooltip. The panel itself only rebuilds when the
+//! offered actions change. The command palette lists the same offers, see
+//! [`super::command_palette`], and the common actions can also be taken with their
+//! [`GameAction::hotkey`], shown next to their label in both. Whatever a feature offers is
+//! all three go by, so an action is available, or blocked for the same reason, everywhere.
+//! A blocked action's hotkey shows the reason in a toast.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::button_press::ButtonPresses;
+use super::hud::{HudRoot, HudSlot};
+use super::overlay::no_modal_open;
+use super::tooltip::Tooltip;
+use crate::fonts::ui_font;
+use crate::palette::{BLUE, DARK, DARKER, LIGHT, YELLOW};
+use crate::settings::Locale;
+use crate::sim::buffs::Consumable;
+use crate::sim::passive_income::IncomeSource;
+use crate::toast::Toast;
+use crate::{AppState, despawn_screen, track_screen};
+
+const ACTION_FONT_SIZE: f32 = 16.0;
+/// Held with a hotkey, these leave it to whatever else uses the key, like the palette.
+const MODIFIER_KEYS: [KeyCode; 6] = [
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::SuperLeft,
+    KeyCode::SuperRight,
+];
+
+/// Something the player can do from the action panel.
+#[derive(
+    Component, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Reflect, Serialize, Deserialize,
+)]
+pub enum GameAction {
+    WorkShift,
+    WorkOvertime,
+    Freelance,
+    AcceptJobOffer,
+    DeclineJobOffer,
+    FindRoommate,
+    AskRoommateToLeave,
+    BuyDoorLock,
+    BuyUmbrella,
+    AdoptPet,
+    DepositCash,
+    WithdrawSavings,
+    SavingsGoal,
+    Travel,
+    Invest,
+    ViewReports,
+    Calendar,
+    Donate,
+    NegotiateRent,
+    PayRent,
+    ToggleAutopay,
+    /// Talk to the character at this index in the current area.
+    TalkTo(usize),
+    /// Take up the charity event at this index in the charity data.
+    AttendEvent(usize),
+    Buy(Consumable),
+    BuyIncomeSource(IncomeSource),
+}
+
+impl GameAction {
+    fn label(&self) -> &'static str {
+        match self {
+            GameAction::WorkShift => "Work a shift",
+            GameAction::WorkOvertime => "Work overtime",
+            GameAction::Freelance => "Freelance",
+            GameAction::AcceptJobOffer => "Accept job offer",
+            GameAction::DeclineJobOffer => "Decline job offer",
+            GameAction::FindRoommate => "Find a roommate",
+            GameAction::AskRoommateToLeave => "Ask roommate to leave",
+            GameAction::BuyDoorLock => "Buy a door lock",
+            GameAction::BuyUmbrella => "Buy an umbrella",
+            GameAction::AdoptPet => "Adopt a pet",
+            GameAction::DepositCash => "Deposit cash",
+            GameAction::WithdrawSavings => "Withdraw savings",
+            GameAction::SavingsGoal => "Set savings goal",
+            GameAction::Travel => "Travel",
+            GameAction::Invest => "Investments",
+            GameAction::ViewReports => "Reports",
+            GameAction::Calendar => "Calendar",
+            GameAction::Donate => "Donate to charity",
+            GameAction::NegotiateRent => "Negotiate rent",
+            GameAction::PayRent => "Pay rent",
+            GameAction::ToggleAutopay => "Rent autopay",
+            GameAction::TalkTo(_) => "Talk",
+            GameAction::AttendEvent(_) => "Attend",
+            GameAction::Buy(_) => "Buy",
+            GameAction::BuyIncomeSource(_) => "Buy",
+        }
+    }
+
+    /// The key that takes the action on the game screen, for the common ones.
+    pub fn hotkey(&self) -> Option<KeyCode> {
+        match self {
+            GameAction::WorkShift => Some(KeyCode::KeyW),
+            GameAction::WorkOvertime => Some(KeyCode::KeyO),
+            GameAction::Freelance => Some(KeyCode::KeyF),
+            GameAction::DepositCash => Some(KeyCode::KeyD),
+            GameAction::Travel => Some(KeyCode::KeyT),
+            GameAction::Invest => Some(KeyCode::KeyI),
+            GameAction::ViewReports => Some(KeyCode::KeyR),
+            GameAction::Calendar => Some(KeyCode::KeyC),
+            GameAction::PayRent => Some(KeyCode::KeyP),
+            _ => None,
+        }
+    }
+}
+
+/// The hotkey after an action's label, for actions that have one.
+pub fn hotkey_hint(action: GameAction, font: &TextFont) -> Option<impl Bundle> {
+    let key = format!("{:?}", action.hotkey()?);
+    let key = key.strip_prefix("Key").unwrap_or(&key);
+    Some((
+        TextSpan::new(format!("  {key}")),
+        font.clone(),
+        TextColor(YELLOW),
+    ))
+}
+
+/// Sent when the player clicks an action button.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ActionPressed(pub GameAction);
+
+/// A button in the action panel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfferedAction {
+    pub action: GameAction,
+    pub label: String,
+    /// Why the action can't be taken right now, if it can't.
+    pub blocked: Option<String>,
+}
+
+/// The actions offered this frame.
+#[derive(Resource, Debug, Default)]
+pub struct AvailableActions(Vec<OfferedAction>);
+
+impl AvailableActions {
+    /// The actions offered this frame, once [`ActionSet::Offer`] is done.
+    pub fn offered(&self) -> &[OfferedAction] {
+        &self.0
+    }
+
+    pub fn offer(&mut self, action: GameAction) {
+        self.offer_labeled(action, action.label());
+    }
+
+    /// Offers `action` with a custom label, e.g. one that spells out its costs.
+    pub fn offer_labeled(&mut self, action: GameAction, label: impl Into<String>) {
+        self.0.push(OfferedAction {
+            action,
+            label: label.into(),
+            blocked: None,
+        });
+    }
+
+    /// Shows `action` greyed out, with `reason` in a tooltip.
+    pub fn offer_blocked(
+        &mut self,
+        action: GameAction,
+        label: impl Into<String>,
+        reason: impl Into<String>,
+    ) {
+        self.0.push(OfferedAction {
+            action,
+            label: label.into(),
+            blocked: Some(reason.into()),
+        });
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActionSet {
+    /// Features push the actions they currently allow into [`AvailableActions`].
+    Offer,
+}
+
+#[derive(Component)]
+struct ActionPanel;
+
+/// Marks an action button that can't be pressed.
+#[derive(Component)]
+struct BlockedAction;
+
+pub fn actions_plugin(app: &mut App) {
+    track_screen::<ActionPanel>(app, AppState::Game);
+    app.add_event::<ActionPressed>()
+        .init_resource::<AvailableActions>()
+        .add_systems(OnEnter(AppState::Game), spawn_action_panel)
+        .add_systems(OnExit(AppState::Game), despawn_screen::<ActionPanel>)
+        .configure_sets(
+            Update,
+            ActionSet::Offer
+                .after(clear_actions)
+                .before(rebuild_action_panel)
+                .run_if(in_state(AppState::Game)),
+        )
+        .add_systems(
+            Update,
+            (
+                clear_actions,
+                rebuild_action_panel,
+                (
+                    (press_actions, press_hotkeys).run_if(no_modal_open),
+                    action_button_colors,
+                ),
+            )
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        );
+}
+
+fn spawn_action_panel(mut commands: Commands, locale: Res<Locale>) {
+    commands.spawn((
+        Node {
+            flex_direction: locale.direction().row(),
+            flex_wrap: FlexWrap::Wrap,
+            justify_content: JustifyContent::FlexEnd,
+            row_gap: Val::Px(8.0),
+            column_gap: Val::Px(8.0),
+            ..default()
+        },
+        ActionPanel,
+        HudRoot,
+        HudSlot::BottomRight,
+    ));
+}
+
+fn clear_actions(mut actions: ResMut<AvailableActions>) {
+    actions.0.clear();
+}
+
+fn rebuild_action_panel(
+    mut commands: Commands,
+    mut actions: ResMut<AvailableActions>,
+    mut shown: Local<Vec<OfferedAction>>,
+    mut panel: Query<(Entity, Ref<ActionPanel>)>,
+    assets: Res<AssetServer>,
+) {
+    let Ok((panel, panel_marker)) = panel.single_mut() else {
+        return;
+    };
+    actions.0.sort_by_key(|offered| offered.action);
+    actions.0.dedup_by_key(|offered| offered.action);
+    if *shown == actions.0 && !panel_marker.is_added() {
+        return;
+    }
+    shown.clone_from(&actions.0);
+
+    let font = ui_font(&assets);
+    commands
+        .entity(panel)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            for offered in &actions.0 {
+                let text_font = TextFont {
+                    font: font.clone(),
+                    font_size: ACTION_FONT_SIZE,
+                    ..default()
+                };
+                let mut button = parent.spawn((
+                    Button,
+                    Node {
+                        padding: UiRect::axes(Val::Px(12.0), Val::Px(8.0)),
+                        ..default()
+                    },
+                    BackgroundColor(DARKER),
+                    offered.action,
+                ));
+                button.with_children(|button| {
+                    let mut label = button.spawn((
+                        Text::new(offered.label.clone()),
+                        text_font.clone(),
+                        TextColor(LIGHT),
+                    ));
+                    if let Some(hint) = hotkey_hint(offered.action, &text_font) {
+                        label.with_child(hint);
+                    }
+                });
+
+                if let Some(reason) = &offered.blocked {
+                    button.insert((BlockedAction, BackgroundColor(DARK), Tooltip::new(reason)));
+                }
+            }
+        });
+}
+
+fn press_actions(
+    mut presses: ButtonPresses<&GameAction, Without<BlockedAction>>,
+    mut pressed: EventWriter<ActionPressed>,
+) {
+    for action in presses.read() {
+        pressed.write(ActionPressed(*action));
+    }
+}
+
+// Goes by the same offers as the buttons, after the panel has sorted them.
+fn press_hotkeys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<AvailableActions>,
+    mut pressed: EventWriter<ActionPressed>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if keyboard_input.any_pressed(MODIFIER_KEYS) {
+        return;
+    }
+    for offered in &actions.0 {
+        if !offered
+            .action
+            .hotkey()
+            .is_some_and(|key| keyboard_input.just_pressed(key))
+        {
+            continue;
+        }
+        if let Some(reason) = &offered.blocked {
+            toasts.write(Toast::new(reason.clone()));
+        } else {
+            pressed.write(ActionPressed(offered.action));
+        }
+    }
+}
+
+fn action_button_colors(
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (
+            Changed<Interaction>,
+            With<GameAction>,
+            Without<BlockedAction>,
+        ),
+    >,
+) {
+    for (interaction, mut color) in &mut interaction_query {
+        color.0 = match interaction {
+            Interaction::None => DARKER,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+    use crate::screens::button_press::ButtonPressed;
+    use crate::screens::command_palette::command_palette_plugin;
+    use crate::screens::overlay::{ModalStack, Overlay};
+    use crate::screens::text_field::TextField;
+
+    /// Stands in for a feature's interaction, the only one offered.
+    const FAKE: GameAction = GameAction::Calendar;
+    const FAKE_LABEL: &str = "Fake interaction";
+    const REASON: &str = "Only at home";
+
+    /// Whether the fake interaction is offered blocked.
+    #[derive(Resource)]
+    struct FakeBlocked(bool);
+
+    fn offer_fake(blocked: Res<FakeBlocked>, mut actions: ResMut<AvailableActions>) {
+        if blocked.0 {
+            actions.offer_blocked(FAKE, FAKE_LABEL, REASON);
+        } else {
+            actions.offer_labeled(FAKE, FAKE_LABEL);
+        }
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Surface {
+        Panel,
+        Hotkey,
+        Palette,
+    }
+
+    /// What taking the fake interaction from a surface did.
+    #[derive(Debug, PartialEq)]
+    struct Outcome {
+        listed: bool,
+        pressed: Vec<GameAction>,
+        /// The reason shown for not taking it, in a tooltip, a toast or the palette row.
+        reason: Option<String>,
+    }
+
+    fn app(blocked: bool) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, AssetPlugin::default()))
+            .init_asset::<Font>()
+            .insert_state(AppState::Game)
+            .add_sub_state::<Overlay>()
+            .add_event::<Toast>()
+            .add_event::<ButtonPressed>()
+            .init_resource::<ModalStack>()
+            .init_resource::<Locale>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(FakeBlocked(blocked))
+            .add_plugins((actions_plugin, command_palette_plugin))
+            .add_systems(Update, offer_fake.in_set(ActionSet::Offer));
+        app.update();
+        app.update();
+        app
+    }
+
+    fn press_key(app: &mut App, key: KeyCode) {
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+        app.update();
+        let mut keyboard = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keyboard.release(key);
+        keyboard.clear();
+    }
+
+    fn texts(app: &mut App) -> Vec<String> {
+        let mut texts = app.world_mut().query::<&Text>();
+        texts.iter(app.world()).map(|text| text.0.clone()).collect()
+    }
+
+    fn take(surface: Surface, blocked: bool) -> Outcome {
+        let mut app = app(blocked);
+        let mut buttons = app
+            .world_mut()
+            .query::<(Entity, &GameAction, Option<&Tooltip>)>();
+        let (button, _, tooltip) = buttons
+            .iter(app.world())
+            .find(|(_, action, _)| **action == FAKE)
+            .expect("the panel shows the fake interaction");
+        let mut outcome = Outcome {
+            listed: true,
+            pressed: Vec::new(),
+            reason: None,
+        };
+        match surface {
+            Surface::Panel => {
+                outcome.reason = tooltip.map(|tooltip| match tooltip {
+                    Tooltip::Text(text) => text.clone(),
+                    Tooltip::Titled { body, .. } => body.clone(),
+                });
+                app.world_mut().send_event(ButtonPressed(button));
+                app.update();
+            }
+            Surface::Hotkey => {
+                press_key(&mut app, FAKE.hotkey().unwrap());
+                let mut toasts = app.world_mut().resource_mut::<Events<Toast>>();
+                outcome.reason = toasts.drain().map(|toast| toast.text).next();
+            }
+            Surface::Palette => {
+                app.world_mut()
+                    .resource_mut::<NextState<Overlay>>()
+                    .set(Overlay::CommandPalette);
+                app.update();
+                let mut field = app.world_mut().query::<&mut TextField>();
+                field.single_mut(app.world_mut()).unwrap().value = "fake".to_string();
+                app.update();
+                let texts = texts(&mut app);
+                // The panel's button has the label too.
+                outcome.listed = texts.iter().filter(|text| *text == FAKE_LABEL).count() == 2;
+                outcome.reason = texts.into_iter().find(|text| text == REASON);
+                press_key(&mut app, KeyCode::Enter);
+            }
+        }
+        let mut pressed = app.world_mut().resource_mut::<Events<ActionPressed>>();
+        outcome.pressed = pressed
+            .drain()
+            .map(|ActionPressed(action)| action)
+            .collect();
+        outcome
+    }
+
+    #[test]
+    fn every_surface_takes_an_available_interaction() {
+        for surface in [Surface::Panel, Surface::Hotkey, Surface::Palette] {
+            let expected = Outcome {
+                listed: true,
+                pressed: vec![FAKE],
+                reason: None,
+            };
+            assert_eq!(take(surface, false), expected, "from the {surface:?}");
+        }
+    }
+
+    #[test]
+    fn every_surface_refuses_a_blocked_interaction_with_its_reason() {
+        for surface in [Surface::Panel, Surface::Hotkey, Surface::Palette] {
+            let expected = Outcome {
+                listed: true,
+                pressed: Vec::new(),
+                reason: Some(REASON.to_string()),
+            };
+            assert_eq!(take(surface, true), expected, "from the {surface:?}");
+        }
+    }
+}
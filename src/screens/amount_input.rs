@@ -0,0 +1,488 @@
+//! An amount input: minus and plus buttons around a value kept between a minimum and a
+//! maximum, with optional 25%/50%/Max presets and a confirm button. Holding a button keeps
+//! stepping, in bigger steps the longer it's held. Clicking the value lets the player type
+//! it, and Enter confirms.
+//!
+//! Spawn [`amount_input`], follow edits with [`AmountChanged`] and take the amount from
+//! [`AmountConfirmed`].
+
+use bevy::prelude::*;
+
+use super::button_press::ButtonPresses;
+use crate::fonts::ui_font;
+use crate::format;
+use crate::palette::{BLUE, DARK, LIGHT, YELLOW};
+use crate::power::AnimationsActive;
+use crate::settings::Locale;
+use crate::timers::RealUiTime;
+
+const AMOUNT_FONT_SIZE: f32 = 16.0;
+/// How long a button is held before it starts repeating, in seconds.
+const REPEAT_DELAY: f32 = 0.4;
+/// Seconds between steps while repeating.
+const REPEAT_INTERVAL: f32 = 0.08;
+/// Every this many seconds of holding, steps get ten times bigger.
+const ACCELERATE_EVERY: f32 = 1.5;
+/// Steps never get bigger than this many times the base step.
+const MAX_ACCELERATION: u32 = 100;
+const BUTTON_SIZE: f32 = 32.0;
+const READOUT_WIDTH: f32 = 110.0;
+const DIGIT_KEYS: [(KeyCode, KeyCode); 10] = [
+    (KeyCode::Digit0, KeyCode::Numpad0),
+    (KeyCode::Digit1, KeyCode::Numpad1),
+    (KeyCode::Digit2, KeyCode::Numpad2),
+    (KeyCode::Digit3, KeyCode::Numpad3),
+    (KeyCode::Digit4, KeyCode::Numpad4),
+    (KeyCode::Digit5, KeyCode::Numpad5),
+    (KeyCode::Digit6, KeyCode::Numpad6),
+    (KeyCode::Digit7, KeyCode::Numpad7),
+    (KeyCode::Digit8, KeyCode::Numpad8),
+    (KeyCode::Digit9, KeyCode::Numpad9),
+];
+
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct AmountInput {
+    value: u32,
+    min: u32,
+    max: u32,
+    step: u32,
+    money: bool,
+    presets: bool,
+    confirm: Option<&'static str>,
+    /// Typed digits go into the value.
+    focused: bool,
+}
+
+impl AmountInput {
+    /// An input from 0 to `max`, starting at 0 and stepping by 1.
+    pub fn new(max: u32) -> Self {
+        AmountInput {
+            value: 0,
+            min: 0,
+            max,
+            step: 1,
+            money: false,
+            presets: false,
+            confirm: None,
+            focused: false,
+        }
+    }
+
+    pub fn starting_at(mut self, value: u32) -> Self {
+        self.value = value.clamp(self.min, self.max);
+        self
+    }
+
+    pub fn with_step(mut self, step: u32) -> Self {
+        self.step = step.max(1);
+        self
+    }
+
+    /// Shows the value as dollars.
+    pub fn money(mut self) -> Self {
+        self.money = true;
+        self
+    }
+
+    /// Adds 25%, 50% and Max buttons, relative to the maximum.
+    pub fn with_presets(mut self) -> Self {
+        self.presets = true;
+        self
+    }
+
+    /// Adds a button that sends [`AmountConfirmed`].
+    pub fn with_confirm(mut self, label: &'static str) -> Self {
+        self.confirm = Some(label);
+        self
+    }
+
+    /// Moves the maximum, pulling the value back inside the range if needed.
+    pub fn set_max(&mut self, max: u32) {
+        self.max = max.max(self.min);
+        self.value = self.value.clamp(self.min, self.max);
+    }
+
+    fn set_value(&mut self, value: u32) {
+        self.value = value.clamp(self.min, self.max);
+    }
+}
+
+/// The player changed an [`AmountInput`]'s value.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountChanged {
+    pub input: Entity,
+    pub value: u32,
+}
+
+/// The player confirmed an [`AmountInput`]'s value.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountConfirmed {
+    pub input: Entity,
+    pub value: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Preset {
+    Quarter,
+    Half,
+    Max,
+}
+
+impl Preset {
+    const ALL: [Preset; 3] = [Preset::Quarter, Preset::Half, Preset::Max];
+
+    fn label(self) -> &'static str {
+        match self {
+            Preset::Quarter => "25%",
+            Preset::Half => "50%",
+            Preset::Max => "Max",
+        }
+    }
+
+    fn amount(self, max: u32) -> u32 {
+        match self {
+            Preset::Quarter => max / 4,
+            Preset::Half => max / 2,
+            Preset::Max => max,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    Minus,
+    Plus,
+    /// Shows the value, and focuses the input when clicked.
+    Readout,
+    Preset(Preset),
+    Confirm,
+}
+
+/// A button of the [`AmountInput`] on `input`.
+#[derive(Component, Debug, Clone, Copy)]
+struct AmountPart {
+    input: Entity,
+    part: Part,
+}
+
+pub fn amount_input_plugin(app: &mut App) {
+    app.add_event::<AmountChanged>()
+        .add_event::<AmountConfirmed>()
+        .add_systems(
+            Update,
+            (
+                build_amount_inputs,
+                (press_amount_parts, repeat_steps, type_amounts),
+                show_amounts,
+                amount_part_colors,
+            )
+                .chain(),
+        );
+}
+
+pub fn amount_input(input: AmountInput) -> impl Bundle {
+    (
+        Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+        input,
+    )
+}
+
+/// The step after holding a button for `held` seconds.
+fn repeat_step(step: u32, held: f32) -> u32 {
+    let acceleration = 10u32.saturating_pow((held / ACCELERATE_EVERY) as u32);
+    step.saturating_mul(acceleration.min(MAX_ACCELERATION))
+}
+
+fn build_amount_inputs(
+    mut commands: Commands,
+    inputs: Query<(Entity, &AmountInput), Added<AmountInput>>,
+    assets: Res<AssetServer>,
+) {
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: AMOUNT_FONT_SIZE,
+        ..default()
+    };
+    for (entity, input) in &inputs {
+        let button = |part: Part, width: Val, label: &str| {
+            (
+                Button,
+                Node {
+                    min_width: width,
+                    height: Val::Px(BUTTON_SIZE),
+                    padding: UiRect::horizontal(Val::Px(8.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                BackgroundColor(DARK),
+                AmountPart {
+                    input: entity,
+                    part,
+                },
+                children![(Text::new(label), font.clone(), TextColor(LIGHT))],
+            )
+        };
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn(Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(8.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn(button(Part::Minus, Val::Px(BUTTON_SIZE), "-"));
+                    row.spawn(button(Part::Readout, Val::Px(READOUT_WIDTH), ""));
+                    row.spawn(button(Part::Plus, Val::Px(BUTTON_SIZE), "+"));
+                    if let Some(label) = input.confirm {
+                        row.spawn(button(Part::Confirm, Val::Auto, label));
+                    }
+                });
+            if input.presets {
+                parent
+                    .spawn(Node {
+                        column_gap: Val::Px(8.0),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        for preset in Preset::ALL {
+                            row.spawn(button(Part::Preset(preset), Val::Auto, preset.label()));
+                        }
+                    });
+            }
+        });
+    }
+}
+
+fn press_amount_parts(
+    mut presses: ButtonPresses<&AmountPart>,
+    mut inputs: Query<(Entity, &mut AmountInput)>,
+    mut changed: EventWriter<AmountChanged>,
+    mut confirmed: EventWriter<AmountConfirmed>,
+) {
+    for part in presses.read() {
+        // Clicking anywhere on an input takes focus from the others.
+        for (entity, mut input) in &mut inputs {
+            let focused = entity == part.input && part.part == Part::Readout;
+            if input.focused != focused {
+                input.focused = focused;
+            }
+        }
+        let Ok((entity, mut input)) = inputs.get_mut(part.input) else {
+            continue;
+        };
+        let before = input.value;
+        match part.part {
+            Part::Minus => {
+                let value = before.saturating_sub(input.step);
+                input.set_value(value);
+            }
+            Part::Plus => {
+                let value = before.saturating_add(input.step);
+                input.set_value(value);
+            }
+            Part::Preset(preset) => {
+                let value = preset.amount(input.max);
+                input.set_value(value);
+            }
+            Part::Readout => {}
+            Part::Confirm => {
+                confirmed.write(AmountConfirmed {
+                    input: entity,
+                    value: input.value,
+                });
+            }
+        }
+        if input.value != before {
+            changed.write(AmountChanged {
+                input: entity,
+                value: input.value,
+            });
+        }
+    }
+}
+
+// After the first step on press, keeps stepping while the same button stays held.
+fn repeat_steps(
+    parts: Query<(Entity, &Interaction, &AmountPart)>,
+    mut inputs: Query<&mut AmountInput>,
+    mut changed: EventWriter<AmountChanged>,
+    time: RealUiTime,
+    mut animations: ResMut<AnimationsActive>,
+    mut held: Local<Option<(Entity, f32, f32)>>,
+) {
+    let Some((entity, part)) = parts
+        .iter()
+        .find(|(_, interaction, part)| {
+            **interaction == Interaction::Pressed && matches!(part.part, Part::Minus | Part::Plus)
+        })
+        .map(|(entity, _, part)| (entity, *part))
+    else {
+        *held = None;
+        return;
+    };
+    let Ok(mut input) = inputs.get_mut(part.input) else {
+        return;
+    };
+    // Holding still sends no input to wake the app for the next step.
+    animations.keep_awake();
+    let (held_entity, held_for, until_step) =
+        held.get_or_insert((entity, -REPEAT_DELAY, REPEAT_DELAY));
+    if *held_entity != entity {
+        *held = Some((entity, -REPEAT_DELAY, REPEAT_DELAY));
+        return;
+    }
+    *held_for += time.delta_secs();
+    *until_step -= time.delta_secs();
+    let before = input.value;
+    while *until_step <= 0.0 {
+        *until_step += REPEAT_INTERVAL;
+        let step = repeat_step(input.step, held_for.max(0.0));
+        let value = if part.part == Part::Plus {
+            input.value.saturating_add(step)
+        } else {
+            input.value.saturating_sub(step)
+        };
+        input.set_value(value);
+    }
+    if input.value != before {
+        changed.write(AmountChanged {
+            input: part.input,
+            value: input.value,
+        });
+    }
+}
+
+fn type_amounts(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut inputs: Query<(Entity, &mut AmountInput)>,
+    mut changed: EventWriter<AmountChanged>,
+    mut confirmed: EventWriter<AmountConfirmed>,
+) {
+    let Some((entity, mut input)) = inputs.iter_mut().find(|(_, input)| input.focused) else {
+        return;
+    };
+    let before = input.value;
+    for (digit, (key, numpad)) in DIGIT_KEYS.iter().enumerate() {
+        if keyboard_input.any_just_pressed([*key, *numpad]) {
+            let value = input.value.saturating_mul(10).saturating_add(digit as u32);
+            input.set_value(value);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Backspace) {
+        let value = input.value / 10;
+        input.set_value(value);
+    }
+    if input.value != before {
+        changed.write(AmountChanged {
+            input: entity,
+            value: input.value,
+        });
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) {
+        confirmed.write(AmountConfirmed {
+            input: entity,
+            value: input.value,
+        });
+    }
+}
+
+fn show_amounts(
+    inputs: Query<&AmountInput>,
+    parts: Query<(&AmountPart, &Children)>,
+    changed: Query<(), Changed<AmountInput>>,
+    mut texts: Query<(&mut Text, &mut TextColor)>,
+    locale: Res<Locale>,
+) {
+    for (part, children) in &parts {
+        if part.part != Part::Readout || !changed.contains(part.input) {
+            continue;
+        }
+        let Ok(input) = inputs.get(part.input) else {
+            continue;
+        };
+        let Some(Ok((mut text, mut color))) = children.first().map(|child| texts.get_mut(*child))
+        else {
+            continue;
+        };
+        let value = if input.money {
+            format::money(input.value, *locale)
+        } else {
+            format::format_int(i64::from(input.value), *locale)
+        };
+        text.0 = if input.focused {
+            format!("{value}_")
+        } else {
+            value
+        };
+        color.0 = if input.focused { YELLOW } else { LIGHT };
+    }
+}
+
+fn amount_part_colors(
+    mut parts: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<AmountPart>),
+    >,
+) {
+    for (interaction, mut background) in &mut parts {
+        background.0 = match interaction {
+            Interaction::None => DARK,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_stay_inside_the_range() {
+        let mut input = AmountInput::new(100).starting_at(250);
+        assert_eq!(input.value, 100);
+        input.set_value(0);
+        assert_eq!(input.value, 0);
+        input.set_value(u32::MAX);
+        assert_eq!(input.value, 100);
+    }
+
+    #[test]
+    fn lowering_the_maximum_pulls_the_value_down() {
+        let mut input = AmountInput::new(100).starting_at(80);
+        input.set_max(50);
+        assert_eq!(input.value, 50);
+        input.set_max(200);
+        assert_eq!(input.value, 50);
+    }
+
+    #[test]
+    fn a_zero_step_still_steps() {
+        assert_eq!(AmountInput::new(10).with_step(0).step, 1);
+    }
+
+    #[test]
+    fn steps_grow_tenfold_every_while_held_up_to_the_cap() {
+        assert_eq!(repeat_step(5, 0.0), 5);
+        assert_eq!(repeat_step(5, ACCELERATE_EVERY - 0.01), 5);
+        assert_eq!(repeat_step(5, ACCELERATE_EVERY), 50);
+        assert_eq!(repeat_step(5, ACCELERATE_EVERY * 2.0), 500);
+        assert_eq!(
+            repeat_step(5, ACCELERATE_EVERY * 10.0),
+            5 * MAX_ACCELERATION
+        );
+        assert_eq!(repeat_step(u32::MAX, ACCELERATE_EVERY), u32::MAX);
+    }
+
+    #[test]
+    fn presets_are_shares_of_the_maximum() {
+        assert_eq!(Preset::Quarter.amount(1000), 250);
+        assert_eq!(Preset::Half.amount(1001), 500);
+        assert_eq!(Preset::Max.amount(1001), 1001);
+        assert_eq!(Preset::Max.amount(0), 0);
+    }
+}
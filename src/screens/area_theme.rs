@@ -0,0 +1,285 @@
+//! Leans the HUD chrome toward the current area's accent color from the area data, so the
+//! DeadbeatArea and the Mansion feel different. Chrome, like the HUD bars, panels and bar
+//! fills, opts in with [`AccentTint`], borders with [`AccentBorder`], and text over it with
+//! [`AccentText`], which keeps the text readable against the tinted chrome.
+//!
+//! Moving to another area blends to its accent over [`ACCENT_BLEND_SECONDS`], or at once
+//! with reduced motion.
+
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::palette::{DARKER, LIGHT};
+use crate::settings::ReducedMotion;
+use crate::sim::catalog::AreaCatalog;
+use crate::sim::economy::CurrentArea;
+use crate::timers::RealUiTime;
+
+pub const ACCENT_BLEND_SECONDS: f32 = 0.5;
+/// The lowest contrast ratio HUD text may have against the chrome behind it, WCAG's for
+/// normal text.
+pub const MIN_TEXT_CONTRAST: f32 = 4.5;
+/// How far chrome behind text leans toward the accent.
+pub const CHROME_STRENGTH: f32 = 0.3;
+/// Bar fills lean less, so their own colors still read.
+pub const FILL_STRENGTH: f32 = 0.2;
+
+/// The accent the HUD is tinted with. The alpha is how much of it shows, 0 for an area
+/// without one.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct HudAccent {
+    from: LinearRgba,
+    to: LinearRgba,
+    /// How far along the blend from `from` to `to` is, from 0 to 1.
+    blend: f32,
+}
+
+impl Default for HudAccent {
+    fn default() -> Self {
+        HudAccent {
+            from: LinearRgba::NONE,
+            to: LinearRgba::NONE,
+            blend: 1.0,
+        }
+    }
+}
+
+impl HudAccent {
+    pub fn current(&self) -> LinearRgba {
+        self.from.mix(&self.to, self.blend)
+    }
+
+    fn retarget(&mut self, accent: Option<Color>, instant: bool) {
+        let current = self.current();
+        // Fading in or out keeps the hue, so no other color shows on the way.
+        self.to = accent.map_or(current.with_alpha(0.0), |accent| {
+            accent.to_linear().with_alpha(1.0)
+        });
+        self.from = if current.alpha > 0.0 {
+            current
+        } else {
+            self.to.with_alpha(0.0)
+        };
+        self.blend = if instant { 1.0 } else { 0.0 };
+    }
+}
+
+/// Tints the entity's [`BackgroundColor`] toward the accent. `base` is the color it has
+/// without one; change it rather than the background.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[require(BackgroundColor)]
+pub struct AccentTint {
+    pub base: Color,
+    /// From 0 to 1, how far it leans toward the accent.
+    pub strength: f32,
+}
+
+impl AccentTint {
+    /// Chrome with text over it, like a bar or a panel.
+    pub fn chrome(base: Color) -> Self {
+        AccentTint {
+            base,
+            strength: CHROME_STRENGTH,
+        }
+    }
+
+    pub fn fill(base: Color) -> Self {
+        AccentTint {
+            base,
+            strength: FILL_STRENGTH,
+        }
+    }
+}
+
+/// Tints the entity's [`BorderColor`] toward the accent as much as chrome, from the color
+/// given.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[require(BorderColor)]
+pub struct AccentBorder(pub Color);
+
+/// Text over tinted chrome: the [`TextColor`] is the one given while it stays readable,
+/// see [`readable_on`].
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[require(TextColor)]
+pub struct AccentText(pub Color);
+
+/// `base` leaned toward `accent` by `strength`, keeping the alpha of `base`.
+pub fn tint(base: Color, accent: LinearRgba, strength: f32) -> Color {
+    let base = base.to_linear();
+    let target = accent.with_alpha(base.alpha);
+    base.mix(&target, strength * accent.alpha).into()
+}
+
+/// Relative luminance as WCAG defines it, from 0 for black to 1 for white.
+pub fn relative_luminance(color: Color) -> f32 {
+    let color = color.to_linear();
+    0.2126 * color.red + 0.7152 * color.green + 0.0722 * color.blue
+}
+
+/// WCAG contrast ratio between two colors, from 1 for the same luminance to 21 for black on
+/// white. Alpha is ignored.
+pub fn contrast_ratio(a: Color, b: Color) -> f32 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// `preferred` if it has [`MIN_TEXT_CONTRAST`] against `background`, or else whichever of
+/// [`LIGHT`] and [`DARKER`] stands out more. On a mid-tone background where neither does
+/// it's white or black, one of which always has the contrast.
+pub fn readable_on(background: Color, preferred: Color) -> Color {
+    let stands_out = |text| contrast_ratio(text, background) >= MIN_TEXT_CONTRAST;
+    let starker = |light, dark| {
+        if contrast_ratio(light, background) >= contrast_ratio(dark, background) {
+            light
+        } else {
+            dark
+        }
+    };
+    if stands_out(preferred) {
+        return preferred;
+    }
+    let palette = starker(LIGHT, DARKER);
+    if stands_out(palette) {
+        palette
+    } else {
+        starker(Color::WHITE, Color::BLACK)
+    }
+}
+
+pub fn area_theme_plugin(app: &mut App) {
+    app.init_resource::<HudAccent>().add_systems(
+        Update,
+        (
+            follow_area.run_if(
+                in_state(AppState::Game).and(
+                    resource_exists_and_changed::<CurrentArea>
+                        .or(resource_exists_and_changed::<AreaCatalog>),
+                ),
+            ),
+            blend_accent,
+            (tint_backgrounds, keep_text_readable),
+        )
+            .chain(),
+    );
+}
+
+fn follow_area(
+    mut accent: ResMut<HudAccent>,
+    area: Res<CurrentArea>,
+    catalog: Res<AreaCatalog>,
+    reduced_motion: Res<ReducedMotion>,
+) {
+    accent.retarget(catalog.accent(area.0), reduced_motion.0);
+}
+
+fn blend_accent(mut accent: ResMut<HudAccent>, time: RealUiTime) {
+    if accent.blend < 1.0 {
+        accent.blend = (accent.blend + time.delta_secs() / ACCENT_BLEND_SECONDS).min(1.0);
+    }
+}
+
+fn tint_backgrounds(
+    accent: Res<HudAccent>,
+    mut chrome: Query<(Ref<AccentTint>, &mut BackgroundColor)>,
+    mut borders: Query<(Ref<AccentBorder>, &mut BorderColor)>,
+) {
+    let current = accent.current();
+    for (tinted, mut background) in &mut chrome {
+        if accent.is_changed() || tinted.is_changed() {
+            background.0 = tint(tinted.base, current, tinted.strength);
+        }
+    }
+    for (tinted, mut border) in &mut borders {
+        if accent.is_changed() || tinted.is_changed() {
+            border.0 = tint(tinted.0, current, CHROME_STRENGTH);
+        }
+    }
+}
+
+// Checked against the chrome as if opaque, since the art showing through it varies.
+fn keep_text_readable(accent: Res<HudAccent>, mut texts: Query<(Ref<AccentText>, &mut TextColor)>) {
+    let background = tint(DARKER, accent.current(), CHROME_STRENGTH);
+    for (text, mut color) in &mut texts {
+        if accent.is_changed() || text.is_changed() {
+            color.0 = readable_on(background, text.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::YELLOW;
+    use crate::sim::catalog::{AREA_CATALOG_PATH, AreaCatalogData};
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn contrast_runs_from_one_to_twenty_one() {
+        assert!(close(contrast_ratio(Color::BLACK, Color::WHITE), 21.0));
+        assert!(close(contrast_ratio(Color::WHITE, Color::BLACK), 21.0));
+        assert!(close(contrast_ratio(DARKER, DARKER), 1.0));
+        // #777777 on white, just short of WCAG's 4.5.
+        let gray = Color::srgb_u8(0x77, 0x77, 0x77);
+        assert!(close(contrast_ratio(gray, Color::WHITE), 4.48));
+        assert!(close(relative_luminance(Color::WHITE.with_alpha(0.2)), 1.0));
+    }
+
+    #[test]
+    fn text_keeps_its_color_only_while_it_reads() {
+        assert_eq!(readable_on(Color::BLACK, YELLOW), YELLOW);
+        assert_eq!(readable_on(Color::WHITE, YELLOW), DARKER);
+        assert_eq!(readable_on(Color::WHITE, LIGHT), DARKER);
+        assert_eq!(readable_on(Color::BLACK, DARKER), LIGHT);
+        // Too light for DARKER and too dark for LIGHT.
+        let mid = Color::srgb_u8(0x77, 0x77, 0x77);
+        assert_eq!(readable_on(mid, LIGHT), Color::BLACK);
+    }
+
+    #[test]
+    fn chrome_borders_lean_toward_the_accent_with_the_chrome() {
+        let yellow = YELLOW.to_linear();
+        let mut app = App::new();
+        app.insert_resource(HudAccent {
+            from: yellow,
+            to: yellow,
+            blend: 1.0,
+        })
+        .add_systems(Update, tint_backgrounds);
+        let panel = app
+            .world_mut()
+            .spawn((AccentTint::chrome(DARKER), AccentBorder(DARKER)))
+            .id();
+        app.update();
+
+        let tinted = tint(DARKER, yellow, CHROME_STRENGTH);
+        assert_ne!(tinted, DARKER);
+        assert_eq!(app.world().get::<BackgroundColor>(panel).unwrap().0, tinted);
+        assert_eq!(app.world().get::<BorderColor>(panel).unwrap().0, tinted);
+    }
+
+    #[test]
+    fn hud_text_reads_on_every_areas_chrome() {
+        let text = std::fs::read_to_string(format!("assets/{AREA_CATALOG_PATH}")).unwrap();
+        let catalog = AreaCatalog::from_data(&ron::from_str::<AreaCatalogData>(&text).unwrap());
+        let accents: Vec<_> = catalog
+            .areas()
+            .into_iter()
+            .filter_map(|area| catalog.accent(area))
+            .collect();
+        assert!(!accents.is_empty());
+        for accent in accents {
+            let chrome = tint(DARKER, accent.to_linear(), CHROME_STRENGTH);
+            for preferred in [LIGHT, YELLOW] {
+                let shown = readable_on(chrome, preferred);
+                let contrast = contrast_ratio(shown, chrome);
+                assert!(
+                    contrast >= MIN_TEXT_CONTRAST,
+                    "{shown:?} on {chrome:?} is only {contrast}"
+                );
+            }
+        }
+    }
+}
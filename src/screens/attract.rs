@@ -0,0 +1,202 @@
+//! The attract sequence: when nobody touches the controls for a while on the menu or an end
+//! screen, the area artwork cycles slowly under the title, like a game on a shop shelf.
+//! From an end screen it fades back to the main menu first. Any input ends it.
+//!
+//! [`AttractMode`] turns it off or sets how long to wait. It never starts during a run.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, ui::FocusPolicy};
+
+use super::nav::{ScreenNav, TransitionCause};
+use crate::fonts::ui_font;
+use crate::input_device::{ActiveInputDevice, IdleTime};
+use crate::palette::LIGHT;
+use crate::power::AnimationsActive;
+use crate::settings::{AttractMode, ReducedMotion};
+use crate::sim::catalog::AreaCatalog;
+use crate::timers::{RealUiTime, UiTimer};
+use crate::{AppState, despawn_screen, track_screen};
+
+/// Above the menus and the settings screens, below the quit prompt.
+const ATTRACT_Z_INDEX: i32 = 150;
+const FADE_IN: Duration = Duration::from_secs(2);
+/// How long each area stays up, fading in and out at the ends.
+const SLIDE_DURATION: Duration = Duration::from_secs(8);
+const SLIDE_FADE_SECONDS: f32 = 1.0;
+const TITLE_WIDTH_PERCENT: f32 = 40.0;
+
+#[derive(Component)]
+struct AttractScreen {
+    fade_in: UiTimer,
+    /// Whether it started over an end screen, to be swapped for the menu once covered.
+    over_end_screen: bool,
+}
+
+#[derive(Component)]
+struct AttractSlides {
+    images: Vec<Handle<Image>>,
+    index: usize,
+    timer: UiTimer,
+}
+
+/// The title art and prompt over the slides.
+#[derive(Component)]
+struct AttractTitle;
+
+pub fn attract_plugin(app: &mut App) {
+    track_screen::<AttractScreen>(app, AppState::Menu);
+    app.add_systems(OnEnter(AppState::Game), despawn_screen::<AttractScreen>)
+        .add_systems(
+            Update,
+            (
+                start_attract.run_if(
+                    in_state(AppState::Menu)
+                        .or(in_state(AppState::GameOver))
+                        .or(in_state(AppState::Win)),
+                ),
+                (end_attract, animate_attract).run_if(any_with_component::<AttractScreen>),
+            )
+                .chain(),
+        );
+}
+
+fn start_attract(
+    mut commands: Commands,
+    idle: Res<IdleTime>,
+    mode: Res<AttractMode>,
+    screens: Query<(), With<AttractScreen>>,
+    app_state: Res<State<AppState>>,
+    catalog: Res<AreaCatalog>,
+    device: Res<ActiveInputDevice>,
+    assets: Res<AssetServer>,
+) {
+    if !mode.enabled || idle.0 < mode.timeout() || !screens.is_empty() {
+        return;
+    }
+    info!("Idle for {:?}, starting the attract sequence", idle.0);
+    let images = catalog
+        .images()
+        .into_iter()
+        .map(|path| assets.load(path))
+        .collect::<Vec<_>>();
+    let first = images.first().cloned().unwrap_or_default();
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(24.0),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.0)),
+        FocusPolicy::Block,
+        GlobalZIndex(ATTRACT_Z_INDEX),
+        AttractScreen {
+            fade_in: UiTimer::new(FADE_IN, TimerMode::Once),
+            over_end_screen: *app_state.get() != AppState::Menu,
+        },
+        children![
+            (
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                ImageNode::new(first).with_color(Color::WHITE.with_alpha(0.0)),
+                AttractSlides {
+                    images,
+                    index: 0,
+                    timer: UiTimer::new(SLIDE_DURATION, TimerMode::Repeating),
+                },
+            ),
+            (
+                Node {
+                    width: Val::Percent(TITLE_WIDTH_PERCENT),
+                    ..default()
+                },
+                ImageNode::new(assets.load("images/title.png"))
+                    .with_color(Color::WHITE.with_alpha(0.0)),
+                AttractTitle,
+            ),
+            (
+                Text::new(device.0.prompt("Press any key", "Press any button")),
+                TextFont {
+                    font: ui_font(&assets),
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextShadow::default(),
+                TextColor(LIGHT.with_alpha(0.0)),
+                AttractTitle,
+            ),
+        ],
+    ));
+}
+
+// Any input, or turning the sequence off, ends it at once.
+fn end_attract(
+    mut commands: Commands,
+    idle: Res<IdleTime>,
+    mode: Res<AttractMode>,
+    screens: Query<Entity, With<AttractScreen>>,
+) {
+    if mode.enabled && idle.0 >= mode.timeout() {
+        return;
+    }
+    for screen in &screens {
+        commands.entity(screen).despawn();
+    }
+}
+
+fn animate_attract(
+    time: RealUiTime,
+    mut animations: ResMut<AnimationsActive>,
+    reduced_motion: Res<ReducedMotion>,
+    mut nav: ScreenNav,
+    mut screen: Query<(&mut AttractScreen, &mut BackgroundColor)>,
+    mut slides: Query<(&mut AttractSlides, &mut ImageNode), Without<AttractTitle>>,
+    mut title_images: Query<&mut ImageNode, With<AttractTitle>>,
+    mut title_texts: Query<&mut TextColor, With<AttractTitle>>,
+) {
+    let Ok((mut attract, mut background)) = screen.single_mut() else {
+        return;
+    };
+    let Ok((mut slides, mut image)) = slides.single_mut() else {
+        return;
+    };
+    animations.keep_awake();
+    let fade = if reduced_motion.0 {
+        1.0
+    } else {
+        attract.fade_in.tick(&time).fraction()
+    };
+    background.0 = Color::BLACK.with_alpha(fade);
+    // Once the end screen is covered, the menu takes its place underneath.
+    if fade >= 1.0 && attract.over_end_screen {
+        attract.over_end_screen = false;
+        nav.set_app(AppState::Menu, TransitionCause::Idle);
+    }
+    if slides.timer.tick(&time).just_finished() && !slides.images.is_empty() {
+        slides.index = (slides.index + 1) % slides.images.len();
+        image.image = slides.images[slides.index].clone();
+    }
+    let elapsed = slides.timer.elapsed_secs();
+    let remaining = slides.timer.remaining_secs();
+    let slide_alpha = if reduced_motion.0 {
+        1.0
+    } else {
+        (elapsed.min(remaining) / SLIDE_FADE_SECONDS).min(1.0)
+    };
+    image.color = Color::WHITE.with_alpha(slide_alpha * fade);
+    for mut title in &mut title_images {
+        title.color = Color::WHITE.with_alpha(fade);
+    }
+    for mut text in &mut title_texts {
+        text.0 = LIGHT.with_alpha(fade);
+    }
+}
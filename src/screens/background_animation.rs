@@ -0,0 +1,122 @@
+//! Looping animations for the area backgrounds, from a list of frames or a sprite sheet in
+//! the area data. Areas without one show their static image.
+//!
+//! The frames belong to the [`BackgroundAnimation`] on the background, so replacing it when
+//! the area changes drops the old area's frames and starts the new one from its first frame.
+//! Animations hold still while the simulation is paused and with reduced motion.
+
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::settings::ReducedMotion;
+use crate::sim::{catalog::AreaAnimation, sim_running};
+use crate::timers::{RealUiTime, UiTimer};
+
+/// Plays an area's animation on the [`ImageNode`] of the same entity.
+#[derive(Component, Debug)]
+pub struct BackgroundAnimation {
+    frames: AnimationFrames,
+    timer: UiTimer,
+    frame: usize,
+}
+
+#[derive(Debug)]
+enum AnimationFrames {
+    /// Handles to every frame, kept so they stay loaded.
+    Images(Vec<Handle<Image>>),
+    /// The number of frames in the sheet the image node shows.
+    Atlas(usize),
+}
+
+impl AnimationFrames {
+    fn len(&self) -> usize {
+        match self {
+            AnimationFrames::Images(frames) => frames.len(),
+            AnimationFrames::Atlas(count) => *count,
+        }
+    }
+}
+
+impl BackgroundAnimation {
+    /// Loads `animation`, returning the image node showing its first frame and the
+    /// animation to play on it. `None` if it has no frames or doesn't move.
+    pub fn load(
+        animation: &AreaAnimation,
+        assets: &AssetServer,
+        layouts: &mut Assets<TextureAtlasLayout>,
+    ) -> Option<(ImageNode, Self)> {
+        let (image, frames, fps) = match animation {
+            AreaAnimation::Frames { paths, fps } => {
+                let frames: Vec<Handle<Image>> =
+                    paths.iter().map(|path| assets.load(path)).collect();
+                let first = ImageNode::new(frames.first()?.clone());
+                (first, AnimationFrames::Images(frames), *fps)
+            }
+            AreaAnimation::Atlas {
+                path,
+                tile_size,
+                columns,
+                rows,
+                fps,
+            } => {
+                let layout = TextureAtlasLayout::from_grid(*tile_size, *columns, *rows, None, None);
+                let first = ImageNode::from_atlas_image(
+                    assets.load(path),
+                    TextureAtlas {
+                        layout: layouts.add(layout),
+                        index: 0,
+                    },
+                );
+                let count = (columns * rows) as usize;
+                (first, AnimationFrames::Atlas(count), *fps)
+            }
+        };
+        if frames.len() == 0 || fps <= 0.0 {
+            warn!("Ignoring a background animation without frames or speed: {animation:?}");
+            return None;
+        }
+        let timer = UiTimer::from_seconds(1.0 / fps, TimerMode::Repeating);
+        Some((
+            image,
+            BackgroundAnimation {
+                frames,
+                timer,
+                frame: 0,
+            },
+        ))
+    }
+}
+
+pub fn background_animation_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        animate_backgrounds.run_if(
+            in_state(AppState::Game)
+                .and(sim_running)
+                .and(resource_equals(ReducedMotion(false))),
+        ),
+    );
+}
+
+fn animate_backgrounds(
+    time: RealUiTime,
+    mut backgrounds: Query<(&mut BackgroundAnimation, &mut ImageNode)>,
+) {
+    for (mut animation, mut image) in &mut backgrounds {
+        animation.timer.tick(&time);
+        let steps = animation.timer.times_finished_this_tick() as usize;
+        if steps == 0 {
+            continue;
+        }
+        animation.frame = (animation.frame + steps) % animation.frames.len();
+        let frame = animation.frame;
+        match &animation.frames {
+            AnimationFrames::Images(frames) => image.image = frames[frame].clone(),
+            AnimationFrames::Atlas(_) => {
+                if let Some(atlas) = &mut image.texture_atlas {
+                    atlas.index = frame;
+                }
+            }
+        }
+    }
+}
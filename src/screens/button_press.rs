@@ -0,0 +1,220 @@
+//! Button presses, captured once at the start of the frame right after the UI works out
+//! what's under the pointer, and handed out as [`ButtonPressed`] events. Systems that act
+//! on a press read them through [`ButtonPresses`] rather than watching for a changed
+//! [`Interaction`], so a press doesn't depend on when in a stuttering frame they run.
+//!
+//! A press on a button that was despawned or stopped taking input before it got handled,
+//! like one on a screen that closed the same frame and is sliding away, is dropped with a
+//! debug log instead of reaching anything else.
+
+use bevy::{
+    ecs::{
+        entity::Entities,
+        query::{QueryData, QueryFilter, ROQueryItem},
+        system::SystemParam,
+    },
+    prelude::*,
+    ui::UiSystem,
+};
+
+/// Sent when a button is pressed.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonPressed(pub Entity);
+
+/// The presses on buttons matching `D` and `F` since the system last ran, each once.
+#[derive(SystemParam)]
+pub struct ButtonPresses<'w, 's, D: QueryData + 'static, F: QueryFilter + 'static = ()> {
+    presses: EventReader<'w, 's, ButtonPressed>,
+    buttons: Query<'w, 's, D, (F, With<Button>, With<Interaction>)>,
+    inert: Query<'w, 's, (), (With<Button>, Without<Interaction>)>,
+    entities: &'w Entities,
+}
+
+impl<D: QueryData, F: QueryFilter> ButtonPresses<'_, '_, D, F> {
+    pub fn read(&mut self) -> impl Iterator<Item = ROQueryItem<'_, D>> {
+        let (buttons, inert, entities) = (&self.buttons, &self.inert, self.entities);
+        self.presses
+            .read()
+            .filter_map(move |ButtonPressed(entity)| match buttons.get(*entity) {
+                Ok(button) => Some(button),
+                Err(_) if !entities.contains(*entity) => {
+                    debug!("Dropped a press on despawned button {entity}");
+                    None
+                }
+                Err(_) if inert.contains(*entity) => {
+                    debug!("Dropped a press on button {entity}, which stopped taking input");
+                    None
+                }
+                Err(_) => None,
+            })
+    }
+}
+
+pub fn button_press_plugin(app: &mut App) {
+    app.add_event::<ButtonPressed>()
+        .add_systems(PreUpdate, capture_presses.after(UiSystem::Focus));
+}
+
+pub fn capture_presses(
+    buttons: Query<(Entity, &Interaction), (Changed<Interaction>, With<Button>)>,
+    mut pressed: EventWriter<ButtonPressed>,
+) {
+    for (entity, interaction) in &buttons {
+        if *interaction == Interaction::Pressed {
+            pressed.write(ButtonPressed(entity));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component)]
+    struct Screen;
+
+    #[derive(Component)]
+    struct Left(u32);
+
+    #[derive(Component)]
+    struct Right(u32);
+
+    /// Closes the screen this frame, after its presses were captured but before they're
+    /// handled.
+    #[derive(Resource, Default)]
+    struct CloseNow(bool);
+
+    #[derive(Resource, Default)]
+    struct Handled(Vec<(&'static str, u32)>);
+
+    fn close_screen(
+        mut commands: Commands,
+        mut close: ResMut<CloseNow>,
+        screens: Query<Entity, With<Screen>>,
+    ) {
+        if std::mem::take(&mut close.0) {
+            for screen in &screens {
+                commands.entity(screen).despawn();
+            }
+        }
+    }
+
+    fn handle_left(mut presses: ButtonPresses<&Left>, mut handled: ResMut<Handled>) {
+        for Left(id) in presses.read() {
+            handled.0.push(("left", *id));
+        }
+    }
+
+    fn handle_right(mut presses: ButtonPresses<&Right>, mut handled: ResMut<Handled>) {
+        for Right(id) in presses.read() {
+            handled.0.push(("right", *id));
+        }
+    }
+
+    fn press(app: &mut App, button: Entity) {
+        *app.world_mut().get_mut::<Interaction>(button).unwrap() = Interaction::Pressed;
+    }
+
+    #[test]
+    fn presses_on_screens_coming_and_going_are_handled_once_by_their_own_screen() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, button_press_plugin))
+            .init_resource::<CloseNow>()
+            .init_resource::<Handled>()
+            .add_systems(Update, (close_screen, (handle_left, handle_right)).chain());
+        app.update();
+
+        let mut expected = Vec::new();
+        let mut buttons: Vec<(Entity, &'static str, u32)> = Vec::new();
+        let mut closed: Vec<Entity> = Vec::new();
+        let mut recycled = 0;
+        let mut next_id = 0;
+        for frame in 0..300u32 {
+            if buttons.is_empty() {
+                let side = if frame % 3 == 0 { "right" } else { "left" };
+                let world = app.world_mut();
+                let screen = world.spawn(Screen).id();
+                for _ in 0..3 {
+                    let button = match side {
+                        "left" => world.spawn((Button, Interaction::None, Left(next_id))),
+                        _ => world.spawn((Button, Interaction::None, Right(next_id))),
+                    }
+                    .insert(ChildOf(screen))
+                    .id();
+                    recycled += closed
+                        .iter()
+                        .filter(|old| old.index() == button.index())
+                        .count();
+                    buttons.push((button, side, next_id));
+                    next_id += 1;
+                }
+            }
+            // Presses left over from the screen closed last frame, for buttons whose
+            // entities may have been handed to the new screen's.
+            for old in closed.drain(..) {
+                app.world_mut().send_event(ButtonPressed(old));
+            }
+
+            let (button, side, id) = buttons[frame as usize % buttons.len()];
+            match frame % 5 {
+                // A press, handled.
+                0 => {
+                    press(&mut app, button);
+                    expected.push((side, id));
+                    app.update();
+                }
+                // Two buttons at once, both handled.
+                1 => {
+                    let (other, _, other_id) = buttons[(frame as usize + 1) % buttons.len()];
+                    press(&mut app, button);
+                    press(&mut app, other);
+                    expected.extend([(side, id), (side, other_id)]);
+                    app.update();
+                }
+                // The screen closes the frame it's pressed.
+                2 => {
+                    press(&mut app, button);
+                    app.world_mut().resource_mut::<CloseNow>().0 = true;
+                    app.update();
+                    closed.extend(buttons.drain(..).map(|(button, ..)| button));
+                }
+                // The button stops taking input before the press is handled.
+                3 => {
+                    press(&mut app, button);
+                    app.update();
+                    app.world_mut().entity_mut(button).remove::<Interaction>();
+                    app.world_mut().send_event(ButtonPressed(button));
+                    app.update();
+                    app.world_mut().entity_mut(button).insert(Interaction::None);
+                    expected.push((side, id));
+                }
+                // Held down over several frames, handled once, then the screen goes.
+                _ => {
+                    press(&mut app, button);
+                    expected.push((side, id));
+                    for _ in 0..3 {
+                        app.update();
+                    }
+                    let world = app.world_mut();
+                    let screen = world.get::<ChildOf>(button).unwrap().parent();
+                    world.despawn(screen);
+                    closed.extend(buttons.drain(..).map(|(button, ..)| button));
+                }
+            }
+            for (button, ..) in &buttons {
+                *app.world_mut().get_mut::<Interaction>(*button).unwrap() = Interaction::None;
+            }
+        }
+        app.update();
+
+        assert!(
+            recycled > 0,
+            "no entity was reused, so nothing could be misrouted"
+        );
+        // Presses in the same frame come in no particular order.
+        let mut handled = app.world().resource::<Handled>().0.clone();
+        handled.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(handled, expected);
+    }
+}
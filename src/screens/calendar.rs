@@ -0,0 +1,157 @@
+//! The calendar popup: the next four weeks laid out by day, with holidays marked so the
+//! player can plan around days when work and the shops are closed.
+
+use bevy::prelude::*;
+
+use super::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use super::overlay::{MODAL_Z_INDEX, Overlay};
+use crate::fonts::ui_font;
+use crate::palette::{CORAL, DARK, DARKER, LIGHT, YELLOW};
+use crate::sim::{calendar::Calendar, clock::Clock};
+use crate::{despawn_screen, track_screen};
+
+const PANEL_FONT_SIZE: f32 = 16.0;
+const DAYS_SHOWN: u32 = 28;
+const DAYS_PER_ROW: u32 = 7;
+const CELL_SIZE: f32 = 48.0;
+
+#[derive(Component)]
+struct CalendarOverlay;
+
+pub fn calendar_plugin(app: &mut App) {
+    track_screen::<CalendarOverlay>(app, Overlay::Calendar);
+    app.add_systems(Update, offer_calendar.in_set(ActionSet::Offer))
+        .add_systems(Update, open_calendar.run_if(in_state(Overlay::None)))
+        .add_systems(OnEnter(Overlay::Calendar), setup_calendar)
+        .add_systems(OnExit(Overlay::Calendar), despawn_screen::<CalendarOverlay>)
+        .add_systems(Update, close_calendar.run_if(in_state(Overlay::Calendar)));
+}
+
+fn offer_calendar(mut actions: ResMut<AvailableActions>) {
+    actions.offer(GameAction::Calendar);
+}
+
+fn open_calendar(
+    mut pressed: EventReader<ActionPressed>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        if *action == GameAction::Calendar {
+            next_state.set(Overlay::Calendar);
+        }
+    }
+}
+
+fn close_calendar(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(Overlay::None);
+    }
+}
+
+fn setup_calendar(
+    mut commands: Commands,
+    calendar: Res<Calendar>,
+    clock: Res<Clock>,
+    assets: Res<AssetServer>,
+) {
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: PANEL_FONT_SIZE,
+        ..default()
+    };
+    let text = |value: String, color: Color| (Text::new(value), font.clone(), TextColor(color));
+    let days = clock.day..clock.day + DAYS_SHOWN;
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            GlobalZIndex(MODAL_Z_INDEX),
+            CalendarOverlay,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(10.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    BackgroundColor(DARKER),
+                ))
+                .with_children(|panel| {
+                    panel.spawn(text(
+                        format!("Calendar - {}", calendar.season(clock.day).name),
+                        YELLOW,
+                    ));
+                    panel
+                        .spawn(Node {
+                            display: Display::Grid,
+                            grid_template_columns: RepeatedGridTrack::px(
+                                DAYS_PER_ROW as u16,
+                                CELL_SIZE,
+                            ),
+                            row_gap: Val::Px(4.0),
+                            column_gap: Val::Px(4.0),
+                            ..default()
+                        })
+                        .with_children(|grid| {
+                            for day in days.clone() {
+                                let background = if calendar.holiday(day).is_some() {
+                                    CORAL
+                                } else {
+                                    DARK
+                                };
+                                let label = format!(
+                                    "{day}\n{}",
+                                    Clock { day, hour: 0 }.weekday().short_name()
+                                );
+                                grid.spawn((
+                                    Node {
+                                        height: Val::Px(CELL_SIZE),
+                                        padding: UiRect::all(Val::Px(4.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(background),
+                                    children![text(label, LIGHT)],
+                                ));
+                            }
+                        });
+
+                    let upcoming: Vec<_> = days
+                        .filter_map(|day| calendar.holiday(day).map(|holiday| (day, holiday)))
+                        .collect();
+                    if upcoming.is_empty() {
+                        panel.spawn(text(
+                            "No holidays in the next four weeks".to_string(),
+                            LIGHT.with_alpha(0.6),
+                        ));
+                    }
+                    for (day, holiday) in upcoming {
+                        let when = match day - clock.day {
+                            0 => "today".to_string(),
+                            1 => "tomorrow".to_string(),
+                            days => format!("in {days} days"),
+                        };
+                        panel.spawn(text(
+                            format!(
+                                "{} on day {day}, {when}: no work, shops closed",
+                                holiday.name
+                            ),
+                            CORAL,
+                        ));
+                    }
+                    panel.spawn(text("Esc to close".to_string(), LIGHT.with_alpha(0.6)));
+                });
+        });
+}
@@ -0,0 +1,67 @@
+//! The weekly challenge's part of the end screens: the week's seed, a line to share and
+//! the week's best result.
+
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+
+use crate::format;
+use crate::palette::{LIGHT, YELLOW};
+use crate::profiles::ProfilePaths;
+use crate::save_file::SaveFiles;
+use crate::settings::Locale;
+use crate::sim::challenge::{Challenge, ChallengeResult, HighScores};
+use crate::sim::scenario::Scenarios;
+
+/// Records how a weekly challenge went in the profile's [`HighScores`] and lists it, with
+/// the seed and a line to share. Nothing for a regular game.
+pub fn challenge_result(
+    challenge: &Challenge,
+    result: ChallengeResult,
+    scenarios: &Scenarios,
+    locale: Locale,
+    profile: &ProfilePaths,
+    files: &mut SaveFiles,
+) -> impl Bundle {
+    let mut lines = Vec::new();
+    if let Some(week) = challenge.0 {
+        let mut scores = HighScores::load(profile, &mut files.corrupt);
+        let best = scores.record(week, result.clone());
+        scores.save(profile, &mut files.saves);
+        let summary = result.summary(
+            week,
+            scenarios.name(result.scenario.as_deref()),
+            &format::money(result.worth, locale),
+        );
+        info!("{summary}");
+        lines.push((summary, YELLOW));
+        lines.push((format!("Seed {:016x}", week.seed()), LIGHT.with_alpha(0.6)));
+        let best_line = if best == result {
+            "Your best this week!".to_string()
+        } else {
+            format!(
+                "Your best this week: {} on day {}",
+                format::money(best.worth, locale),
+                best.days
+            )
+        };
+        lines.push((best_line, LIGHT.with_alpha(0.8)));
+    }
+    (
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(4.0),
+            margin: UiRect::top(Val::Px(20.0)),
+            ..default()
+        },
+        Children::spawn(SpawnIter(lines.into_iter().map(|(line, color)| {
+            (
+                Text::new(line),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(color),
+            )
+        }))),
+    )
+}
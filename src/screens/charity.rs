@@ -0,0 +1,283 @@
+//! The charity office in the business district: an amount input to pick a donation, the player's
+//! reputation and how far they are from the philanthropist ending. The best title earned
+//! shows with the inbox on the game screen.
+
+use bevy::prelude::*;
+
+use super::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use super::amount_input::{AmountConfirmed, AmountInput, amount_input};
+use super::hud::{HudOrder, HudRoot, HudSlot};
+use super::nav::{ScreenNav, TransitionCause};
+use super::overlay::{MODAL_Z_INDEX, Overlay};
+use super::tooltip::Tooltip;
+use crate::fonts::ui_font;
+use crate::format;
+use crate::palette::{BLUE, DARKER, LIGHT, YELLOW};
+use crate::settings::Locale;
+use crate::sim::{
+    bank::Bank,
+    charity::{CharityConfig, Reputation},
+    economy::{Area, CurrentArea, Ending, FreePlay, Money},
+    ledger::{Category, Transaction},
+    new_game_plus::PriceLevel,
+};
+use crate::toast::Toast;
+use crate::{AppState, despawn_screen, track_screen};
+
+const PANEL_FONT_SIZE: f32 = 16.0;
+const TITLE_FONT_SIZE: f32 = 14.0;
+/// The input starts here, or at everything the player has if that's less.
+const DEFAULT_DONATION: u32 = 100;
+const DONATION_STEP: u32 = 10;
+
+#[derive(Component)]
+struct CharityOverlay;
+
+#[derive(Component)]
+struct CharityInfo;
+
+#[derive(Component)]
+struct ReputationTitle;
+
+pub fn charity_plugin(app: &mut App) {
+    track_screen::<ReputationTitle>(app, AppState::Game);
+    track_screen::<CharityOverlay>(app, Overlay::Charity);
+    app.add_systems(OnEnter(AppState::Game), spawn_reputation_title)
+        .add_systems(OnExit(AppState::Game), despawn_screen::<ReputationTitle>)
+        .add_systems(
+            Update,
+            update_reputation_title
+                .run_if(resource_changed::<Reputation>.and(in_state(AppState::Game))),
+        )
+        .add_systems(Update, offer_donate.in_set(ActionSet::Offer))
+        .add_systems(Update, open_charity.run_if(in_state(Overlay::None)))
+        .add_systems(OnEnter(Overlay::Charity), setup_charity)
+        .add_systems(OnExit(Overlay::Charity), despawn_screen::<CharityOverlay>)
+        .add_systems(
+            Update,
+            (
+                (close_charity, donate),
+                (limit_donation, render_charity_info).run_if(
+                    resource_changed::<Reputation>
+                        .or(resource_changed::<Money>)
+                        .or(resource_changed::<Bank>),
+                ),
+            )
+                .chain()
+                .run_if(in_state(Overlay::Charity)),
+        );
+}
+
+fn spawn_reputation_title(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.spawn((
+        Text::default(),
+        TextFont {
+            font: ui_font(&assets),
+            font_size: TITLE_FONT_SIZE,
+            ..default()
+        },
+        TextColor(YELLOW),
+        Tooltip::new("Earned by giving to charity in the business district"),
+        ReputationTitle,
+        HudRoot,
+        HudSlot::TopLeft,
+        HudOrder(2),
+    ));
+}
+
+fn update_reputation_title(
+    reputation: Res<Reputation>,
+    config: Res<CharityConfig>,
+    mut title: Query<&mut Text, With<ReputationTitle>>,
+) {
+    let Ok(mut title) = title.single_mut() else {
+        return;
+    };
+    title.0 = reputation.title(&config).unwrap_or_default().to_string();
+}
+
+fn offer_donate(area: Res<CurrentArea>, mut actions: ResMut<AvailableActions>) {
+    if area.0 == Area::BusinessDistrict {
+        actions.offer(GameAction::Donate);
+    }
+}
+
+fn open_charity(
+    mut pressed: EventReader<ActionPressed>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        if *action == GameAction::Donate {
+            next_state.set(Overlay::Charity);
+        }
+    }
+}
+
+fn setup_charity(
+    mut commands: Commands,
+    money: Res<Money>,
+    bank: Res<Bank>,
+    mut reputation: ResMut<Reputation>,
+    assets: Res<AssetServer>,
+) {
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: PANEL_FONT_SIZE,
+        ..default()
+    };
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        GlobalZIndex(MODAL_Z_INDEX),
+        CharityOverlay,
+        children![(
+            Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            BackgroundColor(DARKER),
+            children![
+                (Text::new("Charity"), font.clone(), TextColor(YELLOW)),
+                (
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    CharityInfo,
+                ),
+                amount_input(
+                    AmountInput::new(money.amount + bank.savings)
+                        .starting_at(DEFAULT_DONATION)
+                        .with_step(DONATION_STEP)
+                        .money()
+                        .with_presets()
+                        .with_confirm("Donate"),
+                ),
+                (
+                    Text::new("Donations draw on cash, then savings. Esc to close"),
+                    font,
+                    TextColor(LIGHT.with_alpha(0.6)),
+                ),
+            ],
+        )],
+    ));
+    // Render on the first frame.
+    reputation.set_changed();
+}
+
+fn close_charity(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(Overlay::None);
+    }
+}
+
+fn donate(
+    mut confirmed: EventReader<AmountConfirmed>,
+    config: Res<CharityConfig>,
+    prices: Res<PriceLevel>,
+    locale: Res<Locale>,
+    mut money: ResMut<Money>,
+    mut bank: ResMut<Bank>,
+    mut reputation: ResMut<Reputation>,
+    mut ending: ResMut<Ending>,
+    free_play: Res<FreePlay>,
+    mut transactions: EventWriter<Transaction>,
+    mut toasts: EventWriter<Toast>,
+    mut nav: ScreenNav,
+) {
+    let Some(amount) = confirmed.read().last().map(|confirmed| confirmed.value) else {
+        return;
+    };
+    if amount == 0 {
+        return;
+    }
+    if !bank.pay(&mut money, amount) {
+        toasts.write(Toast::new("Not enough money"));
+        return;
+    }
+    transactions.write(Transaction::Expense(Category::Donations, amount));
+    reputation.donate(amount, &config);
+    toasts.write(Toast::new(format!(
+        "Donated {}. Thank you!",
+        format::money(amount, *locale)
+    )));
+    if reputation.lifetime_donations >= config.philanthropist_target(&prices)
+        && free_play.0.is_none()
+    {
+        *ending = Ending::Philanthropy;
+        nav.set_app(AppState::Win, TransitionCause::WinCondition);
+    }
+}
+
+fn limit_donation(money: Res<Money>, bank: Res<Bank>, mut input: Query<&mut AmountInput>) {
+    let Ok(mut input) = input.single_mut() else {
+        return;
+    };
+    input.set_max(money.amount + bank.savings);
+}
+
+fn render_charity_info(
+    mut commands: Commands,
+    reputation: Res<Reputation>,
+    config: Res<CharityConfig>,
+    prices: Res<PriceLevel>,
+    info: Query<Entity, With<CharityInfo>>,
+    assets: Res<AssetServer>,
+    locale: Res<Locale>,
+) {
+    let Ok(info) = info.single() else {
+        return;
+    };
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: PANEL_FONT_SIZE,
+        ..default()
+    };
+    let text = |value: String, color: Color| (Text::new(value), font.clone(), TextColor(color));
+    let target = config.philanthropist_target(&prices);
+    let next_title = config
+        .titles
+        .iter()
+        .filter(|title| title.reputation > reputation.points)
+        .min_by_key(|title| title.reputation);
+
+    commands
+        .entity(info)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            parent.spawn(text(
+                format!(
+                    "Reputation: {} ({})",
+                    reputation.points,
+                    reputation.title(&config).unwrap_or("unknown")
+                ),
+                LIGHT,
+            ));
+            if let Some(next) = next_title {
+                parent.spawn(text(
+                    format!("{} at {} reputation", next.title, next.reputation),
+                    LIGHT.with_alpha(0.6),
+                ));
+            }
+            parent.spawn(text(
+                format!(
+                    "Given so far: {} of {} to retire a philanthropist",
+                    format::money(reputation.lifetime_donations, *locale),
+                    format::money(target, *locale)
+                ),
+                BLUE,
+            ));
+        });
+}
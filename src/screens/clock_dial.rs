@@ -0,0 +1,198 @@
+//! A small analog clock for the HUD. Its hand sweeps between simulation ticks instead of
+//! jumping once an hour: the fraction of the next tick already elapsed is read from the
+//! [`FixedUpdate`] accumulator and added to [`Clock::hour`].
+//!
+//! Spawn [`clock_dial`] anywhere in the UI; the widget only reads the [`Clock`] and hides
+//! itself when [`ClockStyle`] doesn't ask for a dial.
+
+use std::f32::consts::TAU;
+
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+
+use super::area_theme::AccentBorder;
+use crate::AppState;
+use crate::palette::{DARK, DARKER, LIGHT};
+use crate::settings::{ClockStyle, ReducedMotion};
+use crate::sim::{clock::Clock, sim_running};
+
+const DIAL_SIZE: f32 = 48.0;
+const DIAL_HOURS: f32 = 12.0;
+const TICK_LENGTH: f32 = 5.0;
+const HAND_WIDTH: f32 = 3.0;
+const PIN_SIZE: f32 = 6.0;
+/// The face goes dark between these hours, so a 12-hour dial still tells day from night.
+const NIGHT_STARTS: u32 = 20;
+const NIGHT_ENDS: u32 = 6;
+
+/// The dial's face.
+#[derive(Component)]
+pub struct ClockDial;
+
+/// Turns around the dial's centre to point at the hour.
+#[derive(Component, Default)]
+struct ClockHand {
+    /// The time shown, in hours since the start of the run.
+    shown: Option<f32>,
+}
+
+/// Hour marks, hand and pin, drawn in the contrasting color to the face.
+#[derive(Component)]
+struct ClockInk;
+
+pub fn clock_dial_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            // While the simulation is paused the hand stays put.
+            turn_clock_hands.run_if(
+                in_state(AppState::Game).and(sim_running.or(any_match_filter::<Added<ClockHand>>)),
+            ),
+            shade_clock_dials
+                .run_if(resource_changed::<Clock>.or(any_match_filter::<Added<ClockDial>>)),
+            show_clock_dials
+                .run_if(resource_changed::<ClockStyle>.or(any_match_filter::<Added<ClockDial>>)),
+        ),
+    );
+}
+
+/// A dial showing the [`Clock`]'s hour.
+pub fn clock_dial() -> impl Bundle {
+    (
+        Node {
+            width: Val::Px(DIAL_SIZE),
+            height: Val::Px(DIAL_SIZE),
+            border: UiRect::all(Val::Px(2.0)),
+            ..default()
+        },
+        BorderRadius::MAX,
+        AccentBorder(DARKER),
+        BackgroundColor(LIGHT),
+        ClockDial,
+        Children::spawn((
+            SpawnIter((0..DIAL_HOURS as u32).map(|hour| {
+                (
+                    spinner(hour as f32 / DIAL_HOURS * TAU),
+                    children![(
+                        Node {
+                            position_type: PositionType::Absolute,
+                            left: Val::Percent(50.0),
+                            margin: UiRect::left(Val::Px(-1.0)),
+                            width: Val::Px(2.0),
+                            height: Val::Px(TICK_LENGTH),
+                            ..default()
+                        },
+                        BackgroundColor(DARKER),
+                        ClockInk,
+                    )],
+                )
+            })),
+            Spawn((
+                spinner(0.0),
+                ClockHand::default(),
+                children![(
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(50.0),
+                        top: Val::Percent(15.0),
+                        margin: UiRect::left(Val::Px(-HAND_WIDTH / 2.0)),
+                        width: Val::Px(HAND_WIDTH),
+                        height: Val::Percent(35.0),
+                        ..default()
+                    },
+                    BorderRadius::MAX,
+                    BackgroundColor(DARKER),
+                    ClockInk,
+                )],
+            )),
+            Spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(50.0),
+                    top: Val::Percent(50.0),
+                    margin: UiRect::new(
+                        Val::Px(-PIN_SIZE / 2.0),
+                        Val::ZERO,
+                        Val::Px(-PIN_SIZE / 2.0),
+                        Val::ZERO,
+                    ),
+                    width: Val::Px(PIN_SIZE),
+                    height: Val::Px(PIN_SIZE),
+                    ..default()
+                },
+                BorderRadius::MAX,
+                BackgroundColor(DARKER),
+                ClockInk,
+            )),
+        )),
+    )
+}
+
+/// Covers its parent and turns by `angle` around the parent's centre, clockwise from twelve.
+/// Anything placed at the top of it ends up at that angle, like a mark on a dial.
+pub fn spinner(angle: f32) -> impl Bundle {
+    (
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        Transform::from_rotation(Quat::from_rotation_z(angle)),
+    )
+}
+
+fn turn_clock_hands(
+    clock: Res<Clock>,
+    time: Res<Time<Fixed>>,
+    reduced_motion: Res<ReducedMotion>,
+    mut hands: Query<(&mut ClockHand, &mut Transform)>,
+) {
+    let fraction = if reduced_motion.0 {
+        0.0
+    } else {
+        time.overstep_fraction()
+    };
+    let now = clock.total_hours() as f32 + fraction;
+    for (mut hand, mut transform) in &mut hands {
+        // The accumulator keeps filling while the simulation is paused, so after a pause
+        // the hand waits for the time it already showed rather than turning back. Jumps
+        // of an hour or more, like sleeping or a new run, snap straight to the new time.
+        let shown = match hand.shown {
+            Some(shown) if now < shown && shown - now < 1.0 => shown,
+            _ => now,
+        };
+        hand.shown = Some(shown);
+        let angle = (shown % DIAL_HOURS) / DIAL_HOURS * TAU;
+        transform.rotation = Quat::from_rotation_z(angle);
+    }
+}
+
+fn shade_clock_dials(
+    clock: Res<Clock>,
+    mut faces: Query<&mut BackgroundColor, (With<ClockDial>, Without<ClockInk>)>,
+    mut ink: Query<&mut BackgroundColor, (With<ClockInk>, Without<ClockDial>)>,
+) {
+    let night = clock.hour >= NIGHT_STARTS || clock.hour < NIGHT_ENDS;
+    let (face_color, ink_color) = if night {
+        (DARK, LIGHT)
+    } else {
+        (LIGHT, DARKER)
+    };
+    for mut face in &mut faces {
+        face.set_if_neq(BackgroundColor(face_color));
+    }
+    for mut mark in &mut ink {
+        mark.set_if_neq(BackgroundColor(ink_color));
+    }
+}
+
+fn show_clock_dials(style: Res<ClockStyle>, mut dials: Query<&mut Node, With<ClockDial>>) {
+    let display = if style.shows_dial() {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    for mut node in &mut dials {
+        node.display = display;
+    }
+}
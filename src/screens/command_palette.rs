@@ -0,0 +1,342 @@
+//! The command palette: Ctrl+K on the game screen lists every action the action panel
+//! offers right now, filtered by what the player types, see [`fuzzy_score`]. Up and Down
+//! pick one and Enter takes it, sending the same [`ActionPressed`] as its button would.
+//! Blocked actions are listed greyed out with the reason, and can't be taken. Actions with
+//! a hotkey show it, for next time.
+
+use bevy::prelude::*;
+
+use super::actions::{ActionPressed, ActionSet, AvailableActions, OfferedAction, hotkey_hint};
+use super::overlay::{MODAL_Z_INDEX, Overlay, no_modal_open};
+use super::text_field::{TextField, text_field};
+use crate::fonts::ui_font;
+use crate::palette::{BLUE, DARKER, LIGHT, YELLOW};
+use crate::{despawn_screen, track_screen};
+
+/// Opens the palette while Ctrl is held.
+pub const PALETTE_KEY: KeyCode = KeyCode::KeyK;
+const PALETTE_FONT_SIZE: f32 = 16.0;
+const REASON_FONT_SIZE: f32 = 12.0;
+const PALETTE_WIDTH: f32 = 420.0;
+const MAX_QUERY_LENGTH: usize = 30;
+const MAX_ROWS: usize = 10;
+const BLOCKED_ALPHA: f32 = 0.4;
+
+#[derive(Component)]
+struct PaletteOverlay;
+
+#[derive(Component)]
+struct PaletteRows;
+
+/// The actions matching what's typed, best first, and the one picked.
+#[derive(Resource, Debug, Default, PartialEq)]
+struct PaletteMatches {
+    query: String,
+    actions: Vec<OfferedAction>,
+    selected: usize,
+}
+
+/// How well `query` matches `label`, or `None` if it doesn't: every character of the query
+/// has to appear in the label in order, ignoring case and the query's spaces. Characters
+/// that start a word or follow the last match score higher, so a few letters of each word
+/// find an action.
+pub fn fuzzy_score(query: &str, label: &str) -> Option<u32> {
+    let label: Vec<char> = label.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut next = 0;
+    for wanted in query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+    {
+        let found = next + label[next..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if found == 0 || !label[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        if found > 0 && found == next {
+            score += 2;
+        }
+        next = found + 1;
+    }
+    Some(score)
+}
+
+pub fn command_palette_plugin(app: &mut App) {
+    track_screen::<PaletteOverlay>(app, Overlay::CommandPalette);
+    app.init_resource::<PaletteMatches>()
+        .add_systems(
+            Update,
+            open_palette.run_if(in_state(Overlay::None).and(no_modal_open)),
+        )
+        .add_systems(OnEnter(Overlay::CommandPalette), spawn_palette)
+        .add_systems(
+            OnExit(Overlay::CommandPalette),
+            despawn_screen::<PaletteOverlay>,
+        )
+        .add_systems(
+            Update,
+            (
+                match_actions,
+                pick_action,
+                fill_rows.run_if(resource_changed::<PaletteMatches>),
+            )
+                .chain()
+                .after(ActionSet::Offer)
+                .run_if(in_state(Overlay::CommandPalette)),
+        );
+}
+
+fn open_palette(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+        && keyboard_input.just_pressed(PALETTE_KEY)
+    {
+        next_state.set(Overlay::CommandPalette);
+    }
+}
+
+fn spawn_palette(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(PaletteMatches::default());
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: PALETTE_FONT_SIZE,
+        ..default()
+    };
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            justify_content: JustifyContent::Center,
+            padding: UiRect::top(Val::Percent(10.0)),
+            ..default()
+        },
+        GlobalZIndex(MODAL_Z_INDEX),
+        PaletteOverlay,
+        children![(
+            Node {
+                width: Val::Px(PALETTE_WIDTH),
+                flex_direction: FlexDirection::Column,
+                align_self: AlignSelf::FlexStart,
+                row_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                ..default()
+            },
+            BackgroundColor(DARKER),
+            children![
+                (
+                    Text::new("Quick actions - Up/Down to pick, Enter to take, Esc to close"),
+                    TextFont {
+                        font_size: REASON_FONT_SIZE,
+                        ..font.clone()
+                    },
+                    TextColor(YELLOW),
+                ),
+                text_field(TextField::new(MAX_QUERY_LENGTH), font),
+                (
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        ..default()
+                    },
+                    PaletteRows,
+                ),
+            ],
+        )],
+    ));
+}
+
+// Redone every frame, since the offers can change under the open palette.
+fn match_actions(
+    available: Res<AvailableActions>,
+    field: Query<&TextField>,
+    mut matches: ResMut<PaletteMatches>,
+) {
+    let Ok(field) = field.single() else {
+        return;
+    };
+    let mut offered = available.offered().to_vec();
+    offered.sort_by_key(|offered| offered.action);
+    offered.dedup_by_key(|offered| offered.action);
+    let mut scored: Vec<_> = offered
+        .into_iter()
+        .filter_map(|offered| Some((fuzzy_score(&field.value, &offered.label)?, offered)))
+        .collect();
+    // Stable, so equal scores keep the panel's order.
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    let actions: Vec<_> = scored
+        .into_iter()
+        .map(|(_, offered)| offered)
+        .take(MAX_ROWS)
+        .collect();
+    let selected = if field.value == matches.query {
+        matches.selected.min(actions.len().saturating_sub(1))
+    } else {
+        0
+    };
+    matches.set_if_neq(PaletteMatches {
+        query: field.value.clone(),
+        actions,
+        selected,
+    });
+}
+
+fn pick_action(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut matches: ResMut<PaletteMatches>,
+    mut pressed: EventWriter<ActionPressed>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(Overlay::None);
+        return;
+    }
+    let count = matches.actions.len();
+    if count == 0 {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        matches.selected = (matches.selected + 1) % count;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        matches.selected = (matches.selected + count - 1) % count;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) {
+        let offered = &matches.actions[matches.selected];
+        if offered.blocked.is_none() {
+            // Whatever the action opens takes over once the palette is closed.
+            pressed.write(ActionPressed(offered.action));
+            next_state.set(Overlay::None);
+        }
+    }
+}
+
+fn fill_rows(
+    mut commands: Commands,
+    matches: Res<PaletteMatches>,
+    rows: Query<Entity, With<PaletteRows>>,
+    assets: Res<AssetServer>,
+) {
+    let Ok(rows) = rows.single() else {
+        return;
+    };
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: PALETTE_FONT_SIZE,
+        ..default()
+    };
+    commands
+        .entity(rows)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            if matches.actions.is_empty() {
+                parent.spawn((
+                    Text::new("Nothing matches"),
+                    font.clone(),
+                    TextColor(LIGHT.with_alpha(BLOCKED_ALPHA)),
+                ));
+            }
+            for (index, offered) in matches.actions.iter().enumerate() {
+                let background = if index == matches.selected {
+                    BLUE
+                } else {
+                    DARKER
+                };
+                let alpha = if offered.blocked.is_some() {
+                    BLOCKED_ALPHA
+                } else {
+                    1.0
+                };
+                parent
+                    .spawn((
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(background),
+                    ))
+                    .with_children(|row| {
+                        let mut label = row.spawn((
+                            Text::new(offered.label.clone()),
+                            font.clone(),
+                            TextColor(LIGHT.with_alpha(alpha)),
+                        ));
+                        if let Some(hint) = hotkey_hint(offered.action, &font) {
+                            label.with_child(hint);
+                        }
+                        if let Some(reason) = &offered.blocked {
+                            row.spawn((
+                                Text::new(reason.clone()),
+                                TextFont {
+                                    font_size: REASON_FONT_SIZE,
+                                    ..font.clone()
+                                },
+                                TextColor(LIGHT.with_alpha(0.6)),
+                            ));
+                        }
+                    });
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_query_has_to_appear_in_order() {
+        assert_eq!(fuzzy_score("", "Travel"), Some(0));
+        assert!(fuzzy_score("trvl", "Travel").is_some());
+        assert_eq!(fuzzy_score("lt", "Travel"), None);
+        assert_eq!(fuzzy_score("travels", "Travel"), None);
+        assert_eq!(fuzzy_score("x", "Travel"), None);
+    }
+
+    #[test]
+    fn case_and_spaces_in_the_query_dont_count() {
+        assert_eq!(
+            fuzzy_score("PAY R", "Pay rent"),
+            fuzzy_score("payr", "Pay rent")
+        );
+        assert_eq!(fuzzy_score(" ", "Pay rent"), Some(0));
+        // Spaces in the label are still there to skip over.
+        assert_eq!(fuzzy_score("yr", "Pay rent"), Some(5));
+    }
+
+    #[test]
+    fn word_starts_and_runs_score_higher() {
+        // A word's first letter is worth 4, the next one on from a match 3, any other 1.
+        assert_eq!(fuzzy_score("t", "Travel"), Some(4));
+        assert_eq!(fuzzy_score("t", "Set savings goal"), Some(1));
+        assert_eq!(fuzzy_score("dep", "Deposit cash"), Some(10));
+        assert_eq!(fuzzy_score("dc", "Deposit cash"), Some(8));
+        assert_eq!(fuzzy_score("dpt", "Deposit cash"), Some(6));
+    }
+
+    #[test]
+    fn a_few_letters_of_each_word_find_the_action() {
+        let labels = [
+            "Freelance 3h: -25 energy  F",
+            "Deposit cash  D",
+            "Set savings goal",
+            "Investments  I",
+            "Turn off rent autopay",
+            "Pay rent ($500)",
+        ];
+        let best = |query: &str| {
+            labels
+                .iter()
+                .filter_map(|label| Some((fuzzy_score(query, label)?, *label)))
+                .max_by_key(|(score, _)| *score)
+                .map(|(_, label)| label)
+        };
+        assert_eq!(best("pay"), Some("Pay rent ($500)"));
+        assert_eq!(best("dep"), Some("Deposit cash  D"));
+        assert_eq!(best("inv"), Some("Investments  I"));
+        assert_eq!(best("auto"), Some("Turn off rent autopay"));
+        assert_eq!(best("zzz"), None);
+    }
+}
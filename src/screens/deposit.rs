@@ -0,0 +1,133 @@
+//! The deposit panel at the bank: pick how much cash to put into savings, where thieves
+//! can't reach it.
+
+use bevy::prelude::*;
+
+use super::actions::{ActionPressed, GameAction};
+use super::amount_input::{AmountConfirmed, AmountInput, amount_input};
+use super::overlay::{MODAL_Z_INDEX, Overlay};
+use crate::fonts::ui_font;
+use crate::format;
+use crate::palette::{DARKER, LIGHT, YELLOW};
+use crate::settings::Locale;
+use crate::sim::{bank::Bank, economy::Money};
+use crate::toast::Toast;
+use crate::{despawn_screen, track_screen};
+
+const PANEL_FONT_SIZE: f32 = 16.0;
+const DEPOSIT_STEP: u32 = 10;
+
+#[derive(Component)]
+struct DepositOverlay;
+
+pub fn deposit_plugin(app: &mut App) {
+    track_screen::<DepositOverlay>(app, Overlay::Deposit);
+    app.add_systems(Update, open_deposit.run_if(in_state(Overlay::None)))
+        .add_systems(OnEnter(Overlay::Deposit), setup_deposit)
+        .add_systems(OnExit(Overlay::Deposit), despawn_screen::<DepositOverlay>)
+        .add_systems(
+            Update,
+            (
+                (close_deposit, deposit),
+                limit_deposit.run_if(resource_changed::<Money>),
+            )
+                .chain()
+                .run_if(in_state(Overlay::Deposit)),
+        );
+}
+
+fn open_deposit(
+    mut pressed: EventReader<ActionPressed>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        if *action == GameAction::DepositCash {
+            next_state.set(Overlay::Deposit);
+        }
+    }
+}
+
+fn setup_deposit(mut commands: Commands, money: Res<Money>, assets: Res<AssetServer>) {
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: PANEL_FONT_SIZE,
+        ..default()
+    };
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        GlobalZIndex(MODAL_Z_INDEX),
+        DepositOverlay,
+        children![(
+            Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            BackgroundColor(DARKER),
+            children![
+                (Text::new("Deposit cash"), font.clone(), TextColor(YELLOW)),
+                amount_input(
+                    AmountInput::new(money.amount)
+                        .starting_at(money.amount)
+                        .with_step(DEPOSIT_STEP)
+                        .money()
+                        .with_presets()
+                        .with_confirm("Deposit"),
+                ),
+                (
+                    Text::new("Savings are safe from thieves. Esc to close"),
+                    font,
+                    TextColor(LIGHT.with_alpha(0.6)),
+                ),
+            ],
+        )],
+    ));
+}
+
+fn close_deposit(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(Overlay::None);
+    }
+}
+
+fn deposit(
+    mut confirmed: EventReader<AmountConfirmed>,
+    mut money: ResMut<Money>,
+    mut bank: ResMut<Bank>,
+    mut toasts: EventWriter<Toast>,
+    mut next_state: ResMut<NextState<Overlay>>,
+    locale: Res<Locale>,
+) {
+    let Some(amount) = confirmed.read().last().map(|confirmed| confirmed.value) else {
+        return;
+    };
+    // Cash may have been spent since the input's maximum was last updated.
+    let amount = amount.min(money.amount);
+    if amount > 0 {
+        money.amount -= amount;
+        bank.savings += amount;
+        toasts.write(Toast::new(format!(
+            "Deposited {}",
+            format::money(amount, *locale)
+        )));
+    }
+    next_state.set(Overlay::None);
+}
+
+fn limit_deposit(money: Res<Money>, mut input: Query<&mut AmountInput>) {
+    let Ok(mut input) = input.single_mut() else {
+        return;
+    };
+    input.set_max(money.amount);
+}
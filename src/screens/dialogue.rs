@@ -0,0 +1,358 @@
+//! The dialog box for talking to the characters of an area: their portrait and line, and
+//! a button for each response the player can give. Closing it drops the
+//! [`DialogueRunner`], so every conversation starts from scratch.
+
+use bevy::prelude::*;
+
+use super::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use super::button_press::ButtonPresses;
+use super::nav::{ScreenNav, TransitionCause};
+use super::overlay::{MODAL_Z_INDEX, Overlay};
+use crate::fonts::ui_font;
+use crate::format;
+use crate::palette::{BLUE, DARK, DARKER, LIGHT, YELLOW};
+use crate::settings::Locale;
+use crate::sim::{
+    catalog::AreaCatalog,
+    choices::{Choice, ChoiceLog},
+    clock::{Clock, HOURS_PER_DAY},
+    content::RunFilters,
+    crime::Upgrades,
+    dialogue::{DialogueContext, DialogueMemory, DialogueRunner, DiscountToken, Effect, NpcDef},
+    economy::{CurrentArea, Ending, FreePlay, Money, RentSchedule, WorkArea},
+    pet::Pet,
+};
+use crate::toast::Toast;
+use crate::{AppState, despawn_screen, track_screen};
+
+const DIALOGUE_FONT_SIZE: f32 = 16.0;
+const PORTRAIT_SIZE: f32 = 64.0;
+const BOX_WIDTH: f32 = 460.0;
+
+#[derive(Component)]
+struct DialogueOverlay;
+
+#[derive(Component)]
+struct DialogueContent;
+
+/// A response button: the index of the response in the node, or `None` to say goodbye.
+#[derive(Component, Debug, Clone, Copy)]
+struct ResponseChoice(Option<usize>);
+
+/// Sent when the player picks a response, by its index in the node, or `None` when they
+/// say goodbye.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponsePicked(pub Option<usize>);
+
+pub fn dialogue_plugin(app: &mut App) {
+    track_screen::<DialogueOverlay>(app, Overlay::Dialogue);
+    app.add_event::<ResponsePicked>()
+        .add_systems(Update, offer_talks.in_set(ActionSet::Offer))
+        .add_systems(Update, start_dialogue.run_if(in_state(Overlay::None)))
+        .add_systems(OnEnter(Overlay::Dialogue), setup_dialogue)
+        .add_systems(
+            OnExit(Overlay::Dialogue),
+            (despawn_screen::<DialogueOverlay>, end_dialogue),
+        )
+        .add_systems(
+            Update,
+            (
+                dialogue_input,
+                respond,
+                render_dialogue.run_if(resource_exists_and_changed::<DialogueRunner>),
+                response_colors,
+            )
+                .chain()
+                .run_if(in_state(Overlay::Dialogue)),
+        );
+}
+
+fn offer_talks(
+    catalog: Res<AreaCatalog>,
+    current: Res<CurrentArea>,
+    filters: Res<RunFilters>,
+    mut actions: ResMut<AvailableActions>,
+) {
+    let npcs = catalog.npcs(current.0).iter().enumerate();
+    for (index, npc) in npcs.filter(|(_, npc)| filters.allows(&npc.tags)) {
+        actions.offer_labeled(GameAction::TalkTo(index), format!("Talk to {}", npc.name));
+    }
+}
+
+fn start_dialogue(
+    mut commands: Commands,
+    mut pressed: EventReader<ActionPressed>,
+    catalog: Res<AreaCatalog>,
+    current: Res<CurrentArea>,
+    filters: Res<RunFilters>,
+    memory: Res<DialogueMemory>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        let GameAction::TalkTo(index) = *action else {
+            continue;
+        };
+        let Some(npc) = catalog
+            .npcs(current.0)
+            .get(index)
+            .filter(|npc| filters.allows(&npc.tags))
+        else {
+            continue;
+        };
+        let node = (!memory.exhausted.contains(&npc.id)).then(|| npc.dialogue.start.clone());
+        commands.insert_resource(DialogueRunner {
+            area: current.0,
+            npc: index,
+            node,
+        });
+        next_state.set(Overlay::Dialogue);
+    }
+}
+
+fn setup_dialogue(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::FlexEnd,
+            justify_content: JustifyContent::Center,
+            padding: UiRect::bottom(Val::Percent(8.0)),
+            ..default()
+        },
+        GlobalZIndex(MODAL_Z_INDEX),
+        DialogueOverlay,
+        children![(
+            Node {
+                width: Val::Px(BOX_WIDTH),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                ..default()
+            },
+            BackgroundColor(DARKER),
+            DialogueContent,
+        )],
+    ));
+}
+
+fn end_dialogue(mut commands: Commands) {
+    commands.remove_resource::<DialogueRunner>();
+}
+
+fn current_npc<'a>(catalog: &'a AreaCatalog, runner: &DialogueRunner) -> Option<&'a NpcDef> {
+    catalog.npcs(runner.area).get(runner.npc)
+}
+
+fn render_dialogue(
+    mut commands: Commands,
+    runner: Res<DialogueRunner>,
+    catalog: Res<AreaCatalog>,
+    content: Query<Entity, With<DialogueContent>>,
+    money: Res<Money>,
+    work: Res<WorkArea>,
+    upgrades: Res<Upgrades>,
+    pet: Res<Pet>,
+    locale: Res<Locale>,
+    assets: Res<AssetServer>,
+) {
+    let Ok(content) = content.single() else {
+        return;
+    };
+    let direction = locale.direction();
+    let Some(npc) = current_npc(&catalog, &runner) else {
+        return;
+    };
+    let context = DialogueContext {
+        money: money.amount,
+        employed: work.location.is_some(),
+        door_lock: upgrades.door_lock,
+        has_pet: pet.0.is_some(),
+    };
+    let node = runner
+        .node
+        .as_ref()
+        .and_then(|id| npc.dialogue.nodes.get(id));
+    let line = node.map_or(&npc.dialogue.fallback, |node| &node.line);
+    let mut responses: Vec<_> = node
+        .iter()
+        .flat_map(|node| node.responses.iter().enumerate())
+        .filter(|(_, response)| response.available(&context))
+        .map(|(index, response)| (response.text.clone(), ResponseChoice(Some(index))))
+        .collect();
+    if responses.is_empty() {
+        responses.push(("Goodbye".to_string(), ResponseChoice(None)));
+    }
+
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: DIALOGUE_FONT_SIZE,
+        ..default()
+    };
+    let portrait = npc.portrait.as_ref().map(|path| assets.load(path));
+    let initial = npc.name.chars().next().unwrap_or('?').to_string();
+
+    commands
+        .entity(content)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: direction.row(),
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(12.0),
+                    ..default()
+                })
+                .with_children(|header| {
+                    let size = Node {
+                        width: Val::Px(PORTRAIT_SIZE),
+                        height: Val::Px(PORTRAIT_SIZE),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    };
+                    // Characters without art get a tile with their initial.
+                    match portrait {
+                        Some(image) => {
+                            header.spawn((ImageNode::new(image), size));
+                        }
+                        None => {
+                            header.spawn((
+                                size,
+                                BackgroundColor(BLUE),
+                                children![(Text::new(initial), font.clone(), TextColor(DARKER))],
+                            ));
+                        }
+                    }
+                    header.spawn((Text::new(npc.name.clone()), font.clone(), TextColor(YELLOW)));
+                });
+            parent.spawn((
+                Text::new(line.clone()),
+                font.clone(),
+                TextColor(LIGHT),
+                direction.text(),
+            ));
+            for (text, choice) in responses {
+                parent.spawn((
+                    Button,
+                    Node {
+                        justify_content: direction.justify(JustifyContent::FlexStart),
+                        padding: UiRect::axes(Val::Px(12.0), Val::Px(8.0)),
+                        ..default()
+                    },
+                    BackgroundColor(DARK),
+                    choice,
+                    children![(
+                        Text::new(text),
+                        font.clone(),
+                        TextColor(LIGHT),
+                        direction.text()
+                    )],
+                ));
+            }
+        });
+}
+
+fn dialogue_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut presses: ButtonPresses<&ResponseChoice>,
+    mut picked: EventWriter<ResponsePicked>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(Overlay::None);
+        return;
+    }
+    if let Some(ResponseChoice(choice)) = presses.read().last() {
+        picked.write(ResponsePicked(*choice));
+    }
+}
+
+fn respond(
+    mut picked: EventReader<ResponsePicked>,
+    catalog: Res<AreaCatalog>,
+    mut runner: ResMut<DialogueRunner>,
+    mut memory: ResMut<DialogueMemory>,
+    mut discount: ResMut<DiscountToken>,
+    mut choices: ResMut<ChoiceLog>,
+    clock: Res<Clock>,
+    schedule: Res<RentSchedule>,
+    (mut ending, free_play): (ResMut<Ending>, Res<FreePlay>),
+    mut nav: ScreenNav,
+    mut toasts: EventWriter<Toast>,
+    mut next_state: ResMut<NextState<Overlay>>,
+    locale: Res<Locale>,
+) {
+    let Some(ResponsePicked(choice)) = picked.read().last().copied() else {
+        return;
+    };
+    let Some(npc) = current_npc(&catalog, &runner) else {
+        next_state.set(Overlay::None);
+        return;
+    };
+    let Some((node_id, index)) = runner.node.clone().zip(choice) else {
+        next_state.set(Overlay::None);
+        return;
+    };
+    let Some(response) = npc
+        .dialogue
+        .nodes
+        .get(&node_id)
+        .and_then(|node| node.responses.get(index))
+    else {
+        next_state.set(Overlay::None);
+        return;
+    };
+
+    let mut choice = Choice::new(&npc.name, &response.text).notable(response.notable.as_deref());
+    if let Some(effect) = response.effect
+        && memory.grant(&npc.id, &node_id, index)
+    {
+        let message = match effect {
+            Effect::RentTip => {
+                let days = schedule.hours_until_due(&clock).div_ceil(HOURS_PER_DAY);
+                format!(
+                    "Tip: rent is due in {} days",
+                    format::format_int(days.into(), *locale)
+                )
+            }
+            Effect::Discount(percent) => {
+                discount.0 = Some(percent);
+                format!("{} gave you {percent}% off your next purchase", npc.name)
+            }
+            // A run played on past its win is welcomed all the same, but not won again.
+            Effect::InnerCircle => {
+                if free_play.0.is_none() {
+                    *ending = Ending::InnerCircle;
+                    nav.set_app(AppState::Win, TransitionCause::WinCondition);
+                }
+                format!("{} welcomed you into the inner circle", npc.name)
+            }
+        };
+        choice = choice.with_outcome(&message);
+        toasts.write(Toast::new(message));
+    }
+    choices.record(clock.day, choice);
+
+    match &response.next {
+        Some(next) => runner.node = Some(next.clone()),
+        None => {
+            memory.exhausted.insert(npc.id.clone());
+            next_state.set(Overlay::None);
+        }
+    }
+}
+
+fn response_colors(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ResponseChoice>),
+    >,
+) {
+    for (interaction, mut background) in &mut buttons {
+        background.0 = match interaction {
+            Interaction::None => DARK,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
@@ -0,0 +1,62 @@
+//! Slow ambient movement for decorative UI, like clouds crossing a backdrop. A node with
+//! [`Drift`] moves its `left` and `top` by the velocity and turns back at the bounds.
+//! Nothing drifts with reduced motion.
+
+use bevy::prelude::*;
+
+use crate::settings::ReducedMotion;
+use crate::timers::RealUiTime;
+
+/// Moves an absolutely positioned node back and forth inside `bounds`, in pixels from
+/// its parent's top left corner.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Drift {
+    /// Pixels per second.
+    pub velocity: Vec2,
+    pub bounds: Rect,
+}
+
+impl Drift {
+    pub fn new(velocity: Vec2, bounds: Rect) -> Self {
+        Drift { velocity, bounds }
+    }
+
+    /// Where a drifting node starts: the middle of its bounds.
+    pub fn start(&self) -> (Val, Val) {
+        let center = self.bounds.center();
+        (Val::Px(center.x), Val::Px(center.y))
+    }
+}
+
+pub fn drift_plugin(app: &mut App) {
+    app.add_systems(Update, drift.run_if(resource_equals(ReducedMotion(false))));
+}
+
+fn drift(time: RealUiTime, mut nodes: Query<(&mut Drift, &mut Node)>) {
+    for (mut drift, mut node) in &mut nodes {
+        let current = Vec2::new(px(node.left), px(node.top));
+        let mut next = current + drift.velocity * time.delta_secs();
+        let (min, max) = (drift.bounds.min, drift.bounds.max);
+        // Turn back at an edge, whichever way the last step went.
+        if next.x <= min.x {
+            drift.velocity.x = drift.velocity.x.abs();
+        } else if next.x >= max.x {
+            drift.velocity.x = -drift.velocity.x.abs();
+        }
+        if next.y <= min.y {
+            drift.velocity.y = drift.velocity.y.abs();
+        } else if next.y >= max.y {
+            drift.velocity.y = -drift.velocity.y.abs();
+        }
+        next = next.clamp(min, max);
+        node.left = Val::Px(next.x);
+        node.top = Val::Px(next.y);
+    }
+}
+
+fn px(value: Val) -> f32 {
+    match value {
+        Val::Px(px) => px,
+        _ => 0.0,
+    }
+}
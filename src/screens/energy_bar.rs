@@ -0,0 +1,166 @@
+//! The player's energy as a bar on the game screen, with their buffs beside it. Each buff
+//! is a small icon ringed with ticks that go out one by one as its time runs down.
+
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+
+use super::area_theme::{AccentText, AccentTint};
+use super::clock_dial::spinner;
+use super::hud::{HudOrder, HudRoot, HudSlot};
+use super::text_backing::TextBacking;
+use super::tooltip::Tooltip;
+use crate::fonts::ui_font;
+use crate::palette::{BLUE, CORAL, DARK, DARKER, LIGHT, YELLOW};
+use crate::sim::{
+    buffs::{Buff, BuffKind, Buffs},
+    clock::Clock,
+    needs::{Energy, MAX_ENERGY},
+};
+use crate::{AppState, despawn_screen, track_screen};
+
+const ENERGY_FONT_SIZE: f32 = 16.0;
+const BAR_WIDTH: f32 = 120.0;
+const BAR_HEIGHT: f32 = 10.0;
+const BUFF_ICON_SIZE: f32 = 24.0;
+const RING_TICKS: u32 = 8;
+const RING_TICK_LENGTH: f32 = 5.0;
+
+#[derive(Component)]
+struct EnergyBar;
+
+#[derive(Component)]
+struct EnergyFill;
+
+#[derive(Component)]
+struct BuffIcons;
+
+pub fn energy_bar_plugin(app: &mut App) {
+    track_screen::<EnergyBar>(app, AppState::Game);
+    app.add_systems(OnEnter(AppState::Game), spawn_energy_bar)
+        .add_systems(OnExit(AppState::Game), despawn_screen::<EnergyBar>)
+        .add_systems(
+            Update,
+            (
+                update_energy_bar.run_if(resource_changed::<Energy>),
+                render_buffs.run_if(resource_changed::<Buffs>.or(resource_changed::<Clock>)),
+            )
+                .run_if(in_state(AppState::Game)),
+        );
+}
+
+fn spawn_energy_bar(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.spawn((
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(8.0),
+            ..default()
+        },
+        EnergyBar,
+        HudRoot,
+        HudSlot::TopLeft,
+        HudOrder(1),
+        children![
+            (
+                Text::new("Energy"),
+                TextFont {
+                    font: ui_font(&assets),
+                    font_size: ENERGY_FONT_SIZE,
+                    ..default()
+                },
+                TextBacking::default(),
+                AccentText(LIGHT),
+            ),
+            (
+                Node {
+                    width: Val::Px(BAR_WIDTH),
+                    height: Val::Px(BAR_HEIGHT),
+                    ..default()
+                },
+                AccentTint::chrome(DARKER),
+                Tooltip::new("Work takes energy. A night's sleep fills it up"),
+                children![(
+                    Node {
+                        width: Val::Percent(100.0),
+                        ..default()
+                    },
+                    AccentTint::fill(YELLOW),
+                    EnergyFill,
+                )],
+            ),
+            (
+                Node {
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                },
+                BuffIcons,
+            ),
+        ],
+    ));
+}
+
+fn update_energy_bar(energy: Res<Energy>, mut fill: Query<&mut Node, With<EnergyFill>>) {
+    let Ok(mut fill) = fill.single_mut() else {
+        return;
+    };
+    fill.width = Val::Percent(energy.0 as f32 / MAX_ENERGY as f32 * 100.0);
+}
+
+fn render_buffs(
+    mut commands: Commands,
+    buffs: Res<Buffs>,
+    clock: Res<Clock>,
+    icons: Query<Entity, With<BuffIcons>>,
+) {
+    let Ok(icons) = icons.single() else {
+        return;
+    };
+    let now = clock.total_hours();
+    commands
+        .entity(icons)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            for buff in buffs.active(now) {
+                parent.spawn(buff_icon(buff, now));
+            }
+        });
+}
+
+fn buff_icon(buff: &Buff, now: u32) -> impl Bundle {
+    let lit = (buff.fraction_left(now) * RING_TICKS as f32).ceil() as u32;
+    let color = match buff.kind {
+        BuffKind::Caffeine => CORAL,
+        BuffKind::ShiftEnergy => BLUE,
+    };
+    (
+        Node {
+            width: Val::Px(BUFF_ICON_SIZE),
+            height: Val::Px(BUFF_ICON_SIZE),
+            ..default()
+        },
+        BorderRadius::MAX,
+        BackgroundColor(color),
+        Tooltip::titled(
+            buff.kind.name(),
+            format!(
+                "{}. {}h left",
+                buff.kind.describe(buff.magnitude),
+                buff.hours_left(now)
+            ),
+        ),
+        Children::spawn(SpawnIter((0..RING_TICKS).map(move |tick| {
+            (
+                spinner(tick as f32 / RING_TICKS as f32 * std::f32::consts::TAU),
+                children![(
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(50.0),
+                        margin: UiRect::left(Val::Px(-1.0)),
+                        width: Val::Px(2.0),
+                        height: Val::Px(RING_TICK_LENGTH),
+                        ..default()
+                    },
+                    BackgroundColor(if tick < lit { LIGHT } else { DARK }),
+                )],
+            )
+        }))),
+    )
+}
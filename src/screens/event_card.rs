@@ -0,0 +1,239 @@
+//! The event card on screen, see [`crate::sim::event_cards`]. Picking a button answers the
+//! card and brings up the next one in the queue, until it's empty.
+
+use bevy::prelude::*;
+
+use super::button_press::ButtonPresses;
+use super::overlay::{MODAL_Z_INDEX, Overlay, no_modal_open};
+use crate::fonts::ui_font;
+use crate::palette::{BLUE, DARK, DARKER, LIGHT, YELLOW};
+use crate::sim::event_cards::{CardChosen, CardId, EventCardQueue};
+use crate::{despawn_screen, track_screen};
+
+const TITLE_FONT_SIZE: f32 = 22.0;
+const BODY_FONT_SIZE: f32 = 16.0;
+const CARD_WIDTH: f32 = 420.0;
+
+#[derive(Component)]
+struct EventCardOverlay;
+
+/// The card this overlay shows.
+#[derive(Component)]
+struct ShownCard(CardId);
+
+#[derive(Component)]
+struct CardButton(usize);
+
+/// Sent when the player picks a button on the card on screen, by its index in
+/// [`crate::sim::event_cards::CardRequest::buttons`].
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CardAnswered(pub usize);
+
+pub fn event_card_plugin(app: &mut App) {
+    track_screen::<EventCardOverlay>(app, Overlay::EventCard);
+    app.add_event::<CardAnswered>()
+        .add_systems(
+            Update,
+            open_event_card.run_if(
+                in_state(Overlay::None)
+                    .and(no_modal_open)
+                    .and(cards_waiting),
+            ),
+        )
+        .add_systems(OnEnter(Overlay::EventCard), spawn_card)
+        .add_systems(
+            OnExit(Overlay::EventCard),
+            despawn_screen::<EventCardOverlay>,
+        )
+        .add_systems(
+            Update,
+            ((press_card_button, answer_card).chain(), card_button_colors)
+                .run_if(in_state(Overlay::EventCard)),
+        );
+}
+
+fn cards_waiting(queue: Res<EventCardQueue>) -> bool {
+    !queue.is_empty()
+}
+
+fn open_event_card(mut next_state: ResMut<NextState<Overlay>>) {
+    next_state.set(Overlay::EventCard);
+}
+
+fn spawn_card(mut commands: Commands, queue: Res<EventCardQueue>, assets: Res<AssetServer>) {
+    let Some((id, card)) = queue.front() else {
+        return;
+    };
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: BODY_FONT_SIZE,
+        ..default()
+    };
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            GlobalZIndex(MODAL_Z_INDEX),
+            EventCardOverlay,
+            ShownCard(id),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(CARD_WIDTH),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(12.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    BackgroundColor(DARKER),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((
+                        Text::new(card.title.clone()),
+                        TextFont {
+                            font_size: TITLE_FONT_SIZE,
+                            ..font.clone()
+                        },
+                        TextColor(YELLOW),
+                    ));
+                    panel.spawn((Text::new(card.body.clone()), font.clone(), TextColor(LIGHT)));
+                    panel
+                        .spawn(Node {
+                            column_gap: Val::Px(8.0),
+                            justify_content: JustifyContent::FlexEnd,
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            for (index, label) in card.buttons.iter().enumerate() {
+                                row.spawn((
+                                    Button,
+                                    Node {
+                                        padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(DARK),
+                                    CardButton(index),
+                                    children![(
+                                        Text::new(label.clone()),
+                                        font.clone(),
+                                        TextColor(LIGHT)
+                                    )],
+                                ));
+                            }
+                        });
+                });
+        });
+}
+
+fn press_card_button(
+    mut presses: ButtonPresses<&CardButton>,
+    mut answers: EventWriter<CardAnswered>,
+) {
+    if let Some(CardButton(button)) = presses.read().last() {
+        answers.write(CardAnswered(*button));
+    }
+}
+
+// The next card takes this one's place without closing the overlay, so the simulation
+// stays paused until the last card is answered.
+fn answer_card(
+    mut commands: Commands,
+    mut answers: EventReader<CardAnswered>,
+    shown: Query<(Entity, &ShownCard)>,
+    mut queue: ResMut<EventCardQueue>,
+    mut chosen: EventWriter<CardChosen>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    let Some(CardAnswered(button)) = answers.read().next().copied() else {
+        return;
+    };
+    for (entity, ShownCard(card)) in &shown {
+        queue.remove(*card);
+        chosen.write(CardChosen {
+            card: *card,
+            button,
+        });
+        commands.entity(entity).despawn();
+    }
+    if queue.is_empty() {
+        next_state.set(Overlay::None);
+    } else {
+        commands.run_system_cached(spawn_card);
+    }
+}
+
+fn card_button_colors(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<CardButton>),
+    >,
+) {
+    for (interaction, mut background) in &mut buttons {
+        background.0 = match interaction {
+            Interaction::None => DARK,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::event_cards::CardRequest;
+    use crate::testing::TestGame;
+
+    fn shown(app: &mut App) -> Vec<CardId> {
+        let mut shown = app.world_mut().query::<&ShownCard>();
+        shown.iter(app.world()).map(|card| card.0).collect()
+    }
+
+    #[test]
+    fn cards_queued_together_are_shown_in_turn_and_answered_on_their_own() {
+        const PROFILE: &str = "Event cards";
+        let mut app = TestGame::in_game(PROFILE);
+        // Only the cards pushed below come up.
+        app.world_mut().resource_mut::<Time<Virtual>>().pause();
+
+        let mut queue = app.world_mut().resource_mut::<EventCardQueue>();
+        let first = queue.push(CardRequest::new("First", "").with_buttons(["a", "b"]));
+        let urgent = queue.push(
+            CardRequest::new("Urgent", "")
+                .with_buttons(["a", "b", "c"])
+                .with_priority(1),
+        );
+        let last = queue.push(CardRequest::new("Last", "").with_buttons(["a", "b"]));
+        let mut chosen = app.world().resource::<Events<CardChosen>>().get_cursor();
+        app.update();
+        app.update();
+
+        for (card, button) in [(urgent, 2), (first, 1), (last, 0)] {
+            assert_eq!(shown(&mut app), [card]);
+            assert_eq!(
+                *app.world().resource::<State<Overlay>>().get(),
+                Overlay::EventCard
+            );
+            app.world_mut().send_event(CardAnswered(button));
+            app.update();
+            let events = app.world().resource::<Events<CardChosen>>();
+            assert_eq!(
+                chosen.read(events).copied().collect::<Vec<_>>(),
+                [CardChosen { card, button }]
+            );
+            app.update();
+        }
+        assert!(shown(&mut app).is_empty());
+        assert!(app.world().resource::<EventCardQueue>().is_empty());
+        assert_eq!(
+            *app.world().resource::<State<Overlay>>().get(),
+            Overlay::None
+        );
+    }
+}
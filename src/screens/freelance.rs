@@ -0,0 +1,354 @@
+//! The freelance gig: a quick timing mini-game played over the game screen for some
+//! evening income. A marker sweeps across a bar and the player stops it as close to the
+//! middle of the highlighted zone as they can, three times. The clock stands still while
+//! it's open.
+
+use bevy::prelude::*;
+
+use super::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use super::overlay::{MODAL_Z_INDEX, Overlay};
+use crate::fonts::ui_font;
+use crate::format;
+use crate::palette::{BLUE, DARKER, LIGHT, YELLOW};
+use crate::settings::Locale;
+use crate::sim::{
+    clock::{Clock, HOURS_PER_DAY},
+    economy::Money,
+    ledger::{Category, Transaction},
+    needs::Energy,
+    rng::GameRng,
+};
+use crate::timers::RealUiTime;
+use crate::toast::Toast;
+use crate::{despawn_screen, track_screen};
+
+pub const GIG_HOURS: u32 = 3;
+pub const GIG_ENERGY: u32 = 25;
+const GIG_ROUNDS: usize = 3;
+/// Pay for a perfect round.
+const ROUND_PAY: u32 = 40;
+/// Half the width of the highlighted zone, as a fraction of the bar.
+const ZONE_HALF_WIDTH: f32 = 0.08;
+/// Full left-right-left sweeps of the marker per second.
+const SWEEPS_PER_SECOND: f32 = 0.6;
+const BAR_WIDTH: f32 = 400.0;
+const BAR_HEIGHT: f32 = 28.0;
+const MARKER_WIDTH: f32 = 4.0;
+
+#[derive(Resource)]
+struct FreelanceGig {
+    /// Seconds since the current round started.
+    elapsed: f32,
+    zone_center: f32,
+    accuracies: Vec<f32>,
+}
+
+#[derive(Component)]
+struct FreelanceOverlay;
+
+#[derive(Component)]
+struct HitZone;
+
+#[derive(Component)]
+struct Marker;
+
+#[derive(Component)]
+struct RoundText;
+
+/// Sent when the player stops the marker, with where it was on the bar, see
+/// [`marker_position`].
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct MarkerStopped(pub f32);
+
+/// Where the marker is after `elapsed` seconds, from 0 (left end) to 1 (right end).
+pub fn marker_position(elapsed: f32) -> f32 {
+    let phase = (elapsed * SWEEPS_PER_SECOND).fract();
+    1.0 - (2.0 * phase - 1.0).abs()
+}
+
+/// How well a stop at `marker` hit the zone: 1 dead centre, falling to 0 at its edges
+/// and beyond.
+pub fn round_accuracy(marker: f32, zone_center: f32) -> f32 {
+    (1.0 - (marker - zone_center).abs() / ZONE_HALF_WIDTH).max(0.0)
+}
+
+pub fn gig_payout(accuracies: &[f32]) -> u32 {
+    accuracies
+        .iter()
+        .map(|accuracy| (ROUND_PAY as f32 * accuracy).round() as u32)
+        .sum()
+}
+
+pub fn freelance_plugin(app: &mut App) {
+    track_screen::<FreelanceOverlay>(app, Overlay::Freelance);
+    app.add_event::<MarkerStopped>()
+        .add_systems(Update, offer_freelance.in_set(ActionSet::Offer))
+        .add_systems(Update, start_freelance.run_if(in_state(Overlay::None)))
+        .add_systems(OnEnter(Overlay::Freelance), setup_freelance)
+        .add_systems(
+            OnExit(Overlay::Freelance),
+            (despawn_screen::<FreelanceOverlay>, end_freelance),
+        )
+        .add_systems(
+            Update,
+            (sweep_marker, stop_marker, score_round, cancel_freelance)
+                .chain()
+                .run_if(in_state(Overlay::Freelance)),
+        );
+}
+
+/// The gig has to end before midnight so the skipped hours never hide a day rollover.
+fn gig_fits_today(clock: &Clock) -> bool {
+    clock.hour + GIG_HOURS < HOURS_PER_DAY
+}
+
+fn offer_freelance(clock: Res<Clock>, energy: Res<Energy>, mut actions: ResMut<AvailableActions>) {
+    if !gig_fits_today(&clock) {
+        return;
+    }
+    let label = format!("Freelance {GIG_HOURS}h: -{GIG_ENERGY} energy");
+    if energy.0 >= GIG_ENERGY {
+        actions.offer_labeled(GameAction::Freelance, label);
+    } else {
+        actions.offer_blocked(GameAction::Freelance, label, "Too tired");
+    }
+}
+
+fn start_freelance(
+    mut pressed: EventReader<ActionPressed>,
+    clock: Res<Clock>,
+    energy: Res<Energy>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        if *action == GameAction::Freelance && gig_fits_today(&clock) && energy.0 >= GIG_ENERGY {
+            next_state.set(Overlay::Freelance);
+        }
+    }
+}
+
+fn random_zone_center(rng: &mut GameRng) -> f32 {
+    ZONE_HALF_WIDTH + rng.next_f32() * (1.0 - 2.0 * ZONE_HALF_WIDTH)
+}
+
+fn setup_freelance(mut commands: Commands, mut rng: ResMut<GameRng>, assets: Res<AssetServer>) {
+    let gig = FreelanceGig {
+        elapsed: 0.0,
+        zone_center: random_zone_center(&mut rng),
+        accuracies: Vec::with_capacity(GIG_ROUNDS),
+    };
+    let font = ui_font(&assets);
+
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(16.0),
+            ..default()
+        },
+        GlobalZIndex(MODAL_Z_INDEX),
+        FreelanceOverlay,
+        children![
+            (
+                Text::new(round_text(&gig)),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(LIGHT),
+                RoundText,
+            ),
+            (
+                Node {
+                    width: Val::Px(BAR_WIDTH),
+                    height: Val::Px(BAR_HEIGHT),
+                    ..default()
+                },
+                BackgroundColor(DARKER),
+                children![
+                    (
+                        Node {
+                            position_type: PositionType::Absolute,
+                            left: zone_left(gig.zone_center),
+                            width: Val::Percent(ZONE_HALF_WIDTH * 200.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(BLUE),
+                        HitZone,
+                    ),
+                    (
+                        Node {
+                            position_type: PositionType::Absolute,
+                            width: Val::Px(MARKER_WIDTH),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(YELLOW),
+                        Marker,
+                    ),
+                ],
+            ),
+            (
+                Text::new("Space or click to stop the marker, Esc to give up"),
+                TextFont {
+                    font,
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(LIGHT),
+            ),
+        ],
+    ));
+    commands.insert_resource(gig);
+}
+
+fn round_text(gig: &FreelanceGig) -> String {
+    format!(
+        "Freelance gig - round {}/{GIG_ROUNDS}",
+        gig.accuracies.len() + 1
+    )
+}
+
+fn zone_left(zone_center: f32) -> Val {
+    Val::Percent((zone_center - ZONE_HALF_WIDTH) * 100.0)
+}
+
+fn sweep_marker(
+    time: RealUiTime,
+    mut gig: ResMut<FreelanceGig>,
+    mut marker: Query<&mut Node, With<Marker>>,
+) {
+    let Ok(mut marker) = marker.single_mut() else {
+        return;
+    };
+    gig.elapsed += time.delta_secs();
+    marker.left = Val::Px(marker_position(gig.elapsed) * (BAR_WIDTH - MARKER_WIDTH));
+}
+
+fn stop_marker(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gig: Res<FreelanceGig>,
+    mut stopped: EventWriter<MarkerStopped>,
+) {
+    if keyboard_input.any_just_pressed([KeyCode::Space, KeyCode::Enter])
+        || mouse_input.just_pressed(MouseButton::Left)
+    {
+        stopped.write(MarkerStopped(marker_position(gig.elapsed)));
+    }
+}
+
+fn score_round(
+    mut stopped: EventReader<MarkerStopped>,
+    mut gig: ResMut<FreelanceGig>,
+    mut rng: ResMut<GameRng>,
+    mut zone: Query<&mut Node, (With<HitZone>, Without<Marker>)>,
+    mut text: Query<&mut Text, With<RoundText>>,
+    mut clock: ResMut<Clock>,
+    mut energy: ResMut<Energy>,
+    mut money: ResMut<Money>,
+    mut toasts: EventWriter<Toast>,
+    mut transactions: EventWriter<Transaction>,
+    mut next_state: ResMut<NextState<Overlay>>,
+    locale: Res<Locale>,
+) {
+    let Ok(mut zone) = zone.single_mut() else {
+        return;
+    };
+    let Ok(mut text) = text.single_mut() else {
+        return;
+    };
+    let Some(MarkerStopped(marker)) = stopped.read().last().copied() else {
+        return;
+    };
+
+    let accuracy = round_accuracy(marker, gig.zone_center);
+    gig.accuracies.push(accuracy);
+    if gig.accuracies.len() < GIG_ROUNDS {
+        gig.elapsed = 0.0;
+        gig.zone_center = random_zone_center(&mut rng);
+        zone.left = zone_left(gig.zone_center);
+        text.0 = round_text(&gig);
+        return;
+    }
+
+    let pay = gig_payout(&gig.accuracies);
+    money.amount += pay;
+    transactions.write(Transaction::Income(Category::Freelance, pay));
+    energy.0 = energy.0.saturating_sub(GIG_ENERGY);
+    for _ in 0..GIG_HOURS {
+        clock.advance();
+    }
+    toasts.write(Toast::new(format!(
+        "Freelance gig done: earned {}",
+        format::money(pay, *locale)
+    )));
+    next_state.set(Overlay::None);
+}
+
+fn cancel_freelance(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut toasts: EventWriter<Toast>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        toasts.write(Toast::new("Gave up on the gig"));
+        next_state.set(Overlay::None);
+    }
+}
+
+fn end_freelance(mut commands: Commands) {
+    commands.remove_resource::<FreelanceGig>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWEEP: f32 = 1.0 / SWEEPS_PER_SECOND;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn the_marker_sweeps_there_and_back() {
+        assert!(close(marker_position(0.0), 0.0));
+        assert!(close(marker_position(SWEEP / 4.0), 0.5));
+        assert!(close(marker_position(SWEEP / 2.0), 1.0));
+        assert!(close(marker_position(SWEEP * 3.0 / 4.0), 0.5));
+        assert!(close(marker_position(SWEEP * 2.0 + SWEEP / 2.0), 1.0));
+        for step in 0..1000 {
+            let position = marker_position(step as f32 * 0.01);
+            assert!((0.0..=1.0).contains(&position), "{position}");
+        }
+    }
+
+    #[test]
+    fn accuracy_falls_off_to_the_zone_edges() {
+        assert_eq!(round_accuracy(0.3, 0.3), 1.0);
+        assert!(close(round_accuracy(0.3 + ZONE_HALF_WIDTH / 2.0, 0.3), 0.5));
+        assert!(close(round_accuracy(0.3 - ZONE_HALF_WIDTH / 2.0, 0.3), 0.5));
+        assert!(close(round_accuracy(0.3 + ZONE_HALF_WIDTH, 0.3), 0.0));
+        assert_eq!(round_accuracy(0.9, 0.3), 0.0);
+        assert_eq!(round_accuracy(0.0, 0.3), 0.0);
+    }
+
+    #[test]
+    fn each_round_pays_its_share_of_the_round_pay() {
+        assert_eq!(
+            gig_payout(&[1.0; GIG_ROUNDS]),
+            ROUND_PAY * GIG_ROUNDS as u32
+        );
+        assert_eq!(gig_payout(&[0.0; GIG_ROUNDS]), 0);
+        assert_eq!(gig_payout(&[]), 0);
+        // Rounded round by round: 40 + 20 + 10.4.
+        assert_eq!(gig_payout(&[1.0, 0.5, 0.26]), 70);
+    }
+}
@@ -1,98 +1,50 @@
-use crate::{AppState, despawn_screen, palette::LIGHT};
+use super::background_animation::BackgroundAnimation;
+use super::clock_dial::clock_dial;
+use super::hud::{HudOrder, HudRoot, HudSlot};
+use super::singleton::{Unique, track_singleton};
+use super::text_backing::TextBacking;
+use super::tooltip::Tooltip;
+use crate::sim::{
+    bank::Bank,
+    bills::Bills,
+    calendar::Calendar,
+    catalog::{AreaCatalog, AreaCatalogReloaded},
+    clock::{Clock, HOURS_PER_DAY},
+    economy::{Area, CurrentArea, FreePlay, HomeArea, Money},
+    landlord::{RentIncreases, RentTerms},
+    ledger::Ledger,
+    new_game_plus::NewGamePlus,
+    reset_run,
+    roommate::Roommate,
+    weather::{Weather, WeatherKind},
+};
+use crate::{
+    AppState, despawn_screen,
+    fonts::ui_font,
+    format,
+    palette::{BLUE, CORAL, DARK, LIGHT, YELLOW},
+    settings::{ClockStyle, LayoutScale, Locale},
+    track_screen,
+};
 use bevy::prelude::*;
 
-use crate::palette::DARKER;
-
 const UI_TEXT_FONT_SIZE: f32 = 50.0;
-const UI_PADDING: Val = Val::Percent(2.0);
 const TEXT_COLOR: Color = LIGHT;
+const RENT_BANNER_FONT_SIZE: f32 = 20.0;
+/// How long before a bill is due the banner starts warning about it.
+const RENT_WARNING_HOURS: u32 = 24;
+const WEATHER_FONT_SIZE: f32 = 20.0;
+const WEATHER_ICON_SIZE: f32 = 16.0;
 
 #[derive(Component)]
 struct GameScreen;
 
-#[derive(Clone, Default, Copy, Debug, Eq, Hash, PartialEq)]
-enum Area {
-    #[default]
-    DeadbeatArea,
-    Condo,
-    LuxuryCondo,
-    Mansion,
-    BusinessDistrict,
-    RestrictedArea, // Example of non-rentable area
-}
-
-impl Area {
-    fn get_rent_cost(&self) -> Option<f32> {
-        match self {
-            Area::DeadbeatArea => Some(250.0),
-            Area::Condo => Some(1000.0),
-            Area::LuxuryCondo => Some(2500.0),
-            Area::Mansion => Some(10000.0),
-            Area::BusinessDistrict => Some(500.0),
-            _ => None, // Non-rentable
-        }
-    }
-}
-
-impl Area {
-    fn get_image(&self) -> &str {
-        match self {
-            Area::DeadbeatArea => "images/locations/deadbeat.png",
-            Area::Condo => "images/locations/condo.png",
-            Area::LuxuryCondo => "images/locations/luxury.png",
-            Area::BusinessDistrict => "images/locations/business.png",
-            Area::Mansion => "images/locations/mansion.png",
-            _ => panic!("Non-rentable area"),
-        }
-    }
-}
-
-#[derive(Resource)]
-struct HomeArea {
-    location: Area,
-    rent: f32,
-}
-
-impl Default for HomeArea {
-    fn default() -> Self {
-        HomeArea {
-            rent: Area::DeadbeatArea.get_rent_cost().unwrap(),
-            location: Area::DeadbeatArea,
-        }
-    }
-}
-
-#[derive(Resource)]
-struct CurrentArea(Area);
-
-impl Default for CurrentArea {
-    fn default() -> Self {
-        CurrentArea(Area::DeadbeatArea)
-    }
-}
-
-#[derive(Resource, Default)]
-struct WorkArea {
-    location: Option<Area>,
-    rent: u32,
-}
-
-#[derive(Resource, Default)]
-struct Money {
-    amount: u32,
-}
-
-#[derive(Resource, Default)]
-struct Time {
-    day: u32,
-    hour: u32,
-}
-
 #[derive(Component)]
 struct Background;
 
+/// The cash on hand in the HUD: a label and the amount, as its first span.
 #[derive(Component)]
-struct MoneyUi;
+pub struct MoneyUi;
 
 #[derive(Component)]
 struct TimeUi;
@@ -100,108 +52,422 @@ struct TimeUi;
 #[derive(Component)]
 struct RentUi;
 
+#[derive(Component)]
+struct WeatherIcon;
+
+#[derive(Component)]
+struct WeatherText;
+
+#[derive(Component)]
+struct RentBanner;
+
 pub fn game_plugin(app: &mut App) {
-    app.init_resource::<HomeArea>()
-        .init_resource::<CurrentArea>()
-        .init_resource::<WorkArea>()
-        .init_resource::<Money>()
-        .init_resource::<Time>()
-        .add_systems(OnEnter(AppState::Game), setup_game)
+    track_screen::<GameScreen>(app, AppState::Game);
+    track_singleton::<Background>(app);
+    track_singleton::<MoneyUi>(app);
+    track_singleton::<TimeUi>(app);
+    track_singleton::<RentUi>(app);
+    app.add_systems(OnEnter(AppState::Game), setup_game.after(reset_run))
         .add_systems(OnExit(AppState::Game), despawn_screen::<GameScreen>)
-        .add_systems(Update, update_ui.run_if(in_state(AppState::Game)));
+        .add_systems(
+            Update,
+            (
+                update_ui,
+                update_rent_banner,
+                update_weather.run_if(resource_changed::<Weather>),
+                refresh_background
+                    .run_if(on_event::<AreaCatalogReloaded>.or(resource_changed::<CurrentArea>)),
+            )
+                .run_if(in_state(AppState::Game)),
+        );
 }
 
-fn setup_game(mut commands: Commands, area: Res<CurrentArea>, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("fonts/PressStart2P-Regular.ttf");
-    commands.spawn((
+/// The area's background, animated if the area data has an animation for it.
+fn background_image(
+    area: Area,
+    catalog: &AreaCatalog,
+    asset_server: &AssetServer,
+    layouts: &mut Assets<TextureAtlasLayout>,
+) -> (ImageNode, Option<BackgroundAnimation>) {
+    if let Some(animation) = catalog.animation(area)
+        && let Some((image, animation)) =
+            BackgroundAnimation::load(animation, asset_server, layouts)
+    {
+        return (image, Some(animation));
+    }
+    let image = match catalog.image(area) {
+        Some(path) => ImageNode::new(asset_server.load(path)),
+        None => {
+            warn!("{area:?} has no background image");
+            ImageNode::default()
+        }
+    };
+    (image, None)
+}
+
+fn setup_game(
+    mut commands: Commands,
+    area: Res<CurrentArea>,
+    catalog: Res<AreaCatalog>,
+    scale: Res<LayoutScale>,
+    locale: Res<Locale>,
+    free_play: Res<FreePlay>,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let direction = locale.direction();
+    let font = ui_font(&asset_server);
+    let label_font = TextFont {
+        font_size: scale.size(UI_TEXT_FONT_SIZE),
+        font: font.clone(),
+        ..default()
+    };
+    let shadow = TextShadow {
+        color: Color::BLACK,
+        offset: Vec2 { x: 3.0, y: 3.0 },
+    };
+    let small_shadow = TextShadow {
+        color: Color::BLACK,
+        offset: Vec2 { x: 2.0, y: 2.0 },
+    };
+    // A label followed by a span the update systems write to.
+    let label = |text: &str| {
+        (
+            Text::new(text),
+            label_font.clone(),
+            TextBacking(shadow),
+            TextColor(TEXT_COLOR),
+            children![(
+                TextSpan::default(),
+                label_font.clone(),
+                TextColor(TEXT_COLOR)
+            )],
+        )
+    };
+
+    let (image, animation) = background_image(area.0, &catalog, &asset_server, &mut layouts);
+    let mut background = commands.spawn((
         Node {
             width: Val::Percent(100.0),
             height: Val::Percent(100.0),
-            align_items: AlignItems::Center,
-            justify_content: JustifyContent::Center,
             ..default()
         },
         GameScreen,
         Background,
-        ImageNode::new(asset_server.load(area.0.get_image())),
+        image,
+    ));
+    if let Some(animation) = animation {
+        background.insert(animation);
+    }
+    commands.spawn((
+        label("Money: "),
+        MoneyUi,
+        Tooltip::new("Cash on hand. Savings at the bank aren't counted, and are safe from thieves"),
+        GameScreen,
+        HudRoot,
+        HudSlot::TopLeft,
+    ));
+    commands.spawn((
+        label("Rent: "),
+        RentUi,
+        GameScreen,
+        HudRoot,
+        HudSlot::TopRight,
+    ));
+    let mut clock_row = commands.spawn((
+        Node {
+            flex_direction: direction.row(),
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(12.0),
+            ..default()
+        },
+        GameScreen,
+        HudRoot,
+        HudSlot::TopCenter,
+        children![clock_dial(), (label("Day "), TimeUi)],
+    ));
+    // Free play only starts from the win screen, so the tag never changes in the game.
+    if free_play.0.is_some() {
+        clock_row.with_child((
+            Text::new("Free play"),
+            TextFont {
+                font_size: WEATHER_FONT_SIZE,
+                font: font.clone(),
+                ..default()
+            },
+            TextBacking(small_shadow),
+            TextColor(YELLOW),
+            Tooltip::new("The run is already won. Play on for as long as the money lasts"),
+        ));
+    }
+    commands.spawn((
+        Node {
+            flex_direction: direction.row(),
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(8.0),
+            ..default()
+        },
+        GameScreen,
+        HudRoot,
+        HudSlot::TopCenter,
+        HudOrder(1),
         children![
             (
-                Text::new("Money: "),
-                TextFont {
-                    font_size: UI_TEXT_FONT_SIZE,
-                    font: font.clone(),
-                    ..default()
-                },
-                TextShadow {
-                    color: Color::BLACK,
-                    offset: Vec2 { x: 3.0, y: 3.0 },
-                },
-                TextColor(TEXT_COLOR),
-                MoneyUi,
                 Node {
-                    position_type: PositionType::Absolute,
-                    top: UI_PADDING,
-                    left: UI_PADDING,
+                    width: Val::Px(WEATHER_ICON_SIZE),
+                    height: Val::Px(WEATHER_ICON_SIZE),
                     ..default()
                 },
-                children![(
-                    TextSpan::default(),
-                    TextFont {
-                        font_size: UI_TEXT_FONT_SIZE,
-                        font: font.clone(),
-                        ..default()
-                    },
-                    TextShadow {
-                        color: Color::BLACK,
-                        offset: Vec2 { x: 3.0, y: 3.0 },
-                    },
-                    TextColor(TEXT_COLOR),
-                )],
+                BorderRadius::MAX,
+                BackgroundColor(YELLOW),
+                WeatherIcon,
             ),
             (
-                Text::new("Rent: "),
+                Text::default(),
                 TextFont {
-                    font_size: UI_TEXT_FONT_SIZE,
+                    font_size: WEATHER_FONT_SIZE,
                     font: font.clone(),
                     ..default()
                 },
+                TextBacking(small_shadow),
                 TextColor(TEXT_COLOR),
-                RentUi,
-                TextShadow {
-                    color: Color::BLACK,
-                    offset: Vec2 { x: 3.0, y: 3.0 },
-                },
-                Node {
-                    position_type: PositionType::Absolute,
-                    top: UI_PADDING,
-                    right: UI_PADDING,
-                    ..default()
-                },
-                children![(
-                    TextSpan::default(),
-                    TextFont {
-                        font_size: UI_TEXT_FONT_SIZE,
-                        font: font.clone(),
-                        ..default()
-                    },
-                    TextShadow {
-                        color: Color::BLACK,
-                        offset: Vec2 { x: 3.0, y: 3.0 },
-                    },
-                    TextColor(TEXT_COLOR),
-                )],
-            )
+                WeatherText,
+            ),
         ],
     ));
+    commands.spawn((
+        Text::default(),
+        TextFont {
+            font_size: RENT_BANNER_FONT_SIZE,
+            font,
+            ..default()
+        },
+        TextBacking(small_shadow),
+        TextColor(YELLOW),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Visibility::Hidden,
+        RentBanner,
+        GameScreen,
+        HudSlot::TopCenter,
+        HudOrder(2),
+    ));
 }
 
 fn update_ui(
     money: Res<Money>,
-    money_root: Single<Entity, With<MoneyUi>>,
+    bank: Res<Bank>,
+    mut money_root: Unique<Entity, With<MoneyUi>>,
     mut writer: TextUiWriter,
     home: Res<HomeArea>,
-    rent_root: Single<Entity, With<RentUi>>,
+    increases: Res<RentIncreases>,
+    roommate: Res<Roommate>,
+    terms: Res<RentTerms>,
+    (ledger, bills): (Res<Ledger>, Res<Bills>),
+    mut rent_root: Unique<Entity, With<RentUi>>,
+    clock: Res<Clock>,
+    mut time_root: Unique<Entity, With<TimeUi>>,
+    calendar: Res<Calendar>,
+    clock_style: Res<ClockStyle>,
+    new_game_plus: Res<NewGamePlus>,
+    locale: Res<Locale>,
 ) {
-    *writer.text(*money_root, 1) = money.amount.to_string();
-    *writer.text(*rent_root, 1) = home.rent.to_string();
+    if let Some(money_root) = money_root.get() {
+        let cash = format::money(money.amount, *locale);
+        *writer.text(money_root, 1) = if bank.savings > 0 {
+            format!("{cash} (+{} banked)", format::money(bank.savings, *locale))
+        } else {
+            cash
+        };
+    }
+    if let Some(rent_root) = rent_root.get() {
+        let rent =
+            roommate.rent_share(terms.rent(increases.listed_rent(&home), ledger.current.month));
+        let rent = format::money(rent, *locale);
+        *writer.text(rent_root, 1) = if roommate.is_splitting() {
+            format!("{rent} (split)")
+        } else {
+            rent
+        };
+        if let Some((percent, day)) = increases.announced(&home) {
+            let raised = RentIncreases {
+                percent,
+                ..increases.clone()
+            };
+            let rent =
+                roommate.rent_share(terms.rent(raised.listed_rent(&home), ledger.current.month));
+            *writer.text(rent_root, 1) +=
+                &format!(", {} from day {day}", format::money(rent, *locale));
+        }
+        if let Some(bill) = bills.next() {
+            *writer.text(rent_root, 1) += &if bill.is_overdue(clock.day) {
+                ", overdue".to_string()
+            } else {
+                format!(", due day {}", bill.due_day)
+            };
+        }
+    }
+    let Some(time_root) = time_root.get() else {
+        return;
+    };
+    let hour = if clock_style.shows_hour_text() {
+        format!(" {:02}:00", clock.hour)
+    } else {
+        String::new()
+    };
+    *writer.text(time_root, 1) = format!(
+        "{} ({}){hour}, {}",
+        clock.day,
+        clock.weekday().short_name(),
+        calendar.season(clock.day).name
+    );
+    if new_game_plus.cycle > 0 {
+        *writer.text(time_root, 1) += &format!(" NG+{}", new_game_plus.cycle);
+    }
+}
+
+fn update_weather(
+    weather: Res<Weather>,
+    mut icon: Query<&mut BackgroundColor, With<WeatherIcon>>,
+    mut text: Query<&mut Text, With<WeatherText>>,
+) {
+    let Ok(mut icon) = icon.single_mut() else {
+        return;
+    };
+    let Ok(mut text) = text.single_mut() else {
+        return;
+    };
+    icon.0 = match weather.today {
+        WeatherKind::Sunny => YELLOW,
+        WeatherKind::Rain => BLUE,
+        WeatherKind::Storm => DARK,
+    };
+    text.0 = format!(
+        "{} - tomorrow: {}",
+        weather.today.label(),
+        weather.tomorrow.label().to_lowercase()
+    );
+}
+
+fn update_rent_banner(
+    clock: Res<Clock>,
+    bills: Res<Bills>,
+    money: Res<Money>,
+    bank: Res<Bank>,
+    mut banner: Query<(&mut Text, &mut TextColor, &mut Visibility), With<RentBanner>>,
+    locale: Res<Locale>,
+) {
+    let Ok((mut text, mut color, mut visibility)) = banner.single_mut() else {
+        return;
+    };
+    let Some(bill) = bills.next() else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let owed = format::money(bill.owed(), *locale);
+    if bill.is_overdue(clock.day) {
+        *visibility = Visibility::Inherited;
+        text.0 = format!(
+            "Rent of {owed} overdue - evicted on day {}",
+            bill.eviction_day()
+        );
+        color.0 = CORAL;
+        return;
+    }
+    // Bills come out days ahead, and the HUD's rent shows their due day until the last day
+    // before it. A bill is due as its due day starts.
+    let hours_left = (bill.due_day * HOURS_PER_DAY).saturating_sub(clock.total_hours());
+    if hours_left > RENT_WARNING_HOURS {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    *visibility = Visibility::Inherited;
+    let balance = money.amount + bank.savings;
+    let autopay = if bills.autopay { " (autopay)" } else { "" };
+    text.0 = format!(
+        "Rent of {owed} due tomorrow{autopay} - balance {}",
+        format::money(balance, *locale)
+    );
+    color.0 = if balance >= bill.owed() {
+        YELLOW
+    } else {
+        CORAL
+    };
+}
+
+// The old area's animation goes with its frames, and the new one starts from the top.
+fn refresh_background(
+    mut commands: Commands,
+    mut background: Unique<Entity, With<Background>>,
+    area: Res<CurrentArea>,
+    catalog: Res<AreaCatalog>,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let Some(background) = background.get() else {
+        return;
+    };
+    let (image, animation) = background_image(area.0, &catalog, &asset_server, &mut layouts);
+    let mut background = commands.entity(background);
+    background.remove::<BackgroundAnimation>().insert(image);
+    if let Some(animation) = animation {
+        background.insert(animation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{bills::Bill, economy::RentCharge};
+
+    /// The banner at `hour` on `day`, for a bill due on day 3, or `None` while it's hidden.
+    fn banner(day: u32, hour: u32) -> Option<String> {
+        let charge = RentCharge {
+            day: 1,
+            area: Area::DeadbeatArea,
+            gross: 500,
+            modifiers: Vec::new(),
+            net: 500,
+        };
+        let mut world = World::new();
+        world.insert_resource(Clock { day, hour });
+        world.insert_resource(Bills {
+            outstanding: vec![Bill::new(charge, 1, 3)],
+            ..default()
+        });
+        world.insert_resource(Money { amount: 0 });
+        world.init_resource::<Bank>();
+        world.init_resource::<Locale>();
+        let banner = world
+            .spawn((
+                Text::default(),
+                TextColor::default(),
+                Visibility::Hidden,
+                RentBanner,
+            ))
+            .id();
+        world.run_system_cached(update_rent_banner).unwrap();
+        (world.get::<Visibility>(banner) != Some(&Visibility::Hidden))
+            .then(|| world.get::<Text>(banner).unwrap().0.clone())
+    }
+
+    #[test]
+    fn the_rent_banner_shows_in_the_last_day_before_the_due_day_and_past_it() {
+        // Issued, but more than a day off.
+        assert_eq!(banner(1, 8), None);
+        assert_eq!(banner(1, 23), None);
+        for hour in [0, 12, 23] {
+            let text = banner(2, hour).unwrap_or_default();
+            assert!(
+                text.ends_with("due tomorrow (autopay) - balance $0"),
+                "{hour}: {text}"
+            );
+        }
+        // Past its due day, well after the bill was issued.
+        for day in [3, 4, 9] {
+            let text = banner(day, 8).unwrap_or_default();
+            assert!(text.contains("overdue - evicted on day 6"), "day {day}");
+        }
+    }
 }
@@ -1,11 +1,85 @@
-use crate::{AppState, TEXT_COLOR, despawn_screen};
+use super::challenge::challenge_result;
+use super::nav::{ScreenNav, TransitionCause};
+use super::report::notable_decisions;
+use crate::format;
+use crate::input_device::{ActiveInputDevice, InputDevice, InputDeviceChanged};
+use crate::palette::{LIGHT, YELLOW};
+use crate::profiles::ProfilePaths;
+use crate::save_file::SaveFiles;
+use crate::settings::Locale;
+use crate::sim::{
+    challenge::{Challenge, ChallengeResult},
+    choices::ChoiceLog,
+    clock::Clock,
+    economy::{FreePlay, GameOverReason},
+    ledger::Wealth,
+    playtime::Playtime,
+    replay::RunSetup,
+    scenario::Scenarios,
+};
+use crate::{AppState, TEXT_COLOR, despawn_screen, track_screen};
 use bevy::prelude::*;
 
 #[derive(Component)]
 struct OnGameOverScreen;
 
+#[derive(Component)]
+struct RestartPrompt;
+
+/// What the screen says about how the run was lost.
+#[derive(Debug, PartialEq)]
+struct Loss {
+    title: &'static str,
+    flavor: &'static str,
+    /// The screen's background.
+    tint: Color,
+    tip: &'static str,
+}
+
+/// For a game over with no [`GameOverReason`] set.
+const GENERIC_LOSS: Loss = Loss {
+    title: "Game Over!",
+    flavor: "The city got the better of you this time.",
+    tint: Color::BLACK,
+    tip: "Tip: the reports show where your money goes each month",
+};
+
+fn loss(reason: Option<GameOverReason>) -> Loss {
+    let Some(reason) = reason else {
+        warn!("Game over without a GameOverReason, showing the generic screen");
+        return GENERIC_LOSS;
+    };
+    match reason {
+        GameOverReason::Evicted => Loss {
+            title: "Evicted!",
+            flavor: "The landlord changed the locks, and your things are out on the curb.",
+            tint: Color::srgb(0.16, 0.04, 0.04),
+            tip: "Tip: autopay takes the rent from your savings too, and a roommate halves it",
+        },
+        GameOverReason::Bankrupt => Loss {
+            title: "Bankrupt!",
+            flavor: "The money ran out, and the bills kept coming.",
+            tint: Color::srgb(0.12, 0.1, 0.02),
+            tip: "Tip: the warning card comes a week before the money runs out, act on it",
+        },
+        GameOverReason::HealthCollapse => Loss {
+            title: "Collapsed!",
+            flavor: "You pushed past what your body could take, and woke up in hospital.",
+            tint: Color::srgb(0.02, 0.08, 0.14),
+            tip: "Tip: a night's sleep refills your energy, so don't work through it",
+        },
+        GameOverReason::Burnout => Loss {
+            title: "Burnt out!",
+            flavor: "Nothing felt worth getting up for any more.",
+            tint: Color::srgb(0.08, 0.08, 0.08),
+            tip: "Tip: spend some of what you earn on what makes you happy",
+        },
+    }
+}
+
 // Plugin definition
 pub fn gameover_plugin(app: &mut App) {
+    track_screen::<OnGameOverScreen>(app, AppState::GameOver);
     app.add_systems(OnEnter(AppState::GameOver), setup_gameover_screen)
         .add_systems(
             OnExit(AppState::GameOver),
@@ -13,11 +87,35 @@ pub fn gameover_plugin(app: &mut App) {
         )
         .add_systems(
             Update,
-            process_commands.run_if(in_state(AppState::GameOver)),
+            (process_commands, update_prompt).run_if(in_state(AppState::GameOver)),
         );
 }
 
-fn setup_gameover_screen(mut commands: Commands) {
+fn setup_gameover_screen(
+    mut commands: Commands,
+    reason: Option<Res<GameOverReason>>,
+    device: Res<ActiveInputDevice>,
+    choices: Res<ChoiceLog>,
+    playtime: Res<Playtime>,
+    challenge: Res<Challenge>,
+    (setup, scenarios): (Res<RunSetup>, Res<Scenarios>),
+    clock: Res<Clock>,
+    free_play: Res<FreePlay>,
+    wealth: Wealth,
+    locale: Res<Locale>,
+    profile: Res<ProfilePaths>,
+    mut files: SaveFiles,
+) {
+    // Going bankrupt in free play doesn't take the win back.
+    let result = ChallengeResult {
+        won: free_play.0.is_some(),
+        days: free_play.0.unwrap_or(clock.day),
+        worth: wealth.net_worth().total(),
+        bankrupt_on: free_play.0.map(|_| clock.day),
+        lost_to: reason.as_deref().copied(),
+        scenario: setup.scenario.clone(),
+    };
+    let loss = loss(reason.as_deref().copied());
     commands.spawn((
         Node {
             width: Val::Percent(100.0),
@@ -27,7 +125,7 @@ fn setup_gameover_screen(mut commands: Commands) {
             ..default()
         },
         OnGameOverScreen,
-        BackgroundColor(Color::BLACK),
+        BackgroundColor(loss.tint),
         children![(
             Node {
                 flex_direction: FlexDirection::Column,
@@ -36,7 +134,7 @@ fn setup_gameover_screen(mut commands: Commands) {
             },
             children![
                 (
-                    Text::new("Game Over!"),
+                    Text::new(loss.title),
                     TextFont {
                         font_size: 67.0,
                         ..default()
@@ -45,27 +143,186 @@ fn setup_gameover_screen(mut commands: Commands) {
                     TextShadow::default()
                 ),
                 (
-                    Text::new("Press any key to restart or esc for the menu."),
+                    Text::new(loss.flavor),
+                    TextFont {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    TextColor(LIGHT.with_alpha(0.8)),
+                    Node {
+                        margin: UiRect::bottom(Val::Px(12.0)),
+                        ..default()
+                    },
+                ),
+                (
+                    Text::new(restart_prompt(device.0)),
                     TextFont {
                         font_size: 33.0,
                         ..default()
                     },
                     TextColor(TEXT_COLOR),
-                    TextShadow::default()
-                )
+                    TextShadow::default(),
+                    RestartPrompt,
+                ),
+                (
+                    Text::new(match free_play.0 {
+                        Some(won) => format!(
+                            "Played {}. Won on day {won}, bankrupt on day {}",
+                            format::playtime(playtime.0),
+                            clock.day
+                        ),
+                        None => format!("Played {}", format::playtime(playtime.0)),
+                    }),
+                    TextFont {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    TextColor(TEXT_COLOR),
+                ),
+                (
+                    Text::new(loss.tip),
+                    TextFont {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    TextColor(YELLOW),
+                    Node {
+                        margin: UiRect::top(Val::Px(12.0)),
+                        ..default()
+                    },
+                ),
+                notable_decisions(&choices),
+                challenge_result(
+                    &challenge, result, &scenarios, *locale, &profile, &mut files,
+                ),
             ],
         )],
     ));
 }
 
-fn process_commands(keyboard_input: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
-        commands.set_state(AppState::Menu);
+fn restart_prompt(device: InputDevice) -> &'static str {
+    device.prompt(
+        "Press any key to restart or esc for the menu.",
+        "Press A to restart or B for the menu.",
+    )
+}
+
+fn update_prompt(
+    mut changed: EventReader<InputDeviceChanged>,
+    mut prompt: Query<&mut Text, With<RestartPrompt>>,
+) {
+    let Ok(mut prompt) = prompt.single_mut() else {
         return;
+    };
+    if let Some(InputDeviceChanged(device)) = changed.read().last() {
+        prompt.0 = restart_prompt(*device).to_string();
     }
+}
 
-    if keyboard_input.get_just_pressed().len() > 0 {
-        commands.set_state(AppState::Game);
+fn process_commands(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut nav: ScreenNav,
+) {
+    let gamepad_pressed = |button| gamepads.iter().any(|gamepad| gamepad.just_pressed(button));
+    if keyboard_input.just_pressed(KeyCode::Escape) || gamepad_pressed(GamepadButton::East) {
+        nav.set_app(AppState::Menu, TransitionCause::Hotkey);
         return;
     }
+
+    if keyboard_input.get_just_pressed().next().is_some() || gamepad_pressed(GamepadButton::South) {
+        nav.set_app(AppState::Game, TransitionCause::Hotkey);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestGame;
+
+    const REASONS: [GameOverReason; 4] = [
+        GameOverReason::Evicted,
+        GameOverReason::Bankrupt,
+        GameOverReason::HealthCollapse,
+        GameOverReason::Burnout,
+    ];
+
+    fn enter(app: &mut TestGame, state: AppState) {
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(state);
+        app.update_until(|app| *app.world().resource::<State<AppState>>().get() == state);
+        app.update();
+    }
+
+    #[test]
+    fn each_loss_spawns_its_own_screen() {
+        const PROFILE: &str = "Game over";
+        let mut app = TestGame::in_game(PROFILE);
+        // Starting over clears the reason, so the last game over has none.
+        for reason in REASONS.map(Some).into_iter().chain([None]) {
+            if let Some(reason) = reason {
+                app.world_mut().insert_resource(reason);
+            }
+            enter(&mut app, AppState::GameOver);
+
+            let expected = loss(reason);
+            let world = app.world_mut();
+            let texts: Vec<String> = world
+                .query::<&Text>()
+                .iter(world)
+                .map(|text| text.0.clone())
+                .collect();
+            for line in [expected.title, expected.flavor, expected.tip] {
+                assert!(texts.iter().any(|text| text == line), "{reason:?}: {line}");
+            }
+            let tint = world
+                .query_filtered::<&BackgroundColor, With<OnGameOverScreen>>()
+                .single(world)
+                .unwrap();
+            assert_eq!(tint.0, expected.tint);
+
+            enter(&mut app, AppState::Game);
+        }
+    }
+
+    #[test]
+    fn every_loss_reads_differently() {
+        for (index, reason) in REASONS.into_iter().enumerate() {
+            let this = loss(Some(reason));
+            for other in REASONS[index + 1..].iter().map(|&other| loss(Some(other))) {
+                assert_ne!(this.title, other.title);
+                assert_ne!(this.tip, other.tip);
+                assert_ne!(this.tint, other.tint);
+            }
+            assert_ne!(this.title, GENERIC_LOSS.title);
+        }
+    }
+
+    #[test]
+    fn an_eviction_says_so() {
+        let evicted = loss(Some(GameOverReason::Evicted));
+        assert_eq!(evicted.title, "Evicted!");
+        assert_eq!(
+            evicted.flavor,
+            "The landlord changed the locks, and your things are out on the curb."
+        );
+        assert_eq!(
+            evicted.tip,
+            "Tip: autopay takes the rent from your savings too, and a roommate halves it"
+        );
+        assert_ne!(evicted.tint, GENERIC_LOSS.tint);
+    }
+
+    #[test]
+    fn a_game_over_without_a_reason_shows_the_generic_screen() {
+        let generic = loss(None);
+        assert_eq!(generic, GENERIC_LOSS);
+        assert_eq!(generic.title, "Game Over!");
+        assert_eq!(generic.flavor, "The city got the better of you this time.");
+        assert_eq!(
+            generic.tip,
+            "Tip: the reports show where your money goes each month"
+        );
+    }
 }
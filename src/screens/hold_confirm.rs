@@ -0,0 +1,139 @@
+//! Hold-to-confirm for destructive buttons. With a gamepad (or when the player asks for it
+//! everywhere) such buttons only fire after being held down for [`HOLD_DURATION`], and a
+//! fill on the button shows how far along the hold is. Letting go early cancels.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::input_device::{ActiveInputDevice, InputDevice};
+use crate::palette::CORAL;
+use crate::power::AnimationsActive;
+use crate::settings::{AlwaysHoldToConfirm, ReducedMotion};
+use crate::timers::RealUiTime;
+
+pub const HOLD_DURATION: Duration = Duration::from_millis(800);
+
+/// A button that has to be held to confirm. `gamepad` is the gamepad button that holds it,
+/// since gamepads can't point at buttons.
+#[derive(Component, Debug)]
+pub struct HoldToConfirm {
+    pub gamepad: GamepadButton,
+    held: Duration,
+}
+
+impl HoldToConfirm {
+    pub fn new(gamepad: GamepadButton) -> Self {
+        HoldToConfirm {
+            gamepad,
+            held: Duration::ZERO,
+        }
+    }
+}
+
+/// Sent with the button's entity when a [`HoldToConfirm`] button fires, either after a
+/// full hold or on a plain click outside hold mode.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct HoldConfirmed(pub Entity);
+
+/// The hold progress shown on a button: a fill, or a countdown with reduced motion.
+#[derive(Component)]
+struct HoldProgress;
+
+/// Whether destructive buttons currently need holding.
+pub fn hold_mode(device: &ActiveInputDevice, always: &AlwaysHoldToConfirm) -> bool {
+    always.0 || device.0 == InputDevice::Gamepad
+}
+
+pub fn hold_confirm_plugin(app: &mut App) {
+    app.add_event::<HoldConfirmed>()
+        .add_systems(Update, hold_to_confirm);
+}
+
+fn hold_to_confirm(
+    mut commands: Commands,
+    time: RealUiTime,
+    device: Res<ActiveInputDevice>,
+    always: Res<AlwaysHoldToConfirm>,
+    reduced_motion: Res<ReducedMotion>,
+    mut animations: ResMut<AnimationsActive>,
+    gamepads: Query<&Gamepad>,
+    mut buttons: Query<(Entity, Ref<Interaction>, &mut HoldToConfirm)>,
+    mut progress: Query<(Entity, &ChildOf, &mut Node, Option<&mut Text>), With<HoldProgress>>,
+    mut confirmed: EventWriter<HoldConfirmed>,
+) {
+    let hold = hold_mode(&device, &always);
+    for (entity, interaction, mut button) in &mut buttons {
+        let pressed = *interaction == Interaction::Pressed;
+        let held = hold
+            && (pressed
+                || gamepads
+                    .iter()
+                    .any(|gamepad| gamepad.pressed(button.gamepad)));
+        button.held = if held {
+            animations.keep_awake();
+            button.held + time.delta()
+        } else {
+            Duration::ZERO
+        };
+        if !hold && pressed && interaction.is_changed() || button.held >= HOLD_DURATION {
+            button.held = Duration::ZERO;
+            confirmed.write(HoldConfirmed(entity));
+        }
+
+        let existing = progress
+            .iter_mut()
+            .find(|(_, parent, ..)| parent.parent() == entity);
+        let left = HOLD_DURATION - button.held;
+        match existing {
+            Some((progress, ..)) if button.held.is_zero() => {
+                commands.entity(progress).despawn();
+            }
+            Some((_, _, mut node, text)) => match text {
+                Some(mut text) => text.0 = countdown(left),
+                None => node.width = fill_width(button.held),
+            },
+            None if !button.held.is_zero() => {
+                let progress = if reduced_motion.0 {
+                    commands
+                        .spawn((
+                            Text::new(countdown(left)),
+                            TextColor(CORAL),
+                            Node {
+                                position_type: PositionType::Absolute,
+                                right: Val::Px(6.0),
+                                ..default()
+                            },
+                            HoldProgress,
+                        ))
+                        .id()
+                } else {
+                    commands
+                        .spawn((
+                            Node {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(0.0),
+                                bottom: Val::Px(0.0),
+                                width: fill_width(button.held),
+                                height: Val::Px(4.0),
+                                ..default()
+                            },
+                            BackgroundColor(CORAL),
+                            HoldProgress,
+                        ))
+                        .id()
+                };
+                commands.entity(entity).add_child(progress);
+            }
+            None => {}
+        }
+    }
+}
+
+fn countdown(left: Duration) -> String {
+    format!("{:.1}", left.as_secs_f32())
+}
+
+fn fill_width(held: Duration) -> Val {
+    Val::Percent(held.as_secs_f32() / HOLD_DURATION.as_secs_f32() * 100.0)
+}
@@ -0,0 +1,294 @@
+//! The game HUD's layout, and hiding the HUD to take in the location art.
+//!
+//! HUD elements don't position themselves: spawning one with a [`HudSlot`] puts it in that
+//! slot of the layout, a bar along the top and bottom of the screen with a band between
+//! them. Everything tagged [`HudRoot`] is hidden together; the background stays. Right to
+//! left locales mirror the layout, so the left slots end up on the right.
+
+use bevy::{ecs::spawn::SpawnIter, prelude::*, ui::UiSystem};
+
+use super::area_theme::AccentTint;
+use super::overlay::{ModalStack, no_modal_open};
+use crate::AppState;
+use crate::palette::DARKER;
+use crate::settings::{HudLayout, LayoutScale, Locale, SafeArea};
+use crate::toast::Toast;
+
+pub const HUD_TOGGLE_KEY: KeyCode = KeyCode::KeyH;
+/// Above the game screen, below modals.
+const HUD_Z_INDEX: i32 = 10;
+/// The widest the bars get with [`HudLayout::Bars`], so wide screens keep the HUD together.
+const BAR_MAX_WIDTH: f32 = 1400.0;
+const BAR_PADDING: f32 = 8.0;
+const SLOT_GAP: f32 = 8.0;
+
+/// Marks the top entity of a HUD element. Tagged entities hide with the rest of the HUD.
+#[derive(Component, Default)]
+pub struct HudRoot;
+
+#[derive(Resource, Debug, Default, PartialEq, Eq)]
+pub struct HudHidden(pub bool);
+
+/// Moves the whole HUD by this many pixels, so static text doesn't sit in the same place
+/// for hours, see [`super::idle_dimming`].
+#[derive(Resource, Debug, Default, PartialEq)]
+pub struct HudNudge(pub Vec2);
+
+/// Where a HUD element goes. Elements in the same slot stack in spawn order.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudSlot {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    Left,
+    Right,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+
+impl HudSlot {
+    fn alignment(self) -> AlignItems {
+        match self {
+            HudSlot::TopLeft | HudSlot::Left | HudSlot::BottomLeft => AlignItems::FlexStart,
+            HudSlot::TopCenter | HudSlot::BottomCenter => AlignItems::Center,
+            HudSlot::TopRight | HudSlot::Right | HudSlot::BottomRight => AlignItems::FlexEnd,
+        }
+    }
+}
+
+/// Where an element goes within its [`HudSlot`]: lower orders come first. Elements without
+/// one have order 0.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HudOrder(pub i32);
+
+/// The node elements in a [`HudSlot`] are placed in.
+#[derive(Component, Debug, Clone, Copy)]
+struct SlotNode(HudSlot);
+
+#[derive(Component)]
+struct HudLayoutRoot;
+
+/// The top or bottom bar.
+#[derive(Component)]
+struct HudBar;
+
+/// The band between the bars.
+#[derive(Component)]
+struct HudBand;
+
+pub fn hud_plugin(app: &mut App) {
+    app.init_resource::<HudHidden>()
+        .init_resource::<HudNudge>()
+        .add_systems(Startup, spawn_hud_layout)
+        .add_systems(OnEnter(AppState::Game), show_hud_layout::<true>)
+        .add_systems(OnExit(AppState::Game), show_hud_layout::<false>)
+        .add_systems(
+            Update,
+            (
+                restore_hud.run_if(state_changed::<AppState>.or(resource_changed::<ModalStack>)),
+                toggle_hud.run_if(in_state(AppState::Game).and(no_modal_open)),
+                apply_hud_visibility
+                    .run_if(resource_changed::<HudHidden>.or(any_match_filter::<Added<HudRoot>>)),
+            )
+                .chain(),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                place_hud_elements,
+                nudge_hud.run_if(resource_changed::<HudNudge>),
+                apply_hud_layout.run_if(
+                    resource_changed::<HudLayout>
+                        .or(resource_changed::<SafeArea>)
+                        .or(resource_changed::<LayoutScale>)
+                        .or(resource_changed::<Locale>)
+                        .or(any_match_filter::<Added<HudLayoutRoot>>),
+                ),
+            )
+                .before(UiSystem::Layout),
+        );
+}
+
+fn spawn_hud_layout(mut commands: Commands) {
+    let bar = |slots: [HudSlot; 3]| {
+        (
+            Node {
+                width: Val::Percent(100.0),
+                align_self: AlignSelf::Center,
+                column_gap: Val::Px(SLOT_GAP),
+                ..default()
+            },
+            AccentTint::chrome(Color::NONE),
+            HudBar,
+            HudRoot,
+            Children::spawn(SpawnIter(slots.into_iter().map(slot_node))),
+        )
+    };
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::SpaceBetween,
+            ..default()
+        },
+        GlobalZIndex(HUD_Z_INDEX),
+        Visibility::Hidden,
+        HudLayoutRoot,
+        children![
+            bar([HudSlot::TopLeft, HudSlot::TopCenter, HudSlot::TopRight]),
+            (
+                Node {
+                    flex_grow: 1.0,
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                HudBand,
+                children![slot_node(HudSlot::Left), slot_node(HudSlot::Right)],
+            ),
+            bar([
+                HudSlot::BottomLeft,
+                HudSlot::BottomCenter,
+                HudSlot::BottomRight
+            ]),
+        ],
+    ));
+}
+
+fn slot_node(slot: HudSlot) -> impl Bundle {
+    (
+        Node {
+            flex_direction: FlexDirection::Column,
+            flex_grow: 1.0,
+            flex_basis: Val::Px(0.0),
+            // Long text wraps instead of pushing the other slots aside.
+            min_width: Val::Px(0.0),
+            align_items: slot.alignment(),
+            row_gap: Val::Px(SLOT_GAP),
+            ..default()
+        },
+        SlotNode(slot),
+    )
+}
+
+// The layout stays for the whole session, and only shows during a game.
+fn show_hud_layout<const SHOWN: bool>(
+    mut root: Query<&mut Visibility, (With<HudLayoutRoot>, Without<HudRoot>)>,
+) {
+    let Ok(mut root) = root.single_mut() else {
+        return;
+    };
+    *root = if SHOWN {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
+
+fn place_hud_elements(
+    mut commands: Commands,
+    elements: Query<(Entity, &HudSlot), Added<HudSlot>>,
+    slots: Query<(Entity, &SlotNode, Option<&Children>)>,
+    orders: Query<&HudOrder>,
+) {
+    for (node, slot, children) in &slots {
+        let added = elements
+            .iter()
+            .filter(|(_, element_slot)| **element_slot == slot.0)
+            .map(|(element, _)| element);
+        let mut placed: Vec<Entity> = children
+            .map(|children| children.iter().collect())
+            .unwrap_or_default();
+        let before = placed.len();
+        placed.extend(added);
+        if placed.len() == before {
+            continue;
+        }
+        // Stable, so elements with the same order keep their spawn order.
+        placed.sort_by_key(|element| orders.get(*element).copied().unwrap_or_default());
+        commands.entity(node).replace_children(&placed);
+    }
+}
+
+fn apply_hud_layout(
+    layout: Res<HudLayout>,
+    safe_area: Res<SafeArea>,
+    scale: Res<LayoutScale>,
+    locale: Res<Locale>,
+    mut root: Query<&mut Node, (With<HudLayoutRoot>, Without<HudBar>, Without<HudBand>)>,
+    mut band: Query<&mut Node, (With<HudBand>, Without<HudBar>, Without<SlotNode>)>,
+    mut bars: Query<(&mut Node, &mut AccentTint), (With<HudBar>, Without<SlotNode>)>,
+    mut slots: Query<(&mut Node, &SlotNode), (Without<HudLayoutRoot>, Without<HudBar>)>,
+) {
+    let Ok(mut root) = root.single_mut() else {
+        return;
+    };
+    let Ok(mut band) = band.single_mut() else {
+        return;
+    };
+    let direction = locale.direction();
+    root.padding = UiRect::all(Val::Percent(safe_area.percent()));
+    band.padding = UiRect::horizontal(scale.px(BAR_PADDING));
+    band.flex_direction = direction.row();
+    for (mut node, slot) in &mut slots {
+        node.align_items = direction.align(slot.0.alignment());
+    }
+    for (mut node, mut tint) in &mut bars {
+        node.padding = UiRect::all(scale.px(BAR_PADDING));
+        node.flex_direction = direction.row();
+        match *layout {
+            HudLayout::Bars => {
+                node.max_width = Val::Px(BAR_MAX_WIDTH);
+                tint.base = DARKER.with_alpha(0.6);
+            }
+            HudLayout::Corners => {
+                node.max_width = Val::Auto;
+                tint.base = Color::NONE;
+            }
+        }
+    }
+}
+
+fn nudge_hud(
+    nudge: Res<HudNudge>,
+    mut root: Query<&mut Node, (With<HudLayoutRoot>, Without<HudBar>, Without<HudBand>)>,
+) {
+    let Ok(mut root) = root.single_mut() else {
+        return;
+    };
+    root.left = Val::Px(nudge.0.x);
+    root.top = Val::Px(nudge.0.y);
+}
+
+// Screen changes and modals always bring the HUD back
+fn restore_hud(mut hidden: ResMut<HudHidden>) {
+    hidden.set_if_neq(HudHidden(false));
+}
+
+fn toggle_hud(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut hidden: ResMut<HudHidden>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if !keyboard_input.just_pressed(HUD_TOGGLE_KEY) {
+        return;
+    }
+    hidden.0 = !hidden.0;
+    if hidden.0 {
+        toasts.write(Toast::new("HUD hidden - press H to restore"));
+    }
+}
+
+fn apply_hud_visibility(hidden: Res<HudHidden>, mut roots: Query<&mut Visibility, With<HudRoot>>) {
+    let visibility = if hidden.0 {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    for mut root in &mut roots {
+        root.set_if_neq(visibility);
+    }
+}
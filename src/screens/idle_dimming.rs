@@ -0,0 +1,112 @@
+//! Burn-in protection for OLED screens and kiosks. When the screen sits idle, everything
+//! slowly dims to [`DIM_BRIGHTNESS`] under a black layer over the whole window, and the
+//! HUD wanders by a couple of pixels so its text doesn't stay in one spot. Any input
+//! brings both back at once.
+//!
+//! It only starts where nothing moves on its own: a paused run, or the menus and end
+//! screens when the attract sequence is off, since that takes over the idle screen there.
+//! Nothing dims while an animation plays. [`IdleDimming`] turns it on, and it's off by
+//! default.
+
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::hud::HudNudge;
+use super::overlay::Overlay;
+use crate::AppState;
+use crate::input_device::IdleTime;
+use crate::power::AnimationsActive;
+use crate::settings::{AttractMode, IdleDimming, ReducedMotion};
+
+/// Over everything, below the stepping debugger.
+const DIM_Z_INDEX: i32 = 800;
+/// How bright the screen ends up, from 0 to 1.
+const DIM_BRIGHTNESS: f32 = 0.4;
+const DIM_FADE: Duration = Duration::from_secs(5);
+/// How far the HUD wanders from its place, in pixels.
+const NUDGE_RADIUS: f32 = 2.0;
+/// How long the HUD takes to go around once.
+const NUDGE_PERIOD: Duration = Duration::from_secs(600);
+
+/// The black layer the screen dims under.
+#[derive(Component)]
+struct DimLayer;
+
+pub fn idle_dimming_plugin(app: &mut App) {
+    app.add_systems(Startup, spawn_dim_layer)
+        // After everything animating has counted itself in.
+        .add_systems(PostUpdate, dim_when_idle);
+}
+
+fn spawn_dim_layer(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        BackgroundColor(Color::NONE),
+        Visibility::Hidden,
+        GlobalZIndex(DIM_Z_INDEX),
+        Pickable::IGNORE,
+        DimLayer,
+    ));
+}
+
+// Whether the current screen is one that can sit still for a long time.
+fn dimmable_screen(app_state: &AppState, overlay: Option<&Overlay>, attract: &AttractMode) -> bool {
+    match app_state {
+        AppState::Game => overlay == Some(&Overlay::Pause),
+        AppState::Menu | AppState::GameOver | AppState::Win => !attract.enabled,
+        AppState::Loading | AppState::AssetError => false,
+    }
+}
+
+fn dim_when_idle(
+    idle: Res<IdleTime>,
+    dimming: Res<IdleDimming>,
+    attract: Res<AttractMode>,
+    app_state: Res<State<AppState>>,
+    overlay: Option<Res<State<Overlay>>>,
+    animations: Res<AnimationsActive>,
+    reduced_motion: Res<ReducedMotion>,
+    mut nudge: ResMut<HudNudge>,
+    mut layer: Query<(&mut BackgroundColor, &mut Visibility), With<DimLayer>>,
+) {
+    let Ok((mut background, mut visibility)) = layer.single_mut() else {
+        return;
+    };
+    let dimmable = dimming.enabled
+        && !animations.any()
+        && dimmable_screen(
+            app_state.get(),
+            overlay.as_deref().map(State::get),
+            &attract,
+        );
+    let dimmed_for = idle.0.saturating_sub(dimming.timeout());
+    if !dimmable || dimmed_for.is_zero() {
+        if *visibility != Visibility::Hidden {
+            background.0 = Color::NONE;
+            *visibility = Visibility::Hidden;
+        }
+        nudge.set_if_neq(HudNudge(Vec2::ZERO));
+        return;
+    }
+
+    let fade = if reduced_motion.0 {
+        1.0
+    } else {
+        (dimmed_for.as_secs_f32() / DIM_FADE.as_secs_f32()).min(1.0)
+    };
+    background.0 = Color::BLACK.with_alpha(fade * (1.0 - DIM_BRIGHTNESS));
+    *visibility = Visibility::Inherited;
+    if !reduced_motion.0 {
+        let angle = TAU * dimmed_for.as_secs_f32() / NUDGE_PERIOD.as_secs_f32();
+        // Whole pixels, so the text stays sharp.
+        let offset = (Vec2::from_angle(angle) - Vec2::X) * NUDGE_RADIUS;
+        nudge.set_if_neq(HudNudge(offset.round()));
+    }
+}
@@ -0,0 +1,207 @@
+//! Warms up the travel menu's destinations: while the menu is open, the images of every
+//! area the player can go to start loading, a few per frame from the frame after it opens,
+//! so the menu comes up as fast as ever and picking an area swaps the background right
+//! away. The upload to the GPU follows each load on its own.
+//!
+//! Closing the menu without going anywhere lets go of the images of areas other than the
+//! current one and home, which the player is likeliest to come back to. Going somewhere
+//! keeps them all, as a player on the move is likely to move again.
+//!
+//! [`ImageWarmth`] counts, each time the background changes to another area, whether its
+//! images were loaded by the time it showed. It's in the dev builds' debug overlay.
+
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use super::overlay::Overlay;
+use crate::AppState;
+use crate::sim::{
+    catalog::{AreaAnimation, AreaCatalog},
+    economy::{Area, CurrentArea, HomeArea},
+    travel::{AreaUnlocks, TravelRequested},
+};
+
+/// Loads started per frame.
+const WARM_PER_FRAME: usize = 1;
+
+/// Background changes whose images were already loaded, and those that had to wait.
+#[derive(Resource, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct ImageWarmth {
+    pub hits: u32,
+    pub misses: u32,
+}
+
+/// The images loaded ahead of time, by area, and those still to load.
+#[derive(Resource, Debug, Default)]
+struct WarmImages {
+    handles: HashMap<Area, Vec<Handle<Image>>>,
+    queue: VecDeque<Area>,
+}
+
+pub fn image_warming_plugin(app: &mut App) {
+    app.register_type::<ImageWarmth>()
+        .init_resource::<ImageWarmth>()
+        .init_resource::<WarmImages>()
+        .add_systems(OnEnter(Overlay::Travel), queue_destinations)
+        .add_systems(OnExit(Overlay::Travel), release_images)
+        .add_systems(
+            Update,
+            (
+                warm_images.run_if(in_state(Overlay::Travel).and(not(state_changed::<Overlay>))),
+                count_warmth.run_if(
+                    in_state(AppState::Game).and(
+                        resource_exists_and_changed::<CurrentArea>
+                            .and(not(resource_added::<CurrentArea>)),
+                    ),
+                ),
+            ),
+        );
+}
+
+/// The images an area's background shows: its animation's frames, or its image.
+fn area_images(area: Area, catalog: &AreaCatalog) -> Vec<&str> {
+    match catalog.animation(area) {
+        Some(AreaAnimation::Frames { paths, .. }) => paths.iter().map(String::as_str).collect(),
+        Some(AreaAnimation::Atlas { path, .. }) => vec![path.as_str()],
+        None => catalog.image(area).into_iter().collect(),
+    }
+}
+
+fn queue_destinations(
+    mut warm: ResMut<WarmImages>,
+    catalog: Res<AreaCatalog>,
+    unlocks: Res<AreaUnlocks>,
+) {
+    let queue = catalog
+        .areas()
+        .into_iter()
+        .filter(|area| unlocks.is_open(*area, &catalog) && !warm.handles.contains_key(area))
+        .collect();
+    warm.queue = queue;
+}
+
+fn warm_images(
+    mut warm: ResMut<WarmImages>,
+    catalog: Res<AreaCatalog>,
+    asset_server: Res<AssetServer>,
+) {
+    for _ in 0..WARM_PER_FRAME {
+        let Some(area) = warm.queue.pop_front() else {
+            return;
+        };
+        let handles = area_images(area, &catalog)
+            .into_iter()
+            .map(|path| asset_server.load(path))
+            .collect();
+        warm.handles.insert(area, handles);
+    }
+}
+
+// The menu closes as the player picks somewhere to go, before they get there.
+fn release_images(
+    mut warm: ResMut<WarmImages>,
+    mut requests: EventReader<TravelRequested>,
+    catalog: Res<AreaCatalog>,
+    unlocks: Res<AreaUnlocks>,
+    current: Res<CurrentArea>,
+    home: Res<HomeArea>,
+) {
+    warm.queue.clear();
+    if requests
+        .read()
+        .any(|TravelRequested(area)| unlocks.is_open(*area, &catalog))
+    {
+        return;
+    }
+    warm.handles
+        .retain(|area, _| *area == current.0 || *area == home.location);
+}
+
+// Runs as the background changes, before the new image has had a frame to load.
+fn count_warmth(
+    current: Res<CurrentArea>,
+    catalog: Res<AreaCatalog>,
+    asset_server: Res<AssetServer>,
+    mut warmth: ResMut<ImageWarmth>,
+) {
+    let loaded = area_images(current.0, &catalog).into_iter().all(|path| {
+        asset_server
+            .get_handle::<Image>(path)
+            .is_some_and(|handle| asset_server.is_loaded_with_dependencies(&handle))
+    });
+    if loaded {
+        warmth.hits += 1;
+    } else {
+        warmth.misses += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestGame;
+
+    fn set_overlay(app: &mut TestGame, overlay: Overlay) {
+        app.world_mut()
+            .resource_mut::<NextState<Overlay>>()
+            .set(overlay);
+        app.update();
+    }
+
+    fn warmed(app: &App) -> Vec<Area> {
+        let mut areas: Vec<_> = app
+            .world()
+            .resource::<WarmImages>()
+            .handles
+            .keys()
+            .copied()
+            .collect();
+        areas.sort_by_key(|area| area.label());
+        areas
+    }
+
+    #[test]
+    fn the_travel_menu_warms_every_open_destination_one_a_frame() {
+        const PROFILE: &str = "Warm images";
+        let mut app = TestGame::in_game(PROFILE);
+        let world = app.world();
+        let (catalog, unlocks) = (
+            world.resource::<AreaCatalog>(),
+            world.resource::<AreaUnlocks>(),
+        );
+        let mut open: Vec<_> = catalog
+            .areas()
+            .into_iter()
+            .filter(|area| unlocks.is_open(*area, catalog))
+            .collect();
+        open.sort_by_key(|area| area.label());
+        let kept = [
+            world.resource::<CurrentArea>().0,
+            world.resource::<HomeArea>().location,
+        ];
+        assert!(open.len() > 1, "{open:?}");
+
+        set_overlay(&mut app, Overlay::Travel);
+        // Nothing loads the frame the menu opens.
+        assert!(warmed(&app).is_empty());
+        for loaded in 1..=open.len() {
+            app.update();
+            assert_eq!(warmed(&app).len(), loaded);
+        }
+        assert_eq!(warmed(&app), open);
+
+        // Closing without going anywhere keeps where the player is and home.
+        set_overlay(&mut app, Overlay::None);
+        assert!(warmed(&app).iter().all(|area| kept.contains(area)));
+
+        // Going somewhere keeps them all.
+        set_overlay(&mut app, Overlay::Travel);
+        app.update_until(|app| warmed(app).len() == open.len());
+        let destination = *open.iter().find(|area| !kept.contains(area)).unwrap();
+        app.world_mut().send_event(TravelRequested(destination));
+        set_overlay(&mut app, Overlay::None);
+        assert_eq!(warmed(&app), open);
+    }
+}
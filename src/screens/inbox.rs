@@ -0,0 +1,399 @@
+//! The inbox: an envelope on the game screen with a badge counting unread mail, and the
+//! overlay listing every message. Opening a message marks it read, and a message's action
+//! button sends the same [`ActionPressed`] the action panel would.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use super::actions::ActionPressed;
+use super::area_theme::AccentBorder;
+use super::button_press::ButtonPresses;
+use super::hud::{HudOrder, HudRoot, HudSlot};
+use super::overlay::{MODAL_Z_INDEX, Overlay};
+use super::scroll::scrollable;
+use crate::fonts::ui_font;
+use crate::palette::{BLUE, CORAL, DARK, DARKER, LIGHT, YELLOW};
+use crate::settings::ReducedMotion;
+use crate::sim::mail::Inbox;
+use crate::timers::RealUiTime;
+use crate::{AppState, despawn_screen, track_screen};
+
+const INBOX_FONT_SIZE: f32 = 16.0;
+const BADGE_FONT_SIZE: f32 = 12.0;
+const ENVELOPE_WIDTH: f32 = 36.0;
+const ENVELOPE_HEIGHT: f32 = 24.0;
+const BADGE_SIZE: f32 = 18.0;
+const LIST_WIDTH: f32 = 260.0;
+const READER_WIDTH: f32 = 360.0;
+/// Pulses of the badge per second while critical mail is unread.
+const PULSE_RATE: f32 = 0.8;
+const PULSE_SCALE: f32 = 0.15;
+
+#[derive(Component)]
+struct MailButton;
+
+#[derive(Component)]
+struct MailBadge;
+
+#[derive(Component)]
+struct InboxOverlay;
+
+#[derive(Component)]
+struct InboxList;
+
+#[derive(Component)]
+struct InboxReader;
+
+/// Opens the message at this index in [`Inbox::messages`].
+#[derive(Component, Debug, Clone, Copy)]
+struct MailRow(usize);
+
+/// Takes the action of the message at this index.
+#[derive(Component, Debug, Clone, Copy)]
+struct MailActionButton(usize);
+
+/// The message open in the reader.
+#[derive(Resource, Debug, Default)]
+struct OpenMail(Option<usize>);
+
+pub fn inbox_plugin(app: &mut App) {
+    track_screen::<MailButton>(app, AppState::Game);
+    track_screen::<InboxOverlay>(app, Overlay::Inbox);
+    app.init_resource::<OpenMail>()
+        .add_systems(OnEnter(AppState::Game), spawn_mail_button)
+        .add_systems(OnExit(AppState::Game), despawn_screen::<MailButton>)
+        .add_systems(
+            Update,
+            (
+                update_mail_badge.run_if(resource_changed::<Inbox>),
+                pulse_mail_badge,
+            )
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        )
+        .add_systems(Update, open_inbox.run_if(in_state(Overlay::None)))
+        .add_systems(OnEnter(Overlay::Inbox), setup_inbox)
+        .add_systems(OnExit(Overlay::Inbox), despawn_screen::<InboxOverlay>)
+        .add_systems(
+            Update,
+            (
+                (close_inbox, open_mail, take_mail_action),
+                render_inbox.run_if(resource_changed::<Inbox>.or(resource_changed::<OpenMail>)),
+                mail_button_colors,
+            )
+                .chain()
+                .run_if(in_state(Overlay::Inbox)),
+        );
+}
+
+fn spawn_mail_button(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.spawn((
+        Button,
+        Node {
+            width: Val::Px(ENVELOPE_WIDTH),
+            height: Val::Px(ENVELOPE_HEIGHT),
+            border: UiRect::all(Val::Px(2.0)),
+            ..default()
+        },
+        BackgroundColor(LIGHT),
+        AccentBorder(DARKER),
+        MailButton,
+        HudRoot,
+        HudSlot::TopLeft,
+        HudOrder(1),
+        children![
+            // The envelope's flap.
+            (
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(50.0),
+                    top: Val::Px(-ENVELOPE_WIDTH / 4.0),
+                    margin: UiRect::left(Val::Px(-ENVELOPE_WIDTH / 4.0)),
+                    width: Val::Px(ENVELOPE_WIDTH / 2.0),
+                    height: Val::Px(ENVELOPE_WIDTH / 2.0),
+                    border: UiRect::new(Val::ZERO, Val::Px(2.0), Val::ZERO, Val::Px(2.0)),
+                    ..default()
+                },
+                AccentBorder(DARKER),
+                Transform::from_rotation(Quat::from_rotation_z(TAU / 8.0)),
+            ),
+            (
+                Node {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(-BADGE_SIZE / 2.0),
+                    top: Val::Px(-BADGE_SIZE / 2.0),
+                    width: Val::Px(BADGE_SIZE),
+                    height: Val::Px(BADGE_SIZE),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                BorderRadius::MAX,
+                BackgroundColor(BLUE),
+                Visibility::Hidden,
+                MailBadge,
+                children![(
+                    Text::default(),
+                    TextFont {
+                        font: ui_font(&assets),
+                        font_size: BADGE_FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(LIGHT),
+                )],
+            ),
+        ],
+    ));
+}
+
+fn update_mail_badge(
+    inbox: Res<Inbox>,
+    mut badge: Query<(&mut Visibility, &mut BackgroundColor, &Children), With<MailBadge>>,
+    mut texts: Query<&mut Text>,
+) {
+    let Ok((mut visibility, mut background, children)) = badge.single_mut() else {
+        return;
+    };
+    let unread = inbox.unread();
+    *visibility = if unread == 0 {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    background.0 = if inbox.has_unread_critical() {
+        CORAL
+    } else {
+        BLUE
+    };
+    if let Some(mut text) = children
+        .first()
+        .and_then(|child| texts.get_mut(*child).ok())
+    {
+        text.0 = unread.to_string();
+    }
+}
+
+fn pulse_mail_badge(
+    inbox: Res<Inbox>,
+    reduced_motion: Res<ReducedMotion>,
+    time: RealUiTime,
+    mut badge: Query<&mut Transform, With<MailBadge>>,
+) {
+    let Ok(mut badge) = badge.single_mut() else {
+        return;
+    };
+    let scale = if inbox.has_unread_critical() && !reduced_motion.0 {
+        1.0 + PULSE_SCALE * (time.elapsed_secs() * PULSE_RATE * TAU).sin().abs()
+    } else {
+        1.0
+    };
+    if badge.scale.x != scale {
+        badge.scale = Vec3::splat(scale);
+    }
+}
+
+fn open_inbox(
+    mut presses: ButtonPresses<(), With<MailButton>>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if presses.read().count() > 0 {
+        next_state.set(Overlay::Inbox);
+    }
+}
+
+fn setup_inbox(mut commands: Commands, mut open: ResMut<OpenMail>, mut inbox: ResMut<Inbox>) {
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        GlobalZIndex(MODAL_Z_INDEX),
+        InboxOverlay,
+        children![(
+            Node {
+                column_gap: Val::Px(16.0),
+                padding: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            BackgroundColor(DARKER),
+            children![
+                (
+                    scrollable(Node {
+                        width: Val::Px(LIST_WIDTH),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        max_height: Val::Vh(60.0),
+                        ..default()
+                    }),
+                    InboxList,
+                ),
+                (
+                    Node {
+                        width: Val::Px(READER_WIDTH),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                    InboxReader,
+                ),
+            ],
+        )],
+    ));
+    open.0 = None;
+    // Render on the first frame.
+    inbox.set_changed();
+}
+
+fn close_inbox(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(Overlay::None);
+    }
+}
+
+fn open_mail(
+    mut presses: ButtonPresses<&MailRow>,
+    mut inbox: ResMut<Inbox>,
+    mut open: ResMut<OpenMail>,
+) {
+    for row in presses.read() {
+        if let Some(mail) = inbox.messages.get_mut(row.0) {
+            mail.read = true;
+            open.0 = Some(row.0);
+        }
+    }
+}
+
+fn take_mail_action(
+    mut presses: ButtonPresses<&MailActionButton>,
+    mut inbox: ResMut<Inbox>,
+    mut pressed: EventWriter<ActionPressed>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    for button in presses.read() {
+        // An action can only be taken once.
+        if let Some((_, action)) = inbox
+            .messages
+            .get_mut(button.0)
+            .and_then(|mail| mail.action.take())
+        {
+            pressed.write(ActionPressed(action));
+            next_state.set(Overlay::None);
+        }
+    }
+}
+
+fn render_inbox(
+    mut commands: Commands,
+    inbox: Res<Inbox>,
+    open: Res<OpenMail>,
+    list: Query<Entity, With<InboxList>>,
+    reader: Query<Entity, With<InboxReader>>,
+    assets: Res<AssetServer>,
+) {
+    let Ok(list) = list.single() else {
+        return;
+    };
+    let Ok(reader) = reader.single() else {
+        return;
+    };
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: INBOX_FONT_SIZE,
+        ..default()
+    };
+    let text = |value: String, color: Color| (Text::new(value), font.clone(), TextColor(color));
+
+    commands
+        .entity(list)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            if inbox.messages.is_empty() {
+                parent.spawn(text("No mail".to_string(), LIGHT.with_alpha(0.6)));
+            }
+            for (index, mail) in inbox.messages.iter().enumerate().rev() {
+                let color = if mail.read {
+                    LIGHT.with_alpha(0.6)
+                } else {
+                    YELLOW
+                };
+                parent.spawn((
+                    Button,
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                        flex_shrink: 0.0,
+                        ..default()
+                    },
+                    BackgroundColor(if open.0 == Some(index) { BLUE } else { DARK }),
+                    MailRow(index),
+                    children![
+                        text(mail.title.clone(), color),
+                        text(format!("Day {}", mail.day), LIGHT.with_alpha(0.6)),
+                    ],
+                ));
+            }
+        });
+
+    commands
+        .entity(reader)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            let Some((index, mail)) = open
+                .0
+                .and_then(|index| inbox.messages.get(index).map(|mail| (index, mail)))
+            else {
+                parent.spawn(text("Inbox".to_string(), YELLOW));
+                parent.spawn(text(
+                    "Pick a message to read it. Esc to close".to_string(),
+                    LIGHT.with_alpha(0.6),
+                ));
+                return;
+            };
+            parent.spawn(text(mail.title.clone(), YELLOW));
+            parent.spawn(text(
+                format!("Received on day {}", mail.day),
+                LIGHT.with_alpha(0.6),
+            ));
+            parent.spawn(text(mail.body.clone(), LIGHT));
+            if let Some((label, _)) = &mail.action {
+                parent.spawn((
+                    Button,
+                    Node {
+                        align_self: AlignSelf::FlexStart,
+                        padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                        ..default()
+                    },
+                    BackgroundColor(DARK),
+                    MailActionButton(index),
+                    children![text(label.clone(), LIGHT)],
+                ));
+            }
+        });
+}
+
+fn mail_button_colors(
+    open: Res<OpenMail>,
+    mut rows: Query<
+        (&Interaction, &mut BackgroundColor, Option<&MailRow>),
+        (
+            Changed<Interaction>,
+            Or<(With<MailRow>, With<MailActionButton>)>,
+        ),
+    >,
+) {
+    for (interaction, mut background, row) in &mut rows {
+        let selected = row.is_some_and(|row| open.0 == Some(row.0));
+        background.0 = match interaction {
+            Interaction::None if !selected => DARK,
+            _ => BLUE,
+        };
+    }
+}
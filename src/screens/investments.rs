@@ -0,0 +1,322 @@
+//! The investments panel at the bank: every fund's price with a chart of the last two
+//! weeks, what the player holds and how it's doing, and buttons to trade one unit at a time.
+//! Purchases are paid like any other bill, and sales go into savings.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use super::button_press::ButtonPresses;
+use super::overlay::{MODAL_Z_INDEX, Overlay};
+use super::tooltip::Tooltip;
+use crate::fonts::ui_font;
+use crate::format;
+use crate::palette::{BLUE, CORAL, DARK, DARKER, LIGHT, YELLOW};
+use crate::settings::Locale;
+use crate::sim::{
+    bank::Bank,
+    economy::Money,
+    ledger::{Category, Transaction},
+    market::{FundPrice, Market, Portfolio},
+};
+use crate::toast::Toast;
+use crate::{despawn_screen, track_screen};
+
+const PANEL_FONT_SIZE: f32 = 16.0;
+const SPARK_WIDTH: f32 = 6.0;
+const SPARK_HEIGHT: f32 = 36.0;
+/// Height of the lowest price's bar, in percent, so it stays visible.
+const SPARK_FLOOR: f32 = 10.0;
+
+#[derive(Component)]
+struct InvestmentsOverlay;
+
+#[derive(Component)]
+struct InvestmentsContent;
+
+/// A trade button, for the fund at this index in [`Market::funds`].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trade {
+    Buy(usize),
+    Sell(usize),
+}
+
+/// Sent when the player presses a trade button, for one unit.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradeRequested(pub Trade);
+
+pub fn investments_plugin(app: &mut App) {
+    track_screen::<InvestmentsOverlay>(app, Overlay::Investments);
+    app.add_event::<TradeRequested>()
+        .add_systems(Update, offer_investments.in_set(ActionSet::Offer))
+        .add_systems(Update, open_investments.run_if(in_state(Overlay::None)))
+        .add_systems(OnEnter(Overlay::Investments), setup_investments)
+        .add_systems(
+            OnExit(Overlay::Investments),
+            despawn_screen::<InvestmentsOverlay>,
+        )
+        .add_systems(
+            Update,
+            (
+                (close_investments, (press_trade, trade).chain()),
+                render_investments.run_if(
+                    resource_changed::<Portfolio>
+                        .or(resource_changed::<Money>)
+                        .or(resource_changed::<Bank>),
+                ),
+                trade_button_colors,
+            )
+                .chain()
+                .run_if(in_state(Overlay::Investments)),
+        );
+}
+
+fn offer_investments(market: Res<Market>, mut actions: ResMut<AvailableActions>) {
+    if !market.funds.is_empty() {
+        actions.offer(GameAction::Invest);
+    }
+}
+
+fn open_investments(
+    mut pressed: EventReader<ActionPressed>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        if *action == GameAction::Invest {
+            next_state.set(Overlay::Investments);
+        }
+    }
+}
+
+fn setup_investments(mut commands: Commands, mut portfolio: ResMut<Portfolio>) {
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        GlobalZIndex(MODAL_Z_INDEX),
+        InvestmentsOverlay,
+        children![(
+            Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            BackgroundColor(DARKER),
+            InvestmentsContent,
+        )],
+    ));
+    // Render on the first frame.
+    portfolio.set_changed();
+}
+
+fn close_investments(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(Overlay::None);
+    }
+}
+
+fn press_trade(mut presses: ButtonPresses<&Trade>, mut requests: EventWriter<TradeRequested>) {
+    for trade in presses.read() {
+        requests.write(TradeRequested(*trade));
+    }
+}
+
+fn trade(
+    mut requests: EventReader<TradeRequested>,
+    market: Res<Market>,
+    mut portfolio: ResMut<Portfolio>,
+    mut money: ResMut<Money>,
+    mut bank: ResMut<Bank>,
+    mut transactions: EventWriter<Transaction>,
+    mut toasts: EventWriter<Toast>,
+) {
+    for TradeRequested(trade) in requests.read() {
+        let (Trade::Buy(index) | Trade::Sell(index)) = *trade;
+        let Some(fund) = market.funds.get(index) else {
+            continue;
+        };
+        let price = fund.unit_price();
+        let holding = portfolio.0.entry(fund.name.clone()).or_default();
+        match trade {
+            Trade::Buy(_) => {
+                if bank.pay(&mut money, price) {
+                    holding.buy(price);
+                    transactions.write(Transaction::Expense(Category::Investments, price));
+                } else {
+                    toasts.write(Toast::new("Not enough money"));
+                }
+            }
+            Trade::Sell(_) => {
+                if holding.sell() {
+                    bank.savings += price;
+                    transactions.write(Transaction::Income(Category::Investments, price));
+                }
+            }
+        }
+    }
+}
+
+fn render_investments(
+    mut commands: Commands,
+    market: Res<Market>,
+    portfolio: Res<Portfolio>,
+    content: Query<Entity, With<InvestmentsContent>>,
+    assets: Res<AssetServer>,
+    locale: Res<Locale>,
+) {
+    let Ok(content) = content.single() else {
+        return;
+    };
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: PANEL_FONT_SIZE,
+        ..default()
+    };
+    let text = |value: String, color: Color| (Text::new(value), font.clone(), TextColor(color));
+
+    commands
+        .entity(content)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            parent.spawn(text("Investments".to_string(), YELLOW));
+            for (index, fund) in market.funds.iter().enumerate() {
+                let price = fund.unit_price();
+                let holding = portfolio.holding(&fund.name);
+                parent
+                    .spawn((
+                        Node {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(12.0),
+                            margin: UiRect::top(Val::Px(8.0)),
+                            ..default()
+                        },
+                        fund_tooltip(fund, *locale),
+                    ))
+                    .with_children(|row| {
+                        row.spawn(text(
+                            format!("{}: {}", fund.name, format::money(price, *locale)),
+                            LIGHT,
+                        ));
+                        spawn_sparkline(row, fund);
+                    });
+
+                let gain = holding.unrealized_gain(price);
+                let sign = if gain < 0 { "" } else { "+" };
+                let holding_line = if holding.units == 0 {
+                    text("No units held".to_string(), LIGHT.with_alpha(0.6))
+                } else {
+                    text(
+                        format!(
+                            "{} units worth {} ({sign}{})",
+                            holding.units,
+                            format::money(holding.value(price), *locale),
+                            format::format_money(gain * 100, *locale)
+                        ),
+                        if gain < 0 { CORAL } else { BLUE },
+                    )
+                };
+                parent.spawn(holding_line);
+
+                parent
+                    .spawn(Node {
+                        column_gap: Val::Px(8.0),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        for (label, trade) in
+                            [("Buy 1", Trade::Buy(index)), ("Sell 1", Trade::Sell(index))]
+                        {
+                            row.spawn((
+                                Button,
+                                Node {
+                                    padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(DARK),
+                                trade,
+                                children![text(label.to_string(), LIGHT)],
+                            ));
+                        }
+                    });
+            }
+            parent.spawn(text(
+                "Buying draws on cash, then savings. Sales go to savings. Esc to close".to_string(),
+                LIGHT.with_alpha(0.6),
+            ));
+        });
+}
+
+/// The fund's price range over the days its chart shows.
+fn fund_tooltip(fund: &FundPrice, locale: Locale) -> Tooltip {
+    let low = fund.history.iter().copied().fold(f32::INFINITY, f32::min);
+    let high = fund
+        .history
+        .iter()
+        .copied()
+        .fold(f32::NEG_INFINITY, f32::max);
+    let body = if fund.history.len() < 2 {
+        "No price history yet".to_string()
+    } else {
+        format!(
+            "Between {} and {} over the last {} days",
+            format::money(low.round().max(1.0) as u32, locale),
+            format::money(high.round().max(1.0) as u32, locale),
+            fund.history.len()
+        )
+    };
+    Tooltip::titled(fund.name.clone(), body)
+}
+
+/// A bar per day of the fund's recent prices, scaled between the lowest and highest.
+fn spawn_sparkline(parent: &mut ChildSpawnerCommands, fund: &FundPrice) {
+    let low = fund.history.iter().copied().fold(f32::INFINITY, f32::min);
+    let high = fund
+        .history
+        .iter()
+        .copied()
+        .fold(f32::NEG_INFINITY, f32::max);
+    let range = (high - low).max(f32::EPSILON);
+    let rising = fund.history.back() >= fund.history.front();
+    let color = if rising { BLUE } else { CORAL };
+
+    parent
+        .spawn(Node {
+            height: Val::Px(SPARK_HEIGHT),
+            align_items: AlignItems::FlexEnd,
+            column_gap: Val::Px(1.0),
+            ..default()
+        })
+        .with_children(|chart| {
+            for price in &fund.history {
+                let share = (price - low) / range;
+                chart.spawn((
+                    Node {
+                        width: Val::Px(SPARK_WIDTH),
+                        height: Val::Percent(SPARK_FLOOR + share * (100.0 - SPARK_FLOOR)),
+                        ..default()
+                    },
+                    BackgroundColor(color),
+                ));
+            }
+        });
+}
+
+fn trade_button_colors(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Trade>)>,
+) {
+    for (interaction, mut background) in &mut buttons {
+        background.0 = match interaction {
+            Interaction::None => DARK,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
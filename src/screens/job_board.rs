@@ -0,0 +1,110 @@
+//! The job board on the game screen: the player's current job and any pending offer.
+
+use bevy::prelude::*;
+
+use super::area_theme::{AccentText, AccentTint};
+use super::hud::{HudRoot, HudSlot};
+use crate::fonts::ui_font;
+use crate::format;
+use crate::palette::{DARKER, LIGHT, YELLOW};
+use crate::settings::Locale;
+use crate::sim::{economy::WorkArea, jobs::JobOffer};
+use crate::{AppState, despawn_screen, track_screen};
+
+const JOB_BOARD_FONT_SIZE: f32 = 16.0;
+
+#[derive(Component)]
+struct JobBoard;
+
+#[derive(Component)]
+struct CurrentJobText;
+
+#[derive(Component)]
+struct JobOfferText;
+
+pub fn job_board_plugin(app: &mut App) {
+    track_screen::<JobBoard>(app, AppState::Game);
+    app.add_systems(OnEnter(AppState::Game), spawn_job_board)
+        .add_systems(OnExit(AppState::Game), despawn_screen::<JobBoard>)
+        .add_systems(
+            Update,
+            update_job_board.run_if(
+                in_state(AppState::Game).and(
+                    resource_changed::<WorkArea>
+                        .or(resource_changed::<JobOffer>)
+                        .or(resource_changed::<Locale>),
+                ),
+            ),
+        );
+}
+
+fn spawn_job_board(mut commands: Commands, assets: Res<AssetServer>) {
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: JOB_BOARD_FONT_SIZE,
+        ..default()
+    };
+    commands.spawn((
+        Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.0),
+            padding: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        AccentTint::chrome(DARKER.with_alpha(0.85)),
+        JobBoard,
+        HudRoot,
+        HudSlot::Left,
+        children![
+            (
+                Text::default(),
+                font.clone(),
+                AccentText(LIGHT),
+                CurrentJobText
+            ),
+            (
+                Text::default(),
+                font,
+                TextColor(YELLOW),
+                Visibility::Hidden,
+                JobOfferText,
+            ),
+        ],
+    ));
+}
+
+fn update_job_board(
+    work: Res<WorkArea>,
+    offer: Res<JobOffer>,
+    mut current: Query<&mut Text, (With<CurrentJobText>, Without<JobOfferText>)>,
+    mut offer_text: Query<(&mut Text, &mut Visibility), With<JobOfferText>>,
+    locale: Res<Locale>,
+) {
+    let Ok(mut current) = current.single_mut() else {
+        return;
+    };
+    let Ok((mut text, mut visibility)) = offer_text.single_mut() else {
+        return;
+    };
+    current.0 = match work.location {
+        Some(employer) => format!(
+            "{} at {employer:?}\n{}/h - {} shifts worked",
+            work.title,
+            format::money(work.wage, *locale),
+            format::format_int(i64::from(work.shifts), *locale)
+        ),
+        None => "Unemployed".to_string(),
+    };
+    match &offer.0 {
+        Some(job) => {
+            text.0 = format!(
+                "Offer: {} at {:?}, {}/h",
+                job.title,
+                job.employer,
+                format::money(job.wage, *locale)
+            );
+            *visibility = Visibility::Inherited;
+        }
+        None => *visibility = Visibility::Hidden,
+    }
+}
@@ -0,0 +1,471 @@
+//! The photo journal, see [`crate::sim::journal`]: taking the photos the run asks for, and
+//! the screen that shows them, opened from the pause menu.
+//!
+//! A photo is a screenshot of the frame it was asked for in, read back from the GPU over
+//! the next few frames and shrunk and written to the profile's [`JOURNAL_DIR`] on the
+//! [`AsyncComputeTaskPool`], so taking one doesn't hitch the game. With [`PhotoHud::Hidden`]
+//! the HUD hides for that one frame.
+//!
+//! The screen lays the photos out in a scrolling grid, captioned with their day, and shows
+//! one full size when it's clicked. The files are read in the background as well; one
+//! that's missing or doesn't decode, like one not written yet, gets a placeholder tile.
+
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    image::{CompressedImageFormats, ImageSampler, ImageType},
+    prelude::*,
+    render::view::screenshot::{Screenshot, ScreenshotCaptured},
+    tasks::{AsyncComputeTaskPool, Task, futures::check_ready},
+};
+
+use super::button_press::ButtonPresses;
+use super::hud::{HudHidden, HudRoot};
+use super::menu::{MenuHistory, MenuState};
+use super::menu_slide::{slide_in, slide_out};
+use super::nav::ScreenNav;
+use super::scroll::scrollable;
+use crate::fonts::ui_font;
+use crate::palette::{BLUE, DARK, DARKER, LIGHT, YELLOW};
+use crate::profiles::ProfilePaths;
+use crate::save_file::Saves;
+use crate::settings::{LayoutScale, PhotoHud};
+use crate::sim::clock::Clock;
+use crate::sim::journal::{JOURNAL_DIR, Journal, PhotoRequest};
+use crate::sim::starting_run;
+use crate::{AppState, track_screen};
+
+/// Photos are shrunk to fit this size before they're written.
+const PHOTO_WIDTH: u32 = 640;
+const PHOTO_HEIGHT: u32 = 360;
+/// The size of a photo in the grid.
+const TILE_WIDTH: f32 = 192.0;
+const TILE_HEIGHT: f32 = 108.0;
+/// Above the game and the hidden pause menu, like the settings screens.
+const JOURNAL_Z_INDEX: i32 = 60;
+
+#[derive(Component)]
+struct OnJournalScreen;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum JournalButton {
+    /// Shows the photo at this index in the [`Journal`] full size.
+    Photo(usize),
+    /// Closes the photo shown full size.
+    Close,
+    Back,
+}
+
+/// Where a photo goes in the grid, by its index in the [`Journal`].
+#[derive(Component)]
+struct Thumbnail(usize);
+
+/// A photo being read from disk for its [`Thumbnail`].
+#[derive(Component)]
+struct Developing(Task<Option<Image>>);
+
+/// A photo shown full size over the grid.
+#[derive(Component)]
+struct FullPhoto;
+
+/// Whether the HUD was hidden for a photo taken last frame and should come back.
+#[derive(Resource, Debug, Default)]
+struct HiddenForPhoto(bool);
+
+pub fn journal_plugin(app: &mut App) {
+    track_screen::<OnJournalScreen>(app, MenuState::Journal);
+    app.init_resource::<HiddenForPhoto>()
+        .add_systems(
+            OnEnter(AppState::Game),
+            clear_old_photos.run_if(starting_run),
+        )
+        .add_systems(
+            Update,
+            (
+                show_hud_after_photo.run_if(|hidden: Res<HiddenForPhoto>| hidden.0),
+                take_photos.run_if(on_event::<PhotoRequest>),
+            )
+                .chain(),
+        )
+        .add_systems(
+            OnEnter(MenuState::Journal),
+            (journal_setup, slide_in::<OnJournalScreen>).chain(),
+        )
+        .add_systems(OnExit(MenuState::Journal), slide_out::<OnJournalScreen>)
+        .add_systems(
+            Update,
+            (
+                journal_input,
+                journal_action,
+                develop_thumbnails,
+                journal_button_colors,
+            )
+                .chain()
+                .run_if(in_state(MenuState::Journal)),
+        );
+}
+
+// A new run starts a new journal. A quicksave from an earlier run loses its photos, which
+// then show as placeholders.
+fn clear_old_photos(profile: Res<ProfilePaths>) {
+    let dir = profile.file(JOURNAL_DIR);
+    if let Err(error) = std::fs::remove_dir_all(&dir)
+        && error.kind() != std::io::ErrorKind::NotFound
+    {
+        warn!("Couldn't clear {}: {error}", dir.display());
+    }
+}
+
+fn take_photos(
+    mut commands: Commands,
+    mut requests: EventReader<PhotoRequest>,
+    clock: Res<Clock>,
+    profile: Res<ProfilePaths>,
+    photo_hud: Res<PhotoHud>,
+    mut journal: ResMut<Journal>,
+    mut hidden: ResMut<HiddenForPhoto>,
+    mut hud: Query<&mut Visibility, With<HudRoot>>,
+) {
+    let dir = profile.file(JOURNAL_DIR);
+    for request in requests.read() {
+        let (photo, evicted) = journal.add(request, clock.day);
+        info!("Taking a photo for the journal: {}", photo.label);
+        if let Some(evicted) = evicted {
+            let path = dir.join(&evicted.file);
+            if let Err(error) = std::fs::remove_file(&path)
+                && error.kind() != std::io::ErrorKind::NotFound
+            {
+                warn!("Couldn't remove {}: {error}", path.display());
+            }
+        }
+        commands
+            .spawn(Screenshot::primary_window())
+            .observe(develop_photo(dir.join(&photo.file)));
+    }
+    if *photo_hud == PhotoHud::Hidden {
+        for mut visibility in &mut hud {
+            *visibility = Visibility::Hidden;
+        }
+        hidden.0 = true;
+    }
+}
+
+// The frame the photo was taken of has gone to the GPU by now, so the HUD can come back
+// as it was.
+fn show_hud_after_photo(mut hidden: ResMut<HiddenForPhoto>, mut hud: ResMut<HudHidden>) {
+    hidden.0 = false;
+    hud.set_changed();
+}
+
+fn develop_photo(path: PathBuf) -> impl FnMut(Trigger<ScreenshotCaptured>, ResMut<Saves>) {
+    move |trigger, mut saves| {
+        let image = trigger.event().0.clone();
+        saves.save_bytes(path.clone(), move || encode_photo(image));
+    }
+}
+
+fn encode_photo(image: Image) -> Result<Vec<u8>, String> {
+    // The alpha channel holds brightness with HDR on, so it's dropped.
+    let photo = image
+        .try_into_dynamic()
+        .map_err(|error| error.to_string())?
+        .thumbnail(PHOTO_WIDTH, PHOTO_HEIGHT)
+        .to_rgb8();
+    let mut bytes = Cursor::new(Vec::new());
+    photo
+        .write_to(&mut bytes, image::ImageFormat::Png)
+        .map_err(|error| error.to_string())?;
+    Ok(bytes.into_inner())
+}
+
+fn read_photo(path: PathBuf) -> Option<Image> {
+    let bytes = std::fs::read(&path).ok()?;
+    Image::from_buffer(
+        &bytes,
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .inspect_err(|error| warn!("Couldn't read {}: {error}", path.display()))
+    .ok()
+}
+
+fn journal_setup(
+    mut commands: Commands,
+    journal: Res<Journal>,
+    profile: Res<ProfilePaths>,
+    scale: Res<LayoutScale>,
+    assets: Res<AssetServer>,
+) {
+    let font = ui_font(&assets);
+    let text_font = |font_size| TextFont {
+        font: font.clone(),
+        font_size: scale.size(font_size),
+        ..default()
+    };
+    let dir = profile.file(JOURNAL_DIR);
+    let pool = AsyncComputeTaskPool::get();
+
+    let root = commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            OnJournalScreen,
+            GlobalZIndex(JOURNAL_Z_INDEX),
+        ))
+        .id();
+    let panel = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: scale.px(12.0),
+                padding: UiRect::all(scale.px(24.0)),
+                max_width: Val::Vw(90.0),
+                ..default()
+            },
+            BackgroundColor(DARKER),
+            ChildOf(root),
+        ))
+        .id();
+    commands.spawn((
+        Text::new("Journal"),
+        text_font(33.0),
+        TextColor(YELLOW),
+        ChildOf(panel),
+    ));
+    if journal.photos.is_empty() {
+        commands.spawn((
+            Text::new("No photos yet. Milestones like your first job will show up here."),
+            text_font(16.0),
+            TextColor(LIGHT),
+            ChildOf(panel),
+        ));
+    }
+    let grid = commands
+        .spawn((
+            scrollable(Node {
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::Center,
+                column_gap: scale.px(12.0),
+                row_gap: scale.px(12.0),
+                max_height: Val::Vh(70.0),
+                ..default()
+            }),
+            ChildOf(panel),
+        ))
+        .id();
+    for (index, photo) in journal.photos.iter().enumerate() {
+        let path = dir.join(&photo.file);
+        let caption = if photo.milestone { YELLOW } else { LIGHT };
+        commands.spawn((
+            Button,
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: scale.px(4.0),
+                padding: UiRect::all(scale.px(6.0)),
+                width: scale.px(TILE_WIDTH + 12.0),
+                ..default()
+            },
+            BackgroundColor(DARK),
+            JournalButton::Photo(index),
+            ChildOf(grid),
+            children![
+                (
+                    Node {
+                        width: scale.px(TILE_WIDTH),
+                        height: scale.px(TILE_HEIGHT),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BackgroundColor(DARKER),
+                    Thumbnail(index),
+                    Developing(pool.spawn(async move { read_photo(path) })),
+                    children![(Text::new("..."), text_font(14.0), TextColor(LIGHT))],
+                ),
+                (
+                    Text::new(format!("Day {}: {}", photo.day, photo.label)),
+                    text_font(14.0),
+                    TextColor(caption),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                ),
+            ],
+        ));
+    }
+    commands.spawn((
+        Button,
+        Node {
+            width: scale.px(200.0),
+            height: scale.px(50.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(DARK),
+        JournalButton::Back,
+        ChildOf(panel),
+        children![(Text::new("Back"), text_font(24.0), TextColor(LIGHT))],
+    ));
+}
+
+fn develop_thumbnails(
+    mut commands: Commands,
+    mut thumbnails: Query<(Entity, &mut Developing)>,
+    mut images: ResMut<Assets<Image>>,
+    scale: Res<LayoutScale>,
+    assets: Res<AssetServer>,
+) {
+    for (entity, mut developing) in &mut thumbnails {
+        let Some(image) = check_ready(&mut developing.0) else {
+            continue;
+        };
+        let mut thumbnail = commands.entity(entity);
+        thumbnail
+            .remove::<Developing>()
+            .despawn_related::<Children>();
+        match image {
+            Some(image) => {
+                thumbnail.insert(ImageNode::new(images.add(image)));
+            }
+            None => {
+                thumbnail.with_child((
+                    Text::new("Photo missing"),
+                    TextFont {
+                        font: ui_font(&assets),
+                        font_size: scale.size(14.0),
+                        ..default()
+                    },
+                    TextColor(LIGHT.with_alpha(0.6)),
+                ));
+            }
+        }
+    }
+}
+
+fn journal_input(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    full: Query<Entity, With<FullPhoto>>,
+    mut history: ResMut<MenuHistory>,
+    mut nav: ScreenNav,
+) {
+    let back = keyboard_input.just_pressed(KeyCode::Escape)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(GamepadButton::East));
+    if !back {
+        return;
+    }
+    if full.is_empty() {
+        history.back(&mut nav);
+    }
+    for entity in &full {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn journal_action(
+    mut commands: Commands,
+    mut presses: ButtonPresses<&JournalButton>,
+    journal: Res<Journal>,
+    thumbnails: Query<(&Thumbnail, Option<&ImageNode>)>,
+    full: Query<Entity, With<FullPhoto>>,
+    mut history: ResMut<MenuHistory>,
+    mut nav: ScreenNav,
+    scale: Res<LayoutScale>,
+    assets: Res<AssetServer>,
+) {
+    for button in presses.read() {
+        match *button {
+            JournalButton::Photo(index) => {
+                let Some(photo) = journal.photos.get(index) else {
+                    continue;
+                };
+                let image = thumbnails
+                    .iter()
+                    .find(|(thumbnail, _)| thumbnail.0 == index)
+                    .and_then(|(_, image)| image.cloned());
+                let font = TextFont {
+                    font: ui_font(&assets),
+                    font_size: scale.size(20.0),
+                    ..default()
+                };
+                let full = commands
+                    .spawn((
+                        Button,
+                        Node {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            position_type: PositionType::Absolute,
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            justify_content: JustifyContent::Center,
+                            row_gap: scale.px(12.0),
+                            ..default()
+                        },
+                        BackgroundColor(Color::BLACK.with_alpha(0.85)),
+                        GlobalZIndex(JOURNAL_Z_INDEX + 1),
+                        JournalButton::Close,
+                        FullPhoto,
+                        OnJournalScreen,
+                    ))
+                    .id();
+                match image {
+                    Some(image) => commands.spawn((
+                        Node {
+                            max_width: Val::Vw(90.0),
+                            max_height: Val::Vh(80.0),
+                            ..default()
+                        },
+                        image,
+                        ChildOf(full),
+                    )),
+                    None => commands.spawn((
+                        Text::new("This photo is missing"),
+                        font.clone(),
+                        TextColor(LIGHT.with_alpha(0.6)),
+                        ChildOf(full),
+                    )),
+                };
+                commands.spawn((
+                    Text::new(format!("Day {}: {}", photo.day, photo.label)),
+                    font,
+                    TextColor(if photo.milestone { YELLOW } else { LIGHT }),
+                    ChildOf(full),
+                ));
+            }
+            JournalButton::Close => {
+                for entity in &full {
+                    commands.entity(entity).despawn();
+                }
+            }
+            JournalButton::Back => history.back(&mut nav),
+        }
+    }
+}
+
+fn journal_button_colors(
+    mut buttons: Query<(&Interaction, &JournalButton, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, button, mut background) in &mut buttons {
+        if *button == JournalButton::Close {
+            continue;
+        }
+        background.0 = match interaction {
+            Interaction::None => DARK,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
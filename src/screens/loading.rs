@@ -0,0 +1,388 @@
+//! Loading the assets the game needs before the menu, and a screen explaining what's
+//! missing when some can't be found.
+//!
+//! The assets folder is looked for before the app starts, next to the executable and then
+//! in the working directory, and the first one holding every required file is used. When
+//! none does, the game goes straight to the error screen, which lists where it looked.
+
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    app::AppExit,
+    asset::{LoadState, UntypedAssetId, io::file::FileAssetReader},
+    prelude::*,
+};
+
+use super::button_press::ButtonPresses;
+use super::loss_feedback::{LOSS_FEEDBACK_PATH, LossFeedbackConfig};
+use super::nav::{ScreenNav, TransitionCause};
+use super::virtual_keyboard::{KEYBOARD_PATH, KeyboardLayouts};
+use crate::fonts::{FALLBACK_FONT, UI_FONT_PATH};
+use crate::palette::{BLUE, LIGHT};
+use crate::sim::calendar::{CALENDAR_PATH, Calendar};
+use crate::sim::catalog::{AREA_CATALOG_PATH, AreaCatalogData};
+use crate::sim::charity::{CHARITY_PATH, CharityConfig};
+use crate::sim::jobs::{JOB_MARKET_PATH, JobMarketData};
+use crate::sim::landlord::{LANDLORD_PATH, LandlordConfig};
+use crate::sim::market::{MARKET_PATH, MarketConfig};
+use crate::sim::new_game_plus::{NEW_GAME_PLUS_PATH, NewGamePlusConfig};
+use crate::sim::passive_income::{PASSIVE_INCOME_PATH, PassiveIncomeConfig};
+use crate::sim::pet::{PET_CONFIG_PATH, PetConfig};
+use crate::sim::scenario::{SCENARIOS_PATH, Scenarios};
+use crate::sim::vignettes::{VIGNETTES_PATH, Vignettes};
+use crate::{AppState, TEXT_COLOR, despawn_screen, track_screen};
+
+/// An asset the game can't run without.
+pub enum RequiredAsset {
+    Font(&'static str),
+    Image(&'static str),
+    AreaCatalog(&'static str),
+    JobMarket(&'static str),
+    PetConfig(&'static str),
+    LandlordConfig(&'static str),
+    MarketConfig(&'static str),
+    NewGamePlusConfig(&'static str),
+    Calendar(&'static str),
+    CharityConfig(&'static str),
+    LossFeedbackConfig(&'static str),
+    KeyboardLayouts(&'static str),
+    Vignettes(&'static str),
+    PassiveIncomeConfig(&'static str),
+    Scenarios(&'static str),
+}
+
+impl RequiredAsset {
+    fn path(&self) -> &'static str {
+        match self {
+            RequiredAsset::Font(path)
+            | RequiredAsset::Image(path)
+            | RequiredAsset::AreaCatalog(path)
+            | RequiredAsset::JobMarket(path)
+            | RequiredAsset::PetConfig(path)
+            | RequiredAsset::LandlordConfig(path)
+            | RequiredAsset::MarketConfig(path)
+            | RequiredAsset::NewGamePlusConfig(path)
+            | RequiredAsset::Calendar(path)
+            | RequiredAsset::CharityConfig(path)
+            | RequiredAsset::LossFeedbackConfig(path)
+            | RequiredAsset::KeyboardLayouts(path)
+            | RequiredAsset::Vignettes(path)
+            | RequiredAsset::PassiveIncomeConfig(path)
+            | RequiredAsset::Scenarios(path) => path,
+        }
+    }
+
+    fn load(&self, assets: &AssetServer) -> UntypedHandle {
+        match self {
+            RequiredAsset::Font(path) => assets.load::<Font>(*path).untyped(),
+            RequiredAsset::Image(path) => assets.load::<Image>(*path).untyped(),
+            RequiredAsset::AreaCatalog(path) => assets.load::<AreaCatalogData>(*path).untyped(),
+            RequiredAsset::JobMarket(path) => assets.load::<JobMarketData>(*path).untyped(),
+            RequiredAsset::PetConfig(path) => assets.load::<PetConfig>(*path).untyped(),
+            RequiredAsset::LandlordConfig(path) => assets.load::<LandlordConfig>(*path).untyped(),
+            RequiredAsset::MarketConfig(path) => assets.load::<MarketConfig>(*path).untyped(),
+            RequiredAsset::NewGamePlusConfig(path) => {
+                assets.load::<NewGamePlusConfig>(*path).untyped()
+            }
+            RequiredAsset::Calendar(path) => assets.load::<Calendar>(*path).untyped(),
+            RequiredAsset::CharityConfig(path) => assets.load::<CharityConfig>(*path).untyped(),
+            RequiredAsset::LossFeedbackConfig(path) => {
+                assets.load::<LossFeedbackConfig>(*path).untyped()
+            }
+            RequiredAsset::KeyboardLayouts(path) => assets.load::<KeyboardLayouts>(*path).untyped(),
+            RequiredAsset::Vignettes(path) => assets.load::<Vignettes>(*path).untyped(),
+            RequiredAsset::PassiveIncomeConfig(path) => {
+                assets.load::<PassiveIncomeConfig>(*path).untyped()
+            }
+            RequiredAsset::Scenarios(path) => assets.load::<Scenarios>(*path).untyped(),
+        }
+    }
+}
+
+/// Every asset that has to load before the menu opens. Assets missing from this list
+/// (like the art of areas that doesn't exist yet) are optional and render blank.
+pub const REQUIRED_ASSETS: &[RequiredAsset] = &[
+    RequiredAsset::Font(UI_FONT_PATH),
+    RequiredAsset::AreaCatalog(AREA_CATALOG_PATH),
+    RequiredAsset::JobMarket(JOB_MARKET_PATH),
+    RequiredAsset::PetConfig(PET_CONFIG_PATH),
+    RequiredAsset::LandlordConfig(LANDLORD_PATH),
+    RequiredAsset::MarketConfig(MARKET_PATH),
+    RequiredAsset::NewGamePlusConfig(NEW_GAME_PLUS_PATH),
+    RequiredAsset::Calendar(CALENDAR_PATH),
+    RequiredAsset::CharityConfig(CHARITY_PATH),
+    RequiredAsset::LossFeedbackConfig(LOSS_FEEDBACK_PATH),
+    RequiredAsset::KeyboardLayouts(KEYBOARD_PATH),
+    RequiredAsset::Vignettes(VIGNETTES_PATH),
+    RequiredAsset::PassiveIncomeConfig(PASSIVE_INCOME_PATH),
+    RequiredAsset::Scenarios(SCENARIOS_PATH),
+    RequiredAsset::Image("images/title.png"),
+    RequiredAsset::Image("images/locations/deadbeat.png"),
+    RequiredAsset::Image("images/locations/condo.png"),
+    RequiredAsset::Image("images/locations/luxury.png"),
+    RequiredAsset::Image("images/locations/mansion.png"),
+    RequiredAsset::Image("images/locations/restricted.png"),
+];
+
+/// Where the game reads its assets from, picked at startup.
+#[derive(Resource, Debug, Clone)]
+pub struct AssetsRoot {
+    /// The folder the asset server reads from.
+    pub path: PathBuf,
+    /// Every folder checked, in order.
+    pub searched: Vec<PathBuf>,
+}
+
+impl AssetsRoot {
+    /// Picks the first folder holding every required asset, or the one next to the
+    /// executable if none does.
+    pub fn find() -> Self {
+        let mut searched = vec![FileAssetReader::get_base_path().join("assets")];
+        if let Ok(dir) = std::env::current_dir()
+            && !searched.contains(&dir.join("assets"))
+        {
+            searched.push(dir.join("assets"));
+        }
+        let path = searched
+            .iter()
+            .find(|dir| missing_assets(dir).is_empty())
+            .unwrap_or(&searched[0])
+            .clone();
+        AssetsRoot { path, searched }
+    }
+
+    /// The path to give the [`AssetPlugin`].
+    pub fn file_path(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+}
+
+/// The required assets that aren't in `dir`.
+fn missing_assets(dir: &Path) -> Vec<&'static str> {
+    REQUIRED_ASSETS
+        .iter()
+        .map(RequiredAsset::path)
+        .filter(|path| !dir.join(path).is_file())
+        .collect()
+}
+
+/// Handles to the [`REQUIRED_ASSETS`], kept alive so the assets stay loaded.
+#[derive(Resource, Default)]
+struct LoadingAssets(Vec<(&'static str, UntypedHandle)>);
+
+/// Paths of required assets that failed to load.
+#[derive(Resource, Default)]
+struct FailedAssets(Vec<&'static str>);
+
+#[derive(Component)]
+struct OnAssetErrorScreen;
+
+#[derive(Component)]
+enum AssetErrorAction {
+    Retry,
+    Quit,
+}
+
+pub fn loading_plugin(app: &mut App) {
+    track_screen::<OnAssetErrorScreen>(app, AppState::AssetError);
+    app.init_resource::<LoadingAssets>()
+        .init_resource::<FailedAssets>()
+        .add_systems(Startup, log_assets_root)
+        .add_systems(OnEnter(AppState::Loading), start_loading)
+        .add_systems(Update, check_loading.run_if(in_state(AppState::Loading)))
+        .add_systems(OnEnter(AppState::AssetError), setup_asset_error_screen)
+        .add_systems(
+            OnExit(AppState::AssetError),
+            despawn_screen::<OnAssetErrorScreen>,
+        )
+        .add_systems(
+            Update,
+            (asset_error_action, asset_error_button_colors).run_if(in_state(AppState::AssetError)),
+        );
+}
+
+// `AssetsRoot::find` runs before logging is set up.
+fn log_assets_root(root: Res<AssetsRoot>) {
+    info!("Reading assets from {}", root.path.display());
+}
+
+fn start_loading(
+    mut loading: ResMut<LoadingAssets>,
+    mut failed: ResMut<FailedAssets>,
+    root: Res<AssetsRoot>,
+    assets: Res<AssetServer>,
+    mut nav: ScreenNav,
+) {
+    loading.0.clear();
+    // Files that aren't there would only fail one by one, so don't wait for them.
+    let missing = missing_assets(&root.path);
+    if !missing.is_empty() {
+        for path in &missing {
+            error!(
+                "Required asset {path} is missing from {}",
+                root.path.display()
+            );
+        }
+        failed.0 = missing;
+        nav.set_app(AppState::AssetError, TransitionCause::Automatic);
+        return;
+    }
+    loading.0 = REQUIRED_ASSETS
+        .iter()
+        .map(|asset| (asset.path(), asset.load(&assets)))
+        .collect();
+}
+
+fn check_loading(
+    loading: Res<LoadingAssets>,
+    mut failed: ResMut<FailedAssets>,
+    assets: Res<AssetServer>,
+    mut nav: ScreenNav,
+) {
+    if loading.0.is_empty() {
+        return;
+    }
+    let states = loading
+        .0
+        .iter()
+        .map(|(path, handle)| {
+            let id: UntypedAssetId = handle.id();
+            (*path, assets.load_state(id))
+        })
+        .collect::<Vec<_>>();
+
+    if states
+        .iter()
+        .any(|(_, state)| matches!(state, LoadState::NotLoaded | LoadState::Loading))
+    {
+        return;
+    }
+
+    let failed_paths = states
+        .into_iter()
+        .filter_map(|(path, state)| match state {
+            LoadState::Failed(error) => {
+                error!("Required asset {path} failed to load: {error}");
+                Some(path)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if failed_paths.is_empty() {
+        nav.set_app(AppState::Menu, TransitionCause::Automatic);
+    } else {
+        failed.0 = failed_paths;
+        nav.set_app(AppState::AssetError, TransitionCause::Automatic);
+    }
+}
+
+fn setup_asset_error_screen(
+    mut commands: Commands,
+    failed: Res<FailedAssets>,
+    root: Res<AssetsRoot>,
+) {
+    // The embedded font, as the UI font may be one of the missing files.
+    let text_font = |font_size| TextFont {
+        font: FALLBACK_FONT,
+        font_size,
+        ..default()
+    };
+
+    let missing = failed
+        .0
+        .iter()
+        .map(|path| format!("- {path}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let searched = root
+        .searched
+        .iter()
+        .map(|dir| format!("- {}", dir.display()))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let button = |action, label| {
+        (
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(20.0), Val::Px(10.0)),
+                margin: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            action,
+            children![(Text::new(label), text_font(20.0), TextColor(LIGHT))],
+        )
+    };
+
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(20.0),
+            ..default()
+        },
+        OnAssetErrorScreen,
+        BackgroundColor(Color::BLACK),
+        children![
+            (
+                Text::new("Some game files are missing"),
+                text_font(33.0),
+                TextColor(TEXT_COLOR),
+            ),
+            (Text::new(missing), text_font(16.0), TextColor(LIGHT)),
+            (
+                Text::new(format!(
+                    "The assets folder should be next to the game. It looked in:\n{searched}"
+                )),
+                text_font(16.0),
+                TextColor(LIGHT),
+            ),
+            (
+                Node::default(),
+                children![
+                    button(AssetErrorAction::Retry, "Retry"),
+                    button(AssetErrorAction::Quit, "Quit"),
+                ],
+            ),
+        ],
+    ));
+}
+
+fn asset_error_action(
+    mut presses: ButtonPresses<&AssetErrorAction>,
+    mut app_exit_events: EventWriter<AppExit>,
+    mut nav: ScreenNav,
+) {
+    for action in presses.read() {
+        match action {
+            // Loading an asset that previously failed tries again, so going back to the
+            // loading screen retries everything that's missing.
+            AssetErrorAction::Retry => {
+                nav.set_app(AppState::Loading, TransitionCause::Button);
+            }
+            AssetErrorAction::Quit => {
+                app_exit_events.write(AppExit::Success);
+            }
+        }
+    }
+}
+
+fn asset_error_button_colors(
+    interaction_query: Query<(&Interaction, &Children), (Changed<Interaction>, With<Button>)>,
+    mut text_query: Query<&mut TextColor>,
+) {
+    for (interaction, children) in &interaction_query {
+        for child in children.iter() {
+            if let Ok(mut color) = text_query.get_mut(child) {
+                color.0 = match interaction {
+                    Interaction::None => LIGHT,
+                    Interaction::Hovered | Interaction::Pressed => BLUE,
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Making big losses feel big: the screen's edges pulse red and the money in the HUD
+//! shakes, harder the more was lost, and the biggest losses get a sound of their own. How
+//! much it takes and how strong each tier is comes from [`LOSS_FEEDBACK_PATH`].
+//!
+//! Everything lost in the same frame is one loss, so a burst of expenses is one pulse.
+//! Going broke is always the biggest tier. With reduced motion the edges show a steady red
+//! border for a fixed time instead, and nothing shakes.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::game::MoneyUi;
+use super::overlay::MODAL_Z_INDEX;
+use crate::AppState;
+use crate::data::register_ron_resource;
+use crate::palette::RED;
+use crate::power::AnimationsActive;
+use crate::settings::ReducedMotion;
+use crate::sim::ledger::{Category, Transaction};
+use crate::timers::{RealUiTime, UiTimer};
+
+pub const LOSS_FEEDBACK_PATH: &str = "data/world.loss_feedback.ron";
+/// Above the game and the HUD, below the modal backdrop.
+const VIGNETTE_Z_INDEX: i32 = MODAL_Z_INDEX - 2;
+/// The vignette is a few nested borders, each fainter than the one outside it.
+const VIGNETTE_LAYERS: usize = 3;
+const VIGNETTE_LAYER_WIDTH: f32 = 10.0;
+/// How often the money swings back and forth while shaking, per second.
+const SHAKE_FREQUENCY: f32 = 30.0;
+const STING_FREQUENCY: f32 = 110.0;
+const STING_LENGTH: Duration = Duration::from_millis(350);
+
+/// How strongly one size of loss is called out.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct LossTier {
+    /// The smallest loss in one frame, in dollars, that reaches this tier.
+    pub min_loss: u32,
+    /// How opaque the red edges get at their peak.
+    pub intensity: f32,
+    /// How far the money shakes, in pixels.
+    pub shake: f32,
+    pub seconds: f32,
+    /// Whether the sting plays.
+    #[serde(default)]
+    pub sound: bool,
+}
+
+/// Loss feedback data.
+#[derive(Asset, TypePath, Resource, Deserialize, Clone, Debug)]
+pub struct LossFeedbackConfig {
+    pub tiers: Vec<LossTier>,
+    /// How long the border shows with reduced motion.
+    pub reduced_motion_seconds: f32,
+}
+
+impl LossFeedbackConfig {
+    /// The strongest tier `loss` reaches, if any.
+    pub fn tier(&self, loss: u32) -> Option<&LossTier> {
+        self.tiers
+            .iter()
+            .filter(|tier| loss >= tier.min_loss)
+            .max_by_key(|tier| tier.min_loss)
+    }
+
+    fn biggest(&self) -> Option<&LossTier> {
+        self.tiers.iter().max_by_key(|tier| tier.min_loss)
+    }
+}
+
+/// The pulse showing, and how far along it is.
+#[derive(Resource, Debug, Default)]
+struct LossPulse {
+    playing: Option<(LossTier, UiTimer)>,
+}
+
+#[derive(Resource)]
+struct LossSting(Handle<Pitch>);
+
+impl FromWorld for LossSting {
+    fn from_world(world: &mut World) -> Self {
+        LossSting(world.add_asset(Pitch::new(STING_FREQUENCY, STING_LENGTH)))
+    }
+}
+
+#[derive(Component)]
+struct Vignette;
+
+/// One border of the vignette, showing this share of the pulse's intensity.
+#[derive(Component)]
+struct VignetteLayer(f32);
+
+pub fn loss_feedback_plugin(app: &mut App) {
+    register_ron_resource::<LossFeedbackConfig>(app, &["loss_feedback.ron"]);
+    app.init_resource::<LossPulse>()
+        .init_resource::<LossSting>()
+        .add_systems(OnEnter(AppState::GameOver), pulse_on_game_over)
+        .add_systems(
+            Update,
+            (
+                pulse_on_losses.run_if(in_state(AppState::Game)),
+                animate_pulse,
+            )
+                .chain(),
+        );
+}
+
+fn pulse_on_losses(
+    mut commands: Commands,
+    mut transactions: EventReader<Transaction>,
+    config: Res<LossFeedbackConfig>,
+    mut pulse: ResMut<LossPulse>,
+    sting: Res<LossSting>,
+    reduced_motion: Res<ReducedMotion>,
+    vignettes: Query<(), With<Vignette>>,
+) {
+    // What the player chose to spend isn't a setback.
+    let loss: u32 = transactions
+        .read()
+        .filter_map(|transaction| match *transaction {
+            Transaction::Expense(Category::Purchases | Category::Donations, _) => None,
+            Transaction::Expense(_, amount) => Some(amount),
+            Transaction::Income(..) => None,
+        })
+        .sum();
+    if let Some(tier) = config.tier(loss) {
+        start_pulse(
+            &mut commands,
+            &mut pulse,
+            tier,
+            &config,
+            &sting,
+            reduced_motion.0,
+            !vignettes.is_empty(),
+        );
+    }
+}
+
+fn pulse_on_game_over(
+    mut commands: Commands,
+    config: Res<LossFeedbackConfig>,
+    mut pulse: ResMut<LossPulse>,
+    sting: Res<LossSting>,
+    reduced_motion: Res<ReducedMotion>,
+    vignettes: Query<(), With<Vignette>>,
+) {
+    if let Some(tier) = config.biggest() {
+        start_pulse(
+            &mut commands,
+            &mut pulse,
+            tier,
+            &config,
+            &sting,
+            reduced_motion.0,
+            !vignettes.is_empty(),
+        );
+    }
+}
+
+// A pulse already showing is only cut short by one at least as strong.
+fn start_pulse(
+    commands: &mut Commands,
+    pulse: &mut LossPulse,
+    tier: &LossTier,
+    config: &LossFeedbackConfig,
+    sting: &LossSting,
+    reduced_motion: bool,
+    has_vignette: bool,
+) {
+    if let Some((playing, _)) = &pulse.playing
+        && playing.min_loss > tier.min_loss
+    {
+        return;
+    }
+    let seconds = if reduced_motion {
+        config.reduced_motion_seconds
+    } else {
+        tier.seconds
+    };
+    pulse.playing = Some((
+        tier.clone(),
+        UiTimer::from_seconds(seconds.max(0.0), TimerMode::Once),
+    ));
+    if tier.sound {
+        commands.spawn((AudioPlayer(sting.0.clone()), PlaybackSettings::DESPAWN));
+    }
+    if !has_vignette {
+        spawn_vignette(commands);
+    }
+}
+
+fn spawn_vignette(commands: &mut Commands) {
+    let mut parent = commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            GlobalZIndex(VIGNETTE_Z_INDEX),
+            Pickable::IGNORE,
+            Vignette,
+        ))
+        .id();
+    for layer in 0..VIGNETTE_LAYERS {
+        let share = 1.0 / (1 << layer) as f32;
+        let child = commands
+            .spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    border: UiRect::all(Val::Px(VIGNETTE_LAYER_WIDTH)),
+                    ..default()
+                },
+                BorderColor(Color::NONE),
+                Pickable::IGNORE,
+                VignetteLayer(share),
+            ))
+            .id();
+        commands.entity(parent).add_child(child);
+        parent = child;
+    }
+}
+
+fn animate_pulse(
+    mut commands: Commands,
+    time: RealUiTime,
+    reduced_motion: Res<ReducedMotion>,
+    mut animations: ResMut<AnimationsActive>,
+    mut pulse: ResMut<LossPulse>,
+    vignettes: Query<Entity, With<Vignette>>,
+    mut layers: Query<(&mut BorderColor, &VignetteLayer)>,
+    mut money: Query<&mut Node, With<MoneyUi>>,
+) {
+    let Some((tier, timer)) = &mut pulse.playing else {
+        return;
+    };
+    animations.keep_awake();
+    if timer.tick(&time).finished() {
+        pulse.playing = None;
+        for vignette in &vignettes {
+            commands.entity(vignette).despawn();
+        }
+        for mut node in &mut money {
+            node.left = Val::Auto;
+        }
+        return;
+    }
+
+    let progress = timer.fraction();
+    // Flares up and dies down, or holds still with reduced motion.
+    let strength = if reduced_motion.0 {
+        1.0
+    } else {
+        (progress * std::f32::consts::PI).sin()
+    };
+    for (mut border, layer) in &mut layers {
+        border.0 = RED.with_alpha(tier.intensity * strength * layer.0);
+    }
+    let offset = if reduced_motion.0 {
+        0.0
+    } else {
+        tier.shake
+            * (1.0 - progress)
+            * (timer.elapsed_secs() * SHAKE_FREQUENCY * std::f32::consts::TAU).sin()
+    };
+    for mut node in &mut money {
+        node.left = Val::Px(offset);
+    }
+}
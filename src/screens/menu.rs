@@ -1,91 +1,486 @@
-use bevy::{
-    app::AppExit,
-    ecs::spawn::{SpawnIter, SpawnWith},
-    prelude::*,
-};
+use std::time::Duration;
+
+use bevy::{ecs::spawn::SpawnWith, prelude::*, window::PrimaryWindow};
 
-use crate::palette::{BLUE, CORAL, DARK, DARKER};
+use super::button_press::{ButtonPressed, ButtonPresses};
+use super::drift::Drift;
+use crate::audio::VolumeAdjusted;
+use crate::fonts::ui_font;
+use crate::palette::{BLUE, CORAL, DARK, DARKER, LIGHT, YELLOW};
+use crate::profiles::{FirstLaunch, ProfilePaths};
+use crate::save_file::SaveFiles;
+use crate::settings::{
+    ClockStyle, ContentFilter, ContentFilters, DisplayMode, HudLayout, LayoutMode, LayoutScale,
+    Locale, MoneyFeedback, PendingSettings, PhotoHud, ReducedMotion, Rumble, SafeArea,
+    SavedSettings, Settings, TextDirection, TextReadability,
+};
 
-use crate::{AppState, Volume, despawn_screen};
+use super::menu_slide::{slide_in, slide_out};
+use super::nav::{ScreenNav, TransitionCause};
+use super::overlay::{Overlay, no_modal_open};
+use super::quit_prompt::QuitRequested;
+use super::scroll::scrollable;
+use super::singleton::track_singleton;
+use crate::sim::challenge::{Challenge, IsoWeek};
+use crate::timers::{RealUiTime, UiTimer};
+use crate::{AppState, Volume, track_screen};
 // This plugin manages the menu, with 5 different screens:
-// - a main menu with "New Game", "Settings", "Quit"
-// - a settings menu with two submenus and a back button
-// - two settings screen with a setting that can be set and a back button
+// - a main menu with "New Game", "Weekly Challenge", "Settings", "Profiles", "Quit"
+// - the settings, with a list of categories and a back button on one side and the picked
+//   category's settings and an apply button on the other. Windows too narrow for both
+//   show one side at a time, see `NarrowSettings`
+// - the profiles screen, which lives in `profiles`
+// - the scenario picker "New Game" opens, which lives in `scenario_picker`
+// - the photo journal, opened from the pause menu, which lives in `journal`
+//
+// The settings screen edits `PendingSettings` and only touches the live settings on Apply,
+// except for the volume, which previews live and is restored on leaving the settings.
+//
+// Screens are opened through `MenuHistory`, so Back returns wherever the player came
+// from, including the pause menu when the settings were opened during a game.
+
+/// How long a new display mode stays without confirmation before it's reverted.
+const KEEP_DISPLAY_TIMEOUT: Duration = Duration::from_secs(10);
+/// How far the title art pans, in pixels, and how long one way takes.
+const TITLE_PAN_DISTANCE: f32 = 6.0;
+const TITLE_PAN_SECONDS: f32 = 20.0;
+const NEON_CYCLE_SECONDS: f32 = 3.0;
+/// Keeps the settings screens above the pause menu, which can open them mid-game.
+const SETTINGS_Z_INDEX: i32 = 60;
+/// Below this window width, at the regular layout scale, the settings show one pane at a
+/// time.
+const SETTINGS_TWO_PANE_MIN_WIDTH: f32 = 1320.0;
+
 pub fn menu_plugin(app: &mut App) {
+    track_screen::<OnMainMenuScreen>(app, MenuState::Main);
+    track_screen::<OnSettingsMenuScreen>(app, MenuState::Settings);
+    // One button of each setting shows as selected.
+    track_singleton::<(SelectedOption, SettingsPaneButton)>(app);
+    track_singleton::<(SelectedOption, Volume)>(app);
+    track_singleton::<(SelectedOption, DisplayMode)>(app);
+    track_singleton::<(SelectedOption, ClockStyle)>(app);
+    track_singleton::<(SelectedOption, HudLayout)>(app);
+    track_singleton::<(SelectedOption, MoneyFeedback)>(app);
+    track_singleton::<(SelectedOption, SafeArea)>(app);
+    track_singleton::<(SelectedOption, LayoutMode)>(app);
+    track_singleton::<(SelectedOption, TextReadability)>(app);
+    track_singleton::<(SelectedOption, Rumble)>(app);
+    track_singleton::<(SelectedOption, PhotoHud)>(app);
+    track_singleton::<(SelectedOption, GamblingFilter)>(app);
+    track_singleton::<(SelectedOption, CrimeFilter)>(app);
     app
         // At start, the menu is not enabled. This will be changed in `menu_setup` when
         // entering the `GameState::Menu` state.
         // Current screen in the menu is handled by an independent state from `GameState`
         .init_state::<MenuState>()
+        .init_resource::<MenuHistory>()
         .add_systems(OnEnter(AppState::Menu), menu_setup)
         // Systems to handle the main menu screen
-        .add_systems(OnEnter(MenuState::Main), main_menu_setup)
-        .add_systems(OnExit(MenuState::Main), despawn_screen::<OnMainMenuScreen>)
-        // Systems to handle the settings menu screen
-        .add_systems(OnEnter(MenuState::Settings), settings_menu_setup)
         .add_systems(
-            OnExit(MenuState::Settings),
-            despawn_screen::<OnSettingsMenuScreen>,
+            OnEnter(MenuState::Main),
+            (main_menu_setup, slide_in::<OnMainMenuScreen>).chain(),
+        )
+        .add_systems(Update, blink_neon_sign.run_if(in_state(MenuState::Main)))
+        .add_systems(OnExit(MenuState::Main), slide_out::<OnMainMenuScreen>)
+        // Systems to handle the settings screen
+        .init_resource::<SettingsCategory>()
+        .init_resource::<NarrowSettings>()
+        .init_resource::<SettingsFocus>()
+        .add_systems(
+            OnEnter(MenuState::Settings),
+            (
+                stage_settings,
+                pick_settings_layout,
+                settings_menu_setup,
+                build_settings_panes,
+                show_settings_focus,
+                slide_in::<OnSettingsMenuScreen>,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                (
+                    setting_button::<Volume>,
+                    setting_button::<DisplayMode>,
+                    setting_button::<ClockStyle>,
+                    setting_button::<HudLayout>,
+                    setting_button::<MoneyFeedback>,
+                    setting_button::<SafeArea>,
+                    setting_button::<LayoutMode>,
+                    setting_button::<TextReadability>,
+                    setting_button::<Rumble>,
+                    setting_button::<PhotoHud>,
+                    setting_button::<GamblingFilter>,
+                    setting_button::<CrimeFilter>,
+                ),
+                preview_volume.run_if(resource_exists_and_changed::<PendingSettings>),
+                show_keep_display_dialog.run_if(resource_added::<KeepDisplayCountdown>),
+                keep_display_countdown.run_if(resource_exists::<KeepDisplayCountdown>),
+            )
+                .chain()
+                .run_if(in_state(MenuState::Settings)),
         )
-        // Systems to handle the display settings screen
-        // Systems to handle the sound settings screen
-        .add_systems(OnEnter(MenuState::SettingsSound), sound_settings_menu_setup)
         .add_systems(
             Update,
-            setting_button::<Volume>.run_if(in_state(MenuState::SettingsSound)),
+            (
+                pick_settings_layout,
+                // The pause menu the settings were opened from is a modal too.
+                settings_focus_input.run_if(no_modal_open.or(in_state(Overlay::Pause))),
+                settings_pane_action,
+                build_settings_panes,
+                show_settings_focus.run_if(
+                    resource_changed::<SettingsFocus>
+                        .or(resource_changed::<SettingsCategory>)
+                        .or(resource_changed::<NarrowSettings>),
+                ),
+            )
+                .chain()
+                .before(menu_action)
+                .run_if(in_state(MenuState::Settings)),
         )
         .add_systems(
-            OnExit(MenuState::SettingsSound),
-            despawn_screen::<OnSoundSettingsMenuScreen>,
+            OnExit(MenuState::Settings),
+            (
+                restore_volume,
+                revert_display.run_if(resource_exists::<KeepDisplayCountdown>),
+                slide_out::<OnSettingsMenuScreen>,
+            ),
         )
         // Common systems to all screens that handles buttons behavior
         .add_systems(
             Update,
-            (menu_action, button_system).run_if(in_state(AppState::Menu)),
+            (
+                menu_action,
+                button_system,
+                (
+                    highlight_staged::<Volume>,
+                    highlight_staged::<DisplayMode>,
+                    highlight_staged::<ClockStyle>,
+                    highlight_staged::<HudLayout>,
+                    highlight_staged::<MoneyFeedback>,
+                    highlight_staged::<SafeArea>,
+                    highlight_staged::<LayoutMode>,
+                    highlight_staged::<TextReadability>,
+                    highlight_staged::<Rumble>,
+                    highlight_staged::<PhotoHud>,
+                    highlight_staged::<GamblingFilter>,
+                    highlight_staged::<CrimeFilter>,
+                )
+                    .run_if(resource_exists_and_changed::<PendingSettings>),
+            )
+                .chain()
+                .run_if(not(in_state(MenuState::Disabled))),
         );
 }
 
 // State used for the current menu screen
-#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
-enum MenuState {
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States, Reflect)]
+pub enum MenuState {
     Main,
     Settings,
-    SettingsSound,
+    Profiles,
+    Scenarios,
+    Journal,
     #[default]
     Disabled,
 }
 
+/// The menu screens the player came through, most recent last. [`MenuState::Disabled`]
+/// stands for the game, where the settings are reached from the pause menu.
+#[derive(Resource, Debug, Default)]
+pub struct MenuHistory {
+    screens: Vec<MenuState>,
+    /// Whether the last screen change went back, which slides screens the other way.
+    pub went_back: bool,
+}
+
+impl MenuHistory {
+    /// Opens `to`, remembering the current screen for [`MenuHistory::back`].
+    pub fn open(&mut self, nav: &mut ScreenNav, to: MenuState) {
+        self.screens.push(nav.menu());
+        self.went_back = false;
+        nav.set_menu(to, TransitionCause::Button);
+    }
+
+    /// Returns to the screen the current one was opened from.
+    pub fn back(&mut self, nav: &mut ScreenNav) {
+        let to = self.screens.pop().unwrap_or(MenuState::Main);
+        self.went_back = true;
+        nav.set_menu(to, TransitionCause::Button);
+    }
+}
+
 // Tag component used to tag entities added on the main menu screen
 #[derive(Component)]
 struct OnMainMenuScreen;
 
+// The blinking sign on the title screen
+#[derive(Component)]
+struct NeonSign;
+
 // Tag component used to tag entities added on the settings menu screen
 #[derive(Component)]
 struct OnSettingsMenuScreen;
 
-// Tag component used to tag entities added on the display settings menu screen
+// Tag component used to tag the "Keep these settings?" dialog
+#[derive(Component)]
+struct KeepDisplayDialog;
+
+#[derive(Component)]
+struct KeepDisplayText;
+
+// Running while a newly applied display mode waits for confirmation
+#[derive(Resource)]
+struct KeepDisplayCountdown {
+    previous: DisplayMode,
+    timer: UiTimer,
+}
+
+/// The settings the settings screen shows.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SettingsCategory {
+    #[default]
+    Sound,
+    Display,
+    Controls,
+    Accessibility,
+    Gameplay,
+}
+
+impl SettingsCategory {
+    const ALL: [SettingsCategory; 5] = [
+        SettingsCategory::Sound,
+        SettingsCategory::Display,
+        SettingsCategory::Controls,
+        SettingsCategory::Accessibility,
+        SettingsCategory::Gameplay,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SettingsCategory::Sound => "Sound",
+            SettingsCategory::Display => "Display",
+            SettingsCategory::Controls => "Controls",
+            SettingsCategory::Accessibility => "Accessibility",
+            SettingsCategory::Gameplay => "Gameplay",
+        }
+    }
+}
+
+// Whether the window is too narrow for the settings' two panes side by side, so they
+// show one at a time
+#[derive(Resource, Debug, Default, PartialEq, Eq)]
+struct NarrowSettings(bool);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum SettingsPane {
+    #[default]
+    Categories,
+    Content,
+}
+
+// Where keyboard and gamepad focus is on the settings screen. It's only shown once the
+// player moves it, so it doesn't get in the way of the mouse.
+#[derive(Resource, Debug, Default)]
+struct SettingsFocus {
+    pane: SettingsPane,
+    // The focused one of the content pane's `FocusRow`s
+    row: usize,
+    shown: bool,
+}
+
+// Tag component for the node holding the settings' panes, with the category and layout
+// they were last built for
+#[derive(Component, Default)]
+struct SettingsPanes(Option<(SettingsCategory, bool)>);
+
 #[derive(Component)]
-struct OnDisplaySettingsMenuScreen;
+struct CategoryList;
 
-// Tag component used to tag entities added on the sound settings menu screen
 #[derive(Component)]
-struct OnSoundSettingsMenuScreen;
+struct ContentPane;
+
+// A row of the content pane that keyboard and gamepad focus stops on
+#[derive(Component)]
+struct FocusRow;
+
+// Buttons that move around the settings' categories and panes
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsPaneButton {
+    Category(SettingsCategory),
+    // Back to the category list, on narrow windows
+    Categories,
+}
 
 // Tag component used to mark which setting is currently selected
 #[derive(Component)]
 struct SelectedOption;
 
-// All actions that can be triggered from a button click
-#[derive(Component)]
-enum MenuButtonAction {
+/// All actions that can be triggered from a button click. `menu_action` handles each
+/// without a catch-all, so a new one can't be left unhandled.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuButtonAction {
     Play,
+    WeeklyChallenge,
     Settings,
-    SettingsSound,
-    BackToMainMenu,
-    BackToSettings,
+    Profiles,
+    ApplySettings,
+    KeepDisplay,
+    RevertDisplay,
+    Back,
     Quit,
 }
 
+impl MenuButtonAction {
+    /// Every action, for `--self-test` to check that each has a button somewhere.
+    pub const ALL: [MenuButtonAction; 9] = [
+        MenuButtonAction::Play,
+        MenuButtonAction::WeeklyChallenge,
+        MenuButtonAction::Settings,
+        MenuButtonAction::Profiles,
+        MenuButtonAction::ApplySettings,
+        MenuButtonAction::KeepDisplay,
+        MenuButtonAction::RevertDisplay,
+        MenuButtonAction::Back,
+        MenuButtonAction::Quit,
+    ];
+}
+
+// A setting that is edited through `PendingSettings`, with one button per value
+trait StagedSetting: Component + PartialEq + Copy {
+    fn get(settings: &Settings) -> Self;
+    fn set(settings: &mut Settings, value: Self);
+}
+
+impl StagedSetting for Volume {
+    fn get(settings: &Settings) -> Self {
+        settings.volume
+    }
+
+    fn set(settings: &mut Settings, value: Self) {
+        settings.volume = value;
+    }
+}
+
+impl StagedSetting for DisplayMode {
+    fn get(settings: &Settings) -> Self {
+        settings.display
+    }
+
+    fn set(settings: &mut Settings, value: Self) {
+        settings.display = value;
+    }
+}
+
+impl StagedSetting for ClockStyle {
+    fn get(settings: &Settings) -> Self {
+        settings.clock
+    }
+
+    fn set(settings: &mut Settings, value: Self) {
+        settings.clock = value;
+    }
+}
+
+impl StagedSetting for HudLayout {
+    fn get(settings: &Settings) -> Self {
+        settings.hud_layout
+    }
+
+    fn set(settings: &mut Settings, value: Self) {
+        settings.hud_layout = value;
+    }
+}
+
+impl StagedSetting for MoneyFeedback {
+    fn get(settings: &Settings) -> Self {
+        settings.money_feedback
+    }
+
+    fn set(settings: &mut Settings, value: Self) {
+        settings.money_feedback = value;
+    }
+}
+
+impl StagedSetting for SafeArea {
+    fn get(settings: &Settings) -> Self {
+        settings.safe_area
+    }
+
+    fn set(settings: &mut Settings, value: Self) {
+        settings.safe_area = value;
+    }
+}
+
+impl StagedSetting for LayoutMode {
+    fn get(settings: &Settings) -> Self {
+        settings.layout_mode
+    }
+
+    fn set(settings: &mut Settings, value: Self) {
+        settings.layout_mode = value;
+    }
+}
+
+impl StagedSetting for TextReadability {
+    fn get(settings: &Settings) -> Self {
+        settings.text_readability
+    }
+
+    fn set(settings: &mut Settings, value: Self) {
+        settings.text_readability = value;
+    }
+}
+
+impl StagedSetting for Rumble {
+    fn get(settings: &Settings) -> Self {
+        settings.rumble
+    }
+
+    fn set(settings: &mut Settings, value: Self) {
+        settings.rumble = value;
+    }
+}
+
+impl StagedSetting for PhotoHud {
+    fn get(settings: &Settings) -> Self {
+        settings.photo_hud
+    }
+
+    fn set(settings: &mut Settings, value: Self) {
+        settings.photo_hud = value;
+    }
+}
+
+// The buttons of each content filter
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+struct GamblingFilter(ContentFilter);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+struct CrimeFilter(ContentFilter);
+
+impl StagedSetting for GamblingFilter {
+    fn get(settings: &Settings) -> Self {
+        GamblingFilter(settings.content_filters.gambling)
+    }
+
+    fn set(settings: &mut Settings, value: Self) {
+        settings.content_filters.gambling = value.0;
+    }
+}
+
+impl StagedSetting for CrimeFilter {
+    fn get(settings: &Settings) -> Self {
+        CrimeFilter(settings.content_filters.crime)
+    }
+
+    fn set(settings: &mut Settings, value: Self) {
+        settings.content_filters.crime = value.0;
+    }
+}
+
 fn button_system(
     mut interaction_query: Query<
         (&Interaction, &Children, Option<&SelectedOption>),
@@ -108,48 +503,131 @@ fn button_system(
     }
 }
 
-// This system updates the settings when a new value for a setting is selected, and marks
-// the button as the one currently selected
-fn setting_button<T: Resource + Component + PartialEq + Copy>(
-    mut interaction_query: Query<(&Interaction, &T, Entity), (Changed<Interaction>, With<Button>)>,
-    selected_query: Single<Entity, With<SelectedOption>>,
-    mut bg_colors: Query<&mut BackgroundColor, With<Button>>,
-    mut commands: Commands,
-    mut setting: ResMut<T>,
+// This system stages a new value when its button is pressed. Which button shows as
+// selected follows the staged value, see `highlight_staged`.
+fn setting_button<T: StagedSetting>(
+    mut interaction_query: Query<
+        (&Interaction, &T, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut presses: ButtonPresses<&T>,
+    mut pending: ResMut<PendingSettings>,
 ) {
-    let previously_selected_button = selected_query.into_inner();
-    for (interaction, button_setting, current_interacted_button) in interaction_query.iter_mut() {
+    for button_setting in presses.read() {
+        if T::get(&pending.staged) != *button_setting {
+            T::set(&mut pending.staged, *button_setting);
+        }
+    }
+    for (interaction, button_setting, mut background) in &mut interaction_query {
+        if T::get(&pending.staged) == *button_setting {
+            continue;
+        }
         match *interaction {
-            Interaction::Pressed if *setting != *button_setting => {
-                bg_colors.get_mut(previously_selected_button).unwrap().0 = DARKER;
-                bg_colors.get_mut(current_interacted_button).unwrap().0 = CORAL;
-                commands
-                    .entity(previously_selected_button)
-                    .remove::<SelectedOption>();
-                commands
-                    .entity(current_interacted_button)
-                    .insert(SelectedOption);
-                *setting = *button_setting;
-            }
-            Interaction::Hovered if *setting != *button_setting => {
-                bg_colors.get_mut(current_interacted_button).unwrap().0 = BLUE;
-            }
-            Interaction::None if *setting != *button_setting => {
-                bg_colors.get_mut(current_interacted_button).unwrap().0 = DARKER;
-            }
-            _ => {}
+            Interaction::Hovered => background.0 = BLUE,
+            Interaction::None => background.0 = DARKER,
+            Interaction::Pressed => {}
         }
     }
 }
 
-fn menu_setup(mut menu_state: ResMut<NextState<MenuState>>) {
-    menu_state.set(MenuState::Main);
+// Marks the button of each staged value as the selected one
+fn highlight_staged<T: StagedSetting>(
+    mut commands: Commands,
+    mut buttons: Query<(Entity, &T, &mut BackgroundColor), With<Button>>,
+    pending: Res<PendingSettings>,
+) {
+    let staged = T::get(&pending.staged);
+    for (entity, button_setting, mut background) in &mut buttons {
+        if *button_setting == staged {
+            background.0 = CORAL;
+            commands.entity(entity).insert(SelectedOption);
+        } else {
+            background.0 = DARKER;
+            commands.entity(entity).remove::<SelectedOption>();
+        }
+    }
+}
+
+// Undoes the volume preview, unless it was applied
+fn restore_volume(pending: Res<PendingSettings>, mut volume: ResMut<Volume>) {
+    volume.set_if_neq(pending.committed.volume);
+}
+
+// Starts a fresh edit from the live settings whenever the settings menu opens
+fn stage_settings(
+    mut commands: Commands,
+    volume: Res<Volume>,
+    display: Res<DisplayMode>,
+    clock: Res<ClockStyle>,
+    hud_layout: Res<HudLayout>,
+    money_feedback: Res<MoneyFeedback>,
+    safe_area: Res<SafeArea>,
+    layout_mode: Res<LayoutMode>,
+    text_readability: Res<TextReadability>,
+    rumble: Res<Rumble>,
+    photo_hud: Res<PhotoHud>,
+    content_filters: Res<ContentFilters>,
+) {
+    commands.insert_resource(PendingSettings::new(Settings {
+        volume: *volume,
+        display: *display,
+        clock: *clock,
+        hud_layout: *hud_layout,
+        money_feedback: *money_feedback,
+        safe_area: *safe_area,
+        layout_mode: *layout_mode,
+        text_readability: *text_readability,
+        rumble: *rumble,
+        photo_hud: *photo_hud,
+        content_filters: *content_filters,
+    }));
+}
+
+// The volume is previewed while it's staged, so the player hears what they pick
+fn preview_volume(
+    pending: Res<PendingSettings>,
+    mut volume: ResMut<Volume>,
+    mut adjusted: EventWriter<VolumeAdjusted>,
+) {
+    if volume.set_if_neq(pending.staged.volume) {
+        adjusted.write(VolumeAdjusted);
+    }
+}
+
+// Reopens the screen the game was started from. The first launch starts on the profiles,
+// so the player can make their own before anything is saved to the default one.
+fn menu_setup(
+    mut nav: ScreenNav,
+    mut history: ResMut<MenuHistory>,
+    mut first_launch: ResMut<FirstLaunch>,
+) {
+    let start = if first_launch.0 {
+        MenuState::Profiles
+    } else {
+        MenuState::Main
+    };
+    first_launch.0 = false;
+    let to = history
+        .screens
+        .pop()
+        .filter(|screen| *screen != MenuState::Disabled)
+        .unwrap_or(start);
+    history.screens.clear();
+    history.went_back = true;
+    nav.set_menu(to, TransitionCause::Automatic);
 }
 
-fn main_menu_setup(mut commands: Commands, assets: Res<AssetServer>) {
+fn main_menu_setup(
+    mut commands: Commands,
+    scale: Res<LayoutScale>,
+    locale: Res<Locale>,
+    profile: Res<ProfilePaths>,
+    assets: Res<AssetServer>,
+) {
+    let direction = locale.direction();
     let button_node = Node {
-        width: Val::Px(300.0),
-        height: Val::Px(65.0),
+        width: scale.px(300.0),
+        height: scale.px(65.0),
         margin: UiRect::all(Val::Px(0.0)),
         justify_content: JustifyContent::Center,
         align_items: AlignItems::Center,
@@ -157,8 +635,8 @@ fn main_menu_setup(mut commands: Commands, assets: Res<AssetServer>) {
     };
 
     let button_text_font = TextFont {
-        font_size: 33.0,
-        font: assets.load("fonts/PressStart2P-Regular.ttf"),
+        font_size: scale.size(33.0),
+        font: ui_font(&assets),
         ..default()
     };
 
@@ -169,74 +647,214 @@ fn main_menu_setup(mut commands: Commands, assets: Res<AssetServer>) {
             width: Val::Percent(100.0),
             height: Val::Percent(100.0),
             align_items: AlignItems::FlexEnd,
-            justify_content: JustifyContent::FlexEnd,
+            justify_content: direction.justify(JustifyContent::FlexEnd),
+            overflow: Overflow::clip(),
             ..default()
         },
-        ImageNode::new(bg),
         OnMainMenuScreen,
-        children![(
-            Node {
-                flex_direction: FlexDirection::Column,
-                align_items: AlignItems::Center,
-                margin: UiRect {
-                    left: Val::Percent(0.0),
-                    right: Val::Percent(19.5),
-                    top: Val::Percent(0.0),
-                    bottom: Val::Percent(10.0),
+        children![
+            title_backdrop(bg, ui_font(&assets)),
+            (
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: scale.px(12.0),
+                    left: scale.px(16.0),
+                    padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                    ..default()
                 },
-                ..default()
-            },
-            children![
-                (
-                    Button,
-                    button_node.clone(),
-                    MenuButtonAction::Play,
-                    children![(
-                        Text::new("New Game"),
-                        button_text_font.clone(),
-                        TextColor(DARKER),
-                    )]
-                ),
-                (
-                    Button,
-                    button_node.clone(),
-                    MenuButtonAction::Settings,
-                    children![(
-                        Text::new("Settings"),
-                        button_text_font.clone(),
-                        TextColor(DARKER),
-                    ),]
-                ),
-                (
-                    Button,
-                    button_node,
-                    MenuButtonAction::Quit,
-                    children![(Text::new("Quit"), button_text_font, TextColor(DARKER),),]
-                ),
-            ]
-        )],
+                BackgroundColor(DARKER.with_alpha(0.8)),
+                children![(
+                    Text::new(format!("Profile: {}", profile.name())),
+                    TextFont {
+                        font_size: scale.size(18.0),
+                        font: ui_font(&assets),
+                        ..default()
+                    },
+                    TextColor(LIGHT),
+                )],
+            ),
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    margin: direction.rect(UiRect {
+                        left: Val::Percent(0.0),
+                        right: Val::Percent(19.5),
+                        top: Val::Percent(0.0),
+                        bottom: Val::Percent(10.0),
+                    }),
+                    ..default()
+                },
+                children![
+                    (
+                        Button,
+                        button_node.clone(),
+                        MenuButtonAction::Play,
+                        children![(
+                            Text::new("New Game"),
+                            button_text_font.clone(),
+                            TextColor(DARKER),
+                        )]
+                    ),
+                    (
+                        Button,
+                        button_node.clone(),
+                        MenuButtonAction::WeeklyChallenge,
+                        children![(
+                            Text::new("Weekly Challenge"),
+                            button_text_font.clone(),
+                            TextColor(DARKER),
+                        )]
+                    ),
+                    (
+                        Button,
+                        button_node.clone(),
+                        MenuButtonAction::Settings,
+                        children![(
+                            Text::new("Settings"),
+                            button_text_font.clone(),
+                            TextColor(DARKER),
+                        ),]
+                    ),
+                    (
+                        Button,
+                        button_node.clone(),
+                        MenuButtonAction::Profiles,
+                        children![(
+                            Text::new("Profiles"),
+                            button_text_font.clone(),
+                            TextColor(DARKER),
+                        )]
+                    ),
+                    (
+                        Button,
+                        button_node,
+                        MenuButtonAction::Quit,
+                        children![(Text::new("Quit"), button_text_font, TextColor(DARKER),),]
+                    ),
+                ]
+            )
+        ],
     ));
 }
 
-fn settings_menu_setup(mut commands: Commands, assets: Res<AssetServer>) {
-    let button_node = Node {
-        width: Val::Px(200.0),
-        height: Val::Px(65.0),
-        margin: UiRect::all(Val::Px(20.0)),
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        ..default()
+/// The title art, panning slowly, with clouds drifting over the sky and a neon sign on
+/// the skyline. Everything stays clear of the button column in the bottom right.
+fn title_backdrop(image: Handle<Image>, font: Handle<Font>) -> impl Bundle {
+    let pan = Drift::new(
+        Vec2::new(-TITLE_PAN_DISTANCE / TITLE_PAN_SECONDS, 0.0),
+        Rect::new(-TITLE_PAN_DISTANCE, 0.0, 0.0, 0.0),
+    );
+    let cloud = |left: f32, drift: Drift| {
+        (
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(left),
+                top: Val::Percent(1.0),
+                ..default()
+            },
+            children![(
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: drift.start().0,
+                    top: drift.start().1,
+                    width: Val::Px(48.0),
+                    height: Val::Px(14.0),
+                    ..default()
+                },
+                BorderRadius::MAX,
+                BackgroundColor(LIGHT.with_alpha(0.85)),
+                drift,
+                children![(
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(12.0),
+                        top: Val::Px(-8.0),
+                        width: Val::Px(20.0),
+                        height: Val::Px(16.0),
+                        ..default()
+                    },
+                    BorderRadius::MAX,
+                    BackgroundColor(LIGHT.with_alpha(0.85)),
+                )],
+            )],
+        )
     };
-
-    let button_text_style = (
-        TextFont {
-            font_size: 33.0,
-            font: assets.load("fonts/PressStart2P-Regular.ttf"),
+    (
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
             ..default()
         },
-        TextColor(DARKER),
-    );
+        children![
+            (
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: pan.start().0,
+                    // Wide enough that panning never shows an edge.
+                    width: Val::Percent(101.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                ImageNode::new(image),
+                pan,
+            ),
+            cloud(
+                40.0,
+                Drift::new(Vec2::new(4.0, 0.0), Rect::new(0.0, 8.0, 220.0, 8.0))
+            ),
+            cloud(
+                72.0,
+                Drift::new(Vec2::new(-3.0, 0.0), Rect::new(0.0, 4.0, 180.0, 4.0))
+            ),
+            (
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(44.0),
+                    top: Val::Percent(60.0),
+                    padding: UiRect::axes(Val::Px(4.0), Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(DARKER.with_alpha(0.8)),
+                children![(
+                    Text::new("OPEN"),
+                    TextFont {
+                        font,
+                        font_size: 10.0,
+                        ..default()
+                    },
+                    TextColor(CORAL),
+                    NeonSign,
+                )],
+            ),
+        ],
+    )
+}
+
+// Flickers the neon sign off twice at the end of every cycle
+fn blink_neon_sign(
+    time: RealUiTime,
+    reduced_motion: Res<ReducedMotion>,
+    mut signs: Query<&mut TextColor, With<NeonSign>>,
+) {
+    let t = time.elapsed_secs() % NEON_CYCLE_SECONDS;
+    let off = !reduced_motion.0
+        && [(2.4, 2.55), (2.7, 2.85)]
+            .iter()
+            .any(|(start, end)| (*start..*end).contains(&t));
+    for mut color in &mut signs {
+        color.0 = if off { CORAL.with_alpha(0.25) } else { CORAL };
+    }
+}
 
+fn settings_menu_setup(
+    mut commands: Commands,
+    mut focus: ResMut<SettingsFocus>,
+    scale: Res<LayoutScale>,
+    locale: Res<Locale>,
+) {
+    *focus = SettingsFocus::default();
     commands.spawn((
         Node {
             width: Val::Percent(100.0),
@@ -246,139 +864,776 @@ fn settings_menu_setup(mut commands: Commands, assets: Res<AssetServer>) {
             ..default()
         },
         OnSettingsMenuScreen,
+        GlobalZIndex(SETTINGS_Z_INDEX),
         children![(
             Node {
-                flex_direction: FlexDirection::Column,
+                flex_direction: locale.direction().row(),
+                align_items: AlignItems::Start,
+                column_gap: scale.px(40.0),
+                ..default()
+            },
+            SettingsPanes::default(),
+        )],
+    ));
+}
+
+// Whether the window is too narrow for both panes of the settings side by side
+fn pick_settings_layout(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    scale: Res<LayoutScale>,
+    mut narrow: ResMut<NarrowSettings>,
+) {
+    let min_width = scale.size(SETTINGS_TWO_PANE_MIN_WIDTH);
+    narrow.set_if_neq(NarrowSettings(
+        windows.iter().any(|window| window.width() < min_width),
+    ));
+}
+
+// Fills the settings screen with the category list and the picked category's settings,
+// whenever either the category or the layout changed since they were last built
+fn build_settings_panes(
+    mut commands: Commands,
+    mut panes: Query<(Entity, &mut SettingsPanes)>,
+    category: Res<SettingsCategory>,
+    narrow: Res<NarrowSettings>,
+    pending: Res<PendingSettings>,
+    scale: Res<LayoutScale>,
+    locale: Res<Locale>,
+    assets: Res<AssetServer>,
+) {
+    let Ok((entity, mut built)) = panes.single_mut() else {
+        return;
+    };
+    let shown = (*category, narrow.0);
+    if built.0 == Some(shown) {
+        return;
+    }
+    built.0 = Some(shown);
+    let style = SettingsStyle::new(*scale, locale.direction(), &assets);
+    let mut panes = commands.entity(entity);
+    panes.despawn_related::<Children>();
+    panes.with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                CategoryList,
+            ))
+            .with_children(|list| {
+                for option in SettingsCategory::ALL {
+                    let button = SettingsPaneButton::Category(option);
+                    if option == *category {
+                        list.spawn((style.button(option.label(), button, BLUE), SelectedOption));
+                    } else {
+                        list.spawn(style.button(option.label(), button, DARKER));
+                    }
+                }
+                list.spawn(style.button("Back", MenuButtonAction::Back, DARKER));
+            });
+        parent
+            .spawn((
+                scrollable(Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    max_height: Val::Vh(90.0),
+                    ..default()
+                }),
+                ContentPane,
+            ))
+            .with_children(|content| {
+                content.spawn((Text::new(category.label()), style.text.clone()));
+                spawn_category_settings(content, *category, &pending.staged, &style);
+                content.spawn((
+                    style.button("Apply", MenuButtonAction::ApplySettings, DARKER),
+                    FocusRow,
+                ));
+                if narrow.0 {
+                    content.spawn((
+                        style.button("Back", SettingsPaneButton::Categories, DARKER),
+                        FocusRow,
+                    ));
+                }
+            });
+    });
+}
+
+// The rows of `category`'s settings
+fn spawn_category_settings(
+    content: &mut ChildSpawnerCommands,
+    category: SettingsCategory,
+    staged: &Settings,
+    style: &SettingsStyle,
+) {
+    match category {
+        SettingsCategory::Sound => {
+            content.spawn((volume_row(staged.volume, style), FocusRow));
+        }
+        SettingsCategory::Display => {
+            content.spawn((
+                style.row(
+                    "Window",
+                    [DisplayMode::Windowed, DisplayMode::Fullscreen]
+                        .map(|mode| (mode, mode.label())),
+                    staged.display,
+                ),
+                FocusRow,
+            ));
+            content.spawn((
+                style.row(
+                    "Layout",
+                    [LayoutMode::Auto, LayoutMode::Regular, LayoutMode::Compact]
+                        .map(|mode| (mode, mode.label())),
+                    staged.layout_mode,
+                ),
+                FocusRow,
+            ));
+            content.spawn((
+                style.row(
+                    "Margin",
+                    [SafeArea::None, SafeArea::Small, SafeArea::Large]
+                        .map(|area| (area, area.label())),
+                    staged.safe_area,
+                ),
+                FocusRow,
+            ));
+            content.spawn((
+                style.row(
+                    "HUD",
+                    [HudLayout::Bars, HudLayout::Corners].map(|layout| (layout, layout.label())),
+                    staged.hud_layout,
+                ),
+                FocusRow,
+            ));
+            content.spawn((
+                style.row(
+                    "Clock",
+                    [ClockStyle::Digital, ClockStyle::Analog, ClockStyle::Both]
+                        .map(|clock| (clock, clock.label())),
+                    staged.clock,
+                ),
+                FocusRow,
+            ));
+            content.spawn((
+                style.row(
+                    "Photos",
+                    [PhotoHud::Shown, PhotoHud::Hidden].map(|hud| (hud, hud.label())),
+                    staged.photo_hud,
+                ),
+                FocusRow,
+            ));
+        }
+        SettingsCategory::Controls => {
+            content.spawn((
+                style.row(
+                    "Rumble",
+                    [Rumble::On, Rumble::Off].map(|rumble| (rumble, rumble.label())),
+                    staged.rumble,
+                ),
+                FocusRow,
+            ));
+        }
+        SettingsCategory::Accessibility => {
+            content.spawn((
+                style.row(
+                    "Text",
+                    [
+                        TextReadability::Off,
+                        TextReadability::Shadow,
+                        TextReadability::StrongShadow,
+                        TextReadability::Panel,
+                    ]
+                    .map(|readability| (readability, readability.label())),
+                    staged.text_readability,
+                ),
+                FocusRow,
+            ));
+            content.spawn((
+                style.row(
+                    "Money",
+                    [
+                        MoneyFeedback::Popups,
+                        MoneyFeedback::Inline,
+                        MoneyFeedback::None,
+                    ]
+                    .map(|feedback| (feedback, feedback.label())),
+                    staged.money_feedback,
+                ),
+                FocusRow,
+            ));
+        }
+        SettingsCategory::Gameplay => {
+            let options = [ContentFilter::Shown, ContentFilter::Hidden];
+            content.spawn((
+                style.row(
+                    "Gambling",
+                    options.map(|filter| (GamblingFilter(filter), filter.label())),
+                    GamblingFilter(staged.content_filters.gambling),
+                ),
+                FocusRow,
+            ));
+            content.spawn((
+                style.row(
+                    "Crime",
+                    options.map(|filter| (CrimeFilter(filter), filter.label())),
+                    CrimeFilter(staged.content_filters.crime),
+                ),
+                FocusRow,
+            ));
+            content.spawn((
+                Text::new("Changes apply from the next new game"),
+                style.note.clone(),
+            ));
+        }
+    }
+}
+
+// The volume, as a row of small buttons from silent to loudest
+fn volume_row(staged: Volume, style: &SettingsStyle) -> impl Bundle {
+    let button_node = Node {
+        width: style.scale.px(30.0),
+        ..style.button_node.clone()
+    };
+    (
+        Node {
+            flex_direction: style.direction.row(),
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        Children::spawn((
+            Spawn((Text::new("Volume"), style.text.clone())),
+            SpawnWith(move |parent: &mut ChildSpawner| {
+                for volume in (0..10).map(Volume) {
+                    let mut entity = parent.spawn((
+                        Button,
+                        button_node.clone(),
+                        BackgroundColor(if volume == staged { CORAL } else { DARKER }),
+                        volume,
+                    ));
+                    if volume == staged {
+                        entity.insert(SelectedOption);
+                    }
+                }
+            }),
+        )),
+    )
+}
+
+// Picks a category from the list, or goes back to it on narrow windows
+fn settings_pane_action(
+    mut presses: ButtonPresses<&SettingsPaneButton>,
+    mut category: ResMut<SettingsCategory>,
+    mut focus: ResMut<SettingsFocus>,
+    narrow: Res<NarrowSettings>,
+) {
+    for button in presses.read() {
+        match *button {
+            SettingsPaneButton::Category(picked) => {
+                category.set_if_neq(picked);
+                if narrow.0 {
+                    focus.pane = SettingsPane::Content;
+                    focus.row = 0;
+                }
+            }
+            SettingsPaneButton::Categories => focus.pane = SettingsPane::Categories,
+        }
+    }
+}
+
+// Moves keyboard and gamepad focus around the settings. In the category list, up and down
+// pick the category, and right or confirm go into its settings. In there, up and down go
+// from row to row, left and right through a row's values, and confirm presses a button.
+// Back, or left past a row's first value, returns to the list, and back from the list
+// leaves the settings.
+fn settings_focus_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut focus: ResMut<SettingsFocus>,
+    mut category: ResMut<SettingsCategory>,
+    locale: Res<Locale>,
+    content: Query<&Children, With<ContentPane>>,
+    focus_rows: Query<(), With<FocusRow>>,
+    children: Query<&Children>,
+    buttons: Query<Has<SelectedOption>, With<Button>>,
+    menu_buttons: Query<(Entity, &MenuButtonAction), With<Interaction>>,
+    dialogs: Query<(), With<KeepDisplayDialog>>,
+    mut pressed: EventWriter<ButtonPressed>,
+) {
+    if !dialogs.is_empty() {
+        return;
+    }
+    let just_pressed = |key, button| {
+        keyboard_input.just_pressed(key) || gamepads.iter().any(|pad| pad.just_pressed(button))
+    };
+    let up = just_pressed(KeyCode::ArrowUp, GamepadButton::DPadUp);
+    let down = just_pressed(KeyCode::ArrowDown, GamepadButton::DPadDown);
+    let mut left = just_pressed(KeyCode::ArrowLeft, GamepadButton::DPadLeft);
+    let mut right = just_pressed(KeyCode::ArrowRight, GamepadButton::DPadRight);
+    let confirm = just_pressed(KeyCode::Enter, GamepadButton::South);
+    let back = just_pressed(KeyCode::Escape, GamepadButton::East);
+    if !(up || down || left || right || confirm || back) {
+        return;
+    }
+    if locale.direction().is_rtl() {
+        std::mem::swap(&mut left, &mut right);
+    }
+    focus.shown = true;
+
+    let Some(list) = content.iter().next() else {
+        return;
+    };
+    match focus.pane {
+        SettingsPane::Categories => {
+            let index = SettingsCategory::ALL
+                .iter()
+                .position(|option| option == &*category)
+                .unwrap_or_default();
+            let picked = if up {
+                index.checked_sub(1)
+            } else if down {
+                Some(index + 1)
+            } else {
+                None
+            };
+            if let Some(&picked) = picked.and_then(|index| SettingsCategory::ALL.get(index)) {
+                *category = picked;
+                focus.row = 0;
+            }
+            if right || confirm {
+                focus.pane = SettingsPane::Content;
+                focus.row = 0;
+            }
+            if back {
+                let back_button = menu_buttons
+                    .iter()
+                    .find(|(_, action)| matches!(action, MenuButtonAction::Back));
+                pressed.write_batch(back_button.map(|(button, _)| ButtonPressed(button)));
+            }
+        }
+        SettingsPane::Content => {
+            let rows: Vec<_> = list
+                .iter()
+                .filter(|entity| focus_rows.contains(*entity))
+                .collect();
+            if up {
+                focus.row = focus.row.saturating_sub(1);
+            }
+            if down && focus.row + 1 < rows.len() {
+                focus.row += 1;
+            }
+            if back {
+                focus.pane = SettingsPane::Categories;
+                return;
+            }
+            let Some(&row) = rows.get(focus.row) else {
+                return;
+            };
+            let row_buttons: Vec<_> = std::iter::once(row)
+                .chain(children.get(row).into_iter().flatten().copied())
+                .filter_map(|entity| Some((entity, buttons.get(entity).ok()?)))
+                .collect();
+            match row_buttons.iter().position(|(_, selected)| *selected) {
+                Some(0) if left => focus.pane = SettingsPane::Categories,
+                Some(selected) if left || right => {
+                    let next = if left { selected - 1 } else { selected + 1 };
+                    if let Some(&(button, _)) = row_buttons.get(next) {
+                        pressed.write(ButtonPressed(button));
+                    }
+                }
+                None if left => focus.pane = SettingsPane::Categories,
+                None if confirm => {
+                    pressed.write_batch(
+                        row_buttons
+                            .first()
+                            .map(|(button, _)| ButtonPressed(*button)),
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// Outlines whatever has keyboard and gamepad focus, once the player has moved it, and on
+// narrow windows shows only the pane that has it
+fn show_settings_focus(
+    mut commands: Commands,
+    focus: Res<SettingsFocus>,
+    category: Res<SettingsCategory>,
+    narrow: Res<NarrowSettings>,
+    mut lists: Query<&mut Node, (With<CategoryList>, Without<ContentPane>)>,
+    mut contents: Query<(&mut Node, &Children), With<ContentPane>>,
+    categories: Query<(Entity, &SettingsPaneButton)>,
+    rows: Query<(), With<FocusRow>>,
+    outlined: Query<
+        Entity,
+        (
+            With<Outline>,
+            Or<(With<FocusRow>, With<SettingsPaneButton>)>,
+        ),
+    >,
+) {
+    for entity in &outlined {
+        commands.entity(entity).remove::<Outline>();
+    }
+    let display = |pane| {
+        if narrow.0 && focus.pane != pane {
+            Display::None
+        } else {
+            Display::Flex
+        }
+    };
+    for mut node in &mut lists {
+        node.display = display(SettingsPane::Categories);
+    }
+    let mut focused = None;
+    for (mut node, children) in &mut contents {
+        node.display = display(SettingsPane::Content);
+        if focus.pane == SettingsPane::Content {
+            focused = children
+                .iter()
+                .filter(|entity| rows.contains(*entity))
+                .nth(focus.row);
+        }
+    }
+    if focus.pane == SettingsPane::Categories {
+        focused = categories
+            .iter()
+            .find(|(_, button)| **button == SettingsPaneButton::Category(*category))
+            .map(|(entity, _)| entity);
+    }
+    if focus.shown
+        && let Some(entity) = focused
+    {
+        commands
+            .entity(entity)
+            .insert(Outline::new(Val::Px(3.0), Val::Px(2.0), YELLOW));
+    }
+}
+
+// How the settings' buttons and text look at the current layout scale
+struct SettingsStyle {
+    scale: LayoutScale,
+    direction: TextDirection,
+    button_node: Node,
+    text: (TextFont, TextColor),
+    note: (TextFont, TextColor),
+}
+
+impl SettingsStyle {
+    fn new(scale: LayoutScale, direction: TextDirection, assets: &AssetServer) -> Self {
+        let font = ui_font(assets);
+        SettingsStyle {
+            scale,
+            direction,
+            button_node: Node {
+                width: scale.px(200.0),
+                height: scale.px(65.0),
+                margin: UiRect::all(scale.px(20.0)),
+                justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 ..default()
             },
-            Children::spawn(SpawnIter(
-                [
-                    (MenuButtonAction::SettingsSound, "Sound"),
-                    (MenuButtonAction::BackToMainMenu, "Back"),
-                ]
-                .into_iter()
-                .map(move |(action, text)| {
-                    (
+            text: (
+                TextFont {
+                    font_size: scale.size(33.0),
+                    font: font.clone(),
+                    ..default()
+                },
+                TextColor(DARKER),
+            ),
+            note: (
+                TextFont {
+                    font_size: scale.size(20.0),
+                    font,
+                    ..default()
+                },
+                TextColor(DARKER),
+            ),
+        }
+    }
+
+    // A button labelled in `color`
+    fn button(&self, label: &'static str, action: impl Component, color: Color) -> impl Bundle {
+        (
+            Button,
+            self.button_node.clone(),
+            action,
+            children![(Text::new(label), self.text.0.clone(), TextColor(color))],
+        )
+    }
+
+    fn row<T: StagedSetting, const N: usize>(
+        &self,
+        label: &'static str,
+        options: [(T, &'static str); N],
+        staged: T,
+    ) -> impl Bundle {
+        setting_row(
+            label,
+            options,
+            staged,
+            self.button_node.clone(),
+            self.text.clone(),
+            self.direction,
+        )
+    }
+}
+
+/// A labelled row with a button per value of a staged setting.
+fn setting_row<T: StagedSetting, const N: usize>(
+    label: &'static str,
+    options: [(T, &'static str); N],
+    staged: T,
+    button_node: Node,
+    text_style: (TextFont, TextColor),
+    direction: TextDirection,
+) -> impl Bundle {
+    (
+        Node {
+            flex_direction: direction.row(),
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        Children::spawn((
+            Spawn((Text::new(label), text_style.clone())),
+            SpawnWith(move |parent: &mut ChildSpawner| {
+                for (value, label) in options {
+                    let mut entity = parent.spawn((
                         Button,
                         button_node.clone(),
-                        action,
-                        children![(Text::new(text), button_text_style.clone())],
-                    )
-                })
-            ))
-        )],
-    ));
+                        if value == staged {
+                            BackgroundColor(CORAL)
+                        } else {
+                            BackgroundColor(DARKER)
+                        },
+                        value,
+                        children![(Text::new(label), text_style.clone())],
+                    ));
+                    if value == staged {
+                        entity.insert(SelectedOption);
+                    }
+                }
+            }),
+        )),
+    )
 }
 
-fn sound_settings_menu_setup(
+fn show_keep_display_dialog(
     mut commands: Commands,
-    volume: Res<Volume>,
+    scale: Res<LayoutScale>,
     assets: Res<AssetServer>,
 ) {
     let button_node = Node {
-        width: Val::Px(200.0),
-        height: Val::Px(65.0),
-        margin: UiRect::all(Val::Px(20.0)),
+        width: scale.px(200.0),
+        height: scale.px(65.0),
+        margin: UiRect::all(scale.px(20.0)),
         justify_content: JustifyContent::Center,
         align_items: AlignItems::Center,
         ..default()
     };
-    let button_text_style = (
-        TextFont {
-            font_size: 33.0,
-            font: assets.load("fonts/PressStart2P-Regular.ttf"),
-            ..default()
-        },
-        TextColor(DARKER),
-    );
+    let text_font = TextFont {
+        font_size: scale.size(33.0),
+        font: ui_font(&assets),
+        ..default()
+    };
 
-    let volume = *volume;
-    let button_node_clone = button_node.clone();
     commands.spawn((
         Node {
             width: Val::Percent(100.0),
             height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
             align_items: AlignItems::Center,
             justify_content: JustifyContent::Center,
             ..default()
         },
-        OnSoundSettingsMenuScreen,
-        children![(
-            Node {
-                flex_direction: FlexDirection::Column,
-                align_items: AlignItems::Center,
-                ..default()
-            },
-            children![
-                (
-                    Node {
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    Children::spawn((
-                        Spawn((Text::new("Volume"), button_text_style.clone())),
-                        SpawnWith(move |parent: &mut ChildSpawner| {
-                            for volume_setting in [0, 1, 2, 3, 4, 5, 6, 7, 8, 9] {
-                                let mut entity = parent.spawn((
-                                    Button,
-                                    Node {
-                                        width: Val::Px(30.0),
-                                        height: Val::Px(65.0),
-                                        ..button_node_clone.clone()
-                                    },
-                                    if volume == Volume(volume_setting) {
-                                        BackgroundColor(CORAL)
-                                    } else {
-                                        BackgroundColor(DARKER)
-                                    },
-                                    Volume(volume_setting),
-                                ));
-
-                                if volume == Volume(volume_setting) {
-                                    entity.insert(SelectedOption);
-                                }
-                            }
-                        })
-                    ))
-                ),
-                (
-                    Button,
-                    button_node,
-                    MenuButtonAction::BackToSettings,
-                    children![(Text::new("Back"), button_text_style)]
-                )
-            ]
-        )],
+        BackgroundColor(Color::BLACK.with_alpha(0.8)),
+        OnSettingsMenuScreen,
+        KeepDisplayDialog,
+        GlobalZIndex(SETTINGS_Z_INDEX + 1),
+        children![
+            (
+                Text::default(),
+                text_font.clone(),
+                TextColor(LIGHT),
+                KeepDisplayText
+            ),
+            (
+                Node {
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                children![
+                    (
+                        Button,
+                        button_node.clone(),
+                        BackgroundColor(DARKER),
+                        MenuButtonAction::KeepDisplay,
+                        children![(Text::new("Keep"), text_font.clone(), TextColor(DARK))]
+                    ),
+                    (
+                        Button,
+                        button_node,
+                        BackgroundColor(DARKER),
+                        MenuButtonAction::RevertDisplay,
+                        children![(Text::new("Revert"), text_font, TextColor(DARK))]
+                    ),
+                ]
+            ),
+        ],
     ));
 }
 
+fn keep_display_countdown(
+    mut commands: Commands,
+    time: RealUiTime,
+    mut countdown: ResMut<KeepDisplayCountdown>,
+    mut text: Query<&mut Text, With<KeepDisplayText>>,
+    dialog: Query<Entity, With<KeepDisplayDialog>>,
+    mut display: ResMut<DisplayMode>,
+    mut pending: ResMut<PendingSettings>,
+) {
+    let Ok(mut text) = text.single_mut() else {
+        return;
+    };
+    let Ok(dialog) = dialog.single() else {
+        return;
+    };
+    if countdown.timer.tick(&time).finished() {
+        info!("Display mode not confirmed, reverting");
+        *display = countdown.previous;
+        pending.staged.display = countdown.previous;
+        pending.committed.display = countdown.previous;
+        commands.remove_resource::<KeepDisplayCountdown>();
+        commands.entity(dialog).despawn();
+        return;
+    }
+    let seconds_left = countdown.timer.remaining_secs().ceil();
+    text.0 = format!("Keep these settings? Reverting in {seconds_left}s");
+}
+
+// Puts the previous display mode back when the player rejects the new one, or leaves
+// the screen without confirming it
+fn revert_display(
+    mut commands: Commands,
+    countdown: Res<KeepDisplayCountdown>,
+    mut display: ResMut<DisplayMode>,
+    mut pending: ResMut<PendingSettings>,
+) {
+    *display = countdown.previous;
+    pending.staged.display = countdown.previous;
+    pending.committed.display = countdown.previous;
+    commands.remove_resource::<KeepDisplayCountdown>();
+}
+
 fn menu_action(
-    interaction_query: Query<
-        (&Interaction, &MenuButtonAction),
-        (Changed<Interaction>, With<Button>),
-    >,
-    mut app_exit_events: EventWriter<AppExit>,
-    mut menu_state: ResMut<NextState<MenuState>>,
-    mut game_state: ResMut<NextState<AppState>>,
+    mut presses: ButtonPresses<&MenuButtonAction>,
+    mut quit: EventWriter<QuitRequested>,
+    mut nav: ScreenNav,
+    mut commands: Commands,
+    mut history: ResMut<MenuHistory>,
+    mut pending: Option<ResMut<PendingSettings>>,
+    mut volume: ResMut<Volume>,
+    mut display: ResMut<DisplayMode>,
+    mut clock: ResMut<ClockStyle>,
+    mut hud_layout: ResMut<HudLayout>,
+    mut money_feedback: ResMut<MoneyFeedback>,
+    mut safe_area: ResMut<SafeArea>,
+    mut layout_mode: ResMut<LayoutMode>,
+    (mut text_readability, mut rumble, mut photo_hud, mut content_filters, profile, mut files): (
+        ResMut<TextReadability>,
+        ResMut<Rumble>,
+        ResMut<PhotoHud>,
+        ResMut<ContentFilters>,
+        Res<ProfilePaths>,
+        SaveFiles,
+    ),
+    mut challenge: ResMut<Challenge>,
+    dialogs: Query<Entity, With<KeepDisplayDialog>>,
 ) {
-    for (interaction, menu_button_action) in &interaction_query {
-        if *interaction == Interaction::Pressed {
-            match menu_button_action {
-                MenuButtonAction::Quit => {
-                    app_exit_events.write(AppExit::Success);
+    for menu_button_action in presses.read() {
+        match menu_button_action {
+            MenuButtonAction::Quit => {
+                quit.write(QuitRequested);
+            }
+            MenuButtonAction::Play => {
+                history.open(&mut nav, MenuState::Scenarios);
+            }
+            MenuButtonAction::WeeklyChallenge => {
+                let week = IsoWeek::current();
+                info!("Starting the weekly challenge for {week}");
+                challenge.0 = Some(week);
+                nav.set_app(AppState::Game, TransitionCause::Button);
+                history.open(&mut nav, MenuState::Disabled);
+            }
+            MenuButtonAction::Settings => {
+                history.open(&mut nav, MenuState::Settings);
+            }
+            MenuButtonAction::Profiles => {
+                history.open(&mut nav, MenuState::Profiles);
+            }
+            MenuButtonAction::Back => {
+                history.back(&mut nav);
+            }
+            MenuButtonAction::ApplySettings => {
+                let Some(pending) = pending.as_deref_mut() else {
+                    continue;
+                };
+                pending.committed = pending.staged;
+                volume.set_if_neq(pending.staged.volume);
+                let clock_changed = clock.set_if_neq(pending.staged.clock);
+                let layout_changed = hud_layout.set_if_neq(pending.staged.hud_layout);
+                let feedback_changed = money_feedback.set_if_neq(pending.staged.money_feedback);
+                let area_changed = safe_area.set_if_neq(pending.staged.safe_area);
+                let mode_changed = layout_mode.set_if_neq(pending.staged.layout_mode);
+                let readability_changed =
+                    text_readability.set_if_neq(pending.staged.text_readability);
+                let rumble_changed = rumble.set_if_neq(pending.staged.rumble);
+                let photo_hud_changed = photo_hud.set_if_neq(pending.staged.photo_hud);
+                let filters_changed = content_filters.set_if_neq(pending.staged.content_filters);
+                if clock_changed
+                    || layout_changed
+                    || feedback_changed
+                    || area_changed
+                    || mode_changed
+                    || readability_changed
+                    || rumble_changed
+                    || photo_hud_changed
+                    || filters_changed
+                {
+                    let mut saved = SavedSettings::load(&profile, &mut files.corrupt);
+                    saved.clock_style = *clock;
+                    saved.hud_layout = *hud_layout;
+                    saved.money_feedback = *money_feedback;
+                    saved.safe_area = *safe_area;
+                    saved.layout_mode = *layout_mode;
+                    saved.text_readability = *text_readability;
+                    saved.rumble = *rumble;
+                    saved.photo_hud = *photo_hud;
+                    saved.content_filters = *content_filters;
+                    saved.save(&profile, &mut files.saves);
                 }
-                MenuButtonAction::Play => {
-                    game_state.set(AppState::Game);
-                    menu_state.set(MenuState::Disabled);
+                if *display != pending.staged.display {
+                    commands.insert_resource(KeepDisplayCountdown {
+                        previous: *display,
+                        timer: UiTimer::new(KEEP_DISPLAY_TIMEOUT, TimerMode::Once),
+                    });
+                    *display = pending.staged.display;
                 }
-                MenuButtonAction::Settings => menu_state.set(MenuState::Settings),
-                MenuButtonAction::SettingsSound => {
-                    menu_state.set(MenuState::SettingsSound);
+            }
+            MenuButtonAction::KeepDisplay => {
+                commands.remove_resource::<KeepDisplayCountdown>();
+                for dialog in &dialogs {
+                    commands.entity(dialog).despawn();
                 }
-                MenuButtonAction::BackToMainMenu => menu_state.set(MenuState::Main),
-                MenuButtonAction::BackToSettings => {
-                    menu_state.set(MenuState::Settings);
+            }
+            MenuButtonAction::RevertDisplay => {
+                commands.run_system_cached(revert_display);
+                for dialog in &dialogs {
+                    commands.entity(dialog).despawn();
                 }
             }
         }
@@ -0,0 +1,194 @@
+//! Menu screens slide and fade in when they open and out when they close, in the direction
+//! the player is going through [`MenuHistory`]: forward slides in from the right, back from
+//! the left. With reduced motion screens just appear and disappear.
+//!
+//! A closing screen loses its marker right away, so it's gone as far as the rest of the
+//! menu is concerned, and loses its buttons' [`Interaction`] so it can't be clicked on the
+//! way out. It's despawned by [`DespawnAfter`] once it has slid away. However fast the
+//! player clicks through, every screen is despawned when its slide ends.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::menu::MenuHistory;
+use crate::power::AnimationsActive;
+use crate::settings::ReducedMotion;
+use crate::timers::{RealUiTime, UiTimer};
+
+const SLIDE_DISTANCE: f32 = 40.0;
+const SLIDE_DURATION: Duration = Duration::from_millis(150);
+
+/// Despawns the entity when the timer ends.
+#[derive(Component, Debug)]
+pub struct DespawnAfter(pub UiTimer);
+
+/// Moves a screen's root from `from` to `to` pixels to the right of its place, fading it in
+/// or out.
+#[derive(Component, Debug)]
+struct Slide {
+    timer: UiTimer,
+    from: f32,
+    to: f32,
+    fading_in: bool,
+}
+
+/// The opacity colors have when the screen is fully shown.
+#[derive(Component, Debug)]
+pub struct ShownAlpha {
+    background: Option<f32>,
+    text: Option<f32>,
+    image: Option<f32>,
+}
+
+type Colors<'a> = (
+    Option<&'a BackgroundColor>,
+    Option<&'a TextColor>,
+    Option<&'a ImageNode>,
+);
+
+pub fn menu_slide_plugin(app: &mut App) {
+    app.add_systems(Update, (animate_slides, despawn_after).chain());
+}
+
+// Screens fading in or out twice keep the opacity they had before the first fade.
+fn remember_alpha(
+    commands: &mut Commands,
+    root: Entity,
+    children: &Query<&Children>,
+    colors: &Query<Colors, Without<ShownAlpha>>,
+) {
+    for entity in std::iter::once(root).chain(children.iter_descendants(root)) {
+        if let Ok((background, text, image)) = colors.get(entity) {
+            commands.entity(entity).insert(ShownAlpha {
+                background: background.map(|color| color.0.alpha()),
+                text: text.map(|color| color.0.alpha()),
+                image: image.map(|image| image.color.alpha()),
+            });
+        }
+    }
+}
+
+/// Slides in the screen marked with `T`. Runs after the screen's setup.
+pub fn slide_in<T: Component>(
+    mut commands: Commands,
+    history: Res<MenuHistory>,
+    reduced_motion: Res<ReducedMotion>,
+    roots: Query<Entity, (With<T>, Without<ChildOf>)>,
+    children: Query<&Children>,
+    colors: Query<Colors, Without<ShownAlpha>>,
+) {
+    if reduced_motion.0 {
+        return;
+    }
+    let side = if history.went_back { -1.0 } else { 1.0 };
+    for root in &roots {
+        remember_alpha(&mut commands, root, &children, &colors);
+        commands.entity(root).insert(Slide {
+            timer: UiTimer::new(SLIDE_DURATION, TimerMode::Once),
+            from: side * SLIDE_DISTANCE,
+            to: 0.0,
+            fading_in: true,
+        });
+    }
+}
+
+/// Slides out the screen marked with `T` and despawns it, in place of
+/// [`crate::despawn_screen`].
+pub fn slide_out<T: Component>(
+    mut commands: Commands,
+    history: Res<MenuHistory>,
+    reduced_motion: Res<ReducedMotion>,
+    screen: Query<(Entity, Has<ChildOf>, Option<&Node>), With<T>>,
+    children: Query<&Children>,
+    interactive: Query<(), With<Interaction>>,
+    colors: Query<Colors, Without<ShownAlpha>>,
+) {
+    let side = if history.went_back { 1.0 } else { -1.0 };
+    for (entity, has_parent, node) in &screen {
+        commands.entity(entity).remove::<T>();
+        if has_parent {
+            continue;
+        }
+        if reduced_motion.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        remember_alpha(&mut commands, entity, &children, &colors);
+        for descendant in children.iter_descendants(entity) {
+            if interactive.contains(descendant) {
+                commands.entity(descendant).remove::<Interaction>();
+            }
+        }
+        // A screen still sliding in leaves from wherever it got to.
+        let from = match node.map(|node| node.left) {
+            Some(Val::Px(left)) => left,
+            _ => 0.0,
+        };
+        commands.entity(entity).insert((
+            Slide {
+                timer: UiTimer::new(SLIDE_DURATION, TimerMode::Once),
+                from,
+                to: side * SLIDE_DISTANCE,
+                fading_in: false,
+            },
+            DespawnAfter(UiTimer::new(SLIDE_DURATION, TimerMode::Once)),
+        ));
+    }
+}
+
+fn animate_slides(
+    mut commands: Commands,
+    time: RealUiTime,
+    mut animations: ResMut<AnimationsActive>,
+    mut slides: Query<(Entity, &mut Slide, &mut Node)>,
+    children: Query<&Children>,
+    mut colors: Query<(
+        &ShownAlpha,
+        Option<&mut BackgroundColor>,
+        Option<&mut TextColor>,
+        Option<&mut ImageNode>,
+    )>,
+) {
+    for (root, mut slide, mut node) in &mut slides {
+        animations.keep_awake();
+        let finished = slide.timer.tick(&time).finished();
+        // Eases out: fast at first, settling at the end.
+        let eased = 1.0 - (1.0 - slide.timer.fraction()).powi(3);
+        node.left = Val::Px(slide.from + (slide.to - slide.from) * eased);
+        let opacity = if slide.fading_in { eased } else { 1.0 - eased };
+
+        for entity in std::iter::once(root).chain(children.iter_descendants(root)) {
+            let Ok((shown, background, text, image)) = colors.get_mut(entity) else {
+                continue;
+            };
+            if let (Some(mut background), Some(alpha)) = (background, shown.background) {
+                background.0.set_alpha(alpha * opacity);
+            }
+            if let (Some(mut text), Some(alpha)) = (text, shown.text) {
+                text.0.set_alpha(alpha * opacity);
+            }
+            if let (Some(mut image), Some(alpha)) = (image, shown.image) {
+                image.color.set_alpha(alpha * opacity);
+            }
+            if finished && slide.fading_in {
+                commands.entity(entity).remove::<ShownAlpha>();
+            }
+        }
+        if finished {
+            commands.entity(root).remove::<Slide>();
+        }
+    }
+}
+
+fn despawn_after(
+    mut commands: Commands,
+    time: RealUiTime,
+    mut timers: Query<(Entity, &mut DespawnAfter)>,
+) {
+    for (entity, mut despawn) in &mut timers {
+        if despawn.0.tick(&time).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -1,4 +1,53 @@
+pub mod actions;
+pub mod amount_input;
+pub mod area_theme;
+pub mod attract;
+pub mod background_animation;
+pub mod button_press;
+pub mod calendar;
+pub mod challenge;
+pub mod charity;
+pub mod clock_dial;
+pub mod command_palette;
+pub mod deposit;
+pub mod dialogue;
+pub mod drift;
+pub mod energy_bar;
+pub mod event_card;
+pub mod freelance;
 pub mod game;
 pub mod gameover;
+pub mod hold_confirm;
+pub mod hud;
+pub mod idle_dimming;
+pub mod image_warming;
+pub mod inbox;
+pub mod investments;
+pub mod job_board;
+pub mod journal;
+pub mod loading;
+pub mod loss_feedback;
 pub mod menu;
+pub mod menu_slide;
+pub mod money_feedback;
+pub mod nav;
+pub mod negotiation;
+pub mod net_worth;
+pub mod onboarding;
+pub mod overlay;
+pub mod pause;
+pub mod pet_status;
+pub mod profile_picker;
+pub mod quit_prompt;
+pub mod repair_prompt;
+pub mod report;
+pub mod savings_goal;
+pub mod scenario_picker;
+pub mod scroll;
+pub mod singleton;
+pub mod text_backing;
+pub mod text_field;
+pub mod tooltip;
+pub mod travel;
+pub mod virtual_keyboard;
 pub mod win;
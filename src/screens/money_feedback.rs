@@ -0,0 +1,203 @@
+//! Calling out money gained and lost, the way [`MoneyFeedback`] says: amounts floating up
+//! from the money in the HUD, or the money flashing with the change written next to it.
+//!
+//! One system turns the [`Transaction`]s into a change of money and hands it to whichever
+//! presentation is picked. Everything that arrives in the same frame is one change, and
+//! inline changes keep adding up while the last one still shows.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::game::MoneyUi;
+use super::singleton::Unique;
+use crate::palette::{BLUE, CORAL, LIGHT};
+use crate::settings::{Locale, MoneyFeedback, ReducedMotion};
+use crate::sim::ledger::Transaction;
+use crate::timers::{RealUiTime, UiTimer};
+use crate::{AppState, format};
+
+/// How long a popup floats, and how far up it goes.
+const POPUP_DURATION: Duration = Duration::from_millis(1500);
+const POPUP_RISE: f32 = 40.0;
+const POPUP_FONT_SIZE: f32 = 24.0;
+/// How long an inline change stays next to the money, fading out.
+const INLINE_DURATION: Duration = Duration::from_secs(2);
+/// How long the money shows in the color of the change.
+const FLASH_SECONDS: f32 = 0.4;
+/// The money's first span is the amount, the one added after it the inline change.
+const AMOUNT_SPAN: usize = 1;
+const DELTA_SPAN: usize = 2;
+
+/// The change shown inline, and how long it's been showing.
+#[derive(Resource, Debug)]
+struct InlineDelta {
+    amount: i64,
+    timer: UiTimer,
+}
+
+impl Default for InlineDelta {
+    fn default() -> Self {
+        InlineDelta {
+            amount: 0,
+            timer: UiTimer::run_out(INLINE_DURATION),
+        }
+    }
+}
+
+#[derive(Component)]
+struct MoneyPopup {
+    timer: UiTimer,
+    top: f32,
+}
+
+pub fn money_feedback_plugin(app: &mut App) {
+    app.init_resource::<InlineDelta>()
+        .add_systems(OnExit(AppState::Game), clear_feedback)
+        .add_systems(
+            Update,
+            (
+                add_delta_span,
+                money_feedback,
+                (show_inline_delta, float_popups),
+            )
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        );
+}
+
+fn color(amount: i64) -> Color {
+    if amount >= 0 { BLUE } else { CORAL }
+}
+
+// Gives the money text a span for the inline change.
+fn add_delta_span(mut commands: Commands, roots: Query<(Entity, &TextFont), Added<MoneyUi>>) {
+    for (root, font) in &roots {
+        commands
+            .entity(root)
+            .with_child((TextSpan::default(), font.clone(), TextColor(LIGHT)));
+    }
+}
+
+fn money_feedback(
+    mut commands: Commands,
+    mut transactions: EventReader<Transaction>,
+    mode: Res<MoneyFeedback>,
+    mut inline: ResMut<InlineDelta>,
+    mut money: Unique<(&ComputedNode, &GlobalTransform, &TextFont), With<MoneyUi>>,
+    locale: Res<Locale>,
+) {
+    let amount: i64 = transactions
+        .read()
+        .map(|transaction| match *transaction {
+            Transaction::Income(_, amount) => i64::from(amount),
+            Transaction::Expense(_, amount) => -i64::from(amount),
+        })
+        .sum();
+    if amount == 0 {
+        return;
+    }
+    match *mode {
+        MoneyFeedback::Popups => {
+            let Some((node, transform, font)) = money.get() else {
+                return;
+            };
+            // Starts at the end of the money text, in logical pixels.
+            let scale = node.inverse_scale_factor();
+            let corner = transform.translation().truncate() + node.size() * Vec2::new(0.5, -0.5);
+            let top = corner.y * scale;
+            commands.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(corner.x * scale),
+                    top: Val::Px(top),
+                    ..default()
+                },
+                Text::new(signed(amount, *locale)),
+                TextFont {
+                    font: font.font.clone(),
+                    font_size: POPUP_FONT_SIZE,
+                    ..default()
+                },
+                TextShadow::default(),
+                TextColor(color(amount)),
+                GlobalZIndex(1),
+                MoneyPopup {
+                    timer: UiTimer::new(POPUP_DURATION, TimerMode::Once),
+                    top,
+                },
+            ));
+        }
+        MoneyFeedback::Inline => {
+            if inline.timer.finished() {
+                inline.amount = 0;
+            }
+            inline.amount += amount;
+            inline.timer.reset();
+        }
+        MoneyFeedback::None => {}
+    }
+}
+
+fn signed(amount: i64, locale: Locale) -> String {
+    let sign = if amount >= 0 { "+" } else { "-" };
+    format!("{sign}{}", format::format_money(amount.abs() * 100, locale))
+}
+
+fn show_inline_delta(
+    time: RealUiTime,
+    mut inline: ResMut<InlineDelta>,
+    mut root: Unique<Entity, With<MoneyUi>>,
+    mut writer: TextUiWriter,
+    locale: Res<Locale>,
+) {
+    if inline.timer.finished() && !inline.is_changed() {
+        return;
+    }
+    let Some(root) = root.get() else {
+        return;
+    };
+    inline.timer.tick(&time);
+    let color = color(inline.amount);
+    let flashing = inline.timer.elapsed_secs() < FLASH_SECONDS && !inline.timer.finished();
+    *writer.color(root, AMOUNT_SPAN) = TextColor(if flashing { color } else { LIGHT });
+    *writer.text(root, DELTA_SPAN) = if inline.timer.finished() {
+        String::new()
+    } else {
+        let arrow = if inline.amount >= 0 { '▲' } else { '▼' };
+        let amount = format::format_int(inline.amount.abs(), *locale);
+        format!(" {arrow}{amount}")
+    };
+    *writer.color(root, DELTA_SPAN) =
+        TextColor(color.with_alpha(inline.timer.fraction_remaining()));
+}
+
+fn float_popups(
+    mut commands: Commands,
+    time: RealUiTime,
+    reduced_motion: Res<ReducedMotion>,
+    mut popups: Query<(Entity, &mut MoneyPopup, &mut Node, &mut TextColor)>,
+) {
+    for (entity, mut popup, mut node, mut color) in &mut popups {
+        if popup.timer.tick(&time).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let progress = popup.timer.fraction();
+        if !reduced_motion.0 {
+            node.top = Val::Px(popup.top - POPUP_RISE * progress);
+        }
+        color.0 = color.0.with_alpha(1.0 - progress);
+    }
+}
+
+fn clear_feedback(
+    mut commands: Commands,
+    mut inline: ResMut<InlineDelta>,
+    popups: Query<Entity, With<MoneyPopup>>,
+) {
+    *inline = InlineDelta::default();
+    for popup in &popups {
+        commands.entity(popup).despawn();
+    }
+}
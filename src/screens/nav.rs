@@ -0,0 +1,221 @@
+//! Screen transitions: [`ScreenNav`] to ask for one, [`ROUTES`] for the ones the game
+//! makes, and the markers each screen tags what it spawns with, see [`track_screen`].
+
+use std::collections::VecDeque;
+
+use bevy::{
+    ecs::{component::ComponentId, system::SystemParam},
+    prelude::*,
+};
+
+use super::menu::MenuState;
+use crate::AppState;
+
+/// How many transitions [`RecentTransitions`] keeps around.
+const RECENT_TRANSITIONS: usize = 5;
+
+/// Any screen the player can end up on, at either level of state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum Screen {
+    App(AppState),
+    Menu(MenuState),
+}
+
+/// A transition from one screen to another of the same level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Route {
+    pub from: Screen,
+    pub to: Screen,
+}
+
+const fn app(from: AppState, to: AppState) -> Route {
+    Route {
+        from: Screen::App(from),
+        to: Screen::App(to),
+    }
+}
+
+const fn menu(from: MenuState, to: MenuState) -> Route {
+    Route {
+        from: Screen::Menu(from),
+        to: Screen::Menu(to),
+    }
+}
+
+/// Every screen transition the game makes. [`ScreenNav`] warns about any that isn't listed,
+/// and `--self-test` walks them all, see [`crate::self_test`].
+pub const ROUTES: &[Route] = &[
+    app(AppState::Loading, AppState::Menu),
+    app(AppState::Loading, AppState::AssetError),
+    app(AppState::AssetError, AppState::Loading),
+    app(AppState::Menu, AppState::Game),
+    app(AppState::Game, AppState::Menu),
+    app(AppState::Game, AppState::GameOver),
+    app(AppState::Game, AppState::Win),
+    app(AppState::GameOver, AppState::Game),
+    app(AppState::GameOver, AppState::Menu),
+    app(AppState::Win, AppState::Game),
+    app(AppState::Win, AppState::Menu),
+    menu(MenuState::Disabled, MenuState::Main),
+    menu(MenuState::Disabled, MenuState::Profiles),
+    menu(MenuState::Main, MenuState::Settings),
+    menu(MenuState::Main, MenuState::Profiles),
+    menu(MenuState::Main, MenuState::Scenarios),
+    menu(MenuState::Main, MenuState::Disabled),
+    menu(MenuState::Settings, MenuState::Main),
+    menu(MenuState::Profiles, MenuState::Main),
+    menu(MenuState::Scenarios, MenuState::Main),
+    menu(MenuState::Scenarios, MenuState::Disabled),
+    // The pause menu's screens.
+    menu(MenuState::Disabled, MenuState::Settings),
+    menu(MenuState::Settings, MenuState::Disabled),
+    menu(MenuState::Disabled, MenuState::Journal),
+    menu(MenuState::Journal, MenuState::Disabled),
+];
+
+/// Screens with nothing of their own to show: loading has nothing to show yet, the menu's
+/// screens are [`MenuState`]s, and a disabled menu is the game.
+pub const SCREENLESS: &[Screen] = &[
+    Screen::App(AppState::Loading),
+    Screen::App(AppState::Menu),
+    Screen::Menu(MenuState::Disabled),
+];
+
+pub fn is_route(from: Screen, to: Screen) -> bool {
+    ROUTES.contains(&Route { from, to })
+}
+
+/// The marker components of each screen of `S`, filled in by [`track_screen`].
+#[derive(Resource)]
+pub struct ScreenMarkers<S: States>(pub Vec<ScreenMarker<S>>);
+
+impl<S: States> Default for ScreenMarkers<S> {
+    fn default() -> Self {
+        ScreenMarkers(Vec::new())
+    }
+}
+
+impl<S: States> ScreenMarkers<S> {
+    pub fn of(&self, state: &S) -> impl Iterator<Item = &ScreenMarker<S>> {
+        self.0.iter().filter(move |marker| marker.state == *state)
+    }
+}
+
+pub struct ScreenMarker<S> {
+    pub state: S,
+    pub name: &'static str,
+    pub component: ComponentId,
+}
+
+impl<S> ScreenMarker<S> {
+    /// How many entities have the marker.
+    pub fn count(&self, world: &World) -> usize {
+        world
+            .archetypes()
+            .iter()
+            .filter(|archetype| archetype.contains(self.component))
+            .map(|archetype| archetype.len())
+            .sum()
+    }
+}
+
+/// See [`crate::track_screen`].
+pub fn track_screen<T: Component, S: States>(app: &mut App, state: S) {
+    let component = app.world_mut().register_component::<T>();
+    let name = std::any::type_name::<T>()
+        .rsplit("::")
+        .next()
+        .unwrap_or_default();
+    app.world_mut()
+        .get_resource_or_init::<ScreenMarkers<S>>()
+        .0
+        .push(ScreenMarker {
+            state,
+            name,
+            component,
+        });
+}
+
+/// What triggered a screen transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum TransitionCause {
+    Button,
+    Hotkey,
+    Bankruptcy,
+    WinCondition,
+    /// A screen moving on by itself, like the menu opening its main screen.
+    Automatic,
+    /// Nobody touched the controls for a while, see [`super::attract`].
+    Idle,
+}
+
+/// Sent every time a system asks for a screen transition through [`ScreenNav`].
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct ScreenChanged {
+    pub from: Screen,
+    pub to: Screen,
+    pub cause: TransitionCause,
+}
+
+/// The last few [`ScreenChanged`] events, newest first.
+#[derive(Resource, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct RecentTransitions(VecDeque<ScreenChanged>);
+
+/// Requests screen transitions and reports them as [`ScreenChanged`] events.
+///
+/// Systems should use this instead of writing to `NextState` directly so that every
+/// transition carries the reason it happened.
+#[derive(SystemParam)]
+pub struct ScreenNav<'w> {
+    app_state: Res<'w, State<AppState>>,
+    next_app_state: ResMut<'w, NextState<AppState>>,
+    menu_state: Res<'w, State<MenuState>>,
+    next_menu_state: ResMut<'w, NextState<MenuState>>,
+    screen_changed: EventWriter<'w, ScreenChanged>,
+}
+
+impl ScreenNav<'_> {
+    pub fn menu(&self) -> MenuState {
+        *self.menu_state.get()
+    }
+
+    pub fn set_app(&mut self, to: AppState, cause: TransitionCause) {
+        self.changed(Screen::App(*self.app_state.get()), Screen::App(to), cause);
+        self.next_app_state.set(to);
+    }
+
+    pub fn set_menu(&mut self, to: MenuState, cause: TransitionCause) {
+        self.changed(
+            Screen::Menu(*self.menu_state.get()),
+            Screen::Menu(to),
+            cause,
+        );
+        self.next_menu_state.set(to);
+    }
+
+    fn changed(&mut self, from: Screen, to: Screen, cause: TransitionCause) {
+        if from != to && !is_route(from, to) {
+            warn!("{from:?} -> {to:?} isn't in nav::ROUTES, so the self-test doesn't walk it");
+        }
+        self.screen_changed.write(ScreenChanged { from, to, cause });
+    }
+}
+
+pub fn nav_plugin(app: &mut App) {
+    app.add_event::<ScreenChanged>()
+        .register_type::<RecentTransitions>()
+        .init_resource::<RecentTransitions>()
+        .add_systems(PostUpdate, log_transitions);
+}
+
+fn log_transitions(
+    mut screen_changed: EventReader<ScreenChanged>,
+    mut recent: ResMut<RecentTransitions>,
+) {
+    for event in screen_changed.read() {
+        info!(from = ?event.from, to = ?event.to, cause = ?event.cause, "screen changed");
+        recent.0.push_front(*event);
+        recent.0.truncate(RECENT_TRANSITIONS);
+    }
+}
@@ -0,0 +1,243 @@
+//! The rent negotiation card: the player picks how to ask the landlord for a lower rent,
+//! with the odds of each approach shown, or walks away.
+
+use bevy::{ecs::spawn::SpawnWith, prelude::*};
+
+use super::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use super::button_press::ButtonPresses;
+use super::overlay::{MODAL_Z_INDEX, Overlay};
+use crate::fonts::ui_font;
+use crate::format;
+use crate::palette::{BLUE, DARK, DARKER, LIGHT, YELLOW};
+use crate::settings::Locale;
+use crate::sim::{
+    choices::{Choice, ChoiceLog},
+    clock::Clock,
+    economy::{CurrentArea, HomeArea},
+    event_cards::CardChosen,
+    landlord::{
+        LandlordConfig, NegotiationOutcome, RentIncreaseCard, RentIncreases, RentTerms, negotiate,
+        negotiation_odds,
+    },
+    ledger::Ledger,
+    rng::GameRng,
+};
+use crate::toast::Toast;
+use crate::{despawn_screen, track_screen};
+
+const CARD_FONT_SIZE: f32 = 16.0;
+
+#[derive(Component)]
+struct NegotiationOverlay;
+
+/// A button on the card: the index of an approach in [`LandlordConfig::approaches`], or
+/// `None` to walk away.
+#[derive(Component, Debug, Clone, Copy)]
+struct NegotiationChoice(Option<usize>);
+
+/// Sent when the player picks an approach on the card, by its index in
+/// [`LandlordConfig::approaches`], or `None` when they walk away.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApproachPicked(pub Option<usize>);
+
+pub fn negotiation_plugin(app: &mut App) {
+    track_screen::<NegotiationOverlay>(app, Overlay::RentNegotiation);
+    app.add_event::<ApproachPicked>()
+        .add_systems(Update, offer_negotiation.in_set(ActionSet::Offer))
+        .add_systems(Update, negotiate_from_card)
+        .add_systems(Update, open_negotiation.run_if(in_state(Overlay::None)))
+        .add_systems(OnEnter(Overlay::RentNegotiation), setup_negotiation)
+        .add_systems(
+            OnExit(Overlay::RentNegotiation),
+            despawn_screen::<NegotiationOverlay>,
+        )
+        .add_systems(
+            Update,
+            ((negotiation_input, negotiate_rent).chain(), choice_colors)
+                .run_if(in_state(Overlay::RentNegotiation)),
+        );
+}
+
+fn offer_negotiation(
+    terms: Res<RentTerms>,
+    ledger: Res<Ledger>,
+    home: Res<HomeArea>,
+    current: Res<CurrentArea>,
+    mut actions: ResMut<AvailableActions>,
+) {
+    if current.0 == home.location && terms.can_negotiate(ledger.current.month) {
+        actions.offer(GameAction::NegotiateRent);
+    }
+}
+
+// Goes through the action like the button would, so the negotiation opens once the card
+// overlay has closed.
+fn negotiate_from_card(
+    mut chosen: EventReader<CardChosen>,
+    card: Res<RentIncreaseCard>,
+    mut pressed: EventWriter<ActionPressed>,
+) {
+    for CardChosen { card: id, button } in chosen.read() {
+        if card.0 == Some(*id) && *button == 0 {
+            pressed.write(ActionPressed(GameAction::NegotiateRent));
+        }
+    }
+}
+
+fn open_negotiation(
+    mut pressed: EventReader<ActionPressed>,
+    terms: Res<RentTerms>,
+    ledger: Res<Ledger>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        if *action == GameAction::NegotiateRent && terms.can_negotiate(ledger.current.month) {
+            next_state.set(Overlay::RentNegotiation);
+        }
+    }
+}
+
+fn setup_negotiation(
+    mut commands: Commands,
+    config: Res<LandlordConfig>,
+    terms: Res<RentTerms>,
+    home: Res<HomeArea>,
+    increases: Res<RentIncreases>,
+    ledger: Res<Ledger>,
+    locale: Res<Locale>,
+    assets: Res<AssetServer>,
+) {
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: CARD_FONT_SIZE,
+        ..default()
+    };
+    let rent = terms.rent(increases.listed_rent(&home), ledger.current.month);
+    let record = format!(
+        "Rent is {} - {} payments on time",
+        format::money(rent, *locale),
+        terms.payments_on_time
+    );
+    let mut choices: Vec<_> = config
+        .approaches
+        .iter()
+        .enumerate()
+        .map(|(index, approach)| {
+            let odds = negotiation_odds(&config, approach, terms.payments_on_time);
+            let label = format!("{} ({:.0}%)", approach.label, odds.success * 100.0);
+            (label, NegotiationChoice(Some(index)))
+        })
+        .collect();
+    choices.push(("Walk away".to_string(), NegotiationChoice(None)));
+
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        GlobalZIndex(MODAL_Z_INDEX),
+        NegotiationOverlay,
+        children![(
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            BackgroundColor(DARKER),
+            Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+                parent.spawn((Text::new("Negotiate rent"), font.clone(), TextColor(YELLOW)));
+                parent.spawn((Text::new(record), font.clone(), TextColor(LIGHT)));
+                for (label, choice) in choices {
+                    parent.spawn((
+                        Button,
+                        Node {
+                            width: Val::Px(320.0),
+                            padding: UiRect::all(Val::Px(10.0)),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        BackgroundColor(DARK),
+                        choice,
+                        children![(Text::new(label), font.clone(), TextColor(LIGHT))],
+                    ));
+                }
+            })),
+        )],
+    ));
+}
+
+fn negotiation_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut presses: ButtonPresses<&NegotiationChoice>,
+    mut picked: EventWriter<ApproachPicked>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(Overlay::None);
+        return;
+    }
+    if let Some(NegotiationChoice(choice)) = presses.read().last() {
+        picked.write(ApproachPicked(*choice));
+    }
+}
+
+fn negotiate_rent(
+    mut picked: EventReader<ApproachPicked>,
+    config: Res<LandlordConfig>,
+    ledger: Res<Ledger>,
+    clock: Res<Clock>,
+    mut terms: ResMut<RentTerms>,
+    mut choices: ResMut<ChoiceLog>,
+    mut rng: ResMut<GameRng>,
+    mut toasts: EventWriter<Toast>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    let Some(ApproachPicked(choice)) = picked.read().last().copied() else {
+        return;
+    };
+    next_state.set(Overlay::None);
+    let Some(approach) = choice.and_then(|index| config.approaches.get(index)) else {
+        choices.record(clock.day, Choice::new("Landlord", "Walk away"));
+        return;
+    };
+
+    let odds = negotiation_odds(&config, approach, terms.payments_on_time);
+    let outcome = negotiate(&config, odds, &mut rng);
+    terms.apply(outcome, ledger.current.month);
+    let message = match outcome {
+        NegotiationOutcome::Discount(percent) => {
+            format!("The landlord agreed: rent down {percent}%")
+        }
+        NegotiationOutcome::Refused => "The landlord said no".to_string(),
+        NegotiationOutcome::Surcharge(percent) => {
+            format!("The landlord is annoyed: rent up {percent}% for the rest of the month")
+        }
+    };
+    choices.record(
+        clock.day,
+        Choice::new("Landlord", &approach.label)
+            .with_outcome(&message)
+            .notable(approach.notable.as_deref()),
+    );
+    toasts.write(Toast::new(message));
+}
+
+fn choice_colors(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<NegotiationChoice>),
+    >,
+) {
+    for (interaction, mut background) in &mut buttons {
+        background.0 = match interaction {
+            Interaction::None => DARK,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
@@ -0,0 +1,75 @@
+//! The player's net worth in the HUD, under the rent. Resting the mouse on it lists what
+//! it's made of.
+
+use bevy::prelude::*;
+
+use super::hud::{HudOrder, HudRoot, HudSlot};
+use super::text_backing::TextBacking;
+use super::tooltip::Tooltip;
+use crate::fonts::ui_font;
+use crate::format;
+use crate::palette::{BLUE, CORAL};
+use crate::settings::Locale;
+use crate::sim::ledger::{NetWorth, Wealth, net_worth_changed};
+use crate::{AppState, despawn_screen, track_screen};
+
+const FONT_SIZE: f32 = 16.0;
+
+#[derive(Component)]
+struct NetWorthUi;
+
+pub fn net_worth_plugin(app: &mut App) {
+    track_screen::<NetWorthUi>(app, AppState::Game);
+    app.add_systems(OnEnter(AppState::Game), spawn_net_worth)
+        .add_systems(OnExit(AppState::Game), despawn_screen::<NetWorthUi>)
+        .add_systems(
+            Update,
+            update_net_worth
+                .run_if(in_state(AppState::Game))
+                .run_if(net_worth_changed.or(resource_changed::<Locale>)),
+        );
+}
+
+fn spawn_net_worth(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.spawn((
+        Text::default(),
+        TextFont {
+            font: ui_font(&assets),
+            font_size: FONT_SIZE,
+            ..default()
+        },
+        TextBacking::default(),
+        TextColor(BLUE),
+        Tooltip::new(""),
+        NetWorthUi,
+        HudRoot,
+        HudSlot::TopRight,
+        HudOrder(1),
+    ));
+}
+
+fn breakdown(worth: &NetWorth, locale: Locale) -> String {
+    [
+        ("Cash", worth.cash),
+        ("Savings", worth.savings),
+        ("Investments", worth.investments),
+        ("Income sources", worth.income_sources),
+    ]
+    .map(|(part, amount)| format!("{part}: {}", format::money(amount, locale)))
+    .join("\n")
+}
+
+// Nothing is owed yet, so the figure can't go below zero; it turns coral when it hits it.
+fn update_net_worth(
+    wealth: Wealth,
+    locale: Res<Locale>,
+    mut ui: Query<(&mut Text, &mut TextColor, &mut Tooltip), With<NetWorthUi>>,
+) {
+    let Ok((mut text, mut color, mut tooltip)) = ui.single_mut() else {
+        return;
+    };
+    let worth = wealth.net_worth();
+    text.0 = format!("Net worth: {}", format::money(worth.total(), *locale));
+    color.0 = if worth.total() > 0 { BLUE } else { CORAL };
+    *tooltip = Tooltip::titled("Net worth", breakdown(&worth, *locale));
+}
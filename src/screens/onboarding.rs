@@ -0,0 +1,221 @@
+//! The first week's checklist in the HUD, under the other panels on the left. The header
+//! folds the list away, and the X skips the checklist for good.
+
+use bevy::prelude::*;
+
+use super::area_theme::AccentTint;
+use super::button_press::ButtonPresses;
+use super::hud::{HudOrder, HudRoot, HudSlot};
+use crate::fonts::ui_font;
+use crate::palette::{BLUE, DARK, DARKER, LIGHT, YELLOW};
+use crate::profiles::ProfilePaths;
+use crate::save_file::SaveFiles;
+use crate::sim::{
+    clock::Clock,
+    onboarding::{CHECKLIST_LAST_DAY, Checklist, ChecklistRetired, StarterTask},
+};
+use crate::{AppState, despawn_screen, track_screen};
+
+const FONT_SIZE: f32 = 12.0;
+
+#[derive(Component)]
+struct ChecklistPanel;
+
+#[derive(Component)]
+struct ChecklistList;
+
+#[derive(Component)]
+struct ChecklistHeader;
+
+#[derive(Component)]
+struct SkipChecklistButton;
+
+#[derive(Component)]
+struct TaskRow(StarterTask);
+
+pub fn onboarding_plugin(app: &mut App) {
+    track_screen::<ChecklistPanel>(app, AppState::Game);
+    app.add_systems(OnEnter(AppState::Game), spawn_checklist)
+        .add_systems(OnExit(AppState::Game), despawn_screen::<ChecklistPanel>)
+        .add_systems(
+            Update,
+            (
+                toggle_checklist,
+                skip_checklist,
+                button_colors,
+                update_checklist.run_if(resource_changed::<Checklist>),
+                retire_checklist,
+            )
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        );
+}
+
+fn showing(retired: &ChecklistRetired, clock: &Clock) -> bool {
+    !retired.0 && clock.day <= CHECKLIST_LAST_DAY
+}
+
+fn task_text(checklist: &Checklist, task: StarterTask) -> (String, Color) {
+    if checklist.is_done(task) {
+        (format!("[x] {}", task.label()), BLUE)
+    } else {
+        (format!("[ ] {}", task.label()), LIGHT)
+    }
+}
+
+fn spawn_checklist(
+    mut commands: Commands,
+    checklist: Res<Checklist>,
+    retired: Res<ChecklistRetired>,
+    clock: Res<Clock>,
+    assets: Res<AssetServer>,
+) {
+    if !showing(&retired, &clock) {
+        return;
+    }
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: FONT_SIZE,
+        ..default()
+    };
+    let button = Node {
+        padding: UiRect::axes(Val::Px(4.0), Val::Px(2.0)),
+        ..default()
+    };
+    commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            AccentTint::chrome(DARKER.with_alpha(0.8)),
+            ChecklistPanel,
+            HudRoot,
+            HudSlot::TopLeft,
+            HudOrder(4),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Node {
+                    column_gap: Val::Px(8.0),
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
+                children![
+                    (
+                        Button,
+                        button.clone(),
+                        BackgroundColor(DARK),
+                        ChecklistHeader,
+                        children![(Text::new("First week -"), font.clone(), TextColor(YELLOW))],
+                    ),
+                    (
+                        Button,
+                        button,
+                        BackgroundColor(DARK),
+                        SkipChecklistButton,
+                        children![(Text::new("X"), font.clone(), TextColor(LIGHT))],
+                    ),
+                ],
+            ));
+            panel
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(2.0),
+                        ..default()
+                    },
+                    ChecklistList,
+                ))
+                .with_children(|list| {
+                    for task in StarterTask::ALL {
+                        let (text, color) = task_text(&checklist, task);
+                        list.spawn((
+                            Text::new(text),
+                            font.clone(),
+                            TextColor(color),
+                            TaskRow(task),
+                        ));
+                    }
+                });
+        });
+}
+
+fn update_checklist(
+    checklist: Res<Checklist>,
+    mut rows: Query<(&TaskRow, &mut Text, &mut TextColor)>,
+) {
+    for (TaskRow(task), mut text, mut color) in &mut rows {
+        (text.0, color.0) = task_text(&checklist, *task);
+    }
+}
+
+fn toggle_checklist(
+    mut presses: ButtonPresses<&Children, With<ChecklistHeader>>,
+    mut list: Query<&mut Node, With<ChecklistList>>,
+    mut texts: Query<&mut Text>,
+) {
+    let Ok(mut list) = list.single_mut() else {
+        return;
+    };
+    for children in presses.read() {
+        let folded = list.display == Display::None;
+        list.display = if folded { Display::Flex } else { Display::None };
+        if let Some(mut text) = children
+            .first()
+            .and_then(|label| texts.get_mut(*label).ok())
+        {
+            text.0 = if folded {
+                "First week -"
+            } else {
+                "First week +"
+            }
+            .to_string();
+        }
+    }
+}
+
+fn skip_checklist(
+    mut presses: ButtonPresses<(), With<SkipChecklistButton>>,
+    mut retired: ResMut<ChecklistRetired>,
+    profile: Res<ProfilePaths>,
+    mut files: SaveFiles,
+) {
+    if presses.read().count() > 0 {
+        retired.retire(&profile, &mut files);
+    }
+}
+
+fn button_colors(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (
+            Changed<Interaction>,
+            Or<(With<ChecklistHeader>, With<SkipChecklistButton>)>,
+        ),
+    >,
+) {
+    for (interaction, mut background) in &mut buttons {
+        background.0 = match interaction {
+            Interaction::None => DARK,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
+
+// Once finished, skipped or past the first week, the panel goes away.
+fn retire_checklist(
+    mut commands: Commands,
+    retired: Res<ChecklistRetired>,
+    clock: Res<Clock>,
+    panels: Query<Entity, With<ChecklistPanel>>,
+) {
+    if showing(&retired, &clock) {
+        return;
+    }
+    for panel in &panels {
+        commands.entity(panel).despawn();
+    }
+}
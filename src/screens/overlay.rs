@@ -0,0 +1,149 @@
+//! Overlays opened on top of the game screen, and the [`ModalStack`] that pauses the
+//! simulation and game input while any of them is open. Open modals share one layer that
+//! dims the scene behind them.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, ui::FocusPolicy};
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::power::AnimationsActive;
+use crate::settings::ReducedMotion;
+use crate::timers::RealUiTime;
+
+/// Z order of the overlay screens.
+pub const MODAL_Z_INDEX: i32 = 50;
+const DIM_ALPHA: f32 = 0.6;
+const DIM_FADE: Duration = Duration::from_millis(200);
+
+#[derive(SubStates, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[source(AppState = AppState::Game)]
+pub enum Overlay {
+    #[default]
+    None,
+    Freelance,
+    MonthlyReport,
+    Pause,
+    RentNegotiation,
+    Dialogue,
+    Investments,
+    Calendar,
+    Inbox,
+    Charity,
+    Deposit,
+    SavingsGoal,
+    Travel,
+    EventCard,
+    CommandPalette,
+}
+
+/// Handed out by [`ModalStack::open_modal`] to close the same modal later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModalToken(u32);
+
+/// Every modal UI currently open. The simulation and game hotkeys wait until it's empty.
+#[derive(Resource, Debug, Default)]
+pub struct ModalStack {
+    open: Vec<ModalToken>,
+    next_token: u32,
+}
+
+impl ModalStack {
+    pub fn open_modal(&mut self) -> ModalToken {
+        let token = ModalToken(self.next_token);
+        self.next_token = self.next_token.wrapping_add(1);
+        self.open.push(token);
+        token
+    }
+
+    /// Closes the modal `token` was given for, even if others were opened after it.
+    pub fn close_modal(&mut self, token: ModalToken) {
+        self.open.retain(|open| *open != token);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.open.is_empty()
+    }
+}
+
+/// Run condition for game input that shouldn't reach the game under a modal.
+pub fn no_modal_open(modals: Res<ModalStack>) -> bool {
+    modals.is_empty()
+}
+
+/// Darkens the scene behind open modals and keeps clicks off the HUD buttons under it.
+#[derive(Component)]
+struct DimLayer;
+
+/// The token held for whichever [`Overlay`] is open.
+#[derive(Resource, Debug, Default)]
+struct OverlayModal(Option<ModalToken>);
+
+pub fn overlay_plugin(app: &mut App) {
+    app.add_sub_state::<Overlay>()
+        .init_resource::<ModalStack>()
+        .init_resource::<OverlayModal>()
+        .add_systems(OnExit(Overlay::None), open_overlay_modal)
+        .add_systems(OnEnter(Overlay::None), close_overlay_modal)
+        // Leaving the game closes the overlay without going back to `Overlay::None`.
+        .add_systems(OnExit(AppState::Game), close_overlay_modal)
+        .add_systems(Update, fade_dim_layer);
+}
+
+fn open_overlay_modal(mut modals: ResMut<ModalStack>, mut overlay: ResMut<OverlayModal>) {
+    overlay.0 = Some(modals.open_modal());
+}
+
+fn close_overlay_modal(mut modals: ResMut<ModalStack>, mut overlay: ResMut<OverlayModal>) {
+    if let Some(token) = overlay.0.take() {
+        modals.close_modal(token);
+    }
+}
+
+fn fade_dim_layer(
+    mut commands: Commands,
+    modals: Res<ModalStack>,
+    reduced_motion: Res<ReducedMotion>,
+    time: RealUiTime,
+    mut animations: ResMut<AnimationsActive>,
+    mut layers: Query<(Entity, &mut BackgroundColor), With<DimLayer>>,
+) {
+    let target = if modals.is_empty() { 0.0 } else { DIM_ALPHA };
+    let Ok((entity, mut background)) = layers.single_mut() else {
+        if target > 0.0 {
+            let alpha = if reduced_motion.0 { target } else { 0.0 };
+            commands.spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                BackgroundColor(Color::BLACK.with_alpha(alpha)),
+                FocusPolicy::Block,
+                GlobalZIndex(MODAL_Z_INDEX - 1),
+                DimLayer,
+            ));
+        }
+        return;
+    };
+
+    let alpha = background.0.alpha();
+    let alpha = if reduced_motion.0 {
+        target
+    } else {
+        let step = DIM_ALPHA * time.delta_secs() / DIM_FADE.as_secs_f32();
+        if alpha < target {
+            (alpha + step).min(target)
+        } else {
+            (alpha - step).max(target)
+        }
+    };
+    if alpha <= 0.0 {
+        commands.entity(entity).despawn();
+    } else if alpha != background.0.alpha() {
+        background.0.set_alpha(alpha);
+        animations.keep_awake();
+    }
+}
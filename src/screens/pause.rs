@@ -0,0 +1,555 @@
+//! The pause menu, opened with Esc during a game. The player can resume, change the
+//! settings, look through the run's photo journal or quit to the main menu from here, and
+//! see how long they have played and the [`Moments`] they've come across. Quitting a run
+//! with unsaved progress asks first, and saving on the way out only leaves once the save
+//! is written.
+
+use bevy::{ecs::spawn::SpawnWith, prelude::*};
+
+use super::button_press::ButtonPresses;
+use super::hold_confirm::{HoldConfirmed, HoldToConfirm};
+use super::menu::{MenuHistory, MenuState};
+use super::nav::{ScreenNav, TransitionCause};
+use super::overlay::{MODAL_Z_INDEX, Overlay, no_modal_open};
+use crate::audio::{AudioSettings, DuckSource};
+use crate::fonts::ui_font;
+use crate::format;
+use crate::input_device::ActiveInputDevice;
+use crate::palette::{BLUE, CORAL, DARK, DARKER, LIGHT, YELLOW};
+use crate::save_file::{SaveFinished, SaveId};
+use crate::settings::Locale;
+use crate::sim::{
+    Dirty,
+    playtime::{Playtime, SessionStats},
+    quicksave::save_run,
+    vignettes::{Moments, Vignettes},
+};
+use crate::{AppState, despawn_screen, track_screen};
+
+/// How loud audio stays while the game is paused.
+const PAUSE_DUCK: f32 = 0.4;
+
+#[derive(Component)]
+struct PauseOverlay;
+
+/// The pause menu itself, hidden while a settings screen is open over it.
+#[derive(Component)]
+struct PausePanel;
+
+/// Asks whether to save before quitting, in place of the pause menu.
+#[derive(Component)]
+struct QuitConfirm;
+
+/// How saving before quitting is going, under the question.
+#[derive(Component)]
+struct QuitConfirmMessage;
+
+/// The save the pause menu is waiting on to quit.
+#[derive(Resource)]
+struct SavingToQuit(SaveId);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseButton {
+    Resume,
+    Settings,
+    Journal,
+    QuitToMenu,
+    SaveAndQuit,
+    QuitWithoutSaving,
+    CancelQuit,
+}
+
+impl PauseButton {
+    fn label(self) -> &'static str {
+        match self {
+            PauseButton::Resume => "Resume",
+            PauseButton::Settings => "Settings",
+            PauseButton::Journal => "Journal",
+            PauseButton::QuitToMenu => "Quit to Menu",
+            PauseButton::SaveAndQuit => "Save and quit",
+            PauseButton::QuitWithoutSaving => "Quit without saving",
+            PauseButton::CancelQuit => "Cancel",
+        }
+    }
+}
+
+pub fn pause_plugin(app: &mut App) {
+    track_screen::<PauseOverlay>(app, Overlay::Pause);
+    app.add_systems(
+        Update,
+        open_pause.run_if(in_state(Overlay::None).and(no_modal_open)),
+    )
+    .add_systems(OnEnter(Overlay::Pause), setup_pause)
+    .add_systems(
+        OnExit(Overlay::Pause),
+        (despawn_screen::<PauseOverlay>, end_pause),
+    )
+    .add_systems(
+        Update,
+        (
+            (pause_input, pause_action, pause_button_colors).run_if(in_state(MenuState::Disabled)),
+            show_pause_panel.run_if(state_changed::<MenuState>),
+            finish_save_and_quit.run_if(resource_exists::<SavingToQuit>),
+        )
+            .run_if(in_state(Overlay::Pause)),
+    );
+}
+
+fn open_pause(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) || start_pressed(&gamepads) {
+        next_state.set(Overlay::Pause);
+    }
+}
+
+fn start_pressed(gamepads: &Query<&Gamepad>) -> bool {
+    gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::Start))
+}
+
+fn setup_pause(
+    mut commands: Commands,
+    mut audio: ResMut<AudioSettings>,
+    device: Res<ActiveInputDevice>,
+    locale: Res<Locale>,
+    playtime: Res<Playtime>,
+    session: Res<SessionStats>,
+    moments: Res<Moments>,
+    vignettes: Res<Vignettes>,
+    assets: Res<AssetServer>,
+) {
+    audio.duck(DuckSource::PauseMenu, PAUSE_DUCK);
+    let font = ui_font(&assets);
+    let text_font = |font_size| TextFont {
+        font: font.clone(),
+        font_size,
+        ..default()
+    };
+    let title = (Text::new("Paused"), text_font(24.0), TextColor(YELLOW));
+    let days = if session.days == 1 { "day" } else { "days" };
+    let stats = (
+        Text::new(format!(
+            "Played {}\nThis session: earned {}, {} {days} passed\nMoments: {} of {}",
+            format::playtime(playtime.0),
+            format::money(session.earnings, *locale),
+            session.days,
+            moments.0.len(),
+            vignettes.vignettes.len()
+        )),
+        text_font(12.0),
+        TextColor(LIGHT),
+        TextLayout::new_with_justify(JustifyText::Center),
+    );
+    let hint = (
+        Text::new(device.0.prompt("", "Start: resume - hold X: quit to menu")),
+        text_font(12.0),
+        TextColor(LIGHT.with_alpha(0.6)),
+        locale.direction().text(),
+    );
+    let button_font = text_font(16.0);
+
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        GlobalZIndex(MODAL_Z_INDEX),
+        PauseOverlay,
+        children![(
+            pause_panel(),
+            PausePanel,
+            Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+                parent.spawn(title);
+                parent.spawn(stats);
+                for button in [
+                    PauseButton::Resume,
+                    PauseButton::Settings,
+                    PauseButton::Journal,
+                    PauseButton::QuitToMenu,
+                ] {
+                    let mut entity = parent.spawn(pause_button(button, button_font.clone()));
+                    // Losing the run can't be undone, so it needs a hold on a gamepad
+                    if button == PauseButton::QuitToMenu {
+                        entity.insert(HoldToConfirm::new(GamepadButton::West));
+                    }
+                }
+                parent.spawn(hint);
+            })),
+        )],
+    ));
+}
+
+fn pause_panel() -> impl Bundle {
+    (
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(12.0),
+            padding: UiRect::all(Val::Px(24.0)),
+            ..default()
+        },
+        BackgroundColor(DARKER),
+    )
+}
+
+fn pause_button(button: PauseButton, font: TextFont) -> impl Bundle {
+    (
+        Button,
+        Node {
+            width: Val::Px(260.0),
+            height: Val::Px(48.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(DARK),
+        button,
+        children![(Text::new(button.label()), font, TextColor(LIGHT))],
+    )
+}
+
+/// Takes the pause menu's place until the player quits or changes their mind.
+fn quit_confirm(font: Handle<Font>, hint: &'static str) -> impl Bundle {
+    let text_font = move |font_size| TextFont {
+        font: font.clone(),
+        font_size,
+        ..default()
+    };
+    (
+        pause_panel(),
+        QuitConfirm,
+        Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+            parent.spawn((
+                Text::new("Quit? This run has progress since its last save"),
+                text_font(16.0),
+                TextColor(YELLOW),
+            ));
+            parent.spawn((
+                Text::default(),
+                text_font(12.0),
+                TextColor(CORAL),
+                QuitConfirmMessage,
+            ));
+            parent.spawn(pause_button(PauseButton::SaveAndQuit, text_font(16.0)));
+            parent.spawn((
+                pause_button(PauseButton::QuitWithoutSaving, text_font(16.0)),
+                HoldToConfirm::new(GamepadButton::North),
+            ));
+            parent.spawn(pause_button(PauseButton::CancelQuit, text_font(16.0)));
+            parent.spawn((
+                Text::new(hint),
+                text_font(12.0),
+                TextColor(LIGHT.with_alpha(0.6)),
+            ));
+        })),
+    )
+}
+
+fn end_pause(mut audio: ResMut<AudioSettings>) {
+    audio.unduck(DuckSource::PauseMenu);
+}
+
+fn pause_input(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    confirm: Query<Entity, With<QuitConfirm>>,
+    mut panel: Query<&mut Visibility, With<PausePanel>>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    let Ok(mut panel) = panel.single_mut() else {
+        return;
+    };
+    let back = keyboard_input.just_pressed(KeyCode::Escape) || start_pressed(&gamepads);
+    match confirm.single().ok() {
+        Some(confirm)
+            if back
+                || gamepads
+                    .iter()
+                    .any(|gamepad| gamepad.just_pressed(GamepadButton::East)) =>
+        {
+            commands.entity(confirm).despawn();
+            *panel = Visibility::Inherited;
+        }
+        None if back => next_state.set(Overlay::None),
+        _ => {}
+    }
+}
+
+fn pause_action(
+    mut commands: Commands,
+    mut presses: ButtonPresses<&PauseButton, Without<HoldToConfirm>>,
+    held_buttons: Query<&PauseButton, With<HoldToConfirm>>,
+    mut confirmed: EventReader<HoldConfirmed>,
+    mut history: ResMut<MenuHistory>,
+    dirty: Res<Dirty>,
+    overlay: Query<Entity, With<PauseOverlay>>,
+    mut panel: Query<&mut Visibility, With<PausePanel>>,
+    confirm: Query<Entity, With<QuitConfirm>>,
+    device: Res<ActiveInputDevice>,
+    assets: Res<AssetServer>,
+    mut nav: ScreenNav,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    let Ok(overlay) = overlay.single() else {
+        return;
+    };
+    let Ok(mut panel) = panel.single_mut() else {
+        return;
+    };
+    let pressed = presses.read();
+    let confirmed = confirmed
+        .read()
+        .filter_map(|HoldConfirmed(entity)| held_buttons.get(*entity).ok());
+    for button in pressed.chain(confirmed) {
+        match button {
+            PauseButton::Resume => next_state.set(Overlay::None),
+            PauseButton::Settings => history.open(&mut nav, MenuState::Settings),
+            PauseButton::Journal => history.open(&mut nav, MenuState::Journal),
+            PauseButton::QuitToMenu if dirty.0 => {
+                *panel = Visibility::Hidden;
+                let hint = device.0.prompt("", "Hold Y: quit - B: cancel");
+                commands
+                    .entity(overlay)
+                    .with_child(quit_confirm(ui_font(&assets), hint));
+            }
+            PauseButton::SaveAndQuit => commands.queue(save_and_quit),
+            PauseButton::QuitToMenu | PauseButton::QuitWithoutSaving => {
+                nav.set_app(AppState::Menu, TransitionCause::Button)
+            }
+            PauseButton::CancelQuit => {
+                for confirm in &confirm {
+                    commands.entity(confirm).despawn();
+                }
+                *panel = Visibility::Inherited;
+            }
+        }
+    }
+}
+
+// The confirmation stays up until the save is written, see `finish_save_and_quit`.
+fn save_and_quit(world: &mut World) {
+    if world.contains_resource::<SavingToQuit>() {
+        return;
+    }
+    let id = save_run(world);
+    world.insert_resource(SavingToQuit(id));
+    let mut message = world.query_filtered::<&mut Text, With<QuitConfirmMessage>>();
+    for mut text in message.iter_mut(world) {
+        text.0 = "Saving…".to_string();
+    }
+}
+
+// Leaves for the menu once the run is written, or says why it couldn't be and stays.
+fn finish_save_and_quit(
+    mut commands: Commands,
+    saving: Res<SavingToQuit>,
+    mut finished: EventReader<SaveFinished>,
+    confirm: Query<(), With<QuitConfirm>>,
+    mut message: Query<&mut Text, With<QuitConfirmMessage>>,
+    mut nav: ScreenNav,
+) {
+    // Backed out of while it was saving.
+    if confirm.is_empty() {
+        commands.remove_resource::<SavingToQuit>();
+        return;
+    }
+    let Some(SaveFinished { result, .. }) = finished.read().find(|save| save.id == saving.0) else {
+        return;
+    };
+    commands.remove_resource::<SavingToQuit>();
+    match result {
+        Ok(()) => nav.set_app(AppState::Menu, TransitionCause::Button),
+        Err(error) => {
+            for mut text in &mut message {
+                text.0 = format!("Couldn't save: {error}");
+            }
+        }
+    }
+}
+
+fn pause_button_colors(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<PauseButton>),
+    >,
+) {
+    for (interaction, mut background) in &mut buttons {
+        background.0 = match interaction {
+            Interaction::None => DARK,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
+
+// The settings and the journal draw over the pause menu, so it steps aside while they're open
+fn show_pause_panel(
+    menu: Res<State<MenuState>>,
+    mut panel: Query<&mut Visibility, With<PausePanel>>,
+) {
+    let Ok(mut panel) = panel.single_mut() else {
+        return;
+    };
+    *panel = if *menu.get() == MenuState::Disabled {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput, NativeKey},
+    };
+
+    use bevy::ui::UiSystem;
+
+    use super::*;
+    use crate::profiles::ProfilePaths;
+    use crate::screens::button_press::capture_presses;
+    use crate::sim::clock::Clock;
+    use crate::sim::quicksave::QUICKSAVE_FILE;
+    use crate::testing::TestGame;
+
+    fn press_escape(app: &mut App) {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            app.world_mut().send_event(KeyboardInput {
+                key_code: KeyCode::Escape,
+                logical_key: Key::Unidentified(NativeKey::Unidentified),
+                state,
+                text: None,
+                repeat: false,
+                window: Entity::PLACEHOLDER,
+            });
+            app.update();
+        }
+        app.update();
+    }
+
+    /// A button to press as if clicked, after the UI has seen there's no pointer about.
+    #[derive(Resource, Default)]
+    struct Press(Option<Entity>);
+
+    fn press_button(mut press: ResMut<Press>, mut buttons: Query<&mut Interaction>) {
+        if let Some(button) = press.0.take() {
+            *buttons.get_mut(button).unwrap() = Interaction::Pressed;
+        }
+    }
+
+    fn click(app: &mut App, which: PauseButton) {
+        let mut buttons = app.world_mut().query::<(Entity, &PauseButton)>();
+        let button = buttons
+            .iter(app.world())
+            .find(|(_, button)| **button == which)
+            .map(|(entity, _)| entity)
+            .unwrap();
+        app.insert_resource(Press(Some(button)));
+        app.update();
+        app.update();
+    }
+
+    fn quit_message(app: &App) -> String {
+        let world = app.world();
+        world
+            .try_query_filtered::<&Text, With<QuitConfirmMessage>>()
+            .and_then(|mut message| message.single(world).ok().map(|text| text.0.clone()))
+            .unwrap_or_default()
+    }
+
+    fn screens(app: &App) -> (Overlay, MenuState) {
+        (
+            *app.world().resource::<State<Overlay>>().get(),
+            *app.world().resource::<State<MenuState>>().get(),
+        )
+    }
+
+    fn panel(app: &mut App) -> Visibility {
+        let mut panel = app
+            .world_mut()
+            .query_filtered::<&Visibility, With<PausePanel>>();
+        *panel.single(app.world()).unwrap()
+    }
+
+    fn duck(app: &App) -> Option<f32> {
+        app.world().resource::<AudioSettings>().duck
+    }
+
+    #[test]
+    fn the_settings_go_back_to_the_pause_menu() {
+        const PROFILE: &str = "Pause settings";
+        let mut app = TestGame::in_game(PROFILE);
+        app.init_resource::<Press>().add_systems(
+            PreUpdate,
+            press_button.after(UiSystem::Focus).before(capture_presses),
+        );
+
+        press_escape(&mut app);
+        assert_eq!(screens(&app), (Overlay::Pause, MenuState::Disabled));
+        assert_eq!(duck(&app), Some(PAUSE_DUCK));
+
+        let mut buttons = app.world_mut().query::<(Entity, &PauseButton)>();
+        let settings = buttons
+            .iter(app.world())
+            .find(|(_, button)| **button == PauseButton::Settings)
+            .map(|(entity, _)| entity)
+            .unwrap();
+        app.insert_resource(Press(Some(settings)));
+        app.update();
+        app.update();
+        assert_eq!(screens(&app), (Overlay::Pause, MenuState::Settings));
+        assert_eq!(panel(&mut app), Visibility::Hidden);
+        let hours = app.world().resource::<Clock>().total_hours();
+
+        // Back from the settings, then out of the pause menu.
+        press_escape(&mut app);
+        assert_eq!(screens(&app), (Overlay::Pause, MenuState::Disabled));
+        assert_eq!(panel(&mut app), Visibility::Inherited);
+        assert_eq!(duck(&app), Some(PAUSE_DUCK));
+        assert_eq!(app.world().resource::<Clock>().total_hours(), hours);
+
+        press_escape(&mut app);
+        assert_eq!(screens(&app), (Overlay::None, MenuState::Disabled));
+        assert_eq!(duck(&app), None);
+    }
+
+    #[test]
+    fn save_and_quit_waits_for_the_save_and_stays_if_it_fails() {
+        const PROFILE: &str = "Pause save and quit";
+        let mut app = TestGame::in_game(PROFILE);
+        app.init_resource::<Press>().add_systems(
+            PreUpdate,
+            press_button.after(UiSystem::Focus).before(capture_presses),
+        );
+        // A directory where the quicksave goes, so it can't be written.
+        let quicksave = app.world().resource::<ProfilePaths>().file(QUICKSAVE_FILE);
+        std::fs::create_dir_all(&quicksave).unwrap();
+        app.world_mut().resource_mut::<Dirty>().0 = true;
+
+        press_escape(&mut app);
+        click(&mut app, PauseButton::QuitToMenu);
+        click(&mut app, PauseButton::SaveAndQuit);
+        app.update_until(|app| quit_message(app).starts_with("Couldn't save: "));
+        assert_eq!(screens(&app), (Overlay::Pause, MenuState::Disabled));
+        assert_eq!(
+            *app.world().resource::<State<AppState>>().get(),
+            AppState::Game
+        );
+        assert!(app.world().resource::<Dirty>().0);
+
+        std::fs::remove_dir(&quicksave).unwrap();
+        click(&mut app, PauseButton::SaveAndQuit);
+        assert_eq!(quit_message(&app), "Saving…");
+        app.update_until(|app| *app.world().resource::<State<AppState>>().get() == AppState::Menu);
+        assert!(quicksave.is_file());
+    }
+}
@@ -0,0 +1,115 @@
+//! A small badge on the game screen showing how the player's pet is doing.
+
+use bevy::prelude::*;
+
+use super::area_theme::{AccentText, AccentTint};
+use super::hud::{HudRoot, HudSlot};
+use crate::fonts::ui_font;
+use crate::palette::{CORAL, DARKER, LIGHT, YELLOW};
+use crate::sim::{clock::Clock, pet::Pet};
+use crate::{AppState, despawn_screen, track_screen};
+
+const PET_STATUS_FONT_SIZE: f32 = 16.0;
+const PET_ICON_SIZE: Val = Val::Px(14.0);
+
+#[derive(Component)]
+struct PetStatus;
+
+/// The badge itself, hidden until the player has a pet.
+#[derive(Component)]
+struct PetBadge;
+
+#[derive(Component)]
+struct PetIcon;
+
+#[derive(Component)]
+struct PetStatusText;
+
+pub fn pet_status_plugin(app: &mut App) {
+    track_screen::<PetStatus>(app, AppState::Game);
+    app.add_systems(OnEnter(AppState::Game), spawn_pet_status)
+        .add_systems(OnExit(AppState::Game), despawn_screen::<PetStatus>)
+        .add_systems(
+            Update,
+            update_pet_status.run_if(
+                in_state(AppState::Game).and(resource_changed::<Pet>.or(resource_changed::<Clock>)),
+            ),
+        );
+}
+
+fn spawn_pet_status(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.spawn((
+        Node::default(),
+        PetStatus,
+        HudRoot,
+        HudSlot::BottomLeft,
+        children![(
+            Node {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            AccentTint::chrome(DARKER.with_alpha(0.85)),
+            Visibility::Hidden,
+            PetBadge,
+            children![
+                (
+                    Node {
+                        width: PET_ICON_SIZE,
+                        height: PET_ICON_SIZE,
+                        ..default()
+                    },
+                    BorderRadius::MAX,
+                    BackgroundColor(YELLOW),
+                    PetIcon,
+                ),
+                (
+                    Text::default(),
+                    TextFont {
+                        font: ui_font(&assets),
+                        font_size: PET_STATUS_FONT_SIZE,
+                        ..default()
+                    },
+                    AccentText(LIGHT),
+                    PetStatusText,
+                ),
+            ],
+        )],
+    ));
+}
+
+fn update_pet_status(
+    pet: Res<Pet>,
+    clock: Res<Clock>,
+    mut badge: Query<&mut Visibility, With<PetBadge>>,
+    mut icon: Query<&mut BackgroundColor, With<PetIcon>>,
+    mut text: Query<&mut Text, With<PetStatusText>>,
+) {
+    let Ok(mut badge) = badge.single_mut() else {
+        return;
+    };
+    let Ok(mut icon) = icon.single_mut() else {
+        return;
+    };
+    let Ok(mut text) = text.single_mut() else {
+        return;
+    };
+    let Some(adopted) = &pet.0 else {
+        *badge = Visibility::Hidden;
+        return;
+    };
+
+    *badge = Visibility::Inherited;
+    let (status, color) = match adopted.days_hungry {
+        0 => ("happy", YELLOW),
+        1 => ("hungry", CORAL),
+        _ => ("starving", CORAL),
+    };
+    icon.0 = color;
+    text.0 = format!(
+        "{} is {status} - {} days together",
+        adopted.name,
+        adopted.days_together(&clock)
+    );
+}
@@ -0,0 +1,306 @@
+//! The profiles screen, see [`crate::profiles`]: switch to another profile, make a new one
+//! or delete one. Deleting asks for the profile's name to be typed first, since its
+//! settings and high scores go with it. The active profile can't be deleted.
+
+use bevy::prelude::*;
+
+use super::button_press::ButtonPresses;
+use super::menu::{MenuHistory, MenuState};
+use super::menu_slide::{slide_in, slide_out};
+use super::nav::ScreenNav;
+use super::text_field::{FieldSubmitted, TextField, text_field};
+use crate::fonts::ui_font;
+use crate::palette::{CORAL, DARKER};
+use crate::profiles::{self, MAX_NAME_LENGTH, ProfilePaths};
+use crate::save_file::Saves;
+use crate::settings::LayoutScale;
+use crate::track_screen;
+
+/// Keeps the screen above the main menu's title art, like the settings screens.
+const PROFILES_Z_INDEX: i32 = 60;
+
+#[derive(Component)]
+struct OnProfilesScreen;
+
+/// Holds the profile list and the field under it, rebuilt whenever they change.
+#[derive(Component)]
+struct ProfileRows;
+
+/// Why the last create or delete didn't go through, or what it did.
+#[derive(Component)]
+struct ProfileMessage;
+
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+enum ProfileButton {
+    Switch(String),
+    Delete(String),
+    Create,
+    ConfirmDelete,
+    CancelDelete,
+    Back,
+}
+
+/// The profile waiting for its name to be typed before it's deleted.
+#[derive(Resource, Debug, Default)]
+struct DeletingProfile(Option<String>);
+
+pub fn profile_picker_plugin(app: &mut App) {
+    track_screen::<OnProfilesScreen>(app, MenuState::Profiles);
+    app.init_resource::<DeletingProfile>()
+        .add_systems(
+            OnEnter(MenuState::Profiles),
+            (profiles_setup, slide_in::<OnProfilesScreen>).chain(),
+        )
+        .add_systems(OnExit(MenuState::Profiles), slide_out::<OnProfilesScreen>)
+        .add_systems(
+            Update,
+            (
+                profile_action,
+                fill_rows.run_if(
+                    resource_changed::<DeletingProfile>.or(resource_changed::<ProfilePaths>),
+                ),
+            )
+                .chain()
+                .run_if(in_state(MenuState::Profiles)),
+        );
+}
+
+fn button_node(scale: &LayoutScale) -> Node {
+    Node {
+        min_width: scale.px(160.0),
+        height: scale.px(50.0),
+        margin: UiRect::all(scale.px(8.0)),
+        padding: UiRect::horizontal(scale.px(12.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    }
+}
+
+fn profiles_setup(mut commands: Commands, scale: Res<LayoutScale>, assets: Res<AssetServer>) {
+    commands.insert_resource(DeletingProfile::default());
+    let button_text_style = (
+        TextFont {
+            font_size: scale.size(33.0),
+            font: ui_font(&assets),
+            ..default()
+        },
+        TextColor(DARKER),
+    );
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        OnProfilesScreen,
+        GlobalZIndex(PROFILES_Z_INDEX),
+        children![(
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            children![
+                (Text::new("Profiles"), button_text_style.clone()),
+                (
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ProfileRows,
+                ),
+                (
+                    Text::default(),
+                    TextFont {
+                        font_size: scale.size(20.0),
+                        font: ui_font(&assets),
+                        ..default()
+                    },
+                    TextColor(CORAL),
+                    ProfileMessage,
+                ),
+                (
+                    Button,
+                    button_node(&scale),
+                    ProfileButton::Back,
+                    children![(Text::new("Back"), button_text_style)]
+                ),
+            ]
+        )],
+    ));
+}
+
+fn name_field() -> TextField {
+    TextField::new(MAX_NAME_LENGTH).accepting(profiles::name_char)
+}
+
+fn fill_rows(
+    mut commands: Commands,
+    deleting: Res<DeletingProfile>,
+    profile: Res<ProfilePaths>,
+    rows: Query<Entity, With<ProfileRows>>,
+    scale: Res<LayoutScale>,
+    assets: Res<AssetServer>,
+) {
+    let Ok(rows) = rows.single() else {
+        return;
+    };
+    let font = TextFont {
+        font_size: scale.size(26.0),
+        font: ui_font(&assets),
+        ..default()
+    };
+    let note_font = TextFont {
+        font_size: scale.size(20.0),
+        ..font.clone()
+    };
+    let button = |action: ProfileButton, label: &str| {
+        (
+            Button,
+            button_node(&scale),
+            action,
+            children![(Text::new(label), font.clone(), TextColor(DARKER))],
+        )
+    };
+    let row = || Node {
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    commands
+        .entity(rows)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            for name in profiles::list(profile.root()) {
+                let mut entry = parent.spawn(row());
+                if name == profile.name() {
+                    entry.with_child((
+                        button(ProfileButton::Switch(name.clone()), &name),
+                        BackgroundColor(CORAL),
+                    ));
+                } else {
+                    entry.with_child(button(ProfileButton::Switch(name.clone()), &name));
+                    entry.with_child(button(ProfileButton::Delete(name), "Delete"));
+                }
+            }
+            if let Some(name) = &deleting.0 {
+                parent.spawn((
+                    Text::new(format!(
+                        "Type \"{name}\" to delete it with its settings and high scores"
+                    )),
+                    note_font.clone(),
+                    TextColor(DARKER),
+                ));
+                parent.spawn(text_field(name_field(), font.clone()));
+                parent.spawn((
+                    row(),
+                    children![
+                        button(ProfileButton::ConfirmDelete, "Confirm"),
+                        button(ProfileButton::CancelDelete, "Cancel"),
+                    ],
+                ));
+            } else {
+                parent.spawn((Text::new("New profile"), note_font, TextColor(DARKER)));
+                parent.spawn((
+                    row(),
+                    children![
+                        text_field(name_field(), font.clone()),
+                        button(ProfileButton::Create, "Create"),
+                    ],
+                ));
+            }
+        });
+}
+
+// Enter, or Done on the on-screen keyboard, does what the field's button would.
+fn profile_action(
+    mut presses: ButtonPresses<&ProfileButton>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut submitted: EventReader<FieldSubmitted>,
+    fields: Query<&TextField>,
+    mut deleting: ResMut<DeletingProfile>,
+    mut message: Query<&mut Text, With<ProfileMessage>>,
+    mut profile: ResMut<ProfilePaths>,
+    mut saves: ResMut<Saves>,
+    mut history: ResMut<MenuHistory>,
+    mut nav: ScreenNav,
+) {
+    let Ok(mut message) = message.single_mut() else {
+        return;
+    };
+    let pressed = presses.read().last().cloned();
+    let submitted = submitted.read().count() > 0;
+    let entered = (keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter])
+        || submitted)
+        .then(|| {
+            if deleting.0.is_some() {
+                ProfileButton::ConfirmDelete
+            } else {
+                ProfileButton::Create
+            }
+        });
+    let Some(action) = pressed.or(entered) else {
+        return;
+    };
+    let typed = fields
+        .iter()
+        .next()
+        .map(|field| field.value.trim().to_string())
+        .unwrap_or_default();
+    message.0.clear();
+    match action {
+        ProfileButton::Switch(name) => {
+            if name != profile.name() {
+                info!("Switching to profile {name}");
+                *profile = ProfilePaths::new(profile.root(), &name);
+                profiles::remember_active(&profile, &mut saves);
+            }
+        }
+        ProfileButton::Delete(name) => deleting.0 = Some(name),
+        ProfileButton::Create => {
+            if !profiles::valid_name(profile.root(), &typed) {
+                message.0 = format!(
+                    "Names are up to {MAX_NAME_LENGTH} letters, digits, spaces, - or _, \
+                     and not one already taken"
+                );
+                return;
+            }
+            match profiles::create(profile.root(), &typed) {
+                Ok(created) => {
+                    info!("Created profile {typed}");
+                    *profile = created;
+                    profiles::remember_active(&profile, &mut saves);
+                }
+                Err(error) => {
+                    warn!("Couldn't create profile {typed}: {error}");
+                    message.0 = format!("Couldn't create {typed}");
+                }
+            }
+        }
+        ProfileButton::ConfirmDelete => {
+            let Some(name) = deleting.0.clone() else {
+                return;
+            };
+            if typed != name {
+                message.0 = format!("Type \"{name}\" exactly to delete it");
+                return;
+            }
+            deleting.0 = None;
+            message.0 = match profiles::delete(profile.root(), &name) {
+                Ok(()) => {
+                    info!("Deleted profile {name}");
+                    format!("Deleted {name}")
+                }
+                Err(error) => {
+                    warn!("Couldn't delete profile {name}: {error}");
+                    format!("Couldn't delete {name}")
+                }
+            };
+        }
+        ProfileButton::CancelDelete => deleting.0 = None,
+        ProfileButton::Back => history.back(&mut nav),
+    }
+}
@@ -0,0 +1,345 @@
+//! Asking before the game closes, whether from the menu's Quit button or the window's close
+//! button. During a run with unsaved progress the prompt says so, and offers to quicksave
+//! first, see [`save_run`], staying up until the save is written, or saying why it
+//! couldn't be. Like other destructive buttons, its Quit button needs holding on a
+//! gamepad.
+//!
+//! A second close request within [`FORCE_QUIT_WINDOW`] quits without asking, so a stuck
+//! prompt can never keep the player from closing the game.
+
+use std::time::Duration;
+
+use bevy::{
+    ecs::spawn::SpawnWith, input::InputSystem, prelude::*, ui::FocusPolicy,
+    window::WindowCloseRequested,
+};
+
+use super::button_press::ButtonPresses;
+use super::hold_confirm::{HoldConfirmed, HoldToConfirm};
+use super::overlay::{ModalStack, ModalToken, Overlay};
+use crate::AppState;
+use crate::fonts::ui_font;
+use crate::input_device::ActiveInputDevice;
+use crate::palette::{BLUE, CORAL, DARK, DARKER, LIGHT, YELLOW};
+use crate::save_file::{SaveFinished, SaveId};
+use crate::settings::Locale;
+use crate::sim::{Dirty, quicksave::save_run};
+use crate::timers::RealUiTime;
+
+/// Above the settings screens and the toasts, since it can open over anything.
+const QUIT_PROMPT_Z_INDEX: i32 = 200;
+/// Closing the window twice this quickly skips the prompt.
+pub const FORCE_QUIT_WINDOW: Duration = Duration::from_secs(2);
+
+/// Send this to ask the player whether to quit the game.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct QuitRequested;
+
+#[derive(Component)]
+struct QuitPrompt;
+
+/// How saving before quitting is going, under the question.
+#[derive(Component)]
+struct QuitPromptMessage;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum QuitPromptButton {
+    SaveAndQuit,
+    Quit,
+    QuitWithoutSaving,
+    Cancel,
+}
+
+impl QuitPromptButton {
+    fn label(self) -> &'static str {
+        match self {
+            QuitPromptButton::SaveAndQuit => "Save and quit",
+            QuitPromptButton::Quit => "Quit",
+            QuitPromptButton::QuitWithoutSaving => "Quit without saving",
+            QuitPromptButton::Cancel => "Cancel",
+        }
+    }
+}
+
+/// The open prompt's hold on the [`ModalStack`] and the save it's waiting on, and when the
+/// window last asked to close.
+#[derive(Resource, Debug, Default)]
+struct QuitPromptState {
+    modal: Option<ModalToken>,
+    saving: Option<SaveId>,
+    last_close_request: Option<Duration>,
+}
+
+fn quit_prompt_closed(prompt: Query<(), With<QuitPrompt>>) -> bool {
+    prompt.is_empty()
+}
+
+pub fn quit_prompt_plugin(app: &mut App) {
+    app.add_event::<QuitRequested>()
+        .init_resource::<QuitPromptState>()
+        .add_systems(
+            Update,
+            (
+                forward_close_requests,
+                open_quit_prompt.run_if(on_event::<QuitRequested>),
+                (
+                    quit_prompt_action,
+                    quit_prompt_button_colors,
+                    finish_save_and_quit.run_if(on_event::<SaveFinished>),
+                )
+                    .run_if(not(quit_prompt_closed)),
+            )
+                .chain(),
+        )
+        .add_systems(
+            PreUpdate,
+            quit_prompt_input
+                .after(InputSystem)
+                .run_if(not(quit_prompt_closed)),
+        );
+}
+
+// The window doesn't close on its own, see `main`; it asks here instead.
+fn forward_close_requests(
+    mut close_requests: EventReader<WindowCloseRequested>,
+    time: RealUiTime,
+    mut state: ResMut<QuitPromptState>,
+    mut quit: EventWriter<QuitRequested>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for _ in close_requests.read() {
+        let now = time.elapsed();
+        let repeated = state
+            .last_close_request
+            .is_some_and(|last| now - last <= FORCE_QUIT_WINDOW);
+        state.last_close_request = Some(now);
+        if repeated {
+            info!("Window closed twice, quitting without asking");
+            exit.write(AppExit::Success);
+        } else {
+            quit.write(QuitRequested);
+        }
+    }
+}
+
+fn open_quit_prompt(
+    mut commands: Commands,
+    mut requests: EventReader<QuitRequested>,
+    prompt: Query<(), With<QuitPrompt>>,
+    app_state: Res<State<AppState>>,
+    overlay: Option<Res<State<Overlay>>>,
+    dirty: Res<Dirty>,
+    device: Res<ActiveInputDevice>,
+    locale: Res<Locale>,
+    mut modals: ResMut<ModalStack>,
+    mut state: ResMut<QuitPromptState>,
+    assets: Res<AssetServer>,
+) {
+    requests.clear();
+    if !prompt.is_empty() {
+        return;
+    }
+    // Not over a choice half made, like an event card, same as the quicksave keys. The
+    // pause menu only opens over the game itself.
+    let can_save =
+        modals.is_empty() || overlay.is_some_and(|overlay| *overlay.get() == Overlay::Pause);
+    state.modal = Some(modals.open_modal());
+    let unsaved = *app_state.get() == AppState::Game && dirty.0;
+    let (question, quit) = match (unsaved, can_save) {
+        (true, true) => (
+            "Quit? This run has progress since its last save",
+            QuitPromptButton::QuitWithoutSaving,
+        ),
+        (true, false) => (
+            "Quit? This run's progress since its last save will be lost",
+            QuitPromptButton::QuitWithoutSaving,
+        ),
+        (false, _) => ("Really quit?", QuitPromptButton::Quit),
+    };
+    let hint = device.0.prompt("Esc: cancel", "Hold Y: quit - B: cancel");
+    let direction = locale.direction().text();
+    let font = ui_font(&assets);
+    let text_font = move |font_size| TextFont {
+        font: font.clone(),
+        font_size,
+        ..default()
+    };
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.6)),
+        FocusPolicy::Block,
+        GlobalZIndex(QUIT_PROMPT_Z_INDEX),
+        QuitPrompt,
+        children![(
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(24.0)),
+                ..default()
+            },
+            BackgroundColor(DARKER),
+            Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+                parent.spawn((Text::new(question), text_font(16.0), TextColor(YELLOW)));
+                parent.spawn((
+                    Text::default(),
+                    text_font(12.0),
+                    TextColor(CORAL),
+                    QuitPromptMessage,
+                ));
+                if unsaved && can_save {
+                    parent.spawn(quit_prompt_button(
+                        QuitPromptButton::SaveAndQuit,
+                        text_font(16.0),
+                    ));
+                }
+                parent.spawn((
+                    quit_prompt_button(quit, text_font(16.0)),
+                    HoldToConfirm::new(GamepadButton::North),
+                ));
+                parent.spawn(quit_prompt_button(
+                    QuitPromptButton::Cancel,
+                    text_font(16.0),
+                ));
+                parent.spawn((
+                    Text::new(hint),
+                    text_font(12.0),
+                    TextColor(LIGHT.with_alpha(0.6)),
+                    direction,
+                ));
+            })),
+        )],
+    ));
+}
+
+fn quit_prompt_button(button: QuitPromptButton, font: TextFont) -> impl Bundle {
+    (
+        Button,
+        Node {
+            width: Val::Px(260.0),
+            height: Val::Px(48.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(DARK),
+        button,
+        children![(Text::new(button.label()), font, TextColor(LIGHT))],
+    )
+}
+
+// Backing out is taken from the input before anything else sees it, so the screen behind
+// the prompt doesn't back out too.
+fn quit_prompt_input(
+    mut commands: Commands,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut gamepads: Query<&mut Gamepad>,
+) {
+    let mut back = keyboard_input.clear_just_pressed(KeyCode::Escape);
+    for mut gamepad in &mut gamepads {
+        let buttons = gamepad.digital_mut();
+        back |= buttons.clear_just_pressed(GamepadButton::East);
+        back |= buttons.clear_just_pressed(GamepadButton::Start);
+    }
+    if back {
+        commands.run_system_cached(close_quit_prompt);
+    }
+}
+
+fn quit_prompt_action(
+    mut commands: Commands,
+    mut presses: ButtonPresses<&QuitPromptButton, Without<HoldToConfirm>>,
+    held_buttons: Query<&QuitPromptButton, With<HoldToConfirm>>,
+    mut confirmed: EventReader<HoldConfirmed>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let pressed = presses.read();
+    let confirmed = confirmed
+        .read()
+        .filter_map(|HoldConfirmed(entity)| held_buttons.get(*entity).ok());
+    for button in pressed.chain(confirmed) {
+        match button {
+            QuitPromptButton::SaveAndQuit => commands.queue(save_and_quit),
+            QuitPromptButton::Quit | QuitPromptButton::QuitWithoutSaving => {
+                exit.write(AppExit::Success);
+            }
+            QuitPromptButton::Cancel => commands.run_system_cached(close_quit_prompt),
+        }
+    }
+}
+
+// The prompt stays up until the save is written, see `finish_save_and_quit`.
+fn save_and_quit(world: &mut World) {
+    if world.resource::<QuitPromptState>().saving.is_some() {
+        return;
+    }
+    let id = save_run(world);
+    world.resource_mut::<QuitPromptState>().saving = Some(id);
+    let mut message = world.query_filtered::<&mut Text, With<QuitPromptMessage>>();
+    for mut text in message.iter_mut(world) {
+        text.0 = "Saving…".to_string();
+    }
+}
+
+// Quits once the run is written, or says why it couldn't be and stays.
+fn finish_save_and_quit(
+    mut finished: EventReader<SaveFinished>,
+    mut state: ResMut<QuitPromptState>,
+    mut message: Query<&mut Text, With<QuitPromptMessage>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some(saving) = state.saving else {
+        finished.clear();
+        return;
+    };
+    let Some(SaveFinished { result, .. }) = finished.read().find(|save| save.id == saving) else {
+        return;
+    };
+    state.saving = None;
+    match result {
+        Ok(()) => {
+            exit.write(AppExit::Success);
+        }
+        Err(error) => {
+            for mut text in &mut message {
+                text.0 = format!("Couldn't save: {error}");
+            }
+        }
+    }
+}
+
+fn quit_prompt_button_colors(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<QuitPromptButton>),
+    >,
+) {
+    for (interaction, mut background) in &mut buttons {
+        background.0 = match interaction {
+            Interaction::None => DARK,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
+
+fn close_quit_prompt(
+    mut commands: Commands,
+    prompt: Query<Entity, With<QuitPrompt>>,
+    mut modals: ResMut<ModalStack>,
+    mut state: ResMut<QuitPromptState>,
+) {
+    if let Some(token) = state.modal.take() {
+        modals.close_modal(token);
+    }
+    state.saving = None;
+    for entity in &prompt {
+        commands.entity(entity).despawn();
+    }
+}
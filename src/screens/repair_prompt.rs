@@ -0,0 +1,251 @@
+//! Telling the player about saved files that were found broken, see
+//! [`crate::save_file::CorruptFiles`]. The prompt opens once the main menu is up, and
+//! offers to salvage what can still be read of the files or to carry on without them.
+//! Either way the player is only asked once; the broken copies stay on disk.
+
+use std::path::Path;
+
+use bevy::{input::InputSystem, prelude::*, ui::FocusPolicy};
+
+use super::button_press::ButtonPresses;
+use super::menu::MenuState;
+use super::overlay::{ModalStack, ModalToken};
+use crate::fonts::ui_font;
+use crate::input_device::ActiveInputDevice;
+use crate::palette::{BLUE, DARK, DARKER, LIGHT, YELLOW};
+use crate::profiles::ProfilePaths;
+use crate::save_file::CorruptFiles;
+use crate::settings::Locale;
+use crate::toast::Toast;
+
+/// Above the menu and the toasts, below the quit prompt.
+const REPAIR_PROMPT_Z_INDEX: i32 = 190;
+
+#[derive(Component)]
+struct RepairPrompt;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum RepairPromptButton {
+    Recover,
+    StartFresh,
+}
+
+impl RepairPromptButton {
+    fn label(self) -> &'static str {
+        match self {
+            RepairPromptButton::Recover => "Try to recover",
+            RepairPromptButton::StartFresh => "Start fresh",
+        }
+    }
+}
+
+/// The open prompt's hold on the [`ModalStack`].
+#[derive(Resource, Debug, Default)]
+struct RepairPromptModal(Option<ModalToken>);
+
+fn repair_prompt_closed(prompt: Query<(), With<RepairPrompt>>) -> bool {
+    prompt.is_empty()
+}
+
+fn files_to_repair(files: Res<CorruptFiles>) -> bool {
+    !files.0.is_empty()
+}
+
+pub fn repair_prompt_plugin(app: &mut App) {
+    app.init_resource::<RepairPromptModal>()
+        .add_systems(
+            Update,
+            (
+                open_repair_prompt.run_if(
+                    in_state(MenuState::Main)
+                        .and(files_to_repair)
+                        .and(repair_prompt_closed),
+                ),
+                (repair_prompt_action, repair_prompt_button_colors)
+                    .run_if(not(repair_prompt_closed)),
+            )
+                .chain(),
+        )
+        .add_systems(
+            PreUpdate,
+            repair_prompt_input
+                .after(InputSystem)
+                .run_if(not(repair_prompt_closed)),
+        );
+}
+
+fn open_repair_prompt(
+    mut commands: Commands,
+    files: Res<CorruptFiles>,
+    device: Res<ActiveInputDevice>,
+    locale: Res<Locale>,
+    mut modals: ResMut<ModalStack>,
+    mut modal: ResMut<RepairPromptModal>,
+    assets: Res<AssetServer>,
+) {
+    modal.0 = Some(modals.open_modal());
+    let names: Vec<_> = files
+        .0
+        .iter()
+        .map(|file| {
+            format!(
+                "{} (kept as {})",
+                file_name(&file.path),
+                file_name(&file.bad)
+            )
+        })
+        .collect();
+    let message = format!(
+        "Some saved files were damaged, maybe by a crash, so the game started without them \
+         and your settings may be back to their defaults:\n{}",
+        names.join("\n")
+    );
+    let font = ui_font(&assets);
+    let text_font = |font_size| TextFont {
+        font: font.clone(),
+        font_size,
+        ..default()
+    };
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.6)),
+        FocusPolicy::Block,
+        GlobalZIndex(REPAIR_PROMPT_Z_INDEX),
+        RepairPrompt,
+        children![(
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(24.0)),
+                max_width: Val::Px(520.0),
+                ..default()
+            },
+            BackgroundColor(DARKER),
+            children![
+                (
+                    Text::new(message),
+                    text_font(16.0),
+                    TextColor(YELLOW),
+                    locale.direction().text(),
+                ),
+                repair_prompt_button(RepairPromptButton::Recover, text_font(16.0)),
+                repair_prompt_button(RepairPromptButton::StartFresh, text_font(16.0)),
+                (
+                    Text::new(device.0.prompt("Esc: start fresh", "B: start fresh")),
+                    text_font(12.0),
+                    TextColor(LIGHT.with_alpha(0.6)),
+                    locale.direction().text(),
+                ),
+            ],
+        )],
+    ));
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn repair_prompt_button(button: RepairPromptButton, font: TextFont) -> impl Bundle {
+    (
+        Button,
+        Node {
+            width: Val::Px(260.0),
+            height: Val::Px(48.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(DARK),
+        button,
+        children![(Text::new(button.label()), font, TextColor(LIGHT))],
+    )
+}
+
+// Backing out is taken from the input before the menu behind sees it, like the quit
+// prompt does.
+fn repair_prompt_input(
+    mut commands: Commands,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut gamepads: Query<&mut Gamepad>,
+) {
+    let mut back = keyboard_input.clear_just_pressed(KeyCode::Escape);
+    for mut gamepad in &mut gamepads {
+        back |= gamepad
+            .digital_mut()
+            .clear_just_pressed(GamepadButton::East);
+    }
+    if back {
+        commands.run_system_cached(close_repair_prompt);
+    }
+}
+
+fn repair_prompt_action(
+    mut commands: Commands,
+    mut presses: ButtonPresses<&RepairPromptButton>,
+    files: Res<CorruptFiles>,
+    mut profile: ResMut<ProfilePaths>,
+    mut toasts: EventWriter<Toast>,
+) {
+    for button in presses.read() {
+        if *button == RepairPromptButton::Recover {
+            for file in &files.0 {
+                let name = file_name(&file.path);
+                match file.recover() {
+                    Ok(()) => {
+                        info!("Recovered {name}");
+                        toasts.write(Toast::new(format!("Recovered {name}")));
+                    }
+                    Err(error) => {
+                        warn!("Couldn't recover {name}: {error}");
+                        toasts.write(Toast::new(format!("Couldn't recover {name}")));
+                    }
+                }
+            }
+            // Everything that reads the profile's files reads them again, as it does
+            // when the player switches profiles.
+            profile.set_changed();
+        }
+        commands.run_system_cached(close_repair_prompt);
+    }
+}
+
+fn repair_prompt_button_colors(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<RepairPromptButton>),
+    >,
+) {
+    for (interaction, mut background) in &mut buttons {
+        background.0 = match interaction {
+            Interaction::None => DARK,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
+
+fn close_repair_prompt(
+    mut commands: Commands,
+    prompt: Query<Entity, With<RepairPrompt>>,
+    mut files: ResMut<CorruptFiles>,
+    mut modals: ResMut<ModalStack>,
+    mut modal: ResMut<RepairPromptModal>,
+) {
+    files.0.clear();
+    if let Some(token) = modal.0.take() {
+        modals.close_modal(token);
+    }
+    for entity in &prompt {
+        commands.entity(entity).despawn();
+    }
+}
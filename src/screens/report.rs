@@ -0,0 +1,706 @@
+//! The monthly report: income and expenses by category, each also split into a stacked
+//! bar, rent burden and net worth change for a finished month. Opens by itself when a
+//! month ends, and past months can be looked up from the action panel. A second tab lists the decisions made over the run, and a
+//! third the rent paid, with a chart of how it changed.
+
+use std::collections::BTreeMap;
+
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+
+use super::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use super::area_theme::readable_on;
+use super::overlay::{MODAL_Z_INDEX, Overlay};
+use super::scroll::scrollable;
+use crate::fonts::ui_font;
+use crate::format;
+use crate::palette::{BLUE, CORAL, DARK, DARKER, LIGHT, RED, YELLOW};
+use crate::settings::Locale;
+use crate::sim::{
+    choices::ChoiceLog,
+    economy::{RentCharge, RentHistory},
+    ledger::{Category, Ledger, MonthEnded, MonthSummary},
+};
+use crate::{AppState, despawn_screen, track_screen};
+
+const REPORT_FONT_SIZE: f32 = 16.0;
+const BAR_TRACK_WIDTH: f32 = 240.0;
+const BAR_HEIGHT: f32 = 14.0;
+const CATEGORY_LABEL_WIDTH: f32 = 110.0;
+const DECISIONS_WIDTH: f32 = 520.0;
+/// How many rent payments the housing table lists, newest first.
+const HOUSING_ROWS: usize = 12;
+const RENT_CHART_HEIGHT: f32 = 60.0;
+const RENT_BAR_WIDTH: f32 = 8.0;
+/// The shortest bar, in percent of the chart, so the cheapest rent still shows.
+const RENT_BAR_FLOOR: f32 = 10.0;
+const SPLIT_BAR_WIDTH: f32 = 420.0;
+const SPLIT_BAR_HEIGHT: f32 = 20.0;
+const SPLIT_FONT_SIZE: f32 = 12.0;
+/// Categories under this share of a stacked bar go into "Other", when there are several.
+const OTHER_SHARE: f32 = 0.05;
+/// Segments under this share are too narrow for their percentage.
+const LABELED_SHARE: f32 = 0.08;
+/// Segment colors, in order, so a bar names at most this many categories. "Other" is
+/// always [`DARK`].
+const SEGMENT_COLORS: [Color; 5] = [BLUE, YELLOW, CORAL, LIGHT, RED];
+
+/// A piece of a stacked bar: one category, or `None` for the small ones put together.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub category: Option<Category>,
+    pub amount: u32,
+    /// Of the bar's total, from 0 to 1.
+    pub share: f32,
+}
+
+/// Splits `totals` into the segments of a stacked bar, in category order with "Other" last.
+/// Categories under [`OTHER_SHARE`] go into "Other", unless only one would, and so do the
+/// smallest ones past the [`SEGMENT_COLORS`] there are. A month without any money in the
+/// totals has no segments.
+pub fn segments(totals: &BTreeMap<Category, u32>) -> Vec<Segment> {
+    let total: u32 = totals.values().sum();
+    if total == 0 {
+        return Vec::new();
+    }
+    let segment = |category, amount| Segment {
+        category,
+        amount,
+        share: amount as f32 / total as f32,
+    };
+    let mut by_size: Vec<_> = totals
+        .iter()
+        .filter(|(_, amount)| **amount > 0)
+        .map(|(category, amount)| segment(Some(*category), *amount))
+        .collect();
+    by_size.sort_by_key(|segment| std::cmp::Reverse(segment.amount));
+    let named = by_size
+        .iter()
+        .take(SEGMENT_COLORS.len())
+        .take_while(|segment| segment.share >= OTHER_SHARE)
+        .count();
+    let named = match by_size.len() - named {
+        1 if named < SEGMENT_COLORS.len() => named + 1,
+        _ => named,
+    };
+    let small = by_size.split_off(named);
+    let mut segments = by_size;
+    segments.sort_by_key(|segment| segment.category);
+    if !small.is_empty() {
+        segments.push(segment(None, small.iter().map(|small| small.amount).sum()));
+    }
+    segments
+}
+
+/// Index into [`Ledger::history`] of the month on screen.
+#[derive(Resource, Debug, Default)]
+struct ShownReport(usize);
+
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ReportTab {
+    #[default]
+    Months,
+    Decisions,
+    Housing,
+}
+
+impl ReportTab {
+    /// The tab Tab switches to. Months are skipped until one has ended.
+    fn next(self, has_months: bool) -> Self {
+        match self {
+            ReportTab::Months => ReportTab::Decisions,
+            ReportTab::Decisions => ReportTab::Housing,
+            ReportTab::Housing if has_months => ReportTab::Months,
+            ReportTab::Housing => ReportTab::Decisions,
+        }
+    }
+}
+
+#[derive(Component)]
+struct ReportOverlay;
+
+#[derive(Component)]
+struct ReportContent;
+
+pub fn report_plugin(app: &mut App) {
+    track_screen::<ReportOverlay>(app, Overlay::MonthlyReport);
+    app.init_resource::<ShownReport>()
+        .init_resource::<ReportTab>()
+        .add_systems(Update, offer_reports.in_set(ActionSet::Offer))
+        .add_systems(
+            Update,
+            (open_report_on_month_end, open_report_on_request).run_if(in_state(AppState::Game)),
+        )
+        .add_systems(OnEnter(Overlay::MonthlyReport), setup_report)
+        .add_systems(
+            OnExit(Overlay::MonthlyReport),
+            despawn_screen::<ReportOverlay>,
+        )
+        .add_systems(
+            Update,
+            (
+                report_input,
+                render_report
+                    .run_if(resource_changed::<ShownReport>.or(resource_changed::<ReportTab>)),
+            )
+                .chain()
+                .run_if(in_state(Overlay::MonthlyReport)),
+        );
+}
+
+fn offer_reports(
+    ledger: Res<Ledger>,
+    choices: Res<ChoiceLog>,
+    rent: Res<RentHistory>,
+    mut actions: ResMut<AvailableActions>,
+) {
+    if !ledger.history.is_empty() || !choices.0.is_empty() || !rent.0.is_empty() {
+        actions.offer(GameAction::ViewReports);
+    }
+}
+
+fn open_report_on_month_end(
+    mut ended: EventReader<MonthEnded>,
+    ledger: Res<Ledger>,
+    mut shown: ResMut<ShownReport>,
+    mut tab: ResMut<ReportTab>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if ended.read().count() > 0 && !ledger.history.is_empty() {
+        shown.0 = ledger.history.len() - 1;
+        *tab = ReportTab::Months;
+        next_state.set(Overlay::MonthlyReport);
+    }
+}
+
+fn open_report_on_request(
+    mut pressed: EventReader<ActionPressed>,
+    ledger: Res<Ledger>,
+    mut shown: ResMut<ShownReport>,
+    mut tab: ResMut<ReportTab>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        if *action != GameAction::ViewReports {
+            continue;
+        }
+        // Before the first month is over there are only decisions to show.
+        *tab = match ledger.history.len() {
+            0 => ReportTab::Decisions,
+            months => {
+                shown.0 = months - 1;
+                ReportTab::Months
+            }
+        };
+        next_state.set(Overlay::MonthlyReport);
+    }
+}
+
+fn setup_report(mut commands: Commands, mut shown: ResMut<ShownReport>) {
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        GlobalZIndex(MODAL_Z_INDEX),
+        ReportOverlay,
+        children![(
+            Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            BackgroundColor(DARKER),
+            ReportContent,
+        )],
+    ));
+    // Render on the first frame even if the same month was shown last time.
+    shown.set_changed();
+}
+
+fn report_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    ledger: Res<Ledger>,
+    mut shown: ResMut<ShownReport>,
+    mut tab: ResMut<ReportTab>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::Enter, KeyCode::Space]) {
+        next_state.set(Overlay::None);
+    }
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        *tab = tab.next(!ledger.history.is_empty());
+    }
+    if *tab != ReportTab::Months {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) && shown.0 > 0 {
+        shown.0 -= 1;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) && shown.0 + 1 < ledger.history.len() {
+        shown.0 += 1;
+    }
+}
+
+fn render_report(
+    mut commands: Commands,
+    ledger: Res<Ledger>,
+    choices: Res<ChoiceLog>,
+    rent: Res<RentHistory>,
+    shown: Res<ShownReport>,
+    tab: Res<ReportTab>,
+    content: Query<Entity, With<ReportContent>>,
+    assets: Res<AssetServer>,
+    locale: Res<Locale>,
+) {
+    let Ok(content) = content.single() else {
+        return;
+    };
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: REPORT_FONT_SIZE,
+        ..default()
+    };
+    let mut content = commands.entity(content);
+    content.despawn_related::<Children>();
+    match *tab {
+        ReportTab::Months => {}
+        ReportTab::Decisions => {
+            content.with_children(|parent| spawn_decisions(parent, &choices, &font));
+            return;
+        }
+        ReportTab::Housing => {
+            content.with_children(|parent| {
+                spawn_housing(parent, &rent.0, !ledger.history.is_empty(), &font, *locale);
+            });
+            return;
+        }
+    }
+    let Some(month) = ledger.history.get(shown.0) else {
+        return;
+    };
+    let text = |value: String, color: Color| (Text::new(value), font.clone(), TextColor(color));
+
+    content.with_children(|parent| {
+        parent.spawn(text(format!("Month {} report", month.month), YELLOW));
+        spawn_category_bars(parent, "Income", &month.income, BLUE, &font, *locale);
+        spawn_category_bars(parent, "Expenses", &month.expenses, CORAL, &font, *locale);
+        spawn_split_bar(parent, "Income split", &month.income, &font);
+        spawn_split_bar(parent, "Expense split", &month.expenses, &font);
+
+        let rent_burden = match month.rent_to_income() {
+            Some(ratio) => format!("{:.0}%", ratio * 100.0),
+            None => "n/a".to_string(),
+        };
+        parent.spawn(text(format!("Rent to income: {rent_burden}"), LIGHT));
+        parent.spawn(text(net_worth_line(month, *locale), LIGHT));
+        parent.spawn(text(month.verdict(), YELLOW));
+        parent.spawn(text(
+            format!(
+                "Month {} of {} - Left/Right to browse, Tab for decisions, Esc to close",
+                shown.0 + 1,
+                ledger.history.len()
+            ),
+            LIGHT.with_alpha(0.6),
+        ));
+    });
+}
+
+/// How many notable decisions the end screens bring up.
+const END_SCREEN_DECISIONS: usize = 2;
+
+/// The run's last notable decisions, a line each, for the end screens.
+pub fn notable_decisions(choices: &ChoiceLog) -> impl Bundle {
+    let lines: Vec<_> = choices
+        .notable(END_SCREEN_DECISIONS)
+        .into_iter()
+        .filter_map(|choice| choice.summary())
+        .collect();
+    (
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(6.0),
+            margin: UiRect::top(Val::Px(20.0)),
+            ..default()
+        },
+        Children::spawn(SpawnIter(lines.into_iter().map(|line| {
+            (
+                Text::new(line),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(LIGHT.with_alpha(0.8)),
+            )
+        }))),
+    )
+}
+
+/// Every recorded choice, newest first.
+fn spawn_decisions(parent: &mut ChildSpawnerCommands, choices: &ChoiceLog, font: &TextFont) {
+    let text = |value: String, color: Color| (Text::new(value), font.clone(), TextColor(color));
+    parent.spawn(text("Decisions".to_string(), YELLOW));
+    parent
+        .spawn(scrollable(Node {
+            width: Val::Px(DECISIONS_WIDTH),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.0),
+            max_height: Val::Vh(60.0),
+            ..default()
+        }))
+        .with_children(|list| {
+            if choices.0.is_empty() {
+                list.spawn(text("No decisions yet".to_string(), LIGHT.with_alpha(0.6)));
+            }
+            for choice in choices.0.iter().rev() {
+                list.spawn(text(
+                    format!("Day {} - {}: {}", choice.day, choice.context, choice.choice),
+                    if choice.notable.is_some() {
+                        YELLOW
+                    } else {
+                        LIGHT
+                    },
+                ));
+                if let Some(outcome) = &choice.outcome {
+                    list.spawn(text(format!("  {outcome}"), LIGHT.with_alpha(0.6)));
+                }
+            }
+        });
+    parent.spawn(text(
+        "Tab for housing, Esc to close".to_string(),
+        LIGHT.with_alpha(0.6),
+    ));
+}
+
+/// The latest rent payments, noting each move, over a bar per payment of the whole run.
+fn spawn_housing(
+    parent: &mut ChildSpawnerCommands,
+    charges: &[RentCharge],
+    has_months: bool,
+    font: &TextFont,
+    locale: Locale,
+) {
+    let text = |value: String, color: Color| (Text::new(value), font.clone(), TextColor(color));
+    parent.spawn(text("Housing".to_string(), YELLOW));
+    if charges.is_empty() {
+        parent.spawn(text("No rent paid yet".to_string(), LIGHT.with_alpha(0.6)));
+    } else {
+        spawn_rent_chart(parent, charges);
+    }
+    parent
+        .spawn(scrollable(Node {
+            width: Val::Px(DECISIONS_WIDTH),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.0),
+            max_height: Val::Vh(50.0),
+            ..default()
+        }))
+        .with_children(|list| {
+            let first = charges.len().saturating_sub(HOUSING_ROWS);
+            for (index, charge) in charges.iter().enumerate().skip(first).rev() {
+                let mut line =
+                    format!("Day {} - {}", charge.day, format::money(charge.net, locale));
+                if charge.modifiers.is_empty() {
+                    line += " (listed rent)";
+                } else {
+                    line += &format!(
+                        " ({} listed, {})",
+                        format::money(charge.gross, locale),
+                        charge.modifiers.join(", ")
+                    );
+                }
+                list.spawn(text(line, LIGHT));
+                let moved = index
+                    .checked_sub(1)
+                    .is_some_and(|previous| charges[previous].area != charge.area);
+                if moved {
+                    list.spawn(text(format!("  Moved to {:?}", charge.area), YELLOW));
+                }
+            }
+        });
+    let footer = if has_months {
+        "Tab for monthly reports, Esc to close"
+    } else {
+        "Tab for decisions, Esc to close"
+    };
+    parent.spawn(text(footer.to_string(), LIGHT.with_alpha(0.6)));
+}
+
+/// A bar per rent payment, from zero up to the highest rent paid.
+fn spawn_rent_chart(parent: &mut ChildSpawnerCommands, charges: &[RentCharge]) {
+    let highest = charges
+        .iter()
+        .map(|charge| charge.net)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    parent
+        .spawn(Node {
+            height: Val::Px(RENT_CHART_HEIGHT),
+            align_items: AlignItems::FlexEnd,
+            column_gap: Val::Px(1.0),
+            ..default()
+        })
+        .with_children(|chart| {
+            for charge in charges {
+                let share = charge.net as f32 / highest as f32;
+                chart.spawn((
+                    Node {
+                        width: Val::Px(RENT_BAR_WIDTH),
+                        height: Val::Percent(RENT_BAR_FLOOR + share * (100.0 - RENT_BAR_FLOOR)),
+                        ..default()
+                    },
+                    BackgroundColor(CORAL),
+                ));
+            }
+        });
+}
+
+fn net_worth_line(month: &MonthSummary, locale: Locale) -> String {
+    let change = month.net_worth_change();
+    let sign = if change < 0 { "" } else { "+" };
+    format!(
+        "Net worth: {} -> {} ({sign}{})",
+        format::money(month.start_net_worth, locale),
+        format::money(month.end_net_worth, locale),
+        format::format_money(change * 100, locale)
+    )
+}
+
+fn spawn_category_bars(
+    parent: &mut ChildSpawnerCommands,
+    title: &str,
+    totals: &BTreeMap<Category, u32>,
+    color: Color,
+    font: &TextFont,
+    locale: Locale,
+) {
+    let total: u32 = totals.values().sum();
+    parent.spawn((
+        Text::new(format!("{title}: {}", format::money(total, locale))),
+        font.clone(),
+        TextColor(LIGHT),
+        Node {
+            margin: UiRect::top(Val::Px(8.0)),
+            ..default()
+        },
+    ));
+    if totals.is_empty() {
+        parent.spawn((
+            Text::new("  none"),
+            font.clone(),
+            TextColor(LIGHT.with_alpha(0.6)),
+        ));
+        return;
+    }
+
+    let largest = totals.values().copied().max().unwrap_or(0).max(1);
+    for (category, amount) in totals {
+        parent.spawn((
+            Node {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.0),
+                ..default()
+            },
+            children![
+                (
+                    Text::new(category.label()),
+                    font.clone(),
+                    TextColor(LIGHT),
+                    Node {
+                        width: Val::Px(CATEGORY_LABEL_WIDTH),
+                        ..default()
+                    },
+                ),
+                (
+                    Node {
+                        width: Val::Px(BAR_TRACK_WIDTH),
+                        height: Val::Px(BAR_HEIGHT),
+                        ..default()
+                    },
+                    children![(
+                        Node {
+                            width: Val::Percent(*amount as f32 / largest as f32 * 100.0),
+                            min_width: Val::Px(2.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(color),
+                    )],
+                ),
+                (
+                    Text::new(format::money(*amount, locale)),
+                    font.clone(),
+                    TextColor(LIGHT)
+                ),
+            ],
+        ));
+    }
+}
+
+/// A bar split by category, each segment as wide as its share, over a legend.
+fn spawn_split_bar(
+    parent: &mut ChildSpawnerCommands,
+    title: &str,
+    totals: &BTreeMap<Category, u32>,
+    font: &TextFont,
+) {
+    let segments = segments(totals);
+    if segments.is_empty() {
+        return;
+    }
+    let small_font = TextFont {
+        font_size: SPLIT_FONT_SIZE,
+        ..font.clone()
+    };
+    let color = |index: usize, segment: &Segment| {
+        if segment.category.is_some() {
+            SEGMENT_COLORS[index]
+        } else {
+            DARK
+        }
+    };
+    let label = |segment: &Segment| segment.category.map_or("Other", Category::label);
+    parent.spawn((
+        Text::new(title),
+        font.clone(),
+        TextColor(LIGHT),
+        Node {
+            margin: UiRect::top(Val::Px(8.0)),
+            ..default()
+        },
+    ));
+    parent
+        .spawn(Node {
+            width: Val::Px(SPLIT_BAR_WIDTH),
+            height: Val::Px(SPLIT_BAR_HEIGHT),
+            ..default()
+        })
+        .with_children(|bar| {
+            for (index, segment) in segments.iter().enumerate() {
+                let background = color(index, segment);
+                let mut piece = bar.spawn((
+                    Node {
+                        width: Val::Percent(segment.share * 100.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        overflow: Overflow::clip(),
+                        ..default()
+                    },
+                    BackgroundColor(background),
+                ));
+                if segment.share >= LABELED_SHARE {
+                    piece.with_child((
+                        Text::new(format!("{:.0}%", segment.share * 100.0)),
+                        small_font.clone(),
+                        TextColor(readable_on(background, DARKER)),
+                    ));
+                }
+            }
+        });
+    parent
+        .spawn(Node {
+            width: Val::Px(SPLIT_BAR_WIDTH),
+            flex_wrap: FlexWrap::Wrap,
+            column_gap: Val::Px(12.0),
+            row_gap: Val::Px(2.0),
+            ..default()
+        })
+        .with_children(|legend| {
+            for (index, segment) in segments.iter().enumerate() {
+                legend.spawn((
+                    Node {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    children![
+                        (
+                            Node {
+                                width: Val::Px(SPLIT_FONT_SIZE),
+                                height: Val::Px(SPLIT_FONT_SIZE),
+                                ..default()
+                            },
+                            BackgroundColor(color(index, segment)),
+                        ),
+                        (
+                            Text::new(format!("{} {:.0}%", label(segment), segment.share * 100.0)),
+                            small_font.clone(),
+                            TextColor(LIGHT),
+                        ),
+                    ],
+                ));
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments_name_no_more_categories_than_there_are_colors() {
+        let totals: BTreeMap<Category, u32> = [
+            Category::Rent,
+            Category::Food,
+            Category::Utilities,
+            Category::Purchases,
+            Category::Donations,
+            Category::Holidays,
+            Category::Theft,
+        ]
+        .into_iter()
+        .zip([400, 200, 150, 100, 80, 60, 10])
+        .collect();
+        let segments = segments(&totals);
+        let named: Vec<_> = segments
+            .iter()
+            .filter_map(|segment| segment.category)
+            .collect();
+        assert_eq!(
+            named,
+            [
+                Category::Rent,
+                Category::Utilities,
+                Category::Food,
+                Category::Purchases,
+                Category::Donations,
+            ]
+        );
+        let other = segments.last().unwrap();
+        assert_eq!((other.category, other.amount), (None, 70));
+    }
+
+    #[test]
+    fn segments_keep_a_lone_small_category_by_name() {
+        let totals = BTreeMap::from([(Category::Rent, 990), (Category::Food, 10)]);
+        let segments = segments(&totals);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[1].category, Some(Category::Food));
+    }
+
+    #[test]
+    fn a_month_without_income_has_an_empty_bar() {
+        assert_eq!(segments(&BTreeMap::new()), []);
+        let nothing_earned = BTreeMap::from([(Category::Wages, 0), (Category::Freelance, 0)]);
+        assert_eq!(segments(&nothing_earned), []);
+    }
+
+    #[test]
+    fn a_lone_category_fills_the_bar() {
+        let totals = BTreeMap::from([(Category::Wages, 0), (Category::Rent, 1750)]);
+        assert_eq!(
+            segments(&totals),
+            [Segment {
+                category: Some(Category::Rent),
+                amount: 1750,
+                share: 1.0,
+            }]
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! The savings goal panel, for picking a target or dropping it, and the slim bar in the HUD
+//! showing how far the player's net worth is toward it.
+
+use bevy::prelude::*;
+
+use super::actions::{ActionPressed, GameAction};
+use super::amount_input::{AmountConfirmed, AmountInput, amount_input};
+use super::area_theme::{AccentText, AccentTint};
+use super::button_press::ButtonPresses;
+use super::hud::{HudOrder, HudRoot, HudSlot};
+use super::overlay::{MODAL_Z_INDEX, Overlay};
+use super::text_backing::TextBacking;
+use super::tooltip::Tooltip;
+use crate::fonts::ui_font;
+use crate::format;
+use crate::palette::{BLUE, DARK, DARKER, LIGHT, YELLOW};
+use crate::settings::Locale;
+use crate::sim::{
+    ledger::{Wealth, net_worth_changed},
+    savings_goal::SavingsGoal,
+};
+use crate::toast::Toast;
+use crate::{AppState, despawn_screen, track_screen};
+
+const PANEL_FONT_SIZE: f32 = 16.0;
+const BAR_FONT_SIZE: f32 = 12.0;
+const BAR_WIDTH: f32 = 120.0;
+const BAR_HEIGHT: f32 = 4.0;
+const GOAL_STEP: u32 = 50;
+/// The largest goal the panel offers.
+const MAX_GOAL: u32 = 100_000;
+
+#[derive(Component)]
+struct SavingsGoalOverlay;
+
+#[derive(Component)]
+struct ClearGoalButton;
+
+#[derive(Component)]
+struct GoalBar;
+
+/// Sent when the player drops their savings goal.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoalCleared;
+
+#[derive(Component)]
+struct GoalFill;
+
+pub fn savings_goal_plugin(app: &mut App) {
+    track_screen::<SavingsGoalOverlay>(app, Overlay::SavingsGoal);
+    track_screen::<GoalBar>(app, AppState::Game);
+    app.add_event::<GoalCleared>()
+        .add_systems(OnEnter(AppState::Game), spawn_goal_bar)
+        .add_systems(OnExit(AppState::Game), despawn_screen::<GoalBar>)
+        .add_systems(
+            Update,
+            update_goal_bar.run_if(in_state(AppState::Game)).run_if(
+                resource_changed::<SavingsGoal>
+                    .or(net_worth_changed)
+                    .or(resource_changed::<Locale>),
+            ),
+        )
+        .add_systems(Update, open_savings_goal.run_if(in_state(Overlay::None)))
+        .add_systems(OnEnter(Overlay::SavingsGoal), setup_savings_goal)
+        .add_systems(
+            OnExit(Overlay::SavingsGoal),
+            despawn_screen::<SavingsGoalOverlay>,
+        )
+        .add_systems(
+            Update,
+            (
+                close_savings_goal,
+                set_goal,
+                (press_clear_goal, clear_goal).chain(),
+                clear_button_color,
+            )
+                .run_if(in_state(Overlay::SavingsGoal)),
+        );
+}
+
+fn spawn_goal_bar(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.spawn((
+        Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(2.0),
+            ..default()
+        },
+        Visibility::Hidden,
+        GoalBar,
+        HudRoot,
+        HudSlot::TopLeft,
+        HudOrder(3),
+        Tooltip::new("Your cash and savings, toward the goal you set"),
+        children![
+            (
+                Text::default(),
+                TextFont {
+                    font: ui_font(&assets),
+                    font_size: BAR_FONT_SIZE,
+                    ..default()
+                },
+                TextBacking::default(),
+                AccentText(LIGHT),
+            ),
+            (
+                Node {
+                    width: Val::Px(BAR_WIDTH),
+                    height: Val::Px(BAR_HEIGHT),
+                    ..default()
+                },
+                AccentTint::chrome(DARKER),
+                children![(
+                    Node {
+                        width: Val::Percent(0.0),
+                        ..default()
+                    },
+                    AccentTint::fill(BLUE),
+                    GoalFill,
+                )],
+            ),
+        ],
+    ));
+}
+
+fn update_goal_bar(
+    goal: Res<SavingsGoal>,
+    wealth: Wealth,
+    locale: Res<Locale>,
+    mut bar: Query<(&mut Visibility, &Children), With<GoalBar>>,
+    mut fill: Query<(&mut Node, &mut AccentTint), With<GoalFill>>,
+    mut texts: Query<&mut Text>,
+) {
+    let Ok((mut visibility, children)) = bar.single_mut() else {
+        return;
+    };
+    let Ok((mut node, mut tint)) = fill.single_mut() else {
+        return;
+    };
+    let Some(target) = goal.target else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+    let progress = goal.progress(wealth.net_worth().total());
+    node.width = Val::Percent(progress * 100.0);
+    tint.base = if goal.reached { YELLOW } else { BLUE };
+    if let Some(mut text) = children
+        .first()
+        .and_then(|label| texts.get_mut(*label).ok())
+    {
+        text.0 = format!(
+            "Goal {}: {:.0}%",
+            format::money(target, *locale),
+            progress * 100.0
+        );
+    }
+}
+
+fn open_savings_goal(
+    mut pressed: EventReader<ActionPressed>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        if *action == GameAction::SavingsGoal {
+            next_state.set(Overlay::SavingsGoal);
+        }
+    }
+}
+
+fn setup_savings_goal(
+    mut commands: Commands,
+    goal: Res<SavingsGoal>,
+    wealth: Wealth,
+    assets: Res<AssetServer>,
+) {
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: PANEL_FONT_SIZE,
+        ..default()
+    };
+    // A new goal starts a little above what the player has now.
+    let worth = wealth.net_worth().total();
+    let start = goal
+        .target
+        .unwrap_or((worth / GOAL_STEP + 10) * GOAL_STEP)
+        .min(MAX_GOAL);
+    let title = if goal.target.is_some() {
+        "Edit savings goal"
+    } else {
+        "Set savings goal"
+    };
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            GlobalZIndex(MODAL_Z_INDEX),
+            SavingsGoalOverlay,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(10.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    BackgroundColor(DARKER),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((Text::new(title), font.clone(), TextColor(YELLOW)));
+                    panel.spawn(amount_input(
+                        AmountInput::new(MAX_GOAL)
+                            .starting_at(start)
+                            .with_step(GOAL_STEP)
+                            .money()
+                            .with_confirm("Set goal"),
+                    ));
+                    if goal.target.is_some() {
+                        panel.spawn((
+                            Button,
+                            Node {
+                                height: Val::Px(40.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            BackgroundColor(DARK),
+                            ClearGoalButton,
+                            children![(Text::new("Clear goal"), font.clone(), TextColor(LIGHT))],
+                        ));
+                    }
+                    panel.spawn((
+                        Text::new("Counts your cash and savings. Esc to close"),
+                        font,
+                        TextColor(LIGHT.with_alpha(0.6)),
+                    ));
+                });
+        });
+}
+
+fn close_savings_goal(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(Overlay::None);
+    }
+}
+
+fn set_goal(
+    mut confirmed: EventReader<AmountConfirmed>,
+    mut goal: ResMut<SavingsGoal>,
+    mut toasts: EventWriter<Toast>,
+    mut next_state: ResMut<NextState<Overlay>>,
+    locale: Res<Locale>,
+) {
+    let Some(target) = confirmed.read().last().map(|confirmed| confirmed.value) else {
+        return;
+    };
+    if target > 0 {
+        goal.set(target);
+        toasts.write(Toast::new(format!(
+            "Saving up for {}",
+            format::money(target, *locale)
+        )));
+    }
+    next_state.set(Overlay::None);
+}
+
+fn press_clear_goal(
+    mut presses: ButtonPresses<(), With<ClearGoalButton>>,
+    mut cleared: EventWriter<GoalCleared>,
+) {
+    if presses.read().count() > 0 {
+        cleared.write(GoalCleared);
+    }
+}
+
+fn clear_goal(
+    mut cleared: EventReader<GoalCleared>,
+    mut goal: ResMut<SavingsGoal>,
+    mut toasts: EventWriter<Toast>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if cleared.read().count() > 0 {
+        goal.clear();
+        toasts.write(Toast::new("Savings goal cleared"));
+        next_state.set(Overlay::None);
+    }
+}
+
+fn clear_button_color(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ClearGoalButton>),
+    >,
+) {
+    for (interaction, mut background) in &mut buttons {
+        background.0 = match interaction {
+            Interaction::None => DARK,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
@@ -0,0 +1,152 @@
+//! The scenario picker "New Game" opens: the regular start or one of the presets in
+//! [`Scenarios`], each with what it starts the run with. Picking one starts the game.
+
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+
+use super::button_press::ButtonPresses;
+use super::menu::{MenuHistory, MenuState};
+use super::menu_slide::{slide_in, slide_out};
+use super::nav::{ScreenNav, TransitionCause};
+use crate::AppState;
+use crate::fonts::ui_font;
+use crate::palette::DARKER;
+use crate::settings::LayoutScale;
+use crate::sim::challenge::Challenge;
+use crate::sim::scenario::{ChosenScenario, Scenarios};
+use crate::track_screen;
+
+/// Keeps the screen above the main menu's title art, like the profiles screen.
+const SCENARIOS_Z_INDEX: i32 = 60;
+const STANDARD_DESCRIPTION: &str = "The usual start: a little cash, a cheap room and no job.";
+
+#[derive(Component)]
+struct OnScenariosScreen;
+
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+enum ScenarioButton {
+    /// Starts a new game from the preset with this id, or the regular way.
+    Start(Option<String>),
+    Back,
+}
+
+pub fn scenario_picker_plugin(app: &mut App) {
+    track_screen::<OnScenariosScreen>(app, MenuState::Scenarios);
+    app.add_systems(
+        OnEnter(MenuState::Scenarios),
+        (scenarios_setup, slide_in::<OnScenariosScreen>).chain(),
+    )
+    .add_systems(OnExit(MenuState::Scenarios), slide_out::<OnScenariosScreen>)
+    .add_systems(
+        Update,
+        scenario_action.run_if(in_state(MenuState::Scenarios)),
+    );
+}
+
+fn scenarios_setup(
+    mut commands: Commands,
+    scenarios: Res<Scenarios>,
+    scale: Res<LayoutScale>,
+    assets: Res<AssetServer>,
+) {
+    let font = |size: f32| TextFont {
+        font_size: scale.size(size),
+        font: ui_font(&assets),
+        ..default()
+    };
+    let button = |action: ScenarioButton, name: String, description: Option<String>| {
+        (
+            Button,
+            Node {
+                width: scale.px(560.0),
+                min_height: scale.px(50.0),
+                margin: UiRect::all(scale.px(6.0)),
+                padding: UiRect::axes(scale.px(12.0), scale.px(6.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            action,
+            Children::spawn(SpawnIter(
+                [Some((name, 28.0)), description.map(|line| (line, 18.0))]
+                    .into_iter()
+                    .flatten()
+                    .map(move |(line, size)| {
+                        (
+                            Text::new(line),
+                            font(size),
+                            TextColor(DARKER),
+                            TextLayout::new_with_justify(JustifyText::Center),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            )),
+        )
+    };
+    let presets: Vec<_> = [button(
+        ScenarioButton::Start(None),
+        scenarios.name(None).to_string(),
+        Some(STANDARD_DESCRIPTION.to_string()),
+    )]
+    .into_iter()
+    .chain(scenarios.presets.iter().map(|preset| {
+        button(
+            ScenarioButton::Start(Some(preset.id.clone())),
+            preset.name.clone(),
+            Some(preset.description.clone()),
+        )
+    }))
+    .collect();
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        OnScenariosScreen,
+        GlobalZIndex(SCENARIOS_Z_INDEX),
+        children![(
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            children![
+                (Text::new("Pick a start"), font(33.0), TextColor(DARKER)),
+                (
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    Children::spawn(SpawnIter(presets.into_iter())),
+                ),
+                button(ScenarioButton::Back, "Back".to_string(), None),
+            ]
+        )],
+    ));
+}
+
+fn scenario_action(
+    mut presses: ButtonPresses<&ScenarioButton>,
+    mut chosen: ResMut<ChosenScenario>,
+    mut challenge: ResMut<Challenge>,
+    mut history: ResMut<MenuHistory>,
+    mut nav: ScreenNav,
+) {
+    for action in presses.read() {
+        match action {
+            ScenarioButton::Start(id) => {
+                info!("Starting a new game from scenario {id:?}");
+                chosen.0.clone_from(id);
+                challenge.0 = None;
+                nav.set_app(AppState::Game, TransitionCause::Button);
+                history.open(&mut nav, MenuState::Disabled);
+            }
+            ScenarioButton::Back => history.back(&mut nav),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Scrolling for long UI lists. Spawn a node with [`scrollable`] and the mouse wheel scrolls
+//! it while the cursor is over it.
+
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    ui::RelativeCursorPosition,
+};
+
+/// Pixels scrolled per line of mouse wheel movement.
+const LINE_HEIGHT: f32 = 24.0;
+
+#[derive(Component)]
+pub struct Scrollable;
+
+pub fn scroll_plugin(app: &mut App) {
+    app.add_systems(Update, scroll_on_wheel);
+}
+
+/// `node`, scrolling vertically once its content is taller than its `max_height`.
+pub fn scrollable(node: Node) -> impl Bundle {
+    (
+        Node {
+            overflow: Overflow::scroll_y(),
+            ..node
+        },
+        ScrollPosition::default(),
+        RelativeCursorPosition::default(),
+        Scrollable,
+    )
+}
+
+fn scroll_on_wheel(
+    mut wheel: EventReader<MouseWheel>,
+    mut lists: Query<(&mut ScrollPosition, &RelativeCursorPosition), With<Scrollable>>,
+) {
+    for event in wheel.read() {
+        let dy = match event.unit {
+            MouseScrollUnit::Line => event.y * LINE_HEIGHT,
+            MouseScrollUnit::Pixel => event.y,
+        };
+        for (mut position, cursor) in &mut lists {
+            if cursor.mouse_over() {
+                // Layout clamps the offset to the content, so it can't scroll past the end.
+                position.offset_y = (position.offset_y - dy).max(0.0);
+            }
+        }
+    }
+}
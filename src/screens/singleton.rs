@@ -0,0 +1,158 @@
+//! The screens' one-of-a-kind entities, like the money in the HUD, which systems look up
+//! on their own rather than through a query over many.
+//!
+//! A `Single` quietly stops its system when a refactor spawns such an entity twice.
+//! [`Unique`] goes on with the first instead, and reports the others as an error. Debug
+//! builds also check every marker recorded with [`track_singleton`] after each state
+//! transition, whether anything looks it up that frame or not, and `--self-test` on every
+//! screen it comes to.
+
+use bevy::ecs::component::ComponentId;
+use bevy::ecs::query::{QueryData, QueryFilter, ROQueryItem};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// The first entity matching a query that should only ever match one. Any others are
+/// reported the first time they're seen, and otherwise ignored.
+#[derive(SystemParam)]
+pub struct Unique<'w, 's, D: QueryData + 'static, F: QueryFilter + 'static = ()> {
+    query: Query<'w, 's, (Entity, D), F>,
+    reported: Local<'s, bool>,
+}
+
+impl<D: QueryData, F: QueryFilter> Unique<'_, '_, D, F> {
+    /// The first match, or `None` if nothing matches.
+    pub fn get(&mut self) -> Option<ROQueryItem<'_, D>> {
+        let mut matches = self.query.iter();
+        let (first, item) = matches.next()?;
+        report::<F>(&mut self.reported, first, matches.map(|(entity, _)| entity));
+        Some(item)
+    }
+}
+
+fn report<F>(reported: &mut bool, first: Entity, others: impl Iterator<Item = Entity>) {
+    if *reported {
+        return;
+    }
+    let others: Vec<_> = others.map(|entity| entity.to_string()).collect();
+    if others.is_empty() {
+        return;
+    }
+    *reported = true;
+    error!(
+        "{} entities match {}, which should be one: using {first}, ignoring {}",
+        others.len() + 1,
+        short_name(std::any::type_name::<F>()),
+        others.join(", ")
+    );
+}
+
+/// `name` without its module paths, like `With<MoneyUi>`.
+fn short_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut path = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+        } else {
+            short += path.rsplit("::").next().unwrap_or_default();
+            path.clear();
+            short.push(c);
+        }
+    }
+    short + path.rsplit("::").next().unwrap_or_default()
+}
+
+/// The combinations of components recorded with [`track_singleton`].
+#[derive(Resource, Default)]
+pub struct Singletons(pub Vec<Singleton>);
+
+pub struct Singleton {
+    pub name: String,
+    components: Vec<ComponentId>,
+}
+
+impl Singleton {
+    /// The entities that have all of the components.
+    pub fn entities(&self, world: &World) -> Vec<Entity> {
+        world
+            .archetypes()
+            .iter()
+            .filter(|archetype| {
+                self.components
+                    .iter()
+                    .all(|component| archetype.contains(*component))
+            })
+            .flat_map(|archetype| archetype.entities().iter().map(|entity| entity.id()))
+            .collect()
+    }
+}
+
+/// Records that only one entity at a time should have all of `B`'s components: a marker,
+/// or a marker together with what it marks, like the selected button of one setting.
+pub fn track_singleton<B: Bundle>(app: &mut App) {
+    let components = app
+        .world_mut()
+        .register_bundle::<B>()
+        .explicit_components()
+        .to_vec();
+    app.world_mut()
+        .get_resource_or_init::<Singletons>()
+        .0
+        .push(Singleton {
+            name: short_name(std::any::type_name::<B>()),
+            components,
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[derive(Component)]
+    struct Marker(u32);
+
+    fn first_marker(mut marker: Unique<&Marker>) -> Option<u32> {
+        marker.get().map(|marker| marker.0)
+    }
+
+    #[test]
+    fn duplicates_fall_back_to_the_first_instead_of_panicking() {
+        let mut world = World::new();
+        assert_eq!(world.run_system_once(first_marker).unwrap(), None);
+        world.spawn(Marker(1));
+        world.spawn(Marker(2));
+        assert_eq!(world.run_system_once(first_marker).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn only_duplicates_are_reported() {
+        let mut reported = false;
+        let first = Entity::from_raw(1);
+        report::<With<Marker>>(&mut reported, first, std::iter::empty());
+        assert!(!reported);
+        report::<With<Marker>>(&mut reported, first, [Entity::from_raw(2)].into_iter());
+        assert!(reported);
+    }
+
+    #[test]
+    fn tracked_singletons_find_every_entity_with_them() {
+        let mut app = App::new();
+        track_singleton::<Marker>(&mut app);
+        let world = app.world_mut();
+        let entities = [world.spawn(Marker(1)).id(), world.spawn(Marker(2)).id()];
+        let singletons = world.resource::<Singletons>();
+        assert_eq!(singletons.0[0].name, "Marker");
+        assert_eq!(singletons.0[0].entities(world), entities);
+    }
+
+    #[test]
+    fn short_names_drop_module_paths() {
+        assert_eq!(
+            short_name("bevy_ecs::query::filter::With<breakout::screens::game::MoneyUi>"),
+            "With<MoneyUi>"
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Keeps HUD text readable over bright location art. Text opts in with [`TextBacking`],
+//! and the [`TextReadability`] setting picks what it gets: nothing, its shadow, a stronger
+//! one, or a dark panel behind it. Changing the setting restyles the text already on
+//! screen.
+//!
+//! The panel is the text node's own background, so it follows the glyphs as the text
+//! grows or shrinks.
+
+use bevy::prelude::*;
+
+use crate::palette::DARKER;
+use crate::settings::TextReadability;
+
+const PANEL_ALPHA: f32 = 0.7;
+const PANEL_RADIUS: f32 = 6.0;
+const PANEL_PADDING: f32 = 6.0;
+/// How much further [`TextReadability::StrongShadow`] puts the shadow.
+const STRONG_SHADOW_SCALE: f32 = 1.5;
+
+/// Text that stands out from the art behind it. The shadow is the one it gets with
+/// [`TextReadability::Shadow`].
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[require(Node)]
+pub struct TextBacking(pub TextShadow);
+
+pub fn text_backing_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        restyle_text
+            .run_if(resource_changed::<TextReadability>.or(any_match_filter::<Added<TextBacking>>)),
+    );
+}
+
+fn restyle_text(
+    mut commands: Commands,
+    readability: Res<TextReadability>,
+    mut texts: Query<(Entity, &TextBacking, &mut Node)>,
+) {
+    for (entity, backing, mut node) in &mut texts {
+        let mut entity = commands.entity(entity);
+        let shadow = backing.0;
+        match *readability {
+            TextReadability::Off => {
+                entity.remove::<TextShadow>();
+            }
+            TextReadability::Shadow => {
+                entity.insert(shadow);
+            }
+            TextReadability::StrongShadow => {
+                entity.insert(TextShadow {
+                    offset: shadow.offset * STRONG_SHADOW_SCALE,
+                    color: shadow.color.with_alpha(1.0),
+                });
+            }
+            TextReadability::Panel => {
+                entity.remove::<TextShadow>().insert((
+                    BackgroundColor(DARKER.with_alpha(PANEL_ALPHA)),
+                    BorderRadius::all(Val::Px(PANEL_RADIUS)),
+                ));
+                node.padding = UiRect::axes(Val::Px(PANEL_PADDING), Val::Px(PANEL_PADDING / 2.0));
+                continue;
+            }
+        }
+        entity.remove::<(BackgroundColor, BorderRadius)>();
+        node.padding = UiRect::ZERO;
+    }
+}
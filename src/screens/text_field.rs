@@ -0,0 +1,112 @@
+//! A one-line field the player types into, like a profile name. Typing goes to every
+//! field on screen, so screens show one at a time, and game hotkeys that are letters hold
+//! off while one is up, see [`typing`]. With a gamepad the on-screen keyboard types into
+//! it, see [`super::virtual_keyboard`].
+
+use bevy::{
+    input::{ButtonState, keyboard::KeyboardInput},
+    prelude::*,
+};
+
+use crate::palette::{DARKER, LIGHT};
+
+const CARET: char = '_';
+
+#[derive(Component, Debug)]
+#[require(Text)]
+pub struct TextField {
+    pub value: String,
+    /// The most characters the field takes.
+    pub max_len: usize,
+    /// Which characters the field takes.
+    pub accepts: fn(char) -> bool,
+}
+
+impl TextField {
+    /// An empty field taking up to `max_len` of any printable character.
+    pub fn new(max_len: usize) -> Self {
+        TextField {
+            value: String::new(),
+            max_len,
+            accepts: |c| !c.is_control(),
+        }
+    }
+
+    pub fn accepting(self, accepts: fn(char) -> bool) -> Self {
+        TextField { accepts, ..self }
+    }
+
+    /// Adds `c` if the field takes it and has room.
+    pub fn push(&mut self, c: char) {
+        if (self.accepts)(c) && self.value.chars().count() < self.max_len {
+            self.value.push(c);
+        }
+    }
+
+    /// The value with the caret after it, as shown.
+    pub fn shown(&self) -> String {
+        format!("{}{CARET}", self.value)
+    }
+}
+
+/// Sent when the player is done typing on the on-screen keyboard, for screens that act on
+/// Enter.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct FieldSubmitted;
+
+/// `field`, empty, shown in `font`.
+pub fn text_field(field: TextField, font: TextFont) -> impl Bundle {
+    (
+        field,
+        Text::new(CARET.to_string()),
+        font,
+        TextColor(LIGHT),
+        Node {
+            min_width: Val::Px(240.0),
+            padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+            border: UiRect::all(Val::Px(2.0)),
+            ..default()
+        },
+        BorderColor(LIGHT),
+        BackgroundColor(DARKER),
+    )
+}
+
+/// Run condition for keyboard shortcuts that would eat typed letters.
+pub fn typing(fields: Query<(), With<TextField>>) -> bool {
+    !fields.is_empty()
+}
+
+pub fn text_field_plugin(app: &mut App) {
+    app.add_event::<FieldSubmitted>().add_systems(
+        Update,
+        (type_into_fields, show_values).chain().run_if(typing),
+    );
+}
+
+fn type_into_fields(mut keys: EventReader<KeyboardInput>, mut fields: Query<&mut TextField>) {
+    let keys: Vec<_> = keys
+        .read()
+        .filter(|key| key.state == ButtonState::Pressed)
+        .collect();
+    if keys.is_empty() {
+        return;
+    }
+    for mut field in &mut fields {
+        for key in &keys {
+            if key.key_code == KeyCode::Backspace {
+                field.value.pop();
+            } else if let Some(typed) = &key.text {
+                for c in typed.chars().filter(|c| !c.is_control()) {
+                    field.push(c);
+                }
+            }
+        }
+    }
+}
+
+fn show_values(mut fields: Query<(&TextField, &mut Text), Changed<TextField>>) {
+    for (field, mut text) in &mut fields {
+        text.0 = field.shown();
+    }
+}
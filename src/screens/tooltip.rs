@@ -0,0 +1,361 @@
+//! Tooltips. Give any UI node a [`Tooltip`] and resting the mouse on it for a moment shows
+//! the text in a small panel by the cursor.
+//!
+//! Hovering goes by what the pointer is over rather than [`Interaction`], so tooltips work
+//! on blocked buttons and plain text too, and nothing shows through a modal covering them.
+
+use std::time::Duration;
+
+use bevy::{
+    picking::{hover::HoverMap, pointer::PointerId},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::fonts::ui_font;
+use crate::palette::{DARKER, LIGHT, YELLOW};
+use crate::timers::{RealUiTime, UiTimer};
+
+const TOOLTIP_DELAY: Duration = Duration::from_millis(500);
+const TOOLTIP_FONT_SIZE: f32 = 14.0;
+const TOOLTIP_MAX_WIDTH: f32 = 320.0;
+/// Between the cursor and the panel's top left corner.
+const CURSOR_OFFSET: Vec2 = Vec2::new(14.0, 18.0);
+/// Above modals and the settings screens, below toasts.
+const TOOLTIP_Z_INDEX: i32 = 90;
+
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub enum Tooltip {
+    Text(String),
+    /// A heading over the text, e.g. an item's name over its description.
+    Titled {
+        title: String,
+        body: String,
+    },
+}
+
+impl Tooltip {
+    pub fn new(text: impl Into<String>) -> Self {
+        Tooltip::Text(text.into())
+    }
+
+    pub fn titled(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Tooltip::Titled {
+            title: title.into(),
+            body: body.into(),
+        }
+    }
+}
+
+/// What the mouse is resting on.
+#[derive(Resource, Debug)]
+struct TooltipHover {
+    target: Option<Entity>,
+    timer: UiTimer,
+    /// A click hides the tooltip until the mouse moves on to something else.
+    dismissed: bool,
+}
+
+impl Default for TooltipHover {
+    fn default() -> Self {
+        TooltipHover {
+            target: None,
+            timer: UiTimer::new(TOOLTIP_DELAY, TimerMode::Once),
+            dismissed: false,
+        }
+    }
+}
+
+#[derive(Component)]
+struct TooltipPanel;
+
+pub fn tooltip_plugin(app: &mut App) {
+    app.init_resource::<TooltipHover>().add_systems(
+        Update,
+        (track_tooltip_hover, show_tooltip, follow_cursor).chain(),
+    );
+}
+
+/// The nearest entity with a [`Tooltip`] at or above `entity`.
+fn tooltip_owner(
+    entity: Entity,
+    parents: &Query<&ChildOf>,
+    tooltips: &Query<(), With<Tooltip>>,
+) -> Option<Entity> {
+    std::iter::once(entity)
+        .chain(parents.iter_ancestors(entity))
+        .find(|entity| tooltips.contains(*entity))
+}
+
+fn track_tooltip_hover(
+    mut commands: Commands,
+    hover_map: Res<HoverMap>,
+    parents: Query<&ChildOf>,
+    tooltips: Query<(), With<Tooltip>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    time: RealUiTime,
+    mut hover: ResMut<TooltipHover>,
+    panels: Query<Entity, With<TooltipPanel>>,
+) {
+    let target = hover_map.get(&PointerId::Mouse).and_then(|hovered| {
+        hovered
+            .keys()
+            .find_map(|entity| tooltip_owner(*entity, &parents, &tooltips))
+    });
+    let clicked = mouse.get_just_pressed().next().is_some();
+    if target != hover.target || clicked {
+        for panel in &panels {
+            commands.entity(panel).despawn();
+        }
+        if target != hover.target {
+            hover.target = target;
+            hover.dismissed = false;
+        }
+        hover.dismissed |= clicked;
+        hover.timer.reset();
+    }
+    hover.timer.tick(&time);
+}
+
+fn show_tooltip(
+    mut commands: Commands,
+    hover: Res<TooltipHover>,
+    tooltips: Query<&Tooltip>,
+    panels: Query<(), With<TooltipPanel>>,
+    assets: Res<AssetServer>,
+) {
+    if hover.dismissed || !hover.timer.finished() || !panels.is_empty() {
+        return;
+    }
+    let Some(tooltip) = hover.target.and_then(|target| tooltips.get(target).ok()) else {
+        return;
+    };
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: TOOLTIP_FONT_SIZE,
+        ..default()
+    };
+    let mut panel = commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            max_width: Val::Px(TOOLTIP_MAX_WIDTH),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        },
+        BackgroundColor(DARKER),
+        GlobalZIndex(TOOLTIP_Z_INDEX),
+        // Placed by `follow_cursor` once it has a size.
+        Visibility::Hidden,
+        Pickable::IGNORE,
+        TooltipPanel,
+    ));
+    match tooltip {
+        Tooltip::Text(text) => {
+            panel.with_child((
+                Text::new(text.clone()),
+                font,
+                TextColor(LIGHT),
+                Pickable::IGNORE,
+            ));
+        }
+        Tooltip::Titled { title, body } => {
+            panel.with_children(|parent| {
+                parent.spawn((
+                    Text::new(title.clone()),
+                    font.clone(),
+                    TextColor(YELLOW),
+                    Pickable::IGNORE,
+                ));
+                parent.spawn((
+                    Text::new(body.clone()),
+                    font,
+                    TextColor(LIGHT),
+                    Pickable::IGNORE,
+                ));
+            });
+        }
+    }
+}
+
+/// Where a panel of `size` goes for the cursor at `cursor`: just below and right of it, but
+/// pushed back inside the window.
+fn panel_position(cursor: Vec2, size: Vec2, window: Vec2) -> Vec2 {
+    let max = (window - size).max(Vec2::ZERO);
+    (cursor + CURSOR_OFFSET).min(max)
+}
+
+// Keeps the panel by the cursor, and inside the window
+fn follow_cursor(
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut panels: Query<(&mut Node, &mut Visibility, &ComputedNode), With<TooltipPanel>>,
+) {
+    let Ok(window) = window.single() else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    for (mut node, mut visibility, computed) in &mut panels {
+        let size = computed.size() * computed.inverse_scale_factor();
+        if size == Vec2::ZERO {
+            continue;
+        }
+        let position = panel_position(cursor, size, window.size());
+        node.left = Val::Px(position.x);
+        node.top = Val::Px(position.y);
+        *visibility = Visibility::Inherited;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        picking::backend::HitData, platform::collections::HashMap, time::TimeUpdateStrategy,
+    };
+
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(100);
+
+    fn tooltip_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Font>()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+            .init_resource::<HoverMap>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .add_plugins(tooltip_plugin);
+        // The first frame only starts the clock.
+        app.update();
+        app
+    }
+
+    /// Puts the mouse over `entity`, or over nothing.
+    fn hover(app: &mut App, entity: Option<Entity>) {
+        let hovered = entity
+            .map(|entity| {
+                let hit = HitData::new(Entity::PLACEHOLDER, 0.0, None, None);
+                HashMap::from_iter([(entity, hit)])
+            })
+            .unwrap_or_default();
+        let mut hover_map = app.world_mut().resource_mut::<HoverMap>();
+        hover_map.insert(PointerId::Mouse, hovered);
+    }
+
+    fn click(app: &mut App) {
+        let mut mouse = app.world_mut().resource_mut::<ButtonInput<MouseButton>>();
+        mouse.press(MouseButton::Left);
+        app.update();
+        let mut mouse = app.world_mut().resource_mut::<ButtonInput<MouseButton>>();
+        mouse.release(MouseButton::Left);
+        mouse.clear();
+    }
+
+    fn wait(app: &mut App, frames: u32) {
+        for _ in 0..frames {
+            app.update();
+        }
+    }
+
+    /// The text of every tooltip panel shown.
+    fn shown(app: &mut App) -> Vec<String> {
+        let world = app.world_mut();
+        let panels: Vec<Entity> = world
+            .query_filtered::<Entity, With<TooltipPanel>>()
+            .iter(world)
+            .collect();
+        let mut texts = world.query::<(&Text, &ChildOf)>();
+        texts
+            .iter(world)
+            .filter(|(_, parent)| panels.contains(&parent.parent()))
+            .map(|(text, _)| text.0.clone())
+            .collect()
+    }
+
+    #[test]
+    fn a_tooltip_shows_once_the_mouse_rests_for_the_delay() {
+        let mut app = tooltip_app();
+        let button = app
+            .world_mut()
+            .spawn(Tooltip::new("Closed on Sundays"))
+            .id();
+        // Hovering the button's label counts as hovering the button.
+        let label = app.world_mut().spawn(ChildOf(button)).id();
+        hover(&mut app, Some(label));
+
+        wait(&mut app, 4);
+        assert!(shown(&mut app).is_empty());
+        wait(&mut app, 2);
+        assert_eq!(shown(&mut app), ["Closed on Sundays"]);
+        wait(&mut app, 10);
+        assert_eq!(shown(&mut app).len(), 1);
+    }
+
+    #[test]
+    fn moving_away_hides_the_tooltip_and_starts_the_delay_over() {
+        let mut app = tooltip_app();
+        let first = app.world_mut().spawn(Tooltip::new("First")).id();
+        let second = app
+            .world_mut()
+            .spawn(Tooltip::titled("Coffee", "A little energy"))
+            .id();
+        hover(&mut app, Some(first));
+        wait(&mut app, 6);
+        assert_eq!(shown(&mut app), ["First"]);
+
+        hover(&mut app, None);
+        wait(&mut app, 1);
+        assert!(shown(&mut app).is_empty());
+
+        hover(&mut app, Some(second));
+        wait(&mut app, 4);
+        assert!(shown(&mut app).is_empty());
+        wait(&mut app, 2);
+        assert_eq!(shown(&mut app), ["Coffee", "A little energy"]);
+    }
+
+    #[test]
+    fn a_click_hides_the_tooltip_until_the_mouse_moves_on() {
+        let mut app = tooltip_app();
+        let button = app.world_mut().spawn(Tooltip::new("Buy")).id();
+        let other = app.world_mut().spawn(Tooltip::new("Sell")).id();
+        hover(&mut app, Some(button));
+        wait(&mut app, 6);
+        assert_eq!(shown(&mut app), ["Buy"]);
+
+        click(&mut app);
+        wait(&mut app, 10);
+        assert!(shown(&mut app).is_empty());
+
+        hover(&mut app, Some(other));
+        wait(&mut app, 1);
+        hover(&mut app, Some(button));
+        wait(&mut app, 6);
+        assert_eq!(shown(&mut app), ["Buy"]);
+    }
+
+    #[test]
+    fn the_panel_stays_inside_the_window() {
+        let window = Vec2::new(800.0, 600.0);
+        let size = Vec2::new(200.0, 50.0);
+        assert_eq!(
+            panel_position(Vec2::new(100.0, 100.0), size, window),
+            Vec2::new(100.0, 100.0) + CURSOR_OFFSET
+        );
+        assert_eq!(
+            panel_position(Vec2::new(790.0, 590.0), size, window),
+            Vec2::new(600.0, 550.0)
+        );
+        assert_eq!(
+            panel_position(Vec2::new(100.0, 590.0), size, window),
+            Vec2::new(100.0 + CURSOR_OFFSET.x, 550.0)
+        );
+        // A panel wider than the window starts at its left edge.
+        assert_eq!(
+            panel_position(Vec2::new(300.0, 0.0), Vec2::new(1000.0, 50.0), window),
+            Vec2::new(0.0, CURSOR_OFFSET.y)
+        );
+    }
+}
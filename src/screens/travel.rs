@@ -0,0 +1,598 @@
+//! The travel map: the whole town, with a hotspot over each area where the area data puts
+//! it. Hovering a hotspot highlights it and tells its rent, the best wage there and how safe
+//! it is, and locked areas show a lock with what it takes to open them. Clicking asks the
+//! simulation to go there, which turns down locked areas on its own. Markers show where
+//! the player is and where they live.
+//!
+//! With the keyboard or a gamepad, left and right (or tab) move focus from one hotspot to
+//! the next across the map from left to right, and the focused area's details show under
+//! the map.
+//!
+//! Each area that can be rented also shows how the run would do paying its rent, with the
+//! same [`Outlook`] the solvency warning goes by. The map is built again when the area
+//! data is reloaded, so the rents shown are the new ones.
+
+use bevy::prelude::*;
+
+use super::actions::{ActionPressed, GameAction};
+use super::button_press::ButtonPresses;
+use super::overlay::{MODAL_Z_INDEX, Overlay};
+use super::tooltip::Tooltip;
+use crate::fonts::ui_font;
+use crate::format;
+use crate::palette::{BLUE, DARK, DARKER, LIGHT, RED, YELLOW};
+use crate::settings::Locale;
+use crate::sim::{
+    catalog::{AreaCatalog, AreaCatalogReloaded},
+    clock::Clock,
+    economy::{Area, CurrentArea, HomeArea, rent_due},
+    jobs::JobMarket,
+    landlord::{RentIncreases, RentTerms},
+    ledger::Ledger,
+    new_game_plus::PriceLevel,
+    roommate::Roommate,
+    solvency::{Affordability, Outlook, Projection},
+    travel::{AreaUnlocks, TravelRequested, unlock_text},
+};
+use crate::{despawn_screen, track_screen};
+
+const PANEL_FONT_SIZE: f32 = 16.0;
+const DETAIL_FONT_SIZE: f32 = 12.0;
+/// Across a hotspot, which is centered on the area's spot on the map.
+const HOTSPOT_SIZE: f32 = 72.0;
+/// How far down the map areas without a spot of their own line up.
+const UNPLACED_ROW: f32 = 92.0;
+
+#[derive(Component)]
+struct TravelOverlay;
+
+/// A hotspot going to this area. Locked areas keep theirs, so picking one explains why
+/// it's closed.
+#[derive(Component)]
+struct TravelHotspot {
+    area: Area,
+    open: bool,
+}
+
+/// Shows the focused hotspot's details under the map.
+#[derive(Component)]
+struct MapCaption;
+
+/// The hotspot keyboard and gamepad focus is on, as an index into [`focus_order`]. Nothing
+/// is focused until the player moves focus, so the mouse has the map to itself.
+#[derive(Resource, Debug, Default)]
+struct MapFocus(Option<usize>);
+
+pub fn travel_plugin(app: &mut App) {
+    track_screen::<TravelOverlay>(app, Overlay::Travel);
+    app.init_resource::<MapFocus>()
+        .add_systems(Update, open_travel.run_if(in_state(Overlay::None)))
+        .add_systems(OnEnter(Overlay::Travel), setup_travel)
+        .add_systems(OnExit(Overlay::Travel), despawn_screen::<TravelOverlay>)
+        .add_systems(
+            Update,
+            (
+                rebuild_travel.run_if(on_event::<AreaCatalogReloaded>),
+                close_travel,
+                pick_area,
+                focus_input,
+                (hotspot_colors, show_focus).chain(),
+            )
+                .chain()
+                .run_if(in_state(Overlay::Travel)),
+        );
+}
+
+fn open_travel(
+    mut pressed: EventReader<ActionPressed>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        if *action == GameAction::Travel {
+            next_state.set(Overlay::Travel);
+        }
+    }
+}
+
+fn close_travel(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(GamepadButton::East))
+    {
+        next_state.set(Overlay::None);
+    }
+}
+
+fn affordability_color(affordability: Affordability) -> Color {
+    match affordability {
+        Affordability::Comfortable => BLUE,
+        Affordability::Tight => YELLOW,
+        Affordability::Unaffordable => RED,
+    }
+}
+
+// Like "Rent $600: +$12 a day, tight", and how long the money lasts when it runs out.
+fn affordability_text(rent: u32, outlook: &Outlook, locale: Locale) -> String {
+    let surplus = outlook.daily_surplus();
+    let sign = if surplus < 0 { "-" } else { "+" };
+    let mut text = format!(
+        "Rent {}: {sign}{} a day, {}",
+        format::money(rent, locale),
+        format::format_money(surplus.abs() * 100, locale),
+        outlook.affordability().label().to_lowercase()
+    );
+    match outlook.days_to_bankruptcy() {
+        Some(0) => text.push_str(", money runs out today"),
+        Some(days) => text.push_str(&format!(
+            ", money lasts {days} {}",
+            if days == 1 { "day" } else { "days" }
+        )),
+        None => {}
+    }
+    text
+}
+
+/// Where `area`'s hotspot goes, in percent of the map. Areas the data doesn't place line up
+/// along the bottom, so they can still be reached.
+fn hotspot_position(catalog: &AreaCatalog, area: Area) -> Vec2 {
+    catalog.map_position(area).unwrap_or_else(|| {
+        let unplaced: Vec<Area> = catalog
+            .areas()
+            .into_iter()
+            .filter(|area| catalog.map_position(*area).is_none())
+            .collect();
+        let index = unplaced
+            .iter()
+            .position(|other| *other == area)
+            .unwrap_or(0);
+        let x = (index + 1) as f32 * 100.0 / (unplaced.len() + 1) as f32;
+        Vec2::new(x, UNPLACED_ROW)
+    })
+}
+
+/// The order focus moves through the hotspots: across the map from left to right, top to
+/// bottom where two line up.
+fn focus_order(catalog: &AreaCatalog) -> Vec<Area> {
+    let mut areas = catalog.areas();
+    areas.sort_by(|a, b| {
+        let (a, b) = (hotspot_position(catalog, *a), hotspot_position(catalog, *b));
+        a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y))
+    });
+    areas
+}
+
+fn setup_travel(
+    mut commands: Commands,
+    catalog: Res<AreaCatalog>,
+    unlocks: Res<AreaUnlocks>,
+    current: Res<CurrentArea>,
+    prices: Res<PriceLevel>,
+    projection: Projection,
+    (home, increases, terms, roommate): (
+        Res<HomeArea>,
+        Res<RentIncreases>,
+        Res<RentTerms>,
+        Res<Roommate>,
+    ),
+    (clock, ledger, jobs): (Res<Clock>, Res<Ledger>, Option<Res<JobMarket>>),
+    locale: Res<Locale>,
+    assets: Res<AssetServer>,
+    mut focus: ResMut<MapFocus>,
+) {
+    *focus = MapFocus::default();
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: PANEL_FONT_SIZE,
+        ..default()
+    };
+    let detail_font = TextFont {
+        font_size: DETAIL_FONT_SIZE,
+        ..font.clone()
+    };
+    let mut map = commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        BackgroundColor(DARKER),
+        GlobalZIndex(MODAL_Z_INDEX),
+        TravelOverlay,
+    ));
+    if let Some(path) = catalog.map_image() {
+        map.insert(ImageNode::new(assets.load(path)));
+    }
+    map.with_children(|map| {
+        for area in catalog.areas() {
+            let open = unlocks.is_open(area, &catalog);
+            let title = catalog.travel_label(area, open).to_string();
+            let mut details = Vec::new();
+            if area == current.0 {
+                details.push("You are here".to_string());
+            }
+            if area == home.location {
+                details.push("Your home".to_string());
+            }
+            match catalog.unlock(area) {
+                Some(unlock) if !open => {
+                    details.push(unlock_text(unlock, &prices, *locale));
+                }
+                _ => {}
+            }
+            // Home keeps its deals and splits, anywhere else is the listed rent.
+            let rent = if area == home.location {
+                Some(
+                    rent_due(
+                        &home,
+                        &increases,
+                        &terms,
+                        &roommate,
+                        &clock,
+                        ledger.current.month,
+                    )
+                    .net,
+                )
+            } else {
+                catalog.rent(area).map(|rent| prices.scale(rent))
+            };
+            // The name takes the color of how affordable the rent is.
+            let mut label_color = LIGHT;
+            if let Some(rent) = rent {
+                let outlook = projection.outlook(rent);
+                label_color = affordability_color(outlook.affordability());
+                details.push(affordability_text(rent, &outlook, *locale));
+            }
+            if let Some(wage) = jobs.as_ref().and_then(|jobs| jobs.best_wage(area)) {
+                details.push(format!(
+                    "Jobs pay up to {} an hour",
+                    format::money(wage, *locale)
+                ));
+            }
+            details.push(format!("Safety {}/100", catalog.safety(area)));
+
+            let position = hotspot_position(&catalog, area);
+            map.spawn((
+                Button,
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(position.x),
+                    top: Val::Percent(position.y),
+                    width: Val::Px(HOTSPOT_SIZE),
+                    height: Val::Px(HOTSPOT_SIZE),
+                    margin: UiRect::new(
+                        Val::Px(-HOTSPOT_SIZE / 2.0),
+                        Val::ZERO,
+                        Val::Px(-HOTSPOT_SIZE / 2.0),
+                        Val::ZERO,
+                    ),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                BorderRadius::MAX,
+                BackgroundColor(Color::NONE),
+                TravelHotspot { area, open },
+                Tooltip::titled(title.clone(), details.join("\n")),
+            ))
+            .with_children(|hotspot| {
+                if !open {
+                    hotspot.spawn((
+                        Node {
+                            position_type: PositionType::Absolute,
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            align_items: AlignItems::Center,
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        BorderRadius::MAX,
+                        BackgroundColor(DARK.with_alpha(0.7)),
+                        children![(Text::new("[locked]"), detail_font.clone(), TextColor(LIGHT),)],
+                    ));
+                }
+                let markers = [
+                    (area == current.0, "HERE", YELLOW),
+                    (area == home.location, "HOME", BLUE),
+                ];
+                for (shown, marker, color) in markers {
+                    if !shown {
+                        continue;
+                    }
+                    hotspot.spawn((
+                        Node {
+                            padding: UiRect::axes(Val::Px(4.0), Val::Px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(DARKER),
+                        children![(Text::new(marker), detail_font.clone(), TextColor(color))],
+                    ));
+                }
+                hotspot.spawn((
+                    Text::new(title),
+                    detail_font.clone(),
+                    TextColor(label_color),
+                    TextShadow::default(),
+                ));
+            });
+        }
+
+        map.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(20.0),
+                top: Val::Px(20.0),
+                ..default()
+            },
+            children![(Text::new("Travel"), font.clone(), TextColor(YELLOW))],
+        ));
+        map.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(20.0),
+                right: Val::Px(20.0),
+                bottom: Val::Px(20.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            children![
+                (
+                    Text::default(),
+                    detail_font.clone(),
+                    TextColor(LIGHT),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                    MapCaption,
+                ),
+                (
+                    Text::new("Esc to close"),
+                    font,
+                    TextColor(LIGHT.with_alpha(0.6)),
+                ),
+            ],
+        ));
+    });
+}
+
+// Keeps the hotspot focus was on.
+fn rebuild_travel(world: &mut World) {
+    let focus = world.resource::<MapFocus>().0;
+    world
+        .run_system_cached(despawn_screen::<TravelOverlay>)
+        .and_then(|()| world.run_system_cached(setup_travel))
+        .unwrap_or_else(|error| warn!("Couldn't rebuild the travel map: {error}"));
+    world.resource_mut::<MapFocus>().0 = focus;
+}
+
+fn go_to(
+    hotspot: &TravelHotspot,
+    requests: &mut EventWriter<TravelRequested>,
+    next_state: &mut NextState<Overlay>,
+) {
+    requests.write(TravelRequested(hotspot.area));
+    if hotspot.open {
+        next_state.set(Overlay::None);
+    }
+}
+
+fn pick_area(
+    mut presses: ButtonPresses<&TravelHotspot>,
+    mut requests: EventWriter<TravelRequested>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    for hotspot in presses.read() {
+        go_to(hotspot, &mut requests, &mut next_state);
+    }
+}
+
+fn focus_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    catalog: Res<AreaCatalog>,
+    locale: Res<Locale>,
+    hotspots: Query<&TravelHotspot>,
+    mut focus: ResMut<MapFocus>,
+    mut requests: EventWriter<TravelRequested>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    let just_pressed = |key, button| {
+        keyboard_input.just_pressed(key) || gamepads.iter().any(|pad| pad.just_pressed(button))
+    };
+    let mut left = just_pressed(KeyCode::ArrowLeft, GamepadButton::DPadLeft);
+    let mut right = just_pressed(KeyCode::ArrowRight, GamepadButton::DPadRight)
+        || keyboard_input.just_pressed(KeyCode::Tab);
+    let confirm = just_pressed(KeyCode::Enter, GamepadButton::South);
+    if locale.direction().is_rtl() {
+        std::mem::swap(&mut left, &mut right);
+    }
+
+    let order = focus_order(&catalog);
+    if order.is_empty() {
+        return;
+    }
+    if left || right {
+        focus.0 = Some(match focus.0 {
+            None => 0,
+            Some(index) if right => (index + 1) % order.len(),
+            Some(index) => (index + order.len() - 1) % order.len(),
+        });
+    }
+    if confirm
+        && let Some(area) = focus.0.and_then(|index| order.get(index))
+        && let Some(hotspot) = hotspots.iter().find(|hotspot| hotspot.area == *area)
+    {
+        go_to(hotspot, &mut requests, &mut next_state);
+    }
+}
+
+// Hovered and focused hotspots light up over their district.
+fn hotspot_colors(
+    focus: Res<MapFocus>,
+    catalog: Res<AreaCatalog>,
+    mut hotspots: Query<(&Interaction, &TravelHotspot, &mut BackgroundColor)>,
+) {
+    let focused = focus
+        .0
+        .and_then(|index| focus_order(&catalog).get(index).copied());
+    for (interaction, hotspot, mut background) in &mut hotspots {
+        let lit = *interaction != Interaction::None || Some(hotspot.area) == focused;
+        background.0 = if lit {
+            BLUE.with_alpha(0.5)
+        } else {
+            Color::NONE
+        };
+    }
+}
+
+fn show_focus(
+    mut commands: Commands,
+    focus: Res<MapFocus>,
+    catalog: Res<AreaCatalog>,
+    hotspots: Query<(Entity, &TravelHotspot, &Tooltip)>,
+    mut caption: Query<&mut Text, With<MapCaption>>,
+) {
+    let Ok(mut caption) = caption.single_mut() else {
+        return;
+    };
+    if !focus.is_changed() {
+        return;
+    }
+    let focused = focus
+        .0
+        .and_then(|index| focus_order(&catalog).get(index).copied());
+    caption.0.clear();
+    for (entity, hotspot, tooltip) in &hotspots {
+        if Some(hotspot.area) != focused {
+            commands.entity(entity).remove::<Outline>();
+            continue;
+        }
+        commands
+            .entity(entity)
+            .insert(Outline::new(Val::Px(3.0), Val::Px(2.0), YELLOW));
+        caption.0 = match tooltip {
+            Tooltip::Text(text) => text.clone(),
+            Tooltip::Titled { title, body } => format!("{title}\n{body}"),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::catalog::{AREA_CATALOG_PATH, AreaCatalogData};
+    use crate::testing::TestGame;
+
+    /// Business and Condo share a column, Deadbeat and Restricted have no spot.
+    const AREAS: &str = "(areas: [
+        (area: Condo, rent: Some(1000), safety: 80, map: Some((40.0, 60.0))),
+        (area: Mansion, rent: Some(10000), safety: 100, map: Some((70.0, 20.0))),
+        (area: BusinessDistrict, rent: Some(500), safety: 60, map: Some((40.0, 30.0))),
+        (area: DeadbeatArea, rent: Some(250), safety: 30),
+        (area: RestrictedArea, rent: None, safety: 100),
+    ])";
+
+    fn catalog(text: &str) -> AreaCatalog {
+        AreaCatalog::from_data(&ron::from_str::<AreaCatalogData>(text).unwrap())
+    }
+
+    #[test]
+    fn hotspots_go_where_the_data_puts_them() {
+        let catalog = catalog(AREAS);
+        assert_eq!(
+            hotspot_position(&catalog, Area::Condo),
+            Vec2::new(40.0, 60.0)
+        );
+        assert_eq!(
+            hotspot_position(&catalog, Area::Mansion),
+            Vec2::new(70.0, 20.0)
+        );
+        // The two without a spot split the bottom row in area order.
+        assert_eq!(
+            hotspot_position(&catalog, Area::DeadbeatArea),
+            Vec2::new(100.0 / 3.0, UNPLACED_ROW)
+        );
+        assert_eq!(
+            hotspot_position(&catalog, Area::RestrictedArea),
+            Vec2::new(200.0 / 3.0, UNPLACED_ROW)
+        );
+    }
+
+    #[test]
+    fn focus_moves_left_to_right_then_top_to_bottom() {
+        assert_eq!(
+            focus_order(&catalog(AREAS)),
+            [
+                Area::DeadbeatArea,
+                Area::BusinessDistrict,
+                Area::Condo,
+                Area::RestrictedArea,
+                Area::Mansion,
+            ]
+        );
+        assert!(focus_order(&catalog("(areas: [])")).is_empty());
+    }
+
+    #[test]
+    fn the_town_has_a_map_with_every_area_on_it() {
+        let path = format!("assets/{AREA_CATALOG_PATH}");
+        let catalog = catalog(&std::fs::read_to_string(path).unwrap());
+        let image = catalog.map_image().expect("the area data sets a map image");
+        assert!(std::path::Path::new("assets").join(image).is_file());
+        for area in catalog.areas() {
+            assert!(catalog.map_position(area).is_some(), "{area:?} has no spot");
+        }
+    }
+
+    fn condo_details(app: &mut App) -> String {
+        let mut hotspots = app.world_mut().query::<(&TravelHotspot, &Tooltip)>();
+        match hotspots
+            .iter(app.world())
+            .find(|(hotspot, _)| hotspot.area == Area::Condo)
+        {
+            Some((_, Tooltip::Titled { body, .. })) => body.clone(),
+            other => panic!("{:?}", other.map(|(_, tooltip)| tooltip)),
+        }
+    }
+
+    #[test]
+    fn the_open_map_shows_the_rent_of_reloaded_area_data() {
+        let mut app = TestGame::in_game("Travel reload");
+        app.world_mut()
+            .resource_mut::<NextState<Overlay>>()
+            .set(Overlay::Travel);
+        app.update();
+        let rent = app
+            .world()
+            .resource::<AreaCatalog>()
+            .rent(Area::Condo)
+            .unwrap();
+        let prices = *app.world().resource::<PriceLevel>();
+        let locale = *app.world().resource::<Locale>();
+        let listed = |rent| format!("Rent {}:", format::money(prices.scale(rent), locale));
+        assert!(condo_details(&mut app).contains(&listed(rent)));
+
+        for (_, data) in app
+            .world_mut()
+            .resource_mut::<Assets<AreaCatalogData>>()
+            .iter_mut()
+        {
+            for def in &mut data.areas {
+                if def.area == Area::Condo {
+                    def.rent = Some(rent + 100);
+                }
+            }
+        }
+        app.update_until(|app| {
+            app.world().resource::<AreaCatalog>().rent(Area::Condo) != Some(rent)
+        });
+        app.update();
+        assert!(condo_details(&mut app).contains(&listed(rent + 100)));
+    }
+}
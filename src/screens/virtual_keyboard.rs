@@ -0,0 +1,412 @@
+//! The on-screen keyboard, for typing into a [`TextField`] with only a gamepad. It opens
+//! on its own when a field shows up while the player is on a gamepad, or with Y while one
+//! is on screen, and types into every field like the physical keyboard, which keeps
+//! working while it's up.
+//!
+//! The D-pad moves between keys, LB and RB jump to the top and bottom rows, A presses the
+//! key, X deletes, Start is Done and B cancels, putting back what the field had. The keys
+//! come in pages from [`KEYBOARD_PATH`]; those the field doesn't take are greyed out.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::button_press::ButtonPresses;
+use super::overlay::{ModalStack, ModalToken};
+use super::text_field::{FieldSubmitted, TextField};
+use crate::data::register_ron_resource;
+use crate::fonts::ui_font;
+use crate::input_device::{ActiveInputDevice, InputDevice};
+use crate::palette::{BLUE, DARK, DARKER, LIGHT, YELLOW};
+
+pub const KEYBOARD_PATH: &str = "data/world.keyboard.ron";
+/// Above the screens and overlays with fields, below the quit prompt.
+const KEYBOARD_Z_INDEX: i32 = 150;
+const KEY_FONT_SIZE: f32 = 16.0;
+const HINT_FONT_SIZE: f32 = 12.0;
+const KEY_SIZE: f32 = 40.0;
+const REJECTED_ALPHA: f32 = 0.3;
+
+/// The keyboard's pages.
+#[derive(Asset, TypePath, Resource, Deserialize, Clone, Debug)]
+pub struct KeyboardLayouts {
+    pub pages: Vec<KeyboardPage>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct KeyboardPage {
+    /// Shown on the key that switches to this page.
+    pub name: String,
+    /// A row of character keys per string.
+    pub rows: Vec<String>,
+}
+
+impl KeyboardLayouts {
+    /// The keys of `page` by row, with the row of special keys under them.
+    fn keys(&self, page: usize) -> Vec<Vec<Key>> {
+        let mut rows: Vec<Vec<Key>> = self
+            .pages
+            .get(page)
+            .map(|page| {
+                page.rows
+                    .iter()
+                    .map(|row| row.chars().map(Key::Char).collect())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut special = vec![Key::Space, Key::Backspace];
+        if self.pages.len() > 1 {
+            special.push(Key::Page);
+        }
+        special.extend([Key::Done, Key::Cancel]);
+        rows.push(special);
+        rows
+    }
+
+    fn next_page(&self, page: usize) -> usize {
+        (page + 1) % self.pages.len().max(1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Space,
+    Backspace,
+    /// Switches to the next page.
+    Page,
+    Done,
+    Cancel,
+}
+
+impl Key {
+    fn label(self, layouts: &KeyboardLayouts, page: usize) -> String {
+        match self {
+            Key::Char(c) => c.to_string(),
+            Key::Space => "Space".to_string(),
+            Key::Backspace => "Delete".to_string(),
+            Key::Page => layouts.pages[layouts.next_page(page)].name.clone(),
+            Key::Done => "Done".to_string(),
+            Key::Cancel => "Cancel".to_string(),
+        }
+    }
+}
+
+/// The keyboard on screen.
+#[derive(Resource, Debug)]
+struct OpenKeyboard {
+    overlay: Entity,
+    modal: ModalToken,
+    page: usize,
+    /// The key picked, by row and column in [`KeyboardLayouts::keys`].
+    row: usize,
+    column: usize,
+    /// What the fields had when it opened, put back on Cancel.
+    original: String,
+}
+
+#[derive(Component)]
+struct KeyboardPreview;
+
+/// Holds the rows of keys, rebuilt when the page changes.
+#[derive(Component, Default)]
+struct KeyboardGrid {
+    page: Option<usize>,
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+struct KeyButton {
+    row: usize,
+    column: usize,
+    key: Key,
+}
+
+pub fn virtual_keyboard_plugin(app: &mut App) {
+    register_ron_resource::<KeyboardLayouts>(app, &["keyboard.ron"]);
+    app.add_systems(
+        Update,
+        (
+            open_keyboard.run_if(
+                not(resource_exists::<OpenKeyboard>).and(resource_exists::<KeyboardLayouts>),
+            ),
+            (
+                close_without_fields,
+                press_keys,
+                (fill_keys, show_selection, show_preview).chain(),
+            )
+                .chain()
+                .run_if(resource_exists::<OpenKeyboard>),
+        )
+            .chain(),
+    );
+}
+
+fn open_keyboard(
+    mut commands: Commands,
+    gamepads: Query<&Gamepad>,
+    added: Query<(), Added<TextField>>,
+    fields: Query<&TextField>,
+    device: Res<ActiveInputDevice>,
+    mut modals: ResMut<ModalStack>,
+    assets: Res<AssetServer>,
+) {
+    let Some(field) = fields.iter().next() else {
+        return;
+    };
+    let asked = gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::North));
+    if !asked && (added.is_empty() || device.0 != InputDevice::Gamepad) {
+        return;
+    }
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: KEY_FONT_SIZE,
+        ..default()
+    };
+    let overlay = commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                align_items: AlignItems::FlexEnd,
+                justify_content: JustifyContent::Center,
+                padding: UiRect::bottom(Val::Percent(5.0)),
+                ..default()
+            },
+            GlobalZIndex(KEYBOARD_Z_INDEX),
+            children![(
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(8.0),
+                    padding: UiRect::all(Val::Px(12.0)),
+                    ..default()
+                },
+                BackgroundColor(DARKER),
+                children![
+                    (
+                        Text::new(field.shown()),
+                        font.clone(),
+                        TextColor(YELLOW),
+                        KeyboardPreview,
+                    ),
+                    (
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            row_gap: Val::Px(4.0),
+                            ..default()
+                        },
+                        KeyboardGrid::default(),
+                    ),
+                    (
+                        Text::new(
+                            "A: type - X: delete - LB/RB: jump rows - Start: done - B: cancel"
+                        ),
+                        TextFont {
+                            font_size: HINT_FONT_SIZE,
+                            ..font
+                        },
+                        TextColor(LIGHT.with_alpha(0.6)),
+                    ),
+                ],
+            )],
+        ))
+        .id();
+    commands.insert_resource(OpenKeyboard {
+        overlay,
+        modal: modals.open_modal(),
+        page: 0,
+        row: 0,
+        column: 0,
+        original: field.value.clone(),
+    });
+}
+
+fn close_keyboard(commands: &mut Commands, open: &OpenKeyboard, modals: &mut ModalStack) {
+    modals.close_modal(open.modal);
+    commands.entity(open.overlay).despawn();
+    commands.remove_resource::<OpenKeyboard>();
+}
+
+// The screen with the fields went away under the keyboard.
+fn close_without_fields(
+    mut commands: Commands,
+    fields: Query<(), With<TextField>>,
+    open: Res<OpenKeyboard>,
+    mut modals: ResMut<ModalStack>,
+) {
+    if fields.is_empty() {
+        close_keyboard(&mut commands, &open, &mut modals);
+    }
+}
+
+fn press_keys(
+    mut commands: Commands,
+    gamepads: Query<&Gamepad>,
+    mut clicked: ButtonPresses<&KeyButton>,
+    layouts: Res<KeyboardLayouts>,
+    mut open: ResMut<OpenKeyboard>,
+    mut fields: Query<&mut TextField>,
+    mut modals: ResMut<ModalStack>,
+    mut submitted: EventWriter<FieldSubmitted>,
+) {
+    let just_pressed = |button| {
+        gamepads
+            .iter()
+            .any(|gamepad: &Gamepad| gamepad.just_pressed(button))
+    };
+    let keys = layouts.keys(open.page);
+    let last_row = keys.len() - 1;
+    let (mut row, mut column) = (open.row, open.column);
+    if just_pressed(GamepadButton::DPadUp) {
+        row = row.checked_sub(1).unwrap_or(last_row);
+    }
+    if just_pressed(GamepadButton::DPadDown) {
+        row = if row == last_row { 0 } else { row + 1 };
+    }
+    if just_pressed(GamepadButton::LeftTrigger) {
+        row = 0;
+    }
+    if just_pressed(GamepadButton::RightTrigger) {
+        row = last_row;
+    }
+    column = column.min(keys[row].len() - 1);
+    if just_pressed(GamepadButton::DPadLeft) {
+        column = column.checked_sub(1).unwrap_or(keys[row].len() - 1);
+    }
+    if just_pressed(GamepadButton::DPadRight) {
+        column = (column + 1) % keys[row].len();
+    }
+
+    let mut pressed = None;
+    if just_pressed(GamepadButton::South) {
+        pressed = Some(keys[row][column]);
+    } else if just_pressed(GamepadButton::West) {
+        pressed = Some(Key::Backspace);
+    } else if just_pressed(GamepadButton::Start) {
+        pressed = Some(Key::Done);
+    } else if just_pressed(GamepadButton::East) {
+        pressed = Some(Key::Cancel);
+    }
+    if let Some(button) = clicked.read().last() {
+        (row, column) = (button.row, button.column);
+        pressed = Some(button.key);
+    }
+    if (row, column) != (open.row, open.column) {
+        (open.row, open.column) = (row, column);
+    }
+
+    let Some(key) = pressed else {
+        return;
+    };
+    match key {
+        Key::Char(c) => fields.iter_mut().for_each(|mut field| field.push(c)),
+        Key::Space => fields.iter_mut().for_each(|mut field| field.push(' ')),
+        Key::Backspace => fields.iter_mut().for_each(|mut field| {
+            field.value.pop();
+        }),
+        Key::Page => {
+            open.page = layouts.next_page(open.page);
+            let keys = layouts.keys(open.page);
+            open.row = open.row.min(keys.len() - 1);
+            open.column = open.column.min(keys[open.row].len() - 1);
+        }
+        Key::Done => {
+            submitted.write(FieldSubmitted);
+            close_keyboard(&mut commands, &open, &mut modals);
+        }
+        Key::Cancel => {
+            for mut field in &mut fields {
+                field.value.clone_from(&open.original);
+            }
+            close_keyboard(&mut commands, &open, &mut modals);
+        }
+    }
+}
+
+fn fill_keys(
+    mut commands: Commands,
+    open: Res<OpenKeyboard>,
+    layouts: Res<KeyboardLayouts>,
+    mut grid: Query<(Entity, &mut KeyboardGrid)>,
+    fields: Query<&TextField>,
+    assets: Res<AssetServer>,
+) {
+    let Ok((entity, mut grid)) = grid.single_mut() else {
+        return;
+    };
+    if grid.page == Some(open.page) {
+        return;
+    }
+    grid.page = Some(open.page);
+    let accepts: fn(char) -> bool = fields.iter().next().map_or(|_| true, |field| field.accepts);
+    let font = TextFont {
+        font: ui_font(&assets),
+        font_size: KEY_FONT_SIZE,
+        ..default()
+    };
+    commands
+        .entity(entity)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            for (row, keys) in layouts.keys(open.page).into_iter().enumerate() {
+                parent
+                    .spawn(Node {
+                        column_gap: Val::Px(4.0),
+                        ..default()
+                    })
+                    .with_children(|line| {
+                        for (column, key) in keys.into_iter().enumerate() {
+                            let alpha = match key {
+                                Key::Char(c) if !accepts(c) => REJECTED_ALPHA,
+                                Key::Space if !accepts(' ') => REJECTED_ALPHA,
+                                _ => 1.0,
+                            };
+                            line.spawn((
+                                Button,
+                                Node {
+                                    min_width: Val::Px(KEY_SIZE),
+                                    height: Val::Px(KEY_SIZE),
+                                    padding: UiRect::horizontal(Val::Px(6.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                BackgroundColor(DARK),
+                                KeyButton { row, column, key },
+                                children![(
+                                    Text::new(key.label(&layouts, open.page)),
+                                    font.clone(),
+                                    TextColor(LIGHT.with_alpha(alpha)),
+                                )],
+                            ));
+                        }
+                    });
+            }
+        });
+}
+
+fn show_selection(open: Res<OpenKeyboard>, mut keys: Query<(&KeyButton, &mut BackgroundColor)>) {
+    for (key, mut background) in &mut keys {
+        let color = if (key.row, key.column) == (open.row, open.column) {
+            BLUE
+        } else {
+            DARK
+        };
+        background.set_if_neq(BackgroundColor(color));
+    }
+}
+
+fn show_preview(fields: Query<&TextField>, mut preview: Query<&mut Text, With<KeyboardPreview>>) {
+    let Ok(mut preview) = preview.single_mut() else {
+        return;
+    };
+    if let Some(field) = fields.iter().next() {
+        let shown = field.shown();
+        if preview.0 != shown {
+            preview.0 = shown;
+        }
+    }
+}
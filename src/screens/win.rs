@@ -1,20 +1,110 @@
-use crate::{AppState, TEXT_COLOR, despawn_screen};
-use bevy::prelude::*;
+use super::button_press::ButtonPresses;
+use super::challenge::challenge_result;
+use super::nav::{ScreenNav, TransitionCause};
+use super::report::notable_decisions;
+use crate::format;
+use crate::input_device::{ActiveInputDevice, InputDevice, InputDeviceChanged};
+use crate::palette::{BLUE, DARKER, YELLOW};
+use crate::profiles::ProfilePaths;
+use crate::save_file::SaveFiles;
+use crate::settings::Locale;
+use crate::sim::{
+    ResumeRun,
+    challenge::{Challenge, ChallengeResult},
+    choices::ChoiceLog,
+    clock::Clock,
+    crime::Upgrades,
+    economy::{Ending, FreePlay},
+    ledger::Wealth,
+    new_game_plus::{CarryOver, NewGamePlus, OwnedItem},
+    pet::Pet,
+    playtime::Playtime,
+    replay::RunSetup,
+    scenario::Scenarios,
+};
+use crate::{AppState, TEXT_COLOR, despawn_screen, track_screen};
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+
+/// Number keys pick the New Game+ options in order.
+const OPTION_KEYS: [KeyCode; 4] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+];
+/// Goes back to the run with the win behind it, like the button.
+const KEEP_PLAYING_KEY: KeyCode = KeyCode::KeyC;
 
 #[derive(Component)]
 struct OnWinScreen;
 
+#[derive(Component)]
+struct RestartPrompt;
+
+#[derive(Component)]
+struct KeepPlayingButton;
+
+/// A New Game+ button: its place in [`OPTION_KEYS`] and the item it carries over.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+struct NewGamePlusOption(usize, Option<OwnedItem>);
+
 // Plugin definition
 pub fn win_plugin(app: &mut App) {
-    app.add_systems(OnEnter(AppState::GameOver), setup_gameover_screen)
-        .add_systems(OnExit(AppState::GameOver), despawn_screen::<OnWinScreen>)
+    track_screen::<OnWinScreen>(app, AppState::Win);
+    app.add_systems(OnEnter(AppState::Win), setup_win_screen)
+        .add_systems(OnExit(AppState::Win), despawn_screen::<OnWinScreen>)
         .add_systems(
             Update,
-            process_commands.run_if(in_state(AppState::GameOver)),
+            (
+                win_button_colors,
+                keep_playing,
+                start_new_game_plus,
+                process_commands,
+                update_prompt,
+            )
+                .chain()
+                .run_if(in_state(AppState::Win)),
         );
 }
 
-fn setup_gameover_screen(mut commands: Commands) {
+fn setup_win_screen(
+    mut commands: Commands,
+    device: Res<ActiveInputDevice>,
+    new_game_plus: Res<NewGamePlus>,
+    upgrades: Res<Upgrades>,
+    pet: Res<Pet>,
+    ending: Res<Ending>,
+    choices: Res<ChoiceLog>,
+    playtime: Res<Playtime>,
+    challenge: Res<Challenge>,
+    (setup, scenarios): (Res<RunSetup>, Res<Scenarios>),
+    clock: Res<Clock>,
+    wealth: Wealth,
+    locale: Res<Locale>,
+    profile: Res<ProfilePaths>,
+    mut files: SaveFiles,
+) {
+    let result = ChallengeResult {
+        won: true,
+        days: clock.day,
+        worth: wealth.net_worth().total(),
+        bankrupt_on: None,
+        lost_to: None,
+        scenario: setup.scenario.clone(),
+    };
+    let heading = match *ending {
+        Ending::Wealth => "You win!",
+        Ending::Philanthropy => "A true philanthropist!",
+        Ending::InnerCircle => "Welcome to the inner circle",
+    };
+    let options: Vec<_> = OwnedItem::owned(&upgrades, &pet)
+        .into_iter()
+        .map(Some)
+        .chain([None])
+        .take(OPTION_KEYS.len())
+        .collect();
+    let next_cycle = new_game_plus.cycle + 1;
+
     commands.spawn((
         Node {
             width: Val::Percent(100.0),
@@ -33,7 +123,7 @@ fn setup_gameover_screen(mut commands: Commands) {
             },
             children![
                 (
-                    Text::new("You win!"),
+                    Text::new(heading),
                     TextFont {
                         font_size: 67.0,
                         ..default()
@@ -42,27 +132,196 @@ fn setup_gameover_screen(mut commands: Commands) {
                     TextShadow::default()
                 ),
                 (
-                    Text::new("Press any key to restart or esc for the menu."),
+                    Text::new(restart_prompt(device.0)),
                     TextFont {
                         font_size: 33.0,
                         ..default()
                     },
                     TextColor(TEXT_COLOR),
-                    TextShadow::default()
-                )
+                    TextShadow::default(),
+                    RestartPrompt,
+                ),
+                (
+                    Text::new(format!("Played {}", format::playtime(playtime.0))),
+                    TextFont {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    TextColor(TEXT_COLOR),
+                ),
+                notable_decisions(&choices),
+                challenge_result(
+                    &challenge, result, &scenarios, *locale, &profile, &mut files,
+                ),
+                (
+                    Button,
+                    Node {
+                        padding: UiRect::axes(Val::Px(14.0), Val::Px(8.0)),
+                        margin: UiRect::top(Val::Px(20.0)),
+                        ..default()
+                    },
+                    BackgroundColor(DARKER),
+                    KeepPlayingButton,
+                    children![(
+                        Text::new("C Keep playing this run"),
+                        TextFont {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                        TextColor(TEXT_COLOR),
+                    )],
+                ),
+                (
+                    Text::new(format!(
+                        "New Game+ {next_cycle}: higher prices, a higher target. Keep one item:"
+                    )),
+                    TextFont {
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(YELLOW),
+                    Node {
+                        margin: UiRect::top(Val::Px(30.0)),
+                        ..default()
+                    },
+                ),
+                (
+                    Node {
+                        column_gap: Val::Px(10.0),
+                        margin: UiRect::top(Val::Px(10.0)),
+                        ..default()
+                    },
+                    Children::spawn(SpawnIter(options.into_iter().enumerate().map(
+                        |(index, option)| {
+                            let label = option
+                                .as_ref()
+                                .map_or("Nothing".to_string(), OwnedItem::label);
+                            (
+                                Button,
+                                Node {
+                                    padding: UiRect::axes(Val::Px(14.0), Val::Px(8.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(DARKER),
+                                NewGamePlusOption(index, option),
+                                children![(
+                                    Text::new(format!("{} {label}", index + 1)),
+                                    TextFont {
+                                        font_size: 20.0,
+                                        ..default()
+                                    },
+                                    TextColor(TEXT_COLOR),
+                                )],
+                            )
+                        },
+                    ))),
+                ),
             ],
         )],
     ));
 }
 
-fn process_commands(keyboard_input: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
-        commands.set_state(AppState::Menu);
+fn win_button_colors(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (
+            Changed<Interaction>,
+            Or<(With<KeepPlayingButton>, With<NewGamePlusOption>)>,
+        ),
+    >,
+) {
+    for (interaction, mut background) in &mut buttons {
+        background.0 = match interaction {
+            Interaction::None => DARKER,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
+
+/// Goes back to the run as it is, from the button or [`KEEP_PLAYING_KEY`]. It can't be
+/// won again, see [`FreePlay`].
+fn keep_playing(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut presses: ButtonPresses<(), With<KeepPlayingButton>>,
+    clock: Res<Clock>,
+    mut free_play: ResMut<FreePlay>,
+    mut nav: ScreenNav,
+) {
+    let clicked = presses.read().count() > 0;
+    if !clicked && !keyboard_input.just_pressed(KEEP_PLAYING_KEY) {
         return;
     }
+    free_play.0.get_or_insert(clock.day);
+    commands.insert_resource(ResumeRun);
+    nav.set_app(AppState::Game, TransitionCause::Button);
+}
 
-    if keyboard_input.get_just_pressed().len() > 0 {
-        commands.set_state(AppState::Game);
+/// Starts the next New Game+ cycle from a button or its number key.
+fn start_new_game_plus(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut presses: ButtonPresses<&NewGamePlusOption>,
+    options: Query<&NewGamePlusOption>,
+    mut new_game_plus: ResMut<NewGamePlus>,
+    mut carry_over: ResMut<CarryOver>,
+    mut challenge: ResMut<Challenge>,
+    mut nav: ScreenNav,
+) {
+    let mut chosen = OPTION_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key))
+        .and_then(|index| options.iter().find(|option| option.0 == index));
+    if let Some(option) = presses.read().last() {
+        chosen = Some(option);
+    }
+    let Some(NewGamePlusOption(_, item)) = chosen else {
         return;
+    };
+    new_game_plus.cycle += 1;
+    carry_over.0 = item.clone();
+    challenge.0 = None;
+    nav.set_app(AppState::Game, TransitionCause::Button);
+}
+
+fn restart_prompt(device: InputDevice) -> &'static str {
+    device.prompt(
+        "Press any key to restart or esc for the menu.",
+        "Press A to restart or B for the menu.",
+    )
+}
+
+fn update_prompt(
+    mut changed: EventReader<InputDeviceChanged>,
+    mut prompt: Query<&mut Text, With<RestartPrompt>>,
+) {
+    let Ok(mut prompt) = prompt.single_mut() else {
+        return;
+    };
+    if let Some(InputDeviceChanged(device)) = changed.read().last() {
+        prompt.0 = restart_prompt(*device).to_string();
+    }
+}
+
+fn process_commands(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut new_game_plus: ResMut<NewGamePlus>,
+    mut carry_over: ResMut<CarryOver>,
+    mut nav: ScreenNav,
+) {
+    let gamepad_pressed = |button| gamepads.iter().any(|gamepad| gamepad.just_pressed(button));
+    if keyboard_input.just_pressed(KeyCode::Escape) || gamepad_pressed(GamepadButton::East) {
+        nav.set_app(AppState::Menu, TransitionCause::Hotkey);
+        return;
+    }
+
+    let restart_key = keyboard_input
+        .get_just_pressed()
+        .any(|key| !OPTION_KEYS.contains(key) && *key != KEEP_PLAYING_KEY);
+    if restart_key || gamepad_pressed(GamepadButton::South) {
+        // A plain restart is a regular game again.
+        *new_game_plus = NewGamePlus::default();
+        *carry_over = CarryOver::default();
+        nav.set_app(AppState::Game, TransitionCause::Hotkey);
     }
 }
@@ -0,0 +1,375 @@
+//! The `--self-test` launch mode, for catching broken wiring after a refactor: a plugin
+//! that isn't added, a state with no screen, a screen that never goes away.
+//!
+//! The game runs without a window and walks every transition in [`ROUTES`], in order. When
+//! the game isn't on a route's starting screen, it's put there first. Every screen it comes
+//! to must have spawned something tagged with one of its markers (see
+//! [`crate::track_screen`]), unless it's [`SCREENLESS`], and every screen it leaves must be
+//! gone within [`LEAVE_TIMEOUT`], sliding out included. No marker recorded with
+//! [`track_singleton`](crate::screens::singleton::track_singleton) may be on more than one
+//! entity on any screen. On the way, it checks that every
+//! [`MenuButtonAction`] shows up on some button: the settings are applied with a new display
+//! mode once to bring up the dialog with the last two.
+//!
+//! At the end the report is printed and the game quits, with a failing exit code if any
+//! check failed. The walk plays in its own [`PROFILE`], which is deleted afterwards.
+//! `cargo test` walks it too.
+
+use std::time::Duration;
+
+use bevy::{
+    app::{PluginGroupBuilder, ScheduleRunnerPlugin},
+    prelude::*,
+    render::{RenderPlugin, settings::WgpuSettings},
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+
+use crate::AppState;
+use crate::profiles::{self, ProfilePaths};
+use crate::screens::button_press::ButtonPressed;
+use crate::screens::menu::{MenuButtonAction, MenuState};
+use crate::screens::nav::{ROUTES, Route, SCREENLESS, Screen, ScreenMarkers};
+use crate::screens::singleton::Singletons;
+use crate::settings::{DisplayMode, PendingSettings};
+use crate::sim::economy::GameOverReason;
+
+/// The profile the walk plays in, unless `--profile` picks one.
+pub const PROFILE: &str = "Self test";
+/// How long loading may take before the walk gives up.
+const LOADING_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long a screen may take to come up, or to be gone once left.
+const ENTER_TIMEOUT: Duration = Duration::from_secs(5);
+const LEAVE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the keep-display dialog may take to come up after applying.
+const DIALOG_TIMEOUT: Duration = Duration::from_secs(2);
+const FRAME: Duration = Duration::from_nanos(16_666_667);
+
+/// `plugins` without a window or a renderer, updating on a timer instead.
+pub fn headless(plugins: PluginGroupBuilder) -> PluginGroupBuilder {
+    plugins
+        .set(WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            close_when_requested: false,
+        })
+        .set(RenderPlugin {
+            render_creation: WgpuSettings {
+                backends: None,
+                ..default()
+            }
+            .into(),
+            ..default()
+        })
+        .disable::<WinitPlugin>()
+        .add(ScheduleRunnerPlugin::run_loop(FRAME))
+}
+
+/// Where the walk is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Loading,
+    /// Waiting for the next route.
+    Ready,
+    /// On the way from one screen to another, which the walk may be forcing to get to a
+    /// route's start.
+    Entering {
+        from: Screen,
+        to: Screen,
+    },
+    /// Waiting for the screen the walk came from to be gone.
+    Leaving {
+        from: Screen,
+    },
+    /// Applied a new display mode, waiting for the dialog asking to keep it.
+    ApplyingDisplay,
+}
+
+#[derive(Resource)]
+struct Walk {
+    step: Step,
+    /// When the step started, on the real clock.
+    since: Duration,
+    /// The next route in [`ROUTES`].
+    next: usize,
+    seen: Vec<MenuButtonAction>,
+    probed_display: bool,
+    checks: usize,
+    failures: Vec<String>,
+}
+
+impl Walk {
+    fn check(&mut self, passed: bool, failure: impl FnOnce() -> String) {
+        self.checks += 1;
+        if !passed {
+            let failure = failure();
+            error!("Self-test: {failure}");
+            self.failures.push(failure);
+        }
+    }
+}
+
+pub fn self_test_plugin(app: &mut App) {
+    app.insert_resource(Walk {
+        step: Step::Loading,
+        since: Duration::ZERO,
+        next: 0,
+        seen: Vec::new(),
+        probed_display: false,
+        checks: 0,
+        failures: Vec::new(),
+    })
+    // After everything else asked for its transitions, so the walk's win.
+    .add_systems(PostUpdate, walk);
+}
+
+// Takes as many steps as it can this frame, stopping at the first that has to wait.
+fn walk(world: &mut World) {
+    let now = world.resource::<Time<Real>>().elapsed();
+    let mut seen = world.query::<&MenuButtonAction>();
+    let seen: Vec<_> = seen.iter(world).copied().collect();
+    let mut walk = world.remove_resource::<Walk>().expect("the walk is set up");
+    for action in seen {
+        if !walk.seen.contains(&action) {
+            walk.seen.push(action);
+        }
+    }
+    while let Some(step) = take_step(world, &mut walk, now) {
+        walk.step = step;
+        walk.since = now;
+    }
+    world.insert_resource(walk);
+}
+
+// The step after the current one, or `None` to wait for the next frame.
+fn take_step(world: &mut World, walk: &mut Walk, now: Duration) -> Option<Step> {
+    let waited = now - walk.since;
+    match walk.step {
+        Step::Loading => match *world.resource::<State<AppState>>().get() {
+            AppState::Menu => Some(Step::Ready),
+            AppState::AssetError => {
+                walk.check(false, || "the assets didn't load".to_string());
+                finish(world, walk)
+            }
+            _ if waited > LOADING_TIMEOUT => {
+                walk.check(false, || "loading never finished".to_string());
+                finish(world, walk)
+            }
+            _ => None,
+        },
+        Step::Ready => {
+            if !walk.probed_display && current(world, Screen::Menu(MenuState::Settings)) {
+                walk.probed_display = true;
+                return apply_new_display(world).then_some(Step::ApplyingDisplay);
+            }
+            let Some(&Route { from, to }) = ROUTES.get(walk.next) else {
+                check_buttons(walk);
+                return finish(world, walk);
+            };
+            let here = current_screen(world, from);
+            let to = if here == from {
+                walk.next += 1;
+                to
+            } else {
+                from
+            };
+            set(world, to);
+            Some(Step::Entering { from: here, to })
+        }
+        Step::Entering { from, to } => {
+            if !current(world, to) {
+                if waited < ENTER_TIMEOUT {
+                    return None;
+                }
+                walk.check(false, || format!("{from:?} -> {to:?} never got to {to:?}"));
+                return Some(Step::Ready);
+            }
+            if !SCREENLESS.contains(&to) {
+                let spawned = marker_counts(world, to);
+                walk.check(!spawned.is_empty(), || {
+                    format!("{to:?} has no screen markers, see track_screen")
+                });
+                walk.check(spawned.iter().any(|(_, count)| *count > 0), || {
+                    format!("{from:?} -> {to:?} spawned nothing for {to:?}")
+                });
+            }
+            check_singletons(world, walk, to);
+            Some(Step::Leaving { from })
+        }
+        Step::Leaving { from } => {
+            let left: Vec<_> = marker_counts(world, from)
+                .into_iter()
+                .filter(|(_, count)| *count > 0)
+                .collect();
+            if left.is_empty() || current(world, from) {
+                return Some(Step::Ready);
+            }
+            if waited < LEAVE_TIMEOUT {
+                return None;
+            }
+            walk.check(false, || {
+                let left: Vec<_> = left
+                    .iter()
+                    .map(|(name, count)| format!("{count} {name}"))
+                    .collect();
+                format!("{} left after leaving {from:?}", left.join(", "))
+            });
+            Some(Step::Ready)
+        }
+        Step::ApplyingDisplay => {
+            let revert = button(world, MenuButtonAction::RevertDisplay);
+            if let Some(revert) = revert
+                && button(world, MenuButtonAction::KeepDisplay).is_some()
+            {
+                world.send_event(ButtonPressed(revert));
+                return Some(Step::Ready);
+            }
+            if waited < DIALOG_TIMEOUT {
+                return None;
+            }
+            walk.check(false, || {
+                "applying a new display mode didn't ask to keep it".to_string()
+            });
+            Some(Step::Ready)
+        }
+    }
+}
+
+// Stages the other display mode and presses Apply, which asks whether to keep it. Returns
+// whether there was an Apply button to press.
+fn apply_new_display(world: &mut World) -> bool {
+    let Some(apply) = button(world, MenuButtonAction::ApplySettings) else {
+        return false;
+    };
+    let Some(mut pending) = world.get_resource_mut::<PendingSettings>() else {
+        return false;
+    };
+    pending.staged.display = match pending.committed.display {
+        DisplayMode::Windowed => DisplayMode::Fullscreen,
+        DisplayMode::Fullscreen => DisplayMode::Windowed,
+    };
+    world.send_event(ButtonPressed(apply));
+    true
+}
+
+fn check_singletons(world: &World, walk: &mut Walk, screen: Screen) {
+    let Some(singletons) = world.get_resource::<Singletons>() else {
+        return;
+    };
+    for singleton in &singletons.0 {
+        let entities = singleton.entities(world);
+        walk.check(entities.len() <= 1, || {
+            format!(
+                "{} entities have {} on {screen:?}",
+                entities.len(),
+                singleton.name
+            )
+        });
+    }
+}
+
+fn check_buttons(walk: &mut Walk) {
+    for action in MenuButtonAction::ALL {
+        let seen = walk.seen.contains(&action);
+        walk.check(seen, || {
+            format!("no button for MenuButtonAction::{action:?}")
+        });
+    }
+}
+
+// Prints the report and quits.
+fn finish(world: &mut World, walk: &Walk) -> Option<Step> {
+    println!(
+        "Self-test: {} checks, {} routes, {} failed",
+        walk.checks,
+        walk.next,
+        walk.failures.len()
+    );
+    for failure in &walk.failures {
+        println!("  FAIL {failure}");
+    }
+    let profile = world.resource::<ProfilePaths>();
+    if profile.name() == PROFILE
+        && let Err(error) = profiles::delete(profile.root(), PROFILE)
+    {
+        warn!("Couldn't delete the {PROFILE} profile: {error}");
+    }
+    world.send_event(if walk.failures.is_empty() {
+        AppExit::Success
+    } else {
+        AppExit::from_code(1)
+    });
+    None
+}
+
+fn button(world: &mut World, action: MenuButtonAction) -> Option<Entity> {
+    let mut buttons = world.query::<(Entity, &MenuButtonAction)>();
+    buttons
+        .iter(world)
+        .find(|(_, button)| **button == action)
+        .map(|(entity, _)| entity)
+}
+
+// The screen at `level`'s level.
+fn current_screen(world: &World, level: Screen) -> Screen {
+    match level {
+        Screen::App(_) => Screen::App(*world.resource::<State<AppState>>().get()),
+        Screen::Menu(_) => Screen::Menu(*world.resource::<State<MenuState>>().get()),
+    }
+}
+
+fn current(world: &World, screen: Screen) -> bool {
+    current_screen(world, screen) == screen
+}
+
+// Goes straight to `screen`, without `ScreenNav`: the walk takes routes the game doesn't
+// to get to a route's start. A game over gets the reason a lost run would have left.
+fn set(world: &mut World, screen: Screen) {
+    if screen == Screen::App(AppState::GameOver) {
+        world.insert_resource(GameOverReason::Evicted);
+    }
+    match screen {
+        Screen::App(state) => world.resource_mut::<NextState<AppState>>().set(state),
+        Screen::Menu(state) => world.resource_mut::<NextState<MenuState>>().set(state),
+    }
+}
+
+// Each marker of `screen` with how many entities have it.
+fn marker_counts(world: &World, screen: Screen) -> Vec<(&'static str, usize)> {
+    fn counts<S: States>(world: &World, state: &S) -> Vec<(&'static str, usize)> {
+        world
+            .get_resource::<ScreenMarkers<S>>()
+            .map(|markers| {
+                markers
+                    .of(state)
+                    .map(|marker| (marker.name, marker.count(world)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    match screen {
+        Screen::App(state) => counts(world, &state),
+        Screen::Menu(state) => counts(world, &state),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::launch::LaunchOptions;
+    use crate::testing::TestGame;
+
+    #[test]
+    fn every_route_walks_without_failures() {
+        let mut app = TestGame::new(LaunchOptions {
+            self_test: true,
+            profile: Some(PROFILE.to_string()),
+            ..default()
+        });
+        let exit = app.run_to_exit();
+        let walk = app.world().resource::<Walk>();
+        assert_eq!(walk.failures, Vec::<String>::new());
+        assert_eq!(walk.next, ROUTES.len());
+        assert_eq!(exit, AppExit::Success);
+    }
+}
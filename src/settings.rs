@@ -0,0 +1,581 @@
+//! Player settings. The live values are plain resources ([`Volume`], [`DisplayMode`]);
+//! the settings screens edit a staged copy in [`PendingSettings`] and only write it back
+//! when the player applies it. Settings that should outlive a session are kept in the
+//! profile's [`SETTINGS_FILE`].
+
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    window::{MonitorSelection, PrimaryWindow, WindowMode, WindowResized},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Volume;
+use crate::profiles::{ProfilePaths, profile_switched};
+use crate::save_file::{CorruptFiles, Saves, load_ron};
+
+/// Where [`SavedSettings`] live, in the profile's directory.
+pub const SETTINGS_FILE: &str = "settings.ron";
+/// Windows shorter than this get [`LayoutScale::Compact`] with [`LayoutMode::Auto`]. The
+/// default 1280x720 window and 1280x800 handhelds keep the regular layout.
+const COMPACT_BELOW_HEIGHT: f32 = 600.0;
+/// How much [`LayoutScale::Compact`] shrinks fonts and buttons.
+const COMPACT_SCALE: f32 = 0.65;
+
+#[derive(Resource, Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "Windowed",
+            DisplayMode::Fullscreen => "Fullscreen",
+        }
+    }
+}
+
+/// How the in-game time is shown in the HUD, see [`crate::screens::clock_dial`].
+#[derive(
+    Resource, Component, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+pub enum ClockStyle {
+    /// The hour as text only.
+    Digital,
+    /// A dial, with the text keeping just the date.
+    Analog,
+    #[default]
+    Both,
+}
+
+impl ClockStyle {
+    pub fn label(self) -> &'static str {
+        match self {
+            ClockStyle::Digital => "Digital",
+            ClockStyle::Analog => "Analog",
+            ClockStyle::Both => "Both",
+        }
+    }
+
+    pub fn shows_dial(self) -> bool {
+        self != ClockStyle::Digital
+    }
+
+    pub fn shows_hour_text(self) -> bool {
+        self != ClockStyle::Analog
+    }
+}
+
+/// How the game HUD is arranged, see [`crate::screens::hud`].
+#[derive(
+    Resource, Component, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+pub enum HudLayout {
+    /// Bars along the top and bottom, kept to a readable width on wide screens.
+    #[default]
+    Bars,
+    /// Elements pushed out to the corners of the screen.
+    Corners,
+}
+
+impl HudLayout {
+    pub fn label(self) -> &'static str {
+        match self {
+            HudLayout::Bars => "Bars",
+            HudLayout::Corners => "Corners",
+        }
+    }
+}
+
+/// How gains and losses of money are called out in the HUD, see
+/// [`crate::screens::money_feedback`].
+#[derive(
+    Resource, Component, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+pub enum MoneyFeedback {
+    /// Amounts floating up from the money.
+    #[default]
+    Popups,
+    /// The money flashes and shows the change next to it.
+    Inline,
+    None,
+}
+
+impl MoneyFeedback {
+    pub fn label(self) -> &'static str {
+        match self {
+            MoneyFeedback::Popups => "Popups",
+            MoneyFeedback::Inline => "Inline",
+            MoneyFeedback::None => "None",
+        }
+    }
+}
+
+/// What HUD text gets to stand out from bright location art, see
+/// [`crate::screens::text_backing`].
+#[derive(
+    Resource, Component, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+pub enum TextReadability {
+    Off,
+    #[default]
+    Shadow,
+    StrongShadow,
+    /// A dark panel behind each block of text.
+    Panel,
+}
+
+impl TextReadability {
+    pub fn label(self) -> &'static str {
+        match self {
+            TextReadability::Off => "Off",
+            TextReadability::Shadow => "Shadow",
+            TextReadability::StrongShadow => "Strong",
+            TextReadability::Panel => "Panel",
+        }
+    }
+}
+
+/// Whether gamepads rumble for the run's big moments, see [`crate::haptics`].
+#[derive(
+    Resource, Component, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+pub enum Rumble {
+    #[default]
+    On,
+    Off,
+}
+
+impl Rumble {
+    pub fn label(self) -> &'static str {
+        match self {
+            Rumble::On => "On",
+            Rumble::Off => "Off",
+        }
+    }
+}
+
+/// Whether the HUD shows in the photos taken for the journal, see
+/// [`crate::screens::journal`].
+#[derive(
+    Resource, Component, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+pub enum PhotoHud {
+    #[default]
+    Shown,
+    Hidden,
+}
+
+impl PhotoHud {
+    pub fn label(self) -> &'static str {
+        match self {
+            PhotoHud::Shown => "With HUD",
+            PhotoHud::Hidden => "Without HUD",
+        }
+    }
+}
+
+/// Whether content with one of the [`ContentTag`]s comes up in new runs.
+///
+/// [`ContentTag`]: crate::sim::content::ContentTag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect, Serialize, Deserialize)]
+pub enum ContentFilter {
+    #[default]
+    Shown,
+    Hidden,
+}
+
+impl ContentFilter {
+    pub fn label(self) -> &'static str {
+        match self {
+            ContentFilter::Shown => "Shown",
+            ContentFilter::Hidden => "Hidden",
+        }
+    }
+}
+
+/// The content filters for new runs, see [`crate::sim::content`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentFilters {
+    pub gambling: ContentFilter,
+    pub crime: ContentFilter,
+}
+
+/// A margin around the HUD for TVs that crop the edges of the picture.
+#[derive(
+    Resource, Component, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+pub enum SafeArea {
+    #[default]
+    None,
+    Small,
+    Large,
+}
+
+impl SafeArea {
+    pub fn label(self) -> &'static str {
+        match self {
+            SafeArea::None => "None",
+            SafeArea::Small => "2.5%",
+            SafeArea::Large => "5%",
+        }
+    }
+
+    /// The margin on each side, as a percentage of the window.
+    pub fn percent(self) -> f32 {
+        match self {
+            SafeArea::None => 0.0,
+            SafeArea::Small => 2.5,
+            SafeArea::Large => 5.0,
+        }
+    }
+}
+
+/// Whether the HUD and menus use their compact sizes, see [`LayoutScale`].
+#[derive(
+    Resource, Component, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+pub enum LayoutMode {
+    /// Compact on small windows only.
+    #[default]
+    Auto,
+    Regular,
+    Compact,
+}
+
+impl LayoutMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            LayoutMode::Auto => "Auto",
+            LayoutMode::Regular => "Regular",
+            LayoutMode::Compact => "Compact",
+        }
+    }
+}
+
+/// The sizes the HUD and menus are built with, picked from [`LayoutMode`] and the window
+/// height. Screens read it when they're set up.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutScale {
+    #[default]
+    Regular,
+    Compact,
+}
+
+impl LayoutScale {
+    /// Scales a font size or length designed for the regular layout.
+    pub fn size(self, regular: f32) -> f32 {
+        match self {
+            LayoutScale::Regular => regular,
+            LayoutScale::Compact => (regular * COMPACT_SCALE).round(),
+        }
+    }
+
+    /// [`LayoutScale::size`] in pixels.
+    pub fn px(self, regular: f32) -> Val {
+        Val::Px(self.size(regular))
+    }
+}
+
+/// How numbers and amounts are written, see [`crate::format`], and which way the UI
+/// reads.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[reflect(Resource)]
+pub enum Locale {
+    #[default]
+    En,
+    Fr,
+    De,
+    /// English text in a right-to-left layout, to try out the mirrored UI without a
+    /// translation.
+    PseudoRtl,
+}
+
+impl Locale {
+    pub fn direction(self) -> TextDirection {
+        match self {
+            Locale::En | Locale::Fr | Locale::De => TextDirection::LeftToRight,
+            Locale::PseudoRtl => TextDirection::RightToLeft,
+        }
+    }
+}
+
+/// Which way a locale reads. Layouts are written left to right and mirrored through these
+/// helpers, so right-to-left locales get the start of a row, the leading margin and so on
+/// on the right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextDirection {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+}
+
+impl TextDirection {
+    pub fn is_rtl(self) -> bool {
+        self == TextDirection::RightToLeft
+    }
+
+    /// The direction of a row that starts at the reading start.
+    pub fn row(self) -> FlexDirection {
+        if self.is_rtl() {
+            FlexDirection::RowReverse
+        } else {
+            FlexDirection::Row
+        }
+    }
+
+    /// Mirrors the placement of a row's content along an axis that isn't reversed.
+    pub fn justify(self, justify: JustifyContent) -> JustifyContent {
+        match (self, justify) {
+            (TextDirection::RightToLeft, JustifyContent::Start) => JustifyContent::End,
+            (TextDirection::RightToLeft, JustifyContent::End) => JustifyContent::Start,
+            (TextDirection::RightToLeft, JustifyContent::FlexStart) => JustifyContent::FlexEnd,
+            (TextDirection::RightToLeft, JustifyContent::FlexEnd) => JustifyContent::FlexStart,
+            _ => justify,
+        }
+    }
+
+    /// Mirrors the horizontal alignment of a column's items.
+    pub fn align(self, align: AlignItems) -> AlignItems {
+        match (self, align) {
+            (TextDirection::RightToLeft, AlignItems::Start) => AlignItems::End,
+            (TextDirection::RightToLeft, AlignItems::End) => AlignItems::Start,
+            (TextDirection::RightToLeft, AlignItems::FlexStart) => AlignItems::FlexEnd,
+            (TextDirection::RightToLeft, AlignItems::FlexEnd) => AlignItems::FlexStart,
+            _ => align,
+        }
+    }
+
+    /// Swaps the left and right sides of margins and paddings.
+    pub fn rect(self, rect: UiRect) -> UiRect {
+        if self.is_rtl() {
+            UiRect {
+                left: rect.right,
+                right: rect.left,
+                ..rect
+            }
+        } else {
+            rect
+        }
+    }
+
+    /// How wrapped text lines up.
+    pub fn text(self) -> TextLayout {
+        TextLayout::new_with_justify(if self.is_rtl() {
+            JustifyText::Right
+        } else {
+            JustifyText::Left
+        })
+    }
+}
+
+/// Replaces UI animations with instant changes.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReducedMotion(pub bool);
+
+/// Makes destructive buttons need holding even with a mouse, see
+/// [`crate::screens::hold_confirm`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AlwaysHoldToConfirm(pub bool);
+
+/// The attract sequence shown when the game sits idle outside a run, see
+/// [`crate::screens::attract`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttractMode {
+    pub enabled: bool,
+    /// Minutes without input before it starts.
+    pub idle_minutes: u32,
+}
+
+impl Default for AttractMode {
+    fn default() -> Self {
+        AttractMode {
+            enabled: true,
+            idle_minutes: 5,
+        }
+    }
+}
+
+impl AttractMode {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(u64::from(self.idle_minutes.max(1)) * 60)
+    }
+}
+
+/// Dimming the screen when it sits idle, against burn-in, see
+/// [`crate::screens::idle_dimming`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdleDimming {
+    pub enabled: bool,
+    /// Minutes without input before it starts.
+    pub idle_minutes: u32,
+}
+
+impl Default for IdleDimming {
+    fn default() -> Self {
+        IdleDimming {
+            enabled: false,
+            idle_minutes: 3,
+        }
+    }
+}
+
+impl IdleDimming {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(u64::from(self.idle_minutes.max(1)) * 60)
+    }
+}
+
+/// A full set of setting values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    pub volume: Volume,
+    pub display: DisplayMode,
+    pub clock: ClockStyle,
+    pub hud_layout: HudLayout,
+    pub money_feedback: MoneyFeedback,
+    pub safe_area: SafeArea,
+    pub layout_mode: LayoutMode,
+    pub text_readability: TextReadability,
+    pub rumble: Rumble,
+    pub photo_hud: PhotoHud,
+    pub content_filters: ContentFilters,
+}
+
+/// The settings being edited on the settings screens.
+#[derive(Resource, Debug)]
+pub struct PendingSettings {
+    /// What the player has picked so far.
+    pub staged: Settings,
+    /// The values in effect when editing started, or at the last Apply.
+    pub committed: Settings,
+}
+
+impl PendingSettings {
+    pub fn new(current: Settings) -> Self {
+        PendingSettings {
+            staged: current,
+            committed: current,
+        }
+    }
+}
+
+/// The settings kept between sessions.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedSettings {
+    pub muted: bool,
+    pub reduced_motion: bool,
+    pub always_hold_to_confirm: bool,
+    pub attract_mode: AttractMode,
+    pub idle_dimming: IdleDimming,
+    pub clock_style: ClockStyle,
+    pub hud_layout: HudLayout,
+    pub money_feedback: MoneyFeedback,
+    pub safe_area: SafeArea,
+    pub layout_mode: LayoutMode,
+    pub text_readability: TextReadability,
+    pub rumble: Rumble,
+    pub photo_hud: PhotoHud,
+    pub content_filters: ContentFilters,
+    /// Whether the first week's checklist was finished or skipped.
+    pub onboarding_done: bool,
+}
+
+impl SavedSettings {
+    /// Reads the profile's [`SETTINGS_FILE`], falling back to the defaults if it's missing
+    /// or broken, see [`load_ron`].
+    pub fn load(profile: &ProfilePaths, corrupt: &mut CorruptFiles) -> Self {
+        load_ron(&profile.file(SETTINGS_FILE), corrupt).unwrap_or_default()
+    }
+
+    /// [`SavedSettings::load`] for the active profile, for plugins and exclusive systems.
+    pub fn load_active(world: &mut World) -> Self {
+        world.resource_scope(|world, mut corrupt: Mut<CorruptFiles>| {
+            Self::load(world.resource::<ProfilePaths>(), &mut corrupt)
+        })
+    }
+
+    /// Writes the profile's [`SETTINGS_FILE`] in the background, see [`crate::save_file`].
+    pub fn save(&self, profile: &ProfilePaths, saves: &mut Saves) {
+        saves.save_ron(profile.file(SETTINGS_FILE), self.clone());
+    }
+
+    /// Puts these settings in effect, except the ones other plugins load themselves.
+    fn apply(self, world: &mut World) {
+        world.insert_resource(ReducedMotion(self.reduced_motion));
+        world.insert_resource(AlwaysHoldToConfirm(self.always_hold_to_confirm));
+        world.insert_resource(self.attract_mode);
+        world.insert_resource(self.idle_dimming);
+        world.insert_resource(self.clock_style);
+        world.insert_resource(self.hud_layout);
+        world.insert_resource(self.money_feedback);
+        world.insert_resource(self.safe_area);
+        world.insert_resource(self.layout_mode);
+        world.insert_resource(self.text_readability);
+        world.insert_resource(self.rumble);
+        world.insert_resource(self.photo_hud);
+        world.insert_resource(self.content_filters);
+    }
+}
+
+pub fn settings_plugin(app: &mut App) {
+    SavedSettings::load_active(app.world_mut()).apply(app.world_mut());
+    app.init_resource::<DisplayMode>()
+        .register_type::<Locale>()
+        .init_resource::<Locale>()
+        .init_resource::<LayoutScale>()
+        .add_systems(PreUpdate, load_profile_settings.run_if(profile_switched))
+        .add_systems(
+            Update,
+            (
+                apply_display_mode.run_if(resource_changed::<DisplayMode>),
+                pick_layout_scale
+                    .run_if(resource_changed::<LayoutMode>.or(on_event::<WindowResized>)),
+            ),
+        );
+}
+
+fn load_profile_settings(world: &mut World) {
+    SavedSettings::load_active(world).apply(world);
+}
+
+fn apply_display_mode(
+    mode: Res<DisplayMode>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    for mut window in &mut windows {
+        window.mode = match *mode {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Fullscreen => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+        };
+    }
+}
+
+fn pick_layout_scale(
+    mode: Res<LayoutMode>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut scale: ResMut<LayoutScale>,
+) {
+    let Ok(window) = window.single() else {
+        return;
+    };
+    let compact = match *mode {
+        LayoutMode::Auto => window.height() < COMPACT_BELOW_HEIGHT,
+        LayoutMode::Regular => false,
+        LayoutMode::Compact => true,
+    };
+    scale.set_if_neq(if compact {
+        LayoutScale::Compact
+    } else {
+        LayoutScale::Regular
+    });
+}
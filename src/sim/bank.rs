@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+use super::economy::Money;
+use crate::AppState;
+use crate::format;
+use crate::screens::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use crate::settings::Locale;
+use crate::toast::Toast;
+
+/// Money kept in the bank instead of carried around, where thieves can't reach it.
+#[derive(Resource, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Bank {
+    pub savings: u32,
+}
+
+impl Bank {
+    /// Pays `amount` from cash, topping up from savings if cash runs short. Returns
+    /// `false` without paying anything if both together can't cover it.
+    pub fn pay(&mut self, money: &mut Money, amount: u32) -> bool {
+        let from_savings = amount.saturating_sub(money.amount);
+        if from_savings > self.savings {
+            return false;
+        }
+        self.savings -= from_savings;
+        money.amount -= amount - from_savings;
+        true
+    }
+}
+
+pub fn bank_plugin(app: &mut App) {
+    app.register_type::<Bank>()
+        .init_resource::<Bank>()
+        .add_systems(Update, offer_bank_actions.in_set(ActionSet::Offer))
+        .add_systems(Update, handle_withdrawals.run_if(in_state(AppState::Game)));
+}
+
+fn offer_bank_actions(money: Res<Money>, bank: Res<Bank>, mut actions: ResMut<AvailableActions>) {
+    if money.amount > 0 {
+        actions.offer(GameAction::DepositCash);
+    }
+    if bank.savings > 0 {
+        actions.offer(GameAction::WithdrawSavings);
+    }
+}
+
+fn handle_withdrawals(
+    mut pressed: EventReader<ActionPressed>,
+    mut money: ResMut<Money>,
+    mut bank: ResMut<Bank>,
+    mut toasts: EventWriter<Toast>,
+    locale: Res<Locale>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        if *action == GameAction::WithdrawSavings && bank.savings > 0 {
+            toasts.write(Toast::new(format!(
+                "Withdrew {}",
+                format::money(bank.savings, *locale)
+            )));
+            money.amount += bank.savings;
+            bank.savings = 0;
+        }
+    }
+}
@@ -0,0 +1,534 @@
+//! Rent comes as a bill: each week's rent is mailed [`BILL_NOTICE_DAYS`] before it's due,
+//! and can be paid from the inbox or the action panel until then. With autopay on, a bill
+//! still open on its due day is paid from cash and savings if they cover it, and retried
+//! every day after. A week with nothing to pay, like one in a rent-free home, gets no
+//! bill.
+//!
+//! A bill that isn't paid on its due day is overdue: it costs its late fee every day it
+//! stays open and breaks the on-time streak the landlord counts, see
+//! [`RentTerms::payments_on_time`]. Once its grace days are up the player is evicted,
+//! which ends the run.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    SimSet,
+    bank::Bank,
+    clock::{Clock, HOURS_PER_DAY, day_started},
+    economy::{
+        GameOverReason, HomeArea, Money, RENT_PERIOD_DAYS, RentCharge, RentHistory, RentSchedule,
+        rent_due,
+    },
+    landlord::{RentIncreases, RentTerms},
+    ledger::{Category, Ledger, Transaction},
+    mail::{Inbox, Mail},
+    roommate::Roommate,
+};
+use crate::AppState;
+use crate::format;
+use crate::screens::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use crate::screens::nav::{ScreenNav, TransitionCause};
+use crate::settings::Locale;
+use crate::toast::Toast;
+
+/// Days before its due day that a bill arrives.
+pub const BILL_NOTICE_DAYS: u32 = 3;
+/// Days a rent bill can stay overdue before the eviction.
+pub const RENT_GRACE_DAYS: u32 = 3;
+/// The late fee, charged every day a bill is overdue, as a percentage of the bill.
+pub const LATE_FEE_PERCENT: u32 = 10;
+
+/// A rent payment owed.
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct Bill {
+    /// The rent, with how it was worked out.
+    pub charge: RentCharge,
+    pub issued_day: u32,
+    /// The bill has to be paid before this day starts, or by autopay on it.
+    pub due_day: u32,
+    /// Days the bill can stay overdue before the eviction.
+    pub grace_days: u32,
+    /// Added every day the bill is overdue.
+    pub late_fee: u32,
+    /// The late fees so far.
+    pub fees: u32,
+}
+
+impl Bill {
+    pub fn new(charge: RentCharge, issued_day: u32, due_day: u32) -> Self {
+        Bill {
+            late_fee: (charge.net * LATE_FEE_PERCENT).div_ceil(100),
+            charge,
+            issued_day,
+            due_day,
+            grace_days: RENT_GRACE_DAYS,
+            fees: 0,
+        }
+    }
+
+    /// The rent and the late fees so far.
+    pub fn owed(&self) -> u32 {
+        self.charge.net + self.fees
+    }
+
+    /// Whether the bill, still open on `day`, has missed its due day. Bills are settled
+    /// at the start of the day, so one still open on its due day already has.
+    pub fn is_overdue(&self, day: u32) -> bool {
+        day >= self.due_day
+    }
+
+    /// The day the player is evicted if the bill is still open.
+    pub fn eviction_day(&self) -> u32 {
+        self.due_day + self.grace_days
+    }
+}
+
+/// The rent bills of the run.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Bills {
+    /// Bills not paid yet, oldest first.
+    pub outstanding: Vec<Bill>,
+    /// Pay bills on their due day without asking.
+    pub autopay: bool,
+    /// The due day of the last bill sent, so each is sent once.
+    pub last_issued: u32,
+}
+
+impl Default for Bills {
+    fn default() -> Self {
+        Bills {
+            outstanding: Vec::new(),
+            autopay: true,
+            last_issued: 0,
+        }
+    }
+}
+
+impl Bills {
+    /// The bill due soonest.
+    pub fn next(&self) -> Option<&Bill> {
+        self.outstanding.first()
+    }
+}
+
+pub fn bills_plugin(app: &mut App) {
+    app.register_type::<Bills>()
+        .init_resource::<Bills>()
+        .add_systems(
+            FixedUpdate,
+            (issue_bills, settle_bills)
+                .chain()
+                .in_set(SimSet::Economy)
+                .run_if(day_started),
+        )
+        .add_systems(Update, offer_bill_actions.in_set(ActionSet::Offer))
+        .add_systems(Update, pay_bills.run_if(in_state(AppState::Game)));
+}
+
+/// Books a bill paid on `day` like the rent it is, with its late fees on their own. A bill
+/// paid before it cost any fees was paid on time.
+fn record_payment(
+    mut bill: Bill,
+    day: u32,
+    terms: &mut RentTerms,
+    history: &mut RentHistory,
+    transactions: &mut EventWriter<Transaction>,
+    locale: Locale,
+) {
+    let owed = bill.owed();
+    info!("Paid ${owed} rent for {:?}", bill.charge.area);
+    transactions.write(Transaction::Expense(Category::Rent, bill.charge.net));
+    bill.charge.day = day;
+    if bill.fees > 0 {
+        transactions.write(Transaction::Expense(Category::LateFees, bill.fees));
+        bill.charge
+            .modifiers
+            .push(format!("Late fees {}", format::money(bill.fees, locale)));
+        bill.charge.net = owed;
+    } else {
+        terms.payments_on_time += 1;
+    }
+    history.0.push(bill.charge);
+}
+
+pub fn issue_bills(
+    clock: Res<Clock>,
+    schedule: Res<RentSchedule>,
+    (home, increases, terms, roommate): (
+        Res<HomeArea>,
+        Res<RentIncreases>,
+        Res<RentTerms>,
+        Res<Roommate>,
+    ),
+    ledger: Res<Ledger>,
+    mut bills: ResMut<Bills>,
+    mut inbox: ResMut<Inbox>,
+    locale: Res<Locale>,
+) {
+    let due_day = schedule.next_due / HOURS_PER_DAY;
+    if bills.last_issued >= due_day || due_day > clock.day + BILL_NOTICE_DAYS {
+        return;
+    }
+    bills.last_issued = due_day;
+    let charge = rent_due(
+        &home,
+        &increases,
+        &terms,
+        &roommate,
+        &clock,
+        ledger.current.month,
+    );
+    // Nothing to pay, so nothing that could go overdue.
+    if charge.net == 0 {
+        info!("No rent due on day {due_day} for {:?}", charge.area);
+        return;
+    }
+    let bill = Bill::new(charge, clock.day, due_day);
+    let paying = if bills.autopay {
+        "Autopay will take it from your cash and savings on the day if they cover it."
+    } else {
+        "Pay it from the inbox or the action panel before then."
+    };
+    let body = format!(
+        "Your rent of {} for the {} is due on day {due_day}. {paying} \
+         Late rent costs {} a day, and after {} days you'll be evicted.",
+        format::money(bill.charge.net, *locale),
+        bill.charge.area.label(),
+        format::money(bill.late_fee, *locale),
+        bill.grace_days,
+    );
+    inbox.send_mail(
+        clock.day,
+        Mail::new("Rent bill", body).with_action("Pay rent", GameAction::PayRent),
+    );
+    bills.outstanding.push(bill);
+}
+
+/// Runs at the start of every day: bills due pay themselves with autopay, and the rest
+/// get their late fee or end the run.
+pub fn settle_bills(
+    mut commands: Commands,
+    clock: Res<Clock>,
+    mut schedule: ResMut<RentSchedule>,
+    mut bills: ResMut<Bills>,
+    (mut money, mut bank): (ResMut<Money>, ResMut<Bank>),
+    (mut terms, mut history): (ResMut<RentTerms>, ResMut<RentHistory>),
+    mut inbox: ResMut<Inbox>,
+    mut transactions: EventWriter<Transaction>,
+    mut nav: ScreenNav,
+    locale: Res<Locale>,
+) {
+    if schedule.hours_until_due(&clock) == 0 {
+        schedule.next_due += RENT_PERIOD_DAYS * HOURS_PER_DAY;
+    }
+    let autopay = bills.autopay;
+    let mut outstanding = Vec::new();
+    for mut bill in std::mem::take(&mut bills.outstanding) {
+        if clock.day < bill.due_day {
+            outstanding.push(bill);
+            continue;
+        }
+        // A bill for nothing, like one from before rent-free homes went unbilled, settles
+        // itself.
+        if (autopay || bill.owed() == 0) && bank.pay(&mut money, bill.owed()) {
+            record_payment(
+                bill,
+                clock.day,
+                &mut terms,
+                &mut history,
+                &mut transactions,
+                *locale,
+            );
+            continue;
+        }
+        if clock.day >= bill.eviction_day() {
+            info!(
+                "Evicted from {:?} over ${} rent",
+                bill.charge.area,
+                bill.owed()
+            );
+            commands.insert_resource(GameOverReason::Evicted);
+            nav.set_app(AppState::GameOver, TransitionCause::Bankruptcy);
+            outstanding.push(bill);
+            continue;
+        }
+        if clock.day == bill.due_day {
+            info!(
+                "Couldn't pay ${} rent for {:?}",
+                bill.owed(),
+                bill.charge.area
+            );
+            terms.payments_on_time = 0;
+            let body = format!(
+                "Your rent of {} is overdue. It costs {} more every day it stays unpaid, \
+                 and on day {} you'll be evicted.",
+                format::money(bill.owed(), *locale),
+                format::money(bill.late_fee, *locale),
+                bill.eviction_day(),
+            );
+            inbox.send_mail(
+                clock.day,
+                Mail::new("Rent overdue", body)
+                    .critical()
+                    .with_action("Pay rent", GameAction::PayRent),
+            );
+        }
+        bill.fees += bill.late_fee;
+        outstanding.push(bill);
+    }
+    bills.outstanding = outstanding;
+}
+
+fn offer_bill_actions(
+    bills: Res<Bills>,
+    money: Res<Money>,
+    bank: Res<Bank>,
+    mut actions: ResMut<AvailableActions>,
+    locale: Res<Locale>,
+) {
+    if let Some(bill) = bills.next() {
+        let label = format!("Pay rent ({})", format::money(bill.owed(), *locale));
+        if money.amount + bank.savings >= bill.owed() {
+            actions.offer_labeled(GameAction::PayRent, label);
+        } else {
+            actions.offer_blocked(GameAction::PayRent, label, "Not enough cash and savings");
+        }
+    }
+    let label = if bills.autopay {
+        "Turn off rent autopay"
+    } else {
+        "Turn on rent autopay"
+    };
+    actions.offer_labeled(GameAction::ToggleAutopay, label);
+}
+
+fn pay_bills(
+    mut pressed: EventReader<ActionPressed>,
+    clock: Res<Clock>,
+    mut bills: ResMut<Bills>,
+    (mut money, mut bank): (ResMut<Money>, ResMut<Bank>),
+    (mut terms, mut history): (ResMut<RentTerms>, ResMut<RentHistory>),
+    mut transactions: EventWriter<Transaction>,
+    mut toasts: EventWriter<Toast>,
+    locale: Res<Locale>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        match action {
+            GameAction::PayRent => {
+                let Some(owed) = bills.next().map(Bill::owed) else {
+                    continue;
+                };
+                if bank.pay(&mut money, owed) {
+                    let bill = bills.outstanding.remove(0);
+                    record_payment(
+                        bill,
+                        clock.day,
+                        &mut terms,
+                        &mut history,
+                        &mut transactions,
+                        *locale,
+                    );
+                    toasts.write(Toast::new(format!(
+                        "Paid {} rent",
+                        format::money(owed, *locale)
+                    )));
+                } else {
+                    toasts.write(Toast::new("You can't cover the rent yet"));
+                }
+            }
+            GameAction::ToggleAutopay => {
+                bills.autopay = !bills.autopay;
+                toasts.write(Toast::new(if bills.autopay {
+                    "Rent autopay is on"
+                } else {
+                    "Rent autopay is off"
+                }));
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+    use crate::screens::menu::MenuState;
+    use crate::screens::nav::ScreenChanged;
+    use crate::sim::{
+        Dirty,
+        clock::clock_plugin,
+        economy::Area,
+        quicksave::{restore_run, run_snapshot},
+    };
+    use crate::testing::sleep_until;
+
+    const RENT: u32 = 500;
+
+    fn bills_app(cash: u32, autopay: bool, due_day: u32) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .insert_state(AppState::Game)
+            .init_state::<MenuState>()
+            .add_plugins(clock_plugin)
+            .add_event::<Transaction>()
+            .add_event::<ScreenChanged>()
+            .add_event::<ActionPressed>()
+            .add_event::<Toast>()
+            .insert_resource(Money { amount: cash })
+            .init_resource::<Bank>()
+            .init_resource::<RentSchedule>()
+            .init_resource::<RentTerms>()
+            .init_resource::<RentHistory>()
+            .init_resource::<Inbox>()
+            .init_resource::<Locale>()
+            .insert_resource(Bills {
+                outstanding: vec![Bill::new(charge(), 1, due_day)],
+                autopay,
+                last_issued: due_day,
+            })
+            .add_systems(
+                FixedUpdate,
+                settle_bills.run_if(day_started).after(SimSet::Clock),
+            )
+            .add_systems(Update, pay_bills.run_if(in_state(AppState::Game)));
+        app.update();
+        app
+    }
+
+    fn charge() -> RentCharge {
+        RentCharge {
+            day: 1,
+            area: Area::DeadbeatArea,
+            gross: RENT,
+            modifiers: Vec::new(),
+            net: RENT,
+        }
+    }
+
+    fn bills(app: &App) -> &Bills {
+        app.world().resource::<Bills>()
+    }
+
+    fn cash(app: &App) -> u32 {
+        app.world().resource::<Money>().amount
+    }
+
+    #[test]
+    fn paying_early_counts_as_on_time() {
+        let mut app = bills_app(2000, false, 3);
+        app.world_mut()
+            .send_event(ActionPressed(GameAction::PayRent));
+        app.update();
+        assert!(bills(&app).outstanding.is_empty());
+        assert_eq!(cash(&app), 2000 - RENT);
+        assert_eq!(app.world().resource::<RentTerms>().payments_on_time, 1);
+        assert_eq!(app.world().resource::<RentHistory>().0, [charge()]);
+
+        sleep_until(&mut app, 4);
+        assert_eq!(cash(&app), 2000 - RENT);
+    }
+
+    #[test]
+    fn autopay_without_the_funds_leaves_the_bill_overdue() {
+        let mut app = bills_app(100, true, 2);
+        app.world_mut().resource_mut::<RentTerms>().payments_on_time = 4;
+        sleep_until(&mut app, 2);
+        let bill = &bills(&app).outstanding[0];
+        assert_eq!(bill.fees, bill.late_fee);
+        assert_eq!(cash(&app), 100);
+        assert_eq!(app.world().resource::<RentTerms>().payments_on_time, 0);
+
+        // Autopay tries again the next day, fees and all.
+        app.world_mut().resource_mut::<Money>().amount = 1000;
+        sleep_until(&mut app, 3);
+        assert!(bills(&app).outstanding.is_empty());
+        assert_eq!(cash(&app), 1000 - RENT - RENT / 10);
+        assert_eq!(app.world().resource::<RentTerms>().payments_on_time, 0);
+        // Booked on the day it was paid, not the day it was sent.
+        let paid = &app.world().resource::<RentHistory>().0;
+        assert_eq!((paid.len(), paid[0].day), (1, 3));
+    }
+
+    #[test]
+    fn late_fees_add_up_over_a_long_sleep_until_the_eviction() {
+        let mut app = bills_app(0, false, 2);
+        let late_fee = bills(&app).outstanding[0].late_fee;
+        sleep_until(&mut app, 4);
+        assert_eq!(bills(&app).outstanding[0].fees, 3 * late_fee);
+        assert!(!app.world().contains_resource::<GameOverReason>());
+
+        sleep_until(&mut app, 6);
+        assert_eq!(
+            app.world().get_resource::<GameOverReason>(),
+            Some(&GameOverReason::Evicted)
+        );
+        assert_eq!(app.world().resource::<Clock>().day, 5);
+    }
+
+    #[test]
+    fn a_rent_free_home_gets_no_bill() {
+        let mut app = bills_app(0, false, 3);
+        app.insert_resource(HomeArea {
+            location: Area::RestrictedArea,
+            rent: 0,
+            scenario_rent: None,
+        })
+        .init_resource::<RentIncreases>()
+        .init_resource::<Roommate>()
+        .init_resource::<Ledger>()
+        .add_systems(
+            FixedUpdate,
+            issue_bills
+                .run_if(day_started)
+                .after(SimSet::Clock)
+                .before(settle_bills),
+        );
+        app.world_mut().resource_mut::<Bills>().outstanding.clear();
+        sleep_until(&mut app, RENT_PERIOD_DAYS + 3);
+        assert_eq!(bills(&app).last_issued, RENT_PERIOD_DAYS);
+        assert!(bills(&app).outstanding.is_empty());
+        assert!(app.world().resource::<Inbox>().messages.is_empty());
+        assert!(!app.world().contains_resource::<GameOverReason>());
+    }
+
+    #[test]
+    fn a_bill_for_nothing_settles_without_autopay() {
+        let mut app = bills_app(0, false, 2);
+        app.world_mut().resource_mut::<Bills>().outstanding[0]
+            .charge
+            .net = 0;
+        sleep_until(&mut app, 6);
+        assert!(bills(&app).outstanding.is_empty());
+        assert!(app.world().resource::<Inbox>().messages.is_empty());
+        assert!(!app.world().contains_resource::<GameOverReason>());
+        assert_eq!(app.world().resource::<RentTerms>().payments_on_time, 1);
+    }
+
+    #[test]
+    fn a_bill_is_overdue_from_its_due_day() {
+        let bill = Bill::new(charge(), 1, 3);
+        assert!(!bill.is_overdue(2));
+        assert!(bill.is_overdue(3));
+        assert!(bill.is_overdue(bill.eviction_day() + 1));
+    }
+
+    #[test]
+    fn outstanding_bills_survive_a_save() {
+        let mut app = bills_app(0, false, 3);
+        app.register_type::<Bills>().init_resource::<Dirty>();
+        app.world_mut().resource_mut::<Bills>().outstanding[0].fees = 50;
+        let saved = bills(&app).clone();
+        let world = app.world();
+        let text = run_snapshot(world)
+            .serialize(&world.resource::<AppTypeRegistry>().read())
+            .unwrap();
+
+        *app.world_mut().resource_mut::<Bills>() = Bills::default();
+        restore_run(app.world_mut(), &text);
+        assert_eq!(*bills(&app), saved);
+    }
+}
@@ -0,0 +1,394 @@
+//! Things to eat and drink from the cafés in the business district, and the timed effects
+//! some of them leave behind.
+//!
+//! Coffee gives energy straight away and a small dip in happiness when it wears off. A
+//! meal gives more energy and nothing else. An energy drink makes work shifts take less
+//! energy for a few hours.
+//!
+//! Each effect is a [`Buff`] in [`Buffs`], on a [`SimTimer`] that ends at an absolute hour
+//! of the [`Clock`]. So a buff lasts exactly as long on screen, at any sim speed, across
+//! skipped hours and in a save. Only so many of a kind can be active at once. When several
+//! buffs change the same cost, their discounts add up first, are capped, and then apply
+//! once to the whole cost, rounding up.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    SimSet,
+    calendar::Calendar,
+    clock::{Clock, SimTimer},
+    cost_of_living::Prices,
+    economy::{Area, CurrentArea, Money},
+    ledger::{Category, Transaction},
+    needs::{Energy, Happiness, MAX_ENERGY},
+};
+use crate::AppState;
+use crate::format;
+use crate::screens::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use crate::settings::Locale;
+use crate::toast::Toast;
+
+const COFFEE_PRICE: u32 = 5;
+const COFFEE_ENERGY: u32 = 20;
+const COFFEE_HOURS: u32 = 4;
+const COFFEE_CRASH_HAPPINESS: i32 = -3;
+const MEAL_PRICE: u32 = 15;
+const MEAL_ENERGY: u32 = 35;
+const ENERGY_DRINK_PRICE: u32 = 8;
+const ENERGY_DRINK_HOURS: u32 = 6;
+/// Percent off the energy a shift takes.
+const ENERGY_DRINK_SHIFT_DISCOUNT: i32 = 25;
+/// However many buffs add up, a shift never gets cheaper than this, in percent off.
+const MAX_SHIFT_DISCOUNT: i32 = 50;
+
+/// Something the player can buy and consume on the spot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Reflect, Serialize, Deserialize)]
+pub enum Consumable {
+    Coffee,
+    Meal,
+    EnergyDrink,
+}
+
+impl Consumable {
+    const ALL: [Consumable; 3] = [
+        Consumable::Coffee,
+        Consumable::Meal,
+        Consumable::EnergyDrink,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Consumable::Coffee => "Coffee",
+            Consumable::Meal => "Meal",
+            Consumable::EnergyDrink => "Energy drink",
+        }
+    }
+
+    fn base_price(self) -> u32 {
+        match self {
+            Consumable::Coffee => COFFEE_PRICE,
+            Consumable::Meal => MEAL_PRICE,
+            Consumable::EnergyDrink => ENERGY_DRINK_PRICE,
+        }
+    }
+
+    /// Energy given right away.
+    fn energy(self) -> u32 {
+        match self {
+            Consumable::Coffee => COFFEE_ENERGY,
+            Consumable::Meal => MEAL_ENERGY,
+            Consumable::EnergyDrink => 0,
+        }
+    }
+
+    /// The buff left behind, if any, starting at `now`.
+    fn buff(self, now: u32) -> Option<Buff> {
+        match self {
+            Consumable::Coffee => Some(Buff::new(
+                BuffKind::Caffeine,
+                COFFEE_CRASH_HAPPINESS,
+                now,
+                COFFEE_HOURS,
+            )),
+            Consumable::Meal => None,
+            Consumable::EnergyDrink => Some(Buff::new(
+                BuffKind::ShiftEnergy,
+                ENERGY_DRINK_SHIFT_DISCOUNT,
+                now,
+                ENERGY_DRINK_HOURS,
+            )),
+        }
+    }
+
+    fn toast(self) -> &'static str {
+        match self {
+            Consumable::Coffee => "Had a coffee",
+            Consumable::Meal => "Had a meal",
+            Consumable::EnergyDrink => "Downed an energy drink",
+        }
+    }
+
+    fn label(self, price: u32, base: u32, locale: Locale) -> String {
+        let effect = match self {
+            Consumable::EnergyDrink => {
+                format!("shifts -{ENERGY_DRINK_SHIFT_DISCOUNT}% energy for {ENERGY_DRINK_HOURS}h")
+            }
+            _ => format!("+{} energy", self.energy()),
+        };
+        format!(
+            "{}: -{}, {effect}",
+            self.name(),
+            format::price(price, base, locale)
+        )
+    }
+}
+
+/// What a buff does while it lasts, or when it ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Reflect, Serialize, Deserialize)]
+pub enum BuffKind {
+    /// Changes happiness by the magnitude when it wears off.
+    Caffeine,
+    /// Takes the magnitude, in percent, off the energy a work shift takes.
+    ShiftEnergy,
+}
+
+impl BuffKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            BuffKind::Caffeine => "Caffeine",
+            BuffKind::ShiftEnergy => "Energized",
+        }
+    }
+
+    /// How many buffs of this kind can be active at once.
+    pub fn max_stacks(self) -> usize {
+        match self {
+            BuffKind::Caffeine => 2,
+            BuffKind::ShiftEnergy => 1,
+        }
+    }
+
+    /// What a buff of this kind does, for the HUD.
+    pub fn describe(self, magnitude: i32) -> String {
+        match self {
+            BuffKind::Caffeine => format!("{magnitude} happiness when it wears off"),
+            BuffKind::ShiftEnergy => format!("Shifts take {magnitude}% less energy"),
+        }
+    }
+}
+
+/// A timed modifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct Buff {
+    pub kind: BuffKind,
+    pub magnitude: i32,
+    pub timer: SimTimer,
+}
+
+impl Buff {
+    pub fn new(kind: BuffKind, magnitude: i32, now: u32, hours: u32) -> Self {
+        Buff {
+            kind,
+            magnitude,
+            timer: SimTimer::new(now, hours),
+        }
+    }
+
+    pub fn is_active(&self, now: u32) -> bool {
+        !self.timer.finished(now)
+    }
+
+    pub fn hours_left(&self, now: u32) -> u32 {
+        self.timer.hours_left(now)
+    }
+
+    /// How much of the buff is left, from 1 when it starts to 0 when it ends.
+    pub fn fraction_left(&self, now: u32) -> f32 {
+        self.timer.fraction_left(now)
+    }
+}
+
+/// The buffs on the player, in the order they were gained.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Buffs(pub Vec<Buff>);
+
+impl Buffs {
+    pub fn active(&self, now: u32) -> impl Iterator<Item = &Buff> {
+        self.0.iter().filter(move |buff| buff.is_active(now))
+    }
+
+    /// Whether another buff of `kind` would go over its cap.
+    pub fn is_capped(&self, kind: BuffKind, now: u32) -> bool {
+        self.active(now).filter(|buff| buff.kind == kind).count() >= kind.max_stacks()
+    }
+
+    /// Adds `buff` unless its kind is at the cap. Returns whether it was added.
+    pub fn add(&mut self, buff: Buff, now: u32) -> bool {
+        if self.is_capped(buff.kind, now) {
+            return false;
+        }
+        self.0.push(buff);
+        true
+    }
+
+    /// The energy a shift costing `base` takes with the active buffs.
+    pub fn shift_energy_cost(&self, base: u32, now: u32) -> u32 {
+        let discount: i32 = self
+            .active(now)
+            .filter(|buff| buff.kind == BuffKind::ShiftEnergy)
+            .map(|buff| buff.magnitude)
+            .sum();
+        let percent = 100 - discount.clamp(0, MAX_SHIFT_DISCOUNT) as u32;
+        (base * percent).div_ceil(100)
+    }
+}
+
+pub fn buffs_plugin(app: &mut App) {
+    app.register_type::<Buffs>()
+        .init_resource::<Buffs>()
+        .add_systems(FixedUpdate, expire_buffs.in_set(SimSet::Economy))
+        .add_systems(Update, offer_consumables.in_set(ActionSet::Offer))
+        .add_systems(Update, buy_consumables.run_if(in_state(AppState::Game)));
+}
+
+// Buffs whose hour has come end here, even when the clock skipped past it.
+fn expire_buffs(
+    clock: Res<Clock>,
+    mut buffs: ResMut<Buffs>,
+    mut happiness: ResMut<Happiness>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let now = clock.total_hours();
+    if buffs.active(now).count() == buffs.0.len() {
+        return;
+    }
+    buffs.0.retain(|buff| {
+        if buff.is_active(now) {
+            return true;
+        }
+        if buff.kind == BuffKind::Caffeine {
+            happiness.change(buff.magnitude);
+            toasts.write(Toast::new("The coffee wore off"));
+        }
+        false
+    });
+}
+
+fn offer_consumables(
+    area: Res<CurrentArea>,
+    clock: Res<Clock>,
+    calendar: Res<Calendar>,
+    money: Res<Money>,
+    buffs: Res<Buffs>,
+    prices: Prices,
+    mut actions: ResMut<AvailableActions>,
+    locale: Res<Locale>,
+) {
+    if area.0 != Area::BusinessDistrict {
+        return;
+    }
+    let now = clock.total_hours();
+    for consumable in Consumable::ALL {
+        let action = GameAction::Buy(consumable);
+        let price = prices.price(consumable.base_price());
+        let base = prices.without_cost_of_living(consumable.base_price());
+        let label = consumable.label(price, base, *locale);
+        if let Some(reason) = calendar.closed_reason(clock.day) {
+            actions.offer_blocked(action, label, reason);
+        } else if money.amount < price {
+            actions.offer_blocked(action, label, "Not enough cash");
+        } else if let Some(buff) = consumable.buff(now)
+            && buffs.is_capped(buff.kind, now)
+        {
+            actions.offer_blocked(
+                action,
+                label,
+                format!("No more than {} at a time", buff.kind.max_stacks()),
+            );
+        } else {
+            actions.offer_labeled(action, label);
+        }
+    }
+}
+
+pub fn buy_consumables(
+    mut pressed: EventReader<ActionPressed>,
+    clock: Res<Clock>,
+    calendar: Res<Calendar>,
+    prices: Prices,
+    mut money: ResMut<Money>,
+    mut energy: ResMut<Energy>,
+    mut buffs: ResMut<Buffs>,
+    mut toasts: EventWriter<Toast>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    let now = clock.total_hours();
+    for ActionPressed(action) in pressed.read() {
+        let GameAction::Buy(consumable) = *action else {
+            continue;
+        };
+        let price = prices.price(consumable.base_price());
+        if money.amount < price || calendar.holiday(clock.day).is_some() {
+            continue;
+        }
+        if let Some(buff) = consumable.buff(now)
+            && !buffs.add(buff, now)
+        {
+            continue;
+        }
+        money.amount -= price;
+        transactions.write(Transaction::Expense(Category::Food, price));
+        energy.0 = (energy.0 + consumable.energy()).min(MAX_ENERGY);
+        toasts.write(Toast::new(consumable.toast()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u32 = 100;
+
+    fn shift_buffs(magnitudes: &[i32]) -> Buffs {
+        let buffs = magnitudes
+            .iter()
+            .map(|magnitude| Buff::new(BuffKind::ShiftEnergy, *magnitude, NOW, 6))
+            .collect();
+        Buffs(buffs)
+    }
+
+    #[test]
+    fn one_discount_rounds_the_cost_up() {
+        assert_eq!(shift_buffs(&[]).shift_energy_cost(30, NOW), 30);
+        assert_eq!(shift_buffs(&[25]).shift_energy_cost(30, NOW), 23);
+        assert_eq!(shift_buffs(&[25]).shift_energy_cost(0, NOW), 0);
+    }
+
+    #[test]
+    fn discounts_add_up_before_they_apply() {
+        // 50% off once, not 25% off twice, which would leave 17.
+        assert_eq!(shift_buffs(&[25, 25]).shift_energy_cost(30, NOW), 15);
+        // 15% off, so 25.5 rounds up.
+        assert_eq!(shift_buffs(&[25, -10]).shift_energy_cost(30, NOW), 26);
+    }
+
+    #[test]
+    fn the_summed_discount_is_capped_both_ways() {
+        assert_eq!(shift_buffs(&[40, 40]).shift_energy_cost(30, NOW), 15);
+        // The cap applies to the sum, so a penalty counts against the whole of it.
+        assert_eq!(shift_buffs(&[40, 40, -20]).shift_energy_cost(30, NOW), 15);
+        assert_eq!(shift_buffs(&[40, -30]).shift_energy_cost(30, NOW), 27);
+        // A cost never goes up.
+        assert_eq!(shift_buffs(&[-20]).shift_energy_cost(30, NOW), 30);
+    }
+
+    #[test]
+    fn only_active_shift_buffs_count() {
+        let mut buffs = shift_buffs(&[25]);
+        buffs.0.push(Buff::new(
+            BuffKind::Caffeine,
+            COFFEE_CRASH_HAPPINESS,
+            NOW,
+            4,
+        ));
+        assert_eq!(buffs.shift_energy_cost(30, NOW + 5), 23);
+        assert_eq!(buffs.shift_energy_cost(30, NOW + 6), 30);
+    }
+
+    #[test]
+    fn a_kind_at_its_cap_takes_no_more_until_one_ends() {
+        let mut buffs = Buffs::default();
+        let drink = |now| Consumable::EnergyDrink.buff(now).unwrap();
+        assert!(buffs.add(drink(NOW), NOW));
+        assert!(!buffs.add(drink(NOW + 1), NOW + 1));
+        assert!(buffs.add(drink(NOW + 6), NOW + 6));
+
+        let coffee = |now| Consumable::Coffee.buff(now).unwrap();
+        assert!(buffs.add(coffee(NOW), NOW));
+        assert!(buffs.add(coffee(NOW), NOW));
+        assert!(!buffs.add(coffee(NOW), NOW));
+        assert_eq!(buffs.shift_energy_cost(40, NOW + 6), 30);
+    }
+}
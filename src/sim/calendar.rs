@@ -0,0 +1,367 @@
+//! The yearly calendar: seasons with their own weather odds, bills and little events, and
+//! fixed holidays when work stops, shops close and something good or bad happens.
+//!
+//! Everything is worked out from [`Clock::day`], so the date never drifts however the days
+//! go by.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    bank::Bank,
+    clock::{Clock, day_started},
+    content::{ContentTag, RunFilters},
+    cost_of_living::Prices,
+    economy::Money,
+    ledger::{Category, Transaction},
+    needs::Happiness,
+    rng::{GameRng, RollSet},
+};
+use crate::data::register_ron_resource;
+use crate::toast::Toast;
+
+pub const CALENDAR_PATH: &str = "data/world.calendar.ron";
+const UNPAID_UTILITIES_HAPPINESS_CHANGE: i32 = -5;
+
+/// Relative odds of each kind of weather.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct WeatherOdds {
+    pub sunny: u32,
+    pub rain: u32,
+    pub storm: u32,
+}
+
+/// Something that may happen on any day of a season.
+#[derive(Deserialize, Clone, Debug)]
+pub struct SeasonEvent {
+    /// Chance each day.
+    pub chance: f32,
+    pub happiness: i32,
+    pub message: String,
+    #[serde(default)]
+    pub tags: Vec<ContentTag>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SeasonDef {
+    pub name: String,
+    pub days: u32,
+    pub weather: WeatherOdds,
+    /// Utilities charged every day of the season, like heating in winter.
+    #[serde(default)]
+    pub utilities_per_day: u32,
+    #[serde(default)]
+    pub events: Vec<SeasonEvent>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct HolidayDef {
+    pub name: String,
+    /// Day of the year, starting at 1.
+    pub day: u32,
+    /// Money gained on the day, or lost if negative.
+    #[serde(default)]
+    pub money: i32,
+    pub message: String,
+}
+
+/// The seasons, in order from the first day of a run, and the holidays.
+#[derive(Asset, TypePath, Resource, Deserialize, Clone, Debug)]
+pub struct Calendar {
+    pub seasons: Vec<SeasonDef>,
+    pub holidays: Vec<HolidayDef>,
+}
+
+impl Calendar {
+    pub fn days_per_year(&self) -> u32 {
+        self.seasons
+            .iter()
+            .map(|season| season.days)
+            .sum::<u32>()
+            .max(1)
+    }
+
+    /// Day of the year for a [`Clock::day`], starting at 1.
+    pub fn day_of_year(&self, day: u32) -> u32 {
+        day.saturating_sub(1) % self.days_per_year() + 1
+    }
+
+    /// The season `day` falls in. The calendar needs at least one season.
+    pub fn season(&self, day: u32) -> &SeasonDef {
+        let mut day_of_year = self.day_of_year(day);
+        for season in &self.seasons {
+            if day_of_year <= season.days {
+                return season;
+            }
+            day_of_year -= season.days;
+        }
+        &self.seasons[0]
+    }
+
+    pub fn holiday(&self, day: u32) -> Option<&HolidayDef> {
+        let day_of_year = self.day_of_year(day);
+        self.holidays
+            .iter()
+            .find(|holiday| holiday.day == day_of_year)
+    }
+
+    /// Why the player can't work or shop on `day`, if it's a holiday.
+    pub fn closed_reason(&self, day: u32) -> Option<String> {
+        self.holiday(day)
+            .map(|holiday| format!("Closed for {}", holiday.name))
+    }
+}
+
+pub fn calendar_plugin(app: &mut App) {
+    register_ron_resource::<Calendar>(app, &["calendar.ron"]);
+    app.add_systems(
+        FixedUpdate,
+        (celebrate_holiday, pay_utilities, roll_season_events)
+            .run_if(day_started)
+            .in_set(RollSet::Calendar),
+    );
+}
+
+fn celebrate_holiday(
+    calendar: Res<Calendar>,
+    clock: Res<Clock>,
+    prices: Prices,
+    mut money: ResMut<Money>,
+    mut bank: ResMut<Bank>,
+    mut toasts: EventWriter<Toast>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    let Some(holiday) = calendar.holiday(clock.day) else {
+        return;
+    };
+    let amount = holiday.money.unsigned_abs();
+    if holiday.money > 0 {
+        money.amount += amount;
+        transactions.write(Transaction::Income(Category::Holidays, amount));
+    } else if holiday.money < 0 {
+        // Holiday spending grows with the prices; what the player can't afford is skipped.
+        let amount = prices.price(amount).min(money.amount + bank.savings);
+        bank.pay(&mut money, amount);
+        transactions.write(Transaction::Expense(Category::Holidays, amount));
+    }
+    toasts.write(Toast::new(format!("{}: {}", holiday.name, holiday.message)));
+}
+
+fn pay_utilities(
+    calendar: Res<Calendar>,
+    clock: Res<Clock>,
+    prices: Prices,
+    mut money: ResMut<Money>,
+    mut bank: ResMut<Bank>,
+    mut happiness: ResMut<Happiness>,
+    mut toasts: EventWriter<Toast>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    let season = calendar.season(clock.day);
+    if season.utilities_per_day == 0 {
+        return;
+    }
+    let bill = prices.price(season.utilities_per_day);
+    if bank.pay(&mut money, bill) {
+        transactions.write(Transaction::Expense(Category::Utilities, bill));
+    } else {
+        happiness.change(UNPAID_UTILITIES_HAPPINESS_CHANGE);
+        toasts.write(Toast::new(
+            "You couldn't pay the utilities and spent a cold day",
+        ));
+    }
+}
+
+fn roll_season_events(
+    calendar: Res<Calendar>,
+    clock: Res<Clock>,
+    filters: Res<RunFilters>,
+    mut rng: ResMut<GameRng>,
+    mut happiness: ResMut<Happiness>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let events = calendar.season(clock.day).events.iter();
+    for event in events.filter(|event| filters.allows(&event.tags)) {
+        if rng.chance(event.chance) {
+            happiness.change(event.happiness);
+            toasts.write(Toast::new(event.message.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::system::RunSystemOnce, state::app::StatesPlugin};
+
+    use super::*;
+    use crate::AppState;
+    use crate::settings::{ContentFilter, ContentFilters};
+    use crate::sim::{
+        SimSet,
+        catalog::{AREA_CATALOG_PATH, AreaCatalog, AreaCatalogData},
+        clock::{HOURS_PER_DAY, clock_plugin},
+        economy::{Area, HomeArea},
+        new_game_plus::PriceLevel,
+        step_ticks,
+    };
+
+    const YEARS: u32 = 4;
+    const LOTTERY: &str = "lottery ticket";
+    const BREAK_IN: &str = "broken into";
+
+    fn read<T: serde::de::DeserializeOwned>(path: &str) -> T {
+        ron::from_str(&std::fs::read_to_string(format!("assets/{path}")).unwrap()).unwrap()
+    }
+
+    /// Every season event message over a seeded run of [`YEARS`].
+    fn rolled_messages(filters: ContentFilters) -> Vec<String> {
+        let calendar: Calendar = read(CALENDAR_PATH);
+        let days = YEARS * calendar.days_per_year();
+        let mut world = World::new();
+        world.insert_resource(calendar);
+        world.insert_resource(RunFilters(filters));
+        world.insert_resource(GameRng::seeded(1));
+        world.init_resource::<Happiness>();
+        world.init_resource::<Events<Toast>>();
+        let mut messages = Vec::new();
+        for day in 1..=days {
+            world.insert_resource(Clock { day, hour: 0 });
+            world.run_system_once(roll_season_events).unwrap();
+            let mut toasts = world.resource_mut::<Events<Toast>>();
+            messages.extend(toasts.drain().map(|toast| toast.text));
+        }
+        messages
+    }
+
+    fn count(messages: &[String], text: &str) -> usize {
+        messages
+            .iter()
+            .filter(|message| message.contains(text))
+            .count()
+    }
+
+    #[test]
+    fn filtered_events_are_never_rolled() {
+        let shown = rolled_messages(ContentFilters::default());
+        assert!(count(&shown, LOTTERY) > 0 && count(&shown, BREAK_IN) > 0);
+
+        let no_gambling = rolled_messages(ContentFilters {
+            gambling: ContentFilter::Hidden,
+            ..default()
+        });
+        assert_eq!(count(&no_gambling, LOTTERY), 0);
+        assert!(count(&no_gambling, BREAK_IN) > 0);
+
+        let hidden = rolled_messages(ContentFilters {
+            gambling: ContentFilter::Hidden,
+            crime: ContentFilter::Hidden,
+        });
+        assert_eq!(count(&hidden, LOTTERY) + count(&hidden, BREAK_IN), 0);
+        // The untagged events still come up.
+        assert!(!hidden.is_empty());
+    }
+
+    #[test]
+    fn days_fall_in_their_season_every_year() {
+        let calendar: Calendar = read(CALENDAR_PATH);
+        assert_eq!(calendar.days_per_year(), 120);
+        assert_eq!(calendar.day_of_year(0), 1);
+        assert_eq!(calendar.day_of_year(1), 1);
+        assert_eq!(calendar.day_of_year(120), 120);
+        assert_eq!(calendar.day_of_year(121), 1);
+
+        let seasons = [
+            (1, "Spring"),
+            (30, "Spring"),
+            (31, "Summer"),
+            (60, "Summer"),
+            (61, "Autumn"),
+            (90, "Autumn"),
+            (91, "Winter"),
+            (120, "Winter"),
+            (121, "Spring"),
+            (10_000, "Summer"),
+        ];
+        for (day, name) in seasons {
+            assert_eq!(calendar.season(day).name, name, "day {day}");
+        }
+    }
+
+    #[test]
+    fn holidays_come_back_on_the_same_day_every_year() {
+        let calendar: Calendar = read(CALENDAR_PATH);
+        for day in [20, 140, 20 + 50 * 120] {
+            assert_eq!(calendar.holiday(day).unwrap().name, "Spring Festival");
+        }
+        assert!(calendar.holiday(21).is_none());
+        assert_eq!(
+            calendar.closed_reason(110 + 120).as_deref(),
+            Some("Closed for Winter Holiday")
+        );
+        assert_eq!(calendar.closed_reason(111), None);
+    }
+
+    #[test]
+    fn a_long_sleep_keeps_every_holiday_and_winter_bill() {
+        let catalog = AreaCatalog::from_data(&read::<AreaCatalogData>(AREA_CATALOG_PATH));
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<AppState>()
+            .add_plugins(clock_plugin)
+            .add_event::<Toast>()
+            .add_event::<Transaction>()
+            .insert_resource(read::<Calendar>(CALENDAR_PATH))
+            .insert_resource(HomeArea::new(Area::DeadbeatArea, &catalog))
+            .insert_resource(catalog)
+            .init_resource::<PriceLevel>()
+            .insert_resource(Money { amount: 100_000 })
+            .init_resource::<Bank>()
+            .init_resource::<Happiness>()
+            .insert_resource(RunFilters(ContentFilters::default()))
+            .insert_resource(GameRng::seeded(1))
+            .add_systems(
+                FixedUpdate,
+                (celebrate_holiday, pay_utilities, roll_season_events)
+                    .run_if(day_started)
+                    .after(SimSet::Clock),
+            );
+        app.update();
+
+        // Two years in one go, from the morning of day 1 to the start of day 241.
+        let now = app.world().resource::<Clock>().total_hours();
+        step_ticks(app.world_mut(), 241 * HOURS_PER_DAY - now);
+        assert_eq!(
+            *app.world().resource::<Clock>(),
+            Clock { day: 241, hour: 0 }
+        );
+
+        let names = [
+            "Spring Festival",
+            "Midsummer",
+            "Harvest Fair",
+            "Winter Holiday",
+        ];
+        let holidays: Vec<_> = app
+            .world_mut()
+            .resource_mut::<Events<Toast>>()
+            .drain()
+            .map(|toast| toast.text)
+            .filter(|text| names.iter().any(|name| text.starts_with(name)))
+            .collect();
+        assert_eq!(holidays.len(), 2 * names.len());
+        for (toast, name) in holidays.iter().zip(names.iter().cycle()) {
+            assert!(toast.starts_with(name), "{toast}");
+        }
+
+        let utilities = app
+            .world_mut()
+            .resource_mut::<Events<Transaction>>()
+            .drain()
+            .filter(|transaction| {
+                matches!(transaction, Transaction::Expense(Category::Utilities, _))
+            })
+            .count();
+        // Days 91 to 120 and 211 to 240.
+        assert_eq!(utilities, 60);
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::dialogue::NpcDef;
+use super::economy::{Area, CurrentArea, HomeArea};
+use super::new_game_plus::PriceLevel;
+use crate::data::register_ron_asset;
+
+pub const AREA_CATALOG_PATH: &str = "data/world.areas.ron";
+
+/// Balance data for a single area.
+#[derive(Deserialize, Clone, Debug)]
+pub struct AreaDef {
+    pub area: Area,
+    /// Rent per rent period, or `None` if the area can't be lived in.
+    pub rent: Option<u32>,
+    pub image: Option<String>,
+    /// Plays in place of the image when present.
+    #[serde(default)]
+    pub animation: Option<AreaAnimation>,
+    /// From 0 to 100. Homes in unsafe areas may get broken into at night.
+    pub safety: u32,
+    /// Whether the player can take in a roommate when living here.
+    #[serde(default)]
+    pub roommates: bool,
+    /// Characters the player can talk to here.
+    #[serde(default)]
+    pub npcs: Vec<NpcDef>,
+    /// What it takes to travel here. Areas without one are open from the start.
+    #[serde(default)]
+    pub unlock: Option<Unlock>,
+    /// What the travel menu calls the area while it's locked, in place of its name.
+    #[serde(default)]
+    pub locked_label: Option<String>,
+    /// The color the HUD leans toward while the player is here, as sRGB from 0 to 255,
+    /// e.g. `accent: Some((212, 175, 90))`.
+    #[serde(default)]
+    pub accent: Option<[u8; 3]>,
+    /// How much more things cost while the player lives here, rent aside, e.g.
+    /// `cost_of_living: Some(1.5)`. See [`super::cost_of_living`].
+    #[serde(default)]
+    pub cost_of_living: Option<f32>,
+    /// Where the area's hotspot sits on the travel map, in percent of the map's width and
+    /// height from its top left corner, e.g. `map: Some((30.0, 55.0))`.
+    #[serde(default)]
+    pub map: Option<[f32; 2]>,
+}
+
+/// A condition that opens up an area for good once the player first meets it, e.g.
+/// `unlock: Some(NetWorth(5000))`, or a chain of them like
+/// `unlock: Some(All([Area(Mansion), Reputation(150)]))`.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Unlock {
+    /// Cash and savings of at least this much, before the run's [`PriceLevel`] scales it.
+    NetWorth(u32),
+    /// Another area open to the player.
+    Area(Area),
+    /// Charity reputation of at least this much, see [`super::charity`].
+    Reputation(u32),
+    /// Every one of these.
+    All(Vec<Unlock>),
+}
+
+/// An area's looping background animation, e.g.
+/// `animation: Some(Frames(paths: ["images/locations/condo_1.png", ...], fps: 4.0))`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub enum AreaAnimation {
+    /// One image per frame.
+    Frames { paths: Vec<String>, fps: f32 },
+    /// A sprite sheet of `columns` by `rows` frames, played row by row.
+    Atlas {
+        path: String,
+        tile_size: UVec2,
+        columns: u32,
+        rows: u32,
+        fps: f32,
+    },
+}
+
+/// The contents of an area data file.
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct AreaCatalogData {
+    pub areas: Vec<AreaDef>,
+    /// The travel map's picture. Without one the map is drawn plain.
+    #[serde(default)]
+    pub map_image: Option<String>,
+}
+
+/// The area data the simulation currently uses, built from [`AreaCatalogData`].
+#[derive(Resource, Debug, Default)]
+pub struct AreaCatalog {
+    areas: HashMap<Area, AreaDef>,
+    map_image: Option<String>,
+}
+
+impl AreaCatalog {
+    pub fn from_data(data: &AreaCatalogData) -> Self {
+        for npc in data.areas.iter().flat_map(|def| &def.npcs) {
+            npc.dialogue.validate(&npc.id);
+        }
+        AreaCatalog {
+            areas: data
+                .areas
+                .iter()
+                .map(|def| (def.area, def.clone()))
+                .collect(),
+            map_image: data.map_image.clone(),
+        }
+    }
+
+    pub fn rent(&self, area: Area) -> Option<u32> {
+        self.areas.get(&area).and_then(|def| def.rent)
+    }
+
+    pub fn allows_roommates(&self, area: Area) -> bool {
+        self.areas.get(&area).is_some_and(|def| def.roommates)
+    }
+
+    /// Areas missing from the data count as perfectly safe.
+    pub fn safety(&self, area: Area) -> u32 {
+        self.areas.get(&area).map_or(100, |def| def.safety)
+    }
+
+    pub fn npcs(&self, area: Area) -> &[NpcDef] {
+        self.areas.get(&area).map_or(&[], |def| &def.npcs)
+    }
+
+    pub fn unlock(&self, area: Area) -> Option<&Unlock> {
+        self.areas.get(&area).and_then(|def| def.unlock.as_ref())
+    }
+
+    /// The area's name in the travel menu, which locked areas may keep a secret.
+    pub fn travel_label(&self, area: Area, open: bool) -> &str {
+        self.areas
+            .get(&area)
+            .and_then(|def| def.locked_label.as_deref())
+            .filter(|_| !open)
+            .unwrap_or(area.label())
+    }
+
+    /// Every area in the data, in a stable order.
+    pub fn areas(&self) -> Vec<Area> {
+        let mut areas: Vec<Area> = self.areas.keys().copied().collect();
+        areas.sort_unstable();
+        areas
+    }
+
+    /// Areas without one cost what things usually cost.
+    pub fn cost_of_living(&self, area: Area) -> f32 {
+        self.areas
+            .get(&area)
+            .and_then(|def| def.cost_of_living)
+            .unwrap_or(1.0)
+    }
+
+    pub fn accent(&self, area: Area) -> Option<Color> {
+        self.areas
+            .get(&area)
+            .and_then(|def| def.accent)
+            .map(|[red, green, blue]| Color::srgb_u8(red, green, blue))
+    }
+
+    pub fn image(&self, area: Area) -> Option<&str> {
+        self.areas.get(&area).and_then(|def| def.image.as_deref())
+    }
+
+    pub fn animation(&self, area: Area) -> Option<&AreaAnimation> {
+        self.areas.get(&area).and_then(|def| def.animation.as_ref())
+    }
+
+    /// The area's spot on the travel map, in percent, see [`AreaDef::map`].
+    pub fn map_position(&self, area: Area) -> Option<Vec2> {
+        self.areas
+            .get(&area)
+            .and_then(|def| def.map)
+            .map(Vec2::from_array)
+    }
+
+    pub fn map_image(&self) -> Option<&str> {
+        self.map_image.as_deref()
+    }
+
+    /// Every area's background image, without repeats, in a stable order.
+    pub fn images(&self) -> Vec<&str> {
+        let mut images: Vec<&str> = self
+            .areas
+            .values()
+            .filter_map(|def| def.image.as_deref())
+            .collect();
+        images.sort_unstable();
+        images.dedup();
+        images
+    }
+}
+
+/// Sent after the area data file changed on disk and the catalog was rebuilt.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AreaCatalogReloaded;
+
+pub fn catalog_plugin(app: &mut App) {
+    register_ron_asset::<AreaCatalogData>(app, &["areas.ron"]);
+    app.add_event::<AreaCatalogReloaded>()
+        .add_systems(Update, apply_area_catalog);
+}
+
+// The catalog is built once when the data first loads. With the `dev` feature the file is
+// watched, and every later change rebuilds it in place without disturbing the current run.
+// The home takes the area's new rent, unless the run's scenario set its own, and rent
+// increases stay on top of it.
+fn apply_area_catalog(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<AreaCatalogData>>,
+    data: Res<Assets<AreaCatalogData>>,
+    mut catalog: Option<ResMut<AreaCatalog>>,
+    mut home: Option<ResMut<HomeArea>>,
+    current: Option<Res<CurrentArea>>,
+    prices: Res<PriceLevel>,
+    mut reloaded: EventWriter<AreaCatalogReloaded>,
+) {
+    for event in events.read() {
+        match (event, catalog.as_deref_mut()) {
+            (AssetEvent::LoadedWithDependencies { id }, None) => {
+                if let Some(data) = data.get(*id) {
+                    commands.insert_resource(AreaCatalog::from_data(data));
+                }
+            }
+            (AssetEvent::Modified { id }, Some(catalog)) => {
+                let Some(data) = data.get(*id) else {
+                    continue;
+                };
+                let mut rebuilt = AreaCatalog::from_data(data);
+
+                // Areas the run depends on must survive even if they were deleted from
+                // the file, or rent and the background would have nothing to point to.
+                let in_use = home
+                    .iter()
+                    .map(|home| home.location)
+                    .chain(current.iter().map(|current| current.0));
+                for area in in_use {
+                    if rebuilt.areas.contains_key(&area) {
+                        continue;
+                    }
+                    if let Some(old) = catalog.areas.get(&area) {
+                        warn!("{area:?} was removed from the area data while in use, keeping it");
+                        rebuilt.areas.insert(area, old.clone());
+                    }
+                }
+
+                *catalog = rebuilt;
+                if let Some(home) = home.as_deref_mut() {
+                    home.reprice(catalog, &prices);
+                }
+
+                info!("Reloaded area data");
+                reloaded.write(AreaCatalogReloaded);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data() -> AreaCatalogData {
+        let path = format!("assets/{AREA_CATALOG_PATH}");
+        ron::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    /// Reloads the area data with the Condo's rent raised by 100, for a run living in the
+    /// Condo at `prices`, with the scenario's rent if it set one. The home's rent after.
+    fn reloaded_rent(prices: PriceLevel, scenario_rent: Option<u32>) -> u32 {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<AreaCatalogData>()
+            .add_event::<AreaCatalogReloaded>();
+        let mut data = data();
+        let catalog = AreaCatalog::from_data(&data);
+        let mut home = HomeArea::new(Area::Condo, &catalog);
+        home.scenario_rent = scenario_rent;
+        home.reprice(&catalog, &prices);
+        app.insert_resource(catalog)
+            .insert_resource(home)
+            .insert_resource(prices);
+        for def in &mut data.areas {
+            if def.area == Area::Condo {
+                def.rent = def.rent.map(|rent| rent + 100);
+            }
+        }
+        let handle = app
+            .world_mut()
+            .resource_mut::<Assets<AreaCatalogData>>()
+            .add(data);
+
+        let world = app.world_mut();
+        world.send_event(AssetEvent::Modified { id: handle.id() });
+        world.run_system_cached(apply_area_catalog).unwrap();
+        assert_eq!(world.resource::<Events<AreaCatalogReloaded>>().len(), 1);
+        world.resource::<HomeArea>().rent
+    }
+
+    #[test]
+    fn reloading_the_data_reprices_the_home_unless_the_scenario_set_its_rent() {
+        let condo = AreaCatalog::from_data(&data()).rent(Area::Condo).unwrap();
+        let dearer = PriceLevel {
+            multiplier: 1.5,
+            ..default()
+        };
+        assert_eq!(reloaded_rent(default(), None), condo + 100);
+        assert_eq!(reloaded_rent(dearer, None), dearer.scale(condo + 100));
+        assert_eq!(reloaded_rent(dearer, Some(700)), dearer.scale(700));
+    }
+}
@@ -0,0 +1,239 @@
+//! The weekly challenge: a run whose seed and difficulty come from the current ISO week, so
+//! everyone playing that week gets the same events and prices. Each week's best result is
+//! kept in [`HIGH_SCORES_FILE`].
+//!
+//! Like [`super::new_game_plus::NewGamePlus`], [`Challenge`] outlives the run and
+//! [`super::reset_run`] reads it to set up the next one.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{economy::GameOverReason, new_game_plus::NewGamePlus, rng::GameRng};
+use crate::profiles::ProfilePaths;
+use crate::save_file::{CorruptFiles, Saves, load_ron};
+
+/// Where [`HighScores`] live, in the profile's directory.
+pub const HIGH_SCORES_FILE: &str = "high_scores.ron";
+/// Challenges pick a price level from the regular game up to this New Game+ cycle.
+const MAX_CHALLENGE_CYCLE: u32 = 2;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// A week as ISO 8601 numbers them: weeks start on Monday, and week 1 is the one with the
+/// year's first Thursday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Reflect)]
+pub struct IsoWeek {
+    pub year: i64,
+    pub week: u32,
+}
+
+impl IsoWeek {
+    /// The week containing the day `days` days after 1970-01-01.
+    pub fn from_unix_days(days: i64) -> Self {
+        let weekday = (days + 3).rem_euclid(7);
+        // The week belongs to the year its Thursday is in.
+        let thursday = days - weekday + 3;
+        let year = civil_year(thursday);
+        let week = (thursday - days_from_civil(year, 1, 1)) / 7 + 1;
+        IsoWeek {
+            year,
+            week: week as u32,
+        }
+    }
+
+    /// This week, by the system clock.
+    pub fn current() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        IsoWeek::from_unix_days((seconds / SECONDS_PER_DAY) as i64)
+    }
+
+    /// The week's run seed. The same week always gets the same seed.
+    pub fn seed(self) -> u64 {
+        let key = self.year as u64 * 100 + u64::from(self.week);
+        GameRng::seeded(key).next_u64()
+    }
+
+    /// The New Game+ cycle whose prices and win target the week's run uses.
+    pub fn difficulty(self) -> NewGamePlus {
+        NewGamePlus {
+            cycle: (self.seed() % u64::from(MAX_CHALLENGE_CYCLE + 1)) as u32,
+        }
+    }
+}
+
+impl fmt::Display for IsoWeek {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-W{:02}", self.year, self.week)
+    }
+}
+
+/// Days from 1970-01-01 to the given date, from Howard Hinnant's `days_from_civil`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The year of the day `days` days after 1970-01-01, the inverse of [`days_from_civil`].
+fn civil_year(days: i64) -> i64 {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let march_based_month = (5 * day_of_year + 2) / 153;
+    // Years in this scheme start in March, so January and February belong to the next.
+    year_of_era + era * 400 + i64::from(march_based_month >= 10)
+}
+
+/// The week of the challenge being played, or `None` for a regular game.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct Challenge(pub Option<IsoWeek>);
+
+/// How a challenge run ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChallengeResult {
+    pub won: bool,
+    /// The day the run was won or lost.
+    pub days: u32,
+    pub worth: u32,
+    /// The day a run played on past its win went bankrupt, see
+    /// [`super::economy::FreePlay`]. `days` is still the day it was won.
+    #[serde(default)]
+    pub bankrupt_on: Option<u32>,
+    /// How the run was lost, if it was and the game knew why.
+    #[serde(default)]
+    pub lost_to: Option<GameOverReason>,
+    /// The id of the scenario the run started from, see [`super::scenario`].
+    #[serde(default)]
+    pub scenario: Option<String>,
+}
+
+impl ChallengeResult {
+    /// Whether this beats `other`: winning first, then ending with more.
+    pub fn beats(&self, other: &ChallengeResult) -> bool {
+        (self.won, self.worth) > (other.won, other.worth)
+    }
+
+    /// A line to share, the same for everyone with the same result. `scenario` is the
+    /// name of the scenario the run started from.
+    pub fn summary(&self, week: IsoWeek, scenario: &str, money: &str) -> String {
+        let lost = self.lost_to.map_or("lost", GameOverReason::outcome);
+        let outcome = match (self.won, self.bankrupt_on) {
+            (true, Some(day)) => format!("won on day {}, {lost} on day {day}", self.days),
+            (true, None) => format!("won on day {}", self.days),
+            (false, _) => format!("{lost} on day {}", self.days),
+        };
+        format!("Weekly challenge {week} ({scenario}): {outcome} with {money}")
+    }
+}
+
+/// Results kept between sessions. Challenges have their own section, keyed by week, and
+/// by scenario too for runs that didn't start the regular way, so only runs from the same
+/// start are compared.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    pub challenges: BTreeMap<String, ChallengeResult>,
+}
+
+impl HighScores {
+    /// Reads the profile's [`HIGH_SCORES_FILE`], starting over if it's missing or broken,
+    /// see [`load_ron`].
+    pub fn load(profile: &ProfilePaths, corrupt: &mut CorruptFiles) -> Self {
+        load_ron(&profile.file(HIGH_SCORES_FILE), corrupt).unwrap_or_default()
+    }
+
+    /// Writes the profile's [`HIGH_SCORES_FILE`] in the background, see
+    /// [`crate::save_file`].
+    pub fn save(&self, profile: &ProfilePaths, saves: &mut Saves) {
+        saves.save_ron(profile.file(HIGH_SCORES_FILE), self.clone());
+    }
+
+    /// Keeps `result` if it's the week's best from its scenario. Returns that best either
+    /// way.
+    pub fn record(&mut self, week: IsoWeek, result: ChallengeResult) -> ChallengeResult {
+        let key = match &result.scenario {
+            Some(scenario) => format!("{week} {scenario}"),
+            None => week.to_string(),
+        };
+        let best = self.challenges.entry(key).or_insert_with(|| result.clone());
+        if result.beats(best) {
+            *best = result;
+        }
+        best.clone()
+    }
+}
+
+pub fn challenge_plugin(app: &mut App) {
+    app.register_type::<Challenge>()
+        .init_resource::<Challenge>();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn week_of(year: i64, month: i64, day: i64) -> IsoWeek {
+        IsoWeek::from_unix_days(days_from_civil(year, month, day))
+    }
+
+    #[test]
+    fn weeks_are_numbered_the_iso_way() {
+        for ((year, month, day), week) in [
+            ((1970, 1, 1), "1970-W01"),
+            ((2020, 12, 31), "2020-W53"),
+            ((2021, 1, 3), "2020-W53"),
+            ((2021, 1, 4), "2021-W01"),
+            ((2024, 12, 30), "2025-W01"),
+            ((2026, 10, 17), "2026-W42"),
+        ] {
+            assert_eq!(week_of(year, month, day).to_string(), week);
+        }
+    }
+
+    #[test]
+    fn every_day_of_a_week_gets_its_seed() {
+        // Monday to Sunday.
+        let monday = days_from_civil(2026, 10, 12);
+        let seeds: HashSet<u64> = (monday..monday + 7)
+            .map(|day| IsoWeek::from_unix_days(day).seed())
+            .collect();
+        assert_eq!(seeds.len(), 1);
+        assert_ne!(
+            IsoWeek::from_unix_days(monday - 1).seed(),
+            IsoWeek::from_unix_days(monday).seed()
+        );
+    }
+
+    #[test]
+    fn no_two_weeks_share_a_seed() {
+        let first = days_from_civil(2000, 1, 3);
+        let weeks = (0..52 * 100).map(|week| IsoWeek::from_unix_days(first + week * 7));
+        let seeds: HashSet<u64> = weeks.clone().map(IsoWeek::seed).collect();
+        assert_eq!(seeds.len(), weeks.count());
+    }
+
+    #[test]
+    fn a_week_keeps_its_seed_from_build_to_build() {
+        // Everyone playing the week has to get the same run, whichever version they have.
+        let week = IsoWeek {
+            year: 2026,
+            week: 42,
+        };
+        assert_eq!(week.seed(), 5_136_194_975_377_838_485);
+        assert_eq!(week.difficulty(), NewGamePlus { cycle: 1 });
+    }
+}
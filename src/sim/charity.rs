@@ -0,0 +1,174 @@
+//! Charity: somewhere for late-game money to go. Donations build [`Reputation`], which
+//! earns the player a title and invitations to events, and giving away enough over a run
+//! ends it as a philanthropist.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    choices::{Choice, ChoiceLog},
+    clock::{Clock, day_started},
+    content::{ContentTag, RunFilters},
+    mail::{Inbox, Mail},
+    needs::Happiness,
+    new_game_plus::PriceLevel,
+    rng::{GameRng, RollSet},
+};
+use crate::AppState;
+use crate::data::register_ron_resource;
+use crate::screens::actions::{ActionPressed, GameAction};
+use crate::toast::Toast;
+
+pub const CHARITY_PATH: &str = "data/world.charity.ron";
+
+/// A title the player earns at `reputation`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct TitleDef {
+    pub reputation: u32,
+    pub title: String,
+}
+
+/// Something that may happen to a player with a good reputation, delivered as mail.
+#[derive(Deserialize, Clone, Debug)]
+pub struct CharityEventDef {
+    pub title: String,
+    pub body: String,
+    pub min_reputation: u32,
+    /// Chance each day once the reputation is high enough.
+    pub chance: f32,
+    /// Days before the same event can happen again.
+    pub cooldown_days: u32,
+    pub happiness: i32,
+    /// Label of the button that takes the player up on the event. Without one, the
+    /// happiness applies as soon as the mail arrives.
+    #[serde(default)]
+    pub accept: Option<String>,
+    /// How the end screens sum up accepting, see [`super::choices::Choice::notable`].
+    #[serde(default)]
+    pub notable: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<ContentTag>,
+}
+
+/// Charity balance data.
+#[derive(Asset, TypePath, Resource, Deserialize, Clone, Debug)]
+pub struct CharityConfig {
+    /// Dollars donated per point of reputation.
+    pub dollars_per_point: u32,
+    /// Lifetime donations that end the run as a philanthropist, as a share of the run's
+    /// win target.
+    pub philanthropist_share: f32,
+    /// From the lowest reputation up.
+    pub titles: Vec<TitleDef>,
+    pub events: Vec<CharityEventDef>,
+}
+
+impl CharityConfig {
+    pub fn philanthropist_target(&self, prices: &PriceLevel) -> u32 {
+        (prices.win_money as f32 * self.philanthropist_share).round() as u32
+    }
+}
+
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct Reputation {
+    pub points: u32,
+    pub lifetime_donations: u32,
+    /// Dollars donated towards the next point.
+    pub change: u32,
+    /// Day each event last happened, by title.
+    pub event_days: BTreeMap<String, u32>,
+}
+
+impl Reputation {
+    pub fn donate(&mut self, amount: u32, config: &CharityConfig) {
+        self.lifetime_donations += amount;
+        let dollars = self.change + amount;
+        let per_point = config.dollars_per_point.max(1);
+        self.points += dollars / per_point;
+        self.change = dollars % per_point;
+    }
+
+    /// The best title earned so far.
+    pub fn title<'a>(&self, config: &'a CharityConfig) -> Option<&'a str> {
+        config
+            .titles
+            .iter()
+            .filter(|title| self.points >= title.reputation)
+            .max_by_key(|title| title.reputation)
+            .map(|title| title.title.as_str())
+    }
+}
+
+pub fn charity_plugin(app: &mut App) {
+    register_ron_resource::<CharityConfig>(app, &["charity.ron"]);
+    app.register_type::<Reputation>()
+        .init_resource::<Reputation>()
+        .add_systems(
+            FixedUpdate,
+            roll_charity_events
+                .run_if(day_started)
+                .in_set(RollSet::Charity),
+        )
+        .add_systems(Update, attend_event.run_if(in_state(AppState::Game)));
+}
+
+fn roll_charity_events(
+    config: Res<CharityConfig>,
+    clock: Res<Clock>,
+    filters: Res<RunFilters>,
+    mut reputation: ResMut<Reputation>,
+    mut rng: ResMut<GameRng>,
+    mut inbox: ResMut<Inbox>,
+    mut happiness: ResMut<Happiness>,
+) {
+    let events = config.events.iter().enumerate();
+    for (index, event) in events.filter(|(_, event)| filters.allows(&event.tags)) {
+        let cooled_down = reputation
+            .event_days
+            .get(&event.title)
+            .is_none_or(|day| clock.day >= day + event.cooldown_days);
+        if reputation.points < event.min_reputation || !cooled_down || !rng.chance(event.chance) {
+            continue;
+        }
+        reputation.event_days.insert(event.title.clone(), clock.day);
+        let mail = Mail::new(event.title.clone(), event.body.clone());
+        match &event.accept {
+            Some(label) => {
+                inbox.send_mail(
+                    clock.day,
+                    mail.with_action(label.clone(), GameAction::AttendEvent(index)),
+                );
+            }
+            None => {
+                happiness.change(event.happiness);
+                inbox.send_mail(clock.day, mail);
+            }
+        }
+    }
+}
+
+fn attend_event(
+    mut pressed: EventReader<ActionPressed>,
+    config: Res<CharityConfig>,
+    clock: Res<Clock>,
+    mut happiness: ResMut<Happiness>,
+    mut choices: ResMut<ChoiceLog>,
+    mut toasts: EventWriter<Toast>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        if let GameAction::AttendEvent(index) = action
+            && let Some(event) = config.events.get(*index)
+        {
+            happiness.change(event.happiness);
+            let label = event.accept.as_deref().unwrap_or("Attend");
+            choices.record(
+                clock.day,
+                Choice::new(&event.title, label).notable(event.notable.as_deref()),
+            );
+            toasts.write(Toast::new(format!("{}: what a night!", event.title)));
+        }
+    }
+}
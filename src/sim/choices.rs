@@ -0,0 +1,86 @@
+//! The decisions the player made over the run. Whatever offers the player options, like a
+//! conversation, the rent negotiation or an invitation in the mail, records the one they
+//! picked in the [`ChoiceLog`]. The data can mark a choice as notable, and the end screens
+//! bring those back up.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The log never holds more than this many choices.
+pub const MAX_CHOICES: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct Choice {
+    /// Day the choice was made.
+    pub day: u32,
+    /// What offered the options, like a character's name.
+    pub context: String,
+    /// The option picked, as it was labelled.
+    pub choice: String,
+    /// What came of it, if anything worth telling.
+    pub outcome: Option<String>,
+    /// How the end screens sum the choice up, for choices the data marks as notable.
+    pub notable: Option<String>,
+}
+
+impl Choice {
+    pub fn new(context: impl Into<String>, choice: impl Into<String>) -> Self {
+        Choice {
+            day: 0,
+            context: context.into(),
+            choice: choice.into(),
+            outcome: None,
+            notable: None,
+        }
+    }
+
+    pub fn with_outcome(mut self, outcome: impl Into<String>) -> Self {
+        self.outcome = Some(outcome.into());
+        self
+    }
+
+    /// Marks the choice as notable, with the line the end screens show for it.
+    pub fn notable(mut self, summary: Option<&str>) -> Self {
+        self.notable = summary.map(str::to_string);
+        self
+    }
+
+    /// The end screen line for a notable choice.
+    pub fn summary(&self) -> Option<String> {
+        let notable = self.notable.as_ref()?;
+        Some(format!("{notable} on Day {}.", self.day))
+    }
+}
+
+/// Every choice the player made this run, oldest first.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct ChoiceLog(pub Vec<Choice>);
+
+impl ChoiceLog {
+    /// Records `choice` as made on `day`, dropping the oldest choice if the log is full.
+    pub fn record(&mut self, day: u32, choice: Choice) {
+        if self.0.len() >= MAX_CHOICES {
+            self.0.remove(0);
+        }
+        self.0.push(Choice { day, ..choice });
+    }
+
+    /// The last `count` notable choices, oldest first.
+    pub fn notable(&self, count: usize) -> Vec<&Choice> {
+        let mut notable: Vec<_> = self
+            .0
+            .iter()
+            .rev()
+            .filter(|choice| choice.notable.is_some())
+            .take(count)
+            .collect();
+        notable.reverse();
+        notable
+    }
+}
+
+pub fn choices_plugin(app: &mut App) {
+    app.register_type::<ChoiceLog>()
+        .init_resource::<ChoiceLog>();
+}
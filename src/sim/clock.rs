@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::SimSet;
+
+pub const HOURS_PER_DAY: u32 = 24;
+pub const DAYS_PER_WEEK: u32 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    const ALL: [Weekday; DAYS_PER_WEEK as usize] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    pub fn is_weekend(self) -> bool {
+        matches!(self, Weekday::Saturday | Weekday::Sunday)
+    }
+
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Weekday::Monday => "Mon",
+            Weekday::Tuesday => "Tue",
+            Weekday::Wednesday => "Wed",
+            Weekday::Thursday => "Thu",
+            Weekday::Friday => "Fri",
+            Weekday::Saturday => "Sat",
+            Weekday::Sunday => "Sun",
+        }
+    }
+}
+
+/// In-game time of the current run. Advances one hour per simulation tick.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct Clock {
+    pub day: u32,
+    pub hour: u32,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock { day: 1, hour: 8 }
+    }
+}
+
+impl Clock {
+    /// Hours elapsed since midnight of day zero, handy for scheduling.
+    pub fn total_hours(&self) -> u32 {
+        self.day * HOURS_PER_DAY + self.hour
+    }
+
+    /// Runs start on a Monday.
+    pub fn weekday(&self) -> Weekday {
+        Weekday::ALL[((self.day - 1) % DAYS_PER_WEEK) as usize]
+    }
+
+    pub fn advance(&mut self) {
+        self.hour += 1;
+        if self.hour == HOURS_PER_DAY {
+            self.hour = 0;
+            self.day += 1;
+        }
+    }
+}
+
+/// A stretch of in-game hours, counted on the [`Clock`] so it lasts as long on screen at
+/// any sim speed, across skipped hours and in a save. UI timers are
+/// [`UiTimer`](crate::timers::UiTimer)s instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct SimTimer {
+    /// [`Clock::total_hours`] when the timer started.
+    pub started: u32,
+    /// [`Clock::total_hours`] when it ends.
+    pub ends: u32,
+}
+
+impl SimTimer {
+    /// A timer of `hours`, starting at `now` in [`Clock::total_hours`].
+    pub fn new(now: u32, hours: u32) -> Self {
+        SimTimer {
+            started: now,
+            ends: now + hours,
+        }
+    }
+
+    pub fn finished(&self, now: u32) -> bool {
+        now >= self.ends
+    }
+
+    pub fn hours_left(&self, now: u32) -> u32 {
+        self.ends.saturating_sub(now)
+    }
+
+    /// From 1 when the timer starts to 0 when it ends.
+    pub fn fraction_left(&self, now: u32) -> f32 {
+        let total = self.ends - self.started;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hours_left(now) as f32 / total as f32
+    }
+}
+
+pub fn clock_plugin(app: &mut App) {
+    app.register_type::<Clock>()
+        .init_resource::<Clock>()
+        .add_systems(FixedUpdate, advance_clock.in_set(SimSet::Clock));
+}
+
+/// Run condition for systems that happen once per in-game day, at midnight.
+pub fn day_started(clock: Res<Clock>) -> bool {
+    clock.hour == 0
+}
+
+/// Run condition for systems that happen at the start of every week, Monday midnight.
+pub fn week_started(clock: Res<Clock>) -> bool {
+    clock.hour == 0 && clock.weekday() == Weekday::Monday
+}
+
+fn advance_clock(mut clock: ResMut<Clock>) {
+    clock.advance();
+}
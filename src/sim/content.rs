@@ -0,0 +1,42 @@
+//! Content filters. Data entries that some players would rather not see carry
+//! [`ContentTag`]s, e.g. `tags: [Gambling]`, and entries with a tag the run filters out
+//! never come up: they aren't rolled, offered or shown.
+//!
+//! A run keeps the filters it started with in [`RunFilters`], so changing them in the
+//! settings takes effect from the next new game and the odds don't shift mid-run.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{ContentFilter, ContentFilters};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentTag {
+    /// Betting, lotteries and the like.
+    Gambling,
+    /// Break-ins and other crime.
+    Crime,
+}
+
+/// The content filters this run started with.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunFilters(pub ContentFilters);
+
+impl RunFilters {
+    /// Whether content with all of `tags` can come up this run.
+    pub fn allows(&self, tags: &[ContentTag]) -> bool {
+        tags.iter()
+            .all(|tag| self.filter(*tag) == ContentFilter::Shown)
+    }
+
+    fn filter(&self, tag: ContentTag) -> ContentFilter {
+        match tag {
+            ContentTag::Gambling => self.0.gambling,
+            ContentTag::Crime => self.0.crime,
+        }
+    }
+}
+
+pub fn content_plugin(app: &mut App) {
+    app.init_resource::<RunFilters>();
+}
@@ -0,0 +1,143 @@
+//! Living somewhere dear makes everything dearer, not just the rent: each area in the
+//! catalog has a cost of living, and what the player buys, gets billed for or loses to an
+//! event costs that much more while their home is there. It's the home that counts, not
+//! the area the player happens to be in.
+//!
+//! Everything priced goes through [`Prices`], so something new to buy follows the cost of
+//! living just by being priced there. Rent doesn't, as the area sets it already, and
+//! neither does money coming in.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use super::{catalog::AreaCatalog, economy::HomeArea, new_game_plus::PriceLevel};
+
+/// `price` at a cost of living of `multiplier`.
+pub fn with_cost_of_living(price: u32, multiplier: f32) -> u32 {
+    (price as f32 * multiplier.max(0.0)).round() as u32
+}
+
+/// What things cost the player right now.
+#[derive(SystemParam)]
+pub struct Prices<'w> {
+    pub level: Res<'w, PriceLevel>,
+    home: Res<'w, HomeArea>,
+    catalog: Res<'w, AreaCatalog>,
+}
+
+impl Prices<'_> {
+    pub fn cost_of_living(&self) -> f32 {
+        self.catalog.cost_of_living(self.home.location)
+    }
+
+    /// What something with a base price of `price` costs.
+    pub fn price(&self, price: u32) -> u32 {
+        with_cost_of_living(self.level.scale(price), self.cost_of_living())
+    }
+
+    /// What it would cost without the cost of living, to show next to [`Prices::price`].
+    pub fn without_cost_of_living(&self, price: u32) -> u32 {
+        self.level.scale(price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::settings::Locale;
+    use crate::sim::{
+        bills::{Bills, issue_bills},
+        catalog::{AREA_CATALOG_PATH, AreaCatalogData},
+        clock::Clock,
+        economy::{Area, RentSchedule},
+        landlord::{RentIncreases, RentTerms},
+        ledger::Ledger,
+        mail::Inbox,
+        roommate::Roommate,
+    };
+
+    fn catalog() -> AreaCatalog {
+        let text = std::fs::read_to_string(format!("assets/{AREA_CATALOG_PATH}")).unwrap();
+        AreaCatalog::from_data(&ron::from_str::<AreaCatalogData>(&text).unwrap())
+    }
+
+    fn price(world: &mut World, base: u32) -> u32 {
+        world
+            .run_system_once(move |prices: Prices| prices.price(base))
+            .unwrap()
+    }
+
+    #[test]
+    fn prices_follow_the_multiplier_and_never_go_negative() {
+        for (multiplier, expected) in [
+            (1.0, 150),
+            (0.85, 128),
+            (1.25, 188),
+            (1.6, 240),
+            (3.0, 450),
+            (0.0, 0),
+            (-0.5, 0),
+            (f32::NEG_INFINITY, 0),
+        ] {
+            assert_eq!(
+                with_cost_of_living(150, multiplier),
+                expected,
+                "at {multiplier}"
+            );
+        }
+        assert_eq!(with_cost_of_living(0, 1.6), 0);
+    }
+
+    #[test]
+    fn the_price_level_applies_before_the_cost_of_living() {
+        let catalog = catalog();
+        let mut world = World::new();
+        world.insert_resource(PriceLevel {
+            multiplier: 1.5,
+            ..default()
+        });
+        world.insert_resource(HomeArea::new(Area::Mansion, &catalog));
+        world.insert_resource(catalog);
+        // 100 becomes 150 at the price level, then 240 at the Mansion's 1.6.
+        assert_eq!(price(&mut world, 100), 240);
+        let without = world
+            .run_system_once(|prices: Prices| prices.without_cost_of_living(100))
+            .unwrap();
+        assert_eq!(without, 150);
+    }
+
+    #[test]
+    fn moving_home_reprices_what_comes_next_but_not_bills_already_sent() {
+        let catalog = catalog();
+        let mut world = World::new();
+        world.insert_resource(Clock { day: 5, hour: 12 });
+        world.init_resource::<PriceLevel>();
+        world.init_resource::<RentSchedule>();
+        world.init_resource::<RentIncreases>();
+        world.init_resource::<RentTerms>();
+        world.init_resource::<Roommate>();
+        world.init_resource::<Ledger>();
+        world.init_resource::<Bills>();
+        world.init_resource::<Inbox>();
+        world.init_resource::<Locale>();
+        world.insert_resource(HomeArea::new(Area::DeadbeatArea, &catalog));
+        world.insert_resource(catalog);
+
+        world.run_system_once(issue_bills).unwrap();
+        let sent = world.resource::<Bills>().outstanding.clone();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].charge.net, 250);
+        assert_eq!(price(&mut world, 100), 85);
+
+        // Moving in the middle of the day.
+        let catalog = world.resource::<AreaCatalog>();
+        let mansion = HomeArea::new(Area::Mansion, catalog);
+        world.insert_resource(mansion);
+        world.resource_mut::<Clock>().hour = 18;
+
+        assert_eq!(price(&mut world, 100), 160);
+        world.run_system_once(issue_bills).unwrap();
+        assert_eq!(world.resource::<Bills>().outstanding, sent);
+    }
+}
@@ -0,0 +1,252 @@
+//! Break-ins: each night, homes in unsafe areas may be robbed of the cash lying around.
+
+use bevy::prelude::*;
+
+use super::{
+    calendar::Calendar,
+    catalog::AreaCatalog,
+    clock::{Clock, day_started},
+    content::{ContentTag, RunFilters},
+    cost_of_living::Prices,
+    dialogue::DiscountToken,
+    economy::{HomeArea, Money},
+    ledger::{Category, Transaction},
+    rng::{GameRng, RollSet},
+    undo::{UndoBuffer, UndoEntry, UndoRestore},
+};
+use crate::AppState;
+use crate::format;
+use crate::screens::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use crate::settings::Locale;
+use crate::toast::Toast;
+
+/// Areas at least this safe never see a break-in.
+pub const SAFE_AREA_RATING: u32 = 80;
+/// Nightly break-in chance in an area with a safety rating of zero.
+const MAX_THEFT_CHANCE: f32 = 0.25;
+/// Share of the carried cash a thief takes.
+const THEFT_PERCENT: u32 = 30;
+pub const DOOR_LOCK_PRICE: u32 = 150;
+
+/// Gear and home improvements the player has bought.
+#[derive(Resource, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Upgrades {
+    /// Halves what a thief gets away with.
+    pub door_lock: bool,
+    /// Keeps rain from slowing down the commute, see [`super::weather`].
+    pub umbrella: bool,
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theft {
+    pub lost: u32,
+}
+
+/// Nightly chance of a break-in for an area with the given safety rating.
+pub fn theft_chance(safety: u32) -> f32 {
+    if safety >= SAFE_AREA_RATING {
+        return 0.0;
+    }
+    MAX_THEFT_CHANCE * (SAFE_AREA_RATING - safety) as f32 / SAFE_AREA_RATING as f32
+}
+
+/// How much of `cash` a thief takes.
+pub fn theft_loss(cash: u32, upgrades: &Upgrades) -> u32 {
+    let loss = cash * THEFT_PERCENT / 100;
+    if upgrades.door_lock { loss / 2 } else { loss }
+}
+
+pub fn crime_plugin(app: &mut App) {
+    app.register_type::<Upgrades>()
+        .init_resource::<Upgrades>()
+        .add_event::<Theft>()
+        .add_systems(
+            FixedUpdate,
+            roll_theft.run_if(day_started).in_set(RollSet::Crime),
+        )
+        .add_systems(Update, offer_door_lock.in_set(ActionSet::Offer))
+        .add_systems(
+            Update,
+            (buy_door_lock, announce_theft).run_if(in_state(AppState::Game)),
+        );
+}
+
+fn roll_theft(
+    home: Res<HomeArea>,
+    catalog: Res<AreaCatalog>,
+    upgrades: Res<Upgrades>,
+    filters: Res<RunFilters>,
+    mut money: ResMut<Money>,
+    mut rng: ResMut<GameRng>,
+    mut thefts: EventWriter<Theft>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    if !filters.allows(&[ContentTag::Crime]) {
+        return;
+    }
+    let chance = theft_chance(catalog.safety(home.location));
+    if chance == 0.0 || !rng.chance(chance) {
+        return;
+    }
+
+    let lost = theft_loss(money.amount, &upgrades);
+    if lost > 0 {
+        money.amount -= lost;
+        transactions.write(Transaction::Expense(Category::Theft, lost));
+        thefts.write(Theft { lost });
+    }
+}
+
+fn announce_theft(
+    mut thefts: EventReader<Theft>,
+    upgrades: Res<Upgrades>,
+    mut toasts: EventWriter<Toast>,
+    locale: Res<Locale>,
+) {
+    for theft in thefts.read() {
+        let hint = if upgrades.door_lock {
+            "Money in the bank is safe."
+        } else {
+            "A door lock or the bank would help."
+        };
+        toasts.write(Toast::new(format!(
+            "Break-in! {} of your cash was stolen. {hint}",
+            format::money(theft.lost, *locale)
+        )));
+    }
+}
+
+fn offer_door_lock(
+    upgrades: Res<Upgrades>,
+    clock: Res<Clock>,
+    calendar: Res<Calendar>,
+    money: Res<Money>,
+    discount: Res<DiscountToken>,
+    prices: Prices,
+    mut actions: ResMut<AvailableActions>,
+    locale: Res<Locale>,
+) {
+    if upgrades.door_lock {
+        return;
+    }
+    let price = discount.price(prices.price(DOOR_LOCK_PRICE));
+    let base = discount.price(prices.without_cost_of_living(DOOR_LOCK_PRICE));
+    let label = format!("Buy a door lock: -{}", format::price(price, base, *locale));
+    if let Some(reason) = calendar.closed_reason(clock.day) {
+        actions.offer_blocked(GameAction::BuyDoorLock, label, reason);
+    } else if money.amount >= price {
+        actions.offer_labeled(GameAction::BuyDoorLock, label);
+    } else {
+        actions.offer_blocked(GameAction::BuyDoorLock, label, "Not enough cash");
+    }
+}
+
+fn buy_door_lock(
+    mut pressed: EventReader<ActionPressed>,
+    clock: Res<Clock>,
+    calendar: Res<Calendar>,
+    mut upgrades: ResMut<Upgrades>,
+    mut money: ResMut<Money>,
+    mut discount: ResMut<DiscountToken>,
+    prices: Prices,
+    mut undo: ResMut<UndoBuffer>,
+    mut toasts: EventWriter<Toast>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        let price = discount.price(prices.price(DOOR_LOCK_PRICE));
+        if *action == GameAction::BuyDoorLock
+            && !upgrades.door_lock
+            && money.amount >= price
+            && calendar.holiday(clock.day).is_none()
+        {
+            money.amount -= price;
+            transactions.write(Transaction::Expense(Category::Purchases, price));
+            let entry = UndoEntry::new("the door lock", price, UndoRestore::DoorLock, discount.0);
+            undo.record(entry, &money, &clock);
+            discount.0 = None;
+            upgrades.door_lock = true;
+            toasts.write(Toast::undoable("Installed a door lock"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{
+        catalog::{AREA_CATALOG_PATH, AreaCatalogData},
+        economy::Area,
+    };
+
+    const CASH: u32 = 1000;
+    const NIGHTS: u32 = 20_000;
+
+    /// Average cash lost a night, living in `area` with `upgrades` and [`CASH`] lying around.
+    fn nightly_loss(area: Area, upgrades: Upgrades) -> f32 {
+        let path = format!("assets/{AREA_CATALOG_PATH}");
+        let data: AreaCatalogData = ron::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let catalog = AreaCatalog::from_data(&data);
+        let mut world = World::new();
+        world.insert_resource(HomeArea::new(area, &catalog));
+        world.insert_resource(catalog);
+        world.insert_resource(upgrades);
+        world.init_resource::<RunFilters>();
+        world.insert_resource(GameRng::seeded(1));
+        world.init_resource::<Events<Theft>>();
+        world.init_resource::<Events<Transaction>>();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(roll_theft);
+        let mut lost = 0;
+        for _ in 0..NIGHTS {
+            world.insert_resource(Money { amount: CASH });
+            schedule.run(&mut world);
+            lost += CASH - world.resource::<Money>().amount;
+        }
+        lost as f32 / NIGHTS as f32
+    }
+
+    #[test]
+    fn safer_areas_are_robbed_less_often() {
+        assert_eq!(theft_chance(0), MAX_THEFT_CHANCE);
+        assert_eq!(theft_chance(SAFE_AREA_RATING / 2), MAX_THEFT_CHANCE / 2.0);
+        assert_eq!(theft_chance(SAFE_AREA_RATING), 0.0);
+        assert_eq!(theft_chance(100), 0.0);
+        assert!(
+            (0..SAFE_AREA_RATING).all(|safety| theft_chance(safety) > theft_chance(safety + 1))
+        );
+    }
+
+    #[test]
+    fn a_door_lock_halves_the_loss() {
+        let lock = Upgrades {
+            door_lock: true,
+            ..default()
+        };
+        assert_eq!(theft_loss(CASH, &Upgrades::default()), 300);
+        assert_eq!(theft_loss(CASH, &lock), 150);
+        assert_eq!(theft_loss(3, &lock), 0);
+    }
+
+    #[test]
+    fn mitigations_cut_the_expected_loss() {
+        let unprotected = nightly_loss(Area::DeadbeatArea, Upgrades::default());
+        // 30% of the cash, a 15.6% chance a night at a safety of 30.
+        let expected = theft_chance(30) * theft_loss(CASH, &Upgrades::default()) as f32;
+        assert!(
+            (unprotected - expected).abs() < 2.0,
+            "{unprotected} vs {expected}"
+        );
+
+        let locked = nightly_loss(
+            Area::DeadbeatArea,
+            Upgrades {
+                door_lock: true,
+                ..default()
+            },
+        );
+        assert_eq!(locked, unprotected / 2.0);
+        assert_eq!(nightly_loss(Area::Condo, Upgrades::default()), 0.0);
+    }
+}
@@ -0,0 +1,116 @@
+//! Debt, which for now means rent gone overdue: the player is in debt from the day a bill
+//! goes overdue until the last overdue bill is paid. Getting out of it is worth a
+//! celebration, the first time in a run, or every time with
+//! [`LandlordConfig::repeat_debt_free`].
+//!
+//! What a stretch of debt cost comes from the [`Ledger`]'s late fees. Debt is checked once
+//! the tick's transactions are booked, so a bill paid off the day it went overdue still
+//! counts as a day in debt.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    SimSet,
+    bills::Bills,
+    choices::{Choice, ChoiceLog},
+    clock::Clock,
+    event_cards::{CardRequest, EventCardQueue},
+    journal::PhotoRequest,
+    landlord::LandlordConfig,
+    ledger::{Category, Ledger, record_transactions},
+    needs::Happiness,
+};
+use crate::format;
+use crate::settings::Locale;
+use crate::toast::Toast;
+
+/// Happiness for getting out of debt the first time.
+pub const DEBT_FREE_HAPPINESS: i32 = 15;
+/// Happiness for getting out of debt again, with repeat celebrations on.
+pub const DEBT_FREE_AGAIN_HAPPINESS: i32 = 5;
+
+/// The run's debt, now and so far.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Debt {
+    /// The day the player went into debt, while they're in it.
+    pub since: Option<u32>,
+    /// Late fees paid before then, to tell what this stretch cost.
+    pub fees_before: u32,
+    /// Days spent in debt, counting the day each stretch started.
+    pub days_in_debt: u32,
+    /// The days the player got out of debt, oldest first.
+    pub cleared: Vec<u32>,
+}
+
+pub fn debt_plugin(app: &mut App) {
+    app.register_type::<Debt>()
+        .init_resource::<Debt>()
+        .add_systems(
+            FixedUpdate,
+            track_debt
+                .run_if(resource_exists::<LandlordConfig>)
+                .in_set(SimSet::Ledger)
+                .after(record_transactions),
+        );
+}
+
+fn track_debt(
+    clock: Res<Clock>,
+    bills: Res<Bills>,
+    ledger: Res<Ledger>,
+    config: Res<LandlordConfig>,
+    mut debt: ResMut<Debt>,
+    mut happiness: ResMut<Happiness>,
+    mut choices: ResMut<ChoiceLog>,
+    mut cards: ResMut<EventCardQueue>,
+    mut toasts: EventWriter<Toast>,
+    mut photos: EventWriter<PhotoRequest>,
+    locale: Res<Locale>,
+) {
+    let in_debt = bills
+        .outstanding
+        .iter()
+        .any(|bill| bill.is_overdue(clock.day));
+    match debt.since {
+        None if in_debt => {
+            debt.since = Some(clock.day);
+            debt.fees_before = ledger.spent_on(Category::LateFees);
+        }
+        Some(since) if !in_debt => {
+            let days = clock.day - since + 1;
+            let fees = ledger.spent_on(Category::LateFees) - debt.fees_before;
+            let first = debt.cleared.is_empty();
+            debt.since = None;
+            debt.days_in_debt += days;
+            debt.cleared.push(clock.day);
+            info!("Out of debt after {days} days and ${fees} in late fees");
+            if first {
+                happiness.change(DEBT_FREE_HAPPINESS);
+                photos.write(PhotoRequest::milestone("Debt free"));
+                choices.record(
+                    clock.day,
+                    Choice::new("Debt", "Paid it all off").notable(Some("You got out of debt")),
+                );
+                cards.push(CardRequest::new(
+                    "Debt free!",
+                    format!(
+                        "Every overdue bill is paid. You spent {days} {} behind on rent, \
+                         and the late fees cost you {} in all. Try to keep it that way.",
+                        if days == 1 { "day" } else { "days" },
+                        format::money(fees, *locale),
+                    ),
+                ));
+            } else if config.repeat_debt_free {
+                happiness.change(DEBT_FREE_AGAIN_HAPPINESS);
+                photos.write(PhotoRequest::moment("Out of debt again"));
+                toasts.write(Toast::new(format!(
+                    "Out of debt again, after {} in late fees",
+                    format::money(fees, *locale)
+                )));
+            }
+        }
+        _ => {}
+    }
+}
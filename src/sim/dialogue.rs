@@ -0,0 +1,167 @@
+//! Conversations with the characters living in each area. Every character has a small
+//! dialogue graph in the area data: nodes with a line and a few responses, where a response
+//! may need something from the player, grant something once, and lead to another node or
+//! end the conversation. Once a conversation has run to its end, talking again only gets
+//! the character's fallback line.
+
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::content::ContentTag;
+use super::economy::Area;
+
+/// A character met in an area.
+#[derive(Deserialize, Clone, Debug)]
+pub struct NpcDef {
+    /// Unique across all areas, used to remember finished conversations.
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub portrait: Option<String>,
+    pub dialogue: Dialogue,
+    #[serde(default)]
+    pub tags: Vec<ContentTag>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Dialogue {
+    /// Id of the first node.
+    pub start: String,
+    /// What the character says once the conversation has run its course.
+    pub fallback: String,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+impl Dialogue {
+    /// Warns about node ids that lead nowhere, so broken data shows up when it loads.
+    pub fn validate(&self, npc: &str) {
+        let targets = self.nodes.values().flat_map(|node| &node.responses);
+        let missing = std::iter::once(&self.start)
+            .chain(targets.filter_map(|response| response.next.as_ref()))
+            .filter(|id| !self.nodes.contains_key(*id));
+        for id in missing {
+            warn!("Dialogue of {npc} refers to missing node {id:?}");
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DialogueNode {
+    pub line: String,
+    pub responses: Vec<Response>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Response {
+    pub text: String,
+    /// The node this leads to, or `None` to end the conversation.
+    #[serde(default)]
+    pub next: Option<String>,
+    /// Hidden unless this holds.
+    #[serde(default)]
+    pub requires: Option<Condition>,
+    /// Granted the first time the response is picked.
+    #[serde(default)]
+    pub effect: Option<Effect>,
+    /// How the end screens sum up picking this, see [`super::choices::Choice::notable`].
+    #[serde(default)]
+    pub notable: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+    /// At least this much cash on hand.
+    MoneyAtLeast(u32),
+    Employed,
+    Unemployed,
+    HasDoorLock,
+    HasPet,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    /// Tells the player when rent is due next.
+    RentTip,
+    /// A [`DiscountToken`] of this many percent.
+    Discount(u32),
+    /// Wins the run with [`super::economy::Ending::InnerCircle`].
+    InnerCircle,
+}
+
+/// The parts of the game state dialogue conditions look at.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DialogueContext {
+    pub money: u32,
+    pub employed: bool,
+    pub door_lock: bool,
+    pub has_pet: bool,
+}
+
+impl Condition {
+    pub fn holds(self, context: &DialogueContext) -> bool {
+        match self {
+            Condition::MoneyAtLeast(amount) => context.money >= amount,
+            Condition::Employed => context.employed,
+            Condition::Unemployed => !context.employed,
+            Condition::HasDoorLock => context.door_lock,
+            Condition::HasPet => context.has_pet,
+        }
+    }
+}
+
+impl Response {
+    pub fn available(&self, context: &DialogueContext) -> bool {
+        self.requires
+            .is_none_or(|condition| condition.holds(context))
+    }
+}
+
+/// The conversation in progress. Only exists while the dialog box is open.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct DialogueRunner {
+    pub area: Area,
+    /// Index of the character in the area's list.
+    pub npc: usize,
+    /// The node on screen, or `None` while showing the fallback line.
+    pub node: Option<String>,
+}
+
+/// What the player has already been through with each character this run.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct DialogueMemory {
+    /// Characters whose conversation ran to its end.
+    pub exhausted: HashSet<String>,
+    /// Effects already granted, as character, node and response index.
+    pub granted: HashSet<(String, String, usize)>,
+}
+
+impl DialogueMemory {
+    /// Records that an effect was granted, returning `false` if it had been before.
+    pub fn grant(&mut self, npc: &str, node: &str, response: usize) -> bool {
+        self.granted
+            .insert((npc.to_string(), node.to_string(), response))
+    }
+}
+
+/// A one-time discount on the next purchase, in percent.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct DiscountToken(pub Option<u32>);
+
+impl DiscountToken {
+    /// What `price` comes to with the token applied.
+    pub fn price(&self, price: u32) -> u32 {
+        let percent = self.0.unwrap_or(0).min(100);
+        price * (100 - percent) / 100
+    }
+}
+
+pub fn dialogue_plugin(app: &mut App) {
+    app.register_type::<DialogueMemory>()
+        .register_type::<DiscountToken>()
+        .init_resource::<DialogueMemory>()
+        .init_resource::<DiscountToken>();
+}
@@ -0,0 +1,335 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    SimSet,
+    bank::Bank,
+    catalog::AreaCatalog,
+    clock::{Clock, HOURS_PER_DAY},
+    landlord::{RentIncreases, RentTerms},
+    new_game_plus::PriceLevel,
+    roommate::Roommate,
+};
+use crate::{
+    AppState,
+    screens::nav::{ScreenNav, TransitionCause},
+};
+
+/// Rent is due at midnight every this many days, see [`super::bills`].
+pub const RENT_PERIOD_DAYS: u32 = 7;
+pub const STARTING_MONEY: u32 = 1000;
+/// Reaching this much money wins a regular run, see [`PriceLevel::win_money`].
+pub const WIN_MONEY: u32 = 100_000;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(
+    Clone,
+    Default,
+    Copy,
+    Debug,
+    Eq,
+    Hash,
+    PartialEq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    Reflect,
+)]
+pub enum Area {
+    #[default]
+    DeadbeatArea,
+    Condo,
+    LuxuryCondo,
+    Mansion,
+    BusinessDistrict,
+    /// The gated compound on the hill. Can't be rented, and opens late in a run.
+    RestrictedArea,
+}
+
+impl Area {
+    pub fn label(self) -> &'static str {
+        match self {
+            Area::DeadbeatArea => "Deadbeat Area",
+            Area::Condo => "Condo",
+            Area::LuxuryCondo => "Luxury Condo",
+            Area::Mansion => "Mansion",
+            Area::BusinessDistrict => "Business District",
+            Area::RestrictedArea => "Restricted Area",
+        }
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct HomeArea {
+    pub location: Area,
+    pub rent: u32,
+    /// The rent a scenario set in place of the area's, before the run's [`PriceLevel`].
+    #[reflect(default)]
+    pub scenario_rent: Option<u32>,
+}
+
+impl HomeArea {
+    /// An area that can't be rented, like the Restricted Area, comes rent-free.
+    pub fn new(location: Area, catalog: &AreaCatalog) -> Self {
+        let rent = catalog.rent(location).unwrap_or_else(|| {
+            warn!("{location:?} can't be rented, living there rent-free");
+            0
+        });
+        HomeArea {
+            location,
+            rent,
+            scenario_rent: None,
+        }
+    }
+
+    /// Sets the rent to the scenario's, or else the area's in `catalog`, at the run's
+    /// `prices`. Rent increases go on top of it, see [`RentIncreases::listed_rent`].
+    pub fn reprice(&mut self, catalog: &AreaCatalog, prices: &PriceLevel) {
+        if let Some(rent) = self.scenario_rent.or_else(|| catalog.rent(self.location)) {
+            self.rent = prices.scale(rent);
+        }
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct CurrentArea(pub Area);
+
+impl Default for CurrentArea {
+    fn default() -> Self {
+        CurrentArea(Area::DeadbeatArea)
+    }
+}
+
+/// The player's job. `location` is `None` while unemployed.
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub struct WorkArea {
+    pub location: Option<Area>,
+    pub title: String,
+    /// Pay per hour worked.
+    pub wage: u32,
+    /// Shifts worked in a row at this job, counting towards the next promotion.
+    pub shifts: u32,
+    /// Day of the last shift, so the player works at most once a day.
+    pub last_shift_day: Option<u32>,
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct Money {
+    pub amount: u32,
+}
+
+impl Default for Money {
+    fn default() -> Self {
+        Money {
+            amount: STARTING_MONEY,
+        }
+    }
+}
+
+/// How the run was won, for the win screen.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub enum Ending {
+    /// Saved up the win target.
+    #[default]
+    Wealth,
+    /// Gave enough away, see [`super::charity`].
+    Philanthropy,
+    /// Taken into the Restricted Area's inner circle, see [`super::dialogue::Effect`].
+    InnerCircle,
+}
+
+/// The day the run was won, once the player keeps playing it past the win screen. The
+/// run can't be won again then, but it can still be lost.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct FreePlay(pub Option<u32>);
+
+/// How the run was lost, for the game over screen. Whatever ends the run sets it, and
+/// nothing is set while the run goes on.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub enum GameOverReason {
+    /// Rent went unpaid until the eviction day, see [`super::bills`].
+    Evicted,
+    /// The money ran out with bills still to pay.
+    Bankrupt,
+    /// Worked past what the player's body could take, see [`super::needs::Energy`].
+    HealthCollapse,
+    /// Too unhappy to carry on, see [`super::needs::Happiness`].
+    Burnout,
+}
+
+impl GameOverReason {
+    /// The loss in a word, like "evicted".
+    pub fn outcome(self) -> &'static str {
+        match self {
+            GameOverReason::Evicted => "evicted",
+            GameOverReason::Bankrupt => "bankrupt",
+            GameOverReason::HealthCollapse => "collapsed",
+            GameOverReason::Burnout => "burnt out",
+        }
+    }
+}
+
+/// Run condition for a run that can still be won, see [`FreePlay`].
+pub fn winnable(free_play: Res<FreePlay>) -> bool {
+    free_play.0.is_none()
+}
+
+/// When the next rent is due.
+#[derive(Resource, Debug, Clone, Copy, Reflect)]
+#[reflect(Resource)]
+pub struct RentSchedule {
+    /// [`Clock::total_hours`] at which rent is next due.
+    pub next_due: u32,
+}
+
+impl Default for RentSchedule {
+    fn default() -> Self {
+        RentSchedule {
+            next_due: RENT_PERIOD_DAYS * HOURS_PER_DAY,
+        }
+    }
+}
+
+impl RentSchedule {
+    /// Hours left until rent is due, zero when it's due right now.
+    pub fn hours_until_due(&self, clock: &Clock) -> u32 {
+        self.next_due.saturating_sub(clock.total_hours())
+    }
+}
+
+/// One rent payment: what the home lists and everything that changed it, worked out by
+/// [`rent_due`] so the breakdown is always the one that was charged.
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct RentCharge {
+    /// The day it was worked out, and once it's in the [`RentHistory`], the day it was paid.
+    pub day: u32,
+    pub area: Area,
+    /// The home's rent before any increase, deal or split.
+    pub gross: u32,
+    /// Each modifier applied, in order, like "Rent increases +10%".
+    pub modifiers: Vec<String>,
+    pub net: u32,
+}
+
+/// Every rent payment of the run, oldest first.
+#[derive(Resource, Debug, Clone, Default, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct RentHistory(pub Vec<RentCharge>);
+
+/// The rent due now at `home`, with how it was arrived at.
+pub fn rent_due(
+    home: &HomeArea,
+    increases: &RentIncreases,
+    terms: &RentTerms,
+    roommate: &Roommate,
+    clock: &Clock,
+    month: u32,
+) -> RentCharge {
+    let listed = increases.listed_rent(home);
+    let mut modifiers = Vec::new();
+    if listed != home.rent {
+        modifiers.push(format!(
+            "Rent increases +{}%",
+            increases.percent.saturating_sub(100)
+        ));
+    }
+    modifiers.extend(terms.modifiers(month));
+    if roommate.is_splitting() {
+        modifiers.push("Roommate pays half".to_string());
+    }
+    RentCharge {
+        day: clock.day,
+        area: home.location,
+        gross: home.rent,
+        modifiers,
+        net: roommate.rent_share(terms.rent(listed, month)),
+    }
+}
+
+pub fn economy_plugin(app: &mut App) {
+    app.register_type::<HomeArea>()
+        .register_type::<CurrentArea>()
+        .register_type::<WorkArea>()
+        .register_type::<Money>()
+        .register_type::<RentSchedule>()
+        .register_type::<Ending>()
+        .register_type::<RentHistory>()
+        .register_type::<FreePlay>()
+        .register_type::<GameOverReason>()
+        .init_resource::<CurrentArea>()
+        .init_resource::<WorkArea>()
+        .init_resource::<Money>()
+        .init_resource::<RentSchedule>()
+        .init_resource::<Ending>()
+        .init_resource::<RentHistory>()
+        .init_resource::<FreePlay>()
+        .add_systems(
+            FixedUpdate,
+            check_win.run_if(winnable).in_set(SimSet::Economy),
+        );
+}
+
+fn check_win(money: Res<Money>, bank: Res<Bank>, prices: Res<PriceLevel>, mut nav: ScreenNav) {
+    if money.amount + bank.savings >= prices.win_money {
+        nav.set_app(AppState::Win, TransitionCause::WinCondition);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+    use crate::screens::menu::MenuState;
+    use crate::screens::nav::ScreenChanged;
+    use crate::sim::{clock::clock_plugin, step_ticks};
+
+    /// A run with enough money to win, played on from `free_play`.
+    fn rich_app(free_play: FreePlay) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .insert_state(AppState::Game)
+            .init_state::<MenuState>()
+            .add_event::<ScreenChanged>()
+            .add_plugins((clock_plugin, economy_plugin))
+            .init_resource::<Bank>()
+            .init_resource::<PriceLevel>()
+            .insert_resource(free_play);
+        app.world_mut().resource_mut::<Money>().amount = WIN_MONEY;
+        app.update();
+        app
+    }
+
+    fn state(app: &App) -> AppState {
+        *app.world().resource::<State<AppState>>().get()
+    }
+
+    #[test]
+    fn enough_money_wins_the_run() {
+        let mut app = rich_app(FreePlay(None));
+        step_ticks(app.world_mut(), 1);
+        app.update();
+        assert_eq!(state(&app), AppState::Win);
+    }
+
+    #[test]
+    fn a_run_in_free_play_isnt_won_again() {
+        let mut app = rich_app(FreePlay(Some(42)));
+        app.world_mut().resource_mut::<Money>().amount = WIN_MONEY * 3;
+        assert_eq!(
+            step_ticks(app.world_mut(), 2 * HOURS_PER_DAY),
+            2 * HOURS_PER_DAY
+        );
+        app.update();
+        assert_eq!(state(&app), AppState::Game);
+        assert!(app.world().resource::<Events<ScreenChanged>>().is_empty());
+    }
+}
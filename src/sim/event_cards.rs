@@ -0,0 +1,102 @@
+//! Event cards: the run's big moments, like a layoff, shown one at a time as a card in the
+//! middle of the screen. Systems push a [`CardRequest`] to the [`EventCardQueue`] rather
+//! than opening anything themselves, so several moments on the same day line up instead of
+//! piling on top of each other. The most urgent card shows first, and cards of the same
+//! priority in the order they came.
+//!
+//! The simulation waits while a card is up. The button the player picks comes back as a
+//! [`CardChosen`] with the [`CardId`] the push returned.
+
+use bevy::prelude::*;
+
+use crate::AppState;
+
+/// Tells apart the cards of a session, including across runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CardId(u64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardRequest {
+    pub title: String,
+    pub body: String,
+    /// Button labels, in order. A card always has at least one.
+    pub buttons: Vec<String>,
+    /// Higher shows first.
+    pub priority: i32,
+}
+
+impl CardRequest {
+    /// A card with a single OK button.
+    pub fn new(title: impl Into<String>, body: impl Into<String>) -> Self {
+        CardRequest {
+            title: title.into(),
+            body: body.into(),
+            buttons: vec!["OK".to_string()],
+            priority: 0,
+        }
+    }
+
+    pub fn with_buttons<S: Into<String>>(mut self, buttons: impl IntoIterator<Item = S>) -> Self {
+        self.buttons = buttons.into_iter().map(Into::into).collect();
+        if self.buttons.is_empty() {
+            self.buttons.push("OK".to_string());
+        }
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// The cards waiting to be seen, including the one on screen.
+#[derive(Resource, Debug, Default)]
+pub struct EventCardQueue {
+    cards: Vec<(CardId, CardRequest)>,
+    next_id: u64,
+}
+
+impl EventCardQueue {
+    pub fn push(&mut self, card: CardRequest) -> CardId {
+        let id = CardId(self.next_id);
+        self.next_id += 1;
+        self.cards.push((id, card));
+        id
+    }
+
+    /// The card to show: the highest priority, then the oldest.
+    pub fn front(&self) -> Option<(CardId, &CardRequest)> {
+        self.cards
+            .iter()
+            .min_by_key(|(id, card)| (std::cmp::Reverse(card.priority), *id))
+            .map(|(id, card)| (*id, card))
+    }
+
+    pub fn remove(&mut self, id: CardId) {
+        self.cards.retain(|(card, _)| *card != id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+}
+
+/// Sent when the player picks a card's button, by its index in [`CardRequest::buttons`].
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CardChosen {
+    pub card: CardId,
+    pub button: usize,
+}
+
+pub fn event_cards_plugin(app: &mut App) {
+    app.init_resource::<EventCardQueue>()
+        .add_event::<CardChosen>()
+        .add_systems(OnExit(AppState::Game), clear_cards);
+}
+
+// Cards left from a run are dropped with it. Ids keep counting, so an answer still
+// expected from the last run can't be mistaken for one from the next.
+fn clear_cards(mut queue: ResMut<EventCardQueue>) {
+    queue.cards.clear();
+}
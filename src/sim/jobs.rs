@@ -0,0 +1,603 @@
+//! Employment: working shifts, and a job market that makes offers, lays the player off
+//! and promotes them as the days go by.
+//!
+//! Every change to the player's job is announced with a [`JobEvent`], so the UI and any
+//! bookkeeping can react without knowing how the market works.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    SimSet,
+    buffs::Buffs,
+    calendar::Calendar,
+    choices::{Choice, ChoiceLog},
+    clock::{Clock, day_started, week_started},
+    crime::Upgrades,
+    economy::{Area, Money, WorkArea},
+    event_cards::{CardRequest, EventCardQueue},
+    ledger::{Category, Transaction},
+    mail::{Inbox, Mail},
+    needs::{Energy, Happiness},
+    rng::{GameRng, RollSet},
+    weather::{DayModifiers, Weather},
+};
+use crate::AppState;
+use crate::data::register_ron_resource_as;
+use crate::format;
+use crate::screens::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use crate::settings::Locale;
+use crate::toast::Toast;
+
+pub const JOB_MARKET_PATH: &str = "data/world.jobs.ron";
+pub const SHIFT_HOURS: u32 = 8;
+pub const OVERTIME_SHIFT_HOURS: u32 = 12;
+pub const MAX_OVERTIME_PER_WEEK: u32 = 2;
+const SHIFT_ENERGY: u32 = 40;
+const OVERTIME_HAPPINESS_CHANGE: i32 = -5;
+/// Energy a commute takes for every hour it runs late.
+const COMMUTE_ENERGY_PER_HOUR: u32 = 5;
+
+/// The two ways to work a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShiftKind {
+    Normal,
+    /// A long shift. The hours past a normal shift pay time and a half, but it takes
+    /// twice the energy and some happiness.
+    Overtime,
+}
+
+impl ShiftKind {
+    pub fn hours(self) -> u32 {
+        match self {
+            ShiftKind::Normal => SHIFT_HOURS,
+            ShiftKind::Overtime => OVERTIME_SHIFT_HOURS,
+        }
+    }
+
+    pub fn pay(self, wage: u32) -> u32 {
+        let extra_hours = self.hours() - SHIFT_HOURS;
+        wage * SHIFT_HOURS + (wage * extra_hours * 3).div_ceil(2)
+    }
+
+    /// Energy the shift takes, including `commute_hours` of delays getting there, before any
+    /// [`Buffs`].
+    pub fn energy_cost(self, commute_hours: u32) -> u32 {
+        let shift = match self {
+            ShiftKind::Normal => SHIFT_ENERGY,
+            ShiftKind::Overtime => SHIFT_ENERGY * 2,
+        };
+        shift + commute_hours * COMMUTE_ENERGY_PER_HOUR
+    }
+
+    pub fn happiness_change(self) -> i32 {
+        match self {
+            ShiftKind::Normal => 0,
+            ShiftKind::Overtime => OVERTIME_HAPPINESS_CHANGE,
+        }
+    }
+
+    fn action(self) -> GameAction {
+        match self {
+            ShiftKind::Normal => GameAction::WorkShift,
+            ShiftKind::Overtime => GameAction::WorkOvertime,
+        }
+    }
+
+    /// The button label, spelling out what the shift earns and costs.
+    fn label(self, wage: u32, commute_hours: u32, energy_cost: u32, locale: Locale) -> String {
+        let name = match self {
+            ShiftKind::Normal => "Work",
+            ShiftKind::Overtime => "Overtime",
+        };
+        let commute = if commute_hours > 0 {
+            format!(" (+{commute_hours}h commute)")
+        } else {
+            String::new()
+        };
+        let mut label = format!(
+            "{name} {}h{commute}: +{}, -{} energy",
+            self.hours(),
+            format::money(self.pay(wage), locale),
+            energy_cost
+        );
+        if self.happiness_change() != 0 {
+            label += &format!(", {} happiness", self.happiness_change());
+        }
+        label
+    }
+}
+
+/// A job the market can offer.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq, Reflect)]
+pub struct JobDef {
+    pub employer: Area,
+    pub title: String,
+    /// Pay per hour worked.
+    pub wage: u32,
+}
+
+/// The contents of the job market data file.
+#[derive(Asset, TypePath, Deserialize, Clone, Debug)]
+pub struct JobMarketData {
+    pub jobs: Vec<JobDef>,
+    /// Chance each day of an offer for a better paid job, while none is pending.
+    pub offer_chance: f32,
+    /// Chance each day of losing the current job.
+    pub layoff_chance: f32,
+    /// Shifts in a row at the same job needed for each promotion.
+    pub promotion_shifts: u32,
+    pub promotion_raise_percent: u32,
+    /// Severance paid on a layoff, in shifts' worth of pay.
+    pub severance_shifts: u32,
+}
+
+/// The job market tuning the simulation currently uses.
+#[derive(Resource, Debug, Deref)]
+pub struct JobMarket(JobMarketData);
+
+impl From<JobMarketData> for JobMarket {
+    fn from(data: JobMarketData) -> Self {
+        JobMarket(data)
+    }
+}
+
+impl JobMarket {
+    /// The jobs paying more than `wage`, apart from the one at `current_employer`.
+    fn better_than(&self, wage: u32, current_employer: Option<Area>) -> Vec<&JobDef> {
+        self.jobs
+            .iter()
+            .filter(|job| job.wage > wage && Some(job.employer) != current_employer)
+            .collect()
+    }
+
+    /// The best hourly wage on offer from employers in `area`.
+    pub fn best_wage(&self, area: Area) -> Option<u32> {
+        self.jobs
+            .iter()
+            .filter(|job| job.employer == area)
+            .map(|job| job.wage)
+            .max()
+    }
+}
+
+/// An offer waiting for the player to accept or decline it.
+#[derive(Resource, Debug, Default, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct JobOffer(pub Option<JobDef>);
+
+/// Overtime shifts worked since Monday.
+#[derive(Resource, Debug, Default, Clone, Copy, Reflect)]
+#[reflect(Resource)]
+pub struct WeeklyOvertime(pub u32);
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub enum JobEvent {
+    Offered(JobDef),
+    Hired(JobDef),
+    ShiftWorked {
+        pay: u32,
+    },
+    /// The workplace closed for the day, so the shift paid nothing.
+    ShiftCancelled,
+    Promoted {
+        wage: u32,
+    },
+    LaidOff {
+        severance: u32,
+    },
+}
+
+pub fn jobs_plugin(app: &mut App) {
+    register_ron_resource_as::<JobMarketData, JobMarket>(app, &["jobs.ron"]);
+    app.register_type::<JobOffer>()
+        .register_type::<WeeklyOvertime>()
+        .init_resource::<JobOffer>()
+        .init_resource::<WeeklyOvertime>()
+        .add_event::<JobEvent>()
+        .add_systems(
+            FixedUpdate,
+            (
+                roll_job_market.run_if(day_started).in_set(RollSet::Jobs),
+                reset_weekly_overtime
+                    .run_if(week_started)
+                    .in_set(SimSet::Economy),
+            ),
+        )
+        .add_systems(Update, offer_job_actions.in_set(ActionSet::Offer))
+        .add_systems(
+            Update,
+            (handle_job_actions, announce_job_events)
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        );
+}
+
+fn roll_job_market(
+    market: Res<JobMarket>,
+    mut work: ResMut<WorkArea>,
+    mut offer: ResMut<JobOffer>,
+    mut money: ResMut<Money>,
+    mut rng: ResMut<GameRng>,
+    mut events: EventWriter<JobEvent>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    if work.location.is_some() && rng.chance(market.layoff_chance) {
+        let severance = ShiftKind::Normal.pay(work.wage) * market.severance_shifts;
+        money.amount += severance;
+        transactions.write(Transaction::Income(Category::Severance, severance));
+        *work = WorkArea::default();
+        events.write(JobEvent::LaidOff { severance });
+    }
+
+    if offer.0.is_none() && rng.chance(market.offer_chance) {
+        let candidates = market.better_than(work.wage, work.location);
+        if !candidates.is_empty() {
+            let job = candidates[rng.index(candidates.len())].clone();
+            offer.0 = Some(job.clone());
+            events.write(JobEvent::Offered(job));
+        }
+    }
+}
+
+fn reset_weekly_overtime(mut overtime: ResMut<WeeklyOvertime>) {
+    overtime.0 = 0;
+}
+
+fn offer_job_actions(
+    work: Res<WorkArea>,
+    offer: Res<JobOffer>,
+    clock: Res<Clock>,
+    energy: Res<Energy>,
+    buffs: Res<Buffs>,
+    overtime: Res<WeeklyOvertime>,
+    weather: Res<Weather>,
+    upgrades: Res<Upgrades>,
+    calendar: Res<Calendar>,
+    mut actions: ResMut<AvailableActions>,
+    locale: Res<Locale>,
+) {
+    if work.location.is_some() && work.last_shift_day != Some(clock.day) {
+        let commute_hours = DayModifiers::today(&weather, &upgrades).extra_commute_hours();
+        let mut shifts = vec![ShiftKind::Normal];
+        if !clock.weekday().is_weekend() {
+            shifts.push(ShiftKind::Overtime);
+        }
+        for shift in shifts {
+            let energy_cost =
+                buffs.shift_energy_cost(shift.energy_cost(commute_hours), clock.total_hours());
+            let label = shift.label(work.wage, commute_hours, energy_cost, *locale);
+            if let Some(reason) = calendar.closed_reason(clock.day) {
+                actions.offer_blocked(shift.action(), label, reason);
+            } else if shift == ShiftKind::Overtime && overtime.0 >= MAX_OVERTIME_PER_WEEK {
+                actions.offer_blocked(
+                    shift.action(),
+                    label,
+                    format!("At most {MAX_OVERTIME_PER_WEEK} overtime shifts a week"),
+                );
+            } else if energy.0 < energy_cost {
+                actions.offer_blocked(shift.action(), label, "Too tired");
+            } else {
+                actions.offer_labeled(shift.action(), label);
+            }
+        }
+    }
+    if offer.0.is_some() {
+        actions.offer(GameAction::AcceptJobOffer);
+        actions.offer(GameAction::DeclineJobOffer);
+    }
+}
+
+fn handle_job_actions(
+    mut pressed: EventReader<ActionPressed>,
+    market: Res<JobMarket>,
+    clock: Res<Clock>,
+    mut work: ResMut<WorkArea>,
+    mut offer: ResMut<JobOffer>,
+    mut money: ResMut<Money>,
+    mut energy: ResMut<Energy>,
+    buffs: Res<Buffs>,
+    mut happiness: ResMut<Happiness>,
+    mut overtime: ResMut<WeeklyOvertime>,
+    (weather, upgrades): (Res<Weather>, Res<Upgrades>),
+    calendar: Res<Calendar>,
+    mut rng: ResMut<GameRng>,
+    mut choices: ResMut<ChoiceLog>,
+    mut events: EventWriter<JobEvent>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        match action {
+            GameAction::WorkShift | GameAction::WorkOvertime
+                if work.location.is_some() && work.last_shift_day != Some(clock.day) =>
+            {
+                let shift = if *action == GameAction::WorkOvertime {
+                    ShiftKind::Overtime
+                } else {
+                    ShiftKind::Normal
+                };
+                let modifiers = DayModifiers::today(&weather, &upgrades);
+                let energy_cost = buffs.shift_energy_cost(
+                    shift.energy_cost(modifiers.extra_commute_hours()),
+                    clock.total_hours(),
+                );
+                if energy.0 < energy_cost
+                    || calendar.holiday(clock.day).is_some()
+                    || shift == ShiftKind::Overtime && overtime.0 >= MAX_OVERTIME_PER_WEEK
+                {
+                    continue;
+                }
+                if modifiers.shift_cancel_chance > 0.0 && rng.chance(modifiers.shift_cancel_chance)
+                {
+                    work.last_shift_day = Some(clock.day);
+                    events.write(JobEvent::ShiftCancelled);
+                    continue;
+                }
+                if shift == ShiftKind::Overtime {
+                    overtime.0 += 1;
+                }
+
+                let pay = shift.pay(work.wage);
+                money.amount += pay;
+                transactions.write(Transaction::Income(Category::Wages, pay));
+                energy.0 -= energy_cost;
+                happiness.change(shift.happiness_change());
+                work.last_shift_day = Some(clock.day);
+                work.shifts += 1;
+                events.write(JobEvent::ShiftWorked { pay });
+
+                if work.shifts.is_multiple_of(market.promotion_shifts) {
+                    work.wage += (work.wage * market.promotion_raise_percent / 100).max(1);
+                    events.write(JobEvent::Promoted { wage: work.wage });
+                }
+            }
+            GameAction::AcceptJobOffer => {
+                if let Some(job) = offer.0.take() {
+                    choices.record(
+                        clock.day,
+                        Choice::new(format!("Job offer: {}", job.title), "Accept")
+                            .with_outcome(format!("Hired at {:?}", job.employer)),
+                    );
+                    *work = WorkArea {
+                        location: Some(job.employer),
+                        title: job.title.clone(),
+                        wage: job.wage,
+                        ..default()
+                    };
+                    events.write(JobEvent::Hired(job));
+                }
+            }
+            GameAction::DeclineJobOffer => {
+                if let Some(job) = offer.0.take() {
+                    choices.record(
+                        clock.day,
+                        Choice::new(format!("Job offer: {}", job.title), "Decline"),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn announce_job_events(
+    mut events: EventReader<JobEvent>,
+    clock: Res<Clock>,
+    mut inbox: ResMut<Inbox>,
+    mut cards: ResMut<EventCardQueue>,
+    mut toasts: EventWriter<Toast>,
+    locale: Res<Locale>,
+) {
+    for event in events.read() {
+        let text = match event {
+            JobEvent::Offered(job) => {
+                let wage = format::money(job.wage, *locale);
+                // The offer is also mailed, so it can be taken up after the toast is gone.
+                let body = format!(
+                    "{:?} would hire you as {} for {wage}/h. The offer stands until you \
+                     accept or decline it.",
+                    job.employer, job.title
+                );
+                inbox.send_mail(
+                    clock.day,
+                    Mail::new(format!("Job offer: {}", job.title), body)
+                        .with_action("Accept offer", GameAction::AcceptJobOffer),
+                );
+                format!("Job offer: {} at {:?}, {wage}/h", job.title, job.employer)
+            }
+            JobEvent::Hired(job) => format!("Hired as {} at {:?}", job.title, job.employer),
+            JobEvent::ShiftWorked { pay } => format!("Earned {}", format::money(*pay, *locale)),
+            JobEvent::ShiftCancelled => {
+                "The storm closed your workplace today - no pay".to_string()
+            }
+            JobEvent::Promoted { wage } => {
+                format!("Promoted! You now earn {}/h", format::money(*wage, *locale))
+            }
+            JobEvent::LaidOff { severance } => {
+                let body = format!(
+                    "Your employer let you go. Severance: {}",
+                    format::money(*severance, *locale)
+                );
+                cards.push(CardRequest::new("Laid off", body).with_priority(2));
+                continue;
+            }
+        };
+        toasts.write(Toast::new(text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::sim::{calendar::CALENDAR_PATH, clock::DAYS_PER_WEEK, needs::MAX_ENERGY};
+
+    const DAYS: u32 = 120;
+
+    fn market() -> JobMarket {
+        let path = format!("assets/{JOB_MARKET_PATH}");
+        JobMarket::from(
+            ron::from_str::<JobMarketData>(&std::fs::read_to_string(path).unwrap()).unwrap(),
+        )
+    }
+
+    /// Each day's market rolls over [`DAYS`] for `seed`, taking up every offer.
+    fn career(seed: u64) -> Vec<(u32, JobEvent)> {
+        let mut world = World::new();
+        world.insert_resource(market());
+        world.insert_resource(GameRng::seeded(seed));
+        world.insert_resource(Money { amount: 0 });
+        world.init_resource::<WorkArea>();
+        world.init_resource::<JobOffer>();
+        world.init_resource::<Events<JobEvent>>();
+        world.init_resource::<Events<Transaction>>();
+        let mut career = Vec::new();
+        for day in 1..=DAYS {
+            world.run_system_once(roll_job_market).unwrap();
+            if let Some(job) = world.resource_mut::<JobOffer>().0.take() {
+                assert!(job.wage > world.resource::<WorkArea>().wage);
+                *world.resource_mut::<WorkArea>() = WorkArea {
+                    location: Some(job.employer),
+                    title: job.title,
+                    wage: job.wage,
+                    ..default()
+                };
+            }
+            let mut events = world.resource_mut::<Events<JobEvent>>();
+            career.extend(events.drain().map(|event| (day, event)));
+        }
+        career
+    }
+
+    #[test]
+    fn a_seed_always_has_the_same_career() {
+        assert_eq!(career(7), career(7));
+        assert_ne!(career(7), career(8));
+    }
+
+    #[test]
+    fn severance_is_the_shifts_worth_of_the_lost_job() {
+        let market = market();
+        let layoffs = (0..20)
+            .flat_map(career)
+            .filter_map(|(_, event)| match event {
+                JobEvent::LaidOff { severance } => Some(severance),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(!layoffs.is_empty());
+        let wages: Vec<_> = market.jobs.iter().map(|job| job.wage).collect();
+        for severance in layoffs {
+            assert!(
+                wages.iter().any(
+                    |wage| ShiftKind::Normal.pay(*wage) * market.severance_shifts == severance
+                ),
+                "{severance}"
+            );
+        }
+    }
+
+    #[test]
+    fn the_career_of_a_seed_is_pinned() {
+        let summary: Vec<_> = career(1)
+            .into_iter()
+            .map(|(day, event)| match event {
+                JobEvent::Offered(job) => format!("{day}: offered {}", job.title),
+                JobEvent::LaidOff { severance } => format!("{day}: laid off with {severance}"),
+                event => format!("{day}: {event:?}"),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                "9: offered Account Manager",
+                "12: offered Property Manager",
+                "115: laid off with 7200",
+                "118: offered Night Clerk",
+                "119: offered Junior Analyst",
+                "120: offered Property Manager",
+            ]
+        );
+    }
+
+    #[test]
+    fn overtime_pays_time_and_a_half_past_a_normal_shift() {
+        for wage in [1, 12, 15, 35] {
+            assert_eq!(ShiftKind::Normal.pay(wage), SHIFT_HOURS * wage);
+            let extra = OVERTIME_SHIFT_HOURS - SHIFT_HOURS;
+            assert_eq!(
+                ShiftKind::Overtime.pay(wage) * 2,
+                (SHIFT_HOURS * 2 + extra * 3) * wage
+            );
+        }
+        assert_eq!(ShiftKind::Overtime.pay(15), 210);
+        assert_eq!(ShiftKind::Normal.energy_cost(0), SHIFT_ENERGY);
+        assert_eq!(
+            ShiftKind::Overtime.energy_cost(2),
+            SHIFT_ENERGY * 2 + 2 * COMMUTE_ENERGY_PER_HOUR
+        );
+        assert_eq!(ShiftKind::Normal.happiness_change(), 0);
+        assert_eq!(
+            ShiftKind::Overtime.happiness_change(),
+            OVERTIME_HAPPINESS_CHANGE
+        );
+    }
+
+    #[test]
+    fn overtime_is_paid_up_to_the_weekly_cap() {
+        const WAGE: u32 = 15;
+        let mut world = World::new();
+        world.insert_resource(market());
+        world.insert_resource(
+            ron::from_str::<Calendar>(
+                &std::fs::read_to_string(format!("assets/{CALENDAR_PATH}")).unwrap(),
+            )
+            .unwrap(),
+        );
+        world.insert_resource(GameRng::seeded(1));
+        world.insert_resource(Money { amount: 0 });
+        world.insert_resource(WorkArea {
+            location: Some(Area::DeadbeatArea),
+            wage: WAGE,
+            ..default()
+        });
+        world.init_resource::<JobOffer>();
+        world.init_resource::<Buffs>();
+        world.init_resource::<Happiness>();
+        world.init_resource::<WeeklyOvertime>();
+        world.init_resource::<Weather>();
+        world.init_resource::<Upgrades>();
+        world.init_resource::<ChoiceLog>();
+        world.init_resource::<Events<ActionPressed>>();
+        world.init_resource::<Events<JobEvent>>();
+        world.init_resource::<Events<Transaction>>();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(handle_job_actions);
+
+        let mut overtime_pay = Vec::new();
+        for day in 1..=DAYS_PER_WEEK + 2 {
+            if (day - 1).is_multiple_of(DAYS_PER_WEEK) {
+                world.run_system_once(reset_weekly_overtime).unwrap();
+            }
+            world.insert_resource(Clock { day, hour: 9 });
+            world.insert_resource(Energy(MAX_ENERGY));
+            let before = world.resource::<Money>().amount;
+            world.send_event(ActionPressed(GameAction::WorkOvertime));
+            schedule.run(&mut world);
+            overtime_pay.push(world.resource::<Money>().amount - before);
+        }
+        // Two long shifts on Monday and Tuesday, then none until next Monday.
+        let paid = ShiftKind::Overtime.pay(WAGE);
+        assert_eq!(overtime_pay, [paid, paid, 0, 0, 0, 0, 0, paid, paid]);
+        let wages: u32 = world
+            .resource_mut::<Events<Transaction>>()
+            .drain()
+            .map(|transaction| match transaction {
+                Transaction::Income(Category::Wages, amount) => amount,
+                transaction => panic!("{transaction:?}"),
+            })
+            .sum();
+        assert_eq!(wages, 4 * paid);
+    }
+}
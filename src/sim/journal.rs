@@ -0,0 +1,167 @@
+//! The photo journal: a picture of the game taken at the run's milestones, like the first
+//! job or getting out of debt, and at lesser moments like a vignette starting. Anything can
+//! ask for one with a [`PhotoRequest`]; taking it and showing the [`Journal`] is up to
+//! [`crate::screens::journal`].
+//!
+//! The journal keeps [`MAX_PHOTOS`]. Past that the oldest photo that isn't a milestone
+//! goes, or the oldest of all once every photo is one. The photos themselves are files in
+//! the profile's [`JOURNAL_DIR`], next to the quicksave.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::economy::{Area, Ending, HomeArea};
+use super::jobs::JobEvent;
+use crate::AppState;
+use crate::screens::nav::{Screen, ScreenChanged};
+
+/// The most photos a run's journal holds.
+pub const MAX_PHOTOS: usize = 20;
+/// Where the photos are kept, in the profile's directory.
+pub const JOURNAL_DIR: &str = "journal";
+
+/// Asks for a photo of the game as it looks this frame.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct PhotoRequest {
+    /// The caption, like "First job".
+    pub label: String,
+    /// Milestones are kept over other photos when the journal is full.
+    pub milestone: bool,
+}
+
+impl PhotoRequest {
+    pub fn milestone(label: impl Into<String>) -> Self {
+        PhotoRequest {
+            label: label.into(),
+            milestone: true,
+        }
+    }
+
+    pub fn moment(label: impl Into<String>) -> Self {
+        PhotoRequest {
+            label: label.into(),
+            milestone: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct Photo {
+    pub label: String,
+    pub day: u32,
+    pub milestone: bool,
+    /// The image's file name in [`JOURNAL_DIR`].
+    pub file: String,
+}
+
+/// The run's photos, oldest first.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Journal {
+    pub photos: Vec<Photo>,
+    /// Photos taken so far this run, evicted ones included, to name the next one.
+    pub taken: u32,
+    /// Whether the first job's photo was taken.
+    pub hired: bool,
+    /// The home at the last photo of a move, to tell when the player moves again.
+    pub home: Option<Area>,
+}
+
+impl Journal {
+    /// Adds a photo for `request` on `day`, and returns it with the one it pushed out of
+    /// the journal, if any.
+    pub fn add(&mut self, request: &PhotoRequest, day: u32) -> (Photo, Option<Photo>) {
+        self.taken += 1;
+        let photo = Photo {
+            label: request.label.clone(),
+            day,
+            milestone: request.milestone,
+            file: format!("{}-{}.png", unix_millis(), self.taken),
+        };
+        self.photos.push(photo.clone());
+        let evicted = (self.photos.len() > MAX_PHOTOS).then(|| {
+            let oldest = self
+                .photos
+                .iter()
+                .position(|photo| !photo.milestone)
+                .unwrap_or_default();
+            self.photos.remove(oldest)
+        });
+        (photo, evicted)
+    }
+}
+
+// Names photos apart from those of earlier runs that a quicksave might still point to.
+fn unix_millis() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default()
+}
+
+pub fn journal_plugin(app: &mut App) {
+    app.register_type::<Journal>()
+        .init_resource::<Journal>()
+        .add_event::<PhotoRequest>()
+        .add_systems(
+            Update,
+            (
+                photo_first_job,
+                photo_move.run_if(resource_exists_and_changed::<HomeArea>),
+                photo_win,
+            )
+                .run_if(in_state(AppState::Game)),
+        );
+}
+
+fn photo_first_job(
+    mut events: EventReader<JobEvent>,
+    mut journal: ResMut<Journal>,
+    mut photos: EventWriter<PhotoRequest>,
+) {
+    for event in events.read() {
+        if let JobEvent::Hired(job) = event
+            && !journal.hired
+        {
+            journal.hired = true;
+            photos.write(PhotoRequest::milestone(format!("First job: {}", job.title)));
+        }
+    }
+}
+
+// The run's first home is where the journal starts, not a move.
+fn photo_move(
+    home: Res<HomeArea>,
+    mut journal: ResMut<Journal>,
+    mut photos: EventWriter<PhotoRequest>,
+) {
+    match journal.home {
+        None => journal.home = Some(home.location),
+        Some(location) if location != home.location => {
+            journal.home = Some(home.location);
+            photos.write(PhotoRequest::milestone(format!(
+                "Moved to the {}",
+                home.location.label()
+            )));
+        }
+        Some(_) => {}
+    }
+}
+
+// The win is caught as it's asked for, while the game is still on screen.
+fn photo_win(
+    mut changes: EventReader<ScreenChanged>,
+    ending: Res<Ending>,
+    mut photos: EventWriter<PhotoRequest>,
+) {
+    if changes
+        .read()
+        .any(|change| change.to == Screen::App(AppState::Win))
+    {
+        photos.write(PhotoRequest::milestone(match *ending {
+            Ending::Wealth => "Saved up enough to win",
+            Ending::Philanthropy => "Won as a philanthropist",
+            Ending::InnerCircle => "Joined the inner circle",
+        }));
+    }
+}
@@ -0,0 +1,522 @@
+//! The landlord: once a month the player can try to talk the rent down. A good payment
+//! record helps, but pushing too hard can annoy the landlord into a surcharge for the rest
+//! of the month. The agreed terms live in [`RentTerms`] and apply when rent is charged, so
+//! the catalog rents never change.
+//!
+//! The landlord also raises the rent on a schedule, with some notice. Increases are kept in
+//! [`RentIncreases`] for the home they were made at, so a new home starts again from its
+//! listed rent.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    SimSet,
+    bills::issue_bills,
+    clock::{Clock, day_started},
+    economy::{Area, HomeArea},
+    event_cards::{CardId, CardRequest, EventCardQueue},
+    ledger::Ledger,
+    mail::{Inbox, Mail},
+    new_game_plus::PriceLevel,
+    rng::GameRng,
+};
+use crate::data::register_ron_resource;
+use crate::format;
+use crate::settings::Locale;
+
+pub const LANDLORD_PATH: &str = "data/world.landlord.ron";
+/// Negotiations never succeed more often than this, however good the record.
+const MAX_SUCCESS_CHANCE: f32 = 0.95;
+
+/// One way of asking, as offered on the negotiation card.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Approach {
+    pub label: String,
+    /// Added to the chance of success.
+    pub success_modifier: f32,
+    /// Added to the chance that a refusal annoys the landlord.
+    pub annoyance_modifier: f32,
+    /// How the end screens sum up asking this way, see [`super::choices::Choice::notable`].
+    #[serde(default)]
+    pub notable: Option<String>,
+}
+
+/// Landlord balance data.
+#[derive(Asset, TypePath, Resource, Deserialize, Clone, Debug)]
+pub struct LandlordConfig {
+    pub base_success_chance: f32,
+    /// Added to the chance of success for every rent payment made on time.
+    pub success_per_payment: f32,
+    pub max_payment_bonus: f32,
+    /// Chance that a refusal comes with a surcharge.
+    pub base_annoyance_chance: f32,
+    /// Range of the rent cut a successful negotiation gets, in percent.
+    pub discount_percent: (u32, u32),
+    /// Surcharge for the rest of the month after annoying the landlord, in percent.
+    pub surcharge_percent: u32,
+    pub approaches: Vec<Approach>,
+    /// Rent goes up on every day that's a multiple of this.
+    pub increase_every_days: u32,
+    /// How much each increase adds, in percent, before the run's [`PriceLevel`] scales it.
+    pub increase_percent: u32,
+    /// Days between the announcement of an increase and the increase.
+    pub increase_notice_days: u32,
+    /// Celebrate every time the player gets out of debt, not just the first, if with less
+    /// fuss. See [`super::debt`].
+    #[serde(default)]
+    pub repeat_debt_free: bool,
+}
+
+/// Chances of each outcome for an approach.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NegotiationOdds {
+    pub success: f32,
+    /// Chance of a surcharge, out of the whole negotiation.
+    pub annoyance: f32,
+}
+
+/// How likely `approach` is to work with `payments_on_time` rent payments behind the
+/// player.
+pub fn negotiation_odds(
+    config: &LandlordConfig,
+    approach: &Approach,
+    payments_on_time: u32,
+) -> NegotiationOdds {
+    let record =
+        (payments_on_time as f32 * config.success_per_payment).min(config.max_payment_bonus);
+    let success = (config.base_success_chance + record + approach.success_modifier)
+        .clamp(0.0, MAX_SUCCESS_CHANCE);
+    let annoyance = (config.base_annoyance_chance + approach.annoyance_modifier).clamp(0.0, 1.0);
+    NegotiationOdds {
+        success,
+        annoyance: (1.0 - success) * annoyance,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NegotiationOutcome {
+    /// Rent goes down by this many percent for good.
+    Discount(u32),
+    Refused,
+    /// Rent goes up by this many percent until the month ends.
+    Surcharge(u32),
+}
+
+/// Rolls the outcome of a negotiation.
+pub fn negotiate(
+    config: &LandlordConfig,
+    odds: NegotiationOdds,
+    rng: &mut GameRng,
+) -> NegotiationOutcome {
+    let roll = rng.next_f32();
+    if roll < odds.success {
+        let (min, max) = config.discount_percent;
+        let spread = max.saturating_sub(min) + 1;
+        NegotiationOutcome::Discount(min + rng.index(spread as usize) as u32)
+    } else if roll < odds.success + odds.annoyance {
+        NegotiationOutcome::Surcharge(config.surcharge_percent)
+    } else {
+        NegotiationOutcome::Refused
+    }
+}
+
+/// What the player agreed with the landlord, applied on top of the home's listed rent.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct RentTerms {
+    /// Permanent rent cut, in percent.
+    pub discount_percent: u32,
+    /// Surcharge in percent, and the month it applies to.
+    pub surcharge: Option<(u32, u32)>,
+    pub payments_on_time: u32,
+    /// Month of the last negotiation, to allow one a month.
+    pub last_negotiated_month: Option<u32>,
+}
+
+impl RentTerms {
+    /// The rent for `listed_rent` in `month` under these terms.
+    pub fn rent(&self, listed_rent: u32, month: u32) -> u32 {
+        let percent = (100 + self.surcharge_percent(month)).saturating_sub(self.discount_percent);
+        (listed_rent * percent).div_ceil(100)
+    }
+
+    fn surcharge_percent(&self, month: u32) -> u32 {
+        match self.surcharge {
+            Some((percent, surcharge_month)) if surcharge_month == month => percent,
+            _ => 0,
+        }
+    }
+
+    /// What these terms do to the rent in `month`, a line each.
+    pub fn modifiers(&self, month: u32) -> Vec<String> {
+        let mut modifiers = Vec::new();
+        if self.discount_percent > 0 {
+            modifiers.push(format!("Negotiated -{}%", self.discount_percent));
+        }
+        let surcharge = self.surcharge_percent(month);
+        if surcharge > 0 {
+            modifiers.push(format!("Landlord surcharge +{surcharge}%"));
+        }
+        modifiers
+    }
+
+    pub fn can_negotiate(&self, month: u32) -> bool {
+        self.last_negotiated_month != Some(month)
+    }
+
+    pub fn apply(&mut self, outcome: NegotiationOutcome, month: u32) {
+        self.last_negotiated_month = Some(month);
+        match outcome {
+            NegotiationOutcome::Discount(percent) => self.discount_percent += percent,
+            NegotiationOutcome::Refused => {}
+            NegotiationOutcome::Surcharge(percent) => self.surcharge = Some((percent, month)),
+        }
+    }
+}
+
+/// Rent increases at the player's home, on top of its listed rent.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct RentIncreases {
+    /// The home the increases were made at.
+    pub home: Area,
+    /// Rent after every increase so far, in percent of the listed rent.
+    pub percent: u32,
+    /// An announced increase: the new percentage and the day it applies from.
+    pub announced: Option<(u32, u32)>,
+}
+
+impl Default for RentIncreases {
+    fn default() -> Self {
+        RentIncreases::new(Area::default())
+    }
+}
+
+impl RentIncreases {
+    pub fn new(home: Area) -> Self {
+        RentIncreases {
+            home,
+            percent: 100,
+            announced: None,
+        }
+    }
+
+    /// The listed rent of `home` after the increases made there.
+    pub fn listed_rent(&self, home: &HomeArea) -> u32 {
+        if home.location == self.home {
+            (home.rent * self.percent).div_ceil(100)
+        } else {
+            home.rent
+        }
+    }
+
+    /// The increase announced at `home`, if any: the new percentage and its day.
+    pub fn announced(&self, home: &HomeArea) -> Option<(u32, u32)> {
+        self.announced.filter(|_| home.location == self.home)
+    }
+}
+
+/// The first day after `day` when rent goes up.
+pub fn next_increase_day(config: &LandlordConfig, day: u32) -> Option<u32> {
+    let every = config.increase_every_days;
+    (every > 0 && config.increase_percent > 0).then(|| (day / every + 1) * every)
+}
+
+/// The latest rent increase card, if it offers to negotiate. Its first button opens the
+/// negotiation.
+#[derive(Resource, Debug, Default)]
+pub struct RentIncreaseCard(pub Option<CardId>);
+
+pub fn landlord_plugin(app: &mut App) {
+    register_ron_resource::<LandlordConfig>(app, &["landlord.ron"]);
+    app.register_type::<RentTerms>()
+        .register_type::<RentIncreases>()
+        .init_resource::<RentTerms>()
+        .init_resource::<RentIncreases>()
+        .init_resource::<RentIncreaseCard>()
+        .add_systems(
+            FixedUpdate,
+            (announce_rent_increase, raise_rent)
+                .chain()
+                .run_if(day_started)
+                .in_set(SimSet::Economy)
+                .before(issue_bills),
+        );
+}
+
+fn announce_rent_increase(
+    config: Res<LandlordConfig>,
+    clock: Res<Clock>,
+    home: Res<HomeArea>,
+    terms: Res<RentTerms>,
+    ledger: Res<Ledger>,
+    prices: Res<PriceLevel>,
+    mut increases: ResMut<RentIncreases>,
+    mut inbox: ResMut<Inbox>,
+    (mut cards, mut card): (ResMut<EventCardQueue>, ResMut<RentIncreaseCard>),
+    locale: Res<Locale>,
+) {
+    let Some(day) = next_increase_day(&config, clock.day) else {
+        return;
+    };
+    if day - clock.day != config.increase_notice_days.max(1) {
+        return;
+    }
+    if increases.home != home.location {
+        *increases = RentIncreases::new(home.location);
+    }
+    let percent = (increases.percent * (100 + prices.scale(config.increase_percent))).div_ceil(100);
+    increases.announced = Some((percent, day));
+    let raised = RentIncreases {
+        percent,
+        ..increases.clone()
+    };
+    let rent = terms.rent(raised.listed_rent(&home), ledger.current.month);
+    let body = format!(
+        "Dear tenant, your rent will increase to {} on day {day}.",
+        format::money(rent, *locale)
+    );
+    let request = CardRequest::new("Rent increase", body.clone()).with_priority(1);
+    card.0 = if terms.can_negotiate(ledger.current.month) {
+        Some(cards.push(request.with_buttons(["Negotiate", "OK"])))
+    } else {
+        cards.push(request);
+        None
+    };
+    inbox.send_mail(clock.day, Mail::new("Rent increase", body).critical());
+}
+
+// Runs on the day itself rather than a count from the announcement, so an increase
+// announced before a save still lands on the day the letter said.
+fn raise_rent(clock: Res<Clock>, mut increases: ResMut<RentIncreases>) {
+    if let Some((percent, day)) = increases.announced
+        && clock.day >= day
+    {
+        increases.percent = percent;
+        increases.announced = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+    use crate::AppState;
+    use crate::sim::{
+        Dirty,
+        clock::clock_plugin,
+        quicksave::{restore_run, run_snapshot},
+    };
+    use crate::testing::sleep_until;
+    use crate::toast::Toast;
+
+    const RENT: u32 = 1000;
+    /// [`RENT`] after one increase of the test config's 5%.
+    const RAISED: u32 = 1050;
+
+    fn config() -> LandlordConfig {
+        LandlordConfig {
+            base_success_chance: 0.3,
+            success_per_payment: 0.05,
+            max_payment_bonus: 0.3,
+            base_annoyance_chance: 0.2,
+            discount_percent: (5, 10),
+            surcharge_percent: 5,
+            approaches: Vec::new(),
+            increase_every_days: 30,
+            increase_percent: 5,
+            increase_notice_days: 7,
+            repeat_debt_free: false,
+        }
+    }
+
+    fn approach(success_modifier: f32, annoyance_modifier: f32) -> Approach {
+        Approach {
+            label: String::new(),
+            success_modifier,
+            annoyance_modifier,
+            notable: None,
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn odds_grow_with_the_payment_record_up_to_the_bonus_cap() {
+        let config = config();
+        let polite = approach(0.0, 0.0);
+        let success = |payments| negotiation_odds(&config, &polite, payments).success;
+        assert_close(success(0), 0.3);
+        assert_close(success(2), 0.4);
+        assert_close(success(6), 0.6);
+        assert_close(success(50), 0.6);
+    }
+
+    #[test]
+    fn odds_never_reach_certainty_or_go_negative() {
+        let config = config();
+        let pushy = negotiation_odds(&config, &approach(1.0, 0.0), 50);
+        assert_close(pushy.success, MAX_SUCCESS_CHANCE);
+        let hopeless = negotiation_odds(&config, &approach(-1.0, 2.0), 0);
+        assert_close(hopeless.success, 0.0);
+        assert_close(hopeless.annoyance, 1.0);
+    }
+
+    #[test]
+    fn annoyance_only_comes_out_of_refusals() {
+        let odds = negotiation_odds(&config(), &approach(0.15, 0.3), 2);
+        assert_close(odds.success, 0.55);
+        assert_close(odds.annoyance, 0.45 * 0.5);
+    }
+
+    #[test]
+    fn rolled_outcomes_follow_the_odds() {
+        let config = config();
+        let odds = NegotiationOdds {
+            success: 0.5,
+            annoyance: 0.2,
+        };
+        let mut rng = GameRng::seeded(7);
+        let (mut discounts, mut surcharges) = (0, 0);
+        let rolls = 10_000;
+        for _ in 0..rolls {
+            match negotiate(&config, odds, &mut rng) {
+                NegotiationOutcome::Discount(percent) => {
+                    assert!((5..=10).contains(&percent));
+                    discounts += 1;
+                }
+                NegotiationOutcome::Surcharge(percent) => {
+                    assert_eq!(percent, 5);
+                    surcharges += 1;
+                }
+                NegotiationOutcome::Refused => {}
+            }
+        }
+        assert!((discounts as f32 / rolls as f32 - 0.5).abs() < 0.03);
+        assert!((surcharges as f32 / rolls as f32 - 0.2).abs() < 0.03);
+    }
+
+    #[test]
+    fn terms_apply_at_charge_time_and_surcharges_end_with_the_month() {
+        let mut terms = RentTerms::default();
+        terms.apply(NegotiationOutcome::Discount(10), 3);
+        assert_eq!(terms.rent(1000, 3), 900);
+        assert!(!terms.can_negotiate(3));
+        assert!(terms.can_negotiate(4));
+
+        terms.apply(NegotiationOutcome::Surcharge(5), 4);
+        assert_eq!(terms.rent(1000, 4), 950);
+        assert_eq!(terms.rent(1000, 5), 900);
+    }
+
+    /// A tenant at a home listing [`RENT`], with the landlord raising it every month.
+    fn landlord_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<AppState>()
+            .add_plugins(clock_plugin)
+            .register_type::<RentIncreases>()
+            .register_type::<Inbox>()
+            .add_event::<Toast>()
+            .insert_resource(config())
+            .insert_resource(HomeArea {
+                location: Area::DeadbeatArea,
+                rent: RENT,
+                scenario_rent: None,
+            })
+            .insert_resource(RentIncreases::new(Area::DeadbeatArea))
+            .init_resource::<RentTerms>()
+            .init_resource::<Ledger>()
+            .init_resource::<PriceLevel>()
+            .init_resource::<Inbox>()
+            .init_resource::<EventCardQueue>()
+            .init_resource::<RentIncreaseCard>()
+            .init_resource::<Locale>()
+            .init_resource::<Dirty>()
+            .add_systems(
+                FixedUpdate,
+                (announce_rent_increase, raise_rent)
+                    .chain()
+                    .run_if(day_started)
+                    .after(SimSet::Clock),
+            );
+        app.update();
+        app
+    }
+
+    fn rent(app: &App) -> u32 {
+        let world = app.world();
+        world
+            .resource::<RentIncreases>()
+            .listed_rent(world.resource::<HomeArea>())
+    }
+
+    fn letters(app: &App) -> Vec<(u32, String)> {
+        let inbox = app.world().resource::<Inbox>();
+        let letters = inbox
+            .messages
+            .iter()
+            .filter(|mail| mail.title == "Rent increase");
+        letters.map(|mail| (mail.day, mail.body.clone())).collect()
+    }
+
+    #[test]
+    fn the_increase_is_announced_the_notice_period_ahead() {
+        let config = config();
+        let increase_day = config.increase_every_days;
+        let notice_day = increase_day - config.increase_notice_days;
+        let mut app = landlord_app();
+
+        sleep_until(&mut app, notice_day - 1);
+        assert!(letters(&app).is_empty());
+
+        sleep_until(&mut app, notice_day);
+        assert_eq!(
+            letters(&app),
+            [(
+                notice_day,
+                format!("Dear tenant, your rent will increase to $1,050 on day {increase_day}.")
+            )]
+        );
+
+        sleep_until(&mut app, increase_day - 1);
+        assert_eq!(rent(&app), RENT);
+        sleep_until(&mut app, increase_day);
+        assert_eq!(rent(&app), RAISED);
+        assert_eq!(app.world().resource::<RentIncreases>().announced, None);
+    }
+
+    #[test]
+    fn a_save_loaded_mid_notice_raises_the_rent_on_the_announced_day() {
+        let config = config();
+        let increase_day = config.increase_every_days;
+        let mut app = landlord_app();
+        sleep_until(&mut app, increase_day - 2);
+        let world = app.world();
+        let text = run_snapshot(world)
+            .serialize(&world.resource::<AppTypeRegistry>().read())
+            .unwrap();
+
+        let mut app = landlord_app();
+        restore_run(app.world_mut(), &text);
+        assert_eq!(app.world().resource::<Clock>().day, increase_day - 2);
+        assert_eq!(
+            app.world().resource::<RentIncreases>().announced,
+            Some((100 + config.increase_percent, increase_day))
+        );
+
+        sleep_until(&mut app, increase_day - 1);
+        assert_eq!(rent(&app), RENT);
+        sleep_until(&mut app, increase_day);
+        assert_eq!(rent(&app), RAISED);
+        // The letter came with the save and isn't sent again.
+        assert_eq!(letters(&app).len(), 1);
+    }
+}
@@ -0,0 +1,397 @@
+//! The ledger: every change to the player's money is reported as a [`Transaction`] and
+//! summed up per category and month, for the monthly report.
+
+use std::collections::BTreeMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    SimSet,
+    bank::Bank,
+    clock::Clock,
+    economy::Money,
+    market::{Market, Portfolio},
+    passive_income::IncomeSources,
+};
+
+pub const DAYS_PER_MONTH: u32 = 30;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect, Serialize, Deserialize,
+)]
+pub enum Category {
+    Wages,
+    Freelance,
+    Severance,
+    Investments,
+    /// Rewards from the game itself, like finishing the first week's checklist.
+    Bonuses,
+    /// What the player's income sources make, see [`super::passive_income`].
+    PassiveIncome,
+    Rent,
+    /// Charged on overdue rent, see [`super::bills`].
+    LateFees,
+    Utilities,
+    Holidays,
+    Food,
+    Purchases,
+    Donations,
+    Theft,
+    /// Fixing the player's income sources.
+    Maintenance,
+    /// Purchases taken back, see [`super::undo`].
+    Refunds,
+}
+
+impl Category {
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::Wages => "Wages",
+            Category::Freelance => "Freelance",
+            Category::Severance => "Severance",
+            Category::Investments => "Investments",
+            Category::Bonuses => "Bonuses",
+            Category::PassiveIncome => "Passive income",
+            Category::Rent => "Rent",
+            Category::LateFees => "Late fees",
+            Category::Utilities => "Utilities",
+            Category::Holidays => "Holidays",
+            Category::Food => "Food",
+            Category::Purchases => "Purchases",
+            Category::Donations => "Donations",
+            Category::Theft => "Theft",
+            Category::Maintenance => "Maintenance",
+            Category::Refunds => "Refunds",
+        }
+    }
+}
+
+/// Money gained or spent. Send one whenever [`Money`] or [`Bank`] savings change hands
+/// with the outside world.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transaction {
+    Income(Category, u32),
+    Expense(Category, u32),
+}
+
+/// Totals for one month of play.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct MonthSummary {
+    /// Starts at 1.
+    pub month: u32,
+    pub income: BTreeMap<Category, u32>,
+    pub expenses: BTreeMap<Category, u32>,
+    pub start_net_worth: u32,
+    pub end_net_worth: u32,
+}
+
+impl MonthSummary {
+    pub fn total_income(&self) -> u32 {
+        self.income.values().sum()
+    }
+
+    pub fn total_expenses(&self) -> u32 {
+        self.expenses.values().sum()
+    }
+
+    pub fn net_worth_change(&self) -> i64 {
+        i64::from(self.end_net_worth) - i64::from(self.start_net_worth)
+    }
+
+    /// Rent as a share of income, or `None` for a month without income.
+    pub fn rent_to_income(&self) -> Option<f32> {
+        let income = self.total_income();
+        let rent = self.expenses.get(&Category::Rent).copied().unwrap_or(0);
+        (income > 0).then(|| rent as f32 / income as f32)
+    }
+
+    /// A one-line take on how the month went.
+    pub fn verdict(&self) -> String {
+        let income = self.total_income();
+        let expenses = self.total_expenses();
+        if income == 0 {
+            return if expenses == 0 {
+                "A quiet month.".to_string()
+            } else {
+                "No income this month - find some work.".to_string()
+            };
+        }
+
+        let saved = (i64::from(income) - i64::from(expenses)) * 100 / i64::from(income);
+        match saved {
+            20.. => format!("You saved {saved}% of income - nice."),
+            1..20 => format!("You saved {saved}% of income."),
+            0 => "You broke even.".to_string(),
+            _ => format!("You spent {}% more than you earned.", -saved),
+        }
+    }
+}
+
+/// The month in progress and every finished one.
+#[derive(Resource, Debug, Clone, Default, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Ledger {
+    pub current: MonthSummary,
+    pub history: Vec<MonthSummary>,
+}
+
+impl Ledger {
+    pub fn new(net_worth: u32) -> Self {
+        Ledger {
+            current: MonthSummary {
+                month: 1,
+                start_net_worth: net_worth,
+                ..default()
+            },
+            history: Vec::new(),
+        }
+    }
+
+    /// Everything spent on `category` this run.
+    pub fn spent_on(&self, category: Category) -> u32 {
+        self.history
+            .iter()
+            .chain([&self.current])
+            .filter_map(|month| month.expenses.get(&category))
+            .sum()
+    }
+
+    /// What was spent a day on `day`, on average over the last finished month and this one
+    /// so far, leaving out rent and its late fees.
+    pub fn daily_spending_besides_rent(&self, day: u32) -> u32 {
+        let months: Vec<_> = self
+            .history
+            .last()
+            .into_iter()
+            .chain([&self.current])
+            .collect();
+        let spent: u32 = months
+            .iter()
+            .flat_map(|month| &month.expenses)
+            .filter(|(category, _)| !matches!(category, Category::Rent | Category::LateFees))
+            .map(|(_, amount)| amount)
+            .sum();
+        let days =
+            (day.max(1) - 1) % DAYS_PER_MONTH + 1 + DAYS_PER_MONTH * (months.len() as u32 - 1);
+        spent.div_ceil(days)
+    }
+}
+
+/// Sent when a month ends and its summary joins [`Ledger::history`].
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MonthEnded;
+
+/// What the player is worth, part by part. There's no debt to take off yet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetWorth {
+    pub cash: u32,
+    pub savings: u32,
+    /// The portfolio at today's prices.
+    pub investments: u32,
+    /// The income sources at what was paid for them.
+    pub income_sources: u32,
+}
+
+impl NetWorth {
+    pub fn new(
+        money: &Money,
+        bank: &Bank,
+        portfolio: &Portfolio,
+        market: &Market,
+        sources: &IncomeSources,
+    ) -> Self {
+        NetWorth {
+            cash: money.amount,
+            savings: bank.savings,
+            investments: market
+                .funds
+                .iter()
+                .map(|fund| portfolio.holding(&fund.name).value(fund.unit_price()))
+                .sum(),
+            income_sources: sources.value(),
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.cash + self.savings + self.investments + self.income_sources
+    }
+}
+
+/// Everything [`NetWorth`] is made of, so the HUD, the goals and the reports all add it up
+/// the same way.
+#[derive(SystemParam)]
+pub struct Wealth<'w> {
+    money: Res<'w, Money>,
+    bank: Res<'w, Bank>,
+    portfolio: Res<'w, Portfolio>,
+    market: Res<'w, Market>,
+    sources: Res<'w, IncomeSources>,
+}
+
+impl Wealth<'_> {
+    pub fn net_worth(&self) -> NetWorth {
+        NetWorth::new(
+            &self.money,
+            &self.bank,
+            &self.portfolio,
+            &self.market,
+            &self.sources,
+        )
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.money.is_changed()
+            || self.bank.is_changed()
+            || self.portfolio.is_changed()
+            || self.market.is_changed()
+            || self.sources.is_changed()
+    }
+}
+
+/// Run condition for a change to any part of the player's [`NetWorth`].
+pub fn net_worth_changed(wealth: Wealth) -> bool {
+    wealth.is_changed()
+}
+
+/// Run condition for the first tick of a new month, at midnight.
+pub fn month_started(clock: Res<Clock>) -> bool {
+    clock.hour == 0 && clock.day > 1 && (clock.day - 1).is_multiple_of(DAYS_PER_MONTH)
+}
+
+pub fn ledger_plugin(app: &mut App) {
+    app.register_type::<Ledger>()
+        .register_type::<Category>()
+        .init_resource::<Ledger>()
+        .add_event::<Transaction>()
+        .add_event::<MonthEnded>()
+        .add_systems(
+            FixedUpdate,
+            (record_transactions, close_month.run_if(month_started))
+                .chain()
+                .in_set(SimSet::Ledger),
+        );
+}
+
+pub fn record_transactions(mut transactions: EventReader<Transaction>, mut ledger: ResMut<Ledger>) {
+    for transaction in transactions.read() {
+        let (totals, category, amount) = match *transaction {
+            Transaction::Income(category, amount) => (&mut ledger.current.income, category, amount),
+            Transaction::Expense(category, amount) => {
+                (&mut ledger.current.expenses, category, amount)
+            }
+        };
+        if amount > 0 {
+            *totals.entry(category).or_default() += amount;
+        }
+    }
+}
+
+fn close_month(wealth: Wealth, mut ledger: ResMut<Ledger>, mut ended: EventWriter<MonthEnded>) {
+    let net_worth = wealth.net_worth().total();
+    let next = MonthSummary {
+        month: ledger.current.month + 1,
+        start_net_worth: net_worth,
+        ..default()
+    };
+    let mut finished = std::mem::replace(&mut ledger.current, next);
+    finished.end_net_worth = net_worth;
+    info!("Month {} ended: {}", finished.month, finished.verdict());
+    ledger.history.push(finished);
+    ended.write(MonthEnded);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::sim::{
+        market::{FundPrice, Holding},
+        passive_income::{IncomeSource, OwnedSource},
+    };
+
+    fn fund(name: &str, price: f32) -> FundPrice {
+        FundPrice {
+            name: name.to_string(),
+            price,
+            history: VecDeque::from([price]),
+        }
+    }
+
+    fn owned(source: IncomeSource, price: u32, earned: i64) -> OwnedSource {
+        OwnedSource {
+            source,
+            bought_day: 1,
+            price,
+            earned,
+        }
+    }
+
+    /// Cash, savings, two funds held and one sold off, and two income sources.
+    fn wealthy_world() -> World {
+        let mut world = World::new();
+        world.insert_resource(Money { amount: 120 });
+        world.insert_resource(Bank { savings: 300 });
+        world.insert_resource(Market {
+            funds: vec![fund("Index", 12.4), fund("Penny", 0.3), fund("Bonds", 50.0)],
+        });
+        world.insert_resource(Portfolio(BTreeMap::from([
+            (
+                "Index".to_string(),
+                Holding {
+                    units: 5,
+                    cost_basis: 80,
+                },
+            ),
+            (
+                "Penny".to_string(),
+                Holding {
+                    units: 10,
+                    cost_basis: 40,
+                },
+            ),
+            ("Bonds".to_string(), Holding::default()),
+        ])));
+        world.insert_resource(IncomeSources(vec![
+            owned(IncomeSource::VendingMachine, 400, -50),
+            owned(IncomeSource::RentalUnit, 1500, 900),
+        ]));
+        world
+    }
+
+    #[test]
+    fn net_worth_adds_up_every_part() {
+        let world = wealthy_world();
+        let worth = NetWorth::new(
+            world.resource(),
+            world.resource(),
+            world.resource(),
+            world.resource(),
+            world.resource(),
+        );
+        // Funds count at today's whole-dollar price, never under a dollar a unit, and the
+        // income sources at what was paid for them whatever they've earned since.
+        assert_eq!(
+            worth,
+            NetWorth {
+                cash: 120,
+                savings: 300,
+                investments: 5 * 12 + 10,
+                income_sources: 1900,
+            }
+        );
+        assert_eq!(worth.total(), 2390);
+    }
+
+    #[test]
+    fn wealth_reads_the_same_net_worth() {
+        let mut world = wealthy_world();
+        let worth = world
+            .run_system_once(|wealth: Wealth| wealth.net_worth().total())
+            .unwrap();
+        assert_eq!(worth, 2390);
+    }
+}
@@ -0,0 +1,86 @@
+//! The player's mail: messages that stay in the [`Inbox`] until read, for news that a
+//! toast would let slip by, like rent increases and job offers. A message can carry a
+//! [`GameAction`] the player can take straight from the inbox.
+
+use bevy::prelude::*;
+
+use crate::screens::actions::GameAction;
+
+/// The inbox never holds more than this many messages.
+pub const MAX_MAIL: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+pub struct Mail {
+    pub title: String,
+    pub body: String,
+    /// Day the message arrived.
+    pub day: u32,
+    pub read: bool,
+    /// Unread critical mail makes the inbox badge pulse.
+    pub critical: bool,
+    /// Something the player can do about the message, offered as a button.
+    pub action: Option<(String, GameAction)>,
+}
+
+impl Mail {
+    pub fn new(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Mail {
+            title: title.into(),
+            body: body.into(),
+            day: 0,
+            read: false,
+            critical: false,
+            action: None,
+        }
+    }
+
+    pub fn critical(mut self) -> Self {
+        self.critical = true;
+        self
+    }
+
+    pub fn with_action(mut self, label: impl Into<String>, action: GameAction) -> Self {
+        self.action = Some((label.into(), action));
+        self
+    }
+}
+
+/// Every message the player has, oldest first.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct Inbox {
+    pub messages: Vec<Mail>,
+    /// Messages received over the run, including any evicted since.
+    pub received: u32,
+}
+
+impl Inbox {
+    /// Delivers `mail` on `day`. A full inbox drops its oldest read message, or its
+    /// oldest message if everything is unread.
+    pub fn send_mail(&mut self, day: u32, mail: Mail) {
+        if self.messages.len() >= MAX_MAIL {
+            let evicted = self
+                .messages
+                .iter()
+                .position(|message| message.read)
+                .unwrap_or(0);
+            self.messages.remove(evicted);
+        }
+        self.messages.push(Mail { day, ..mail });
+        self.received += 1;
+    }
+
+    pub fn unread(&self) -> usize {
+        self.messages.iter().filter(|message| !message.read).count()
+    }
+
+    pub fn has_unread_critical(&self) -> bool {
+        self.messages
+            .iter()
+            .any(|message| message.critical && !message.read)
+    }
+}
+
+pub fn mail_plugin(app: &mut App) {
+    app.register_type::<Inbox>().init_resource::<Inbox>();
+}
@@ -0,0 +1,247 @@
+//! Investments: a couple of index funds whose prices drift every day, and the player's
+//! [`Portfolio`] of them. Prices follow a mean-reverting random walk, so they wander but
+//! keep coming back towards a long-run price set in the data.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    clock::day_started,
+    rng::{GameRng, RollSet},
+};
+use crate::data::register_ron_resource;
+
+pub const MARKET_PATH: &str = "data/world.market.ron";
+
+/// One fund, as described in the data.
+#[derive(Deserialize, Clone, Debug)]
+pub struct FundDef {
+    pub name: String,
+    pub start_price: f32,
+    /// The price the fund keeps drifting back towards.
+    pub mean_price: f32,
+    /// Share of the distance to the mean price closed each day, from 0 to 1.
+    pub reversion: f32,
+    /// Standard deviation of the daily change in log price.
+    pub volatility: f32,
+}
+
+/// Market balance data.
+#[derive(Asset, TypePath, Resource, Deserialize, Clone, Debug)]
+pub struct MarketConfig {
+    /// Days of price history kept for the charts.
+    pub history_days: usize,
+    pub funds: Vec<FundDef>,
+}
+
+/// A fund's price today and over the last days.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct FundPrice {
+    pub name: String,
+    pub price: f32,
+    /// Oldest first, ending with today's price.
+    pub history: VecDeque<f32>,
+}
+
+impl FundPrice {
+    /// What one unit costs or sells for, in whole dollars.
+    pub fn unit_price(&self) -> u32 {
+        self.price.round().max(1.0) as u32
+    }
+}
+
+/// Today's fund prices.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Market {
+    pub funds: Vec<FundPrice>,
+}
+
+impl Market {
+    pub fn new(config: &MarketConfig) -> Self {
+        Market {
+            funds: config
+                .funds
+                .iter()
+                .map(|fund| FundPrice {
+                    name: fund.name.clone(),
+                    price: fund.start_price,
+                    history: VecDeque::from([fund.start_price]),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The next day's price of `fund` after `price`: an Ornstein-Uhlenbeck step on the log
+/// price.
+pub fn next_price(fund: &FundDef, price: f32, rng: &mut GameRng) -> f32 {
+    let log_price = price.ln();
+    let pull = fund.reversion * (fund.mean_price.ln() - log_price);
+    (log_price + pull + fund.volatility * rng.normal()).exp()
+}
+
+/// Units of one fund the player owns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct Holding {
+    pub units: u32,
+    /// What the units still held cost in total.
+    pub cost_basis: u32,
+}
+
+impl Holding {
+    pub fn value(&self, unit_price: u32) -> u32 {
+        self.units * unit_price
+    }
+
+    /// Gain if everything were sold at `unit_price`, negative for a loss.
+    pub fn unrealized_gain(&self, unit_price: u32) -> i64 {
+        i64::from(self.value(unit_price)) - i64::from(self.cost_basis)
+    }
+
+    pub fn buy(&mut self, unit_price: u32) {
+        self.units += 1;
+        self.cost_basis += unit_price;
+    }
+
+    /// Sells a unit, taking its share of the cost basis at the average cost. Returns
+    /// `false` if there was nothing to sell.
+    pub fn sell(&mut self) -> bool {
+        if self.units == 0 {
+            return false;
+        }
+        self.cost_basis -= self.cost_basis / self.units;
+        self.units -= 1;
+        true
+    }
+}
+
+/// The player's holdings, by fund name.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Portfolio(pub BTreeMap<String, Holding>);
+
+impl Portfolio {
+    pub fn holding(&self, fund: &str) -> Holding {
+        self.0.get(fund).copied().unwrap_or_default()
+    }
+}
+
+pub fn market_plugin(app: &mut App) {
+    register_ron_resource::<MarketConfig>(app, &["market.ron"]);
+    app.register_type::<Market>()
+        .register_type::<Portfolio>()
+        .register_type::<Holding>()
+        .init_resource::<Market>()
+        .init_resource::<Portfolio>()
+        .add_systems(
+            FixedUpdate,
+            move_prices.run_if(day_started).in_set(RollSet::Market),
+        );
+}
+
+fn move_prices(config: Res<MarketConfig>, mut market: ResMut<Market>, mut rng: ResMut<GameRng>) {
+    for fund in &config.funds {
+        let Some(price) = market
+            .funds
+            .iter_mut()
+            .find(|price| price.name == fund.name)
+        else {
+            continue;
+        };
+        price.price = next_price(fund, price.price, &mut rng);
+        price.history.push_back(price.price);
+        while price.history.len() > config.history_days {
+            price.history.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fund(reversion: f32, volatility: f32) -> FundDef {
+        FundDef {
+            name: "Test Fund".to_string(),
+            start_price: 100.0,
+            mean_price: 100.0,
+            reversion,
+            volatility,
+        }
+    }
+
+    /// Log prices of `days` steps of `fund` from `start`.
+    fn walk(fund: &FundDef, start: f32, days: usize, seed: u64) -> Vec<f32> {
+        let mut rng = GameRng::seeded(seed);
+        let mut price = start;
+        (0..days)
+            .map(|_| {
+                price = next_price(fund, price, &mut rng);
+                price.ln()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn without_volatility_prices_close_a_share_of_the_gap_each_day() {
+        let fund = fund(0.5, 0.0);
+        let mut rng = GameRng::seeded(1);
+        let price = next_price(&fund, 400.0, &mut rng);
+        // Halfway to the mean in log terms, so the geometric mean of 400 and 100.
+        assert!((price - 200.0).abs() < 0.01, "got {price}");
+    }
+
+    #[test]
+    fn prices_wander_around_the_mean_with_the_expected_spread() {
+        let (reversion, volatility) = (0.1, 0.05);
+        let log_prices = walk(&fund(reversion, volatility), 100.0, 20_000, 3);
+        let count = log_prices.len() as f32;
+        let mean = log_prices.iter().sum::<f32>() / count;
+        let variance = log_prices
+            .iter()
+            .map(|log_price| (log_price - mean).powi(2))
+            .sum::<f32>()
+            / count;
+        // The stationary spread of an AR(1) process with this pull and noise.
+        let expected_spread = volatility / (1.0 - (1.0 - reversion).powi(2)).sqrt();
+        assert!((mean - 100f32.ln()).abs() < 0.02, "mean log price {mean}");
+        assert!(
+            (variance.sqrt() / expected_spread - 1.0).abs() < 0.1,
+            "spread {} against {expected_spread}",
+            variance.sqrt()
+        );
+    }
+
+    #[test]
+    fn the_same_seed_walks_the_same_prices() {
+        let fund = fund(0.05, 0.02);
+        assert_eq!(walk(&fund, 100.0, 30, 9), walk(&fund, 100.0, 30, 9));
+        assert_ne!(walk(&fund, 100.0, 30, 9), walk(&fund, 100.0, 30, 10));
+    }
+
+    #[test]
+    fn gains_count_against_what_the_units_cost() {
+        let mut holding = Holding::default();
+        holding.buy(10);
+        holding.buy(12);
+        assert_eq!(holding.cost_basis, 22);
+        assert_eq!(holding.value(15), 30);
+        assert_eq!(holding.unrealized_gain(15), 8);
+        assert_eq!(holding.unrealized_gain(8), -6);
+    }
+
+    #[test]
+    fn selling_takes_the_average_cost_out_of_the_basis() {
+        let mut holding = Holding::default();
+        holding.buy(10);
+        holding.buy(12);
+        assert!(holding.sell());
+        assert_eq!((holding.units, holding.cost_basis), (1, 11));
+        assert!(holding.sell());
+        assert_eq!((holding.units, holding.cost_basis), (0, 0));
+        assert!(!holding.sell());
+    }
+}
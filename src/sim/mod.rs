@@ -0,0 +1,375 @@
+//! The game simulation: everything that mutates the run's state over time.
+//!
+//! Simulation systems run on [`FixedUpdate`] so that the clock, rent and any other
+//! time-based rule advance at the same pace regardless of the frame rate. Input handling
+//! and UI stay in [`Update`] and only ever read the simulation resources.
+//!
+//! All simulation systems live in the chained [`SimSet`]s, so a whole tick (clock
+//! advanced, rent charged, win/loss checked) completes before `Update` gets to look at
+//! the resources. The UI can never observe a half-applied tick.
+//!
+//! The simulation is paused while a modal is open (see [`ModalStack`]) or the window is in
+//! the background, and while a replay has actions to feed in, see [`replay`].
+
+pub mod bank;
+pub mod bills;
+pub mod buffs;
+pub mod calendar;
+pub mod catalog;
+pub mod challenge;
+pub mod charity;
+pub mod choices;
+pub mod clock;
+pub mod content;
+pub mod cost_of_living;
+pub mod crime;
+pub mod debt;
+pub mod dialogue;
+pub mod economy;
+pub mod event_cards;
+pub mod jobs;
+pub mod journal;
+pub mod landlord;
+pub mod ledger;
+pub mod mail;
+pub mod market;
+pub mod needs;
+pub mod new_game_plus;
+pub mod onboarding;
+pub mod passive_income;
+pub mod pet;
+pub mod playtime;
+pub mod predicate;
+pub mod quicksave;
+pub mod replay;
+pub mod rng;
+pub mod roommate;
+pub mod savings_goal;
+pub mod scenario;
+pub mod solvency;
+pub mod travel;
+pub mod undo;
+pub mod vignettes;
+pub mod weather;
+
+use bevy::{app::FixedMain, prelude::*, window::PrimaryWindow};
+
+use crate::AppState;
+use crate::launch::LaunchOptions;
+use crate::screens::actions::ActionPressed;
+use crate::screens::overlay::ModalStack;
+use crate::settings::ContentFilters;
+
+/// How many simulation ticks run per real-time second. One tick is one in-game hour.
+///
+/// Changing this resource retunes the [`FixedUpdate`] timestep on the next frame. Only
+/// sim-time things speed up with it, see [`crate::timers`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct SimTickRate(pub f64);
+
+impl Default for SimTickRate {
+    fn default() -> Self {
+        SimTickRate(1.0)
+    }
+}
+
+/// Whether the run has changed since it was last saved or loaded, see [`quicksave`], or
+/// since it started.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct Dirty(pub bool);
+
+/// Ordered phases of a simulation tick.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SimSet {
+    /// Advances the in-game clock.
+    Clock,
+    /// Applies everything that depends on the new time: rent, win and loss checks.
+    Economy,
+    /// Books the tick's transactions and closes the month when it ends.
+    Ledger,
+}
+
+pub fn sim_plugin(app: &mut App) {
+    app.register_type::<SimTickRate>()
+        .register_type::<Dirty>()
+        .init_resource::<SimTickRate>()
+        .init_resource::<Dirty>()
+        .configure_sets(
+            FixedUpdate,
+            (SimSet::Clock, SimSet::Economy, SimSet::Ledger)
+                .chain()
+                .run_if(
+                    in_state(AppState::Game)
+                        .and(sim_running)
+                        .and(replay::playback_caught_up),
+                ),
+        )
+        .add_systems(OnEnter(AppState::Game), reset_run.run_if(starting_run))
+        .add_systems(
+            Update,
+            (
+                apply_tick_rate.run_if(resource_changed::<SimTickRate>),
+                mark_dirty.run_if(on_event::<ActionPressed>),
+                finish_resuming.run_if(resource_exists::<ResumeRun>),
+            ),
+        )
+        // Every tick changes the run, if only the clock.
+        .add_systems(FixedUpdate, mark_dirty.in_set(SimSet::Ledger))
+        .add_plugins((
+            bank::bank_plugin,
+            calendar::calendar_plugin,
+            catalog::catalog_plugin,
+            challenge::challenge_plugin,
+            charity::charity_plugin,
+            choices::choices_plugin,
+            clock::clock_plugin,
+            content::content_plugin,
+            crime::crime_plugin,
+            dialogue::dialogue_plugin,
+            economy::economy_plugin,
+            jobs::jobs_plugin,
+            landlord::landlord_plugin,
+            ledger::ledger_plugin,
+            mail::mail_plugin,
+        ))
+        .add_plugins((
+            buffs::buffs_plugin,
+            market::market_plugin,
+            needs::needs_plugin,
+            new_game_plus::new_game_plus_plugin,
+            onboarding::onboarding_plugin,
+            pet::pet_plugin,
+            playtime::playtime_plugin,
+            replay::replay_plugin,
+            rng::rng_plugin,
+            roommate::roommate_plugin,
+            savings_goal::savings_goal_plugin,
+            travel::travel_plugin,
+            event_cards::event_cards_plugin,
+            undo::undo_plugin,
+            weather::weather_plugin,
+        ))
+        .add_plugins((
+            bills::bills_plugin,
+            debt::debt_plugin,
+            journal::journal_plugin,
+            passive_income::passive_income_plugin,
+            quicksave::quicksave_plugin,
+            scenario::scenario_plugin,
+            solvency::solvency_plugin,
+            vignettes::vignettes_plugin,
+        ));
+}
+
+/// Marks the way into [`AppState::Game`] as a return to the run on hand, like playing on
+/// past the win screen, rather than a new run. Nothing is reset then.
+#[derive(Resource, Debug, Default)]
+pub struct ResumeRun;
+
+/// Run condition for entering [`AppState::Game`] to start a run, see [`ResumeRun`].
+pub fn starting_run(resume: Option<Res<ResumeRun>>) -> bool {
+    resume.is_none()
+}
+
+/// Run condition for the simulation: no modal is open and the game window has focus.
+pub fn sim_running(modals: Res<ModalStack>, windows: Query<&Window, With<PrimaryWindow>>) -> bool {
+    modals.is_empty() && windows.iter().all(|window| window.focused)
+}
+
+/// Puts every simulation resource back to its starting value so each run starts fresh.
+/// The New Game+ cycle and the item carried over are kept, and set the run's prices, and
+/// the picked [`scenario::ChosenScenario`] goes over the starting values. A weekly
+/// challenge takes its seed and prices from its week and plays the pinned scenario
+/// instead, and a replay everything from its log.
+pub fn reset_run(
+    mut commands: Commands,
+    catalog: Res<catalog::AreaCatalog>,
+    market_config: Res<market::MarketConfig>,
+    new_game_plus: Res<new_game_plus::NewGamePlus>,
+    new_game_plus_config: Res<new_game_plus::NewGamePlusConfig>,
+    carry_over: Res<new_game_plus::CarryOver>,
+    challenge: Res<challenge::Challenge>,
+    content_filters: Res<ContentFilters>,
+    (scenarios, chosen_scenario): (Res<scenario::Scenarios>, Res<scenario::ChosenScenario>),
+    playback: Option<Res<replay::Playback>>,
+    launch_options: Res<LaunchOptions>,
+) {
+    let setup = match (playback, challenge.0) {
+        (Some(playback), _) => playback.setup().clone(),
+        (None, Some(week)) => replay::RunSetup {
+            seed: week.seed(),
+            cycle: week.difficulty().cycle,
+            carry_over: None,
+            content_filters: *content_filters,
+            scenario: scenarios.challenge.clone(),
+        },
+        (None, None) => replay::RunSetup {
+            seed: launch_options
+                .seed
+                .unwrap_or_else(|| rng::GameRng::from_entropy().seed()),
+            cycle: new_game_plus.cycle,
+            carry_over: carry_over.0.clone(),
+            content_filters: *content_filters,
+            scenario: chosen_scenario.0.clone(),
+        },
+    };
+    let rng = rng::GameRng::seeded(setup.seed);
+    let cycle = new_game_plus::NewGamePlus { cycle: setup.cycle };
+    let prices = new_game_plus::PriceLevel::new(&cycle, &new_game_plus_config);
+    let mut home = economy::HomeArea::new(economy::Area::default(), &catalog);
+    home.reprice(&catalog, &prices);
+    let mut upgrades = crime::Upgrades::default();
+    let mut pet = pet::Pet::default();
+    new_game_plus::CarryOver(setup.carry_over.clone()).apply(&mut upgrades, &mut pet);
+
+    commands.insert_resource(Dirty::default());
+    commands.insert_resource(clock::Clock::default());
+    commands.insert_resource(economy::Money::default());
+    commands.insert_resource(bank::Bank::default());
+    commands.insert_resource(ledger::Ledger::new(economy::STARTING_MONEY));
+    commands.insert_resource(market::Market::new(&market_config));
+    commands.insert_resource(market::Portfolio::default());
+    commands.insert_resource(upgrades);
+    commands.insert_resource(dialogue::DialogueMemory::default());
+    commands.insert_resource(dialogue::DiscountToken::default());
+    commands.insert_resource(home);
+    commands.insert_resource(prices);
+    commands.insert_resource(economy::CurrentArea::default());
+    commands.insert_resource(economy::WorkArea::default());
+    commands.insert_resource(jobs::JobOffer::default());
+    commands.insert_resource(economy::RentSchedule::default());
+    commands.insert_resource(economy::RentHistory::default());
+    commands.insert_resource(bills::Bills::default());
+    commands.insert_resource(debt::Debt::default());
+    commands.insert_resource(landlord::RentTerms::default());
+    commands.insert_resource(landlord::RentIncreases::default());
+    commands.insert_resource(mail::Inbox::default());
+    commands.insert_resource(charity::Reputation::default());
+    commands.insert_resource(choices::ChoiceLog::default());
+    commands.insert_resource(content::RunFilters(setup.content_filters));
+    commands.insert_resource(onboarding::Checklist::default());
+    commands.insert_resource(playtime::Playtime::default());
+    commands.insert_resource(playtime::SessionStats::default());
+    commands.insert_resource(economy::Ending::default());
+    commands.insert_resource(economy::FreePlay::default());
+    commands.insert_resource(needs::Happiness::default());
+    commands.insert_resource(needs::Energy::default());
+    commands.insert_resource(buffs::Buffs::default());
+    commands.insert_resource(jobs::WeeklyOvertime::default());
+    commands.insert_resource(journal::Journal::default());
+    commands.insert_resource(passive_income::IncomeSources::default());
+    commands.insert_resource(rng);
+    commands.insert_resource(roommate::Roommate::default());
+    commands.insert_resource(savings_goal::SavingsGoal::default());
+    commands.insert_resource(solvency::SolvencyWarning::default());
+    commands.insert_resource(travel::AreaUnlocks::default());
+    commands.insert_resource(pet);
+    commands.insert_resource(undo::UndoBuffer::default());
+    commands.insert_resource(vignettes::Moments::default());
+    commands.insert_resource(weather::Weather::default());
+    if let Some(id) = &setup.scenario {
+        match scenarios.get(id) {
+            Some(preset) => preset.apply(&mut commands, &catalog, &prices),
+            None => warn!("There's no scenario {id}, starting the regular way"),
+        }
+    }
+    commands.insert_resource(setup);
+    commands.remove_resource::<economy::GameOverReason>();
+}
+
+/// Runs `ticks` simulation ticks right away, the way the fixed loop runs them when it's
+/// behind, stopping early if the run ends partway. Returns how many ran.
+pub fn step_ticks(world: &mut World, ticks: u32) -> u32 {
+    for ran in 0..ticks {
+        if matches!(
+            *world.resource::<NextState<AppState>>(),
+            NextState::Pending(_)
+        ) {
+            return ran;
+        }
+        world.run_schedule(FixedMain);
+    }
+    ticks
+}
+
+// Once everything entering the game has seen it.
+fn finish_resuming(mut commands: Commands) {
+    commands.remove_resource::<ResumeRun>();
+}
+
+fn mark_dirty(mut dirty: ResMut<Dirty>) {
+    dirty.0 = true;
+}
+
+fn apply_tick_rate(rate: Res<SimTickRate>, mut time: ResMut<Time<Fixed>>) {
+    time.set_timestep_hz(rate.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+    use crate::sim::clock::{Clock, HOURS_PER_DAY, clock_plugin};
+    use crate::testing::TestGame;
+
+    fn clock_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<AppState>()
+            .add_plugins(clock_plugin);
+        app
+    }
+
+    #[test]
+    fn step_ticks_advances_one_hour_per_tick() {
+        let mut app = clock_app();
+        let start = app.world().resource::<Clock>().total_hours();
+        let ran = step_ticks(app.world_mut(), HOURS_PER_DAY + 3);
+        assert_eq!(ran, HOURS_PER_DAY + 3);
+        let clock = app.world().resource::<Clock>();
+        assert_eq!(clock.total_hours(), start + HOURS_PER_DAY + 3);
+        assert_eq!(clock.day, 2);
+    }
+
+    #[test]
+    fn step_ticks_stops_when_the_run_ends() {
+        let mut app = clock_app();
+        let start = app.world().resource::<Clock>().total_hours();
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::GameOver);
+        assert_eq!(step_ticks(app.world_mut(), 10), 0);
+        assert_eq!(app.world().resource::<Clock>().total_hours(), start);
+    }
+
+    #[test]
+    fn the_clock_holds_until_the_last_modal_closes() {
+        const PROFILE: &str = "Modal clock";
+        let mut app = TestGame::in_game(PROFILE);
+        // Only the ticks stepped below move the clock.
+        app.world_mut().resource_mut::<Time<Virtual>>().pause();
+        let hours = |app: &App| app.world().resource::<Clock>().total_hours();
+        let start = hours(&app);
+
+        let mut modals = app.world_mut().resource_mut::<ModalStack>();
+        let (card, confirm) = (modals.open_modal(), modals.open_modal());
+        step_ticks(app.world_mut(), 5);
+        assert_eq!(hours(&app), start);
+
+        // Closed out of order, the confirmation still holds the clock.
+        app.world_mut()
+            .resource_mut::<ModalStack>()
+            .close_modal(card);
+        step_ticks(app.world_mut(), 5);
+        assert_eq!(hours(&app), start);
+
+        app.world_mut()
+            .resource_mut::<ModalStack>()
+            .close_modal(confirm);
+        step_ticks(app.world_mut(), 1);
+        assert_eq!(hours(&app), start + 1);
+    }
+}
@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+
+use super::{SimSet, clock::day_started};
+
+pub const MAX_HAPPINESS: u32 = 100;
+pub const MAX_ENERGY: u32 = 100;
+
+/// How content the player is, from 0 to [`MAX_HAPPINESS`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct Happiness(pub u32);
+
+impl Default for Happiness {
+    fn default() -> Self {
+        Happiness(70)
+    }
+}
+
+impl Happiness {
+    /// Adds `delta` (which may be negative), staying within the valid range.
+    pub fn change(&mut self, delta: i32) {
+        self.0 = self.0.saturating_add_signed(delta).min(MAX_HAPPINESS);
+    }
+}
+
+/// How much the player has left in them today, from 0 to [`MAX_ENERGY`]. Refilled by
+/// a night's sleep.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct Energy(pub u32);
+
+impl Default for Energy {
+    fn default() -> Self {
+        Energy(MAX_ENERGY)
+    }
+}
+
+pub fn needs_plugin(app: &mut App) {
+    app.register_type::<Happiness>()
+        .register_type::<Energy>()
+        .init_resource::<Happiness>()
+        .init_resource::<Energy>()
+        .add_systems(
+            FixedUpdate,
+            sleep.run_if(day_started).in_set(SimSet::Economy),
+        );
+}
+
+fn sleep(mut energy: ResMut<Energy>) {
+    energy.0 = MAX_ENERGY;
+}
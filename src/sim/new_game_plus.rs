@@ -0,0 +1,260 @@
+//! New Game+: after a win the player can start over on a harder cycle. Rent and prices go
+//! up and the win target moves further away with every cycle, up to a cap, and one item the
+//! player owned comes along into the new run.
+//!
+//! [`NewGamePlus`] and [`CarryOver`] outlive the run until the player goes back to the
+//! menu; [`super::reset_run`] reads them to set up the next run and its [`PriceLevel`].
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    crime::Upgrades,
+    economy::WIN_MONEY,
+    pet::{AdoptedPet, Pet},
+};
+use crate::AppState;
+use crate::data::register_ron_resource;
+
+pub const NEW_GAME_PLUS_PATH: &str = "data/world.new_game_plus.ron";
+
+/// New Game+ balance data.
+#[derive(Asset, TypePath, Resource, Deserialize, Clone, Debug)]
+pub struct NewGamePlusConfig {
+    /// Rent and prices are multiplied by this once per cycle.
+    pub price_multiplier_per_cycle: f32,
+    pub max_price_multiplier: f32,
+    /// The win target is multiplied by this once per cycle.
+    pub win_multiplier_per_cycle: f32,
+    pub max_win_multiplier: f32,
+}
+
+/// Which New Game+ cycle the run is on, 0 for a regular game.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct NewGamePlus {
+    pub cycle: u32,
+}
+
+impl NewGamePlus {
+    pub fn price_multiplier(&self, config: &NewGamePlusConfig) -> f32 {
+        stacked(
+            config.price_multiplier_per_cycle,
+            config.max_price_multiplier,
+            self.cycle,
+        )
+    }
+
+    pub fn win_multiplier(&self, config: &NewGamePlusConfig) -> f32 {
+        stacked(
+            config.win_multiplier_per_cycle,
+            config.max_win_multiplier,
+            self.cycle,
+        )
+    }
+}
+
+fn stacked(per_cycle: f32, max: f32, cycle: u32) -> f32 {
+    let cycle = i32::try_from(cycle).unwrap_or(i32::MAX);
+    per_cycle.powi(cycle).min(max).max(1.0)
+}
+
+/// Something the player owns that can come along into a New Game+ run.
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum OwnedItem {
+    DoorLock,
+    Umbrella,
+    Pet(String),
+}
+
+impl OwnedItem {
+    pub fn label(&self) -> String {
+        match self {
+            OwnedItem::DoorLock => "Door lock".to_string(),
+            OwnedItem::Umbrella => "Umbrella".to_string(),
+            OwnedItem::Pet(name) => name.clone(),
+        }
+    }
+
+    /// Everything the player owns right now.
+    pub fn owned(upgrades: &Upgrades, pet: &Pet) -> Vec<OwnedItem> {
+        let mut items = Vec::new();
+        if upgrades.door_lock {
+            items.push(OwnedItem::DoorLock);
+        }
+        if upgrades.umbrella {
+            items.push(OwnedItem::Umbrella);
+        }
+        if let Some(adopted) = &pet.0 {
+            items.push(OwnedItem::Pet(adopted.name.clone()));
+        }
+        items
+    }
+}
+
+/// The item picked to keep for the current New Game+ cycle.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct CarryOver(pub Option<OwnedItem>);
+
+impl CarryOver {
+    /// Gives the kept item back at the start of a run.
+    pub fn apply(&self, upgrades: &mut Upgrades, pet: &mut Pet) {
+        match &self.0 {
+            Some(OwnedItem::DoorLock) => upgrades.door_lock = true,
+            Some(OwnedItem::Umbrella) => upgrades.umbrella = true,
+            Some(OwnedItem::Pet(name)) => {
+                pet.0 = Some(AdoptedPet {
+                    name: name.clone(),
+                    adopted_day: 1,
+                    days_hungry: 0,
+                });
+            }
+            None => {}
+        }
+    }
+}
+
+/// The run's prices and win target, set when the run starts.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct PriceLevel {
+    pub multiplier: f32,
+    pub win_money: u32,
+}
+
+impl Default for PriceLevel {
+    fn default() -> Self {
+        PriceLevel {
+            multiplier: 1.0,
+            win_money: WIN_MONEY,
+        }
+    }
+}
+
+impl PriceLevel {
+    pub fn new(new_game_plus: &NewGamePlus, config: &NewGamePlusConfig) -> Self {
+        PriceLevel {
+            multiplier: new_game_plus.price_multiplier(config),
+            win_money: (WIN_MONEY as f32 * new_game_plus.win_multiplier(config)).round() as u32,
+        }
+    }
+
+    /// What a base price of `price` costs in this run.
+    pub fn scale(&self, price: u32) -> u32 {
+        (price as f32 * self.multiplier).round() as u32
+    }
+}
+
+pub fn new_game_plus_plugin(app: &mut App) {
+    register_ron_resource::<NewGamePlusConfig>(app, &["new_game_plus.ron"]);
+    app.register_type::<NewGamePlus>()
+        .register_type::<CarryOver>()
+        .register_type::<PriceLevel>()
+        .init_resource::<NewGamePlus>()
+        .init_resource::<CarryOver>()
+        .init_resource::<PriceLevel>()
+        .add_systems(OnEnter(AppState::Menu), end_new_game_plus);
+}
+
+/// Games started from the menu are regular games.
+pub fn end_new_game_plus(
+    mut new_game_plus: ResMut<NewGamePlus>,
+    mut carry_over: ResMut<CarryOver>,
+) {
+    *new_game_plus = NewGamePlus::default();
+    *carry_over = CarryOver::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{
+        economy::{HomeArea, Money, STARTING_MONEY},
+        reset_run,
+    };
+    use crate::testing::TestGame;
+
+    fn config() -> NewGamePlusConfig {
+        let path = format!("assets/{NEW_GAME_PLUS_PATH}");
+        ron::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn multipliers(cycle: u32) -> (f32, f32) {
+        let config = config();
+        let cycle = NewGamePlus { cycle };
+        (
+            cycle.price_multiplier(&config),
+            cycle.win_multiplier(&config),
+        )
+    }
+
+    #[test]
+    fn multipliers_stack_each_cycle_up_to_their_caps() {
+        assert_eq!(multipliers(0), (1.0, 1.0));
+        assert_eq!(multipliers(1), (1.25, 1.5));
+        assert_eq!(multipliers(2), (1.5625, 2.25));
+        // 1.25^5 and 1.5^4 are just over the caps.
+        assert_eq!(multipliers(4).1, 5.0);
+        assert_eq!(multipliers(5), (3.0, 5.0));
+        assert_eq!(multipliers(u32::MAX), (3.0, 5.0));
+    }
+
+    #[test]
+    fn the_price_level_scales_prices_and_the_win_target() {
+        let level = PriceLevel::new(&NewGamePlus { cycle: 2 }, &config());
+        assert_eq!(level.win_money, WIN_MONEY * 9 / 4);
+        assert_eq!(level.scale(100), 156);
+        assert_eq!(level.scale(0), 0);
+        assert_eq!(PriceLevel::default().scale(250), 250);
+    }
+
+    #[test]
+    fn a_new_cycle_starts_over_with_only_the_kept_item() {
+        const PROFILE: &str = "New cycle";
+        let mut app = TestGame::in_game(PROFILE);
+        let world = app.world_mut();
+        let regular_rent = world.resource::<HomeArea>().rent;
+        world.resource_mut::<Money>().amount = WIN_MONEY;
+        *world.resource_mut::<Upgrades>() = Upgrades {
+            door_lock: true,
+            umbrella: true,
+        };
+        world.resource_mut::<Pet>().0 = Some(AdoptedPet {
+            name: "Biscuit".to_string(),
+            adopted_day: 3,
+            days_hungry: 1,
+        });
+        world.insert_resource(NewGamePlus { cycle: 1 });
+        world.insert_resource(CarryOver(Some(OwnedItem::Pet("Biscuit".to_string()))));
+
+        world.run_system_cached(reset_run).unwrap();
+        assert_eq!(world.resource::<Money>().amount, STARTING_MONEY);
+        let upgrades = world.resource::<Upgrades>();
+        assert!(!upgrades.door_lock && !upgrades.umbrella);
+        // The pet comes along as if adopted on the first day, and fed.
+        assert_eq!(
+            world.resource::<Pet>().0,
+            Some(AdoptedPet {
+                name: "Biscuit".to_string(),
+                adopted_day: 1,
+                days_hungry: 0,
+            })
+        );
+        let level = *world.resource::<PriceLevel>();
+        assert_eq!(
+            (level.multiplier, level.win_money),
+            (1.25, WIN_MONEY * 3 / 2)
+        );
+        assert_eq!(world.resource::<HomeArea>().rent, level.scale(regular_rent));
+        // The cycle and the kept item last until the menu.
+        assert_eq!(world.resource::<NewGamePlus>().cycle, 1);
+        assert!(world.resource::<CarryOver>().0.is_some());
+
+        world.run_system_cached(end_new_game_plus).unwrap();
+        world.run_system_cached(reset_run).unwrap();
+        assert_eq!(*world.resource::<PriceLevel>(), PriceLevel::default());
+        assert_eq!(world.resource::<Pet>().0, None);
+        assert_eq!(world.resource::<HomeArea>().rent, regular_rent);
+    }
+}
@@ -0,0 +1,183 @@
+//! The first week's checklist: a few starter tasks ticked off as the player does them.
+//! Finishing the list pays a small bonus. Finishing or skipping it retires the checklist
+//! for good, as [`SavedSettings::onboarding_done`].
+
+use bevy::prelude::*;
+
+use super::{
+    SimSet,
+    buffs::buy_consumables,
+    economy::{Area, CurrentArea, Money},
+    jobs::JobEvent,
+    ledger::{Category, Transaction},
+    new_game_plus::PriceLevel,
+};
+use crate::AppState;
+use crate::format;
+use crate::profiles::{ProfilePaths, profile_switched};
+use crate::save_file::{CorruptFiles, SaveFiles};
+use crate::screens::actions::{ActionPressed, GameAction};
+use crate::settings::{Locale, SavedSettings};
+use crate::toast::Toast;
+
+/// Paid for finishing the checklist, before the run's [`PriceLevel`] scales it.
+const CHECKLIST_BONUS: u32 = 100;
+/// The checklist shows until the end of this day.
+pub const CHECKLIST_LAST_DAY: u32 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum StarterTask {
+    VisitBusinessDistrict,
+    GetJob,
+    WorkShift,
+    PayRent,
+    BuyItem,
+}
+
+impl StarterTask {
+    pub const ALL: [StarterTask; 5] = [
+        StarterTask::VisitBusinessDistrict,
+        StarterTask::GetJob,
+        StarterTask::WorkShift,
+        StarterTask::PayRent,
+        StarterTask::BuyItem,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StarterTask::VisitBusinessDistrict => "Visit the Business District",
+            StarterTask::GetJob => "Get a job",
+            StarterTask::WorkShift => "Work a shift",
+            StarterTask::PayRent => "Pay rent",
+            StarterTask::BuyItem => "Buy something",
+        }
+    }
+}
+
+/// The starter tasks done this run.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct Checklist {
+    done: Vec<StarterTask>,
+}
+
+impl Checklist {
+    pub fn is_done(&self, task: StarterTask) -> bool {
+        self.done.contains(&task)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        StarterTask::ALL.iter().all(|task| self.is_done(*task))
+    }
+
+    fn complete(&mut self, task: StarterTask) {
+        if !self.is_done(task) {
+            self.done.push(task);
+        }
+    }
+}
+
+/// Whether the checklist was finished or skipped, in this session or an earlier one.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct ChecklistRetired(pub bool);
+
+impl ChecklistRetired {
+    /// Retires the checklist and remembers it in the profile between sessions.
+    pub fn retire(&mut self, profile: &ProfilePaths, files: &mut SaveFiles) {
+        self.0 = true;
+        let mut saved = SavedSettings::load(profile, &mut files.corrupt);
+        saved.onboarding_done = true;
+        saved.save(profile, &mut files.saves);
+    }
+}
+
+pub fn onboarding_plugin(app: &mut App) {
+    let done = SavedSettings::load_active(app.world_mut()).onboarding_done;
+    app.register_type::<Checklist>()
+        .register_type::<ChecklistRetired>()
+        .init_resource::<Checklist>()
+        .insert_resource(ChecklistRetired(done))
+        .add_systems(PreUpdate, load_profile_checklist.run_if(profile_switched))
+        .add_systems(
+            Update,
+            (track_task_events.after(buy_consumables), track_visits)
+                .run_if(in_state(AppState::Game).and(resource_equals(ChecklistRetired(false)))),
+        )
+        .add_systems(
+            FixedUpdate,
+            pay_checklist_bonus
+                .in_set(SimSet::Economy)
+                .run_if(resource_equals(ChecklistRetired(false))),
+        );
+}
+
+fn load_profile_checklist(
+    profile: Res<ProfilePaths>,
+    mut corrupt: ResMut<CorruptFiles>,
+    mut retired: ResMut<ChecklistRetired>,
+) {
+    retired.0 = SavedSettings::load(&profile, &mut corrupt).onboarding_done;
+}
+
+fn track_visits(area: Res<CurrentArea>, mut checklist: ResMut<Checklist>) {
+    if area.0 == Area::BusinessDistrict {
+        checklist.complete(StarterTask::VisitBusinessDistrict);
+    }
+}
+
+// Snacks are booked as food, like what the pet and the roommate eat, so a food expense
+// counts when it comes with a press of a buy action.
+fn track_task_events(
+    mut jobs: EventReader<JobEvent>,
+    mut transactions: EventReader<Transaction>,
+    mut pressed: EventReader<ActionPressed>,
+    mut checklist: ResMut<Checklist>,
+) {
+    for event in jobs.read() {
+        match event {
+            JobEvent::Hired(_) => checklist.complete(StarterTask::GetJob),
+            JobEvent::ShiftWorked { .. } => checklist.complete(StarterTask::WorkShift),
+            _ => {}
+        }
+    }
+    let bought_snack = pressed
+        .read()
+        .any(|ActionPressed(action)| matches!(action, GameAction::Buy(_)));
+    for transaction in transactions.read() {
+        match *transaction {
+            Transaction::Expense(Category::Rent, _) => checklist.complete(StarterTask::PayRent),
+            Transaction::Expense(Category::Purchases, _) => {
+                checklist.complete(StarterTask::BuyItem);
+            }
+            Transaction::Expense(Category::Food, _) if bought_snack => {
+                checklist.complete(StarterTask::BuyItem);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn pay_checklist_bonus(
+    checklist: Res<Checklist>,
+    prices: Res<PriceLevel>,
+    mut retired: ResMut<ChecklistRetired>,
+    mut money: ResMut<Money>,
+    mut transactions: EventWriter<Transaction>,
+    mut toasts: EventWriter<Toast>,
+    locale: Res<Locale>,
+    profile: Res<ProfilePaths>,
+    mut files: SaveFiles,
+) {
+    if !checklist.is_complete() {
+        return;
+    }
+    let bonus = prices.scale(CHECKLIST_BONUS);
+    money.amount += bonus;
+    transactions.write(Transaction::Income(Category::Bonuses, bonus));
+    toasts.write(Toast::new(format!(
+        "First week checklist done! Bonus: {}",
+        format::money(bonus, *locale)
+    )));
+    retired.retire(&profile, &mut files);
+}
@@ -0,0 +1,365 @@
+//! Things the player can buy late in a run that make a little money every day on their
+//! own, like a vending machine in the Business District. Each midnight a source pays out
+//! its daily yield, give or take a few percent, and now and then needs fixing instead.
+//!
+//! Sources are sold in the Business District once their [`Unlock`] condition is met, one of
+//! each. They count toward net worth at what was paid for them.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    calendar::Calendar,
+    catalog::{AreaCatalog, Unlock},
+    charity::Reputation,
+    clock::{Clock, day_started},
+    cost_of_living::Prices,
+    economy::{Area, CurrentArea, Money},
+    ledger::{Category, Transaction, Wealth},
+    rng::{GameRng, RollSet},
+    travel::AreaUnlocks,
+    undo::{UndoBuffer, UndoEntry, UndoRestore},
+};
+use crate::AppState;
+use crate::data::register_ron_resource;
+use crate::format;
+use crate::screens::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use crate::settings::Locale;
+use crate::toast::Toast;
+
+pub const PASSIVE_INCOME_PATH: &str = "data/world.income.ron";
+
+/// Something that makes money on its own once bought.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Reflect, Serialize, Deserialize)]
+pub enum IncomeSource {
+    VendingMachine,
+    RentalUnit,
+}
+
+/// One source's balance data.
+#[derive(Deserialize, Clone, Debug)]
+pub struct SourceDef {
+    pub source: IncomeSource,
+    pub name: String,
+    pub price: u32,
+    pub daily_yield: u32,
+    /// How far a day's yield can stray from `daily_yield`, in percent either way.
+    pub yield_variance_percent: u32,
+    /// Chance each midnight that the source needs fixing and pays nothing that day.
+    pub maintenance_chance: f32,
+    pub maintenance_cost: u32,
+    /// The toast when it does, like "The vending machine jammed".
+    pub maintenance_message: String,
+    /// What it takes before the source is for sale.
+    pub unlock: Option<Unlock>,
+}
+
+impl SourceDef {
+    /// What the source makes a day on average: its yield on the days it works, less fixing
+    /// it on the days it doesn't.
+    pub fn expected_daily_net(&self, prices: &Prices) -> f32 {
+        (1.0 - self.maintenance_chance) * prices.level.scale(self.daily_yield) as f32
+            - self.maintenance_chance * prices.price(self.maintenance_cost) as f32
+    }
+
+    /// Days until the source has paid for itself on average, or `None` if it never does.
+    pub fn break_even_days(&self, prices: &Prices) -> Option<u32> {
+        break_even_days(prices.price(self.price), self.expected_daily_net(prices))
+    }
+}
+
+/// Days for `daily_net` a day to add up to `price`, or `None` if it never does.
+pub fn break_even_days(price: u32, daily_net: f32) -> Option<u32> {
+    (daily_net > 0.0).then(|| (price as f32 / daily_net).ceil() as u32)
+}
+
+/// A day's yield of a source yielding `base` a day, with `roll` in `[0, 1)` picking where
+/// in the variance it lands.
+pub fn roll_yield(base: u32, variance_percent: u32, roll: f32) -> u32 {
+    let percent = 100.0 + variance_percent as f32 * (roll * 2.0 - 1.0);
+    (base as f32 * percent / 100.0).round() as u32
+}
+
+/// Passive income balance data.
+#[derive(Asset, TypePath, Resource, Deserialize, Clone, Debug)]
+pub struct PassiveIncomeConfig {
+    pub sources: Vec<SourceDef>,
+}
+
+impl PassiveIncomeConfig {
+    pub fn get(&self, source: IncomeSource) -> Option<&SourceDef> {
+        self.sources.iter().find(|def| def.source == source)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct OwnedSource {
+    pub source: IncomeSource,
+    pub bought_day: u32,
+    /// What was paid for it, which is what it counts for in net worth.
+    pub price: u32,
+    /// Yields so far, maintenance taken off.
+    pub earned: i64,
+}
+
+/// The income sources the player owns, in the order they were bought. Not to be mixed up
+/// with Bevy's `Assets`.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct IncomeSources(pub Vec<OwnedSource>);
+
+impl IncomeSources {
+    pub fn owns(&self, source: IncomeSource) -> bool {
+        self.0.iter().any(|owned| owned.source == source)
+    }
+
+    pub fn value(&self) -> u32 {
+        self.0.iter().map(|owned| owned.price).sum()
+    }
+}
+
+pub fn passive_income_plugin(app: &mut App) {
+    register_ron_resource::<PassiveIncomeConfig>(app, &["income.ron"]);
+    app.register_type::<IncomeSources>()
+        .init_resource::<IncomeSources>()
+        .add_systems(
+            FixedUpdate,
+            collect_yields.run_if(day_started).in_set(RollSet::Income),
+        )
+        .add_systems(Update, offer_income_sources.in_set(ActionSet::Offer))
+        .add_systems(Update, buy_income_sources.run_if(in_state(AppState::Game)));
+}
+
+fn collect_yields(
+    config: Res<PassiveIncomeConfig>,
+    prices: Prices,
+    mut sources: ResMut<IncomeSources>,
+    mut money: ResMut<Money>,
+    mut rng: ResMut<GameRng>,
+    mut toasts: EventWriter<Toast>,
+    mut transactions: EventWriter<Transaction>,
+    locale: Res<Locale>,
+) {
+    for owned in &mut sources.0 {
+        let Some(def) = config.get(owned.source) else {
+            continue;
+        };
+        if rng.chance(def.maintenance_chance) {
+            // Fixing it comes out of cash, down to nothing if that's all there is.
+            let cost = prices.price(def.maintenance_cost).min(money.amount);
+            money.amount -= cost;
+            owned.earned -= i64::from(cost);
+            transactions.write(Transaction::Expense(Category::Maintenance, cost));
+            toasts.write(Toast::new(format!(
+                "{}: -{}",
+                def.maintenance_message,
+                format::money(cost, *locale)
+            )));
+            continue;
+        }
+        let amount = roll_yield(
+            prices.level.scale(def.daily_yield),
+            def.yield_variance_percent,
+            rng.next_f32(),
+        );
+        money.amount += amount;
+        owned.earned += i64::from(amount);
+        transactions.write(Transaction::Income(Category::PassiveIncome, amount));
+    }
+}
+
+fn offer_income_sources(
+    config: Res<PassiveIncomeConfig>,
+    sources: Res<IncomeSources>,
+    area: Res<CurrentArea>,
+    (catalog, unlocks, reputation): (Res<AreaCatalog>, Res<AreaUnlocks>, Res<Reputation>),
+    wealth: Wealth,
+    clock: Res<Clock>,
+    calendar: Res<Calendar>,
+    money: Res<Money>,
+    prices: Prices,
+    mut actions: ResMut<AvailableActions>,
+    locale: Res<Locale>,
+) {
+    if area.0 != Area::BusinessDistrict {
+        return;
+    }
+    let worth = wealth.net_worth().total();
+    for def in &config.sources {
+        let action = GameAction::BuyIncomeSource(def.source);
+        if let Some(owned) = sources.0.iter().find(|owned| owned.source == def.source) {
+            let sign = if owned.earned < 0 { "-" } else { "+" };
+            actions.offer_blocked(
+                action,
+                format!(
+                    "{}: {sign}{} so far",
+                    def.name,
+                    format::format_money(owned.earned.abs() * 100, *locale)
+                ),
+                format!("Bought on day {}", owned.bought_day),
+            );
+            continue;
+        }
+        if let Some(unlock) = &def.unlock
+            && !unlocks.is_met(unlock, &catalog, worth, reputation.points, &prices.level)
+        {
+            continue;
+        }
+        let price = prices.price(def.price);
+        let base = prices.without_cost_of_living(def.price);
+        let pays_off = match def.break_even_days(&prices) {
+            Some(days) => format!("pays off in ~{days} days"),
+            None => "never pays off".to_string(),
+        };
+        let label = format!(
+            "Buy a {}: -{}, ~{} a day, {pays_off}",
+            def.name.to_lowercase(),
+            format::price(price, base, *locale),
+            format::money(prices.level.scale(def.daily_yield), *locale)
+        );
+        if let Some(reason) = calendar.closed_reason(clock.day) {
+            actions.offer_blocked(action, label, reason);
+        } else if money.amount >= price {
+            actions.offer_labeled(action, label);
+        } else {
+            actions.offer_blocked(action, label, "Not enough cash");
+        }
+    }
+}
+
+fn buy_income_sources(
+    mut pressed: EventReader<ActionPressed>,
+    config: Res<PassiveIncomeConfig>,
+    clock: Res<Clock>,
+    calendar: Res<Calendar>,
+    prices: Prices,
+    mut sources: ResMut<IncomeSources>,
+    mut money: ResMut<Money>,
+    mut undo: ResMut<UndoBuffer>,
+    mut toasts: EventWriter<Toast>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        let GameAction::BuyIncomeSource(source) = *action else {
+            continue;
+        };
+        let Some(def) = config.get(source) else {
+            continue;
+        };
+        let price = prices.price(def.price);
+        if sources.owns(source) || money.amount < price || calendar.holiday(clock.day).is_some() {
+            continue;
+        }
+        money.amount -= price;
+        transactions.write(Transaction::Expense(Category::Purchases, price));
+        sources.0.push(OwnedSource {
+            source,
+            bought_day: clock.day,
+            price,
+            earned: 0,
+        });
+        let label = format!("the {}", def.name.to_lowercase());
+        undo.record(
+            UndoEntry::new(label, price, UndoRestore::IncomeSource(source), None),
+            &money,
+            &clock,
+        );
+        toasts.write(Toast::undoable(format!(
+            "Bought a {}. It pays out every midnight",
+            def.name.to_lowercase()
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::sim::{
+        catalog::{AREA_CATALOG_PATH, AreaCatalogData},
+        economy::HomeArea,
+        new_game_plus::PriceLevel,
+    };
+
+    fn income_world() -> World {
+        let read = |path: &str| std::fs::read_to_string(format!("assets/{path}")).unwrap();
+        let catalog = AreaCatalog::from_data(
+            &ron::from_str::<AreaCatalogData>(&read(AREA_CATALOG_PATH)).unwrap(),
+        );
+        let mut world = World::new();
+        world.insert_resource(
+            ron::from_str::<PassiveIncomeConfig>(&read(PASSIVE_INCOME_PATH)).unwrap(),
+        );
+        world.init_resource::<PriceLevel>();
+        // The Condo's cost of living is the usual one.
+        world.insert_resource(HomeArea::new(Area::Condo, &catalog));
+        world.insert_resource(catalog);
+        world
+    }
+
+    fn expected_daily_net(world: &mut World, source: IncomeSource) -> f32 {
+        world
+            .run_system_once(move |config: Res<PassiveIncomeConfig>, prices: Prices| {
+                config.get(source).unwrap().expected_daily_net(&prices)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn break_even_rounds_up_and_needs_a_profit() {
+        assert_eq!(break_even_days(90, 30.0), Some(3));
+        assert_eq!(break_even_days(100, 30.0), Some(4));
+        assert_eq!(break_even_days(100, 0.0), None);
+        assert_eq!(break_even_days(100, -5.0), None);
+        assert_eq!(break_even_days(0, 1.0), Some(0));
+    }
+
+    #[test]
+    fn a_source_breaks_even_on_its_expected_net() {
+        let mut world = income_world();
+        // 18 a day on the 95% of days it works, and 40 to fix it on the rest.
+        let net = expected_daily_net(&mut world, IncomeSource::VendingMachine);
+        assert!((net - 15.1).abs() < 1e-4, "{net}");
+        let days = world
+            .run_system_once(|config: Res<PassiveIncomeConfig>, prices: Prices| {
+                config
+                    .get(IncomeSource::VendingMachine)
+                    .unwrap()
+                    .break_even_days(&prices)
+            })
+            .unwrap();
+        assert_eq!(days, Some(60));
+    }
+
+    #[test]
+    fn the_expected_net_is_what_a_source_makes_on_average() {
+        const DAYS: u32 = 20_000;
+        let mut world = income_world();
+        world.insert_resource(Money {
+            amount: u32::MAX / 2,
+        });
+        world.insert_resource(GameRng::seeded(7));
+        world.init_resource::<Locale>();
+        world.init_resource::<Events<Toast>>();
+        world.init_resource::<Events<Transaction>>();
+        world.insert_resource(IncomeSources(vec![OwnedSource {
+            source: IncomeSource::VendingMachine,
+            bought_day: 1,
+            price: 900,
+            earned: 0,
+        }]));
+        let mut schedule = Schedule::default();
+        schedule.add_systems(collect_yields);
+        for _ in 0..DAYS {
+            schedule.run(&mut world);
+        }
+
+        let average = world.resource::<IncomeSources>().0[0].earned as f32 / DAYS as f32;
+        let expected = expected_daily_net(&mut world, IncomeSource::VendingMachine);
+        assert!(
+            (average - expected).abs() < 0.3,
+            "{average} a day, expected {expected}"
+        );
+    }
+}
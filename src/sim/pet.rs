@@ -0,0 +1,287 @@
+//! A pet the player can adopt from the shelter. It eats every day, cheers the player up
+//! while fed, and runs away if it goes hungry for too long.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    SimSet,
+    bank::Bank,
+    calendar::Calendar,
+    clock::{Clock, day_started},
+    cost_of_living::Prices,
+    dialogue::DiscountToken,
+    economy::{CurrentArea, HomeArea, Money},
+    ledger::{Category, Transaction},
+    needs::Happiness,
+    undo::{UndoBuffer, UndoEntry, UndoRestore},
+};
+use crate::AppState;
+use crate::data::register_ron_resource;
+use crate::format;
+use crate::screens::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use crate::settings::Locale;
+use crate::toast::Toast;
+
+pub const PET_CONFIG_PATH: &str = "data/world.pet.ron";
+
+/// Pet balance data.
+#[derive(Asset, TypePath, Resource, Deserialize, Clone, Debug)]
+pub struct PetConfig {
+    pub name: String,
+    pub adoption_fee: u32,
+    /// Charged every midnight, from cash first and then savings.
+    pub daily_food_cost: u32,
+    pub daily_happiness_bonus: i32,
+    /// Days in a row without food before the pet runs away.
+    pub run_away_after_days: u32,
+    pub run_away_happiness_change: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct AdoptedPet {
+    pub name: String,
+    pub adopted_day: u32,
+    /// Days in a row the pet went without food.
+    pub days_hungry: u32,
+}
+
+impl AdoptedPet {
+    pub fn days_together(&self, clock: &Clock) -> u32 {
+        clock.day - self.adopted_day
+    }
+}
+
+/// The player's pet, if they have one.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Pet(pub Option<AdoptedPet>);
+
+pub fn pet_plugin(app: &mut App) {
+    register_ron_resource::<PetConfig>(app, &["pet.ron"]);
+    app.register_type::<Pet>()
+        .init_resource::<Pet>()
+        .add_systems(
+            FixedUpdate,
+            feed_pet.run_if(day_started).in_set(SimSet::Economy),
+        )
+        .add_systems(Update, offer_adoption.in_set(ActionSet::Offer))
+        .add_systems(Update, adopt_pet.run_if(in_state(AppState::Game)));
+}
+
+fn feed_pet(
+    config: Res<PetConfig>,
+    prices: Prices,
+    mut pet: ResMut<Pet>,
+    mut money: ResMut<Money>,
+    mut bank: ResMut<Bank>,
+    mut happiness: ResMut<Happiness>,
+    mut toasts: EventWriter<Toast>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    let Some(adopted) = pet.0.as_mut() else {
+        return;
+    };
+
+    let food_cost = prices.price(config.daily_food_cost);
+    if bank.pay(&mut money, food_cost) {
+        info!("Paid ${food_cost} for {}'s food", adopted.name);
+        transactions.write(Transaction::Expense(Category::Food, food_cost));
+        adopted.days_hungry = 0;
+        happiness.change(config.daily_happiness_bonus);
+        return;
+    }
+
+    adopted.days_hungry += 1;
+    if adopted.days_hungry >= config.run_away_after_days {
+        toasts.write(Toast::new(format!(
+            "{} ran away after {} days without food",
+            adopted.name, adopted.days_hungry
+        )));
+        happiness.change(config.run_away_happiness_change);
+        pet.0 = None;
+    } else {
+        toasts.write(Toast::new(format!(
+            "You couldn't afford food for {}",
+            adopted.name
+        )));
+    }
+}
+
+fn offer_adoption(
+    config: Res<PetConfig>,
+    pet: Res<Pet>,
+    home: Res<HomeArea>,
+    current: Res<CurrentArea>,
+    clock: Res<Clock>,
+    calendar: Res<Calendar>,
+    money: Res<Money>,
+    discount: Res<DiscountToken>,
+    prices: Prices,
+    mut actions: ResMut<AvailableActions>,
+    locale: Res<Locale>,
+) {
+    if pet.0.is_some() || current.0 != home.location {
+        return;
+    }
+    let fee = discount.price(prices.price(config.adoption_fee));
+    let base = discount.price(prices.without_cost_of_living(config.adoption_fee));
+    let label = format!(
+        "Shelter: adopt {} -{}",
+        config.name,
+        format::price(fee, base, *locale)
+    );
+    if let Some(reason) = calendar.closed_reason(clock.day) {
+        actions.offer_blocked(GameAction::AdoptPet, label, reason);
+    } else if money.amount >= fee {
+        actions.offer_labeled(GameAction::AdoptPet, label);
+    } else {
+        actions.offer_blocked(GameAction::AdoptPet, label, "Not enough cash");
+    }
+}
+
+fn adopt_pet(
+    mut pressed: EventReader<ActionPressed>,
+    config: Res<PetConfig>,
+    clock: Res<Clock>,
+    calendar: Res<Calendar>,
+    mut pet: ResMut<Pet>,
+    mut money: ResMut<Money>,
+    mut discount: ResMut<DiscountToken>,
+    prices: Prices,
+    mut undo: ResMut<UndoBuffer>,
+    mut toasts: EventWriter<Toast>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        let fee = discount.price(prices.price(config.adoption_fee));
+        if *action == GameAction::AdoptPet
+            && pet.0.is_none()
+            && money.amount >= fee
+            && calendar.holiday(clock.day).is_none()
+        {
+            money.amount -= fee;
+            transactions.write(Transaction::Expense(Category::Purchases, fee));
+            let label = format!("{} to the shelter", config.name);
+            undo.record(
+                UndoEntry::new(label, fee, UndoRestore::Pet, discount.0),
+                &money,
+                &clock,
+            );
+            discount.0 = None;
+            pet.0 = Some(AdoptedPet {
+                name: config.name.clone(),
+                adopted_day: clock.day,
+                days_hungry: 0,
+            });
+            toasts.write(Toast::undoable(format!(
+                "{} moved in with you!",
+                config.name
+            )));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::sim::{
+        catalog::{AREA_CATALOG_PATH, AreaCatalog, AreaCatalogData},
+        economy::Area,
+        new_game_plus::PriceLevel,
+    };
+
+    fn read<T: serde::de::DeserializeOwned>(path: &str) -> T {
+        ron::from_str(&std::fs::read_to_string(format!("assets/{path}")).unwrap()).unwrap()
+    }
+
+    /// A world with Biscuit adopted on day 1 and no money to feed them.
+    fn pet_world() -> (World, Schedule) {
+        let catalog = AreaCatalog::from_data(&read::<AreaCatalogData>(AREA_CATALOG_PATH));
+        let config: PetConfig = read(PET_CONFIG_PATH);
+        let mut world = World::new();
+        world.insert_resource(Pet(Some(AdoptedPet {
+            name: config.name.clone(),
+            adopted_day: 1,
+            days_hungry: 0,
+        })));
+        world.insert_resource(config);
+        world.insert_resource(HomeArea::new(Area::DeadbeatArea, &catalog));
+        world.insert_resource(catalog);
+        world.init_resource::<PriceLevel>();
+        world.insert_resource(Money { amount: 0 });
+        world.init_resource::<Bank>();
+        world.insert_resource(Happiness(50));
+        world.init_resource::<Events<Toast>>();
+        world.init_resource::<Events<Transaction>>();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(feed_pet);
+        (world, schedule)
+    }
+
+    fn days_hungry(world: &World) -> Option<u32> {
+        let pet = world.resource::<Pet>();
+        pet.0.as_ref().map(|adopted| adopted.days_hungry)
+    }
+
+    fn food_price(world: &mut World) -> u32 {
+        let cost = world.resource::<PetConfig>().daily_food_cost;
+        world
+            .run_system_once(move |prices: Prices| prices.price(cost))
+            .unwrap()
+    }
+
+    #[test]
+    fn a_pet_runs_away_on_its_last_hungry_day() {
+        let (mut world, mut schedule) = pet_world();
+        let config = world.resource::<PetConfig>().clone();
+        for day in 1..config.run_away_after_days {
+            schedule.run(&mut world);
+            assert_eq!(days_hungry(&world), Some(day));
+        }
+        assert_eq!(world.resource::<Happiness>().0, 50);
+        schedule.run(&mut world);
+        assert_eq!(days_hungry(&world), None);
+        let happiness = 50 + config.run_away_happiness_change;
+        assert_eq!(world.resource::<Happiness>().0, happiness as u32);
+        let toasts: Vec<_> = world.resource_mut::<Events<Toast>>().drain().collect();
+        assert_eq!(toasts.len() as u32, config.run_away_after_days);
+        assert!(toasts.last().unwrap().text.contains("ran away"));
+    }
+
+    #[test]
+    fn a_meal_resets_the_hunger() {
+        let (mut world, mut schedule) = pet_world();
+        let config = world.resource::<PetConfig>().clone();
+        let food = food_price(&mut world);
+        for _ in 1..config.run_away_after_days {
+            schedule.run(&mut world);
+        }
+        world.resource_mut::<Money>().amount = food;
+        schedule.run(&mut world);
+        assert_eq!(days_hungry(&world), Some(0));
+        assert_eq!(world.resource::<Money>().amount, 0);
+        let happiness = 50 + config.daily_happiness_bonus;
+        assert_eq!(world.resource::<Happiness>().0, happiness as u32);
+
+        // The count starts over, so it takes the full stretch again.
+        for day in 1..config.run_away_after_days {
+            schedule.run(&mut world);
+            assert_eq!(days_hungry(&world), Some(day));
+        }
+    }
+
+    #[test]
+    fn savings_feed_the_pet_when_cash_runs_short() {
+        let (mut world, mut schedule) = pet_world();
+        let food = food_price(&mut world);
+        world.resource_mut::<Money>().amount = food - 1;
+        world.resource_mut::<Bank>().savings = 100;
+        schedule.run(&mut world);
+        assert_eq!(days_hungry(&world), Some(0));
+        assert_eq!(world.resource::<Money>().amount, 0);
+        assert_eq!(world.resource::<Bank>().savings, 99);
+    }
+}
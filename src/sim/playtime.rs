@@ -0,0 +1,66 @@
+//! How long the player has spent on the run, in real time, and what the current session of
+//! play brought in. Time only counts while the simulation runs, so pausing, a modal or
+//! leaving the window in the background stops the count.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    SimSet,
+    clock::day_started,
+    ledger::{Category, Transaction},
+    sim_running,
+};
+use crate::AppState;
+use crate::timers::RealUiTime;
+
+/// Real time played on the run. Kept exact rather than in rounded seconds, so it doesn't
+/// drift however often it's saved and loaded.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Playtime(pub Duration);
+
+/// What happened since the player last entered the game.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct SessionStats {
+    /// Everything earned, before expenses.
+    pub earnings: u32,
+    /// Days the clock moved on.
+    pub days: u32,
+}
+
+pub fn playtime_plugin(app: &mut App) {
+    app.register_type::<Playtime>()
+        .register_type::<SessionStats>()
+        .init_resource::<Playtime>()
+        .init_resource::<SessionStats>()
+        .add_systems(
+            Update,
+            count_playtime.run_if(in_state(AppState::Game).and(sim_running)),
+        )
+        .add_systems(
+            FixedUpdate,
+            (count_earnings, count_days.run_if(day_started)).in_set(SimSet::Ledger),
+        );
+}
+
+fn count_playtime(time: RealUiTime, mut playtime: ResMut<Playtime>) {
+    playtime.0 += time.delta();
+}
+
+fn count_earnings(mut transactions: EventReader<Transaction>, mut stats: ResMut<SessionStats>) {
+    for transaction in transactions.read() {
+        if let Transaction::Income(category, amount) = transaction
+            && *category != Category::Refunds
+        {
+            stats.earnings += amount;
+        }
+    }
+}
+
+fn count_days(mut stats: ResMut<SessionStats>) {
+    stats.days += 1;
+}
@@ -0,0 +1,227 @@
+//! A small language for conditions written in the data, like `home == Mansion && hour >= 21`.
+//! A [`Predicate`] is comparisons joined by `&&`, each of a named fact with a number or a
+//! word, and holds when all of them do. What the names mean is up to the [`Facts`] it's
+//! checked against.
+
+use std::fmt;
+
+use serde::Deserialize;
+
+/// What a fact or the right-hand side of a comparison can be.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Number(i64),
+    /// A name, like an area's.
+    Word(String),
+}
+
+/// Answers the names a [`Predicate`] asks about.
+pub trait Facts {
+    /// The fact called `name`, or `None` if there's no such fact.
+    fn fact(&self, name: &str) -> Option<Value>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Le,
+    Ge,
+    Lt,
+    Gt,
+}
+
+impl Op {
+    /// Two-character operators come first, so `<=` isn't read as `<`.
+    const ALL: [Op; 6] = [Op::Eq, Op::Ne, Op::Le, Op::Ge, Op::Lt, Op::Gt];
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Eq => "==",
+            Op::Ne => "!=",
+            Op::Le => "<=",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Gt => ">",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Comparison {
+    fact: String,
+    op: Op,
+    value: Value,
+}
+
+impl Comparison {
+    fn parse(text: &str) -> Result<Self, PredicateError> {
+        let error = || PredicateError(text.trim().to_string());
+        let (op, (fact, value)) = Op::ALL
+            .into_iter()
+            .find_map(|op| Some((op, text.split_once(op.symbol())?)))
+            .ok_or_else(error)?;
+        let (fact, value) = (fact.trim(), value.trim());
+        let is_name = |word: &str| {
+            !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+        if !is_name(fact) || fact.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(error());
+        }
+        let value = match value.parse() {
+            Ok(number) => Value::Number(number),
+            Err(_) if is_name(value) => Value::Word(value.to_string()),
+            Err(_) => return Err(error()),
+        };
+        Ok(Comparison {
+            fact: fact.to_string(),
+            op,
+            value,
+        })
+    }
+
+    fn holds(&self, facts: &impl Facts) -> bool {
+        match (facts.fact(&self.fact), &self.value) {
+            (Some(Value::Number(fact)), Value::Number(value)) => match self.op {
+                Op::Eq => fact == *value,
+                Op::Ne => fact != *value,
+                Op::Le => fact <= *value,
+                Op::Ge => fact >= *value,
+                Op::Lt => fact < *value,
+                Op::Gt => fact > *value,
+            },
+            // Words only compare for equality.
+            (Some(Value::Word(fact)), Value::Word(value)) => match self.op {
+                Op::Eq => fact == *value,
+                Op::Ne => fact != *value,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+/// A comparison that doesn't parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PredicateError(String);
+
+impl fmt::Display for PredicateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` isn't a comparison like `money >= 100`", self.0)
+    }
+}
+
+impl std::error::Error for PredicateError {}
+
+/// Comparisons that all have to hold. A fact the [`Facts`] don't know, or a comparison of
+/// a number with a word, never holds.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub struct Predicate(Vec<Comparison>);
+
+impl Predicate {
+    pub fn parse(text: &str) -> Result<Self, PredicateError> {
+        text.split("&&")
+            .map(Comparison::parse)
+            .collect::<Result<_, _>>()
+            .map(Predicate)
+    }
+
+    pub fn holds(&self, facts: &impl Facts) -> bool {
+        self.0.iter().all(|comparison| comparison.holds(facts))
+    }
+}
+
+impl TryFrom<String> for Predicate {
+    type Error = PredicateError;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Predicate::parse(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    struct Known(HashMap<&'static str, Value>);
+
+    impl Facts for Known {
+        fn fact(&self, name: &str) -> Option<Value> {
+            self.0.get(name).cloned()
+        }
+    }
+
+    fn facts() -> Known {
+        Known(HashMap::from([
+            ("hour", Value::Number(21)),
+            ("money", Value::Number(-40)),
+            ("home", Value::Word("Mansion".to_string())),
+        ]))
+    }
+
+    fn holds(text: &str) -> bool {
+        Predicate::parse(text).unwrap().holds(&facts())
+    }
+
+    #[test]
+    fn two_character_operators_are_not_read_as_one() {
+        assert_eq!(Predicate::parse("hour <= 21").unwrap().0[0].op, Op::Le);
+        assert_eq!(Predicate::parse("hour>=21").unwrap().0[0].op, Op::Ge);
+        assert!(holds("hour <= 21"));
+        assert!(!holds("hour < 21"));
+        assert!(holds("hour >= 21"));
+        assert!(!holds("hour > 21"));
+        assert!(holds("hour < 22") && holds("hour != 20") && holds("money < -39"));
+    }
+
+    #[test]
+    fn words_only_compare_with_words_and_for_equality() {
+        assert!(holds("home == Mansion"));
+        assert!(holds("home != Condo"));
+        assert!(!holds("home == 3"));
+        assert!(!holds("home != 3"));
+        assert!(!holds("hour == Mansion"));
+        assert!(!holds("home >= Condo"));
+    }
+
+    #[test]
+    fn an_unknown_fact_never_holds() {
+        assert!(!holds("luck > 0"));
+        assert!(!holds("luck != 0"));
+        assert!(!holds("luck != Mansion"));
+    }
+
+    #[test]
+    fn every_comparison_in_a_chain_has_to_hold() {
+        assert!(holds("home == Mansion && hour >= 21 && money < 0"));
+        assert!(!holds("home == Mansion && hour >= 22 && money < 0"));
+        assert!(!holds("home == Mansion && luck > 0"));
+    }
+
+    #[test]
+    fn malformed_text_is_refused() {
+        for text in [
+            "",
+            "   ",
+            "hour",
+            "hour >= ",
+            "== 21",
+            "21 == hour",
+            "hour >= 21 &&",
+            "hour >= 21 && && home == Mansion",
+            "home == Big House",
+            "hour >= 2.5",
+        ] {
+            assert!(Predicate::parse(text).is_err(), "{text:?} parsed");
+        }
+        assert_eq!(
+            Predicate::parse("hour >= 21 && oops")
+                .unwrap_err()
+                .to_string(),
+            "`oops` isn't a comparison like `money >= 100`"
+        );
+    }
+}
@@ -0,0 +1,617 @@
+//! Quick-save and quick-load: [`QUICKSAVE_KEY`] during a run writes the run's state to the
+//! profile's [`QUICKSAVE_FILE`], and [`QUICKLOAD_KEY`] puts it back, also from the pause
+//! menu. Neither works while another modal or an event card is up, so a save never
+//! catches a choice half made. Loading over a run that has changed, see [`Dirty`], and
+//! moved on past the quicksave's time asks first. A weekly challenge only loads a quicksave of the same
+//! challenge, and a regular run only a regular one, so challenge results stay fair.
+//!
+//! The quit prompts can save on the way out with [`save_run`], and `--load` starts the
+//! game in one of the profile's [`save_slots`], the quicksave if it doesn't name one, see
+//! [`LoadOnStart`].
+//!
+//! The state is the run's resources, written through reflection as a scene. They're
+//! copied on the main thread, then serialized and written in the background, and read
+//! back the same way, see [`crate::save_file`]. Quick-saves closer together than
+//! [`QUICKSAVE_DEBOUNCE`] are written once, when it's up.
+
+use std::any::TypeId;
+use std::time::Duration;
+
+use bevy::{
+    ecs::entity::EntityHashMap,
+    prelude::*,
+    reflect::TypeRegistry,
+    scene::{DynamicScene, DynamicSceneBuilder, serde::SceneDeserializer},
+};
+use serde::de::DeserializeSeed;
+
+use super::{
+    Dirty,
+    bank::Bank,
+    bills::Bills,
+    buffs::Buffs,
+    challenge::Challenge,
+    charity::Reputation,
+    choices::ChoiceLog,
+    clock::Clock,
+    content::RunFilters,
+    crime::Upgrades,
+    debt::Debt,
+    dialogue::{DialogueMemory, DiscountToken},
+    economy::{
+        CurrentArea, Ending, FreePlay, HomeArea, Money, RentHistory, RentSchedule, WorkArea,
+    },
+    event_cards::{CardChosen, CardId, CardRequest, EventCardQueue},
+    jobs::{JobOffer, WeeklyOvertime},
+    journal::Journal,
+    landlord::{RentIncreases, RentTerms},
+    ledger::Ledger,
+    mail::Inbox,
+    market::{Market, Portfolio},
+    needs::{Energy, Happiness},
+    new_game_plus::PriceLevel,
+    onboarding::Checklist,
+    passive_income::IncomeSources,
+    pet::Pet,
+    playtime::Playtime,
+    replay::RunSetup,
+    rng::GameRng,
+    roommate::Roommate,
+    savings_goal::SavingsGoal,
+    travel::AreaUnlocks,
+    undo::UndoBuffer,
+    vignettes::Moments,
+    weather::Weather,
+};
+use crate::AppState;
+use crate::profiles::ProfilePaths;
+use crate::save_file::{SaveFinished, SaveId, Saves, load_in_background};
+use crate::screens::overlay::{Overlay, no_modal_open};
+use crate::toast::Toast;
+
+pub const QUICKSAVE_KEY: KeyCode = KeyCode::F5;
+pub const QUICKLOAD_KEY: KeyCode = KeyCode::F9;
+/// What a save's file name ends with, after its slot.
+pub const SAVE_EXTENSION: &str = ".scn.ron";
+/// The slot the quicksave is in.
+pub const QUICKSAVE_SLOT: &str = "quicksave";
+/// Where the quicksave lives, in the profile's directory.
+pub const QUICKSAVE_FILE: &str = "quicksave.scn.ron";
+/// The least time between two quicksave writes.
+pub const QUICKSAVE_DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// Insert before entering [`AppState::Game`] to continue the save in this slot instead of
+/// starting over, like `--load` does.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct LoadOnStart(pub String);
+
+/// The slots of the saves in `profile`, sorted by name.
+pub fn save_slots(profile: &ProfilePaths) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(profile.dir()) else {
+        return Vec::new();
+    };
+    let mut slots: Vec<_> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            name.strip_suffix(SAVE_EXTENSION).map(str::to_string)
+        })
+        .collect();
+    slots.sort();
+    slots
+}
+
+#[derive(Resource, Debug, Default)]
+struct QuickSaves {
+    /// [`Time<Real>`] elapsed at the last write.
+    last_write: Option<Duration>,
+    /// A quicksave asked for and not written yet.
+    save_requested: bool,
+    load_requested: bool,
+    /// The card asking whether to drop the progress since the last save.
+    confirm: Option<CardId>,
+    /// The save being written, and the time in the run it was taken at.
+    writing: Option<(SaveId, Clock)>,
+    /// The time in the run of the quicksave last written or loaded. `None` for one from an
+    /// earlier session, which counts as behind the run.
+    saved_at: Option<Clock>,
+}
+
+pub fn quicksave_plugin(app: &mut App) {
+    app.init_resource::<QuickSaves>()
+        .add_systems(
+            Update,
+            (
+                request_quicksave.run_if(no_modal_open),
+                request_quickload.run_if(no_modal_open.or(in_state(Overlay::Pause))),
+                answer_quickload,
+                write_quicksave.run_if(no_modal_open.and(quicksave_requested)),
+                load_quicksave.run_if(|saves: Res<QuickSaves>| saves.load_requested),
+            )
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        )
+        .add_systems(Update, finish_quicksave.run_if(on_event::<SaveFinished>))
+        .add_systems(
+            OnEnter(AppState::Game),
+            load_on_start
+                .after(super::reset_run)
+                .run_if(resource_exists::<LoadOnStart>),
+        );
+}
+
+fn quicksave_requested(saves: Res<QuickSaves>) -> bool {
+    saves.save_requested
+}
+
+/// What a quicksave is loaded over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoadOver {
+    /// The run being played, which has to be the same kind of run.
+    Run,
+    /// A run only just started to make way for it, with `--load`.
+    NewRun,
+    /// A replayed run, which loaded it at the same point when it was played.
+    Replay,
+}
+
+/// The run's resources that a quicksave keeps. The rest, like the undo buffer, start over
+/// on load.
+pub fn run_snapshot(world: &World) -> DynamicScene {
+    DynamicSceneBuilder::from_world(world)
+        .allow_resource::<RunSetup>()
+        .allow_resource::<Challenge>()
+        .allow_resource::<Clock>()
+        .allow_resource::<Money>()
+        .allow_resource::<Bank>()
+        .allow_resource::<Ledger>()
+        .allow_resource::<Market>()
+        .allow_resource::<Portfolio>()
+        .allow_resource::<Upgrades>()
+        .allow_resource::<DialogueMemory>()
+        .allow_resource::<DiscountToken>()
+        .allow_resource::<HomeArea>()
+        .allow_resource::<PriceLevel>()
+        .allow_resource::<CurrentArea>()
+        .allow_resource::<WorkArea>()
+        .allow_resource::<JobOffer>()
+        .allow_resource::<RentSchedule>()
+        .allow_resource::<RentHistory>()
+        .allow_resource::<Bills>()
+        .allow_resource::<Debt>()
+        .allow_resource::<RentTerms>()
+        .allow_resource::<RentIncreases>()
+        .allow_resource::<Inbox>()
+        .allow_resource::<Reputation>()
+        .allow_resource::<ChoiceLog>()
+        .allow_resource::<Checklist>()
+        .allow_resource::<Playtime>()
+        .allow_resource::<Ending>()
+        .allow_resource::<FreePlay>()
+        .allow_resource::<Happiness>()
+        .allow_resource::<Energy>()
+        .allow_resource::<Buffs>()
+        .allow_resource::<WeeklyOvertime>()
+        .allow_resource::<GameRng>()
+        .allow_resource::<Roommate>()
+        .allow_resource::<SavingsGoal>()
+        .allow_resource::<AreaUnlocks>()
+        .allow_resource::<Pet>()
+        .allow_resource::<Weather>()
+        .allow_resource::<Moments>()
+        .allow_resource::<Journal>()
+        .allow_resource::<IncomeSources>()
+        .extract_resources()
+        .build()
+}
+
+fn request_quicksave(keyboard_input: Res<ButtonInput<KeyCode>>, mut saves: ResMut<QuickSaves>) {
+    if keyboard_input.just_pressed(QUICKSAVE_KEY) {
+        saves.save_requested = true;
+    }
+}
+
+fn write_quicksave(world: &mut World) {
+    let now = world.resource::<Time<Real>>().elapsed();
+    let mut saves = world.resource_mut::<QuickSaves>();
+    if saves
+        .last_write
+        .is_some_and(|last| now.saturating_sub(last) < QUICKSAVE_DEBOUNCE)
+    {
+        return;
+    }
+    saves.save_requested = false;
+    saves.last_write = Some(now);
+    save_run(world);
+}
+
+/// Copies the run into the quicksave, which is serialized and written in the background.
+/// A toast says when it's written, or that it couldn't be, and quitting waits for it, see
+/// [`crate::save_file`]. The run is only clean, see [`Dirty`], once it's written.
+pub fn save_run(world: &mut World) -> SaveId {
+    let scene = run_snapshot(world);
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    info!("Quick-saving {} resources", scene.resources.len());
+    let path = world.resource::<ProfilePaths>().file(QUICKSAVE_FILE);
+    let id = world
+        .resource_mut::<Saves>()
+        .save_bytes_with_toast(path, "Quick-saved", move || {
+            scene
+                .serialize(&registry.read())
+                .map(String::into_bytes)
+                .map_err(|error| error.to_string())
+        });
+    let clock = *world.resource::<Clock>();
+    world.resource_mut::<QuickSaves>().writing = Some((id, clock));
+    id
+}
+
+// The run is clean once its save is written, unless it has moved on since it was taken.
+fn finish_quicksave(
+    mut finished: EventReader<SaveFinished>,
+    mut saves: ResMut<QuickSaves>,
+    clock: Res<Clock>,
+    mut dirty: ResMut<Dirty>,
+) {
+    let Some((writing, taken_at)) = saves.writing else {
+        finished.clear();
+        return;
+    };
+    if let Some(SaveFinished { result, .. }) = finished.read().find(|save| save.id == writing) {
+        saves.writing = None;
+        if result.is_ok() {
+            saves.saved_at = Some(taken_at);
+            if *clock == taken_at {
+                dirty.0 = false;
+            }
+        }
+    }
+}
+
+fn request_quickload(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    profile: Res<ProfilePaths>,
+    (dirty, clock): (Res<Dirty>, Res<Clock>),
+    mut saves: ResMut<QuickSaves>,
+    mut cards: ResMut<EventCardQueue>,
+    mut toasts: EventWriter<Toast>,
+    mut next_state: ResMut<NextState<Overlay>>,
+) {
+    if !keyboard_input.just_pressed(QUICKLOAD_KEY) {
+        return;
+    }
+    if !profile.file(QUICKSAVE_FILE).exists() {
+        toasts.write(Toast::new("There's no quicksave yet"));
+        return;
+    }
+    // Closes the pause menu, if that's where it came from.
+    next_state.set(Overlay::None);
+    let ahead = saves
+        .saved_at
+        .is_none_or(|saved| clock.total_hours() > saved.total_hours());
+    if dirty.0 && ahead {
+        let request = CardRequest::new(
+            "Load the quicksave?",
+            "Everything since the last save will be lost.",
+        )
+        .with_buttons(["Load", "Cancel"])
+        .with_priority(2);
+        saves.confirm = Some(cards.push(request));
+    } else {
+        saves.load_requested = true;
+    }
+}
+
+fn answer_quickload(mut chosen: EventReader<CardChosen>, mut saves: ResMut<QuickSaves>) {
+    for CardChosen { card, button } in chosen.read() {
+        if saves.confirm == Some(*card) {
+            saves.confirm = None;
+            saves.load_requested = *button == 0;
+        }
+    }
+}
+
+fn load_quicksave(world: &mut World) {
+    world.resource_mut::<QuickSaves>().load_requested = false;
+    load_run(world, QUICKSAVE_FILE, LoadOver::Run);
+}
+
+// Continues the save asked for with `--load`, over the run just started.
+fn load_on_start(world: &mut World) {
+    let Some(LoadOnStart(slot)) = world.remove_resource::<LoadOnStart>() else {
+        return;
+    };
+    load_run(world, &format!("{slot}{SAVE_EXTENSION}"), LoadOver::NewRun);
+}
+
+/// Reads the save in the profile's `file` in the background, to put it over the current
+/// run once it's read.
+fn load_run(world: &mut World, file: &str, over: LoadOver) {
+    let path = world.resource::<ProfilePaths>().file(file);
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let quicksave = file == QUICKSAVE_FILE;
+    load_in_background(
+        world,
+        path,
+        move |text| parse_run(&registry.read(), text),
+        move |world, scene| {
+            if apply_quicksave(world, scene, over) && quicksave {
+                let clock = *world.resource::<Clock>();
+                world.resource_mut::<QuickSaves>().saved_at = Some(clock);
+            }
+        },
+    );
+}
+
+/// Puts a quicksave's text over the run straight away, the way loading it from the file
+/// would. Replays give the quick-loads they logged this way, see [`super::replay`].
+pub fn restore_run(world: &mut World, text: &str) {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let scene = parse_run(&registry.read(), text);
+    apply_quicksave(world, scene, LoadOver::Replay);
+}
+
+fn parse_run(registry: &TypeRegistry, text: &str) -> Result<DynamicScene, String> {
+    let mut deserializer =
+        ron::de::Deserializer::from_str(text).map_err(|error| error.to_string())?;
+    SceneDeserializer {
+        type_registry: registry,
+    }
+    .deserialize(&mut deserializer)
+    .map_err(|error| error.to_string())
+}
+
+/// Whether it was put over the run.
+fn apply_quicksave(world: &mut World, scene: Result<DynamicScene, String>, over: LoadOver) -> bool {
+    let loaded = scene.and_then(|scene| {
+        let saved = saved_challenge(&scene);
+        if over == LoadOver::Run && saved != *world.resource::<Challenge>() {
+            let refusal = match saved.0 {
+                Some(_) => "The quicksave is from a weekly challenge",
+                None => "The quicksave isn't from this challenge",
+            };
+            world.send_event(Toast::new(refusal));
+            return Ok(false);
+        }
+        #[cfg(feature = "dev")]
+        super::replay::record_quick_load(world, &scene);
+        scene
+            .write_to_world(world, &mut EntityHashMap::default())
+            .map(|()| true)
+            .map_err(|error| error.to_string())
+    });
+    match loaded {
+        Ok(true) => {
+            info!("Quick-loaded");
+            if let Some(setup) = world.get_resource::<RunSetup>() {
+                let filters = RunFilters(setup.content_filters);
+                world.insert_resource(filters);
+            }
+            world.insert_resource(UndoBuffer::default());
+            world.resource_mut::<Dirty>().0 = false;
+            if over != LoadOver::NewRun {
+                world.send_event(Toast::new("Quick-loaded"));
+            }
+            true
+        }
+        Ok(false) => false,
+        Err(error) => {
+            warn!("Couldn't load the quicksave: {error}");
+            world.send_event(Toast::new("Couldn't load the quicksave"));
+            false
+        }
+    }
+}
+
+/// The challenge the quicksave was played in. Quicksaves from before challenges were kept
+/// are regular runs.
+fn saved_challenge(scene: &DynamicScene) -> Challenge {
+    scene
+        .resources
+        .iter()
+        .filter(|resource| {
+            resource
+                .get_represented_type_info()
+                .is_some_and(|info| info.type_id() == TypeId::of::<Challenge>())
+        })
+        .find_map(|resource| Challenge::from_reflect(resource.as_ref()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+    use crate::save_file::save_file_plugin;
+    use crate::screens::overlay::ModalStack;
+    use crate::sim::{challenge::IsoWeek, step_ticks};
+    use crate::testing::{ScratchDir, TestGame};
+
+    /// A run with `money` in hand, playing in its own profile named after `test`, in the
+    /// scratch directory that goes with it.
+    fn quicksave_app(test: &str, money: u32) -> (App, ScratchDir) {
+        let dir = ScratchDir::new();
+        let profile = format!("Quicksave test {test}");
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, AssetPlugin::default()))
+            .init_asset::<Font>()
+            .insert_state(AppState::Game)
+            .add_sub_state::<Overlay>()
+            .add_event::<Toast>()
+            .add_event::<CardChosen>()
+            .register_type::<Money>()
+            .register_type::<Challenge>()
+            .insert_resource(ProfilePaths::new(&dir.profiles(), &profile))
+            .insert_resource(Money { amount: money })
+            .init_resource::<Challenge>()
+            .init_resource::<Clock>()
+            .init_resource::<Dirty>()
+            .init_resource::<ModalStack>()
+            .init_resource::<EventCardQueue>()
+            .init_resource::<UndoBuffer>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .add_plugins((save_file_plugin, quicksave_plugin));
+        app.update();
+        (app, dir)
+    }
+
+    // Writes the quicksave right away, so the test doesn't wait on the background write.
+    fn write_quicksave_now(app: &App) {
+        let world = app.world();
+        let text = run_snapshot(world)
+            .serialize(&world.resource::<AppTypeRegistry>().read())
+            .unwrap();
+        let path = world.resource::<ProfilePaths>().file(QUICKSAVE_FILE);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+
+    fn press(app: &mut App, key: KeyCode) {
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+        app.update();
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.release(key);
+        input.clear();
+    }
+
+    /// Updates until the quicksave has been read and applied, or refused.
+    fn finish_loading(app: &mut App) -> Vec<String> {
+        let mut toasts = Vec::new();
+        for _ in 0..1000 {
+            app.update();
+            toasts.extend(
+                app.world_mut()
+                    .resource_mut::<Events<Toast>>()
+                    .drain()
+                    .map(|toast| toast.text),
+            );
+            if !toasts.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        toasts
+    }
+
+    fn money(app: &App) -> u32 {
+        app.world().resource::<Money>().amount
+    }
+
+    #[test]
+    fn quick_load_puts_the_run_back_and_resets_the_dirty_flag() {
+        let (mut app, _dir) = quicksave_app("load", 100);
+        write_quicksave_now(&app);
+        app.world_mut().resource_mut::<Money>().amount = 40;
+        app.world_mut().resource_mut::<Dirty>().0 = true;
+
+        press(&mut app, QUICKLOAD_KEY);
+        let card = app
+            .world()
+            .resource::<QuickSaves>()
+            .confirm
+            .expect("loading over unsaved progress asks first");
+        app.world_mut().send_event(CardChosen { card, button: 0 });
+        let toasts = finish_loading(&mut app);
+
+        assert_eq!(toasts, ["Quick-loaded"]);
+        assert_eq!(money(&app), 100);
+        assert!(!app.world().resource::<Dirty>().0);
+    }
+
+    #[test]
+    fn quick_load_only_asks_once_the_run_has_moved_on_past_the_quicksave() {
+        let (mut app, _dir) = quicksave_app("ahead", 100);
+        press(&mut app, QUICKSAVE_KEY);
+        assert_eq!(finish_loading(&mut app), ["Quick-saved"]);
+
+        // Changed, but still at the quicksave's time.
+        app.world_mut().resource_mut::<Money>().amount = 40;
+        app.world_mut().resource_mut::<Dirty>().0 = true;
+        press(&mut app, QUICKLOAD_KEY);
+        assert_eq!(app.world().resource::<QuickSaves>().confirm, None);
+        assert_eq!(finish_loading(&mut app), ["Quick-loaded"]);
+        assert_eq!(money(&app), 100);
+
+        app.world_mut().resource_mut::<Clock>().hour += 1;
+        app.world_mut().resource_mut::<Dirty>().0 = true;
+        press(&mut app, QUICKLOAD_KEY);
+        assert!(app.world().resource::<QuickSaves>().confirm.is_some());
+    }
+
+    #[test]
+    fn neither_key_does_anything_while_a_modal_is_open() {
+        let (mut app, _dir) = quicksave_app("modal", 100);
+        write_quicksave_now(&app);
+        app.world_mut().resource_mut::<Money>().amount = 40;
+        app.world_mut().resource_mut::<ModalStack>().open_modal();
+
+        press(&mut app, QUICKLOAD_KEY);
+        press(&mut app, QUICKSAVE_KEY);
+        for _ in 0..10 {
+            app.update();
+        }
+
+        let saves = app.world().resource::<QuickSaves>();
+        assert!(!saves.load_requested && !saves.save_requested);
+        assert_eq!(saves.confirm, None);
+        assert_eq!(money(&app), 40);
+    }
+
+    #[test]
+    fn a_challenge_refuses_a_regular_quicksave() {
+        let (mut app, _dir) = quicksave_app("challenge", 100);
+        write_quicksave_now(&app);
+        app.world_mut().resource_mut::<Money>().amount = 40;
+        *app.world_mut().resource_mut::<Challenge>() =
+            Challenge(Some(IsoWeek::from_unix_days(20_000)));
+
+        press(&mut app, QUICKLOAD_KEY);
+        let toasts = finish_loading(&mut app);
+
+        assert_eq!(toasts, ["The quicksave isn't from this challenge"]);
+        assert_eq!(money(&app), 40);
+    }
+
+    #[test]
+    fn the_dirty_flag_follows_ticks_saves_loads_and_new_games() {
+        // Only the ticks stepped below move the run.
+        let mut app = TestGame::paused_in_game("Dirty flag");
+        let in_game = |app: &App| {
+            *app.world().resource::<State<AppState>>().get() == AppState::Game
+                && app.world().resource::<ModalStack>().is_empty()
+        };
+        let dirty = |app: &App| app.world().resource::<Dirty>().0;
+        assert!(!dirty(&app), "a new run starts clean");
+
+        step_ticks(app.world_mut(), 1);
+        assert!(dirty(&app));
+
+        save_run(app.world_mut());
+        assert!(dirty(&app), "not clean until it's written");
+        let saved = *app.world().resource::<Clock>();
+        app.update_until(|app| !dirty(app));
+
+        step_ticks(app.world_mut(), 1);
+        assert!(dirty(&app));
+        app.world_mut().resource_mut::<QuickSaves>().load_requested = true;
+        app.update_until(|app| !dirty(app));
+        assert_eq!(*app.world().resource::<Clock>(), saved);
+
+        step_ticks(app.world_mut(), 1);
+        assert!(dirty(&app));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Menu);
+        app.update_until(|app| *app.world().resource::<State<AppState>>().get() == AppState::Menu);
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Game);
+        app.update_until(in_game);
+        assert!(!dirty(&app), "a new game starts clean");
+        assert_eq!(*app.world().resource::<Clock>(), Clock::default());
+    }
+}
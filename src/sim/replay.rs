@@ -0,0 +1,694 @@
+//! Replay logs, for reproducing a bug report. Dev builds log every run to the profile's
+//! [`REPLAY_FILE`]: what the run was set up from, every [`Input`] the run depends on with
+//! the hour it came in and the screen it was given on, and a [`checksum`] of the run at
+//! the start of each day.
+//!
+//! Starting the game with `--replay <file>` goes straight into a new run set up from the
+//! log and gives the logged inputs again at the same hours, one a frame. An input waits
+//! for its screen to open, and screens the player closed without doing anything are
+//! closed again. The simulation holds off on the next hour until the inputs due are in,
+//! and since every roll goes through the seeded [`GameRng`] the run plays out the same.
+//! Each day's checksum is compared with the log's, and the first day that doesn't match
+//! is reported. With `--headless` too, the log plays without a window as fast as it can,
+//! and the game quits once it's over, with an error if it diverged.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+
+use bevy::prelude::*;
+#[cfg(feature = "dev")]
+use bevy::scene::DynamicScene;
+use serde::{Deserialize, Serialize};
+
+use super::SimSet;
+use super::bank::Bank;
+use super::clock::{Clock, day_started};
+use super::economy::{Area, Money};
+use super::event_cards::EventCardQueue;
+use super::needs::{Energy, Happiness};
+use super::new_game_plus::OwnedItem;
+use super::quicksave::restore_run;
+use super::rng::GameRng;
+use super::travel::TravelRequested;
+use super::undo::UndoRequested;
+use crate::AppState;
+use crate::launch::LaunchOptions;
+use crate::screens::actions::{ActionPressed, GameAction};
+use crate::screens::amount_input::{AmountConfirmed, AmountInput};
+use crate::screens::dialogue::ResponsePicked;
+use crate::screens::event_card::CardAnswered;
+use crate::screens::freelance::MarkerStopped;
+use crate::screens::investments::{Trade, TradeRequested};
+use crate::screens::menu::MenuState;
+use crate::screens::nav::{ScreenNav, TransitionCause};
+use crate::screens::negotiation::ApproachPicked;
+use crate::screens::overlay::Overlay;
+use crate::screens::savings_goal::GoalCleared;
+use crate::settings::ContentFilters;
+use crate::toast::Toast;
+#[cfg(feature = "dev")]
+use crate::{profiles::ProfilePaths, save_file::Saves};
+
+/// The last run's log, in the profile's directory.
+#[cfg(feature = "dev")]
+pub const REPLAY_FILE: &str = "replay.ron";
+
+/// Everything a run was set up from, so a replay can set up the same run.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct RunSetup {
+    pub seed: u64,
+    /// The New Game+ cycle whose prices the run uses.
+    pub cycle: u32,
+    pub carry_over: Option<OwnedItem>,
+    pub content_filters: ContentFilters,
+    /// The starting scenario's id, the regular start if `None`, see [`super::scenario`].
+    #[serde(default)]
+    pub scenario: Option<String>,
+}
+
+/// Something the player did that the run depends on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Input {
+    Action(GameAction),
+    /// An amount confirmed in an [`AmountInput`], like a deposit or a donation.
+    Amount(u32),
+    Travel(Area),
+    Trade(Trade),
+    /// A button on the event card on screen.
+    Card(usize),
+    /// An approach to the landlord, or walking away.
+    Approach(Option<usize>),
+    /// A response in a dialogue, or saying goodbye.
+    Response(Option<usize>),
+    /// Where the freelance gig's marker was stopped.
+    Marker(f32),
+    ClearGoal,
+    Undo,
+    /// A quicksave loaded over the run, as the quicksave's text.
+    QuickLoad(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedInput {
+    /// [`Clock::total_hours`] when it came in.
+    pub at: u32,
+    /// The overlay it was given on.
+    pub overlay: Overlay,
+    pub input: Input,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayCheck {
+    pub day: u32,
+    /// Numbers drawn from the [`GameRng`] so far.
+    pub draws: u64,
+    pub checksum: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayLog {
+    pub setup: RunSetup,
+    pub inputs: Vec<LoggedInput>,
+    pub days: Vec<DayCheck>,
+}
+
+/// The log of the run being played, while dev builds keep one.
+#[cfg(feature = "dev")]
+#[derive(Resource, Debug)]
+pub struct Recording {
+    log: ReplayLog,
+    /// [`Clock::total_hours`] once this frame's ticks ran, before anything the player
+    /// did in it moved the clock on.
+    hour: u32,
+}
+
+#[cfg(feature = "dev")]
+impl Recording {
+    fn push(&mut self, at: u32, overlay: Overlay, input: Input) {
+        self.log.inputs.push(LoggedInput { at, overlay, input });
+    }
+}
+
+/// Frames an input waits for its overlay to open before it's skipped. Opening one can
+/// take a couple of frames, when it comes from a card's answer.
+const OVERLAY_WAIT_FRAMES: u32 = 10;
+
+/// The log being played back, from `--replay`.
+#[derive(Resource, Debug)]
+pub struct Playback {
+    log: ReplayLog,
+    next_input: usize,
+    next_day: usize,
+    started: bool,
+    /// An input went in this frame, so the hour waits for it to be handled.
+    holding: bool,
+    /// Frames the next input has waited for its overlay.
+    waited: u32,
+    diverged: bool,
+}
+
+impl Playback {
+    pub fn setup(&self) -> &RunSetup {
+        &self.log.setup
+    }
+
+    /// Every input is in and every day has been checked.
+    fn finished(&self) -> bool {
+        !self.holding
+            && self.next_input == self.log.inputs.len()
+            && self.next_day == self.log.days.len()
+    }
+}
+
+/// A fingerprint of the run's state: money, savings, needs and where the [`GameRng`] is.
+/// Two runs with the same checksum on a day very likely did the same things up to it.
+pub fn checksum(
+    money: &Money,
+    bank: &Bank,
+    happiness: &Happiness,
+    energy: &Energy,
+    rng: &GameRng,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    money.amount.hash(&mut hasher);
+    bank.savings.hash(&mut hasher);
+    happiness.0.hash(&mut hasher);
+    energy.0.hash(&mut hasher);
+    rng.state().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Run condition for the simulation: a replay has fed in every input due this hour.
+pub fn playback_caught_up(playback: Option<Res<Playback>>, clock: Res<Clock>) -> bool {
+    playback.is_none_or(|playback| {
+        !playback.holding
+            && playback
+                .log
+                .inputs
+                .get(playback.next_input)
+                .is_none_or(|next| next.at > clock.total_hours())
+    })
+}
+
+pub fn replay_plugin(app: &mut App) {
+    if let Some(path) = app.world().resource::<LaunchOptions>().replay.clone() {
+        match load(&path) {
+            Ok(log) => {
+                info!(
+                    "Replaying {} with {} inputs over {} days",
+                    path.display(),
+                    log.inputs.len(),
+                    log.days.len()
+                );
+                app.insert_resource(Playback {
+                    log,
+                    next_input: 0,
+                    next_day: 0,
+                    started: false,
+                    holding: false,
+                    waited: 0,
+                    diverged: false,
+                });
+            }
+            Err(error) => error!("Couldn't read the replay {}: {error}", path.display()),
+        }
+    }
+    app.register_type::<RunSetup>()
+        .add_systems(
+            OnEnter(MenuState::Main),
+            start_playback.run_if(resource_exists::<Playback>),
+        )
+        .add_systems(
+            PreUpdate,
+            feed_inputs.run_if(in_state(AppState::Game).and(resource_exists::<Playback>)),
+        )
+        .add_systems(
+            FixedUpdate,
+            check_day
+                .in_set(SimSet::Ledger)
+                .run_if(day_started.and(resource_exists::<Playback>)),
+        )
+        .add_systems(Last, release_hold.run_if(resource_exists::<Playback>));
+    if app.world().resource::<LaunchOptions>().headless {
+        app.add_systems(
+            PostUpdate,
+            (play_headless, finish_headless)
+                .chain()
+                .run_if(in_state(AppState::Game).and(resource_exists::<Playback>)),
+        );
+    }
+
+    #[cfg(feature = "dev")]
+    app.add_systems(
+        OnEnter(AppState::Game),
+        start_recording
+            .after(super::reset_run)
+            .run_if(not(resource_exists::<Playback>).and(super::starting_run)),
+    )
+    .add_systems(
+        RunFixedMainLoop,
+        record_hour
+            .in_set(RunFixedMainLoopSystem::AfterFixedMainLoop)
+            .run_if(resource_exists::<Recording>),
+    )
+    .add_systems(
+        PostUpdate,
+        record_inputs.run_if(in_state(AppState::Game).and(resource_exists::<Recording>)),
+    )
+    .add_systems(
+        FixedUpdate,
+        record_day
+            .in_set(SimSet::Ledger)
+            .run_if(day_started.and(resource_exists::<Recording>)),
+    )
+    .add_systems(
+        OnExit(AppState::Game),
+        stop_recording.run_if(resource_exists::<Recording>),
+    );
+}
+
+fn load(path: &Path) -> Result<ReplayLog, String> {
+    let text = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+    ron::from_str(&text).map_err(|error| error.to_string())
+}
+
+// Skips the menu once, so the replay starts as soon as the game has loaded. Like
+// `--skip-menu`, this waits for the menu's main screen so the menu ends up disabled.
+fn start_playback(mut playback: ResMut<Playback>, mut nav: ScreenNav) {
+    if !playback.started {
+        playback.started = true;
+        nav.set_app(AppState::Game, TransitionCause::Automatic);
+        nav.set_menu(MenuState::Disabled, TransitionCause::Automatic);
+    }
+}
+
+// Before `Update`, so each input is handled in the hour it came in.
+fn feed_inputs(world: &mut World) {
+    let hour = world.resource::<Clock>().total_hours();
+    let overlay = *world.resource::<State<Overlay>>().get();
+    let opening = matches!(
+        *world.resource::<NextState<Overlay>>(),
+        NextState::Pending(_)
+    );
+    let cards_waiting = !world.resource::<EventCardQueue>().is_empty();
+    if opening {
+        return;
+    }
+    let playback = world.resource::<Playback>();
+    let next = playback.log.inputs.get(playback.next_input).cloned();
+    if next
+        .as_ref()
+        .is_none_or(|next| next.at > hour || next.overlay != overlay)
+    {
+        // The player closed whatever is open without doing anything more there.
+        if overlay != Overlay::None {
+            world
+                .resource_mut::<NextState<Overlay>>()
+                .set(Overlay::None);
+            return;
+        }
+        let Some(next) = next.filter(|next| next.at <= hour) else {
+            return;
+        };
+        // Cards open on their own once nothing else is, and other overlays from the
+        // action fed in before.
+        let card_coming = cards_waiting && next.overlay == Overlay::EventCard;
+        let mut playback = world.resource_mut::<Playback>();
+        if card_coming || playback.waited < OVERLAY_WAIT_FRAMES {
+            playback.waited += 1;
+            return;
+        }
+        // Like the answer to the quick-load prompt, which isn't part of the run.
+        info!("Replay: skipped {:?}, its overlay never opened", next.input);
+    } else if let Some(next) = next {
+        give(world, next.input);
+    }
+    let mut playback = world.resource_mut::<Playback>();
+    playback.waited = 0;
+    playback.next_input += 1;
+    playback.holding = true;
+    if playback.next_input == playback.log.inputs.len() {
+        info!("Replay: every logged input is in");
+    }
+}
+
+fn give(world: &mut World, input: Input) {
+    match input {
+        Input::Action(action) => {
+            world.send_event(ActionPressed(action));
+        }
+        Input::Amount(value) => {
+            let input = world
+                .query_filtered::<Entity, With<AmountInput>>()
+                .iter(world)
+                .next()
+                .unwrap_or(Entity::PLACEHOLDER);
+            world.send_event(AmountConfirmed { input, value });
+        }
+        Input::Travel(area) => {
+            world.send_event(TravelRequested(area));
+        }
+        Input::Trade(trade) => {
+            world.send_event(TradeRequested(trade));
+        }
+        Input::Card(button) => {
+            world.send_event(CardAnswered(button));
+        }
+        Input::Approach(choice) => {
+            world.send_event(ApproachPicked(choice));
+        }
+        Input::Response(choice) => {
+            world.send_event(ResponsePicked(choice));
+        }
+        Input::Marker(position) => {
+            world.send_event(MarkerStopped(position));
+        }
+        Input::ClearGoal => {
+            world.send_event(GoalCleared);
+        }
+        Input::Undo => {
+            world.send_event(UndoRequested);
+        }
+        Input::QuickLoad(text) => restore_run(world, &text),
+    }
+}
+
+fn release_hold(mut playback: ResMut<Playback>) {
+    playback.holding = false;
+}
+
+fn check_day(
+    mut playback: ResMut<Playback>,
+    clock: Res<Clock>,
+    (money, bank, happiness, energy, rng): (
+        Res<Money>,
+        Res<Bank>,
+        Res<Happiness>,
+        Res<Energy>,
+        Res<GameRng>,
+    ),
+    mut toasts: EventWriter<Toast>,
+) {
+    let Some(&logged) = playback.log.days.get(playback.next_day) else {
+        return;
+    };
+    if logged.day != clock.day {
+        return;
+    }
+    playback.next_day += 1;
+    let checksum = checksum(&money, &bank, &happiness, &energy, &rng);
+    if checksum == logged.checksum || playback.diverged {
+        return;
+    }
+    playback.diverged = true;
+    warn!(
+        "Replay diverged on day {}: {} RNG draws against {} logged, ${} in hand",
+        clock.day,
+        rng.draws(),
+        logged.draws,
+        money.amount
+    );
+    toasts.write(Toast::new(format!("Replay diverged on day {}", clock.day)));
+}
+
+// Without a window to wait on, an hour goes by every frame the simulation can run.
+fn play_headless(world: &mut World) {
+    super::step_ticks(world, 1);
+}
+
+// There's nobody watching to see where a diverged replay goes from there.
+fn finish_headless(playback: Res<Playback>, mut exit: EventWriter<AppExit>) {
+    if playback.diverged {
+        exit.write(AppExit::error());
+    } else if playback.finished() {
+        info!("Replay: played back without diverging");
+        exit.write(AppExit::Success);
+    }
+}
+
+#[cfg(feature = "dev")]
+fn start_recording(mut commands: Commands, setup: Res<RunSetup>) {
+    commands.insert_resource(Recording {
+        log: ReplayLog {
+            setup: setup.clone(),
+            inputs: Vec::new(),
+            days: Vec::new(),
+        },
+        hour: 0,
+    });
+}
+
+#[cfg(feature = "dev")]
+fn record_hour(mut recording: ResMut<Recording>, clock: Res<Clock>) {
+    recording.hour = clock.total_hours();
+}
+
+// After `Update`, so the overlay is still the one the inputs were given on.
+#[cfg(feature = "dev")]
+fn record_inputs(
+    mut recording: ResMut<Recording>,
+    overlay: Res<State<Overlay>>,
+    (mut actions, mut amounts, mut trips, mut trades, mut cards): (
+        EventReader<ActionPressed>,
+        EventReader<AmountConfirmed>,
+        EventReader<TravelRequested>,
+        EventReader<TradeRequested>,
+        EventReader<CardAnswered>,
+    ),
+    (mut approaches, mut responses, mut markers, mut cleared, mut undos): (
+        EventReader<ApproachPicked>,
+        EventReader<ResponsePicked>,
+        EventReader<MarkerStopped>,
+        EventReader<GoalCleared>,
+        EventReader<UndoRequested>,
+    ),
+) {
+    let at = recording.hour;
+    let on = *overlay.get();
+    let inputs = amounts
+        .read()
+        .map(|confirmed| Input::Amount(confirmed.value))
+        .chain(
+            trips
+                .read()
+                .map(|TravelRequested(area)| Input::Travel(*area)),
+        )
+        .chain(
+            trades
+                .read()
+                .map(|TradeRequested(trade)| Input::Trade(*trade)),
+        )
+        .chain(
+            cards
+                .read()
+                .map(|CardAnswered(button)| Input::Card(*button)),
+        )
+        .chain(
+            approaches
+                .read()
+                .map(|ApproachPicked(choice)| Input::Approach(*choice)),
+        )
+        .chain(
+            responses
+                .read()
+                .map(|ResponsePicked(choice)| Input::Response(*choice)),
+        )
+        .chain(
+            markers
+                .read()
+                .map(|MarkerStopped(marker)| Input::Marker(*marker)),
+        )
+        .chain(cleared.read().map(|_| Input::ClearGoal))
+        .chain(undos.read().map(|_| Input::Undo))
+        .collect::<Vec<_>>();
+    for input in inputs {
+        recording.push(at, on, input);
+    }
+    // Actions are only ever taken with nothing open, even the ones picked from a screen.
+    // After the rest, since answering a card can take one.
+    for ActionPressed(action) in actions.read() {
+        recording.push(at, Overlay::None, Input::Action(*action));
+    }
+}
+
+/// Logs a quicksave about to be loaded over the run, while one is being recorded.
+#[cfg(feature = "dev")]
+pub fn record_quick_load(world: &mut World, scene: &DynamicScene) {
+    if !world.contains_resource::<Recording>() {
+        return;
+    }
+    let at = world.resource::<Clock>().total_hours();
+    let overlay = *world.resource::<State<Overlay>>().get();
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    match scene.serialize(&registry.read()) {
+        Ok(text) => world
+            .resource_mut::<Recording>()
+            .push(at, overlay, Input::QuickLoad(text)),
+        Err(error) => warn!("Couldn't log the quick-load: {error}"),
+    }
+}
+
+// Written every day, so a run that crashes still leaves its log.
+#[cfg(feature = "dev")]
+fn record_day(
+    mut recording: ResMut<Recording>,
+    clock: Res<Clock>,
+    (money, bank, happiness, energy, rng): (
+        Res<Money>,
+        Res<Bank>,
+        Res<Happiness>,
+        Res<Energy>,
+        Res<GameRng>,
+    ),
+    profile: Res<ProfilePaths>,
+    mut saves: ResMut<Saves>,
+) {
+    recording.log.days.push(DayCheck {
+        day: clock.day,
+        draws: rng.draws(),
+        checksum: checksum(&money, &bank, &happiness, &energy, &rng),
+    });
+    saves.save_ron(profile.file(REPLAY_FILE), recording.log.clone());
+}
+
+#[cfg(feature = "dev")]
+fn stop_recording(
+    mut commands: Commands,
+    recording: Res<Recording>,
+    profile: Res<ProfilePaths>,
+    mut saves: ResMut<Saves>,
+) {
+    saves.save_ron(profile.file(REPLAY_FILE), recording.log.clone());
+    commands.remove_resource::<Recording>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestGame;
+
+    /// A few days of a run with every kind of input in it, recorded with a dev build by
+    /// `record_the_fixture`.
+    const FIXTURE: &str = "replays/short_run.ron";
+    const PROFILE: &str = "Replay test";
+
+    #[test]
+    fn the_fixture_plays_back_the_same_run() {
+        let mut app = TestGame::new(LaunchOptions {
+            replay: Some(FIXTURE.into()),
+            headless: true,
+            profile: Some(PROFILE.to_string()),
+            ..default()
+        });
+        let exit = app.run_to_exit();
+
+        let playback = app.world().resource::<Playback>();
+        assert!(
+            playback
+                .log
+                .inputs
+                .iter()
+                .any(|logged| matches!(logged.input, Input::QuickLoad(_))),
+            "the fixture should have a quick-load in it"
+        );
+        assert_eq!(playback.next_input, playback.log.inputs.len());
+        assert_eq!(playback.next_day, playback.log.days.len());
+        assert!(
+            !playback.diverged,
+            "the run didn't match the log's checksums"
+        );
+        assert_eq!(exit, AppExit::Success);
+    }
+
+    /// Records [`FIXTURE`] again, for when the game changes in a way that makes it
+    /// diverge: `cargo test --features dev -- --ignored record_the_fixture`.
+    #[cfg(feature = "dev")]
+    #[test]
+    #[ignore]
+    fn record_the_fixture() {
+        use crate::screens::investments::Trade;
+        use crate::sim::economy::Area;
+        use crate::sim::quicksave::{restore_run, run_snapshot};
+
+        const RECORDING: &str = "Replay fixture";
+        let mut app = TestGame::new(LaunchOptions {
+            skip_menu: true,
+            headless: true,
+            seed: Some(2024),
+            profile: Some(RECORDING.to_string()),
+            ..default()
+        });
+        app.update_until(|app| app.world().contains_resource::<Recording>());
+
+        // Plays each input the way the player would, answering whatever cards come up.
+        let play = |app: &mut App, input: Input| {
+            give(app.world_mut(), input);
+            for _ in 0..3 {
+                app.update();
+            }
+        };
+        let close = |app: &mut App| {
+            app.world_mut()
+                .resource_mut::<NextState<Overlay>>()
+                .set(Overlay::None);
+            app.update();
+            app.update();
+            while !app.world().resource::<EventCardQueue>().is_empty() {
+                play(app, Input::Card(0));
+            }
+        };
+        let wait_until = |app: &mut App, hour: u32| {
+            while app.world().resource::<Clock>().total_hours() < hour {
+                super::super::step_ticks(app.world_mut(), 1);
+                app.update();
+                close(app);
+            }
+        };
+
+        let mut quicksave = None;
+        for day in 0..3 {
+            wait_until(&mut app, day * 24 + 9);
+            play(&mut app, Input::Action(GameAction::WorkShift));
+            close(&mut app);
+            play(&mut app, Input::Action(GameAction::DepositCash));
+            play(&mut app, Input::Amount(20 + day * 10));
+            close(&mut app);
+            play(&mut app, Input::Action(GameAction::Invest));
+            play(&mut app, Input::Trade(Trade::Buy(0)));
+            play(&mut app, Input::Trade(Trade::Sell(0)));
+            close(&mut app);
+            play(&mut app, Input::Action(GameAction::Travel));
+            play(&mut app, Input::Travel(Area::Condo));
+            close(&mut app);
+            wait_until(&mut app, day * 24 + 18);
+            play(&mut app, Input::Action(GameAction::Freelance));
+            for marker in [0.2, 0.5, 0.8] {
+                play(&mut app, Input::Marker(marker));
+            }
+            close(&mut app);
+            match day {
+                0 => {
+                    let registry = app.world().resource::<AppTypeRegistry>().clone();
+                    let scene = run_snapshot(app.world());
+                    quicksave = Some(scene.serialize(&registry.read()).unwrap());
+                }
+                1 => restore_run(app.world_mut(), quicksave.as_deref().unwrap()),
+                _ => {
+                    play(&mut app, Input::Action(GameAction::SavingsGoal));
+                    play(&mut app, Input::Amount(500));
+                    close(&mut app);
+                }
+            }
+        }
+        wait_until(&mut app, 4 * 24);
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Menu);
+        app.update();
+        app.update();
+
+        let recorded = app.world().resource::<ProfilePaths>().file(REPLAY_FILE);
+        std::fs::copy(recorded, FIXTURE).unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use super::SimSet;
+
+/// The run's random number generator. Every random roll in the simulation goes through it,
+/// so a run is fully reproducible from its seed.
+///
+/// This is SplitMix64: tiny, fast and good enough for game rolls.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct GameRng {
+    seed: u64,
+    state: u64,
+    /// How many numbers were drawn so far.
+    draws: u64,
+}
+
+impl GameRng {
+    pub fn seeded(seed: u64) -> Self {
+        GameRng {
+            seed,
+            state: seed,
+            draws: 0,
+        }
+    }
+
+    /// A generator seeded from the system clock.
+    pub fn from_entropy() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        GameRng::seeded(nanos)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn draws(&self) -> u64 {
+        self.draws
+    }
+
+    /// Where the generator is in its sequence, the same for two generators only if they
+    /// drew the same numbers from the same seed.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.draws += 1;
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly distributed float in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A uniformly distributed index into a slice of length `len`, which must not be zero.
+    pub fn index(&mut self, len: usize) -> usize {
+        (self.next_u64() % len as u64) as usize
+    }
+
+    /// A normally distributed float with mean 0 and standard deviation 1.
+    pub fn normal(&mut self) -> f32 {
+        // Box-Muller; `1 - u` keeps the logarithm away from zero.
+        let radius = (-2.0 * (1.0 - self.next_f32()).ln()).sqrt();
+        radius * (std::f32::consts::TAU * self.next_f32()).cos()
+    }
+
+    /// Returns `true` with the given probability.
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::from_entropy()
+    }
+}
+
+/// The order in which the simulation's rolls draw from the [`GameRng`] within a tick.
+///
+/// Systems that draw from the same generator must not be left unordered: the scheduler
+/// would then pick their order from whatever else is in the schedule, so a replay recorded
+/// by a dev build could roll the same numbers into different systems than a release build.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RollSet {
+    Jobs,
+    Calendar,
+    Income,
+    Roommate,
+    Weather,
+    Charity,
+    Crime,
+    Market,
+}
+
+pub fn rng_plugin(app: &mut App) {
+    app.register_type::<GameRng>()
+        .init_resource::<GameRng>()
+        .configure_sets(
+            FixedUpdate,
+            (
+                RollSet::Jobs,
+                RollSet::Calendar,
+                RollSet::Income,
+                RollSet::Roommate,
+                RollSet::Weather,
+                RollSet::Charity,
+                RollSet::Crime,
+                RollSet::Market,
+            )
+                .chain()
+                .in_set(SimSet::Economy),
+        );
+}
@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    catalog::AreaCatalog,
+    clock::{Clock, HOURS_PER_DAY, day_started},
+    cost_of_living::Prices,
+    economy::{CurrentArea, HomeArea, Money},
+    ledger::{Category, Transaction},
+    needs::Happiness,
+    rng::{GameRng, RollSet},
+};
+use crate::AppState;
+use crate::screens::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use crate::toast::Toast;
+
+const SEARCH_HOURS: u32 = HOURS_PER_DAY;
+const SEARCH_SUCCESS_CHANCE: f32 = 0.6;
+/// Daily chance that the roommate moves out on their own.
+const MOVE_OUT_CHANCE: f32 = 0.03;
+/// Daily chance that the roommate eats your food.
+const FOOD_EVENT_CHANCE: f32 = 0.1;
+const FOOD_EVENT_COST: u32 = 20;
+const DAILY_HAPPINESS_PENALTY: i32 = -1;
+
+/// Whether someone shares the player's home, halving the rent.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub enum Roommate {
+    #[default]
+    None,
+    /// Looking for a roommate until [`Clock::total_hours`] reaches `done_at`.
+    Searching {
+        done_at: u32,
+    },
+    Living,
+}
+
+impl Roommate {
+    pub fn is_splitting(&self) -> bool {
+        *self == Roommate::Living
+    }
+
+    /// The player's part of `rent`. Applied when rent is charged, so the split always
+    /// follows whatever home the player currently rents.
+    pub fn rent_share(&self, rent: u32) -> u32 {
+        if self.is_splitting() {
+            rent.div_ceil(2)
+        } else {
+            rent
+        }
+    }
+}
+
+pub fn roommate_plugin(app: &mut App) {
+    app.register_type::<Roommate>()
+        .init_resource::<Roommate>()
+        .add_systems(
+            FixedUpdate,
+            (resolve_search, roommate_daily_life.run_if(day_started))
+                .chain()
+                .in_set(RollSet::Roommate),
+        )
+        .add_systems(Update, offer_roommate_actions.in_set(ActionSet::Offer))
+        .add_systems(
+            Update,
+            handle_roommate_actions.run_if(in_state(AppState::Game)),
+        );
+}
+
+fn offer_roommate_actions(
+    roommate: Res<Roommate>,
+    home: Res<HomeArea>,
+    current: Res<CurrentArea>,
+    catalog: Res<AreaCatalog>,
+    mut actions: ResMut<AvailableActions>,
+) {
+    if current.0 != home.location || !catalog.allows_roommates(home.location) {
+        return;
+    }
+    match *roommate {
+        Roommate::None => actions.offer(GameAction::FindRoommate),
+        Roommate::Living => actions.offer(GameAction::AskRoommateToLeave),
+        Roommate::Searching { .. } => {}
+    }
+}
+
+fn handle_roommate_actions(
+    mut pressed: EventReader<ActionPressed>,
+    mut roommate: ResMut<Roommate>,
+    clock: Res<Clock>,
+    mut toasts: EventWriter<Toast>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        match action {
+            GameAction::FindRoommate if *roommate == Roommate::None => {
+                *roommate = Roommate::Searching {
+                    done_at: clock.total_hours() + SEARCH_HOURS,
+                };
+                toasts.write(Toast::new("Looking for a roommate..."));
+            }
+            GameAction::AskRoommateToLeave if roommate.is_splitting() => {
+                *roommate = Roommate::None;
+                toasts.write(Toast::new("Your roommate moved out. Full rent again."));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn resolve_search(
+    mut roommate: ResMut<Roommate>,
+    clock: Res<Clock>,
+    mut rng: ResMut<GameRng>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let Roommate::Searching { done_at } = *roommate else {
+        return;
+    };
+    if clock.total_hours() < done_at {
+        return;
+    }
+
+    if rng.chance(SEARCH_SUCCESS_CHANCE) {
+        *roommate = Roommate::Living;
+        toasts.write(Toast::new(
+            "You found a roommate! Rent is split from now on.",
+        ));
+    } else {
+        *roommate = Roommate::None;
+        toasts.write(Toast::new("Nobody wanted the room. Try again?"));
+    }
+}
+
+fn roommate_daily_life(
+    mut roommate: ResMut<Roommate>,
+    mut happiness: ResMut<Happiness>,
+    mut money: ResMut<Money>,
+    prices: Prices,
+    mut rng: ResMut<GameRng>,
+    mut toasts: EventWriter<Toast>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    if !roommate.is_splitting() {
+        return;
+    }
+
+    happiness.change(DAILY_HAPPINESS_PENALTY);
+    if rng.chance(MOVE_OUT_CHANCE) {
+        *roommate = Roommate::None;
+        toasts.write(Toast::new("Your roommate moved out. Full rent again."));
+    } else if rng.chance(FOOD_EVENT_CHANCE) {
+        let cost = prices.price(FOOD_EVENT_COST);
+        let eaten = cost.min(money.amount);
+        money.amount -= eaten;
+        transactions.write(Transaction::Expense(Category::Food, eaten));
+        toasts.write(Toast::new(format!("Your roommate ate your food. -${cost}")));
+    }
+}
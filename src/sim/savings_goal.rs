@@ -0,0 +1,93 @@
+//! A savings target the player sets for themselves. Progress is their net worth, the same
+//! figure the monthly reports use, and reaching the target is celebrated once and kept
+//! among the run's notable moments.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    choices::{Choice, ChoiceLog},
+    clock::Clock,
+    ledger::{Wealth, net_worth_changed},
+};
+use crate::AppState;
+use crate::format;
+use crate::screens::actions::{ActionSet, AvailableActions, GameAction};
+use crate::settings::Locale;
+use crate::toast::Toast;
+
+/// The player's savings target, if they set one.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct SavingsGoal {
+    pub target: Option<u32>,
+    /// Whether the current target has been reached.
+    pub reached: bool,
+}
+
+impl SavingsGoal {
+    /// Sets a new target, to be reached afresh.
+    pub fn set(&mut self, target: u32) {
+        self.target = Some(target);
+        self.reached = false;
+    }
+
+    pub fn clear(&mut self) {
+        *self = SavingsGoal::default();
+    }
+
+    /// How far `worth` is toward the target, from 0 to 1.
+    pub fn progress(&self, worth: u32) -> f32 {
+        match self.target {
+            Some(0) => 1.0,
+            Some(target) => (worth as f32 / target as f32).min(1.0),
+            None => 0.0,
+        }
+    }
+}
+
+pub fn savings_goal_plugin(app: &mut App) {
+    app.register_type::<SavingsGoal>()
+        .init_resource::<SavingsGoal>()
+        .add_systems(Update, offer_savings_goal.in_set(ActionSet::Offer))
+        .add_systems(
+            Update,
+            check_savings_goal.run_if(
+                in_state(AppState::Game).and(resource_changed::<SavingsGoal>.or(net_worth_changed)),
+            ),
+        );
+}
+
+fn offer_savings_goal(goal: Res<SavingsGoal>, mut actions: ResMut<AvailableActions>) {
+    let label = if goal.target.is_some() {
+        "Edit savings goal"
+    } else {
+        "Set savings goal"
+    };
+    actions.offer_labeled(GameAction::SavingsGoal, label);
+}
+
+// A target below what the player already has is reached straight away.
+fn check_savings_goal(
+    wealth: Wealth,
+    clock: Res<Clock>,
+    mut goal: ResMut<SavingsGoal>,
+    mut choices: ResMut<ChoiceLog>,
+    mut toasts: EventWriter<Toast>,
+    locale: Res<Locale>,
+) {
+    let Some(target) = goal.target else {
+        return;
+    };
+    if goal.reached || wealth.net_worth().total() < target {
+        return;
+    }
+    goal.reached = true;
+    let target = format::money(target, *locale);
+    toasts.write(Toast::new(format!("Savings goal reached: {target}!")));
+    let summary = format!("Reached a savings goal of {target}");
+    choices.record(
+        clock.day,
+        Choice::new("Savings goal", target).notable(Some(&summary)),
+    );
+}
@@ -0,0 +1,232 @@
+//! Starting scenarios: presets picked when starting a new game, read from
+//! [`SCENARIOS_PATH`], that start the run with other money, home, job or bills than the
+//! regular start. [`super::reset_run`] sets the regular start up first and applies the
+//! preset over it, see [`ScenarioDef::apply`].
+//!
+//! Weekly challenges always play the preset [`Scenarios::challenge`] pins, so everyone's
+//! results for a week compare.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    bank::Bank,
+    bills::{BILL_NOTICE_DAYS, Bill, Bills},
+    catalog::AreaCatalog,
+    economy::{Area, CurrentArea, HomeArea, Money, RentCharge, WorkArea},
+    jobs::{JobDef, JobOffer},
+    ledger::Ledger,
+    new_game_plus::PriceLevel,
+    travel::AreaUnlocks,
+};
+use crate::data::register_ron_resource;
+
+pub const SCENARIOS_PATH: &str = "data/world.scenarios.ron";
+
+/// The contents of the scenarios data file.
+#[derive(Asset, TypePath, Resource, Deserialize, Clone, Debug)]
+pub struct Scenarios {
+    /// In the order the picker lists them.
+    pub presets: Vec<ScenarioDef>,
+    /// The id of the preset weekly challenges play, the regular start if `None`.
+    #[serde(default)]
+    pub challenge: Option<String>,
+}
+
+impl Scenarios {
+    pub fn get(&self, id: &str) -> Option<&ScenarioDef> {
+        self.presets.iter().find(|preset| preset.id == id)
+    }
+
+    /// The name to show for the preset `id`, or for the regular start.
+    pub fn name(&self, id: Option<&str>) -> &str {
+        id.and_then(|id| self.get(id))
+            .map_or("Standard", |preset| &preset.name)
+    }
+}
+
+/// Where a run starts.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ScenarioDef {
+    /// Kept in replays and high scores, so it shouldn't change once released.
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub money: u32,
+    #[serde(default)]
+    pub savings: u32,
+    /// Where the player lives, and starts the run.
+    pub home: Area,
+    /// The home's rent in place of the area's, before the run's [`PriceLevel`].
+    #[serde(default)]
+    pub rent: Option<u32>,
+    #[serde(default)]
+    pub job: Option<JobDef>,
+    /// An offer waiting on the first day, for a player whose skills get them hired.
+    #[serde(default)]
+    pub job_offer: Option<JobDef>,
+    /// Rent bills still open from before the run.
+    #[serde(default)]
+    pub bills: Vec<StartingBill>,
+    #[serde(default = "autopay_on")]
+    pub autopay: bool,
+}
+
+fn autopay_on() -> bool {
+    true
+}
+
+/// A rent bill the player starts the run owing.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StartingBill {
+    /// The rent owed, before the run's [`PriceLevel`].
+    pub rent: u32,
+    pub due_day: u32,
+    /// Late fees already added, which makes the bill overdue.
+    #[serde(default)]
+    pub fees: u32,
+}
+
+impl ScenarioDef {
+    /// Replaces the parts of the regular start the preset sets. Rent goes by the run's
+    /// prices like the regular start's does; money and wages don't.
+    pub fn apply(&self, commands: &mut Commands, catalog: &AreaCatalog, prices: &PriceLevel) {
+        let mut home = HomeArea::new(self.home, catalog);
+        home.scenario_rent = self.rent;
+        home.reprice(catalog, prices);
+        let bills = self
+            .bills
+            .iter()
+            .map(|bill| {
+                let rent = prices.scale(bill.rent);
+                let charge = RentCharge {
+                    day: bill.due_day,
+                    area: self.home,
+                    gross: rent,
+                    modifiers: Vec::new(),
+                    net: rent,
+                };
+                Bill {
+                    fees: bill.fees,
+                    ..Bill::new(
+                        charge,
+                        bill.due_day.saturating_sub(BILL_NOTICE_DAYS),
+                        bill.due_day,
+                    )
+                }
+            })
+            .collect();
+        let work = match &self.job {
+            Some(job) => WorkArea {
+                location: Some(job.employer),
+                title: job.title.clone(),
+                wage: job.wage,
+                ..default()
+            },
+            None => WorkArea::default(),
+        };
+        // A home behind an unlock has to be open to get back to.
+        let unlocks = if catalog.unlock(self.home).is_some() {
+            vec![self.home]
+        } else {
+            Vec::new()
+        };
+
+        commands.insert_resource(Money { amount: self.money });
+        commands.insert_resource(Bank {
+            savings: self.savings,
+        });
+        commands.insert_resource(Ledger::new(self.money + self.savings));
+        commands.insert_resource(home);
+        commands.insert_resource(CurrentArea(self.home));
+        commands.insert_resource(AreaUnlocks(unlocks));
+        commands.insert_resource(work);
+        commands.insert_resource(JobOffer(self.job_offer.clone()));
+        commands.insert_resource(Bills {
+            outstanding: bills,
+            autopay: self.autopay,
+            ..default()
+        });
+    }
+}
+
+/// The preset picked for the next new game, `None` for the regular start.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct ChosenScenario(pub Option<String>);
+
+pub fn scenario_plugin(app: &mut App) {
+    register_ron_resource::<Scenarios>(app, &["scenarios.ron"]);
+    app.register_type::<ChosenScenario>()
+        .init_resource::<ChosenScenario>();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::world::CommandQueue;
+
+    use super::*;
+    use crate::sim::catalog::{AREA_CATALOG_PATH, AreaCatalogData};
+
+    fn read<T: serde::de::DeserializeOwned>(path: &str) -> T {
+        ron::from_str(&std::fs::read_to_string(format!("assets/{path}")).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn each_preset_starts_the_run_the_file_describes() {
+        let scenarios: Scenarios = read(SCENARIOS_PATH);
+        let catalog = AreaCatalog::from_data(&read::<AreaCatalogData>(AREA_CATALOG_PATH));
+        let prices = PriceLevel {
+            multiplier: 1.5,
+            ..default()
+        };
+        assert!(!scenarios.presets.is_empty());
+        let challenge = scenarios.challenge.as_deref().unwrap();
+        assert!(scenarios.get(challenge).is_some(), "no preset {challenge}");
+
+        for preset in &scenarios.presets {
+            let mut world = World::new();
+            let mut queue = CommandQueue::default();
+            preset.apply(&mut Commands::new(&mut queue, &world), &catalog, &prices);
+            queue.apply(&mut world);
+
+            let id = &preset.id;
+            assert_eq!(world.resource::<Money>().amount, preset.money, "{id}");
+            assert_eq!(world.resource::<Bank>().savings, preset.savings, "{id}");
+            let home = world.resource::<HomeArea>();
+            assert_eq!(home.location, preset.home, "{id}");
+            assert_eq!(
+                home.rent,
+                prices.scale(preset.rent.or(catalog.rent(preset.home)).unwrap()),
+                "{id}"
+            );
+            assert_eq!(world.resource::<CurrentArea>().0, preset.home, "{id}");
+            assert_eq!(
+                world.resource::<AreaUnlocks>().0.contains(&preset.home),
+                catalog.unlock(preset.home).is_some(),
+                "{id}"
+            );
+
+            let work = world.resource::<WorkArea>();
+            let job = preset.job.as_ref();
+            assert_eq!(work.location, job.map(|job| job.employer), "{id}");
+            assert_eq!(work.title, job.map_or("", |job| job.title.as_str()), "{id}");
+            assert_eq!(work.wage, job.map_or(0, |job| job.wage), "{id}");
+            assert_eq!(world.resource::<JobOffer>().0, preset.job_offer, "{id}");
+
+            let bills = world.resource::<Bills>();
+            assert_eq!(bills.autopay, preset.autopay, "{id}");
+            let owed = bills
+                .outstanding
+                .iter()
+                .map(|bill| (bill.charge.net, bill.due_day, bill.fees))
+                .collect::<Vec<_>>();
+            let listed = preset
+                .bills
+                .iter()
+                .map(|bill| (prices.scale(bill.rent), bill.due_day, bill.fees))
+                .collect::<Vec<_>>();
+            assert_eq!(owed, listed, "{id}");
+        }
+    }
+}
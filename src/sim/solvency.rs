@@ -0,0 +1,444 @@
+//! Warns the player once a run can't recover: when even working every shift there is
+//! doesn't cover the rent and the other daily costs, and the money left runs out within
+//! [`DIRE_DAYS`]. The warning is an event card, shown once a run, pointing to the travel
+//! menu and the bank.
+//!
+//! How much help the card offers depends on the run: a regular run also offers a one-time
+//! bailout, a New Game+ run only the links, and the weekly challenge only the warning.
+//!
+//! Every [`Outlook`] comes from [`project`], so the warning and the travel menu's take on
+//! what each area's rent would do to the run always agree.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use super::{
+    SimSet,
+    bank::Bank,
+    bills::settle_bills,
+    calendar::Calendar,
+    challenge::Challenge,
+    clock::{Clock, DAYS_PER_WEEK, day_started},
+    cost_of_living::Prices,
+    economy::{HomeArea, Money, RENT_PERIOD_DAYS, WorkArea, rent_due},
+    event_cards::{CardChosen, CardId, CardRequest, EventCardQueue},
+    jobs::{MAX_OVERTIME_PER_WEEK, ShiftKind},
+    landlord::{RentIncreases, RentTerms},
+    ledger::{Category, Ledger, Transaction},
+    pet::{Pet, PetConfig},
+    replay::RunSetup,
+    roommate::Roommate,
+};
+use crate::AppState;
+use crate::screens::actions::{ActionPressed, GameAction};
+
+/// The card shows when the money runs out in fewer days than this.
+pub const DIRE_DAYS: u32 = 7;
+/// A daily surplus under this share of the daily costs, in percent, is tight.
+const TIGHT_SURPLUS_PERCENT: i64 = 20;
+
+/// How well a run covers its costs at best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affordability {
+    Comfortable,
+    Tight,
+    /// The run loses money every day.
+    Unaffordable,
+}
+
+impl Affordability {
+    pub fn label(self) -> &'static str {
+        match self {
+            Affordability::Comfortable => "Comfortable",
+            Affordability::Tight => "Tight",
+            Affordability::Unaffordable => "Unaffordable",
+        }
+    }
+}
+
+/// The money a run has to go on and what it takes in and pays out a day at best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outlook {
+    /// Cash and savings.
+    pub funds: u32,
+    pub daily_income: u32,
+    pub daily_costs: u32,
+}
+
+impl Outlook {
+    /// What's left over a day, negative when the costs are more than the income.
+    pub fn daily_surplus(&self) -> i64 {
+        i64::from(self.daily_income) - i64::from(self.daily_costs)
+    }
+
+    pub fn affordability(&self) -> Affordability {
+        let surplus = self.daily_surplus();
+        if surplus < 0 {
+            Affordability::Unaffordable
+        } else if surplus * 100 < i64::from(self.daily_costs) * TIGHT_SURPLUS_PERCENT {
+            Affordability::Tight
+        } else {
+            Affordability::Comfortable
+        }
+    }
+
+    /// Days until the money runs out, or `None` if the best case covers the costs.
+    pub fn days_to_bankruptcy(&self) -> Option<u32> {
+        let deficit = self.daily_costs.checked_sub(self.daily_income)?;
+        (deficit > 0).then(|| self.funds / deficit)
+    }
+
+    /// Whether the run can't recover: it loses money every day at best and runs out
+    /// within [`DIRE_DAYS`].
+    pub fn is_dire(&self) -> bool {
+        self.days_to_bankruptcy()
+            .is_some_and(|days| days < DIRE_DAYS)
+    }
+}
+
+/// The outlook of a run with `funds`, a job paying `wage` and `daily_expenses`, paying
+/// `rent` every [`RENT_PERIOD_DAYS`].
+pub fn project(funds: u32, wage: u32, daily_expenses: u32, rent: u32) -> Outlook {
+    Outlook {
+        funds,
+        daily_income: best_daily_income(wage),
+        daily_costs: rent.div_ceil(RENT_PERIOD_DAYS) + daily_expenses,
+    }
+}
+
+/// What goes into an [`Outlook`] besides the rent.
+#[derive(SystemParam)]
+pub struct Projection<'w> {
+    money: Res<'w, Money>,
+    bank: Res<'w, Bank>,
+    work: Res<'w, WorkArea>,
+    calendar: Res<'w, Calendar>,
+    pet: Res<'w, Pet>,
+    pet_config: Res<'w, PetConfig>,
+    prices: Prices<'w>,
+    clock: Res<'w, Clock>,
+    ledger: Res<'w, Ledger>,
+}
+
+impl Projection<'_> {
+    /// What the run spends a day besides rent: its recent spending in the ledger, or the
+    /// costs it can't avoid if those are more.
+    pub fn daily_expenses(&self) -> u32 {
+        let pet_food = if self.pet.0.is_some() {
+            self.prices.price(self.pet_config.daily_food_cost)
+        } else {
+            0
+        };
+        let unavoidable = self
+            .prices
+            .price(self.calendar.season(self.clock.day).utilities_per_day)
+            + pet_food;
+        unavoidable.max(self.ledger.daily_spending_besides_rent(self.clock.day))
+    }
+
+    /// The run's outlook paying `rent`, see [`project`].
+    pub fn outlook(&self, rent: u32) -> Outlook {
+        project(
+            self.money.amount + self.bank.savings,
+            self.work.wage,
+            self.daily_expenses(),
+            rent,
+        )
+    }
+}
+
+/// The most a job paying `wage` an hour earns a day: a shift every day, with as many of
+/// them overtime as a week allows.
+pub fn best_daily_income(wage: u32) -> u32 {
+    let week = ShiftKind::Overtime.pay(wage) * MAX_OVERTIME_PER_WEEK
+        + ShiftKind::Normal.pay(wage) * (DAYS_PER_WEEK - MAX_OVERTIME_PER_WEEK);
+    week / DAYS_PER_WEEK
+}
+
+/// The warning card, once it's been shown this run.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolvencyWarning {
+    pub card: Option<CardId>,
+    /// What the card's bailout button pays, if it has one and it hasn't been taken.
+    pub bailout: Option<u32>,
+}
+
+/// A button on the warning card, in the order they're shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WarningButton {
+    Travel,
+    Bank,
+    Bailout,
+    Dismiss,
+}
+
+impl WarningButton {
+    fn label(self) -> &'static str {
+        match self {
+            WarningButton::Travel => "Travel",
+            WarningButton::Bank => "Bank",
+            WarningButton::Bailout => "Take a bailout",
+            WarningButton::Dismiss => "OK",
+        }
+    }
+
+    /// The buttons a run's card gets, see the module docs.
+    fn for_run(setup: &RunSetup, challenge: &Challenge) -> Vec<WarningButton> {
+        if challenge.0.is_some() {
+            vec![WarningButton::Dismiss]
+        } else if setup.cycle > 0 {
+            vec![
+                WarningButton::Travel,
+                WarningButton::Bank,
+                WarningButton::Dismiss,
+            ]
+        } else {
+            vec![
+                WarningButton::Travel,
+                WarningButton::Bank,
+                WarningButton::Bailout,
+                WarningButton::Dismiss,
+            ]
+        }
+    }
+}
+
+pub fn solvency_plugin(app: &mut App) {
+    app.init_resource::<SolvencyWarning>()
+        .add_systems(
+            FixedUpdate,
+            check_solvency
+                .in_set(SimSet::Economy)
+                .after(settle_bills)
+                .run_if(day_started),
+        )
+        .add_systems(Update, answer_warning.run_if(in_state(AppState::Game)));
+}
+
+fn check_solvency(
+    projection: Projection,
+    (home, increases, terms, roommate): (
+        Res<HomeArea>,
+        Res<RentIncreases>,
+        Res<RentTerms>,
+        Res<Roommate>,
+    ),
+    (setup, challenge): (Res<RunSetup>, Res<Challenge>),
+    clock: Res<Clock>,
+    ledger: Res<Ledger>,
+    mut warning: ResMut<SolvencyWarning>,
+    mut cards: ResMut<EventCardQueue>,
+) {
+    if warning.card.is_some() {
+        return;
+    }
+    let rent = rent_due(
+        &home,
+        &increases,
+        &terms,
+        &roommate,
+        &clock,
+        ledger.current.month,
+    )
+    .net;
+    let outlook = projection.outlook(rent);
+    if !outlook.is_dire() {
+        return;
+    }
+    info!("The run can't recover: {outlook:?}");
+    let buttons = WarningButton::for_run(&setup, &challenge);
+    let request = CardRequest::new(
+        "Things look dire",
+        "Even working every shift won't cover the rent for long. \
+         Consider moving somewhere cheaper or visiting the bank.",
+    )
+    .with_buttons(buttons.iter().map(|button| button.label()))
+    .with_priority(1);
+    warning.card = Some(cards.push(request));
+    warning.bailout = buttons.contains(&WarningButton::Bailout).then_some(rent);
+}
+
+// Goes through the actions like their buttons would, so what they open shows once the
+// card has closed.
+fn answer_warning(
+    mut chosen: EventReader<CardChosen>,
+    (setup, challenge): (Res<RunSetup>, Res<Challenge>),
+    mut warning: ResMut<SolvencyWarning>,
+    mut money: ResMut<Money>,
+    mut pressed: EventWriter<ActionPressed>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    for CardChosen { card, button } in chosen.read() {
+        if warning.card != Some(*card) {
+            continue;
+        }
+        let buttons = WarningButton::for_run(&setup, &challenge);
+        match buttons.get(*button) {
+            Some(WarningButton::Travel) => {
+                pressed.write(ActionPressed(GameAction::Travel));
+            }
+            Some(WarningButton::Bank) => {
+                pressed.write(ActionPressed(GameAction::DepositCash));
+            }
+            Some(WarningButton::Bailout) => {
+                if let Some(bailout) = warning.bailout.take() {
+                    info!("Took a ${bailout} bailout");
+                    money.amount += bailout;
+                    transactions.write(Transaction::Income(Category::Bonuses, bailout));
+                }
+            }
+            Some(WarningButton::Dismiss) | None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use serde::de::DeserializeOwned;
+
+    use super::*;
+    use crate::settings::ContentFilters;
+    use crate::sim::{
+        calendar::CALENDAR_PATH,
+        catalog::{AREA_CATALOG_PATH, AreaCatalog, AreaCatalogData},
+        economy::Area,
+        new_game_plus::PriceLevel,
+        pet::PET_CONFIG_PATH,
+    };
+
+    fn read<T: DeserializeOwned>(path: &str) -> T {
+        ron::from_str(&std::fs::read_to_string(format!("assets/{path}")).unwrap()).unwrap()
+    }
+
+    /// A regular run on day `day` that's broke and out of work, with rent to pay.
+    fn broke_world(day: u32) -> World {
+        let catalog = AreaCatalog::from_data(&read::<AreaCatalogData>(AREA_CATALOG_PATH));
+        let mut world = World::new();
+        world.insert_resource(Money { amount: 0 });
+        world.init_resource::<Bank>();
+        world.init_resource::<WorkArea>();
+        world.insert_resource(read::<Calendar>(CALENDAR_PATH));
+        world.init_resource::<Pet>();
+        world.insert_resource(read::<PetConfig>(PET_CONFIG_PATH));
+        world.init_resource::<PriceLevel>();
+        world.insert_resource(HomeArea::new(Area::DeadbeatArea, &catalog));
+        world.insert_resource(catalog);
+        world.insert_resource(Clock { day, hour: 0 });
+        world.init_resource::<Ledger>();
+        world.init_resource::<RentIncreases>();
+        world.init_resource::<RentTerms>();
+        world.init_resource::<Roommate>();
+        world.insert_resource(RunSetup {
+            seed: 1,
+            cycle: 0,
+            carry_over: None,
+            content_filters: ContentFilters::default(),
+            scenario: None,
+        });
+        world.init_resource::<Challenge>();
+        world.init_resource::<SolvencyWarning>();
+        world.init_resource::<EventCardQueue>();
+        world
+    }
+
+    #[test]
+    fn the_warning_is_shown_once_a_run() {
+        let mut world = broke_world(2);
+        world.run_system_once(check_solvency).unwrap();
+        let (card, request) = world
+            .resource::<EventCardQueue>()
+            .front()
+            .expect("a broke run gets the warning");
+        assert_eq!(request.title, "Things look dire");
+        assert_eq!(world.resource::<SolvencyWarning>().card, Some(card));
+        assert_eq!(world.resource::<SolvencyWarning>().bailout, Some(250));
+
+        // Dismissed, and the run is no better the next days.
+        world.resource_mut::<EventCardQueue>().remove(card);
+        for day in 3..10 {
+            world.resource_mut::<Clock>().day = day;
+            world.run_system_once(check_solvency).unwrap();
+        }
+        assert!(world.resource::<EventCardQueue>().is_empty());
+    }
+
+    #[test]
+    fn a_run_that_covers_its_costs_isnt_warned() {
+        let mut world = broke_world(2);
+        world.resource_mut::<WorkArea>().wage = 15;
+        world.run_system_once(check_solvency).unwrap();
+        assert!(world.resource::<EventCardQueue>().is_empty());
+        assert_eq!(world.resource::<SolvencyWarning>().card, None);
+    }
+
+    #[test]
+    fn a_deficit_is_dire_only_once_the_runway_is_under_a_week() {
+        let outlook = |funds| Outlook {
+            funds,
+            daily_income: 0,
+            daily_costs: 100,
+        };
+        assert!(outlook(DIRE_DAYS * 100 - 1).is_dire());
+        assert!(!outlook(DIRE_DAYS * 100).is_dire());
+        let covered = Outlook {
+            funds: 0,
+            daily_income: 100,
+            daily_costs: 100,
+        };
+        assert!(!covered.is_dire());
+    }
+
+    #[test]
+    fn the_best_day_counts_the_overtime_a_week_allows() {
+        assert_eq!(best_daily_income(0), 0);
+        let normal = ShiftKind::Normal.pay(15);
+        let overtime = ShiftKind::Overtime.pay(15);
+        assert!(overtime > normal);
+        assert_eq!(
+            best_daily_income(15),
+            (overtime * MAX_OVERTIME_PER_WEEK + normal * (DAYS_PER_WEEK - MAX_OVERTIME_PER_WEEK))
+                / DAYS_PER_WEEK
+        );
+    }
+
+    #[test]
+    fn without_income_the_funds_run_down_by_the_costs() {
+        let outlook = project(1000, 0, 20, 700);
+        assert_eq!(outlook.daily_income, 0);
+        assert_eq!(outlook.daily_costs, 120);
+        assert_eq!(outlook.daily_surplus(), -120);
+        assert_eq!(outlook.affordability(), Affordability::Unaffordable);
+        assert_eq!(outlook.days_to_bankruptcy(), Some(8));
+    }
+
+    #[test]
+    fn without_income_or_costs_the_money_lasts() {
+        let outlook = project(0, 0, 0, 0);
+        assert_eq!(outlook.daily_surplus(), 0);
+        assert_eq!(outlook.days_to_bankruptcy(), None);
+        assert!(!outlook.is_dire());
+    }
+
+    #[test]
+    fn a_deficit_is_a_negative_surplus_with_a_runway() {
+        let income = best_daily_income(15);
+        let outlook = project(500, 15, income + 50, 0);
+        assert_eq!(outlook.daily_surplus(), -50);
+        assert_eq!(outlook.affordability(), Affordability::Unaffordable);
+        assert_eq!(outlook.days_to_bankruptcy(), Some(10));
+    }
+
+    #[test]
+    fn a_surplus_under_a_fifth_of_the_costs_is_tight() {
+        let outlook = |daily_income| Outlook {
+            funds: 0,
+            daily_income,
+            daily_costs: 100,
+        };
+        assert_eq!(outlook(120).affordability(), Affordability::Comfortable);
+        assert_eq!(outlook(119).affordability(), Affordability::Tight);
+        assert_eq!(outlook(100).affordability(), Affordability::Tight);
+        assert_eq!(outlook(99).affordability(), Affordability::Unaffordable);
+        assert_eq!(outlook(100).days_to_bankruptcy(), None);
+    }
+}
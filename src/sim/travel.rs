@@ -0,0 +1,311 @@
+//! Getting around town. The player can travel to any area in the data, except those with an
+//! [`Unlock`] condition they haven't met yet. Meeting it opens the area for the rest of the
+//! run, once, with a toast and an entry among the run's notable moments. A condition can
+//! need other areas opened first, so areas unlock in a chain.
+//!
+//! Every trip goes through [`TravelRequested`], and the request for a locked area is
+//! refused here whatever the travel menu shows.
+
+use bevy::prelude::*;
+
+use super::{
+    catalog::{AreaCatalog, Unlock},
+    charity::Reputation,
+    choices::{Choice, ChoiceLog},
+    clock::Clock,
+    economy::{Area, CurrentArea},
+    ledger::{Wealth, net_worth_changed},
+    new_game_plus::PriceLevel,
+};
+use crate::AppState;
+use crate::format;
+use crate::screens::actions::{ActionSet, AvailableActions, GameAction};
+use crate::settings::Locale;
+use crate::toast::Toast;
+
+/// The areas with an [`Unlock`] condition the player has met this run.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct AreaUnlocks(pub Vec<Area>);
+
+impl AreaUnlocks {
+    pub fn is_open(&self, area: Area, catalog: &AreaCatalog) -> bool {
+        catalog.unlock(area).is_none() || self.0.contains(&area)
+    }
+
+    /// Whether a player with this net worth and reputation meets `unlock`.
+    pub fn is_met(
+        &self,
+        unlock: &Unlock,
+        catalog: &AreaCatalog,
+        worth: u32,
+        reputation: u32,
+        prices: &PriceLevel,
+    ) -> bool {
+        match unlock {
+            Unlock::NetWorth(target) => worth >= prices.scale(*target),
+            Unlock::Area(area) => self.is_open(*area, catalog),
+            Unlock::Reputation(points) => reputation >= *points,
+            Unlock::All(unlocks) => unlocks
+                .iter()
+                .all(|unlock| self.is_met(unlock, catalog, worth, reputation, prices)),
+        }
+    }
+}
+
+/// Asks to go to an area.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TravelRequested(pub Area);
+
+/// Sent once per run for each area the player unlocks.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AreaUnlocked(pub Area);
+
+/// What the player still has to do to unlock an area, for the travel menu.
+pub fn unlock_text(unlock: &Unlock, prices: &PriceLevel, locale: Locale) -> String {
+    match unlock {
+        Unlock::NetWorth(worth) => format!(
+            "Reach a net worth of {}",
+            format::money(prices.scale(*worth), locale)
+        ),
+        Unlock::Area(area) => format!("Unlock the {}", area.label()),
+        Unlock::Reputation(points) => format!("Earn a reputation of {points}"),
+        Unlock::All(unlocks) => unlocks
+            .iter()
+            .map(|unlock| unlock_text(unlock, prices, locale))
+            .collect::<Vec<_>>()
+            .join(". "),
+    }
+}
+
+pub fn travel_plugin(app: &mut App) {
+    app.register_type::<AreaUnlocks>()
+        .init_resource::<AreaUnlocks>()
+        .add_event::<TravelRequested>()
+        .add_event::<AreaUnlocked>()
+        .add_systems(Update, offer_travel.in_set(ActionSet::Offer))
+        .add_systems(
+            Update,
+            (
+                check_unlocks.run_if(net_worth_changed.or(resource_changed::<Reputation>)),
+                travel,
+            )
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        );
+}
+
+fn offer_travel(mut actions: ResMut<AvailableActions>) {
+    actions.offer(GameAction::Travel);
+}
+
+fn check_unlocks(
+    catalog: Res<AreaCatalog>,
+    wealth: Wealth,
+    reputation: Res<Reputation>,
+    prices: Res<PriceLevel>,
+    clock: Res<Clock>,
+    mut unlocks: ResMut<AreaUnlocks>,
+    mut unlocked: EventWriter<AreaUnlocked>,
+    mut choices: ResMut<ChoiceLog>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let worth = wealth.net_worth().total();
+    // In the catalog's order, so an area unlocked here counts for the ones after it.
+    for area in catalog.areas() {
+        let Some(unlock) = catalog.unlock(area) else {
+            continue;
+        };
+        if unlocks.0.contains(&area)
+            || !unlocks.is_met(unlock, &catalog, worth, reputation.points, &prices)
+        {
+            continue;
+        }
+        unlocks.0.push(area);
+        unlocked.write(AreaUnlocked(area));
+        toasts.write(Toast::new(format!("{} unlocked!", area.label())));
+        let summary = format!("Unlocked the {}", area.label());
+        choices.record(
+            clock.day,
+            Choice::new("Area unlocked", area.label()).notable(Some(&summary)),
+        );
+    }
+}
+
+fn travel(
+    mut requests: EventReader<TravelRequested>,
+    catalog: Res<AreaCatalog>,
+    unlocks: Res<AreaUnlocks>,
+    prices: Res<PriceLevel>,
+    locale: Res<Locale>,
+    mut current: ResMut<CurrentArea>,
+    mut toasts: EventWriter<Toast>,
+) {
+    for TravelRequested(area) in requests.read() {
+        if unlocks.is_open(*area, &catalog) {
+            if current.0 != *area {
+                current.0 = *area;
+            }
+        } else if let Some(unlock) = catalog.unlock(*area) {
+            toasts.write(Toast::new(format!(
+                "{} is locked. {}",
+                catalog.travel_label(*area, false),
+                unlock_text(unlock, &prices, *locale)
+            )));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+    use crate::sim::{
+        bank::Bank,
+        catalog::{AREA_CATALOG_PATH, AreaCatalogData},
+        economy::Money,
+        market::{MARKET_PATH, Market, MarketConfig, Portfolio},
+        passive_income::IncomeSources,
+    };
+
+    fn read<T: serde::de::DeserializeOwned>(path: &str) -> T {
+        ron::from_str(&std::fs::read_to_string(format!("assets/{path}")).unwrap()).unwrap()
+    }
+
+    fn travel_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .insert_state(AppState::Game)
+            .insert_resource(AreaCatalog::from_data(&read::<AreaCatalogData>(
+                AREA_CATALOG_PATH,
+            )))
+            .insert_resource(Market::new(&read::<MarketConfig>(MARKET_PATH)))
+            .insert_resource(Money { amount: 0 })
+            .init_resource::<Bank>()
+            .init_resource::<Portfolio>()
+            .init_resource::<IncomeSources>()
+            .init_resource::<Reputation>()
+            .init_resource::<PriceLevel>()
+            .init_resource::<Clock>()
+            .init_resource::<ChoiceLog>()
+            .init_resource::<CurrentArea>()
+            .init_resource::<Locale>()
+            .init_resource::<AvailableActions>()
+            .add_event::<Toast>()
+            .add_plugins(travel_plugin);
+        app.update();
+        app
+    }
+
+    fn set_money(app: &mut App, amount: u32) -> Vec<Area> {
+        app.world_mut().resource_mut::<Money>().amount = amount;
+        app.update();
+        unlocked(app)
+    }
+
+    fn set_reputation(app: &mut App, points: u32) -> Vec<Area> {
+        app.world_mut().resource_mut::<Reputation>().points = points;
+        app.update();
+        unlocked(app)
+    }
+
+    fn unlocked(app: &mut App) -> Vec<Area> {
+        let mut unlocked = app.world_mut().resource_mut::<Events<AreaUnlocked>>();
+        unlocked.drain().map(|AreaUnlocked(area)| area).collect()
+    }
+
+    fn go_to(app: &mut App, area: Area) -> Area {
+        app.world_mut().send_event(TravelRequested(area));
+        app.update();
+        app.world().resource::<CurrentArea>().0
+    }
+
+    fn toasts(app: &mut App) -> Vec<String> {
+        let mut toasts = app.world_mut().resource_mut::<Events<Toast>>();
+        toasts.drain().map(|toast| toast.text).collect()
+    }
+
+    #[test]
+    fn an_area_unlocks_once_a_run() {
+        let mut app = travel_app();
+        assert_eq!(set_money(&mut app, 4999), []);
+        assert_eq!(set_money(&mut app, 5000), [Area::LuxuryCondo]);
+        assert_eq!(toasts(&mut app), ["Luxury Condo unlocked!"]);
+        // Falling back under the target and reaching it again changes nothing.
+        assert_eq!(set_money(&mut app, 6000), []);
+        assert_eq!(set_money(&mut app, 0), []);
+        assert_eq!(set_money(&mut app, 5000), []);
+        assert_eq!(set_money(&mut app, 25_000), [Area::Mansion]);
+        assert_eq!(
+            app.world().resource::<AreaUnlocks>().0,
+            [Area::LuxuryCondo, Area::Mansion]
+        );
+        assert_eq!(app.world().resource::<ChoiceLog>().0.len(), 2);
+    }
+
+    #[test]
+    fn travel_to_a_locked_area_is_refused() {
+        let mut app = travel_app();
+        toasts(&mut app);
+        app.world_mut().send_event(TravelRequested(Area::Mansion));
+        app.update();
+        assert_eq!(app.world().resource::<CurrentArea>().0, Area::DeadbeatArea);
+        let refused = toasts(&mut app);
+        assert_eq!(refused.len(), 1);
+        assert!(refused[0].contains("is locked"), "{refused:?}");
+
+        app.world_mut().send_event(TravelRequested(Area::Condo));
+        app.update();
+        assert_eq!(app.world().resource::<CurrentArea>().0, Area::Condo);
+
+        set_money(&mut app, 25_000);
+        app.world_mut().send_event(TravelRequested(Area::Mansion));
+        app.update();
+        assert_eq!(app.world().resource::<CurrentArea>().0, Area::Mansion);
+    }
+
+    #[test]
+    fn the_restricted_area_needs_the_mansion_and_the_reputation() {
+        let mut app = travel_app();
+        assert_eq!(set_reputation(&mut app, 50), []);
+        set_reputation(&mut app, 49);
+        assert_eq!(
+            set_money(&mut app, 25_000),
+            [Area::LuxuryCondo, Area::Mansion]
+        );
+        assert_eq!(set_reputation(&mut app, 50), [Area::RestrictedArea]);
+        assert_eq!(
+            toasts(&mut app).last().map(String::as_str),
+            Some("Restricted Area unlocked!")
+        );
+    }
+
+    #[test]
+    fn the_mansion_and_the_restricted_area_can_open_together() {
+        let mut app = travel_app();
+        set_reputation(&mut app, 80);
+        // Areas unlock in the catalog's order, so the Mansion counts straight away.
+        assert_eq!(
+            set_money(&mut app, 25_000),
+            [Area::LuxuryCondo, Area::Mansion, Area::RestrictedArea]
+        );
+    }
+
+    #[test]
+    fn travel_to_the_restricted_area_is_refused_until_it_unlocks() {
+        let mut app = travel_app();
+        set_reputation(&mut app, 50);
+        toasts(&mut app);
+        assert_eq!(go_to(&mut app, Area::RestrictedArea), Area::DeadbeatArea);
+        assert_eq!(
+            toasts(&mut app),
+            ["A gated compound on the hill is locked. Unlock the Mansion. Earn a reputation of 50"]
+        );
+
+        set_money(&mut app, 25_000);
+        toasts(&mut app);
+        assert_eq!(go_to(&mut app, Area::RestrictedArea), Area::RestrictedArea);
+        assert_eq!(toasts(&mut app), Vec::<String>::new());
+    }
+}
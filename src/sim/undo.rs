@@ -0,0 +1,158 @@
+//! Taking back the last purchase. Buying gear or adopting a pet records an [`UndoEntry`] in
+//! the [`UndoBuffer`], and the toast for it gets an Undo button for [`UNDO_WINDOW`]. Undoing
+//! refunds the money as a [`Category::Refunds`] transaction, so the ledger shows both the
+//! purchase and its reversal, and puts back whatever the purchase changed.
+//!
+//! Only the most recent purchase can be undone, and only until anything else touches the
+//! player's cash or the day ends. Work shifts, food that's been eaten and time passing are
+//! never undoable.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::{
+    clock::Clock,
+    crime::Upgrades,
+    dialogue::DiscountToken,
+    economy::Money,
+    ledger::{Category, Transaction},
+    passive_income::{IncomeSource, IncomeSources},
+    pet::Pet,
+};
+use crate::AppState;
+use crate::timers::{RealUiTime, UiTimer};
+use crate::toast::Toast;
+
+/// How long a purchase can be taken back, in real time like the toast offering it.
+pub const UNDO_WINDOW: Duration = Duration::from_secs(10);
+
+/// What undoing a purchase puts back, besides the money.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoRestore {
+    DoorLock,
+    Umbrella,
+    Pet,
+    IncomeSource(IncomeSource),
+}
+
+/// A purchase and how to reverse it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndoEntry {
+    /// What was bought, for the toast, like "the umbrella".
+    pub label: String,
+    /// Money given back.
+    pub refund: u32,
+    pub restore: UndoRestore,
+    /// The discount token the purchase used up, given back too.
+    pub discount: Option<u32>,
+    /// Cash on hand right after the purchase. If it changed, something else happened since.
+    cash: u32,
+    day: u32,
+}
+
+impl UndoEntry {
+    pub fn new(
+        label: impl Into<String>,
+        refund: u32,
+        restore: UndoRestore,
+        discount: Option<u32>,
+    ) -> Self {
+        UndoEntry {
+            label: label.into(),
+            refund,
+            restore,
+            discount,
+            cash: 0,
+            day: 0,
+        }
+    }
+}
+
+/// The one purchase that can still be undone, if any.
+#[derive(Resource, Debug, Default)]
+pub struct UndoBuffer {
+    entry: Option<UndoEntry>,
+    timer: UiTimer,
+}
+
+impl UndoBuffer {
+    /// Makes `entry` the purchase to undo, replacing the last one. Call it right after
+    /// taking the money, with what's left in `money`.
+    pub fn record(&mut self, entry: UndoEntry, money: &Money, clock: &Clock) {
+        self.entry = Some(UndoEntry {
+            cash: money.amount,
+            day: clock.day,
+            ..entry
+        });
+        self.timer = UiTimer::new(UNDO_WINDOW, TimerMode::Once);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entry.is_none()
+    }
+}
+
+/// Send this to undo the purchase in the [`UndoBuffer`].
+#[derive(Event, Debug, Clone, Copy)]
+pub struct UndoRequested;
+
+pub fn undo_plugin(app: &mut App) {
+    app.init_resource::<UndoBuffer>()
+        .add_event::<UndoRequested>()
+        .add_systems(
+            Update,
+            (expire_undo, undo_purchase)
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        );
+}
+
+// Anything that moved cash since, a new day or the window running out ends the chance.
+fn expire_undo(
+    time: RealUiTime,
+    money: Res<Money>,
+    clock: Res<Clock>,
+    mut buffer: ResMut<UndoBuffer>,
+) {
+    let Some(entry) = &buffer.entry else {
+        return;
+    };
+    let stale = entry.cash != money.amount || entry.day != clock.day;
+    if stale || buffer.timer.tick(&time).finished() {
+        buffer.entry = None;
+    }
+}
+
+fn undo_purchase(
+    mut requests: EventReader<UndoRequested>,
+    mut buffer: ResMut<UndoBuffer>,
+    mut money: ResMut<Money>,
+    mut upgrades: ResMut<Upgrades>,
+    mut pet: ResMut<Pet>,
+    mut sources: ResMut<IncomeSources>,
+    mut discount: ResMut<DiscountToken>,
+    mut toasts: EventWriter<Toast>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    if requests.read().count() == 0 {
+        return;
+    }
+    let Some(entry) = buffer.entry.take() else {
+        return;
+    };
+    money.amount += entry.refund;
+    transactions.write(Transaction::Income(Category::Refunds, entry.refund));
+    if discount.0.is_none() {
+        discount.0 = entry.discount;
+    }
+    match entry.restore {
+        UndoRestore::DoorLock => upgrades.door_lock = false,
+        UndoRestore::Umbrella => upgrades.umbrella = false,
+        UndoRestore::Pet => pet.0 = None,
+        UndoRestore::IncomeSource(source) => {
+            sources.0.retain(|owned| owned.source != source);
+        }
+    }
+    toasts.write(Toast::new(format!("Returned {}", entry.label)));
+}
@@ -0,0 +1,218 @@
+//! Vignettes: short scripted scenes that play once a run, the first time their trigger
+//! holds, like the first night in the Mansion. Each is a few event cards shown one after
+//! the other, and a card can offer choices. The scenes and their triggers come from
+//! [`VIGNETTES_PATH`], with triggers written as a [`Predicate`] over the run's
+//! [`RunFacts`].
+//!
+//! Triggers are checked every tick, so never while a modal is up, and the cards wait their
+//! turn in the [`EventCardQueue`]. The scenes seen this run are its [`Moments`].
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    SimSet,
+    bank::Bank,
+    choices::{Choice, ChoiceLog},
+    clock::Clock,
+    content::{ContentTag, RunFilters},
+    economy::{CurrentArea, HomeArea, Money},
+    event_cards::{CardChosen, CardId, CardRequest, EventCardQueue},
+    journal::PhotoRequest,
+    needs::{Energy, Happiness},
+    predicate::{Facts, Predicate, Value},
+};
+use crate::AppState;
+use crate::data::register_ron_resource;
+
+pub const VIGNETTES_PATH: &str = "data/world.vignettes.ron";
+
+/// A button on a vignette card.
+#[derive(Deserialize, Clone, Debug)]
+pub struct VignetteChoice {
+    pub label: String,
+    #[serde(default)]
+    pub happiness: i32,
+    /// How the end screens sum up picking it, see [`Choice::notable`].
+    #[serde(default)]
+    pub notable: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct VignetteCard {
+    pub title: String,
+    pub body: String,
+    /// Without any, the card only has a button to go on.
+    #[serde(default)]
+    pub choices: Vec<VignetteChoice>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct VignetteDef {
+    /// Names the vignette in [`Moments`], so it has to be unique.
+    pub id: String,
+    pub trigger: Predicate,
+    /// In the order they show.
+    pub cards: Vec<VignetteCard>,
+    #[serde(default)]
+    pub tags: Vec<ContentTag>,
+}
+
+/// Every vignette there is.
+#[derive(Asset, TypePath, Resource, Deserialize, Clone, Debug)]
+pub struct Vignettes {
+    pub vignettes: Vec<VignetteDef>,
+}
+
+/// The vignettes that played this run, by id, in order.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Moments(pub Vec<String>);
+
+/// The vignette on screen: its index in [`Vignettes`], the card showing and that card's
+/// id in the queue.
+#[derive(Resource, Debug, Default)]
+struct PlayingVignette(Option<(usize, usize, CardId)>);
+
+/// The facts a vignette's trigger can compare: `day`, `hour`, `money`, `savings`,
+/// `happiness`, `energy`, and `area` and `home` by the area's name, like `Mansion`.
+#[derive(Clone, Copy)]
+pub struct RunFacts<'a> {
+    pub clock: &'a Clock,
+    pub money: &'a Money,
+    pub bank: &'a Bank,
+    pub happiness: &'a Happiness,
+    pub energy: &'a Energy,
+    pub area: &'a CurrentArea,
+    pub home: &'a HomeArea,
+}
+
+impl Facts for RunFacts<'_> {
+    fn fact(&self, name: &str) -> Option<Value> {
+        let number = |value: u32| Some(Value::Number(value.into()));
+        match name {
+            "day" => number(self.clock.day),
+            "hour" => number(self.clock.hour),
+            "money" => number(self.money.amount),
+            "savings" => number(self.bank.savings),
+            "happiness" => number(self.happiness.0),
+            "energy" => number(self.energy.0),
+            "area" => Some(Value::Word(format!("{:?}", self.area.0))),
+            "home" => Some(Value::Word(format!("{:?}", self.home.location))),
+            _ => None,
+        }
+    }
+}
+
+pub fn vignettes_plugin(app: &mut App) {
+    register_ron_resource::<Vignettes>(app, &["vignettes.ron"]);
+    app.register_type::<Moments>()
+        .init_resource::<Moments>()
+        .init_resource::<PlayingVignette>()
+        .add_systems(
+            FixedUpdate,
+            start_vignettes
+                .run_if(resource_exists::<Vignettes>)
+                .in_set(SimSet::Ledger),
+        )
+        .add_systems(
+            Update,
+            continue_vignette.run_if(in_state(AppState::Game).and(resource_exists::<Vignettes>)),
+        )
+        .add_systems(OnExit(AppState::Game), stop_vignette);
+}
+
+fn card_request(card: &VignetteCard, last: bool) -> CardRequest {
+    let request = CardRequest::new(card.title.clone(), card.body.clone());
+    if !card.choices.is_empty() {
+        request.with_buttons(card.choices.iter().map(|choice| choice.label.clone()))
+    } else if last {
+        request
+    } else {
+        request.with_buttons(["Continue"])
+    }
+}
+
+fn start_vignettes(
+    vignettes: Res<Vignettes>,
+    filters: Res<RunFilters>,
+    (clock, money, bank): (Res<Clock>, Res<Money>, Res<Bank>),
+    (happiness, energy): (Res<Happiness>, Res<Energy>),
+    (area, home): (Res<CurrentArea>, Res<HomeArea>),
+    mut moments: ResMut<Moments>,
+    mut playing: ResMut<PlayingVignette>,
+    mut cards: ResMut<EventCardQueue>,
+    mut photos: EventWriter<PhotoRequest>,
+) {
+    if playing.0.is_some() {
+        return;
+    }
+    let facts = RunFacts {
+        clock: &clock,
+        money: &money,
+        bank: &bank,
+        happiness: &happiness,
+        energy: &energy,
+        area: &area,
+        home: &home,
+    };
+    let started = vignettes
+        .vignettes
+        .iter()
+        .enumerate()
+        .find(|(_, vignette)| {
+            !vignette.cards.is_empty()
+                && filters.allows(&vignette.tags)
+                && !moments.0.contains(&vignette.id)
+                && vignette.trigger.holds(&facts)
+        });
+    if let Some((index, vignette)) = started {
+        info!("Playing vignette {}", vignette.id);
+        // Seen as soon as it starts, so leaving halfway doesn't play it again.
+        moments.0.push(vignette.id.clone());
+        photos.write(PhotoRequest::moment(vignette.cards[0].title.clone()));
+        let id = cards.push(card_request(&vignette.cards[0], vignette.cards.len() == 1));
+        playing.0 = Some((index, 0, id));
+    }
+}
+
+fn continue_vignette(
+    mut chosen: EventReader<CardChosen>,
+    vignettes: Res<Vignettes>,
+    clock: Res<Clock>,
+    mut playing: ResMut<PlayingVignette>,
+    mut cards: ResMut<EventCardQueue>,
+    mut happiness: ResMut<Happiness>,
+    mut choices: ResMut<ChoiceLog>,
+) {
+    for CardChosen { card, button } in chosen.read() {
+        let Some((index, shown, id)) = playing.0 else {
+            return;
+        };
+        if *card != id {
+            continue;
+        }
+        let Some(vignette) = vignettes.vignettes.get(index) else {
+            playing.0 = None;
+            return;
+        };
+        if let Some(choice) = vignette.cards[shown].choices.get(*button) {
+            happiness.change(choice.happiness);
+            choices.record(
+                clock.day,
+                Choice::new(&vignette.cards[shown].title, &choice.label)
+                    .notable(choice.notable.as_deref()),
+            );
+        }
+        let next = shown + 1;
+        playing.0 = vignette.cards.get(next).map(|card| {
+            let last = next + 1 == vignette.cards.len();
+            (index, next, cards.push(card_request(card, last)))
+        });
+    }
+}
+
+// The run's cards go with it, so the vignette it was playing can't go on.
+fn stop_vignette(mut playing: ResMut<PlayingVignette>) {
+    playing.0 = None;
+}
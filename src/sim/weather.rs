@@ -0,0 +1,235 @@
+//! Daily weather, rolled at midnight with the odds of the season in the [`Calendar`]. Rain makes
+//! the commute longer, storms may close the workplace, and sunny days cheer the player up.
+//!
+//! Everything that bends the day's routine is expressed as [`DayModifiers`], which add up,
+//! so the weather and the player's gear combine without knowing about each other.
+
+use std::ops::Add;
+
+use bevy::prelude::*;
+
+use super::{
+    calendar::{Calendar, WeatherOdds},
+    clock::{Clock, day_started},
+    cost_of_living::Prices,
+    crime::Upgrades,
+    dialogue::DiscountToken,
+    economy::Money,
+    ledger::{Category, Transaction},
+    needs::Happiness,
+    rng::{GameRng, RollSet},
+    undo::{UndoBuffer, UndoEntry, UndoRestore},
+};
+use crate::AppState;
+use crate::format;
+use crate::screens::actions::{ActionPressed, ActionSet, AvailableActions, GameAction};
+use crate::settings::Locale;
+use crate::toast::Toast;
+
+pub const UMBRELLA_PRICE: u32 = 30;
+const RAIN_COMMUTE_HOURS: i32 = 1;
+const STORM_SHIFT_CANCEL_CHANCE: f32 = 0.5;
+const SUNNY_HAPPINESS_CHANGE: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum WeatherKind {
+    #[default]
+    Sunny,
+    Rain,
+    Storm,
+}
+
+impl WeatherKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            WeatherKind::Sunny => "Sunny",
+            WeatherKind::Rain => "Rain",
+            WeatherKind::Storm => "Storm",
+        }
+    }
+
+    pub fn modifiers(self) -> DayModifiers {
+        match self {
+            WeatherKind::Sunny => DayModifiers {
+                happiness: SUNNY_HAPPINESS_CHANGE,
+                ..default()
+            },
+            WeatherKind::Rain => DayModifiers {
+                commute_hours: RAIN_COMMUTE_HOURS,
+                ..default()
+            },
+            WeatherKind::Storm => DayModifiers {
+                shift_cancel_chance: STORM_SHIFT_CANCEL_CHANCE,
+                ..default()
+            },
+        }
+    }
+}
+
+/// Rolls the weather for a day with the given odds.
+pub fn roll_weather(odds: WeatherOdds, rng: &mut GameRng) -> WeatherKind {
+    let weights = [
+        (WeatherKind::Sunny, odds.sunny),
+        (WeatherKind::Rain, odds.rain),
+        (WeatherKind::Storm, odds.storm),
+    ];
+    let total: u32 = weights.iter().map(|(_, weight)| weight).sum();
+    if total == 0 {
+        return WeatherKind::Sunny;
+    }
+    let mut roll = rng.index(total as usize) as u32;
+    for (kind, weight) in weights {
+        if roll < weight {
+            return kind;
+        }
+        roll -= weight;
+    }
+    WeatherKind::Sunny
+}
+
+/// Today's weather and the forecast for tomorrow, which is what tomorrow will bring.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct Weather {
+    pub today: WeatherKind,
+    pub tomorrow: WeatherKind,
+}
+
+/// Adjustments to the day's routine from one source, or from all of them added up.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DayModifiers {
+    /// Hours added to the commute to work. May be negative to cancel out a delay.
+    pub commute_hours: i32,
+    /// Chance that a shift is called off when the player shows up.
+    pub shift_cancel_chance: f32,
+    /// Happiness change when the day starts.
+    pub happiness: i32,
+}
+
+impl Add for DayModifiers {
+    type Output = DayModifiers;
+
+    fn add(self, other: DayModifiers) -> DayModifiers {
+        DayModifiers {
+            commute_hours: self.commute_hours + other.commute_hours,
+            shift_cancel_chance: 1.0
+                - (1.0 - self.shift_cancel_chance) * (1.0 - other.shift_cancel_chance),
+            happiness: self.happiness + other.happiness,
+        }
+    }
+}
+
+impl DayModifiers {
+    /// Everything affecting today: the weather and the player's gear.
+    pub fn today(weather: &Weather, upgrades: &Upgrades) -> Self {
+        weather.today.modifiers() + upgrades.modifiers()
+    }
+
+    /// Extra commute hours, never less than none.
+    pub fn extra_commute_hours(&self) -> u32 {
+        self.commute_hours.max(0) as u32
+    }
+}
+
+impl Upgrades {
+    pub fn modifiers(&self) -> DayModifiers {
+        if self.umbrella {
+            DayModifiers {
+                commute_hours: -RAIN_COMMUTE_HOURS,
+                ..default()
+            }
+        } else {
+            DayModifiers::default()
+        }
+    }
+}
+
+pub fn weather_plugin(app: &mut App) {
+    app.register_type::<Weather>()
+        .init_resource::<Weather>()
+        .add_systems(
+            FixedUpdate,
+            (advance_weather, apply_morning_mood)
+                .chain()
+                .run_if(day_started)
+                .in_set(RollSet::Weather),
+        )
+        .add_systems(Update, offer_umbrella.in_set(ActionSet::Offer))
+        .add_systems(Update, buy_umbrella.run_if(in_state(AppState::Game)));
+}
+
+// Tomorrow is rolled a day ahead so the forecast is always right.
+fn advance_weather(
+    clock: Res<Clock>,
+    calendar: Res<Calendar>,
+    mut weather: ResMut<Weather>,
+    mut rng: ResMut<GameRng>,
+) {
+    weather.today = weather.tomorrow;
+    weather.tomorrow = roll_weather(calendar.season(clock.day + 1).weather, &mut rng);
+}
+
+fn apply_morning_mood(
+    weather: Res<Weather>,
+    upgrades: Res<Upgrades>,
+    mut happiness: ResMut<Happiness>,
+) {
+    happiness.change(DayModifiers::today(&weather, &upgrades).happiness);
+}
+
+fn offer_umbrella(
+    upgrades: Res<Upgrades>,
+    clock: Res<Clock>,
+    calendar: Res<Calendar>,
+    money: Res<Money>,
+    discount: Res<DiscountToken>,
+    prices: Prices,
+    mut actions: ResMut<AvailableActions>,
+    locale: Res<Locale>,
+) {
+    if upgrades.umbrella {
+        return;
+    }
+    let price = discount.price(prices.price(UMBRELLA_PRICE));
+    let base = discount.price(prices.without_cost_of_living(UMBRELLA_PRICE));
+    let label = format!("Buy an umbrella: -{}", format::price(price, base, *locale));
+    if let Some(reason) = calendar.closed_reason(clock.day) {
+        actions.offer_blocked(GameAction::BuyUmbrella, label, reason);
+    } else if money.amount >= price {
+        actions.offer_labeled(GameAction::BuyUmbrella, label);
+    } else {
+        actions.offer_blocked(GameAction::BuyUmbrella, label, "Not enough cash");
+    }
+}
+
+fn buy_umbrella(
+    mut pressed: EventReader<ActionPressed>,
+    clock: Res<Clock>,
+    calendar: Res<Calendar>,
+    mut upgrades: ResMut<Upgrades>,
+    mut money: ResMut<Money>,
+    mut discount: ResMut<DiscountToken>,
+    prices: Prices,
+    mut undo: ResMut<UndoBuffer>,
+    mut toasts: EventWriter<Toast>,
+    mut transactions: EventWriter<Transaction>,
+) {
+    for ActionPressed(action) in pressed.read() {
+        let price = discount.price(prices.price(UMBRELLA_PRICE));
+        if *action == GameAction::BuyUmbrella
+            && !upgrades.umbrella
+            && money.amount >= price
+            && calendar.holiday(clock.day).is_none()
+        {
+            money.amount -= price;
+            transactions.write(Transaction::Expense(Category::Purchases, price));
+            let entry = UndoEntry::new("the umbrella", price, UndoRestore::Umbrella, discount.0);
+            undo.record(entry, &money, &clock);
+            discount.0 = None;
+            upgrades.umbrella = true;
+            toasts.write(Toast::undoable(
+                "Bought an umbrella - rain won't slow you down",
+            ));
+        }
+    }
+}
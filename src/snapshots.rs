@@ -0,0 +1,632 @@
+//! The `--snapshots` launch mode, for catching layout regressions like buttons over the
+//! title art or HUD corners running into each other, which otherwise only show on screen.
+//!
+//! The game runs without a renderer in a window that never opens, and goes to each screen
+//! in [`SHOTS`] in order, resizing the window to the shot's resolution first. Most are at
+//! [`REGULAR`], the default window, and a few at [`HANDHELD`] and [`COMPACT`] to cover
+//! smaller screens. The last two are in [`Locale::PseudoRtl`], whose layouts mirror the
+//! regular ones.
+//! Once a screen has settled, with nothing animating and every image and font it uses
+//! loaded, the laid out UI is written down one node per line after the [`LayoutScale`] it
+//! was built with, nested as in the tree: the node's markers from this crate, its size and
+//! position in pixels, and its text. Only visible nodes are written.
+//!
+//! Each summary is compared with its file in [`SNAPSHOTS_DIR`], next to the assets folder,
+//! and any that differ are printed, along with any button that isn't wholly inside the
+//! window. `--update-snapshots` writes the files instead, for
+//! layouts that changed on purpose. The game quits with a failing exit code if any shot
+//! differs, is missing, or never settled.
+//!
+//! The simulation's clock is stopped throughout and motion is reduced, so the same build
+//! always lays out the same way. The shots are taken in their own [`PROFILE`], which is
+//! deleted afterwards. `cargo test` checks them too.
+
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::{
+    app::PluginGroupBuilder,
+    asset::UntypedAssetId,
+    prelude::*,
+    window::{ExitCondition, PrimaryWindow, WindowResized, WindowResolution},
+};
+
+use crate::AppState;
+use crate::launch::LaunchOptions;
+use crate::power::AnimationsActive;
+use crate::profiles::{self, ProfilePaths};
+use crate::screens::loading::AssetsRoot;
+use crate::screens::menu::MenuState;
+use crate::screens::overlay::Overlay;
+use crate::settings::{LayoutScale, Locale, ReducedMotion};
+use crate::sim::{economy::GameOverReason, playtime::Playtime};
+
+/// The profile the shots are taken in, unless `--profile` picks one.
+pub const PROFILE: &str = "Snapshots";
+/// The folder the snapshot files live in, beside the assets folder.
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+/// The default window, which gets [`LayoutScale::Regular`].
+const REGULAR: Vec2 = Vec2::new(1280.0, 720.0);
+/// Short enough for [`LayoutScale::Compact`].
+const COMPACT: Vec2 = Vec2::new(960.0, 540.0);
+/// A handheld's screen, which still gets [`LayoutScale::Regular`].
+const HANDHELD: Vec2 = Vec2::new(1280.0, 800.0);
+/// Frames in a row a screen has to stay still before its shot is taken.
+const SETTLE_FRAMES: u32 = 5;
+/// How long loading may take, and how long a screen may take to settle.
+const LOADING_TIMEOUT: Duration = Duration::from_secs(60);
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Seeds the run the game screens show.
+const SEED: u64 = 1;
+
+/// Whether `--snapshots` compares the shots with their files or writes them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotMode {
+    Check,
+    Update,
+}
+
+/// `plugins` without a renderer, with a window of [`REGULAR`] size that never opens, so
+/// the UI has a size to lay out in.
+pub fn headless(plugins: PluginGroupBuilder) -> PluginGroupBuilder {
+    crate::self_test::headless(plugins).set(WindowPlugin {
+        primary_window: Some(Window {
+            resolution: WindowResolution::new(REGULAR.x, REGULAR.y).with_scale_factor_override(1.0),
+            ..default()
+        }),
+        exit_condition: ExitCondition::DontExit,
+        close_when_requested: false,
+    })
+}
+
+/// A screen to take a shot of, and how to get there.
+struct Shot {
+    name: &'static str,
+    app: AppState,
+    menu: MenuState,
+    /// Only for [`AppState::Game`].
+    overlay: Overlay,
+    /// The window's size, in pixels.
+    resolution: Vec2,
+    /// Whether every button has to be wholly inside the window. Not on the HUD, whose
+    /// action panel runs past the bottom of the window when a lot is on offer.
+    buttons_on_screen: bool,
+    /// Sets up what the screen shows, before going there.
+    prepare: fn(&mut World),
+}
+
+/// Every shot, in the order they're taken.
+const SHOTS: &[Shot] = &[
+    Shot {
+        name: "main_menu",
+        app: AppState::Menu,
+        menu: MenuState::Main,
+        overlay: Overlay::None,
+        resolution: REGULAR,
+        buttons_on_screen: true,
+        prepare: |_| {},
+    },
+    Shot {
+        name: "main_menu_handheld",
+        app: AppState::Menu,
+        menu: MenuState::Main,
+        overlay: Overlay::None,
+        resolution: HANDHELD,
+        buttons_on_screen: true,
+        prepare: |_| {},
+    },
+    Shot {
+        name: "settings",
+        app: AppState::Menu,
+        menu: MenuState::Settings,
+        overlay: Overlay::None,
+        resolution: REGULAR,
+        buttons_on_screen: true,
+        prepare: |_| {},
+    },
+    Shot {
+        name: "hud",
+        app: AppState::Game,
+        menu: MenuState::Disabled,
+        overlay: Overlay::None,
+        resolution: REGULAR,
+        buttons_on_screen: false,
+        prepare: |_| {},
+    },
+    Shot {
+        name: "pause_settings",
+        app: AppState::Game,
+        menu: MenuState::Settings,
+        overlay: Overlay::Pause,
+        resolution: REGULAR,
+        buttons_on_screen: false,
+        prepare: |_| {},
+    },
+    Shot {
+        name: "game_over",
+        app: AppState::GameOver,
+        menu: MenuState::Disabled,
+        overlay: Overlay::None,
+        resolution: REGULAR,
+        buttons_on_screen: true,
+        prepare: |world| {
+            world.insert_resource(GameOverReason::Evicted);
+            world.insert_resource(Playtime::default());
+        },
+    },
+    Shot {
+        name: "win",
+        app: AppState::Win,
+        menu: MenuState::Disabled,
+        overlay: Overlay::None,
+        resolution: REGULAR,
+        buttons_on_screen: true,
+        prepare: |world| world.insert_resource(Playtime::default()),
+    },
+    Shot {
+        name: "main_menu_compact",
+        app: AppState::Menu,
+        menu: MenuState::Main,
+        overlay: Overlay::None,
+        resolution: COMPACT,
+        buttons_on_screen: true,
+        prepare: |_| {},
+    },
+    Shot {
+        name: "hud_compact",
+        app: AppState::Game,
+        menu: MenuState::Disabled,
+        overlay: Overlay::None,
+        resolution: COMPACT,
+        buttons_on_screen: false,
+        prepare: |_| {},
+    },
+    // The mirrored layouts, last so the locale stays put for the rest.
+    Shot {
+        name: "main_menu_rtl",
+        app: AppState::Menu,
+        menu: MenuState::Main,
+        overlay: Overlay::None,
+        resolution: REGULAR,
+        buttons_on_screen: true,
+        prepare: |world| world.insert_resource(Locale::PseudoRtl),
+    },
+    Shot {
+        name: "hud_rtl",
+        app: AppState::Game,
+        menu: MenuState::Disabled,
+        overlay: Overlay::None,
+        resolution: REGULAR,
+        buttons_on_screen: false,
+        prepare: |_| {},
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Loading,
+    /// Waiting for the next shot.
+    Ready,
+    /// On the way to a shot's screen, or waiting for it to settle.
+    Settling {
+        still_frames: u32,
+    },
+}
+
+#[derive(Resource)]
+struct Snapshots {
+    mode: SnapshotMode,
+    dir: PathBuf,
+    step: Step,
+    /// When the step started, on the real clock.
+    since: Duration,
+    /// The next shot in [`SHOTS`].
+    next: usize,
+    failures: Vec<String>,
+}
+
+pub fn snapshots_plugin(app: &mut App) {
+    let mode = app
+        .world()
+        .resource::<LaunchOptions>()
+        .snapshots
+        .unwrap_or(SnapshotMode::Check);
+    let root = &app.world().resource::<AssetsRoot>().path;
+    let dir = root.parent().unwrap_or(root).join(SNAPSHOTS_DIR);
+    app.insert_resource(Snapshots {
+        mode,
+        dir,
+        step: Step::Loading,
+        since: Duration::ZERO,
+        next: 0,
+        failures: Vec::new(),
+    })
+    // After the layout and every animation counted itself in.
+    .add_systems(Last, take_shots);
+}
+
+fn take_shots(world: &mut World) {
+    let now = world.resource::<Time<Real>>().elapsed();
+    let mut snapshots = world
+        .remove_resource::<Snapshots>()
+        .expect("the snapshots are set up");
+    let waited = now - snapshots.since;
+    let step = match snapshots.step {
+        Step::Loading => {
+            // Every frame until the menu is up, over the profile's settings however late
+            // they load, so nothing on it moves from the start.
+            world.insert_resource(ReducedMotion(true));
+            loading(world, &mut snapshots, waited)
+        }
+        Step::Ready => match SHOTS.get(snapshots.next) {
+            Some(shot) => {
+                (shot.prepare)(world);
+                Some(Step::Settling { still_frames: 0 })
+            }
+            None => {
+                finish(world, &snapshots);
+                None
+            }
+        },
+        Step::Settling { still_frames } => {
+            let shot = &SHOTS[snapshots.next];
+            let still = go_to(world, shot) && settled(world);
+            let still_frames = if still { still_frames + 1 } else { 0 };
+            if still_frames >= SETTLE_FRAMES {
+                compare(world, &mut snapshots, shot);
+                snapshots.next += 1;
+                Some(Step::Ready)
+            } else if waited > SETTLE_TIMEOUT {
+                snapshots
+                    .failures
+                    .push(format!("{} never settled", shot.name));
+                snapshots.next += 1;
+                Some(Step::Ready)
+            } else {
+                snapshots.step = Step::Settling { still_frames };
+                None
+            }
+        }
+    };
+    if let Some(step) = step {
+        snapshots.step = step;
+        snapshots.since = now;
+    }
+    world.insert_resource(snapshots);
+}
+
+fn loading(world: &mut World, snapshots: &mut Snapshots, waited: Duration) -> Option<Step> {
+    match *world.resource::<State<AppState>>().get() {
+        AppState::Menu => {
+            world.resource_mut::<LaunchOptions>().seed = Some(SEED);
+            world.resource_mut::<Time<Virtual>>().pause();
+            Some(Step::Ready)
+        }
+        AppState::AssetError => {
+            snapshots
+                .failures
+                .push("the assets didn't load".to_string());
+            finish(world, snapshots);
+            None
+        }
+        _ if waited > LOADING_TIMEOUT => {
+            snapshots
+                .failures
+                .push("loading never finished".to_string());
+            finish(world, snapshots);
+            None
+        }
+        _ => None,
+    }
+}
+
+// Resizes the window if it isn't the shot's size yet, before anything else so the screens
+// are built for it, telling the game the way a real window would. Then asks for whichever
+// of the shot's states the game isn't in yet, the app's first since the others depend on
+// it. Returns whether it's there.
+fn go_to(world: &mut World, shot: &Shot) -> bool {
+    let mut windows = world.query_filtered::<(Entity, &mut Window), With<PrimaryWindow>>();
+    if let Ok((window, mut size)) = windows.single_mut(world)
+        && size.resolution.size() != shot.resolution
+    {
+        size.resolution.set(shot.resolution.x, shot.resolution.y);
+        world.send_event(WindowResized {
+            window,
+            width: shot.resolution.x,
+            height: shot.resolution.y,
+        });
+        return false;
+    }
+    if *world.resource::<State<AppState>>().get() != shot.app {
+        world.resource_mut::<NextState<AppState>>().set(shot.app);
+        return false;
+    }
+    let mut there = true;
+    if *world.resource::<State<MenuState>>().get() != shot.menu {
+        world.resource_mut::<NextState<MenuState>>().set(shot.menu);
+        there = false;
+    }
+    if let Some(overlay) = world.get_resource::<State<Overlay>>()
+        && *overlay.get() != shot.overlay
+    {
+        world.resource_mut::<NextState<Overlay>>().set(shot.overlay);
+        there = false;
+    }
+    there
+}
+
+// Nothing animating, and every image and font on screen loaded or given up on.
+fn settled(world: &mut World) -> bool {
+    if world.resource::<AnimationsActive>().any() {
+        return false;
+    }
+    let mut images = world.query::<&ImageNode>();
+    let mut fonts = world.query::<&TextFont>();
+    let ids: Vec<UntypedAssetId> = images
+        .iter(world)
+        .map(|image| image.image.id().untyped())
+        .chain(fonts.iter(world).map(|font| font.font.id().untyped()))
+        .collect();
+    let assets = world.resource::<AssetServer>();
+    ids.into_iter().all(|id| {
+        assets.is_loaded_with_dependencies(id)
+            || assets
+                .get_load_state(id)
+                .is_none_or(|state| state.is_failed())
+    })
+}
+
+// Writes down the laid out UI, roots in drawing order.
+fn summary(world: &mut World) -> String {
+    let mut roots = world.query_filtered::<(Entity, Option<&GlobalZIndex>, &GlobalTransform), (With<Node>, Without<ChildOf>)>();
+    let mut roots: Vec<_> = roots
+        .iter(world)
+        .map(|(entity, z, transform)| {
+            let position = transform.translation();
+            (z.map_or(0, |z| z.0), position.y, position.x, entity)
+        })
+        .collect();
+    roots.sort_by(|a, b| {
+        a.0.cmp(&b.0)
+            .then(a.1.total_cmp(&b.1))
+            .then(a.2.total_cmp(&b.2))
+    });
+    let mut text = format!("{:?}\n", world.resource::<LayoutScale>());
+    for (.., root) in roots {
+        write_node(world, root, 0, &mut text);
+    }
+    text
+}
+
+fn write_node(world: &World, entity: Entity, depth: usize, out: &mut String) {
+    let entity_ref = world.entity(entity);
+    if entity_ref
+        .get::<InheritedVisibility>()
+        .is_some_and(|visibility| !visibility.get())
+    {
+        return;
+    }
+    let (Some(node), Some(transform)) = (
+        entity_ref.get::<ComputedNode>(),
+        entity_ref.get::<GlobalTransform>(),
+    ) else {
+        return;
+    };
+    let size = node.size();
+    let top_left = transform.translation().truncate() - size / 2.0;
+    let markers = markers(world, entity);
+    let _ = write!(
+        out,
+        "{:indent$}{} {}x{} at {},{}",
+        "",
+        if markers.is_empty() { "-" } else { &markers },
+        size.x.round(),
+        size.y.round(),
+        top_left.x.round(),
+        top_left.y.round(),
+        indent = depth * 2
+    );
+    if let Some(text) = entity_ref.get::<Text>() {
+        let spans = entity_ref
+            .get::<Children>()
+            .into_iter()
+            .flat_map(|children| children.iter())
+            .filter_map(|span| world.get::<TextSpan>(span));
+        let text = spans.fold(text.0.clone(), |text, span| text + &span.0);
+        let _ = write!(out, " {text:?}");
+    }
+    out.push('\n');
+    if let Some(children) = entity_ref.get::<Children>() {
+        for child in children.iter() {
+            write_node(world, child, depth + 1, out);
+        }
+    }
+}
+
+// The names of the entity's components from this crate, like "OnMainMenuScreen".
+fn markers(world: &World, entity: Entity) -> String {
+    let prefix = concat!(env!("CARGO_CRATE_NAME"), "::");
+    let Ok(components) = world.inspect_entity(entity) else {
+        return String::new();
+    };
+    let mut names: Vec<String> = components
+        .filter(|info| info.name().starts_with(prefix))
+        .map(|info| short_name(info.name()))
+        .collect();
+    names.sort_unstable();
+    names.join(",")
+}
+
+// `name` without module paths, generics included.
+fn short_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut path = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+            continue;
+        }
+        short.push_str(path.rsplit("::").next().unwrap_or_default());
+        path.clear();
+        short.push(c);
+    }
+    short.push_str(path.rsplit("::").next().unwrap_or_default());
+    short
+}
+
+fn compare(world: &mut World, snapshots: &mut Snapshots, shot: &Shot) {
+    let off_screen = if shot.buttons_on_screen {
+        off_screen_buttons(world, shot.resolution)
+    } else {
+        Vec::new()
+    };
+    for button in off_screen {
+        snapshots
+            .failures
+            .push(format!("{}: {button} isn't wholly on screen", shot.name));
+    }
+    let summary = summary(world);
+    let path = snapshots.dir.join(format!("{}.txt", shot.name));
+    match snapshots.mode {
+        SnapshotMode::Update => {
+            let written = std::fs::create_dir_all(&snapshots.dir)
+                .and_then(|_| std::fs::write(&path, &summary));
+            match written {
+                Ok(()) => info!("Snapshots: wrote {}", path.display()),
+                Err(error) => snapshots
+                    .failures
+                    .push(format!("couldn't write {}: {error}", path.display())),
+            }
+        }
+        SnapshotMode::Check => match std::fs::read_to_string(&path) {
+            Ok(expected) if expected == summary => {}
+            Ok(expected) => snapshots.failures.push(format!(
+                "{} changed:\n{}",
+                shot.name,
+                diff(&expected, &summary)
+            )),
+            Err(_) => snapshots.failures.push(format!(
+                "{} has no snapshot at {}, see --update-snapshots",
+                shot.name,
+                path.display()
+            )),
+        },
+    }
+}
+
+// The visible buttons that stick out of a window of `resolution`, or sit outside it.
+fn off_screen_buttons(world: &mut World, resolution: Vec2) -> Vec<String> {
+    let screen = Rect::from_corners(Vec2::ZERO, resolution);
+    let mut buttons = world.query_filtered::<(
+        Entity,
+        &ComputedNode,
+        &GlobalTransform,
+        &InheritedVisibility,
+    ), With<Button>>();
+    buttons
+        .iter(world)
+        .filter(|(.., visibility)| visibility.get())
+        .filter_map(|(entity, node, transform, _)| {
+            let rect = Rect::from_center_size(transform.translation().truncate(), node.size());
+            let (min, max) = (rect.min.round(), rect.max.round());
+            (!screen.contains(min) || !screen.contains(max)).then(|| {
+                format!(
+                    "{} from {},{} to {},{}",
+                    markers(world, entity),
+                    min.x,
+                    min.y,
+                    max.x,
+                    max.y
+                )
+            })
+        })
+        .collect()
+}
+
+// The lines only one side has, marked like a diff.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = String::new();
+    for line in expected.iter().filter(|line| !actual.contains(line)) {
+        let _ = writeln!(out, "    - {line}");
+    }
+    for line in actual.iter().filter(|line| !expected.contains(line)) {
+        let _ = writeln!(out, "    + {line}");
+    }
+    out
+}
+
+// Prints the report and quits.
+fn finish(world: &mut World, snapshots: &Snapshots) {
+    println!(
+        "Snapshots: {} shots, {} failed",
+        snapshots.next,
+        snapshots.failures.len()
+    );
+    for failure in &snapshots.failures {
+        println!("  FAIL {failure}");
+    }
+    let profile = world.resource::<ProfilePaths>();
+    if profile.name() == PROFILE
+        && let Err(error) = profiles::delete(profile.root(), PROFILE)
+    {
+        warn!("Couldn't delete the {PROFILE} profile: {error}");
+    }
+    world.send_event(if snapshots.failures.is_empty() {
+        AppExit::Success
+    } else {
+        AppExit::from_code(1)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestGame;
+
+    /// The left edge and width of every node in shot `name` whose markers start with
+    /// `markers`, in the order they're written down.
+    fn columns(name: &str, markers: &str) -> Vec<(i32, i32)> {
+        let text = std::fs::read_to_string(format!("{SNAPSHOTS_DIR}/{name}.txt")).unwrap();
+        text.lines()
+            .filter_map(|line| {
+                let line = line.trim_start().strip_prefix(markers)?.strip_prefix(' ')?;
+                let (size, position) = line.split_once(" at ")?;
+                let width = size.split_once('x')?.0.parse().ok()?;
+                let left = position.split_once(',')?.0.parse().ok()?;
+                Some((left, width))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_rtl_shots_mirror_the_regular_ones() {
+        let window = REGULAR.x as i32;
+        for (shot, markers) in [("main_menu", "MenuButtonAction"), ("hud", "SlotNode")] {
+            let regular = columns(shot, markers);
+            let mirrored = columns(&format!("{shot}_rtl"), markers);
+            assert!(!regular.is_empty(), "no {markers} in {shot}");
+            let expected: Vec<_> = regular
+                .iter()
+                .map(|(left, width)| (window - left - width, *width))
+                .collect();
+            assert_eq!(mirrored, expected, "{shot}");
+        }
+    }
+
+    #[test]
+    fn every_shot_matches_its_snapshot() {
+        let mut app = TestGame::new(LaunchOptions {
+            snapshots: Some(SnapshotMode::Check),
+            profile: Some(PROFILE.to_string()),
+            ..default()
+        });
+        let exit = app.run_to_exit();
+        let snapshots = app.world().resource::<Snapshots>();
+        assert_eq!(snapshots.failures, Vec::<String>::new());
+        assert_eq!(snapshots.next, SHOTS.len());
+        assert_eq!(exit, AppExit::Success);
+    }
+}
@@ -1,4 +1,17 @@
-use bevy::{app::MainScheduleOrder, ecs::schedule::*, prelude::*};
+//! Bevy's stepping example UI, for stepping through the simulation's systems one at a
+//! time. The overlay lists its keys, and each system's row is tinted by the [`SimSet`] it
+//! runs in and can be clicked to skip it.
+
+use bevy::{
+    app::MainScheduleOrder,
+    ecs::schedule::{graph::Direction, *},
+    platform::collections::HashSet,
+    prelude::*,
+};
+
+use crate::palette::{BLUE, CORAL, LIGHT, YELLOW};
+use crate::screens::button_press::ButtonPresses;
+use crate::sim::SimSet;
 
 /// Independent [`Schedule`] for stepping systems.
 ///
@@ -55,13 +68,15 @@ impl Plugin for SteppingPlugin {
         app.insert_resource(State {
             ui_top: self.top,
             ui_left: self.left,
-            systems: Vec::new(),
+            built: false,
         })
+        .init_resource::<DisabledSystems>()
         .add_systems(
             DebugSchedule,
             (
                 build_ui.run_if(not(initialized)),
                 handle_input,
+                handle_clicks.run_if(initialized),
                 update_ui.run_if(initialized),
             )
                 .chain(),
@@ -72,25 +87,89 @@ impl Plugin for SteppingPlugin {
 /// Struct for maintaining stepping state
 #[derive(Resource, Debug)]
 struct State {
-    // vector of schedule/node id -> text index offset
-    systems: Vec<(InternedScheduleLabel, NodeId, usize)>,
+    // whether the system rows have been built
+    built: bool,
 
     // ui positioning
     ui_top: Val,
     ui_left: Val,
 }
 
+/// Systems clicked off in the stepping UI, kept for the whole session so they stay off
+/// when stepping is toggled off and on again. They only skip while stepping is enabled.
+#[derive(Resource, Debug, Default)]
+struct DisabledSystems(HashSet<(InternedScheduleLabel, NodeId)>);
+
 /// condition to check if the stepping UI has been constructed
 fn initialized(state: Res<State>) -> bool {
-    !state.systems.is_empty()
+    state.built
 }
 
 const FONT_COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
-const FONT_BOLD: &str = "fonts/FiraSans-Bold.ttf";
+const HELP_KEY: KeyCode = KeyCode::F1;
+/// Above the game's modals and toasts, so the rows can always be clicked.
+const STEPPING_Z_INDEX: i32 = 900;
+const ROW_ALPHA: f32 = 0.6;
+const SKIPPED_ALPHA: f32 = 0.4;
+const HELP_TEXT: &str = "` toggle stepping    S run the next system\n\
+    Space run to the end of the frame    / print the stepping state\n\
+    Click a system to skip it or run it again    F1 hide this help";
 
 #[derive(Component)]
 struct SteppingUi;
 
+#[derive(Component)]
+struct SteppingHelp;
+
+/// A clickable row for one system in a stepped schedule.
+#[derive(Component, Debug)]
+struct SystemRow {
+    schedule: InternedScheduleLabel,
+    node: NodeId,
+    name: String,
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum StepButton {
+    StepSystem,
+    RunToFrameEnd,
+}
+
+/// The color a system's row is tinted with, from the [`SimSet`] it runs in.
+fn sim_set_color(set: Option<SimSet>) -> Color {
+    match set {
+        Some(SimSet::Clock) => BLUE,
+        Some(SimSet::Economy) => YELLOW,
+        Some(SimSet::Ledger) => CORAL,
+        None => LIGHT,
+    }
+    .with_alpha(ROW_ALPHA)
+}
+
+/// The [`SimSet`] the system at `node` runs in, found by walking up the sets it's in.
+fn sim_set_of(graph: &ScheduleGraph, node: NodeId) -> Option<SimSet> {
+    let hierarchy = graph.hierarchy().graph();
+    let mut parents: Vec<_> = hierarchy
+        .neighbors_directed(node, Direction::Incoming)
+        .collect();
+    let mut seen = HashSet::new();
+    while let Some(parent) = parents.pop() {
+        if !seen.insert(parent) {
+            continue;
+        }
+        if let Some(set) = graph.get_set_at(parent) {
+            let found = [SimSet::Clock, SimSet::Economy, SimSet::Ledger]
+                .into_iter()
+                .find(|sim_set| set == sim_set as &dyn SystemSet);
+            if found.is_some() {
+                return found;
+            }
+        }
+        parents.extend(hierarchy.neighbors_directed(parent, Direction::Incoming));
+    }
+    None
+}
+
 /// Construct the stepping UI elements from the [`Schedules`] resource.
 ///
 /// This system may run multiple times before constructing the UI as all of the
@@ -98,12 +177,11 @@ struct SteppingUi;
 /// one of the stepping schedules has not yet been run.
 fn build_ui(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
     schedules: Res<Schedules>,
     mut stepping: ResMut<Stepping>,
     mut state: ResMut<State>,
 ) {
-    let mut text_spans = Vec::new();
+    let mut rows = Vec::new();
     let mut always_run = Vec::new();
 
     let Ok(schedule_order) = stepping.schedules() else {
@@ -114,14 +192,6 @@ fn build_ui(
     // each label
     for label in schedule_order {
         let schedule = schedules.get(*label).unwrap();
-        text_spans.push((
-            TextSpan(format!("{label:?}\n")),
-            TextFont {
-                font: asset_server.load(FONT_BOLD),
-                ..default()
-            },
-            TextColor(FONT_COLOR),
-        ));
 
         // grab the list of systems in the schedule, in the order the
         // single-threaded executor would run them.
@@ -129,57 +199,126 @@ fn build_ui(
             return;
         };
 
+        let mut systems_rows = Vec::new();
         for (node_id, system) in systems {
             // skip bevy default systems; we don't want to step those
             if system.name().starts_with("bevy") {
                 always_run.push((*label, node_id));
                 continue;
             }
-
-            // Add an entry to our systems list so we can find where to draw
-            // the cursor when the stepping cursor is at this system
-            // we add plus 1 to account for the empty root span
-            state.systems.push((*label, node_id, text_spans.len() + 1));
-
-            // Add a text section for displaying the cursor for this system
-            text_spans.push((
-                TextSpan::new("   "),
-                TextFont::default(),
-                TextColor(FONT_COLOR),
-            ));
-
-            // add the name of the system to the ui
-            text_spans.push((
-                TextSpan(format!("{}\n", system.name())),
-                TextFont::default(),
-                TextColor(FONT_COLOR),
+            let set = sim_set_of(schedule.graph(), node_id);
+            systems_rows.push((
+                SystemRow {
+                    schedule: *label,
+                    node: node_id,
+                    name: system.name().to_string(),
+                },
+                sim_set_color(set),
             ));
         }
+        rows.push((*label, systems_rows));
     }
 
     for (label, node) in always_run.drain(..) {
         stepping.always_run_node(label, node);
     }
+    state.built = true;
 
-    commands.spawn((
-        Text::default(),
-        SteppingUi,
-        Node {
-            position_type: PositionType::Absolute,
-            top: state.ui_top,
-            left: state.ui_left,
-            padding: UiRect::all(Val::Px(10.0)),
-            ..default()
-        },
-        BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.33)),
-        Visibility::Hidden,
-        Children::spawn(text_spans),
-    ));
+    let font = TextFont {
+        font_size: 14.0,
+        ..default()
+    };
+    let button = |action: StepButton, label: &str| {
+        (
+            Button,
+            action,
+            Node {
+                padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                ..default()
+            },
+            BackgroundColor(LIGHT),
+            children![(Text::new(label), font.clone(), TextColor(FONT_COLOR))],
+        )
+    };
+    commands
+        .spawn((
+            SteppingUi,
+            Node {
+                position_type: PositionType::Absolute,
+                top: state.ui_top,
+                left: state.ui_left,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(2.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.33)),
+            GlobalZIndex(STEPPING_Z_INDEX),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(HELP_TEXT),
+                font.clone(),
+                TextColor(FONT_COLOR),
+                BackgroundColor(LIGHT.with_alpha(ROW_ALPHA)),
+                Node {
+                    padding: UiRect::all(Val::Px(6.0)),
+                    margin: UiRect::bottom(Val::Px(4.0)),
+                    ..default()
+                },
+                SteppingHelp,
+            ));
+            // the legend, in the colors the rows are tinted with
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                })
+                .with_children(|legend| {
+                    for set in [SimSet::Clock, SimSet::Economy, SimSet::Ledger] {
+                        legend.spawn((
+                            Text(format!("{set:?}")),
+                            font.clone(),
+                            TextColor(FONT_COLOR),
+                            BackgroundColor(sim_set_color(Some(set))),
+                            Node {
+                                padding: UiRect::horizontal(Val::Px(4.0)),
+                                ..default()
+                            },
+                        ));
+                    }
+                });
+            parent.spawn((
+                Node {
+                    column_gap: Val::Px(6.0),
+                    margin: UiRect::vertical(Val::Px(4.0)),
+                    ..default()
+                },
+                children![
+                    button(StepButton::StepSystem, "Step system"),
+                    button(StepButton::RunToFrameEnd, "Run to frame end"),
+                ],
+            ));
+            for (label, systems) in rows {
+                parent.spawn((Text::new(format!("{label:?}")), TextColor(FONT_COLOR)));
+                for (row, color) in systems {
+                    parent.spawn((
+                        Button,
+                        Text::new(format!("   [x] {}", row.name)),
+                        font.clone(),
+                        TextColor(FONT_COLOR),
+                        BackgroundColor(color),
+                        row,
+                    ));
+                }
+            }
+        });
 }
 
 fn build_stepping_hint(mut commands: Commands) {
     let hint_text = if cfg!(feature = "bevy_debug_stepping") {
-        "Press ` to toggle stepping mode (S: step system, Space: step frame)"
+        "Press ` to toggle stepping mode (S: step system, Space: step frame, F1: help)"
     } else {
         "Bevy was compiled without stepping support. Run with `--features=bevy_debug_stepping` to enable stepping."
     };
@@ -201,7 +340,11 @@ fn build_stepping_hint(mut commands: Commands) {
     ));
 }
 
-fn handle_input(keyboard_input: Res<ButtonInput<KeyCode>>, mut stepping: ResMut<Stepping>) {
+fn handle_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut stepping: ResMut<Stepping>,
+    mut help: Query<&mut Node, With<SteppingHelp>>,
+) {
     if keyboard_input.just_pressed(KeyCode::Slash) {
         info!("{:#?}", stepping);
     }
@@ -220,6 +363,15 @@ fn handle_input(keyboard_input: Res<ButtonInput<KeyCode>>, mut stepping: ResMut<
         return;
     }
 
+    if keyboard_input.just_pressed(HELP_KEY) {
+        for mut node in &mut help {
+            node.display = match node.display {
+                Display::None => Display::Flex,
+                _ => Display::None,
+            };
+        }
+    }
+
     // space key will step the remainder of this frame
     if keyboard_input.just_pressed(KeyCode::Space) {
         debug!("continue");
@@ -230,15 +382,43 @@ fn handle_input(keyboard_input: Res<ButtonInput<KeyCode>>, mut stepping: ResMut<
     }
 }
 
+/// The buttons do what S and Space do; clicking a system row skips it or runs it again.
+fn handle_clicks(
+    mut buttons: ButtonPresses<&StepButton>,
+    mut rows: ButtonPresses<&SystemRow>,
+    mut stepping: ResMut<Stepping>,
+    mut disabled: ResMut<DisabledSystems>,
+) {
+    for button in buttons.read() {
+        match button {
+            StepButton::StepSystem => stepping.step_frame(),
+            StepButton::RunToFrameEnd => stepping.continue_frame(),
+        };
+    }
+    for row in rows.read() {
+        let key = (row.schedule, row.node);
+        if disabled.0.remove(&key) {
+            debug!("running {} again", row.name);
+            stepping.clear_node(row.schedule, row.node);
+        } else {
+            debug!("skipping {}", row.name);
+            disabled.0.insert(key);
+            stepping.never_run_node(row.schedule, row.node);
+        }
+    }
+}
+
 fn update_ui(
     mut commands: Commands,
-    state: Res<State>,
     stepping: Res<Stepping>,
-    ui: Single<(Entity, &Visibility), With<SteppingUi>>,
-    mut writer: TextUiWriter,
+    disabled: Res<DisabledSystems>,
+    ui: Query<(Entity, &Visibility), With<SteppingUi>>,
+    mut rows: Query<(&SystemRow, &mut Text, &mut TextColor)>,
 ) {
     // ensure the UI is only visible when stepping is enabled
-    let (ui, vis) = *ui;
+    let Ok((ui, vis)) = ui.single() else {
+        return;
+    };
     match (vis, stepping.is_enabled()) {
         (Visibility::Hidden, true) => {
             commands.entity(ui).insert(Visibility::Inherited);
@@ -254,18 +434,21 @@ fn update_ui(
         return;
     }
 
-    let (cursor_schedule, cursor_system) = match stepping.cursor() {
-        // no cursor means stepping isn't enabled, so we're done here
-        None => return,
-        Some(c) => c,
-    };
-
-    for (schedule, system, text_index) in &state.systems {
-        let mark = if &cursor_schedule == schedule && *system == cursor_system {
-            "-> "
+    // no cursor until the first stepped frame starts
+    let cursor = stepping.cursor();
+    for (row, mut text, mut color) in &mut rows {
+        let mark = if cursor == Some((row.schedule, row.node)) {
+            "->"
         } else {
-            "   "
+            "  "
         };
-        *writer.text(ui, *text_index) = mark.to_string();
+        let skipped = disabled.0.contains(&(row.schedule, row.node));
+        let check = if skipped { "[ ]" } else { "[x]" };
+        let line = format!("{mark} {check} {}", row.name);
+        if text.0 != line {
+            text.0 = line;
+        }
+        let alpha = if skipped { SKIPPED_ALPHA } else { 1.0 };
+        color.set_if_neq(TextColor(FONT_COLOR.with_alpha(alpha)));
     }
 }
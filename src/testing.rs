@@ -0,0 +1,138 @@
+//! Helpers for tests that run the whole game. A [`TestGame`] boots [`crate::game_app`] with
+//! its profiles in a [`ScratchDir`] of its own, removed with it even when the test panics,
+//! and gives up after [`MAX_FRAMES`] instead of hanging when what it waits for never
+//! happens.
+
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::launch::LaunchOptions;
+use crate::profiles::PROFILES_DIR;
+use crate::screens::overlay::ModalStack;
+use crate::sim::{
+    clock::{Clock, HOURS_PER_DAY},
+    step_ticks,
+};
+
+/// How many frames a [`TestGame`] waits for something before failing the test.
+pub const MAX_FRAMES: u32 = 20_000;
+
+/// A directory of its own for a test's files, removed when dropped.
+pub struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    pub fn new() -> Self {
+        static NEXT: AtomicU32 = AtomicU32::new(0);
+        ScratchDir(std::env::temp_dir().join(format!(
+            "breakout-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        )))
+    }
+
+    /// Where the profiles go, made when the first one is.
+    pub fn profiles(&self) -> PathBuf {
+        self.0.join(PROFILES_DIR)
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if let Err(error) = std::fs::remove_dir_all(&self.0)
+            && error.kind() != std::io::ErrorKind::NotFound
+        {
+            warn!("Couldn't remove {}: {error}", self.0.display());
+        }
+    }
+}
+
+pub struct TestGame {
+    app: App,
+    _dir: ScratchDir,
+}
+
+impl TestGame {
+    /// The game launched with `options`, keeping its profiles in a new [`ScratchDir`].
+    pub fn new(mut options: LaunchOptions) -> Self {
+        let dir = ScratchDir::new();
+        options.profiles_dir = Some(dir.profiles());
+        let mut app = crate::game_app(options);
+        app.finish();
+        app.cleanup();
+        TestGame { app, _dir: dir }
+    }
+
+    /// A new run as `profile`, seeded and straight past the menu, with nothing open over it.
+    pub fn in_game(profile: &str) -> Self {
+        Self::started(profile, false)
+    }
+
+    /// [`TestGame::in_game`] with virtual time paused from the first frame, so the run
+    /// hasn't moved when it's handed over, and only the ticks the test steps move it.
+    pub fn paused_in_game(profile: &str) -> Self {
+        Self::started(profile, true)
+    }
+
+    fn started(profile: &str, paused: bool) -> Self {
+        let mut game = TestGame::new(LaunchOptions {
+            skip_menu: true,
+            headless: true,
+            seed: Some(1),
+            profile: Some(profile.to_string()),
+            ..default()
+        });
+        if paused {
+            game.world_mut().resource_mut::<Time<Virtual>>().pause();
+        }
+        game.update_until(|app| {
+            *app.world().resource::<State<AppState>>().get() == AppState::Game
+                && app.world().resource::<ModalStack>().is_empty()
+        });
+        game
+    }
+
+    /// Updates until `done` holds, failing the test if it doesn't within [`MAX_FRAMES`].
+    pub fn update_until(&mut self, mut done: impl FnMut(&App) -> bool) {
+        for _ in 0..MAX_FRAMES {
+            if done(&self.app) {
+                return;
+            }
+            self.app.update();
+        }
+        panic!("gave up waiting after {MAX_FRAMES} frames");
+    }
+
+    /// Updates until the game asks to quit, and how.
+    pub fn run_to_exit(&mut self) -> AppExit {
+        let mut exit = None;
+        self.update_until(|app| {
+            exit = app.should_exit();
+            exit.is_some()
+        });
+        exit.unwrap()
+    }
+}
+
+impl Deref for TestGame {
+    type Target = App;
+
+    fn deref(&self) -> &App {
+        &self.app
+    }
+}
+
+impl DerefMut for TestGame {
+    fn deref_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+}
+
+/// Runs the clock to midnight at the start of `day`.
+pub fn sleep_until(app: &mut App, day: u32) {
+    let now = app.world().resource::<Clock>().total_hours();
+    step_ticks(app.world_mut(), day * HOURS_PER_DAY - now);
+}
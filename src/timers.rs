@@ -0,0 +1,167 @@
+//! The game's two clocks, so no timer has to guess which one it's on.
+//!
+//! - The UI clock is wall-clock time, read with [`RealUiTime`] and counted by a
+//!   [`UiTimer`]. It always runs: fades, toasts, tooltips, sounds and the like take as long
+//!   whether the simulation is fast, slow, paused behind a modal or waiting for the window
+//!   to come back into focus.
+//! - The sim clock is the run's [`Clock`](crate::sim::clock::Clock), one hour per tick,
+//!   counted by a [`SimTimer`](crate::sim::clock::SimTimer). Buffs, bills and events run on
+//!   it, so they go faster with the sim speed and stop when it stops.
+//!
+//! Systems shouldn't read `Res<Time>` for either: in `Update` it's virtual time, which
+//! follows neither.
+
+use std::time::Duration;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+/// Wall-clock time for the UI.
+#[derive(SystemParam)]
+pub struct RealUiTime<'w> {
+    time: Res<'w, Time<Real>>,
+}
+
+impl RealUiTime<'_> {
+    pub fn delta(&self) -> Duration {
+        self.time.delta()
+    }
+
+    pub fn delta_secs(&self) -> f32 {
+        self.time.delta_secs()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.time.elapsed()
+    }
+
+    pub fn elapsed_secs(&self) -> f32 {
+        self.time.elapsed_secs()
+    }
+}
+
+/// A [`Timer`] that can only be ticked by the UI clock. It reads like a plain one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UiTimer(Timer);
+
+impl UiTimer {
+    pub fn new(duration: Duration, mode: TimerMode) -> Self {
+        UiTimer(Timer::new(duration, mode))
+    }
+
+    pub fn from_seconds(seconds: f32, mode: TimerMode) -> Self {
+        UiTimer(Timer::from_seconds(seconds, mode))
+    }
+
+    /// A one-shot timer that has already run out, for a cooldown that starts ready.
+    pub fn run_out(duration: Duration) -> Self {
+        let mut timer = Timer::new(duration, TimerMode::Once);
+        timer.tick(duration);
+        UiTimer(timer)
+    }
+
+    pub fn tick(&mut self, time: &RealUiTime) -> &Timer {
+        self.0.tick(time.delta())
+    }
+
+    pub fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+impl std::ops::Deref for UiTimer {
+    type Target = Timer;
+
+    fn deref(&self) -> &Timer {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::sim::{
+        SimTickRate,
+        buffs::{Buff, BuffKind, Buffs},
+        clock::Clock,
+    };
+    use crate::testing::TestGame;
+    use crate::toast::Toast;
+
+    /// How far the real clock moves each update.
+    const FRAME: Duration = Duration::from_millis(100);
+    const TOAST: &str = "Timers test";
+
+    /// A run just started, with a two-hour buff and a toast given, where each update
+    /// takes a [`FRAME`] of real time and the sim ticks `ticks_per_second`.
+    fn started(profile: &str, ticks_per_second: f64) -> TestGame {
+        let mut app = TestGame::in_game(profile);
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+            .insert_resource(SimTickRate(ticks_per_second));
+        // Lets the time the run took to start drain away first.
+        run_for(&mut app, 1);
+        let now = hours(&app);
+        app.world_mut().resource_mut::<Buffs>().0.push(Buff::new(
+            BuffKind::ShiftEnergy,
+            10,
+            now,
+            2,
+        ));
+        app.world_mut().send_event(Toast::new(TOAST));
+        app
+    }
+
+    fn run_for(app: &mut App, seconds: u32) {
+        for _ in 0..seconds * 10 {
+            app.update();
+        }
+    }
+
+    fn hours(app: &App) -> u32 {
+        app.world().resource::<Clock>().total_hours()
+    }
+
+    fn toast_shown(app: &mut App) -> bool {
+        let mut texts = app.world_mut().query::<&Text>();
+        texts.iter(app.world()).any(|text| text.0 == TOAST)
+    }
+
+    fn buffed(app: &App) -> bool {
+        app.world()
+            .resource::<Buffs>()
+            .0
+            .iter()
+            .any(|buff| buff.kind == BuffKind::ShiftEnergy)
+    }
+
+    #[test]
+    fn at_four_times_speed_buffs_end_sooner_and_toasts_keep_their_time() {
+        const PROFILE: &str = "Timers at 4x";
+        let mut app = started(PROFILE, 4.0);
+        let start = hours(&app);
+        run_for(&mut app, 1);
+        let ran = hours(&app) - start;
+        assert!((3..=5).contains(&ran), "{ran} hours in a second");
+        assert!(!buffed(&app));
+        assert!(toast_shown(&mut app));
+
+        run_for(&mut app, 3);
+        assert!(!toast_shown(&mut app));
+    }
+
+    #[test]
+    fn while_the_sim_is_paused_toasts_expire_and_buffs_dont() {
+        const PROFILE: &str = "Timers paused";
+        let mut app = started(PROFILE, 1.0);
+        app.world_mut().resource_mut::<Time<Virtual>>().pause();
+        let start = hours(&app);
+        run_for(&mut app, 1);
+        assert!(toast_shown(&mut app));
+
+        run_for(&mut app, 3);
+        assert!(!toast_shown(&mut app));
+        assert_eq!(hours(&app), start);
+        assert!(buffed(&app));
+    }
+}
@@ -0,0 +1,187 @@
+//! Short-lived notifications stacked in the bottom-right corner of the screen. A toast for
+//! a purchase that can be taken back has an Undo button, see [`crate::sim::undo`].
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::fonts::ui_font;
+use crate::palette::{BLUE, DARK, DARKER, LIGHT};
+use crate::screens::button_press::ButtonPresses;
+use crate::sim::undo::{UNDO_WINDOW, UndoBuffer, UndoRequested};
+use crate::timers::{RealUiTime, UiTimer};
+
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+/// Send this to show a toast with the given text.
+#[derive(Event, Debug, Clone)]
+pub struct Toast {
+    pub text: String,
+    /// Whether the toast offers to undo the purchase in the [`UndoBuffer`].
+    pub undo: bool,
+}
+
+impl Toast {
+    pub fn new(text: impl Into<String>) -> Self {
+        Toast {
+            text: text.into(),
+            undo: false,
+        }
+    }
+
+    /// A toast with an Undo button, for a purchase just recorded in the [`UndoBuffer`].
+    pub fn undoable(text: impl Into<String>) -> Self {
+        Toast {
+            undo: true,
+            ..Toast::new(text)
+        }
+    }
+}
+
+#[derive(Component)]
+struct ToastContainer;
+
+#[derive(Component)]
+struct ToastTimer(UiTimer);
+
+#[derive(Component)]
+struct UndoButton;
+
+pub fn toast_plugin(app: &mut App) {
+    app.add_event::<Toast>()
+        .add_systems(Startup, spawn_toast_container)
+        .add_systems(
+            Update,
+            (
+                show_toasts,
+                expire_toasts,
+                press_undo,
+                highlight_undo,
+                hide_stale_undo,
+            ),
+        );
+}
+
+fn spawn_toast_container(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            // Leaves room for the mute indicator in the corner.
+            bottom: Val::Px(30.0),
+            right: Val::Px(10.0),
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::FlexEnd,
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+        GlobalZIndex(100),
+        ToastContainer,
+    ));
+}
+
+fn show_toasts(
+    mut commands: Commands,
+    mut toasts: EventReader<Toast>,
+    container: Query<Entity, With<ToastContainer>>,
+    undo_buttons: Query<Entity, With<UndoButton>>,
+    assets: Res<AssetServer>,
+) {
+    let Ok(container) = container.single() else {
+        return;
+    };
+    for toast in toasts.read() {
+        let font = TextFont {
+            font: ui_font(&assets),
+            font_size: 14.0,
+            ..default()
+        };
+        let duration = if toast.undo {
+            UNDO_WINDOW
+        } else {
+            TOAST_DURATION
+        };
+        let entity = commands
+            .spawn((
+                Node {
+                    padding: UiRect::axes(Val::Px(12.0), Val::Px(8.0)),
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                BackgroundColor(DARKER.with_alpha(0.9)),
+                ToastTimer(UiTimer::new(duration, TimerMode::Once)),
+                ChildOf(container),
+                children![(
+                    Text::new(toast.text.clone()),
+                    font.clone(),
+                    TextColor(LIGHT)
+                )],
+            ))
+            .id();
+        if toast.undo {
+            // Only the latest purchase can be undone.
+            for button in &undo_buttons {
+                commands.entity(button).despawn();
+            }
+            commands.entity(entity).with_child((
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(DARK),
+                UndoButton,
+                children![(Text::new("Undo"), font, TextColor(LIGHT))],
+            ));
+        }
+    }
+}
+
+fn press_undo(
+    mut presses: ButtonPresses<(), With<UndoButton>>,
+    mut requests: EventWriter<UndoRequested>,
+) {
+    if presses.read().count() > 0 {
+        requests.write(UndoRequested);
+    }
+}
+
+fn highlight_undo(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<UndoButton>),
+    >,
+) {
+    for (interaction, mut color) in &mut buttons {
+        color.0 = match interaction {
+            Interaction::None => DARK,
+            Interaction::Hovered | Interaction::Pressed => BLUE,
+        };
+    }
+}
+
+// The button goes once the purchase can't be undone anymore, or has been.
+fn hide_stale_undo(
+    mut commands: Commands,
+    buffer: Option<Res<UndoBuffer>>,
+    buttons: Query<Entity, With<UndoButton>>,
+) {
+    if buffer.is_some_and(|buffer| !buffer.is_empty()) {
+        return;
+    }
+    for button in &buttons {
+        commands.entity(button).despawn();
+    }
+}
+
+fn expire_toasts(
+    mut commands: Commands,
+    mut toasts: Query<(Entity, &mut ToastTimer)>,
+    time: RealUiTime,
+) {
+    for (entity, mut timer) in &mut toasts {
+        if timer.0.tick(&time).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}